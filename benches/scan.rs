@@ -0,0 +1,84 @@
+//! Compares `LensDiscovery::scan` against loading every lens one at a time,
+//! which is what scanning did before it loaded lenses in parallel.
+//!
+//! ```text
+//! cargo bench --features runtime --bench scan
+//! ```
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use lens::LensDiscovery;
+
+const LENSES: usize = 48;
+const ROUNDS: u32 = 20;
+
+const OUTPUT_SPEC: &str = r#"
+lens_id: {id}
+outputs:
+  - key: result
+    title: Result
+    render_blocks:
+      - type: json_view
+    examples:
+      - {}
+"#;
+
+fn write_lens(root: &Path, id: &str) {
+    let dir = root.join(id);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("lens.toml"),
+        format!(
+            "[lens]\nid = \"{id}\"\nname = \"{id}\"\nversion = \"1.0.0\"\n\n\
+             [[message_types]]\nkey = \"result\"\ncomponent = \"components/Result.tsx\"\n"
+        ),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("lens.output.yaml"),
+        OUTPUT_SPEC.replace("{id}", id),
+    )
+    .unwrap();
+}
+
+fn time(rounds: u32, mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..rounds {
+        f();
+    }
+    start.elapsed() / rounds
+}
+
+fn main() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    for i in 0..LENSES {
+        write_lens(temp_dir.path(), &format!("lens-{:02}", i));
+    }
+    let discovery = LensDiscovery::new(temp_dir.path());
+    let mut dirs: Vec<_> = fs::read_dir(temp_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    dirs.sort();
+
+    let sequential = time(ROUNDS, || {
+        for dir in &dirs {
+            discovery.load_lens(dir).unwrap();
+        }
+    });
+    let parallel = time(ROUNDS, || {
+        assert_eq!(discovery.scan().unwrap().len(), LENSES);
+    });
+    let cached_discovery = discovery.clone().with_cache();
+    cached_discovery.scan().unwrap();
+    let cached = time(ROUNDS, || {
+        assert_eq!(cached_discovery.scan().unwrap().len(), LENSES);
+    });
+
+    println!("{} lenses, mean of {} rounds", LENSES, ROUNDS);
+    println!("  sequential load: {:?}", sequential);
+    println!("  scan:            {:?}", parallel);
+    println!("  cached scan:     {:?}", cached);
+}
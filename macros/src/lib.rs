@@ -0,0 +1,393 @@
+//! # Lens Macros
+//!
+//! Procedural macros re-exported by `lens` under the `macros` feature.
+//!
+//! `#[mcp_tools]` goes on an inherent `impl` block; each `async fn` marked with
+//! `#[mcp_tool]` becomes an MCP tool. The macro generates the tool definitions,
+//! a typed dispatcher, and the `McpServerLens` impl:
+//!
+//! ```rust,ignore
+//! #[lens::mcp_tools]
+//! impl BaseLens {
+//!     /// Search the knowledge base
+//!     #[mcp_tool(params(query = "Search query", limit = "Maximum results"))]
+//!     async fn search(&self, query: String, limit: Option<u32>) -> lens::Result<Value> {
+//!         /* ... */
+//!     }
+//! }
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, FnArg, GenericArgument, ImplItem, ImplItemFn, ItemImpl, LitStr,
+    Pat, PathArguments, Type,
+};
+
+/// Generate MCP tool definitions, dispatch, and `McpServerLens` for an impl block.
+///
+/// Accepts an optional `name = "..."` overriding `mcp_server_name()`.
+#[proc_macro_attribute]
+pub fn mcp_tools(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut server_name: Option<LitStr> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            server_name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported mcp_tools argument; expected `name = \"...\"`"))
+        }
+    });
+    parse_macro_input!(args with parser);
+
+    let item = parse_macro_input!(input as ItemImpl);
+    match expand(item, server_name) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Marks a method inside a `#[mcp_tools]` impl block as an MCP tool.
+///
+/// Arguments: `name = "..."` (defaults to the method name), `description = "..."`
+/// (defaults to the doc comment), `params(arg = "description", ...)`, and the
+/// annotation flags `read_only`, `destructive`, `idempotent`, and `open_world`
+/// (bare for `true`, or `flag = false`).
+#[proc_macro_attribute]
+pub fn mcp_tool(_args: TokenStream, input: TokenStream) -> TokenStream {
+    let input = TokenStream2::from(input);
+    quote! {
+        ::core::compile_error!("#[mcp_tool] methods must be inside an impl block marked #[mcp_tools]");
+        #input
+    }
+    .into()
+}
+
+struct ToolMethod {
+    tool_name: LitStr,
+    description: String,
+    method: syn::Ident,
+    params: Vec<ToolParam>,
+    hints: Vec<(syn::Ident, bool)>,
+}
+
+struct ToolParam {
+    name: syn::Ident,
+    ty: Type,
+    description: String,
+}
+
+fn expand(mut item: ItemImpl, server_name: Option<LitStr>) -> syn::Result<TokenStream2> {
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(syn::Error::new(
+            path.span(),
+            "#[mcp_tools] goes on an inherent impl block, not a trait impl",
+        ));
+    }
+
+    let mut tools = Vec::new();
+    for impl_item in &mut item.items {
+        let ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+        let Some(index) = method
+            .attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("mcp_tool"))
+        else {
+            continue;
+        };
+        let attr = method.attrs.remove(index);
+        tools.push(parse_tool(&attr, method)?);
+    }
+
+    let self_ty = &item.self_ty;
+    let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+
+    let definitions = tools.iter().map(|tool| {
+        let name = &tool.tool_name;
+        let description = &tool.description;
+        let properties = tool.params.iter().map(|param| {
+            let name = param.name.to_string();
+            let (schema, required) = property_schema(&param.ty, &param.description);
+            let required = required.then(|| quote! { .required(#name) });
+            quote! { .property(#name, #schema) #required }
+        });
+        let hints = tool
+            .hints
+            .iter()
+            .map(|(hint, value)| quote! { .#hint(#value) });
+        quote! {
+            ::lens::McpTool::builder(#name)
+                .description(#description)
+                #(#properties)*
+                #(#hints)*
+                .build()
+        }
+    });
+
+    let arms = tools.iter().map(|tool| {
+        let name = &tool.tool_name;
+        let method = &tool.method;
+        let bindings = tool.params.iter().map(|param| {
+            let ident = &param.name;
+            let ty = &param.ty;
+            let key = ident.to_string();
+            quote_spanned! {ty.span()=>
+                let #ident: #ty = ::lens::mcp_server::tool_param(&params, #key)?;
+            }
+        });
+        let args = tool.params.iter().map(|param| &param.name);
+        quote! {
+            #name => {
+                #(#bindings)*
+                ::lens::mcp_server::IntoToolResponse::into_tool_response(
+                    self.#method(#(#args),*).await,
+                )
+            }
+        }
+    });
+
+    let server_name = server_name.map(|name| {
+        quote! {
+            fn mcp_server_name(&self) -> ::std::string::String {
+                ::std::string::String::from(#name)
+            }
+        }
+    });
+
+    Ok(quote! {
+        #item
+
+        impl #impl_generics #self_ty #where_clause {
+            /// MCP tool definitions generated by `#[mcp_tools]`
+            pub fn mcp_tool_definitions() -> ::std::vec::Vec<::lens::McpTool> {
+                ::std::vec![#(#definitions),*]
+            }
+
+            /// Deserialize `params` and call the matching `#[mcp_tool]` method
+            pub async fn dispatch_mcp_tool(
+                &self,
+                name: &str,
+                params: ::lens::__private::serde_json::Value,
+            ) -> ::lens::Result<::lens::McpToolResponse> {
+                #[allow(unused_variables)]
+                let params = params;
+                match name {
+                    #(#arms)*
+                    _ => ::std::result::Result::Err(::lens::LensError::InvalidInput(
+                        ::std::format!("Unknown tool: {}", name),
+                    )),
+                }
+            }
+        }
+
+        #[::lens::__private::async_trait::async_trait]
+        impl #impl_generics ::lens::McpServerLens for #self_ty #where_clause {
+            fn mcp_tools(&self) -> ::std::vec::Vec<::lens::McpTool> {
+                Self::mcp_tool_definitions()
+            }
+
+            async fn call_tool(
+                &self,
+                name: &str,
+                params: ::lens::__private::serde_json::Value,
+            ) -> ::lens::Result<::lens::McpToolResponse> {
+                self.dispatch_mcp_tool(name, params).await
+            }
+
+            #server_name
+        }
+    })
+}
+
+fn parse_tool(attr: &Attribute, method: &ImplItemFn) -> syn::Result<ToolMethod> {
+    let sig = &method.sig;
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            sig.fn_token.span(),
+            "#[mcp_tool] methods must be async",
+        ));
+    }
+    match sig.inputs.first() {
+        Some(FnArg::Receiver(receiver)) if receiver.reference.is_some() => {}
+        _ => {
+            return Err(syn::Error::new(
+                sig.ident.span(),
+                "#[mcp_tool] methods must take &self",
+            ))
+        }
+    }
+
+    let mut tool_name = LitStr::new(&sig.ident.to_string(), sig.ident.span());
+    let mut description = None;
+    let mut param_docs: Vec<(syn::Ident, String)> = Vec::new();
+    let mut hints: Vec<(syn::Ident, bool)> = Vec::new();
+
+    if !matches!(attr.meta, syn::Meta::Path(_)) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                tool_name = meta.value()?.parse()?;
+            } else if meta.path.is_ident("description") {
+                description = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("params") {
+                meta.parse_nested_meta(|param| {
+                    let ident = param
+                        .path
+                        .get_ident()
+                        .cloned()
+                        .ok_or_else(|| param.error("expected a parameter name"))?;
+                    let doc: LitStr = param.value()?.parse()?;
+                    param_docs.push((ident, doc.value()));
+                    Ok(())
+                })?;
+            } else if let Some(hint) = HINTS.iter().find(|hint| meta.path.is_ident(hint)) {
+                // Bare `read_only` means true; `read_only = false` is explicit
+                let value = if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<syn::LitBool>()?.value
+                } else {
+                    true
+                };
+                hints.push((syn::Ident::new(hint, meta.path.span()), value));
+            } else {
+                return Err(meta.error(
+                    "unsupported mcp_tool argument; expected name, description, params(...), \
+                     read_only, destructive, idempotent, or open_world",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+
+    let description = description.unwrap_or_else(|| doc_comment(&method.attrs));
+
+    let mut params = Vec::new();
+    for input in sig.inputs.iter().skip(1) {
+        let FnArg::Typed(typed) = input else {
+            continue;
+        };
+        let Pat::Ident(pat) = &*typed.pat else {
+            return Err(syn::Error::new(
+                typed.pat.span(),
+                "#[mcp_tool] parameters must be plain identifiers",
+            ));
+        };
+        if matches!(&*typed.ty, Type::Reference(_)) {
+            return Err(syn::Error::new(
+                typed.ty.span(),
+                "#[mcp_tool] parameters must be owned types (e.g. String instead of &str)",
+            ));
+        }
+        let name = pat.ident.clone();
+        let description = param_docs
+            .iter()
+            .find(|(ident, _)| *ident == name)
+            .map(|(_, doc)| doc.clone())
+            .unwrap_or_default();
+        params.push(ToolParam {
+            name,
+            ty: (*typed.ty).clone(),
+            description,
+        });
+    }
+
+    for (ident, _) in &param_docs {
+        if !params.iter().any(|param| param.name == *ident) {
+            return Err(syn::Error::new(
+                ident.span(),
+                format!("`{}` is not a parameter of this method", ident),
+            ));
+        }
+    }
+
+    Ok(ToolMethod {
+        tool_name,
+        description,
+        method: sig.ident.clone(),
+        params,
+        hints,
+    })
+}
+
+/// Annotation flags accepted by `#[mcp_tool]`, named after the builder methods
+const HINTS: &[&str] = &["read_only", "destructive", "idempotent", "open_world"];
+
+fn doc_comment(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(nv) => match &nv.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(s),
+                    ..
+                }) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim()
+        .to_string()
+}
+
+/// Schema expression for a parameter type, and whether it is required.
+fn property_schema(ty: &Type, description: &str) -> (TokenStream2, bool) {
+    if let Some(inner) = generic_inner(ty, "Option") {
+        let (schema, _) = property_schema(inner, description);
+        return (schema, false);
+    }
+
+    let prop_type = json_type(ty);
+    let items = ["Vec", "HashSet", "BTreeSet"]
+        .iter()
+        .find_map(|wrapper| generic_inner(ty, wrapper))
+        .map(|inner| {
+            let (items, _) = property_schema(inner, "");
+            quote! { .with_items(#items) }
+        });
+
+    (
+        quote! { ::lens::McpPropertySchema::new(#prop_type, #description) #items },
+        true,
+    )
+}
+
+fn json_type(ty: &Type) -> &'static str {
+    let Some(ident) = last_segment(ty).map(|segment| segment.ident.to_string()) else {
+        return "object";
+    };
+    match ident.as_str() {
+        "String" | "str" | "char" | "PathBuf" => "string",
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128"
+        | "usize" => "integer",
+        "f32" | "f64" => "number",
+        "bool" => "boolean",
+        "Vec" | "HashSet" | "BTreeSet" => "array",
+        _ => "object",
+    }
+}
+
+fn last_segment(ty: &Type) -> Option<&syn::PathSegment> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last(),
+        _ => None,
+    }
+}
+
+fn generic_inner<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let segment = last_segment(ty)?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    args.args.iter().find_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
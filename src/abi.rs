@@ -0,0 +1,498 @@
+//! # Stable Lens ABI
+//!
+//! Compiled lenses are loaded through a `#[repr(C)]` vtable rather than a
+//! Rust trait object, so a lens built with one rustc version loads safely
+//! into a host built with another. [`export_lens!`](crate::export_lens)
+//! generates the entry point:
+//!
+//! ```rust,ignore
+//! use lens::export_lens;
+//!
+//! struct MyLens;
+//! // impl Lens for MyLens { ... }
+//!
+//! export_lens!(MyLens);
+//! ```
+//!
+//! The library exports `lens_abi_v1() -> LensVTable`, and optionally
+//! `lens_streaming_v1() -> StreamingVTable` and `lens_mcp_v1() -> McpVTable`
+//! for lenses that also implement [`StreamingLens`] or [`McpServerLens`]. Strings cross the
+//! boundary as borrowed UTF-8 slices; [`LensContext`] and the execution
+//! result cross as JSON, and buffers are freed by the side that allocated
+//! them. Host-injected brokers and tool callers (`tool_caller`,
+//! `oauth_broker`, `credentials_broker`) are not serializable and are not
+//! passed to lenses loaded this way.
+//!
+//! Each call to `execute` runs on a current-thread Tokio runtime owned by
+//! the lens library. Panics never unwind across the boundary: a panicking
+//! constructor leaves the instance null, and a panicking `execute` is
+//! reported as [`AbiResponse::Panicked`].
+
+use std::ffi::c_void;
+use std::panic::AssertUnwindSafe;
+
+use serde::{Deserialize, Serialize};
+
+use tokio_stream::StreamExt;
+
+use crate::error::Result;
+use crate::lens::{panic_message, Lens};
+use crate::mcp_server::McpServerLens;
+use crate::streaming::StreamingLens;
+use crate::{LensContext, LensResult};
+
+/// Version of the [`LensVTable`] layout
+pub const LENS_ABI_VERSION: u32 = 1;
+
+/// Entry point symbol that lenses export (see [`export_lens!`](crate::export_lens))
+pub const LENS_ENTRY_POINT: &[u8] = b"lens_abi_v1";
+
+/// Function signature for [`LENS_ENTRY_POINT`]
+pub type LensEntryFn = unsafe extern "C" fn() -> LensVTable;
+
+/// Optional symbol exporting a [`StreamingVTable`]
+pub const STREAMING_ENTRY_POINT: &[u8] = b"lens_streaming_v1";
+
+/// Optional symbol exporting an [`McpVTable`]
+pub const MCP_ENTRY_POINT: &[u8] = b"lens_mcp_v1";
+
+/// Function signature for [`STREAMING_ENTRY_POINT`]
+pub type StreamingEntryFn = unsafe extern "C" fn() -> StreamingVTable;
+
+/// Function signature for [`MCP_ENTRY_POINT`]
+pub type McpEntryFn = unsafe extern "C" fn() -> McpVTable;
+
+/// Symbol exporting [`LensBuildInfo`] (see [`export_lens!`](crate::export_lens))
+pub const BUILD_INFO_SYMBOL: &[u8] = b"lens_build_info";
+
+/// Function signature for [`BUILD_INFO_SYMBOL`]
+pub type BuildInfoFn = unsafe extern "C" fn() -> LensBuildInfo;
+
+/// Borrowed UTF-8 string or byte slice
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FfiStr {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+impl FfiStr {
+    pub fn new(bytes: &[u8]) -> Self {
+        Self {
+            ptr: bytes.as_ptr(),
+            len: bytes.len(),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `ptr` must point to `len` bytes that stay valid for `'a`.
+    pub unsafe fn as_bytes<'a>(&self) -> &'a [u8] {
+        if self.len == 0 {
+            &[]
+        } else {
+            std::slice::from_raw_parts(self.ptr, self.len)
+        }
+    }
+}
+
+/// Byte buffer owned by the lens library; release it with
+/// [`LensVTable::free_buffer`]
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiBuffer {
+    pub ptr: *mut u8,
+    pub len: usize,
+    pub cap: usize,
+}
+
+impl FfiBuffer {
+    fn from_vec(bytes: Vec<u8>) -> Self {
+        let mut bytes = std::mem::ManuallyDrop::new(bytes);
+        Self {
+            ptr: bytes.as_mut_ptr(),
+            len: bytes.len(),
+            cap: bytes.capacity(),
+        }
+    }
+}
+
+/// Function table a compiled lens hands to the host
+#[repr(C)]
+#[derive(Debug)]
+pub struct LensVTable {
+    /// Must equal [`LENS_ABI_VERSION`]
+    pub abi_version: u32,
+    /// Opaque lens instance passed back to every function below; null when
+    /// the lens constructor panicked
+    pub instance: *mut c_void,
+    pub id: unsafe extern "C" fn(*const c_void) -> FfiStr,
+    pub name: unsafe extern "C" fn(*const c_void) -> FfiStr,
+    pub version: unsafe extern "C" fn(*const c_void) -> FfiStr,
+    pub description: unsafe extern "C" fn(*const c_void) -> FfiStr,
+    pub supports_mcp: unsafe extern "C" fn(*const c_void) -> bool,
+    /// Run the lens on a JSON-encoded `LensContext`, returning a JSON
+    /// [`AbiResponse`]
+    pub execute: unsafe extern "C" fn(*const c_void, FfiStr) -> FfiBuffer,
+    pub free_buffer: unsafe extern "C" fn(FfiBuffer),
+    /// Drop the instance; no function may be called afterwards
+    pub drop: unsafe extern "C" fn(*mut c_void),
+}
+
+/// How a lens library was built, exported as `lens_build_info()`.
+///
+/// Strings point into the library's static data.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LensBuildInfo {
+    /// Must equal [`LENS_ABI_VERSION`]
+    pub abi_version: u32,
+    /// Name of the crate that invoked `export_lens!`
+    pub crate_name: FfiStr,
+    /// Version of that crate
+    pub crate_version: FfiStr,
+    /// Version of the `lens` crate compiled into the library
+    pub framework_version: FfiStr,
+    /// Output of `rustc --version`
+    pub rustc_version: FfiStr,
+    /// Target triple
+    pub target: FfiStr,
+    /// Unix time at which the `lens` crate in the library was compiled
+    /// (`SOURCE_DATE_EPOCH` when set)
+    pub built_at: u64,
+}
+
+impl LensBuildInfo {
+    /// Build info for the calling crate; used by [`export_lens!`](crate::export_lens)
+    pub fn new(crate_name: &'static str, crate_version: &'static str) -> Self {
+        Self {
+            abi_version: LENS_ABI_VERSION,
+            crate_name: FfiStr::new(crate_name.as_bytes()),
+            crate_version: FfiStr::new(crate_version.as_bytes()),
+            framework_version: FfiStr::new(crate::manifest::FRAMEWORK_VERSION.as_bytes()),
+            rustc_version: FfiStr::new(env!("LENS_BUILD_RUSTC_VERSION").as_bytes()),
+            target: FfiStr::new(env!("LENS_BUILD_TARGET").as_bytes()),
+            built_at: env!("LENS_BUILD_TIMESTAMP").parse().unwrap_or(0),
+        }
+    }
+
+    /// Copy the strings out of the library
+    ///
+    /// # Safety
+    ///
+    /// The library that produced `self` must still be loaded.
+    pub unsafe fn to_owned(&self) -> BuildInfo {
+        let string = |s: FfiStr| String::from_utf8_lossy(s.as_bytes()).into_owned();
+        BuildInfo {
+            crate_name: string(self.crate_name),
+            crate_version: string(self.crate_version),
+            framework_version: string(self.framework_version),
+            rustc_version: string(self.rustc_version),
+            target: string(self.target),
+            built_at: self.built_at,
+        }
+    }
+}
+
+/// Owned copy of [`LensBuildInfo`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub crate_name: String,
+    pub crate_version: String,
+    pub framework_version: String,
+    pub rustc_version: String,
+    pub target: String,
+    /// Unix time in seconds
+    pub built_at: u64,
+}
+
+impl BuildInfo {
+    /// How this `lens` crate was built; hosts compare it with a library's
+    pub fn current() -> Self {
+        Self {
+            crate_name: env!("CARGO_PKG_NAME").to_string(),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            framework_version: crate::manifest::FRAMEWORK_VERSION.to_string(),
+            rustc_version: env!("LENS_BUILD_RUSTC_VERSION").to_string(),
+            target: env!("LENS_BUILD_TARGET").to_string(),
+            built_at: env!("LENS_BUILD_TIMESTAMP").parse().unwrap_or(0),
+        }
+    }
+}
+
+impl std::fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} built with {} for {} against lens {}",
+            self.crate_name,
+            self.crate_version,
+            self.rustc_version,
+            self.target,
+            self.framework_version
+        )
+    }
+}
+
+/// Result of a call through a vtable, as JSON
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbiResponse<T = LensResult> {
+    Ok(T),
+    Error(String),
+    /// The lens panicked; carries the panic message
+    Panicked(String),
+}
+
+/// Host callback receiving each event of a streaming run as JSON
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct EventSink {
+    pub context: *mut c_void,
+    pub emit: unsafe extern "C" fn(*mut c_void, FfiStr),
+}
+
+/// [`StreamingLens`] entry points, called with [`LensVTable::instance`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct StreamingVTable {
+    /// Must equal [`LENS_ABI_VERSION`]
+    pub abi_version: u32,
+    /// Like [`LensVTable::execute`], passing every event to the sink before
+    /// returning
+    pub execute_streaming: unsafe extern "C" fn(*const c_void, FfiStr, EventSink) -> FfiBuffer,
+}
+
+impl StreamingVTable {
+    /// Table for the lens type `constructor` returns; the constructor is
+    /// only used for type inference and is never called
+    pub fn new<L: StreamingLens + 'static>(_constructor: impl FnOnce() -> L) -> Self {
+        Self {
+            abi_version: LENS_ABI_VERSION,
+            execute_streaming: lens_execute_streaming::<L>,
+        }
+    }
+}
+
+/// [`McpServerLens`] entry points, called with [`LensVTable::instance`].
+///
+/// Every function returns a JSON [`AbiResponse`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct McpVTable {
+    /// Must equal [`LENS_ABI_VERSION`]
+    pub abi_version: u32,
+    /// `[server name, server version]`
+    pub server_info: unsafe extern "C" fn(*const c_void) -> FfiBuffer,
+    /// `Vec<McpTool>`
+    pub tools: unsafe extern "C" fn(*const c_void) -> FfiBuffer,
+    /// Call the tool named by the first argument with JSON params;
+    /// `McpToolResponse`
+    pub call_tool: unsafe extern "C" fn(*const c_void, FfiStr, FfiStr) -> FfiBuffer,
+}
+
+impl McpVTable {
+    /// Table for the lens type `constructor` returns; the constructor is
+    /// only used for type inference and is never called
+    pub fn new<L: McpServerLens + 'static>(_constructor: impl FnOnce() -> L) -> Self {
+        Self {
+            abi_version: LENS_ABI_VERSION,
+            server_info: lens_mcp_server_info::<L>,
+            tools: lens_mcp_tools::<L>,
+            call_tool: lens_mcp_call_tool::<L>,
+        }
+    }
+}
+
+impl LensVTable {
+    /// Box `lens` behind a vtable
+    pub fn new<L: Lens + 'static>(lens: L) -> Self {
+        Self::with_instance::<L>(Box::into_raw(Box::new(lens)) as *mut c_void)
+    }
+
+    /// Construct the lens with `constructor`, leaving `instance` null if it
+    /// panics; used by [`export_lens!`](crate::export_lens)
+    pub fn catching<L: Lens + 'static>(constructor: impl FnOnce() -> L) -> Self {
+        match std::panic::catch_unwind(AssertUnwindSafe(constructor)) {
+            Ok(lens) => Self::new(lens),
+            Err(_) => Self::with_instance::<L>(std::ptr::null_mut()),
+        }
+    }
+
+    fn with_instance<L: Lens + 'static>(instance: *mut c_void) -> Self {
+        Self {
+            abi_version: LENS_ABI_VERSION,
+            instance,
+            id: lens_id::<L>,
+            name: lens_name::<L>,
+            version: lens_version::<L>,
+            description: lens_description::<L>,
+            supports_mcp: lens_supports_mcp::<L>,
+            execute: lens_execute::<L>,
+            free_buffer,
+            drop: lens_drop::<L>,
+        }
+    }
+}
+
+/// Borrow a string from the lens, or an empty one if the getter panics
+unsafe fn lens_str<L: Lens>(this: *const c_void, get: fn(&L) -> &str) -> FfiStr {
+    let lens = &*(this as *const L);
+    std::panic::catch_unwind(AssertUnwindSafe(|| FfiStr::new(get(lens).as_bytes())))
+        .unwrap_or(FfiStr::new(&[]))
+}
+
+unsafe extern "C" fn lens_id<L: Lens>(this: *const c_void) -> FfiStr {
+    lens_str::<L>(this, L::id)
+}
+
+unsafe extern "C" fn lens_name<L: Lens>(this: *const c_void) -> FfiStr {
+    lens_str::<L>(this, L::name)
+}
+
+unsafe extern "C" fn lens_version<L: Lens>(this: *const c_void) -> FfiStr {
+    lens_str::<L>(this, L::version)
+}
+
+unsafe extern "C" fn lens_description<L: Lens>(this: *const c_void) -> FfiStr {
+    lens_str::<L>(this, L::description)
+}
+
+unsafe extern "C" fn lens_supports_mcp<L: Lens>(this: *const c_void) -> bool {
+    let lens = &*(this as *const L);
+    std::panic::catch_unwind(AssertUnwindSafe(|| lens.supports_mcp())).unwrap_or(false)
+}
+
+unsafe extern "C" fn lens_execute<L: Lens>(this: *const c_void, request: FfiStr) -> FfiBuffer {
+    let lens = &*(this as *const L);
+    let request = request.as_bytes();
+    respond(|| {
+        let ctx: LensContext = serde_json::from_slice(request)?;
+        block_on(lens.execute(ctx))?
+    })
+}
+
+unsafe extern "C" fn lens_execute_streaming<L: StreamingLens>(
+    this: *const c_void,
+    request: FfiStr,
+    sink: EventSink,
+) -> FfiBuffer {
+    let lens = &*(this as *const L);
+    let request = request.as_bytes();
+    respond(|| {
+        let ctx: LensContext = serde_json::from_slice(request)?;
+        block_on(async {
+            let (result, mut events) = lens.execute_streaming(ctx).await?;
+            while let Some(event) = events.next().await {
+                let event = serde_json::to_vec(&event)?;
+                (sink.emit)(sink.context, FfiStr::new(&event));
+            }
+            Ok(result)
+        })?
+    })
+}
+
+unsafe extern "C" fn lens_mcp_server_info<L: McpServerLens>(this: *const c_void) -> FfiBuffer {
+    let lens = &*(this as *const L);
+    respond(|| Ok((lens.mcp_server_name(), lens.mcp_server_version())))
+}
+
+unsafe extern "C" fn lens_mcp_tools<L: McpServerLens>(this: *const c_void) -> FfiBuffer {
+    let lens = &*(this as *const L);
+    respond(|| Ok(lens.mcp_tools()))
+}
+
+unsafe extern "C" fn lens_mcp_call_tool<L: McpServerLens>(
+    this: *const c_void,
+    name: FfiStr,
+    params: FfiStr,
+) -> FfiBuffer {
+    let lens = &*(this as *const L);
+    let (name, params) = (name.as_bytes(), params.as_bytes());
+    respond(|| {
+        let name = String::from_utf8_lossy(name);
+        let params: serde_json::Value = serde_json::from_slice(params)?;
+        block_on(lens.call_tool(&name, params))?
+    })
+}
+
+/// Run `future` to completion on a fresh current-thread runtime
+fn block_on<F: std::future::Future>(future: F) -> Result<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok(runtime.block_on(future))
+}
+
+/// Run `call`, encoding its result, error, or panic as a JSON [`AbiResponse`]
+fn respond<T: Serialize>(call: impl FnOnce() -> Result<T>) -> FfiBuffer {
+    let response = match std::panic::catch_unwind(AssertUnwindSafe(call)) {
+        Ok(Ok(value)) => AbiResponse::Ok(value),
+        Ok(Err(e)) => AbiResponse::Error(e.to_string()),
+        Err(payload) => AbiResponse::Panicked(panic_message(payload.as_ref())),
+    };
+    FfiBuffer::from_vec(serde_json::to_vec(&response).unwrap_or_default())
+}
+
+unsafe extern "C" fn free_buffer(buffer: FfiBuffer) {
+    if !buffer.ptr.is_null() {
+        drop(Vec::from_raw_parts(buffer.ptr, buffer.len, buffer.cap));
+    }
+}
+
+unsafe extern "C" fn lens_drop<L: Lens>(this: *mut c_void) {
+    // A panicking destructor leaks whatever it had not yet freed
+    let _ = std::panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(this as *mut L))));
+}
+
+/// Macro to generate the stable lens entry point
+///
+/// List `streaming` and/or `mcp` after the constructor to also export the
+/// [`StreamingLens`] and [`McpServerLens`] entry points; the lens type must
+/// implement those traits. Hosts reach them through
+/// `LoadedLens::as_streaming` and `LoadedLens::as_mcp`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use lens::export_lens;
+///
+/// struct MyLens { /* ... */ }
+/// impl Lens for MyLens { /* ... */ }
+/// impl StreamingLens for MyLens { /* ... */ }
+///
+/// export_lens!(MyLens::new(), streaming);
+/// ```
+#[macro_export]
+macro_rules! export_lens {
+    ($constructor:expr $(, $capability:ident)* $(,)?) => {
+        #[no_mangle]
+        pub extern "C" fn lens_abi_v1() -> $crate::abi::LensVTable {
+            $crate::abi::LensVTable::catching(|| $constructor)
+        }
+
+        #[no_mangle]
+        pub extern "C" fn lens_build_info() -> $crate::abi::LensBuildInfo {
+            $crate::abi::LensBuildInfo::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+        }
+
+        $($crate::__export_lens_capability!($capability, $constructor);)*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __export_lens_capability {
+    (streaming, $constructor:expr) => {
+        #[no_mangle]
+        pub extern "C" fn lens_streaming_v1() -> $crate::abi::StreamingVTable {
+            $crate::abi::StreamingVTable::new(|| $constructor)
+        }
+    };
+    (mcp, $constructor:expr) => {
+        #[no_mangle]
+        pub extern "C" fn lens_mcp_v1() -> $crate::abi::McpVTable {
+            $crate::abi::McpVTable::new(|| $constructor)
+        }
+    };
+}
@@ -0,0 +1,247 @@
+//! # Run Artifacts
+//!
+//! Per-run artifact storage with an append-only index.
+//!
+//! Requires the `runtime` feature.
+//!
+//! # Directory Structure
+//!
+//! ```text
+//! <artifacts root>/
+//! └── <run_id>/
+//!     ├── artifacts.jsonl     # Append-only index, one ArtifactRecord per line
+//!     └── files/
+//!         └── tokens.json
+//! ```
+//!
+//! Each index entry is written with a single `O_APPEND` write while holding the
+//! store lock, so concurrent writers never interleave partial records.
+//!
+//! # Scope
+//!
+//! The store is standalone: hosts choose the run ids, store artifacts with
+//! [`ArtifactStore::put`], and read a run's index with
+//! [`ArtifactStore::artifacts`]. Runs started through `LensExecutor` or
+//! `LensPipeline` do not record artifacts on their own, and the MCP gateway
+//! does not serve them; a host exposing artifacts over MCP lists them
+//! through its own tools.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{LensError, Result};
+
+/// Index filename inside each run directory
+pub const ARTIFACT_INDEX_FILENAME: &str = "artifacts.jsonl";
+
+/// One artifact produced by a run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArtifactRecord {
+    /// Artifact name, unique within the run (e.g. "tokens.json")
+    pub name: String,
+    /// Path relative to the run directory
+    pub path: PathBuf,
+    /// Content hash, formatted as "sha256:<hex>"
+    pub hash: String,
+    /// MIME type (e.g. "application/json")
+    pub mime_type: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Pipeline step or phase that produced the artifact
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<String>,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+}
+
+/// Filesystem-backed artifact store
+#[derive(Debug)]
+pub struct ArtifactStore {
+    root: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl ArtifactStore {
+    /// Create a store rooted at the given directory
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Directory holding a run's artifacts and index
+    pub fn run_dir(&self, run_id: &str) -> PathBuf {
+        self.root.join(run_id)
+    }
+
+    /// Store an artifact for a run and append it to the run's index
+    pub fn put(
+        &self,
+        run_id: &str,
+        name: &str,
+        bytes: &[u8],
+        mime_type: &str,
+        step: Option<&str>,
+    ) -> Result<ArtifactRecord> {
+        validate_segment("run id", run_id)?;
+        validate_segment("artifact name", name)?;
+
+        let relative = PathBuf::from("files").join(name);
+        let run_dir = self.run_dir(run_id);
+        std::fs::create_dir_all(run_dir.join("files"))?;
+
+        let record = ArtifactRecord {
+            name: name.to_string(),
+            path: relative.clone(),
+            hash: format!("sha256:{:x}", Sha256::digest(bytes)),
+            mime_type: mime_type.to_string(),
+            size: bytes.len() as u64,
+            step: step.map(str::to_string),
+            created_at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+
+        let _guard = self
+            .lock
+            .lock()
+            .map_err(|_| LensError::Other("Artifact store lock poisoned".to_string()))?;
+
+        if self.artifacts(run_id)?.iter().any(|a| a.name == name) {
+            return Err(LensError::InvalidInput(format!(
+                "Artifact '{}' already exists for run '{}'",
+                name, run_id
+            )));
+        }
+
+        std::fs::write(run_dir.join(&relative), bytes)?;
+
+        let mut index = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(run_dir.join(ARTIFACT_INDEX_FILENAME))?;
+        index.write_all(&line)?;
+
+        Ok(record)
+    }
+
+    /// List all artifacts recorded for a run, in the order they were produced
+    pub fn artifacts(&self, run_id: &str) -> Result<Vec<ArtifactRecord>> {
+        let index_path = self.run_dir(run_id).join(ARTIFACT_INDEX_FILENAME);
+        if !index_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        std::fs::read_to_string(&index_path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(LensError::from))
+            .collect()
+    }
+
+    /// Absolute path to a stored artifact
+    pub fn artifact_path(&self, run_id: &str, record: &ArtifactRecord) -> PathBuf {
+        self.run_dir(run_id).join(&record.path)
+    }
+}
+
+pub(crate) fn validate_segment(kind: &str, value: &str) -> Result<()> {
+    if value.is_empty() || value.contains('/') || value.contains('\\') || value == ".." {
+        return Err(LensError::InvalidInput(format!(
+            "Invalid {} '{}': must be a single path segment",
+            kind, value
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_put_and_list_artifacts() {
+        let temp_dir = tempdir().unwrap();
+        let store = ArtifactStore::new(temp_dir.path());
+
+        let record = store
+            .put(
+                "run-1",
+                "tokens.json",
+                b"{}",
+                "application/json",
+                Some("phase_0"),
+            )
+            .unwrap();
+        assert_eq!(record.size, 2);
+        assert!(record.hash.starts_with("sha256:"));
+        assert_eq!(
+            std::fs::read(store.artifact_path("run-1", &record)).unwrap(),
+            b"{}"
+        );
+
+        store
+            .put("run-1", "preview.png", b"png", "image/png", None)
+            .unwrap();
+
+        let artifacts = store.artifacts("run-1").unwrap();
+        let names: Vec<&str> = artifacts.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["tokens.json", "preview.png"]);
+        assert_eq!(artifacts[0].step.as_deref(), Some("phase_0"));
+        assert!(store.artifacts("run-2").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_duplicates_and_path_escapes() {
+        let temp_dir = tempdir().unwrap();
+        let store = ArtifactStore::new(temp_dir.path());
+
+        store.put("run", "a.txt", b"a", "text/plain", None).unwrap();
+        assert!(store.put("run", "a.txt", b"b", "text/plain", None).is_err());
+        assert!(store
+            .put("run", "../escape.txt", b"x", "text/plain", None)
+            .is_err());
+        assert!(store.put("..", "a.txt", b"x", "text/plain", None).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_appends_keep_index_intact() {
+        let temp_dir = tempdir().unwrap();
+        let store = Arc::new(ArtifactStore::new(temp_dir.path()));
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let store = Arc::clone(&store);
+                std::thread::spawn(move || {
+                    store
+                        .put(
+                            "run",
+                            &format!("part-{}.txt", i),
+                            b"data",
+                            "text/plain",
+                            None,
+                        )
+                        .unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(store.artifacts("run").unwrap().len(), 8);
+    }
+}
@@ -1,9 +1,23 @@
+use crate::cancel::CancellationToken;
+use crate::credentials::CredentialsBroker;
+use crate::events::{LensEvent, EVENT_SCHEMA_VERSION};
 use crate::oauth::OAuthBroker;
+use crate::output_spec::RenderBlockType;
+use crate::sandbox::{FsAccess, FsGuard, NetworkGuard};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+mod retry;
+mod sandboxed;
+mod scoped;
+
+pub use retry::{rpc_error_code, RetryPolicy, RetryingToolCaller};
+pub use sandboxed::SandboxedToolCaller;
+pub use scoped::ScopedToolCaller;
+
 /// Trait for invoking external MCP tools from within a lens.
 ///
 /// Injected by the host (Desktop) into `LensContext` when MCP servers are available.
@@ -27,10 +41,228 @@ pub trait ToolCaller: Send + Sync {
         name: &str,
         params: serde_json::Value,
     ) -> crate::Result<serde_json::Value>;
+
+    /// Call an MCP tool and receive partial results as they arrive.
+    ///
+    /// The stream yields any number of `Partial` chunks followed by one
+    /// `Complete` chunk (or an error). The default implementation waits for
+    /// `call_tool` and yields only the `Complete` chunk, so lenses can always
+    /// consume the stream regardless of what the host supports.
+    ///
+    /// ```ignore
+    /// let mut stream = caller.call_tool_streaming("search", json!({"q": "button"})).await?;
+    /// while let Some(chunk) = stream.next().await {
+    ///     match chunk? {
+    ///         ToolResultChunk::Partial(hit) => emitter.data(hit).await?,
+    ///         ToolResultChunk::Complete(result) => return Ok(result),
+    ///     }
+    /// }
+    /// ```
+    async fn call_tool_streaming(
+        &self,
+        name: &str,
+        params: serde_json::Value,
+    ) -> crate::Result<ToolResultStream> {
+        let result = self.call_tool(name, params).await?;
+        Ok(Box::pin(tokio_stream::once(Ok(ToolResultChunk::Complete(
+            result,
+        )))))
+    }
+}
+
+/// One item of a streaming tool call
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolResultChunk {
+    /// Intermediate output (e.g. a search hit or MCP progress notification)
+    Partial(serde_json::Value),
+    /// The tool's final result; always the last chunk
+    Complete(serde_json::Value),
+}
+
+/// Stream of chunks from [`ToolCaller::call_tool_streaming`]
+pub type ToolResultStream =
+    std::pin::Pin<Box<dyn tokio_stream::Stream<Item = crate::Result<ToolResultChunk>> + Send>>;
+
+/// Capabilities advertised by the host running a lens.
+///
+/// Lenses read this from `ctx.host` to adapt their behavior instead of guessing,
+/// e.g. skipping interactive checkpoints when running headless under an agent, or
+/// avoiding render block types the host cannot display.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HostInfo {
+    /// Host name (e.g. "graphyn-desktop", "mcp-stdio")
+    pub name: String,
+
+    /// Host version
+    #[serde(default)]
+    pub version: String,
+
+    /// Render block types the host can display.
+    /// Empty means the host did not advertise a list and supports the full catalog.
+    #[serde(default)]
+    pub supported_block_types: Vec<RenderBlockType>,
+
+    /// `LensEvent` schema versions the host understands
+    #[serde(default = "default_event_versions")]
+    pub supported_event_versions: Vec<u32>,
+
+    /// Whether a user is present to answer interactive outputs (checkpoints, forms)
+    #[serde(default = "default_interactive")]
+    pub interactive: bool,
+
+    /// Whether the lens may raise user notifications
+    #[serde(default = "default_interactive")]
+    pub notifications: bool,
+}
+
+fn default_event_versions() -> Vec<u32> {
+    (1..=EVENT_SCHEMA_VERSION).collect()
+}
+
+fn default_interactive() -> bool {
+    true
+}
+
+impl Default for HostInfo {
+    fn default() -> Self {
+        Self {
+            name: "unknown".to_string(),
+            version: String::new(),
+            supported_block_types: Vec::new(),
+            supported_event_versions: default_event_versions(),
+            interactive: true,
+            notifications: true,
+        }
+    }
+}
+
+impl HostInfo {
+    /// Create host info for a named host with default capabilities
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Create host info for a headless host (no user present to answer prompts)
+    pub fn headless(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            interactive: false,
+            notifications: false,
+            ..Self::new(name, version)
+        }
+    }
+
+    /// Restrict the advertised render block types (builder pattern)
+    pub fn with_block_types(mut self, block_types: Vec<RenderBlockType>) -> Self {
+        self.supported_block_types = block_types;
+        self
+    }
+
+    /// Check whether the host can render a block type
+    pub fn supports_block_type(&self, block_type: RenderBlockType) -> bool {
+        self.supported_block_types.is_empty() || self.supported_block_types.contains(&block_type)
+    }
+
+    /// Restrict the advertised `LensEvent` schema versions (builder pattern)
+    pub fn with_event_versions(mut self, versions: Vec<u32>) -> Self {
+        self.supported_event_versions = versions;
+        self
+    }
+
+    /// Check whether the host understands a `LensEvent` schema version
+    pub fn supports_event_version(&self, version: u32) -> bool {
+        self.supported_event_versions.contains(&version)
+    }
+
+    /// Newest `LensEvent` schema version both the host and this crate
+    /// understand; version 1 when the host advertises none
+    pub fn event_version(&self) -> u32 {
+        self.supported_event_versions
+            .iter()
+            .copied()
+            .filter(|version| *version <= EVENT_SCHEMA_VERSION)
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Rewrite `event` for the newest schema version the host understands
+    pub fn adapt_event(&self, event: LensEvent) -> LensEvent {
+        event.for_schema_version(self.event_version())
+    }
+}
+
+/// Who started a lens run.
+///
+/// Recorded on the context and on `LensEvent::Started` so audit logs and run
+/// history can answer "which agent invoked this lens".
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Initiator {
+    /// A user invoked the lens (e.g. via @mention)
+    #[default]
+    User,
+    /// An agent invoked the lens (e.g. via an MCP tool call)
+    Agent {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        agent_id: Option<String>,
+        /// MCP tool the agent called, if any
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tool: Option<String>,
+    },
+    /// A host scheduler ran the lens from a declared schedule
+    Schedule {
+        /// Schedule or trigger identifier
+        schedule: String,
+    },
+    /// An external event triggered the lens (file change, webhook, another lens)
+    Trigger {
+        /// Trigger source description
+        source: String,
+    },
+}
+
+impl Initiator {
+    /// Agent initiator for an MCP tool call
+    pub fn agent_tool_call(agent_id: Option<String>, tool: impl Into<String>) -> Self {
+        Self::Agent {
+            agent_id,
+            tool: Some(tool.into()),
+        }
+    }
+
+    /// Whether the run was started by an agent rather than a person or schedule
+    pub fn is_agent(&self) -> bool {
+        matches!(self, Self::Agent { .. })
+    }
+}
+
+impl std::fmt::Display for Initiator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::User => write!(f, "user"),
+            Self::Agent { agent_id, tool } => {
+                write!(f, "agent:{}", agent_id.as_deref().unwrap_or("unknown"))?;
+                if let Some(tool) = tool {
+                    write!(f, " via {}", tool)?;
+                }
+                Ok(())
+            }
+            Self::Schedule { schedule } => write!(f, "schedule:{}", schedule),
+            Self::Trigger { source } => write!(f, "trigger:{}", source),
+        }
+    }
 }
 
 /// Context passed to lens execution
+///
+/// Build it with [`new`](Self::new) or [`with_config`](Self::with_config)
+/// and the `with_*` builders; the struct is `#[non_exhaustive]` so hosts
+/// are not broken when it gains fields.
 #[derive(Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct LensContext {
     /// Current working directory
     pub cwd: PathBuf,
@@ -42,6 +274,14 @@ pub struct LensContext {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub config: Option<serde_json::Value>,
 
+    /// Capabilities of the host running this lens
+    #[serde(default)]
+    pub host: HostInfo,
+
+    /// Who started this run
+    #[serde(default)]
+    pub initiator: Initiator,
+
     /// Optional MCP tool caller — injected by host (Desktop) when available.
     /// Lenses should always fall back gracefully when this is `None`.
     #[serde(skip)]
@@ -51,6 +291,40 @@ pub struct LensContext {
     /// Lenses declare OAuth requirements in lens.toml → Desktop injects broker.
     #[serde(skip)]
     pub oauth_broker: Option<Arc<dyn OAuthBroker>>,
+
+    /// Optional credentials broker — injected by host for API keys, basic auth,
+    /// and other non-OAuth secrets.
+    #[serde(skip)]
+    pub credentials_broker: Option<Arc<dyn CredentialsBroker>>,
+
+    /// Account the host preselected per OAuth provider, keyed by provider id.
+    /// Pass it to `OAuthBroker::get_token_for_account`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub accounts: HashMap<String, String>,
+
+    /// Filesystem permissions checked by [`read_file`](Self::read_file) and
+    /// friends — injected by the host from the lens's `[security]` section.
+    /// Without a guard those helpers refuse all access.
+    #[serde(skip)]
+    pub fs_guard: Option<Arc<FsGuard>>,
+
+    /// Network permissions checked by [`check_network`](Self::check_network)
+    /// — injected by the host from the lens's `[security]` section. Without
+    /// a guard every request is refused.
+    #[serde(skip)]
+    pub network_guard: Option<Arc<NetworkGuard>>,
+
+    /// Cancelled when the host wants the run to stop, e.g. on shutdown.
+    /// Long-running lenses should race their work against
+    /// [`cancelled`](CancellationToken::cancelled) and return early.
+    #[serde(skip)]
+    pub cancellation: CancellationToken,
+
+    /// Whose run this is, as named by the host (e.g. `"user-42/project-7"`).
+    /// A `LensExecutor` only shares cached and in-flight results between
+    /// runs with the same scope, and never for runs without one.
+    #[serde(skip)]
+    pub run_scope: Option<String>,
 }
 
 impl std::fmt::Debug for LensContext {
@@ -59,6 +333,8 @@ impl std::fmt::Debug for LensContext {
             .field("cwd", &self.cwd)
             .field("input", &self.input)
             .field("config", &self.config)
+            .field("host", &self.host)
+            .field("initiator", &self.initiator)
             .field(
                 "tool_caller",
                 &self.tool_caller.as_ref().map(|_| "<ToolCaller>"),
@@ -67,6 +343,18 @@ impl std::fmt::Debug for LensContext {
                 "oauth_broker",
                 &self.oauth_broker.as_ref().map(|_| "<OAuthBroker>"),
             )
+            .field(
+                "credentials_broker",
+                &self
+                    .credentials_broker
+                    .as_ref()
+                    .map(|_| "<CredentialsBroker>"),
+            )
+            .field("accounts", &self.accounts)
+            .field("fs_guard", &self.fs_guard)
+            .field("network_guard", &self.network_guard)
+            .field("cancelled", &self.cancellation.is_cancelled())
+            .field("run_scope", &self.run_scope)
             .finish()
     }
 }
@@ -78,22 +366,44 @@ impl LensContext {
             cwd,
             input,
             config: None,
+            host: HostInfo::default(),
+            initiator: Initiator::default(),
             tool_caller: None,
             oauth_broker: None,
+            credentials_broker: None,
+            accounts: HashMap::new(),
+            fs_guard: None,
+            network_guard: None,
+            cancellation: CancellationToken::new(),
+            run_scope: None,
         }
     }
 
     /// Create context with configuration
     pub fn with_config(cwd: PathBuf, input: serde_json::Value, config: serde_json::Value) -> Self {
         Self {
-            cwd,
-            input,
             config: Some(config),
-            tool_caller: None,
-            oauth_broker: None,
+            ..Self::new(cwd, input)
         }
     }
 
+    /// Attach host capability info to this context (builder pattern)
+    pub fn with_host(mut self, host: HostInfo) -> Self {
+        self.host = host;
+        self
+    }
+
+    /// Record who started this run (builder pattern)
+    pub fn with_initiator(mut self, initiator: Initiator) -> Self {
+        self.initiator = initiator;
+        self
+    }
+
+    /// Whether a user is present to answer interactive outputs
+    pub fn is_interactive(&self) -> bool {
+        self.host.interactive
+    }
+
     /// Attach a tool caller to this context (builder pattern)
     pub fn with_tool_caller(mut self, caller: Arc<dyn ToolCaller>) -> Self {
         self.tool_caller = Some(caller);
@@ -105,6 +415,114 @@ impl LensContext {
         self.oauth_broker = Some(broker);
         self
     }
+
+    /// Attach a credentials broker to this context (builder pattern)
+    pub fn with_credentials_broker(mut self, broker: Arc<dyn CredentialsBroker>) -> Self {
+        self.credentials_broker = Some(broker);
+        self
+    }
+
+    /// Preselect the account to use for an OAuth provider (builder pattern)
+    pub fn with_account(
+        mut self,
+        provider: impl Into<String>,
+        account_id: impl Into<String>,
+    ) -> Self {
+        self.accounts.insert(provider.into(), account_id.into());
+        self
+    }
+
+    /// Account the host preselected for `provider`, if any
+    pub fn account_for(&self, provider: &str) -> Option<&str> {
+        self.accounts.get(provider).map(String::as_str)
+    }
+
+    /// Attach the guard enforcing the lens's filesystem permissions
+    /// (builder pattern)
+    pub fn with_fs_guard(mut self, guard: Arc<FsGuard>) -> Self {
+        self.fs_guard = Some(guard);
+        self
+    }
+
+    /// Attach the guard enforcing the lens's network permissions
+    /// (builder pattern)
+    pub fn with_network_guard(mut self, guard: Arc<NetworkGuard>) -> Self {
+        self.network_guard = Some(guard);
+        self
+    }
+
+    /// Use `token` to signal cancellation of the run (builder pattern)
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Name whose run this is, so identical runs in the same scope may
+    /// share results (builder pattern).
+    ///
+    /// The scope should cover everything that changes what the lens sees
+    /// besides its input and config: the user, project, and accounts.
+    pub fn with_run_scope(mut self, scope: impl Into<String>) -> Self {
+        self.run_scope = Some(scope.into());
+        self
+    }
+
+    /// Whether the host has asked the run to stop
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Check a request to `url` against the injected [`NetworkGuard`].
+    ///
+    /// HTTP clients used by lenses call this before connecting.
+    pub fn check_network(&self, url: &str) -> crate::Result<()> {
+        match &self.network_guard {
+            Some(guard) => guard.check_url(url),
+            None => Err(crate::LensError::PermissionDenied(format!(
+                "No network access granted to this lens (cannot reach {})",
+                url
+            ))),
+        }
+    }
+
+    /// Check `access` to `path` (relative to `cwd`) against the injected
+    /// [`FsGuard`], returning the canonical path to use
+    pub fn check_fs(&self, access: FsAccess, path: impl AsRef<Path>) -> crate::Result<PathBuf> {
+        match &self.fs_guard {
+            Some(guard) => guard.check(access, path.as_ref(), &self.cwd),
+            None => Err(crate::LensError::PermissionDenied(format!(
+                "No filesystem access granted to this lens (cannot {} {:?})",
+                access.as_str(),
+                path.as_ref()
+            ))),
+        }
+    }
+
+    /// Read a file the lens has `fs:read` permission for
+    pub fn read_file(&self, path: impl AsRef<Path>) -> crate::Result<Vec<u8>> {
+        Ok(std::fs::read(self.check_fs(FsAccess::Read, path)?)?)
+    }
+
+    /// Read a UTF-8 file the lens has `fs:read` permission for
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> crate::Result<String> {
+        Ok(std::fs::read_to_string(
+            self.check_fs(FsAccess::Read, path)?,
+        )?)
+    }
+
+    /// Write a file the lens has `fs:write` permission for, creating its
+    /// parent directories
+    pub fn write_file(
+        &self,
+        path: impl AsRef<Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> crate::Result<()> {
+        let path = self.check_fs(FsAccess::Write, path)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(std::fs::write(path, contents)?)
+    }
 }
 
 /// Result returned from lens execution
@@ -164,6 +582,71 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_fs_helpers_consult_guard() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ctx = LensContext::new(temp_dir.path().to_path_buf(), json!({}));
+        let err = ctx.write_file("out/a.txt", "hi").unwrap_err();
+        assert!(matches!(err, crate::LensError::PermissionDenied(_)));
+
+        let security: crate::manifest::SecurityConfig = toml::from_str(
+            "sandbox = \"full\"\npermissions = [\"fs:write:out/*\", \"fs:read:out/*\"]",
+        )
+        .unwrap();
+        let ctx = ctx.with_fs_guard(Arc::new(FsGuard::from_security(
+            "test",
+            &security,
+            temp_dir.path(),
+        )));
+        ctx.write_file("out/a.txt", "hi").unwrap();
+        assert_eq!(ctx.read_to_string("out/a.txt").unwrap(), "hi");
+        assert!(ctx.write_file("elsewhere.txt", "hi").is_err());
+        assert!(ctx.read_file("out/../elsewhere.txt").is_err());
+    }
+
+    #[test]
+    fn test_check_network_consults_guard() {
+        let ctx = LensContext::new(PathBuf::from("/tmp"), json!({}));
+        assert!(ctx.check_network("https://api.figma.com/v1").is_err());
+
+        let security: crate::manifest::SecurityConfig =
+            toml::from_str("sandbox = \"network\"\npermissions = [\"network:*.figma.com\"]")
+                .unwrap();
+        let ctx = ctx.with_network_guard(Arc::new(NetworkGuard::from_security("figma", &security)));
+        ctx.check_network("https://api.figma.com/v1").unwrap();
+        assert!(ctx.check_network("https://example.com").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_default_streaming_yields_complete_result() {
+        use tokio_stream::StreamExt;
+
+        struct FixedCaller;
+
+        #[async_trait]
+        impl ToolCaller for FixedCaller {
+            async fn call_tool(
+                &self,
+                name: &str,
+                _params: serde_json::Value,
+            ) -> crate::Result<serde_json::Value> {
+                Ok(json!({ "tool": name }))
+            }
+        }
+
+        let chunks: Vec<_> = FixedCaller
+            .call_tool_streaming("search", json!({}))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(
+            chunks[0].as_ref().unwrap(),
+            &ToolResultChunk::Complete(json!({ "tool": "search" }))
+        );
+    }
+
     #[test]
     fn test_lens_context_new() {
         let cwd = PathBuf::from("/tmp/test");
@@ -201,6 +684,18 @@ mod tests {
         assert_eq!(deserialized.config, ctx.config);
     }
 
+    #[test]
+    fn test_lens_context_account_hints() {
+        let ctx = LensContext::new(PathBuf::from("/tmp"), json!({})).with_account("figma", "acme");
+        assert_eq!(ctx.account_for("figma"), Some("acme"));
+        assert_eq!(ctx.account_for("github"), None);
+
+        let serialized = serde_json::to_value(&ctx).unwrap();
+        assert_eq!(serialized["accounts"]["figma"], "acme");
+        let deserialized: LensContext = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized.account_for("figma"), Some("acme"));
+    }
+
     #[test]
     fn test_lens_context_config_skipped_when_none() {
         let ctx = LensContext::new(PathBuf::from("/tmp"), json!({}));
@@ -209,6 +704,60 @@ mod tests {
         assert!(!serialized.contains("config"));
     }
 
+    #[test]
+    fn test_lens_context_default_host() {
+        let ctx = LensContext::new(PathBuf::from("/tmp"), json!({}));
+
+        assert_eq!(ctx.host, HostInfo::default());
+        assert!(ctx.is_interactive());
+        assert!(ctx.host.supports_block_type(RenderBlockType::Table));
+        assert!(ctx.host.supports_event_version(EVENT_SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn test_lens_context_with_headless_host() {
+        let host = HostInfo::headless("mcp-stdio", "0.1.0")
+            .with_block_types(vec![RenderBlockType::JsonView, RenderBlockType::Notice]);
+        let ctx = LensContext::new(PathBuf::from("/tmp"), json!({})).with_host(host);
+
+        assert!(!ctx.is_interactive());
+        assert!(ctx.host.supports_block_type(RenderBlockType::JsonView));
+        assert!(!ctx
+            .host
+            .supports_block_type(RenderBlockType::CheckpointGate));
+    }
+
+    #[test]
+    fn test_host_event_version() {
+        let host = HostInfo::new("desktop", "1.0.0");
+        assert_eq!(host.event_version(), EVENT_SCHEMA_VERSION);
+        let event = LensEvent::cache_hit("figma", std::time::Duration::from_secs(1));
+        assert_eq!(host.adapt_event(event.clone()).event_type(), "CacheHit");
+
+        let old = host.with_event_versions(vec![1]);
+        assert_eq!(old.event_version(), 1);
+        assert_eq!(old.adapt_event(event).event_type(), "Progress");
+        assert_eq!(
+            HostInfo::default()
+                .with_event_versions(vec![])
+                .event_version(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_lens_context_host_defaults_when_missing() {
+        let ctx: LensContext =
+            serde_json::from_str(r#"{"cwd":"/tmp","input":{},"host":{"name":"desktop"}}"#).unwrap();
+
+        assert_eq!(ctx.host.name, "desktop");
+        assert!(ctx.host.interactive);
+        assert_eq!(ctx.host.supported_event_versions, vec![1, 2]);
+
+        let legacy: LensContext = serde_json::from_str(r#"{"cwd":"/tmp","input":{}}"#).unwrap();
+        assert_eq!(legacy.host, HostInfo::default());
+    }
+
     #[test]
     fn test_lens_result_success() {
         let output = json!({"result": "ok"});
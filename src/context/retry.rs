@@ -0,0 +1,297 @@
+//! # Retrying Tool Caller
+//!
+//! [`RetryingToolCaller`] retries transient tool call failures with
+//! exponential backoff instead of letting them fail the lens run. Hosts can
+//! wrap the caller they inject; lenses can wrap `ctx.tool_caller` themselves:
+//!
+//! ```rust,ignore
+//! let policy = RetryPolicy::new()
+//!     .with_max_attempts(4)
+//!     .with_retry_on_codes([INTERNAL_ERROR]);
+//! let caller = RetryingToolCaller::new(host_caller, policy);
+//! ```
+//!
+//! By default only transport failures (`LensError::IoError`, e.g. a closed
+//! connection or a timed out request) are retried. JSON-RPC errors are retried
+//! when their code is listed with [`RetryPolicy::with_retry_on_codes`], other
+//! errors when their [`LensError::code`] is listed with
+//! [`RetryPolicy::with_retry_on`].
+//!
+//! The same policy drives whole-run retries in `LensExecutor` (`runtime`
+//! feature).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::{ToolCaller, ToolResultStream};
+use crate::error::{LensError, Result};
+
+/// When and how often a [`RetryingToolCaller`] retries
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first call
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound for any single delay
+    pub max_backoff: Duration,
+    /// Factor applied to the delay after each retry
+    pub multiplier: f64,
+    /// JSON-RPC error codes worth retrying
+    pub retry_on_codes: Vec<i64>,
+    /// [`LensError::code`]s worth retrying, e.g. `ResourceLimitExceeded`
+    pub retry_on: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            retry_on_codes: Vec::new(),
+            retry_on: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Three attempts, backing off from 100ms, retrying transport failures only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the total number of attempts (at least one)
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the first delay and the cap on later delays
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Set the factor applied to the delay after each retry
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Also retry JSON-RPC errors with these codes
+    pub fn with_retry_on_codes(mut self, codes: impl IntoIterator<Item = i64>) -> Self {
+        self.retry_on_codes.extend(codes);
+        self
+    }
+
+    /// Also retry errors with these [`LensError::code`]s
+    pub fn with_retry_on<S: Into<String>>(mut self, codes: impl IntoIterator<Item = S>) -> Self {
+        self.retry_on.extend(codes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Delay before retry number `retry` (starting at 1)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .max(1.0)
+            .powi(retry.saturating_sub(1) as i32);
+        self.initial_backoff
+            .mul_f64(factor.min(u32::MAX as f64))
+            .min(self.max_backoff)
+    }
+
+    /// Whether `error` is worth another attempt
+    pub fn should_retry(&self, error: &LensError) -> bool {
+        match error {
+            LensError::IoError(_) => true,
+            other if self.retry_on.iter().any(|code| code == other.code()) => true,
+            other => rpc_error_code(other).is_some_and(|code| self.retry_on_codes.contains(&code)),
+        }
+    }
+}
+
+/// JSON-RPC error code carried by `error`, if it came from an MCP response.
+///
+/// Tool callers report these as `ExecutionFailed("MCP error <code>: ...")`.
+pub fn rpc_error_code(error: &LensError) -> Option<i64> {
+    let LensError::ExecutionFailed(message) = error else {
+        return None;
+    };
+    let (code, _) = message.strip_prefix("MCP error ")?.split_once(':')?;
+    code.trim().parse().ok()
+}
+
+/// [`ToolCaller`] that retries failed calls according to a [`RetryPolicy`]
+pub struct RetryingToolCaller {
+    inner: Arc<dyn ToolCaller>,
+    policy: RetryPolicy,
+}
+
+impl RetryingToolCaller {
+    /// Retry calls to `inner` according to `policy`
+    pub fn new(inner: Arc<dyn ToolCaller>, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// The policy in effect
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    async fn retry<T, F, Fut>(&self, name: &str, mut call: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Err(e) if attempt < self.policy.max_attempts && self.policy.should_retry(&e) => {
+                    let delay = self.policy.backoff(attempt);
+                    eprintln!(
+                        "Warning: tool '{}' failed (attempt {}/{}), retrying in {}ms: {}",
+                        name,
+                        attempt,
+                        self.policy.max_attempts,
+                        delay.as_millis(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                outcome => return outcome,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ToolCaller for RetryingToolCaller {
+    async fn call_tool(&self, name: &str, params: serde_json::Value) -> Result<serde_json::Value> {
+        self.retry(name, || self.inner.call_tool(name, params.clone()))
+            .await
+    }
+
+    /// Retries opening the stream; failures after the first chunk are passed
+    /// through, since partial results may already have been consumed.
+    async fn call_tool_streaming(
+        &self,
+        name: &str,
+        params: serde_json::Value,
+    ) -> Result<ToolResultStream> {
+        self.retry(name, || {
+            self.inner.call_tool_streaming(name, params.clone())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::io;
+    use std::sync::Mutex;
+
+    /// Fails with the queued errors, then succeeds
+    struct FlakyCaller {
+        failures: Mutex<Vec<LensError>>,
+        calls: Mutex<u32>,
+    }
+
+    impl FlakyCaller {
+        fn new(failures: Vec<LensError>) -> Arc<Self> {
+            Arc::new(Self {
+                failures: Mutex::new(failures),
+                calls: Mutex::new(0),
+            })
+        }
+
+        fn calls(&self) -> u32 {
+            *self.calls.lock().unwrap()
+        }
+    }
+
+    #[async_trait]
+    impl ToolCaller for FlakyCaller {
+        async fn call_tool(&self, _name: &str, _params: Value) -> Result<Value> {
+            *self.calls.lock().unwrap() += 1;
+            match self.failures.lock().unwrap().pop() {
+                Some(e) => Err(e),
+                None => Ok(json!({ "ok": true })),
+            }
+        }
+    }
+
+    fn closed() -> LensError {
+        LensError::IoError(io::Error::new(io::ErrorKind::UnexpectedEof, "closed"))
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy::new()
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(350))
+            .with_multiplier(2.0);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+        assert_eq!(policy.backoff(40), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_retry_policy_classifies_errors() {
+        let policy = RetryPolicy::new().with_retry_on_codes([-32603]);
+        let rpc = |code: i64| LensError::ExecutionFailed(format!("MCP error {}: boom", code));
+
+        assert!(policy.should_retry(&closed()));
+        assert!(policy.should_retry(&rpc(-32603)));
+        assert!(!policy.should_retry(&rpc(-32602)));
+        assert!(!policy.should_retry(&LensError::ExecutionFailed(
+            "Tool 'x' failed: boom".to_string()
+        )));
+        assert_eq!(rpc_error_code(&rpc(-32602)), Some(-32602));
+
+        let limit = LensError::ResourceLimitExceeded("ran for 30s".to_string());
+        assert!(!policy.should_retry(&limit));
+        assert!(policy
+            .with_retry_on(["ResourceLimitExceeded"])
+            .should_retry(&limit));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retrying_caller_recovers_from_transient_failures() {
+        let inner = FlakyCaller::new(vec![closed(), closed()]);
+        let caller = RetryingToolCaller::new(inner.clone(), RetryPolicy::new());
+
+        let result = caller.call_tool("search", json!({})).await.unwrap();
+        assert_eq!(result["ok"], true);
+        assert_eq!(inner.calls(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retrying_caller_gives_up() {
+        // Exhausts its attempts on transport failures
+        let inner = FlakyCaller::new(vec![closed(), closed(), closed()]);
+        let caller = RetryingToolCaller::new(inner.clone(), RetryPolicy::new());
+        assert!(matches!(
+            caller.call_tool("search", json!({})).await,
+            Err(LensError::IoError(_))
+        ));
+        assert_eq!(inner.calls(), 3);
+
+        // Does not retry permanent failures at all
+        let inner = FlakyCaller::new(vec![LensError::ExecutionFailed(
+            "MCP error -32602: Unknown tool".to_string(),
+        )]);
+        let caller = RetryingToolCaller::new(inner.clone(), RetryPolicy::new());
+        assert!(caller.call_tool("search", json!({})).await.is_err());
+        assert_eq!(inner.calls(), 1);
+    }
+}
@@ -0,0 +1,160 @@
+//! # Sandboxed Tool Caller
+//!
+//! [`SandboxedToolCaller`] keeps a lens from reaching through MCP tools what
+//! its sandbox level forbids, e.g. a `restricted` lens calling a tool that
+//! needs `fs:read`. Hosts register the tools' declared permissions (as listed
+//! by the MCP server) and inject the wrapper instead of the raw caller:
+//!
+//! ```rust,ignore
+//! let tools = host_caller.list_tools().await?;
+//! let sandboxed = SandboxedToolCaller::new(host_caller.clone(), &security).with_tools(tools);
+//! let ctx = LensContext::new(cwd, input).with_tool_caller(Arc::new(sandboxed));
+//! ```
+//!
+//! Tools that were never registered are treated as needing no permissions.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::{ToolCaller, ToolResultStream};
+use crate::error::{LensError, Result};
+use crate::manifest::{Permission, SandboxLevel, SecurityConfig};
+use crate::mcp_server::McpTool;
+
+/// [`ToolCaller`] that denies tools needing more than the lens's sandbox level
+pub struct SandboxedToolCaller {
+    inner: Arc<dyn ToolCaller>,
+    sandbox: SandboxLevel,
+    /// Declared permissions by tool name
+    tools: HashMap<String, Vec<String>>,
+}
+
+impl SandboxedToolCaller {
+    /// Restrict `inner` to tools allowed by `security.sandbox`
+    pub fn new(inner: Arc<dyn ToolCaller>, security: &SecurityConfig) -> Self {
+        Self {
+            inner,
+            sandbox: security.sandbox.clone(),
+            tools: HashMap::new(),
+        }
+    }
+
+    /// Register the permissions `tools` declare, keyed by tool name
+    pub fn with_tools(mut self, tools: impl IntoIterator<Item = McpTool>) -> Self {
+        for tool in tools {
+            self.tools.insert(tool.name, tool.permissions);
+        }
+        self
+    }
+
+    /// Register the permissions the tool `name` needs
+    pub fn with_tool(mut self, name: impl Into<String>, permissions: Vec<String>) -> Self {
+        self.tools.insert(name.into(), permissions);
+        self
+    }
+
+    /// Whether the sandbox allows every permission `tool` declares
+    pub fn permits(&self, tool: &str) -> bool {
+        self.check(tool).is_ok()
+    }
+
+    fn check(&self, tool: &str) -> Result<()> {
+        let Some(permissions) = self.tools.get(tool) else {
+            return Ok(());
+        };
+        for raw in permissions {
+            let allowed = Permission::parse(raw)
+                .is_some_and(|required| self.sandbox.permits(&required.permission_type));
+            if !allowed {
+                return Err(LensError::PermissionDenied(format!(
+                    "tool '{}' requires '{}', which the {:?} sandbox does not allow",
+                    tool, raw, self.sandbox
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolCaller for SandboxedToolCaller {
+    async fn call_tool(&self, name: &str, params: serde_json::Value) -> Result<serde_json::Value> {
+        self.check(name)?;
+        self.inner.call_tool(name, params).await
+    }
+
+    async fn call_tool_streaming(
+        &self,
+        name: &str,
+        params: serde_json::Value,
+    ) -> Result<ToolResultStream> {
+        self.check(name)?;
+        self.inner.call_tool_streaming(name, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingCaller {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ToolCaller for CountingCaller {
+        async fn call_tool(&self, name: &str, _params: Value) -> Result<Value> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(json!({ "tool": name }))
+        }
+    }
+
+    fn sandboxed(inner: Arc<CountingCaller>, sandbox: SandboxLevel) -> SandboxedToolCaller {
+        let security = SecurityConfig {
+            library_hash: None,
+            permissions: Vec::new(),
+            sandbox,
+        };
+        SandboxedToolCaller::new(inner, &security)
+            .with_tools([
+                McpTool::builder("read_file")
+                    .permission("fs:read:*")
+                    .build(),
+                McpTool::builder("fetch").permission("network:*").build(),
+            ])
+            .with_tool("broken", vec!["not-a-permission".to_string()])
+    }
+
+    #[tokio::test]
+    async fn test_sandboxed_caller_denies_by_sandbox_level() {
+        let inner = Arc::new(CountingCaller::default());
+
+        let restricted = sandboxed(inner.clone(), SandboxLevel::Restricted);
+        assert!(restricted.call_tool("search", json!({})).await.is_ok());
+        for denied in ["read_file", "fetch", "broken"] {
+            assert!(matches!(
+                restricted.call_tool(denied, json!({})).await,
+                Err(LensError::PermissionDenied(_))
+            ));
+            assert!(restricted
+                .call_tool_streaming(denied, json!({}))
+                .await
+                .is_err());
+        }
+
+        let network = sandboxed(inner.clone(), SandboxLevel::Network);
+        assert!(network.permits("fetch"));
+        assert!(!network.permits("read_file"));
+
+        let full = sandboxed(inner.clone(), SandboxLevel::Full);
+        assert!(full.call_tool("read_file", json!({})).await.is_ok());
+        assert!(!full.permits("broken"));
+
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,120 @@
+//! # Scoped Tool Caller
+//!
+//! [`ScopedToolCaller`] wraps the host's [`ToolCaller`] and rejects calls the
+//! lens's `[tool_access]` manifest section doesn't allow, before they reach
+//! any MCP server. Hosts inject it instead of the raw caller:
+//!
+//! ```rust,ignore
+//! let scoped = ScopedToolCaller::from_manifest(host_caller.clone(), &manifest);
+//! let ctx = LensContext::new(cwd, input).with_tool_caller(Arc::new(scoped));
+//! ```
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::{ToolCaller, ToolResultStream};
+use crate::error::{LensError, Result};
+use crate::manifest::{LensManifest, ToolAccess};
+
+/// [`ToolCaller`] limited to the tools a lens declared
+pub struct ScopedToolCaller {
+    lens_id: String,
+    inner: Arc<dyn ToolCaller>,
+    access: ToolAccess,
+}
+
+impl ScopedToolCaller {
+    /// Restrict `inner` to `access` for the lens `lens_id`
+    pub fn new(lens_id: impl Into<String>, inner: Arc<dyn ToolCaller>, access: ToolAccess) -> Self {
+        Self {
+            lens_id: lens_id.into(),
+            inner,
+            access,
+        }
+    }
+
+    /// Restrict `inner` to the manifest's `[tool_access]` section
+    pub fn from_manifest(inner: Arc<dyn ToolCaller>, manifest: &LensManifest) -> Self {
+        Self::new(manifest.lens.id.clone(), inner, manifest.tool_access())
+    }
+
+    /// Whether the lens may call `tool`
+    pub fn permits(&self, tool: &str) -> bool {
+        self.access.permits(tool)
+    }
+
+    fn check(&self, tool: &str) -> Result<()> {
+        if self.permits(tool) {
+            Ok(())
+        } else {
+            Err(LensError::PermissionDenied(format!(
+                "lens '{}' is not allowed to call tool '{}'",
+                self.lens_id, tool
+            )))
+        }
+    }
+}
+
+#[async_trait]
+impl ToolCaller for ScopedToolCaller {
+    async fn call_tool(&self, name: &str, params: serde_json::Value) -> Result<serde_json::Value> {
+        self.check(name)?;
+        self.inner.call_tool(name, params).await
+    }
+
+    async fn call_tool_streaming(
+        &self,
+        name: &str,
+        params: serde_json::Value,
+    ) -> Result<ToolResultStream> {
+        self.check(name)?;
+        self.inner.call_tool_streaming(name, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingCaller {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ToolCaller for CountingCaller {
+        async fn call_tool(&self, name: &str, _params: Value) -> Result<Value> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(json!({ "tool": name }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scoped_caller_filters_calls() {
+        let inner = Arc::new(CountingCaller::default());
+        let scoped = ScopedToolCaller::new(
+            "researcher",
+            inner.clone(),
+            ToolAccess {
+                allow: vec!["mcp__base__".to_string()],
+                deny: vec!["mcp__base__drop".to_string()],
+            },
+        );
+
+        assert!(scoped
+            .call_tool("mcp__base__search", json!({}))
+            .await
+            .is_ok());
+        for denied in ["mcp__base__drop", "mcp__shell__exec"] {
+            assert!(matches!(
+                scoped.call_tool(denied, json!({})).await,
+                Err(LensError::PermissionDenied(_))
+            ));
+            assert!(scoped.call_tool_streaming(denied, json!({})).await.is_err());
+        }
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,213 @@
+//! # Credentials
+//!
+//! [`CredentialsBroker`] is the single injection point for every secret a
+//! lens needs: API keys, bearer tokens, basic auth, and OAuth tokens. Hosts
+//! manage the secrets; lenses ask for them by name.
+//!
+//! ```rust,ignore
+//! let broker = ctx.credentials_broker.as_ref().ok_or(...)?;
+//! match broker.get_credential("openai").await? {
+//!     Credential::ApiKey(key) => client.api_key(key),
+//!     other => return Err(...),
+//! }
+//! ```
+//!
+//! Existing OAuth brokers plug in through [`OAuthCredentials`], which serves
+//! each provider's token as `Credential::OAuth`.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::oauth::{OAuthBroker, OAuthError, OAuthToken};
+
+/// A secret handed to a lens
+///
+/// `Debug` output never includes the secret itself.
+#[derive(Clone)]
+pub enum Credential {
+    /// API key sent as-is (e.g. an OpenAI key)
+    ApiKey(String),
+    /// Static bearer token
+    Bearer(String),
+    /// HTTP basic auth
+    Basic { username: String, password: String },
+    /// OAuth access token from an [`OAuthBroker`]
+    OAuth(OAuthToken),
+}
+
+impl Credential {
+    /// Credential kind, for logs and error messages
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Credential::ApiKey(_) => "api_key",
+            Credential::Bearer(_) => "bearer",
+            Credential::Basic { .. } => "basic",
+            Credential::OAuth(_) => "oauth",
+        }
+    }
+
+    /// Token for an `Authorization: Bearer` header, for bearer and OAuth credentials
+    pub fn bearer_token(&self) -> Option<&str> {
+        match self {
+            Credential::Bearer(token) => Some(token),
+            Credential::OAuth(token) => Some(&token.access_token),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for Credential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Credential::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &"<redacted>")
+                .finish(),
+            other => write!(f, "{}(<redacted>)", other.kind()),
+        }
+    }
+}
+
+/// Credential broker errors
+#[derive(Error, Debug)]
+pub enum CredentialError {
+    #[error("Credential not configured: {0}")]
+    NotFound(String),
+
+    #[error("Credential unavailable: {0}")]
+    Unavailable(String),
+
+    #[error(transparent)]
+    OAuth(#[from] OAuthError),
+}
+
+/// Broker interface for fetching credentials on behalf of lenses
+#[async_trait]
+pub trait CredentialsBroker: Send + Sync {
+    /// Fetch the credential registered under `name` (e.g. "openai", "figma")
+    async fn get_credential(&self, name: &str) -> Result<Credential, CredentialError>;
+
+    /// Check whether a credential is configured under `name`
+    async fn has_credential(&self, name: &str) -> bool;
+}
+
+/// In-memory credentials, for tests and hosts that resolve secrets up front
+#[derive(Clone, Default)]
+pub struct StaticCredentials {
+    credentials: HashMap<String, Credential>,
+}
+
+impl StaticCredentials {
+    /// Create an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `credential` under `name` (builder pattern)
+    pub fn with(mut self, name: impl Into<String>, credential: Credential) -> Self {
+        self.credentials.insert(name.into(), credential);
+        self
+    }
+}
+
+#[async_trait]
+impl CredentialsBroker for StaticCredentials {
+    async fn get_credential(&self, name: &str) -> Result<Credential, CredentialError> {
+        self.credentials
+            .get(name)
+            .cloned()
+            .ok_or_else(|| CredentialError::NotFound(name.to_string()))
+    }
+
+    async fn has_credential(&self, name: &str) -> bool {
+        self.credentials.contains_key(name)
+    }
+}
+
+/// Serves an [`OAuthBroker`]'s tokens as `Credential::OAuth`, keyed by provider
+pub struct OAuthCredentials<B>(pub B);
+
+#[async_trait]
+impl<B: OAuthBroker> CredentialsBroker for OAuthCredentials<B> {
+    async fn get_credential(&self, name: &str) -> Result<Credential, CredentialError> {
+        match self.0.get_token(name).await {
+            Ok(token) => Ok(Credential::OAuth(token)),
+            Err(OAuthError::NotConnected(provider)) => Err(CredentialError::NotFound(provider)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn has_credential(&self, name: &str) -> bool {
+        self.0.is_connected(name).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FigmaOnly;
+
+    #[async_trait]
+    impl OAuthBroker for FigmaOnly {
+        async fn get_token(&self, provider: &str) -> Result<OAuthToken, OAuthError> {
+            if provider != "figma" {
+                return Err(OAuthError::NotConnected(provider.to_string()));
+            }
+            Ok(OAuthToken {
+                access_token: "figma-token".to_string(),
+                refresh_token: None,
+                expires_at: None,
+                scope: None,
+            })
+        }
+
+        async fn is_connected(&self, provider: &str) -> bool {
+            provider == "figma"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_static_credentials() {
+        let broker = StaticCredentials::new()
+            .with("openai", Credential::ApiKey("sk-secret".to_string()))
+            .with(
+                "jenkins",
+                Credential::Basic {
+                    username: "ci".to_string(),
+                    password: "hunter2".to_string(),
+                },
+            );
+
+        assert!(broker.has_credential("openai").await);
+        let key = broker.get_credential("openai").await.unwrap();
+        assert!(matches!(&key, Credential::ApiKey(k) if k == "sk-secret"));
+        assert!(key.bearer_token().is_none());
+        assert!(matches!(
+            broker.get_credential("github").await,
+            Err(CredentialError::NotFound(_))
+        ));
+
+        // Secrets never show up in Debug output
+        let jenkins = broker.get_credential("jenkins").await.unwrap();
+        assert!(!format!("{:?}", key).contains("sk-secret"));
+        assert!(!format!("{:?}", jenkins).contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn test_oauth_credentials_adapter() {
+        let broker = OAuthCredentials(FigmaOnly);
+
+        assert!(broker.has_credential("figma").await);
+        let credential = broker.get_credential("figma").await.unwrap();
+        assert_eq!(credential.kind(), "oauth");
+        assert_eq!(credential.bearer_token(), Some("figma-token"));
+        assert!(matches!(
+            broker.get_credential("github").await,
+            Err(CredentialError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,403 @@
+//! # Cron Schedules
+//!
+//! Parser for the standard 5-field cron syntax used by `[[triggers]]`:
+//!
+//! ```text
+//! ┌───────── minute (0-59)
+//! │ ┌─────── hour (0-23)
+//! │ │ ┌───── day of month (1-31)
+//! │ │ │ ┌─── month (1-12 or JAN-DEC)
+//! │ │ │ │ ┌─ day of week (0-7 or SUN-SAT, 0 and 7 are Sunday)
+//! * * * * *
+//! ```
+//!
+//! Each field accepts `*`, single values, ranges (`1-5`), steps (`*/15`, `0-30/10`),
+//! and comma-separated lists. As in classic cron, when both day of month and day
+//! of week are restricted, a time matches if either one does.
+//!
+//! [`CronSchedule::next_after`] and [`CronSchedule::last_at_or_before`] find
+//! fire times on the UTC calendar.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::LensError;
+
+const MONTH_NAMES: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const DAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+/// How far fire time searches look; covers schedules that only fire on
+/// February 29th, which can be eight years apart
+const SEARCH_DAYS: i64 = 9 * 366;
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_any: bool,
+    day_of_week_any: bool,
+}
+
+impl CronSchedule {
+    /// Parse a 5-field cron expression
+    pub fn parse(expression: &str) -> crate::Result<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(invalid(
+                expression,
+                &format!("expected 5 fields, found {}", fields.len()),
+            ));
+        }
+
+        let field = |index: usize, name: &str, min: u32, max: u32, names: &[&str]| {
+            parse_field(fields[index], min, max, names)
+                .map_err(|reason| invalid(expression, &format!("{}: {}", name, reason)))
+        };
+
+        let minutes = field(0, "minute", 0, 59, &[])?;
+        let hours = field(1, "hour", 0, 23, &[])?;
+        let days_of_month = field(2, "day of month", 1, 31, &[])?;
+        let months = field(3, "month", 1, 12, &MONTH_NAMES)?;
+        let mut days_of_week = field(4, "day of week", 0, 7, &DAY_NAMES)?;
+
+        // 7 is an alias for Sunday
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+
+        Ok(Self {
+            expression: fields.join(" "),
+            minutes,
+            hours,
+            days_of_month,
+            months,
+            days_of_week,
+            day_of_month_any: fields[2] == "*",
+            day_of_week_any: fields[4] == "*",
+        })
+    }
+
+    /// The normalized expression
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Check whether the schedule fires at the given calendar minute.
+    ///
+    /// `day_of_week` is 0 for Sunday through 6 for Saturday.
+    pub fn matches(&self, minute: u32, hour: u32, day: u32, month: u32, day_of_week: u32) -> bool {
+        has(self.minutes, minute)
+            && has(self.hours, hour)
+            && self.matches_day(day, month, day_of_week)
+    }
+
+    /// First minute after `time` the schedule fires at, on the UTC calendar
+    pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+        let start = unix_minutes(time)? + 1;
+        let mut minute = start;
+        while minute < start + SEARCH_DAYS * MINUTES_PER_DAY {
+            let (days, hour, minute_of_hour) = split_minutes(minute);
+            if !self.matches_unix_day(days) {
+                minute = (days + 1) * MINUTES_PER_DAY;
+            } else if !has(self.hours, hour) {
+                minute = days * MINUTES_PER_DAY + (hour as i64 + 1) * 60;
+            } else if !has(self.minutes, minute_of_hour) {
+                minute += 1;
+            } else {
+                return Some(from_unix_minutes(minute));
+            }
+        }
+        None
+    }
+
+    /// Last minute at or before `time` the schedule fired at, on the UTC
+    /// calendar
+    pub fn last_at_or_before(&self, time: SystemTime) -> Option<SystemTime> {
+        let start = unix_minutes(time)?;
+        let mut minute = start;
+        while minute >= 0 && minute > start - SEARCH_DAYS * MINUTES_PER_DAY {
+            let (days, hour, minute_of_hour) = split_minutes(minute);
+            if !self.matches_unix_day(days) {
+                minute = days * MINUTES_PER_DAY - 1;
+            } else if !has(self.hours, hour) {
+                minute = days * MINUTES_PER_DAY + hour as i64 * 60 - 1;
+            } else if !has(self.minutes, minute_of_hour) {
+                minute -= 1;
+            } else {
+                return Some(from_unix_minutes(minute));
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, day: u32, month: u32, day_of_week: u32) -> bool {
+        if !has(self.months, month) {
+            return false;
+        }
+        let day_of_month = has(self.days_of_month, day);
+        let weekday = has(self.days_of_week, day_of_week % 7);
+        match (self.day_of_month_any, self.day_of_week_any) {
+            (false, false) => day_of_month || weekday,
+            _ => day_of_month && weekday,
+        }
+    }
+
+    /// Whether the schedule fires on the day `days` after the Unix epoch
+    fn matches_unix_day(&self, days: i64) -> bool {
+        let (_, month, day) = civil_from_days(days);
+        // 1970-01-01 was a Thursday
+        let day_of_week = (days + 4).rem_euclid(7) as u32;
+        self.matches_day(day, month, day_of_week)
+    }
+}
+
+fn has(mask: u64, value: u32) -> bool {
+    value < 64 && mask & (1 << value) != 0
+}
+
+/// Whole minutes since the Unix epoch, or `None` before it
+fn unix_minutes(time: SystemTime) -> Option<i64> {
+    let secs = time.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some((secs / 60) as i64)
+}
+
+fn from_unix_minutes(minutes: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(minutes as u64 * 60)
+}
+
+/// Day since the epoch, hour, and minute of a minute since the epoch
+fn split_minutes(minutes: i64) -> (i64, u32, u32) {
+    let minute_of_day = minutes.rem_euclid(MINUTES_PER_DAY);
+    (
+        minutes.div_euclid(MINUTES_PER_DAY),
+        (minute_of_day / 60) as u32,
+        (minute_of_day % 60) as u32,
+    )
+}
+
+/// Year, month (1-12), and day (1-31) of the day `days` after the Unix
+/// epoch, in the proleptic Gregorian calendar (Howard Hinnant's algorithm)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+impl std::str::FromStr for CronSchedule {
+    type Err = LensError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = LensError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(schedule: CronSchedule) -> Self {
+        schedule.expression
+    }
+}
+
+impl std::fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+fn invalid(expression: &str, reason: &str) -> LensError {
+    LensError::InvalidInput(format!(
+        "Invalid cron expression '{}': {}",
+        expression, reason
+    ))
+}
+
+/// Parse one field into a bitmask of allowed values
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut mask = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("invalid step '{}'", step))?;
+                if step == 0 {
+                    return Err("step must be greater than zero".to_string());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_value(start, min, max, names)?,
+                parse_value(end, min, max, names)?,
+            )
+        } else {
+            let value = parse_value(range, min, max, names)?;
+            // "5/15" means "from 5 to the end, every 15"
+            let end = if part.contains('/') { max } else { value };
+            (value, end)
+        };
+
+        if start > end {
+            return Err(format!("range {}-{} is reversed", start, end));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+
+    Ok(mask)
+}
+
+fn parse_value(value: &str, min: u32, max: u32, names: &[&str]) -> Result<u32, String> {
+    let parsed = match names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(value))
+    {
+        // Month names start at 1, day names at 0
+        Some(index) => index as u32 + min,
+        None => value
+            .parse()
+            .map_err(|_| format!("invalid value '{}'", value))?,
+    };
+
+    if parsed < min || parsed > max {
+        return Err(format!("value {} out of range {}-{}", parsed, min, max));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_schedule() {
+        let schedule = CronSchedule::parse("0 9 * * *").unwrap();
+        assert!(schedule.matches(0, 9, 15, 6, 3));
+        assert!(!schedule.matches(1, 9, 15, 6, 3));
+        assert!(!schedule.matches(0, 10, 15, 6, 3));
+    }
+
+    #[test]
+    fn test_steps_ranges_and_names() {
+        let schedule = CronSchedule::parse("*/15 9-17 * JAN-MAR MON-FRI").unwrap();
+        assert!(schedule.matches(45, 17, 10, 2, 1));
+        assert!(!schedule.matches(50, 12, 10, 2, 1));
+        assert!(!schedule.matches(0, 12, 10, 4, 1));
+        assert!(!schedule.matches(0, 12, 10, 2, 0));
+
+        let offset = CronSchedule::parse("5/20 * * * *").unwrap();
+        assert!(offset.matches(25, 0, 1, 1, 0));
+        assert!(!offset.matches(20, 0, 1, 1, 0));
+    }
+
+    #[test]
+    fn test_sunday_alias_and_day_union() {
+        let sunday = CronSchedule::parse("0 0 * * 7").unwrap();
+        assert!(sunday.matches(0, 0, 5, 1, 0));
+
+        // Both day fields restricted: either may match
+        let either = CronSchedule::parse("0 0 1 * MON").unwrap();
+        assert!(either.matches(0, 0, 1, 1, 3));
+        assert!(either.matches(0, 0, 12, 1, 1));
+        assert!(!either.matches(0, 0, 12, 1, 3));
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn test_next_and_last_fire_times() {
+        // 2024-03-15 10:30:20 UTC, a Friday
+        let now = at(1_710_498_620);
+        let daily = CronSchedule::parse("0 9 * * *").unwrap();
+        assert_eq!(daily.next_after(now), Some(at(1_710_493_200 + 86_400)));
+        assert_eq!(daily.last_at_or_before(now), Some(at(1_710_493_200)));
+        assert_eq!(
+            daily.last_at_or_before(at(1_710_493_200)),
+            Some(at(1_710_493_200))
+        );
+        assert_eq!(
+            daily.next_after(at(1_710_493_200)),
+            Some(at(1_710_493_200 + 86_400))
+        );
+
+        // Next Monday 08:00, 2024-03-18
+        let weekly = CronSchedule::parse("0 8 * * MON").unwrap();
+        assert_eq!(weekly.next_after(now), Some(at(1_710_748_800)));
+
+        let every_quarter = CronSchedule::parse("*/15 * * * *").unwrap();
+        assert_eq!(every_quarter.next_after(now), Some(at(1_710_499_500)));
+        assert_eq!(
+            every_quarter.last_at_or_before(now),
+            Some(at(1_710_498_600))
+        );
+
+        let leap_day = CronSchedule::parse("0 0 29 2 *").unwrap();
+        assert_eq!(leap_day.next_after(now), Some(at(1_835_395_200)));
+
+        let never = CronSchedule::parse("0 0 31 2 *").unwrap();
+        assert_eq!(never.next_after(now), None);
+        assert_eq!(never.last_at_or_before(now), None);
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for expression in [
+            "0 9 * *",
+            "60 * * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "x * * * *",
+        ] {
+            let err = CronSchedule::parse(expression).unwrap_err();
+            assert!(
+                err.to_string().contains("Invalid cron expression"),
+                "{}",
+                expression
+            );
+        }
+    }
+}
@@ -16,12 +16,33 @@
 //!     ├── lens.toml
 //!     └── libvibe.dylib
 //! ```
+//!
+//! [`LensDiscovery::for_workspace`] also scans the nearest project-local
+//! `.graphyn/lenses` above the working directory; its lenses take precedence
+//! over user lenses with the same id.
+//!
+//! Hosts that scan often can enable [`LensDiscovery::with_cache`] to reuse
+//! parsed lenses until their files change.
 
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use crate::error::{LensError, Result};
-use crate::manifest::{LensManifest, LensSurface};
-use crate::output_spec::{LensOutputSpec, OUTPUT_SPEC_FILENAME};
+use crate::lens::Lens;
+use crate::loader::LensCapabilities;
+use crate::manifest::{
+    current_platform, EnvVar, HookEvent, LensEntry, LensEntryType, LensExample, LensHook,
+    LensManifest, LensMetadata, LensSurface, LensTrigger, OAuthProviderRequirement,
+};
+use crate::oauth::OAuthBroker;
+use crate::output_spec::{InteractivityMode, LensOutputSpec, OUTPUT_SPEC_FILENAME};
+
+mod cache;
+mod deps;
+mod watch;
+
+pub use deps::{resolve_load_order, DependencyError, DependencyIssue};
+pub use watch::{DiscoveryEvent, LensWatchStream};
 
 /// Manifest filename
 pub const MANIFEST_FILENAME: &str = "lens.toml";
@@ -31,6 +52,87 @@ pub const LENS_URI_PREFIX: &str = "lens:";
 /// Default lenses directory name
 pub const LENS_DIR: &str = "lenses";
 
+/// Scans only use extra threads once each would load at least this many lenses
+const PARALLEL_SCAN_MIN_LENSES_PER_THREAD: usize = 4;
+
+/// Marker file that turns a lens off without uninstalling it
+pub const DISABLED_MARKER: &str = ".disabled";
+
+/// A mismatch between `lens.toml` message types and `lens.output.yaml` outputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsistencyIssue {
+    /// A manifest message type has no matching output definition
+    MissingOutput { key: String },
+    /// An output definition has no matching manifest message type
+    MissingMessageType { key: String },
+    /// `interactive` in the manifest disagrees with `interactivity` in the output spec
+    InteractivityMismatch {
+        key: String,
+        interactive: bool,
+        interactivity: InteractivityMode,
+    },
+}
+
+impl std::fmt::Display for ConsistencyIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingOutput { key } => write!(
+                f,
+                "message type '{}' has no output definition in {}",
+                key, OUTPUT_SPEC_FILENAME
+            ),
+            Self::MissingMessageType { key } => write!(
+                f,
+                "output '{}' has no message type in {}",
+                key, MANIFEST_FILENAME
+            ),
+            Self::InteractivityMismatch {
+                key,
+                interactive,
+                interactivity,
+            } => write!(
+                f,
+                "message type '{}' has interactive = {} but its output has interactivity {:?}",
+                key, interactive, interactivity
+            ),
+        }
+    }
+}
+
+/// A declared OAuth provider the user hasn't connected yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingConnection {
+    /// Provider identifier (e.g. "figma")
+    pub provider: String,
+    /// Scopes the lens will request once connected
+    pub scopes: Vec<String>,
+    /// Whether the lens can run without it
+    pub optional: bool,
+    /// Why the lens needs the provider, for the connect prompt
+    pub description: Option<String>,
+}
+
+impl From<&OAuthProviderRequirement> for MissingConnection {
+    fn from(requirement: &OAuthProviderRequirement) -> Self {
+        Self {
+            provider: requirement.provider.clone(),
+            scopes: requirement.scopes.clone(),
+            optional: requirement.optional,
+            description: requirement.description.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for MissingConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "connect {} first", self.provider)?;
+        if let Some(description) = &self.description {
+            write!(f, " ({})", description)?;
+        }
+        Ok(())
+    }
+}
+
 /// A discovered Lens with its manifest and location
 #[derive(Debug, Clone)]
 pub struct DiscoveredLens {
@@ -51,9 +153,62 @@ pub struct DiscoveredLens {
 
     /// Path to the compiled lens library (if exists)
     pub library_path: Option<PathBuf>,
+
+    /// Path to the declared non-dylib entry point (wasm module or executable)
+    pub entry_path: Option<PathBuf>,
+
+    /// Turned off with [`LensDiscovery::set_enabled`]; hosts should not run it
+    pub disabled: bool,
+
+    /// See [`hash_verified`](Self::hash_verified)
+    hash_verified: Option<bool>,
+
+    /// Entry points the library exports; `None` until probed with
+    /// [`LensLoader::probe_discovered`](crate::loader::LensLoader::probe_discovered)
+    pub capabilities: Option<LensCapabilities>,
 }
 
 impl DiscoveredLens {
+    /// Describe a lens compiled into the host as if it had been discovered.
+    ///
+    /// The manifest carries the lens's metadata and an `embedded` entry;
+    /// there is no directory or library.
+    pub fn embedded(lens: &dyn Lens) -> Self {
+        let metadata = LensMetadata {
+            id: lens.id().to_string(),
+            name: lens.name().to_string(),
+            version: lens.version().to_string(),
+            description: lens.description().to_string(),
+            entry: Some(LensEntry {
+                entry_type: LensEntryType::Embedded,
+                path: String::new(),
+            }),
+            ..LensMetadata::default()
+        };
+        let manifest = serde_json::from_value(serde_json::json!({ "lens": metadata }))
+            .expect("a manifest with only metadata is valid");
+        Self {
+            manifest,
+            path: PathBuf::new(),
+            manifest_path: PathBuf::new(),
+            output_spec_path: None,
+            output_spec: None,
+            library_path: None,
+            entry_path: None,
+            disabled: false,
+            hash_verified: None,
+            capabilities: None,
+        }
+    }
+
+    /// Whether the binary matched `[security].library_hash` when it was
+    /// checked during discovery (see [`LensDiscovery::with_hash_verification`]);
+    /// `None` when it was not checked, the manifest declares no hash, or
+    /// there is no binary
+    pub fn hash_verified(&self) -> Option<bool> {
+        self.hash_verified
+    }
+
     /// Get the lens ID from manifest
     pub fn id(&self) -> &str {
         &self.manifest.lens.id
@@ -69,6 +224,11 @@ impl DiscoveredLens {
         &self.manifest.lens.version
     }
 
+    /// Whether the lens is enabled (has no [`DISABLED_MARKER`])
+    pub fn is_enabled(&self) -> bool {
+        !self.disabled
+    }
+
     /// Get path to a component file
     pub fn component_path(&self, component: &str) -> PathBuf {
         self.path.join(component)
@@ -94,6 +254,200 @@ impl DiscoveredLens {
             .collect()
     }
 
+    /// Entry point kind. Lenses without an `entry` declaration are dylibs.
+    pub fn entry_type(&self) -> LensEntryType {
+        self.manifest
+            .lens
+            .entry
+            .as_ref()
+            .map(|entry| entry.entry_type)
+            .unwrap_or(LensEntryType::Dylib)
+    }
+
+    /// Resolved icon for a theme (branding icon, then registry icon), if the file exists
+    pub fn icon_path(&self, dark: bool) -> Option<PathBuf> {
+        self.manifest
+            .branding
+            .as_ref()
+            .and_then(|branding| branding.icon_for_theme(dark))
+            .or_else(|| {
+                self.manifest
+                    .registry
+                    .as_ref()
+                    .and_then(|registry| registry.icon.as_deref())
+            })
+            .map(|icon| self.path.join(icon))
+            .filter(|path| path.is_file())
+    }
+
+    /// Declared accent color
+    pub fn accent_color(&self) -> Option<&str> {
+        self.manifest
+            .branding
+            .as_ref()
+            .and_then(|branding| branding.accent_color.as_deref())
+    }
+
+    /// Check if the manifest declares support for the current host platform
+    pub fn is_platform_compatible(&self) -> bool {
+        self.manifest.lens.supports_current_platform()
+    }
+
+    /// OAuth providers declared in the manifest
+    pub fn oauth_providers(&self) -> &[OAuthProviderRequirement] {
+        &self.manifest.oauth_providers
+    }
+
+    /// OAuth providers the host must connect before executing this lens
+    pub fn required_oauth_providers(&self) -> Vec<&OAuthProviderRequirement> {
+        self.manifest.required_oauth_providers()
+    }
+
+    /// Lifecycle hooks declared in `[hooks]`, in lifecycle order
+    pub fn hooks(&self) -> Vec<LensHook> {
+        self.manifest
+            .hooks
+            .as_ref()
+            .map(|hooks| hooks.all())
+            .unwrap_or_default()
+    }
+
+    /// Resolved script for `event` if the lens's sandbox level permits running it.
+    ///
+    /// Returns `Ok(None)` when no hook is declared, and an error when the hook is
+    /// declared but blocked by the sandbox level, escapes the lens directory, or is missing.
+    pub fn runnable_hook(&self, event: HookEvent) -> Result<Option<PathBuf>> {
+        let Some(hook) = self.manifest.hooks.as_ref().and_then(|h| h.get(event)) else {
+            return Ok(None);
+        };
+
+        let sandbox = self
+            .manifest
+            .security
+            .as_ref()
+            .map(|security| security.sandbox.clone())
+            .unwrap_or_default();
+        if !hook.is_allowed(&sandbox) {
+            return Err(LensError::InvalidInput(format!(
+                "Lens '{}' {} hook requires sandbox = \"full\" (declared {:?})",
+                self.id(),
+                event.as_str(),
+                sandbox
+            )));
+        }
+
+        let script = hook.resolve(&self.path)?;
+        if !script.is_file() {
+            return Err(LensError::InvalidInput(format!(
+                "Lens '{}' {} hook script {:?} does not exist",
+                self.id(),
+                event.as_str(),
+                script
+            )));
+        }
+        Ok(Some(script))
+    }
+
+    /// Scheduled triggers declared in the manifest
+    pub fn triggers(&self) -> &[LensTrigger] {
+        &self.manifest.triggers
+    }
+
+    /// Example invocations declared in the manifest
+    pub fn examples(&self) -> &[LensExample] {
+        &self.manifest.examples
+    }
+
+    /// Required environment variables that are unset or empty in the host process
+    pub fn missing_env(&self) -> Vec<&EnvVar> {
+        self.manifest
+            .env
+            .as_ref()
+            .map(|env| env.missing(|name| std::env::var(name).ok()))
+            .unwrap_or_default()
+    }
+
+    /// SHA-256 of the lens binary (library or declared entry), as "sha256:<hex>"
+    pub fn compute_library_hash(&self) -> Result<Option<String>> {
+        use sha2::{Digest, Sha256};
+
+        let Some(path) = self.library_path.as_ref().or(self.entry_path.as_ref()) else {
+            return Ok(None);
+        };
+        let bytes = std::fs::read(path)?;
+        Ok(Some(format!("sha256:{:x}", Sha256::digest(&bytes))))
+    }
+
+    /// Compare the binary on disk with the declared `[security].library_hash`.
+    ///
+    /// Returns `None` when there is no declared hash or no binary to check.
+    pub fn verify_library_hash(&self) -> Result<Option<bool>> {
+        let Some(security) = self
+            .manifest
+            .security
+            .as_ref()
+            .filter(|security| security.library_hash.is_some())
+        else {
+            return Ok(None);
+        };
+        Ok(self
+            .compute_library_hash()?
+            .map(|actual| security.verify_hash(&actual)))
+    }
+
+    /// Verify the manifest signature and that the binary on disk matches the signed hash.
+    ///
+    /// See [`LensManifest::verify_signature`] for how `trusted_keys` is applied.
+    #[cfg(feature = "signing")]
+    pub fn verify_signature(&self, trusted_keys: &[String]) -> Result<()> {
+        match self.library_path.as_ref().or(self.entry_path.as_ref()) {
+            Some(path) => crate::security::verify_signature(
+                &self.manifest,
+                &std::fs::read(path)?,
+                trusted_keys,
+            ),
+            None => self.manifest.verify_signature(trusted_keys),
+        }
+    }
+
+    /// Cross-check manifest message types against the output spec.
+    ///
+    /// Returns no issues when the lens has no output spec.
+    pub fn validate_consistency(&self) -> Vec<ConsistencyIssue> {
+        let Some(spec) = &self.output_spec else {
+            return Vec::new();
+        };
+
+        let mut issues = Vec::new();
+        for message_type in &self.manifest.message_types {
+            match spec.get_output(&message_type.key) {
+                None => issues.push(ConsistencyIssue::MissingOutput {
+                    key: message_type.key.clone(),
+                }),
+                Some(output) => {
+                    let spec_interactive = output.interactivity != InteractivityMode::None;
+                    if message_type.interactive != spec_interactive {
+                        issues.push(ConsistencyIssue::InteractivityMismatch {
+                            key: message_type.key.clone(),
+                            interactive: message_type.interactive,
+                            interactivity: output.interactivity,
+                        });
+                    }
+                }
+            }
+        }
+
+        for output in &spec.outputs {
+            if self.manifest.get_message_type(&output.key).is_none() {
+                issues.push(ConsistencyIssue::MissingMessageType {
+                    key: output.key.clone(),
+                });
+            }
+        }
+
+        issues
+    }
+
     /// Stable launch URI for this discovered lens.
     pub fn launch_uri(&self) -> String {
         format!("{}{}", LENS_URI_PREFIX, self.id())
@@ -105,6 +459,16 @@ impl DiscoveredLens {
 pub struct LensDiscovery {
     /// Base directory to scan for lenses
     lenses_dir: PathBuf,
+
+    /// Project-scoped lenses directory, whose lenses shadow same-id user lenses
+    project_dir: Option<PathBuf>,
+
+    /// Parsed lenses reused between scans, see [`with_cache`](Self::with_cache)
+    cache: Option<Arc<cache::LensCache>>,
+
+    /// Hash pinned binaries while loading, see
+    /// [`with_hash_verification`](Self::with_hash_verification)
+    verify_hashes: bool,
 }
 
 impl LensDiscovery {
@@ -112,6 +476,9 @@ impl LensDiscovery {
     pub fn new<P: AsRef<Path>>(lenses_dir: P) -> Self {
         Self {
             lenses_dir: lenses_dir.as_ref().to_path_buf(),
+            project_dir: None,
+            cache: None,
+            verify_hashes: false,
         }
     }
 
@@ -130,6 +497,40 @@ impl LensDiscovery {
         Self::new(graphyn_dir.as_ref().join(LENS_DIR))
     }
 
+    /// Create discovery for the user directory merged with the project-local
+    /// `.graphyn/lenses` nearest to `cwd`
+    pub fn for_workspace<P: AsRef<Path>>(cwd: P) -> Result<Self> {
+        Ok(Self::default_directory()?.with_workspace(cwd))
+    }
+
+    /// Also scan the `.graphyn/lenses` directory found by walking up from
+    /// `cwd` (builder pattern). Leaves discovery unchanged if there is none.
+    pub fn with_workspace<P: AsRef<Path>>(mut self, cwd: P) -> Self {
+        self.project_dir =
+            find_project_lenses_dir(cwd.as_ref()).filter(|dir| !same_dir(dir, &self.lenses_dir));
+        self
+    }
+
+    /// Also scan `project_dir`, whose lenses take precedence (builder pattern)
+    pub fn with_project_dir<P: AsRef<Path>>(mut self, project_dir: P) -> Self {
+        self.project_dir = Some(project_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Check each binary against its `[security].library_hash` while
+    /// loading, recording the result in [`DiscoveredLens::hash_verified`]
+    /// (builder pattern). Off by default, since it reads every binary;
+    /// the loader checks hashes itself before opening a library.
+    pub fn with_hash_verification(mut self, verify: bool) -> Self {
+        self.verify_hashes = verify;
+        self
+    }
+
+    /// Project-scoped lenses directory, if any
+    pub fn project_dir(&self) -> Option<&Path> {
+        self.project_dir.as_deref()
+    }
+
     /// Get the lenses directory path
     pub fn plugins_dir(&self) -> &Path {
         &self.lenses_dir
@@ -158,19 +559,36 @@ impl LensDiscovery {
         self.scan_with_options(false)
     }
 
-    /// Scan with configurable validation policies
+    /// Scan with configurable validation policies.
+    ///
+    /// Lenses in the project directory replace user lenses with the same id.
     pub fn scan_with_options(&self, require_output_spec: bool) -> Result<Vec<DiscoveredLens>> {
-        if !self.exists() {
+        let mut discovered = self.scan_dir(&self.lenses_dir, require_output_spec)?;
+
+        if let Some(project_dir) = &self.project_dir {
+            let project = self.scan_dir(project_dir, require_output_spec)?;
+            discovered.retain(|lens| project.iter().all(|p| p.id() != lens.id()));
+            discovered.extend(project);
+        }
+
+        discovered.sort_by(|a, b| {
+            a.id()
+                .cmp(b.id())
+                .then_with(|| a.manifest_path.cmp(&b.manifest_path))
+        });
+
+        Ok(discovered)
+    }
+
+    fn scan_dir(&self, dir: &Path, require_output_spec: bool) -> Result<Vec<DiscoveredLens>> {
+        if !dir.is_dir() {
             return Ok(Vec::new());
         }
 
         let mut discovered = Vec::new();
 
-        let entries = std::fs::read_dir(&self.lenses_dir).map_err(|e| {
-            LensError::Initialization(format!(
-                "Failed to read lenses directory {:?}: {}",
-                self.lenses_dir, e
-            ))
+        let entries = std::fs::read_dir(dir).map_err(|e| {
+            LensError::Initialization(format!("Failed to read lenses directory {:?}: {}", dir, e))
         })?;
 
         let mut lens_dirs = Vec::new();
@@ -184,14 +602,18 @@ impl LensDiscovery {
             if !path.is_dir() {
                 continue;
             }
+            // Hidden directories hold in-progress installs
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
 
             lens_dirs.push(path);
         }
 
         lens_dirs.sort();
 
-        for path in lens_dirs {
-            match self.load_lens(&path) {
+        for (path, loaded) in lens_dirs.iter().zip(self.load_lenses(&lens_dirs)) {
+            match loaded {
                 Ok(lens) => {
                     if require_output_spec && lens.output_spec.is_none() {
                         eprintln!(
@@ -208,13 +630,165 @@ impl LensDiscovery {
             }
         }
 
-        discovered.sort_by(|a, b| {
-            a.id()
-                .cmp(b.id())
-                .then_with(|| a.manifest_path.cmp(&b.manifest_path))
+        Ok(discovered)
+    }
+
+    /// Load `lens_dirs` in order, spreading larger directories across threads
+    fn load_lenses(&self, lens_dirs: &[PathBuf]) -> Vec<Result<DiscoveredLens>> {
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(lens_dirs.len() / PARALLEL_SCAN_MIN_LENSES_PER_THREAD);
+        if threads <= 1 {
+            return lens_dirs
+                .iter()
+                .map(|dir| self.load_lens_cached(dir))
+                .collect();
+        }
+
+        let chunk_size = lens_dirs.len().div_ceil(threads);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = lens_dirs
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|dir| self.load_lens_cached(dir))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("lens loading thread panicked"))
+                .collect()
+        })
+    }
+
+    /// Scan and drop lenses whose binary does not match their declared
+    /// `[security].library_hash`. Lenses without a declared hash are kept.
+    pub fn scan_verified(&self) -> Result<Vec<DiscoveredLens>> {
+        Ok(self
+            .scan()?
+            .into_iter()
+            .filter_map(|mut lens| {
+                if lens.hash_verified.is_none() {
+                    // An unreadable binary cannot be shown to match
+                    lens.hash_verified = lens.verify_library_hash().unwrap_or(Some(false));
+                }
+                let tampered = lens.hash_verified == Some(false);
+                if tampered {
+                    eprintln!(
+                        "Warning: Skipping lens '{}': library hash mismatch",
+                        lens.id()
+                    );
+                }
+                (!tampered).then_some(lens)
+            })
+            .collect())
+    }
+
+    /// Scan and keep only enabled lenses
+    pub fn scan_enabled(&self) -> Result<Vec<DiscoveredLens>> {
+        Ok(self
+            .scan()?
+            .into_iter()
+            .filter(DiscoveredLens::is_enabled)
+            .collect())
+    }
+
+    /// Turn the installed lens `lens_id` on or off.
+    ///
+    /// Disabling writes a [`DISABLED_MARKER`] file into the lens directory,
+    /// so its configuration and state are kept. Disabled lenses are still
+    /// returned by [`scan`](Self::scan), flagged with `disabled`.
+    pub fn set_enabled(&self, lens_id: &str, enabled: bool) -> Result<()> {
+        let lens = self
+            .scan()?
+            .into_iter()
+            .find(|lens| lens.id() == lens_id)
+            .ok_or_else(|| LensError::LensNotFound(lens_id.to_string()))?;
+        let marker = lens.path.join(DISABLED_MARKER);
+        if enabled {
+            match std::fs::remove_file(&marker) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        } else {
+            std::fs::write(&marker, "")?;
+        }
+        Ok(())
+    }
+
+    /// Scan and keep only lenses compatible with the current host platform.
+    ///
+    /// Incompatible lenses are skipped with a warning instead of failing later
+    /// with an opaque dynamic loading error.
+    pub fn scan_compatible(&self) -> Result<Vec<DiscoveredLens>> {
+        let platform = current_platform();
+        Ok(self
+            .scan()?
+            .into_iter()
+            .filter(|lens| {
+                let compatible = lens.is_platform_compatible();
+                if !compatible {
+                    eprintln!(
+                        "Warning: Lens '{}' does not support platform {} (declares {:?})",
+                        lens.id(),
+                        platform,
+                        lens.manifest.lens.platforms
+                    );
+                }
+                compatible
+            })
+            .collect())
+    }
+
+    /// Scan and keep only lenses with a valid signature from one of `trusted_keys`.
+    ///
+    /// An empty `trusted_keys` accepts no lens. Unsigned, untrusted, or
+    /// tampered lenses are skipped with a warning.
+    #[cfg(feature = "signing")]
+    pub fn scan_signed(&self, trusted_keys: &[String]) -> Result<Vec<DiscoveredLens>> {
+        Ok(self
+            .scan()?
+            .into_iter()
+            .filter(|lens| match lens.verify_signature(trusted_keys) {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!("Warning: Skipping lens '{}': {}", lens.id(), e);
+                    false
+                }
+            })
+            .collect())
+    }
+
+    /// Enabled lenses that declare `capability`, directly or through a more
+    /// specific capability that implies it (see [`crate::manifest::capability`])
+    pub fn find_by_capability(&self, capability: &str) -> Result<Vec<DiscoveredLens>> {
+        Ok(self
+            .scan_enabled()?
+            .into_iter()
+            .filter(|lens| lens.manifest.has_capability(capability))
+            .collect())
+    }
+
+    /// Search installed lenses by id, name, categories, tags, keywords, and description.
+    ///
+    /// Results are ordered by relevance, then by id.
+    pub fn search(&self, query: &str) -> Result<Vec<DiscoveredLens>> {
+        let mut scored: Vec<(u32, DiscoveredLens)> = self
+            .scan()?
+            .into_iter()
+            .map(|lens| (lens.manifest.lens.search_score(query), lens))
+            .filter(|(score, _)| *score > 0)
+            .collect();
+
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score.cmp(a_score).then_with(|| a.id().cmp(b.id()))
         });
 
-        Ok(discovered)
+        Ok(scored.into_iter().map(|(_, lens)| lens).collect())
     }
 
     /// Load a single lens from a directory
@@ -236,12 +810,40 @@ impl LensDiscovery {
             ))
         })?;
 
-        let manifest = LensManifest::from_toml(&manifest_content).map_err(|e| {
-            LensError::InvalidInput(format!(
-                "Failed to parse manifest {:?}: {}",
-                manifest_path, e
-            ))
-        })?;
+        let (manifest, migration_notes) = LensManifest::from_toml_migrated(&manifest_content)
+            .map_err(|e| {
+                LensError::InvalidInput(format!(
+                    "Failed to parse manifest {:?}: {}",
+                    manifest_path, e
+                ))
+            })?;
+
+        for note in migration_notes {
+            eprintln!(
+                "Warning: Lens '{}' uses an outdated manifest layout ({})",
+                manifest.lens.id, note
+            );
+        }
+
+        if let Err(e) = manifest.validate_examples() {
+            eprintln!(
+                "Warning: Lens '{}' has an invalid example: {}",
+                manifest.lens.id, e
+            );
+        }
+
+        for warning in manifest.deprecation_warnings() {
+            eprintln!("Warning: Lens '{}': {}", manifest.lens.id, warning);
+        }
+
+        if let Some(branding) = &manifest.branding {
+            if let Err(e) = branding.validate(lens_dir) {
+                eprintln!(
+                    "Warning: Lens '{}' has invalid branding: {}",
+                    manifest.lens.id, e
+                );
+            }
+        }
 
         // Load output spec if present
         let output_spec_path_candidate = lens_dir.join(OUTPUT_SPEC_FILENAME);
@@ -257,17 +859,52 @@ impl LensDiscovery {
             (None, None)
         };
 
-        // Look for compiled library
-        let library_path = self.find_library(lens_dir, &manifest.lens.id);
+        // Prefer the declared entry point; fall back to filename conventions
+        let (library_path, entry_path) = match &manifest.lens.entry {
+            Some(entry) => {
+                let resolved = entry.resolve(lens_dir)?;
+                if resolved.is_none() {
+                    eprintln!(
+                        "Warning: Lens '{}' declares {:?} entry '{}' but it does not exist",
+                        manifest.lens.id, entry.entry_type, entry.path
+                    );
+                }
+                match entry.entry_type {
+                    LensEntryType::Dylib => (resolved, None),
+                    LensEntryType::Wasm | LensEntryType::Subprocess => (None, resolved),
+                    LensEntryType::Embedded => (None, None),
+                }
+            }
+            None => (self.find_library(lens_dir, &manifest.lens.id), None),
+        };
 
-        Ok(DiscoveredLens {
+        let mut lens = DiscoveredLens {
             manifest,
             path: lens_dir.to_path_buf(),
             manifest_path,
             output_spec_path,
             output_spec,
             library_path,
-        })
+            entry_path,
+            disabled: lens_dir.join(DISABLED_MARKER).exists(),
+            hash_verified: None,
+            capabilities: None,
+        };
+        if self.verify_hashes {
+            lens.hash_verified = lens.verify_library_hash()?;
+            if lens.hash_verified == Some(false) {
+                eprintln!(
+                    "Warning: Lens '{}' binary does not match [security].library_hash",
+                    lens.id()
+                );
+            }
+        }
+
+        for issue in lens.validate_consistency() {
+            eprintln!("Warning: Lens '{}': {}", lens.id(), issue);
+        }
+
+        Ok(lens)
     }
 
     /// Find the compiled library for a lens
@@ -367,6 +1004,21 @@ impl LensDiscovery {
     }
 }
 
+/// Nearest `.graphyn/lenses` directory in `start` or one of its ancestors
+pub fn find_project_lenses_dir(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(".graphyn").join(LENS_DIR))
+        .find(|candidate| candidate.is_dir())
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
 /// Parse a runtime launch URI in the form `lens:<id>`.
 pub fn parse_lens_uri(launch_uri: &str) -> Result<&str> {
     let lens_id = launch_uri.strip_prefix(LENS_URI_PREFIX).ok_or_else(|| {
@@ -419,7 +1071,8 @@ pub fn load_manifest<P: AsRef<Path>>(path: P) -> Result<LensManifest> {
         ))
     })?;
 
-    LensManifest::from_toml(&content)
+    LensManifest::from_toml_migrated(&content)
+        .map(|(manifest, _)| manifest)
         .map_err(|e| LensError::InvalidInput(format!("Failed to parse manifest: {}", e)))
 }
 
@@ -428,6 +1081,24 @@ pub fn load_output_spec<P: AsRef<Path>>(path: P) -> Result<LensOutputSpec> {
     LensOutputSpec::from_file(path)
 }
 
+/// OAuth providers declared by `lens` that `broker` reports as not connected.
+///
+/// Hosts call this before execution to prompt the user to connect accounts.
+/// Optional providers are included with `optional: true`; only the others
+/// should block the run.
+pub async fn check_auth_requirements(
+    lens: &DiscoveredLens,
+    broker: &dyn OAuthBroker,
+) -> Vec<MissingConnection> {
+    let mut missing = Vec::new();
+    for requirement in lens.oauth_providers() {
+        if !broker.is_connected(&requirement.provider).await {
+            missing.push(MissingConnection::from(requirement));
+        }
+    }
+    missing
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -462,6 +1133,42 @@ component = "components/Result.tsx"
         fs::write(lens_dir.join(MANIFEST_FILENAME), manifest).unwrap();
     }
 
+    #[test]
+    fn test_workspace_lenses_shadow_user_lenses() {
+        let temp_dir = tempdir().unwrap();
+        let user_dir = temp_dir.path().join("home").join(LENS_DIR);
+        let repo = temp_dir.path().join("repo");
+        let project_dir = repo.join(".graphyn").join(LENS_DIR);
+        let cwd = repo.join("src").join("deep");
+        fs::create_dir_all(&cwd).unwrap();
+
+        create_test_lens(&user_dir, "figma", "Figma");
+        create_test_lens(&user_dir, "vibe", "User Vibe");
+        create_test_lens(&project_dir, "vibe", "Project Vibe");
+        create_test_lens(&project_dir, "release", "Release Notes");
+
+        assert_eq!(find_project_lenses_dir(&cwd), Some(project_dir.clone()));
+        let discovery = LensDiscovery::new(&user_dir).with_workspace(&cwd);
+        assert_eq!(discovery.project_dir(), Some(project_dir.as_path()));
+
+        let lenses = discovery.scan().unwrap();
+        let names: Vec<(&str, &str)> = lenses.iter().map(|l| (l.id(), l.name())).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("figma", "Figma"),
+                ("release", "Release Notes"),
+                ("vibe", "Project Vibe"),
+            ]
+        );
+        assert!(discovery.resolve_lens_uri("lens:vibe").is_ok());
+
+        // Outside the repo only user lenses are found
+        let elsewhere = LensDiscovery::new(&user_dir).with_workspace(temp_dir.path());
+        assert!(elsewhere.project_dir().is_none());
+        assert_eq!(elsewhere.scan().unwrap().len(), 2);
+    }
+
     #[test]
     fn test_scan_empty_directory() {
         let temp_dir = tempdir().unwrap();
@@ -506,6 +1213,138 @@ component = "components/Result.tsx"
         assert_eq!(ids, vec!["alpha", "middle", "zeta"]);
     }
 
+    #[test]
+    fn test_parallel_scan_keeps_order_and_skips_broken_lenses() {
+        let temp_dir = tempdir().unwrap();
+        for i in 0..40 {
+            let id = format!("lens-{:02}", i);
+            create_test_lens(temp_dir.path(), &id, &id);
+        }
+        let broken = temp_dir.path().join("broken");
+        fs::create_dir_all(&broken).unwrap();
+        fs::write(broken.join(MANIFEST_FILENAME), "not toml").unwrap();
+
+        let lenses = LensDiscovery::new(temp_dir.path()).scan().unwrap();
+        let ids: Vec<String> = lenses.iter().map(|lens| lens.id().to_string()).collect();
+        let expected: Vec<String> = (0..40).map(|i| format!("lens-{:02}", i)).collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    fn test_find_by_capability() {
+        let temp_dir = tempdir().unwrap();
+        for (id, capabilities) in [
+            ("kb", r#"["semantic_search", "indexing"]"#),
+            ("grep", r#"["full-text-search"]"#),
+            ("figma", r#"["design-decomposition"]"#),
+        ] {
+            let dir = temp_dir.path().join(id);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join(MANIFEST_FILENAME),
+                format!(
+                    "capabilities = {}\n\n[lens]\nid = \"{}\"\nname = \"{}\"\nversion = \"1.0.0\"\n",
+                    capabilities, id, id
+                ),
+            )
+            .unwrap();
+        }
+        let discovery = LensDiscovery::new(temp_dir.path());
+        let ids = |capability: &str| -> Vec<String> {
+            discovery
+                .find_by_capability(capability)
+                .unwrap()
+                .iter()
+                .map(|lens| lens.id().to_string())
+                .collect()
+        };
+
+        assert_eq!(ids("semantic-search"), vec!["kb"]);
+        assert_eq!(ids("search"), vec!["grep", "kb"]);
+        assert!(ids("image-generation").is_empty());
+
+        discovery.set_enabled("grep", false).unwrap();
+        assert_eq!(ids("search"), vec!["kb"]);
+    }
+
+    #[test]
+    fn test_scan_records_library_hash_verification() {
+        use sha2::{Digest, Sha256};
+
+        let temp_dir = tempdir().unwrap();
+        let good_hash = format!("sha256:{:x}", Sha256::digest(b"compiled"));
+        for (id, hash) in [
+            ("good", Some(good_hash.as_str())),
+            ("tampered", Some("sha256:0000")),
+            ("unpinned", None),
+        ] {
+            let dir = temp_dir.path().join(id);
+            fs::create_dir_all(&dir).unwrap();
+            let mut manifest = format!(
+                "[lens]\nid = \"{}\"\nname = \"{}\"\nversion = \"1.0.0\"\n",
+                id, id
+            );
+            if let Some(hash) = hash {
+                manifest.push_str(&format!("\n[security]\nlibrary_hash = \"{}\"\n", hash));
+            }
+            fs::write(dir.join(MANIFEST_FILENAME), manifest).unwrap();
+            fs::write(
+                dir.join(format!("lib{}.{}", id, std::env::consts::DLL_EXTENSION)),
+                "compiled",
+            )
+            .unwrap();
+        }
+        let discovery = LensDiscovery::new(temp_dir.path());
+        let verified = |discovery: &LensDiscovery| -> Vec<Option<bool>> {
+            discovery
+                .scan()
+                .unwrap()
+                .iter()
+                .map(DiscoveredLens::hash_verified)
+                .collect()
+        };
+
+        // Binaries are only read when asked to
+        assert_eq!(verified(&discovery), vec![None, None, None]);
+        assert_eq!(
+            verified(&discovery.clone().with_hash_verification(true)),
+            vec![Some(true), Some(false), None]
+        );
+
+        let ids: Vec<String> = discovery
+            .scan_verified()
+            .unwrap()
+            .iter()
+            .map(|lens| lens.id().to_string())
+            .collect();
+        assert_eq!(ids, vec!["good", "unpinned"]);
+    }
+
+    #[test]
+    fn test_set_enabled() {
+        let temp_dir = tempdir().unwrap();
+        create_test_lens(temp_dir.path(), "figma", "Figma Decomposer");
+        create_test_lens(temp_dir.path(), "noisy", "Noisy Lens");
+        let discovery = LensDiscovery::new(temp_dir.path());
+
+        discovery.set_enabled("noisy", false).unwrap();
+        let lenses = discovery.scan().unwrap();
+        assert_eq!(lenses.len(), 2);
+        assert!(lenses[0].is_enabled());
+        assert!(lenses[1].disabled);
+        let enabled = discovery.scan_enabled().unwrap();
+        assert_eq!(enabled.len(), 1);
+        assert_eq!(enabled[0].id(), "figma");
+
+        discovery.set_enabled("noisy", true).unwrap();
+        discovery.set_enabled("noisy", true).unwrap();
+        assert_eq!(discovery.scan_enabled().unwrap().len(), 2);
+        assert!(matches!(
+            discovery.set_enabled("missing", false),
+            Err(LensError::LensNotFound(_))
+        ));
+    }
+
     #[test]
     fn test_load_single_lens() {
         let temp_dir = tempdir().unwrap();
@@ -610,6 +1449,464 @@ surfaces = ["tray"]
         assert!(path_id.to_string().contains("path separators"));
     }
 
+    #[test]
+    fn test_discovered_lens_oauth_providers() {
+        let temp_dir = tempdir().unwrap();
+        let manifest = r#"
+[lens]
+id = "figma"
+name = "Figma"
+version = "1.0.0"
+
+[[oauth_providers]]
+provider = "figma"
+scopes = ["file_read"]
+
+[[oauth_providers]]
+provider = "slack"
+optional = true
+"#;
+        create_test_lens_with_manifest(temp_dir.path(), "figma", manifest);
+
+        let discovery = LensDiscovery::new(temp_dir.path());
+        let lens = discovery.get_lens("figma").unwrap().unwrap();
+
+        assert_eq!(lens.oauth_providers().len(), 2);
+        let required: Vec<&str> = lens
+            .required_oauth_providers()
+            .iter()
+            .map(|p| p.provider.as_str())
+            .collect();
+        assert_eq!(required, vec!["figma"]);
+    }
+
+    #[tokio::test]
+    async fn test_check_auth_requirements() {
+        use crate::oauth::{OAuthError, OAuthToken};
+
+        struct SlackOnly;
+
+        #[async_trait::async_trait]
+        impl OAuthBroker for SlackOnly {
+            async fn get_token(
+                &self,
+                provider: &str,
+            ) -> std::result::Result<OAuthToken, OAuthError> {
+                Err(OAuthError::NotConnected(provider.to_string()))
+            }
+
+            async fn is_connected(&self, provider: &str) -> bool {
+                provider == "slack"
+            }
+        }
+
+        let temp_dir = tempdir().unwrap();
+        let manifest = r#"
+[lens]
+id = "figma"
+name = "Figma"
+version = "1.0.0"
+
+[[oauth_providers]]
+provider = "figma"
+scopes = ["file_read"]
+description = "Reads your design files"
+
+[[oauth_providers]]
+provider = "slack"
+optional = true
+
+[[oauth_providers]]
+provider = "github"
+optional = true
+"#;
+        create_test_lens_with_manifest(temp_dir.path(), "figma", manifest);
+        let lens = LensDiscovery::new(temp_dir.path())
+            .get_lens("figma")
+            .unwrap()
+            .unwrap();
+
+        let missing = check_auth_requirements(&lens, &SlackOnly).await;
+        let providers: Vec<(&str, bool)> = missing
+            .iter()
+            .map(|m| (m.provider.as_str(), m.optional))
+            .collect();
+        assert_eq!(providers, vec![("figma", false), ("github", true)]);
+        assert_eq!(missing[0].scopes, vec!["file_read"]);
+        assert_eq!(
+            missing[0].to_string(),
+            "connect figma first (Reads your design files)"
+        );
+    }
+
+    #[test]
+    fn test_scan_compatible_skips_other_platforms() {
+        let temp_dir = tempdir().unwrap();
+        create_test_lens(temp_dir.path(), "portable", "Portable");
+        create_test_lens_with_manifest(
+            temp_dir.path(),
+            "native",
+            &format!(
+                r#"
+[lens]
+id = "native"
+name = "Native"
+version = "1.0.0"
+platforms = ["{}"]
+"#,
+                current_platform()
+            ),
+        );
+        create_test_lens_with_manifest(
+            temp_dir.path(),
+            "foreign",
+            r#"
+[lens]
+id = "foreign"
+name = "Foreign"
+version = "1.0.0"
+platforms = ["plan9-mips"]
+"#,
+        );
+
+        let discovery = LensDiscovery::new(temp_dir.path());
+
+        let all = discovery.scan().unwrap();
+        assert_eq!(all.len(), 3);
+        let foreign = all.iter().find(|l| l.id() == "foreign").unwrap();
+        assert!(!foreign.is_platform_compatible());
+
+        let compatible = discovery.scan_compatible().unwrap();
+        let ids: Vec<&str> = compatible.iter().map(|l| l.id()).collect();
+        assert_eq!(ids, vec!["native", "portable"]);
+    }
+
+    #[test]
+    fn test_declared_entry_points() {
+        let temp_dir = tempdir().unwrap();
+        create_test_lens_with_manifest(
+            temp_dir.path(),
+            "native",
+            r#"
+[lens]
+id = "native"
+name = "Native"
+version = "1.0.0"
+entry = { type = "dylib", path = "build/custom_name" }
+"#,
+        );
+        let build_dir = temp_dir.path().join("native").join("build");
+        fs::create_dir_all(&build_dir).unwrap();
+        let library = build_dir.join(format!("custom_name.{}", std::env::consts::DLL_EXTENSION));
+        fs::write(&library, b"").unwrap();
+
+        create_test_lens_with_manifest(
+            temp_dir.path(),
+            "script",
+            r#"
+[lens]
+id = "script"
+name = "Script"
+version = "1.0.0"
+entry = { type = "subprocess", path = "run.sh" }
+"#,
+        );
+        fs::write(temp_dir.path().join("script").join("run.sh"), b"").unwrap();
+
+        let discovery = LensDiscovery::new(temp_dir.path());
+
+        let native = discovery.get_lens("native").unwrap().unwrap();
+        assert_eq!(native.entry_type(), LensEntryType::Dylib);
+        assert_eq!(native.library_path, Some(library));
+        assert!(native.entry_path.is_none());
+
+        let script = discovery.get_lens("script").unwrap().unwrap();
+        assert_eq!(script.entry_type(), LensEntryType::Subprocess);
+        assert!(script.library_path.is_none());
+        assert_eq!(
+            script.entry_path,
+            Some(temp_dir.path().join("script").join("run.sh"))
+        );
+    }
+
+    #[test]
+    fn test_search_lenses() {
+        let temp_dir = tempdir().unwrap();
+        create_test_lens_with_manifest(
+            temp_dir.path(),
+            "figma",
+            r#"
+[lens]
+id = "figma"
+name = "Figma"
+version = "1.0.0"
+categories = ["design"]
+tags = ["ui"]
+"#,
+        );
+        create_test_lens_with_manifest(
+            temp_dir.path(),
+            "palette",
+            r#"
+[lens]
+id = "palette"
+name = "Palette"
+version = "1.0.0"
+description = "Generate color palettes for design systems"
+"#,
+        );
+        create_test_lens(temp_dir.path(), "vibe", "Vibe");
+
+        let discovery = LensDiscovery::new(temp_dir.path());
+
+        let ids: Vec<String> = discovery
+            .search("design")
+            .unwrap()
+            .iter()
+            .map(|l| l.id().to_string())
+            .collect();
+        assert_eq!(ids, vec!["figma", "palette"]);
+
+        assert!(discovery.search("spotify").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_branding_paths() {
+        let temp_dir = tempdir().unwrap();
+        create_test_lens_with_manifest(
+            temp_dir.path(),
+            "branded",
+            r##"
+[lens]
+id = "branded"
+name = "Branded"
+version = "1.0.0"
+
+[branding]
+icon = "assets/icon.png"
+icon_dark = "assets/missing-dark.png"
+accent_color = "#123456"
+"##,
+        );
+        let assets = temp_dir.path().join("branded").join("assets");
+        fs::create_dir_all(&assets).unwrap();
+        fs::write(assets.join("icon.png"), b"").unwrap();
+
+        let discovery = LensDiscovery::new(temp_dir.path());
+        let lens = discovery.get_lens("branded").unwrap().unwrap();
+
+        assert_eq!(lens.icon_path(false), Some(assets.join("icon.png")));
+        // Declared dark variant is missing on disk, so no path is exposed
+        assert_eq!(lens.icon_path(true), None);
+        assert_eq!(lens.accent_color(), Some("#123456"));
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn test_scan_signed() {
+        use ed25519_dalek::SigningKey;
+
+        let temp_dir = tempdir().unwrap();
+        let library = b"lens library bytes";
+        let hash = {
+            use sha2::{Digest, Sha256};
+            format!("sha256:{:x}", Sha256::digest(library))
+        };
+
+        let mut manifest = LensManifest::from_toml(&format!(
+            r#"
+[lens]
+id = "signed"
+name = "Signed"
+version = "1.0.0"
+manifest_version = 2
+entry = {{ type = "wasm", path = "lens.wasm" }}
+
+[security]
+library_hash = "{}"
+"#,
+            hash
+        ))
+        .unwrap();
+        let key = SigningKey::from_bytes(&[3u8; 32]);
+        let trusted: Vec<String> = vec![key
+            .verifying_key()
+            .as_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()];
+        manifest.sign(&key).unwrap();
+        create_test_lens_with_manifest(temp_dir.path(), "signed", &manifest.to_toml().unwrap());
+        fs::write(temp_dir.path().join("signed").join("lens.wasm"), library).unwrap();
+
+        create_test_lens(temp_dir.path(), "unsigned", "Unsigned");
+
+        let discovery = LensDiscovery::new(temp_dir.path());
+        let signed = discovery.scan_signed(&trusted).unwrap();
+        assert_eq!(signed.len(), 1);
+        assert_eq!(signed[0].id(), "signed");
+        // No trusted keys means no lens is trusted
+        assert!(discovery.scan_signed(&[]).unwrap().is_empty());
+
+        // Swapping the binary breaks verification even though the manifest is intact
+        fs::write(
+            temp_dir.path().join("signed").join("lens.wasm"),
+            b"tampered",
+        )
+        .unwrap();
+        assert!(discovery.scan_signed(&trusted).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_runnable_hooks() {
+        let temp_dir = tempdir().unwrap();
+        for (dir, sandbox) in [("trusted", "full"), ("restricted", "restricted")] {
+            create_test_lens_with_manifest(
+                temp_dir.path(),
+                dir,
+                &format!(
+                    r#"
+[lens]
+id = "{}"
+name = "Hooked"
+version = "1.0.0"
+
+[security]
+sandbox = "{}"
+
+[hooks]
+post_install = "scripts/setup.sh"
+pre_uninstall = "scripts/missing.sh"
+"#,
+                    dir, sandbox
+                ),
+            );
+            let scripts = temp_dir.path().join(dir).join("scripts");
+            fs::create_dir_all(&scripts).unwrap();
+            fs::write(scripts.join("setup.sh"), "#!/bin/sh\n").unwrap();
+        }
+
+        let discovery = LensDiscovery::new(temp_dir.path());
+        let trusted = discovery.get_lens("trusted").unwrap().unwrap();
+        assert_eq!(trusted.hooks().len(), 2);
+        assert_eq!(
+            trusted.runnable_hook(HookEvent::PostInstall).unwrap(),
+            Some(trusted.path.join("scripts/setup.sh"))
+        );
+        assert!(trusted.runnable_hook(HookEvent::PreUninstall).is_err());
+        assert_eq!(trusted.runnable_hook(HookEvent::PreEnable).unwrap(), None);
+
+        let restricted = discovery.get_lens("restricted").unwrap().unwrap();
+        let err = restricted
+            .runnable_hook(HookEvent::PostInstall)
+            .unwrap_err();
+        assert!(err.to_string().contains("requires sandbox"));
+    }
+
+    #[test]
+    fn test_missing_env() {
+        let temp_dir = tempdir().unwrap();
+        create_test_lens_with_manifest(
+            temp_dir.path(),
+            "env-lens",
+            r#"
+[lens]
+id = "env-lens"
+name = "Env Lens"
+version = "1.0.0"
+
+[env]
+required = ["PATH", "LENS_TEST_SURELY_UNSET_VAR"]
+optional = ["LENS_TEST_OPTIONAL_VAR"]
+"#,
+        );
+
+        let discovery = LensDiscovery::new(temp_dir.path());
+        let lens = discovery.get_lens("env-lens").unwrap().unwrap();
+        assert!(lens.examples().is_empty());
+        let missing: Vec<&str> = lens.missing_env().iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(missing, vec!["LENS_TEST_SURELY_UNSET_VAR"]);
+    }
+
+    #[test]
+    fn test_validate_consistency() {
+        let temp_dir = tempdir().unwrap();
+        create_test_lens_with_manifest(
+            temp_dir.path(),
+            "drift",
+            r#"
+[lens]
+id = "drift"
+name = "Drift"
+version = "1.0.0"
+
+[[message_types]]
+key = "result"
+component = "components/Result.tsx"
+
+[[message_types]]
+key = "approval"
+component = "components/Approval.tsx"
+
+[[message_types]]
+key = "legacy"
+component = "components/Legacy.tsx"
+"#,
+        );
+        fs::write(
+            temp_dir.path().join("drift").join(OUTPUT_SPEC_FILENAME),
+            r#"
+lens_id: drift
+outputs:
+  - key: result
+    title: Result
+    render_blocks:
+      - type: json_view
+    examples:
+      - {}
+  - key: approval
+    title: Approval
+    render_blocks:
+      - type: checkpoint_gate
+    interactivity: confirm
+    examples:
+      - {}
+  - key: extra
+    title: Extra
+    render_blocks:
+      - type: json_view
+    examples:
+      - {}
+"#,
+        )
+        .unwrap();
+
+        let discovery = LensDiscovery::new(temp_dir.path());
+        let lens = discovery.get_lens("drift").unwrap().unwrap();
+        let issues = lens.validate_consistency();
+        assert_eq!(
+            issues,
+            vec![
+                ConsistencyIssue::InteractivityMismatch {
+                    key: "approval".to_string(),
+                    interactive: false,
+                    interactivity: InteractivityMode::Confirm,
+                },
+                ConsistencyIssue::MissingOutput {
+                    key: "legacy".to_string(),
+                },
+                ConsistencyIssue::MissingMessageType {
+                    key: "extra".to_string(),
+                },
+            ]
+        );
+        assert!(issues[1].to_string().contains("no output definition"));
+
+        create_test_lens(temp_dir.path(), "plain", "Plain");
+        let plain = discovery.get_lens("plain").unwrap().unwrap();
+        assert!(plain.validate_consistency().is_empty());
+    }
+
     #[test]
     fn test_ensure_exists() {
         let temp_dir = tempdir().unwrap();
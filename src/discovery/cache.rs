@@ -0,0 +1,153 @@
+//! # Discovery Cache
+//!
+//! [`LensDiscovery::with_cache`] keeps parsed lenses in memory and reuses
+//! them while the files they were loaded from are unchanged, so hosts can
+//! scan frequently without re-reading every manifest and output spec.
+//!
+//! A cached lens is reloaded when the modification time of its directory,
+//! manifest, output spec, binary, or [`DISABLED_MARKER`] changes. Files
+//! pulled in through output spec `extends`/`include` are not tracked; call
+//! [`LensDiscovery::invalidate`] after editing them.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::{DiscoveredLens, LensDiscovery, DISABLED_MARKER};
+use crate::error::Result;
+
+/// Modification times of the files a lens is loaded from
+pub(super) type Fingerprint = Vec<Option<SystemTime>>;
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Fingerprint of the files `lens` was loaded from
+pub(super) fn fingerprint(lens: &DiscoveredLens) -> Fingerprint {
+    let marker = lens.path.join(DISABLED_MARKER);
+    [
+        Some(&lens.manifest_path),
+        lens.output_spec_path.as_ref(),
+        lens.library_path.as_ref(),
+        lens.entry_path.as_ref(),
+        Some(&marker),
+    ]
+    .into_iter()
+    .map(|path| path.and_then(|p| modified(p)))
+    .collect()
+}
+
+/// Cached lenses by directory, shared between clones of a `LensDiscovery`
+#[derive(Debug, Default)]
+pub(super) struct LensCache {
+    lenses: Mutex<HashMap<PathBuf, CachedLens>>,
+}
+
+#[derive(Debug)]
+struct CachedLens {
+    /// Directory mtime, which changes when files are added or removed
+    dir_modified: Option<SystemTime>,
+    fingerprint: Fingerprint,
+    lens: DiscoveredLens,
+}
+
+impl LensDiscovery {
+    /// Cache parsed lenses between scans, reloading only changed ones.
+    ///
+    /// Clones share the cache.
+    pub fn with_cache(mut self) -> Self {
+        self.cache = Some(Arc::new(LensCache::default()));
+        self
+    }
+
+    /// Drop every cached lens so the next scan reloads them from disk
+    pub fn invalidate(&self) {
+        if let Some(cache) = &self.cache {
+            cache.lenses.lock().unwrap().clear();
+        }
+    }
+
+    /// [`load_lens`](Self::load_lens), served from the cache when enabled
+    pub(super) fn load_lens_cached(&self, lens_dir: &Path) -> Result<DiscoveredLens> {
+        let Some(cache) = &self.cache else {
+            return self.load_lens(lens_dir);
+        };
+
+        let dir_modified = modified(lens_dir);
+        if let Some(cached) = cache.lenses.lock().unwrap().get(lens_dir) {
+            if cached.dir_modified == dir_modified
+                && cached.fingerprint == fingerprint(&cached.lens)
+            {
+                return Ok(cached.lens.clone());
+            }
+        }
+
+        let lens = self.load_lens(lens_dir)?;
+        cache.lenses.lock().unwrap().insert(
+            lens_dir.to_path_buf(),
+            CachedLens {
+                dir_modified,
+                fingerprint: fingerprint(&lens),
+                lens: lens.clone(),
+            },
+        );
+        Ok(lens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn write_manifest(dir: &Path, name: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(
+            dir.join("lens.toml"),
+            format!(
+                "[lens]\nid = \"cached\"\nname = \"{}\"\nversion = \"1.0.0\"\n",
+                name
+            ),
+        )
+        .unwrap();
+    }
+
+    /// Set a file's mtime explicitly so the test does not depend on timer resolution
+    fn touch(path: &Path, secs: u64) {
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_cached_scan_reloads_changed_lenses() {
+        let temp_dir = tempdir().unwrap();
+        let lens_dir = temp_dir.path().join("cached");
+        write_manifest(&lens_dir, "First");
+        touch(&lens_dir.join("lens.toml"), 1_000);
+        let discovery = LensDiscovery::new(temp_dir.path()).with_cache();
+
+        assert_eq!(discovery.scan().unwrap()[0].name(), "First");
+
+        // Same mtime: the cached lens is served without re-reading
+        write_manifest(&lens_dir, "Second");
+        touch(&lens_dir.join("lens.toml"), 1_000);
+        assert_eq!(discovery.scan().unwrap()[0].name(), "First");
+        assert_eq!(discovery.clone().scan().unwrap()[0].name(), "First");
+
+        touch(&lens_dir.join("lens.toml"), 2_000);
+        assert_eq!(discovery.scan().unwrap()[0].name(), "Second");
+
+        write_manifest(&lens_dir, "Third");
+        touch(&lens_dir.join("lens.toml"), 2_000);
+        discovery.invalidate();
+        assert_eq!(
+            discovery.get_lens("cached").unwrap().unwrap().name(),
+            "Third"
+        );
+    }
+}
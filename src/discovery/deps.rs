@@ -0,0 +1,291 @@
+//! # Dependency Resolution
+//!
+//! Orders discovered lenses so every lens comes after the lenses it declares
+//! in `[[dependencies]]` / `[[dependencies_v2]]`, and reports dependencies
+//! that are missing, have the wrong version, or form a cycle.
+//!
+//! Missing optional dependencies are not an error; when present they are
+//! ordered and version-checked like required ones.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use semver::{Version, VersionReq};
+
+use super::{DiscoveredLens, LensDiscovery};
+use crate::error::{LensError, Result};
+
+/// A problem preventing dependency resolution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DependencyIssue {
+    /// A required dependency is not installed
+    Missing { lens: String, dependency: String },
+    /// An installed dependency does not satisfy the version requirement
+    VersionMismatch {
+        lens: String,
+        dependency: String,
+        required: String,
+        found: String,
+    },
+    /// Lenses that depend on each other; the first id is repeated at the end
+    Cycle { path: Vec<String> },
+}
+
+impl std::fmt::Display for DependencyIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing { lens, dependency } => write!(
+                f,
+                "lens '{}' depends on '{}', which is not installed",
+                lens, dependency
+            ),
+            Self::VersionMismatch {
+                lens,
+                dependency,
+                required,
+                found,
+            } => write!(
+                f,
+                "lens '{}' requires '{}' {}, but {} is installed",
+                lens, dependency, required, found
+            ),
+            Self::Cycle { path } => write!(f, "dependency cycle: {}", path.join(" -> ")),
+        }
+    }
+}
+
+/// Every issue found while resolving dependencies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyError {
+    pub issues: Vec<DependencyIssue>,
+}
+
+impl std::fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unresolvable lens dependencies:")?;
+        for issue in &self.issues {
+            write!(f, "\n  - {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DependencyError {}
+
+impl From<DependencyError> for LensError {
+    fn from(error: DependencyError) -> Self {
+        LensError::InvalidInput(error.to_string())
+    }
+}
+
+/// Declared dependency: id, version requirement, optional
+fn declared_dependencies(lens: &DiscoveredLens) -> Vec<(&str, Option<&str>, bool)> {
+    let manifest = &lens.manifest;
+    manifest
+        .dependencies
+        .iter()
+        .map(|d| (d.id.as_str(), d.version.as_deref(), false))
+        .chain(
+            manifest
+                .dependencies_v2
+                .iter()
+                .map(|d| (d.id.as_str(), d.version.as_deref(), d.optional)),
+        )
+        .collect()
+}
+
+fn satisfies(version: &str, required: &str) -> bool {
+    match (Version::parse(version), VersionReq::parse(required)) {
+        (Ok(version), Ok(required)) => required.matches(&version),
+        _ => false,
+    }
+}
+
+/// Order `lenses` so dependencies come before their dependents.
+///
+/// Lenses without an ordering constraint between them are sorted by id, so
+/// the result is deterministic.
+pub fn resolve_load_order(
+    lenses: Vec<DiscoveredLens>,
+) -> std::result::Result<Vec<DiscoveredLens>, DependencyError> {
+    let mut by_id: BTreeMap<String, DiscoveredLens> = lenses
+        .into_iter()
+        .map(|lens| (lens.id().to_string(), lens))
+        .collect();
+
+    // id -> ids it depends on (installed ones only)
+    let mut edges: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut issues = Vec::new();
+    for (id, lens) in &by_id {
+        let deps = edges.entry(id.clone()).or_default();
+        for (dependency, required, optional) in declared_dependencies(lens) {
+            let Some(installed) = by_id.get(dependency) else {
+                if !optional {
+                    issues.push(DependencyIssue::Missing {
+                        lens: id.clone(),
+                        dependency: dependency.to_string(),
+                    });
+                }
+                continue;
+            };
+            if let Some(required) = required {
+                if !satisfies(installed.version(), required) {
+                    issues.push(DependencyIssue::VersionMismatch {
+                        lens: id.clone(),
+                        dependency: dependency.to_string(),
+                        required: required.to_string(),
+                        found: installed.version().to_string(),
+                    });
+                }
+            }
+            deps.insert(dependency.to_string());
+        }
+    }
+
+    // Kahn's algorithm, always taking the smallest ready id
+    let mut remaining = edges.clone();
+    let mut order = Vec::new();
+    while let Some(ready) = remaining
+        .iter()
+        .find(|(_, deps)| deps.is_empty())
+        .map(|(id, _)| id.clone())
+    {
+        remaining.remove(&ready);
+        for deps in remaining.values_mut() {
+            deps.remove(&ready);
+        }
+        order.push(ready);
+    }
+    if !remaining.is_empty() {
+        issues.push(DependencyIssue::Cycle {
+            path: find_cycle(&remaining),
+        });
+    }
+
+    if !issues.is_empty() {
+        return Err(DependencyError { issues });
+    }
+    Ok(order
+        .into_iter()
+        .filter_map(|id| by_id.remove(&id))
+        .collect())
+}
+
+/// Follow dependencies among `remaining` (all of which lie on or lead into a
+/// cycle) until an id repeats
+fn find_cycle(remaining: &BTreeMap<String, BTreeSet<String>>) -> Vec<String> {
+    let mut path: Vec<String> = Vec::new();
+    let mut current = remaining.keys().next().cloned();
+    while let Some(id) = current {
+        if let Some(start) = path.iter().position(|seen| *seen == id) {
+            let mut cycle = path.split_off(start);
+            cycle.push(id);
+            return cycle;
+        }
+        current = remaining[&id]
+            .iter()
+            .find(|dep| remaining.contains_key(*dep))
+            .cloned();
+        path.push(id);
+    }
+    path
+}
+
+impl LensDiscovery {
+    /// Scan and return lenses in dependency order (dependencies first).
+    ///
+    /// Fails with a report of every missing dependency, version mismatch,
+    /// and cycle.
+    pub fn scan_ordered(&self) -> Result<Vec<DiscoveredLens>> {
+        Ok(resolve_load_order(self.scan()?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::LensManifest;
+    use std::path::PathBuf;
+
+    fn lens(id: &str, version: &str, deps: &[(&str, &str)]) -> DiscoveredLens {
+        let mut toml = format!(
+            "[lens]\nid = \"{}\"\nname = \"{}\"\nversion = \"{}\"\n",
+            id, id, version
+        );
+        for (dep, req) in deps {
+            toml.push_str(&format!(
+                "\n[[dependencies]]\nid = \"{}\"\nversion = \"{}\"\n",
+                dep, req
+            ));
+        }
+        DiscoveredLens {
+            manifest: LensManifest::from_toml(&toml).unwrap(),
+            path: PathBuf::from(id),
+            manifest_path: PathBuf::from(id).join("lens.toml"),
+            output_spec_path: None,
+            output_spec: None,
+            library_path: None,
+            entry_path: None,
+            disabled: false,
+            hash_verified: None,
+            capabilities: None,
+        }
+    }
+
+    fn ids(lenses: &[DiscoveredLens]) -> Vec<&str> {
+        lenses.iter().map(|l| l.id()).collect()
+    }
+
+    #[test]
+    fn test_resolve_load_order() {
+        let ordered = resolve_load_order(vec![
+            lens("app", "1.0.0", &[("ui", "^1"), ("base", ">=0.2")]),
+            lens("ui", "1.4.0", &[("base", "*")]),
+            lens("base", "0.3.0", &[]),
+            lens("alone", "1.0.0", &[]),
+        ])
+        .unwrap();
+        assert_eq!(ids(&ordered), vec!["alone", "base", "ui", "app"]);
+    }
+
+    #[test]
+    fn test_resolve_reports_missing_and_mismatched() {
+        let err = resolve_load_order(vec![
+            lens("app", "1.0.0", &[("ui", "^2"), ("auth", "*")]),
+            lens("ui", "1.4.0", &[]),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err.issues,
+            vec![
+                DependencyIssue::VersionMismatch {
+                    lens: "app".into(),
+                    dependency: "ui".into(),
+                    required: "^2".into(),
+                    found: "1.4.0".into(),
+                },
+                DependencyIssue::Missing {
+                    lens: "app".into(),
+                    dependency: "auth".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_reports_cycles() {
+        let err = resolve_load_order(vec![
+            lens("a", "1.0.0", &[("b", "*")]),
+            lens("b", "1.0.0", &[("c", "*")]),
+            lens("c", "1.0.0", &[("b", "*")]),
+            lens("d", "1.0.0", &[]),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err.issues,
+            vec![DependencyIssue::Cycle {
+                path: vec!["b".into(), "c".into(), "b".into()]
+            }]
+        );
+        assert!(err.to_string().contains("dependency cycle: b -> c -> b"));
+    }
+}
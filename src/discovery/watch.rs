@@ -0,0 +1,240 @@
+//! # Discovery Watch Mode
+//!
+//! [`LensDiscovery::watch`] reports lenses being installed, updated, or
+//! removed while the host runs, using filesystem notifications instead of
+//! periodic full rescans. Only the lens directories touched by a notification
+//! are reloaded.
+//!
+//! ```rust,ignore
+//! let mut events = LensDiscovery::default_directory()?.watch()?;
+//! while let Some(event) = events.next().await {
+//!     match event {
+//!         DiscoveryEvent::Added(lens) | DiscoveryEvent::Updated(lens) => registry.insert(lens),
+//!         DiscoveryEvent::Removed { id, .. } => registry.remove(&id),
+//!     }
+//! }
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::Stream;
+
+use super::cache::{fingerprint, Fingerprint};
+use super::{DiscoveredLens, LensDiscovery, MANIFEST_FILENAME};
+use crate::error::{LensError, Result};
+
+/// A change to the set of installed lenses
+#[derive(Debug, Clone)]
+pub enum DiscoveryEvent {
+    /// A lens directory with a valid manifest appeared
+    Added(DiscoveredLens),
+    /// An installed lens's manifest, output spec, or library changed, or it
+    /// was enabled or disabled
+    Updated(DiscoveredLens),
+    /// A lens directory or its manifest was deleted
+    Removed { id: String, path: PathBuf },
+}
+
+/// Stream of [`DiscoveryEvent`]s; watching stops when it is dropped
+pub struct LensWatchStream {
+    _watcher: RecommendedWatcher,
+    events: UnboundedReceiverStream<DiscoveryEvent>,
+}
+
+impl Stream for LensWatchStream {
+    type Item = DiscoveryEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().events).poll_next(cx)
+    }
+}
+
+/// Lenses known to the watcher, keyed by lens directory
+struct Snapshot {
+    discovery: LensDiscovery,
+    lenses: HashMap<PathBuf, (String, Fingerprint)>,
+}
+
+impl Snapshot {
+    fn new(discovery: LensDiscovery) -> Result<Self> {
+        let lenses = discovery
+            .scan()?
+            .into_iter()
+            .map(|lens| {
+                let fingerprint = fingerprint(&lens);
+                (lens.path.clone(), (lens.id().to_string(), fingerprint))
+            })
+            .collect();
+        Ok(Self { discovery, lenses })
+    }
+
+    /// Lens directories affected by changes to `paths`
+    fn lens_dirs(&self, paths: &[PathBuf]) -> BTreeSet<PathBuf> {
+        let root = self.discovery.plugins_dir();
+        let mut dirs = BTreeSet::new();
+        for path in paths {
+            match path
+                .strip_prefix(root)
+                .ok()
+                .and_then(|p| p.components().next())
+            {
+                Some(first) => {
+                    dirs.insert(root.join(first));
+                }
+                // The root itself changed: recheck everything
+                None => {
+                    dirs.extend(self.lenses.keys().cloned());
+                    if let Ok(entries) = std::fs::read_dir(root) {
+                        dirs.extend(entries.flatten().map(|e| e.path()));
+                    }
+                }
+            }
+        }
+        dirs
+    }
+
+    /// Reload `dir` and describe how it changed, if at all
+    fn reload(&mut self, dir: &Path) -> Option<DiscoveryEvent> {
+        if !dir.join(MANIFEST_FILENAME).is_file() {
+            let (id, _) = self.lenses.remove(dir)?;
+            return Some(DiscoveryEvent::Removed {
+                id,
+                path: dir.to_path_buf(),
+            });
+        }
+
+        let lens = match self.discovery.load_lens(dir) {
+            Ok(lens) => lens,
+            Err(e) => {
+                // Usually a manifest caught mid-write; the next event retries
+                eprintln!("Warning: Failed to reload lens from {:?}: {}", dir, e);
+                return None;
+            }
+        };
+        let fingerprint = fingerprint(&lens);
+        let previous = self.lenses.insert(
+            dir.to_path_buf(),
+            (lens.id().to_string(), fingerprint.clone()),
+        );
+        match previous {
+            None => Some(DiscoveryEvent::Added(lens)),
+            Some((_, old)) if old != fingerprint => Some(DiscoveryEvent::Updated(lens)),
+            Some(_) => None,
+        }
+    }
+}
+
+impl LensDiscovery {
+    /// Watch the lenses directory for installed, updated, and removed lenses.
+    ///
+    /// Creates the directory if needed. Lenses present when watching starts
+    /// are not reported; call [`scan`](Self::scan) first for the initial set.
+    pub fn watch(&self) -> Result<LensWatchStream> {
+        self.ensure_exists()?;
+        let mut snapshot = Snapshot::new(self.clone())?;
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        eprintln!("Warning: Lens directory watch error: {}", e);
+                        return;
+                    }
+                };
+                // Reloading reads the lens files, which would otherwise
+                // trigger another reload
+                if event.kind.is_access() {
+                    return;
+                }
+                for dir in snapshot.lens_dirs(&event.paths) {
+                    if let Some(change) = snapshot.reload(&dir) {
+                        let _ = tx.send(change);
+                    }
+                }
+            })
+            .map_err(|e| watch_error(self.plugins_dir(), e))?;
+        watcher
+            .watch(self.plugins_dir(), RecursiveMode::Recursive)
+            .map_err(|e| watch_error(self.plugins_dir(), e))?;
+
+        Ok(LensWatchStream {
+            _watcher: watcher,
+            events: UnboundedReceiverStream::new(rx),
+        })
+    }
+}
+
+fn watch_error(dir: &Path, error: notify::Error) -> LensError {
+    LensError::Initialization(format!("Failed to watch {:?}: {}", dir, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::tempdir;
+    use tokio_stream::StreamExt;
+
+    fn manifest(version: &str) -> String {
+        format!(
+            "[lens]\nid = \"watched\"\nname = \"Watched\"\nversion = \"{}\"\n",
+            version
+        )
+    }
+
+    /// Wait for the first event accepted by `matches`, skipping others
+    async fn next_matching(
+        events: &mut LensWatchStream,
+        matches: impl Fn(&DiscoveryEvent) -> bool,
+    ) -> DiscoveryEvent {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let event = events.next().await.expect("watch stream ended");
+                if matches(&event) {
+                    return event;
+                }
+            }
+        })
+        .await
+        .expect("timed out waiting for discovery event")
+    }
+
+    #[tokio::test]
+    async fn test_watch_reports_lens_changes() {
+        let temp_dir = tempdir().unwrap();
+        let discovery = LensDiscovery::new(temp_dir.path().join("lenses"));
+        let mut events = discovery.watch().unwrap();
+        let lens_dir = discovery.plugins_dir().join("watched");
+
+        fs::create_dir_all(&lens_dir).unwrap();
+        fs::write(lens_dir.join(MANIFEST_FILENAME), manifest("1.0.0")).unwrap();
+        let added = next_matching(&mut events, |e| matches!(e, DiscoveryEvent::Added(_))).await;
+        assert!(matches!(added, DiscoveryEvent::Added(lens) if lens.id() == "watched"));
+
+        // Make sure the rewrite gets a different mtime
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        fs::write(lens_dir.join(MANIFEST_FILENAME), manifest("1.1.0")).unwrap();
+        next_matching(
+            &mut events,
+            |e| matches!(e, DiscoveryEvent::Updated(lens) if lens.version() == "1.1.0"),
+        )
+        .await;
+
+        fs::remove_dir_all(&lens_dir).unwrap();
+        let removed =
+            next_matching(&mut events, |e| matches!(e, DiscoveryEvent::Removed { .. })).await;
+        assert!(matches!(
+            removed,
+            DiscoveryEvent::Removed { id, path } if id == "watched" && path == lens_dir
+        ));
+    }
+}
@@ -18,6 +18,12 @@ pub enum LensError {
     #[error("Lens initialization failed: {0}")]
     Initialization(String),
 
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("Resource limit exceeded: {0}")]
+    ResourceLimitExceeded(String),
+
     #[error("Event stream error: {0}")]
     StreamError(String),
 
@@ -31,6 +37,26 @@ pub enum LensError {
     Other(String),
 }
 
+impl LensError {
+    /// Stable machine-readable name of the error kind, carried as the
+    /// `code` of [`LensEvent::Failed`](crate::LensEvent::Failed)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ExecutionFailed(_) => "ExecutionFailed",
+            Self::InvalidContext(_) => "InvalidContext",
+            Self::InvalidInput(_) => "InvalidInput",
+            Self::LensNotFound(_) => "LensNotFound",
+            Self::Initialization(_) => "Initialization",
+            Self::PermissionDenied(_) => "PermissionDenied",
+            Self::ResourceLimitExceeded(_) => "ResourceLimitExceeded",
+            Self::StreamError(_) => "StreamError",
+            Self::SerializationError(_) => "SerializationError",
+            Self::IoError(_) => "IoError",
+            Self::Other(_) => "Other",
+        }
+    }
+}
+
 /// Result type for lens operations
 pub type Result<T> = std::result::Result<T, LensError>;
 
@@ -65,6 +91,13 @@ mod tests {
         assert_eq!(error.to_string(), "Lens not found: figma");
     }
 
+    #[test]
+    fn test_resource_limit_exceeded_error() {
+        let error = LensError::ResourceLimitExceeded("ran for 30s".to_string());
+        assert_eq!(error.to_string(), "Resource limit exceeded: ran for 30s");
+        assert_eq!(error.code(), "ResourceLimitExceeded");
+    }
+
     #[test]
     fn test_stream_error() {
         let error = LensError::StreamError("channel closed".to_string());
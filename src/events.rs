@@ -1,11 +1,26 @@
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, SystemTime};
 
+use crate::context::Initiator;
+
+/// Current `LensEvent` wire schema version.
+///
+/// Hosts advertise the versions they understand via `HostInfo::supported_event_versions`.
+///
+/// - Version 1: `Started`, `Progress`, `Data`, `Checkpoint`, `Completed`, `Failed`
+/// - Version 2: adds the `Retrying` and `CacheHit` events, `Started.initiator`,
+///   and `Failed.code`
+///
+/// Events are rewritten for older hosts with [`LensEvent::for_schema_version`].
+pub const EVENT_SCHEMA_VERSION: u32 = 2;
+
 /// Events emitted during lens execution.
 ///
-/// The framework provides 5 core event types. Lenses emit custom message types
-/// via the `Data` variant, where `key` identifies the message type and `value`
-/// contains the payload. Framework-owned renderers map `key` via `lens.output.yaml`.
+/// Lenses report their lifecycle through `Started`, `Progress`, `Checkpoint`,
+/// `Completed`, and `Failed`; hosts running lenses through a `LensExecutor` also
+/// see `Retrying` and `CacheHit`. Lenses emit custom message types via the
+/// `Data` variant, where `key` identifies the message type and `value` contains
+/// the payload. Framework-owned renderers map `key` via `lens.output.yaml`.
 ///
 /// # Example
 ///
@@ -31,6 +46,10 @@ pub enum LensEvent {
     Started {
         lens: String,
         task: String,
+        /// Who started the run (schema version 2; absent in events from older
+        /// lenses)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        initiator: Option<Initiator>,
         #[serde(with = "system_time_serde")]
         timestamp: SystemTime,
     },
@@ -74,6 +93,35 @@ pub enum LensEvent {
         lens: String,
         error: String,
         recoverable: bool,
+        /// Machine-readable failure code, e.g. `ResourceLimitExceeded` (see
+        /// [`LensError::code`](crate::LensError::code); schema version 2)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        code: Option<String>,
+        #[serde(with = "system_time_serde")]
+        timestamp: SystemTime,
+    },
+
+    /// A recoverable failure is being retried; `attempt` is the attempt
+    /// about to start, after `delay` (schema version 2)
+    Retrying {
+        lens: String,
+        attempt: u32,
+        max_attempts: u32,
+        #[serde(with = "duration_serde")]
+        delay: Duration,
+        /// The failure that caused the retry
+        error: String,
+        #[serde(with = "system_time_serde")]
+        timestamp: SystemTime,
+    },
+
+    /// The run was answered from a cached result instead of executing
+    /// (schema version 2)
+    CacheHit {
+        lens: String,
+        /// How long ago the cached result was produced
+        #[serde(with = "duration_serde")]
+        age: Duration,
         #[serde(with = "system_time_serde")]
         timestamp: SystemTime,
     },
@@ -102,6 +150,23 @@ impl LensEvent {
         Self::Started {
             lens: lens.into(),
             task: task.into(),
+            initiator: None,
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    /// Create a Started event recording who started the run
+    ///
+    /// Lenses typically pass `ctx.initiator.clone()`.
+    pub fn started_by(
+        lens: impl Into<String>,
+        task: impl Into<String>,
+        initiator: Initiator,
+    ) -> Self {
+        Self::Started {
+            lens: lens.into(),
+            task: task.into(),
+            initiator: Some(initiator),
             timestamp: SystemTime::now(),
         }
     }
@@ -160,6 +225,45 @@ impl LensEvent {
             lens: lens.into(),
             error: error.into(),
             recoverable,
+            code: None,
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    /// Create a Failed event describing `error`, tagged with its code
+    pub fn failed_with_error(lens: impl Into<String>, error: &crate::LensError) -> Self {
+        Self::Failed {
+            lens: lens.into(),
+            error: error.to_string(),
+            recoverable: false,
+            code: Some(error.code().to_string()),
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    /// Create a Retrying event announcing attempt `attempt` of `max_attempts`
+    pub fn retrying(
+        lens: impl Into<String>,
+        attempt: u32,
+        max_attempts: u32,
+        delay: Duration,
+        error: impl Into<String>,
+    ) -> Self {
+        Self::Retrying {
+            lens: lens.into(),
+            attempt,
+            max_attempts,
+            delay,
+            error: error.into(),
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    /// Create a CacheHit event for a result produced `age` ago
+    pub fn cache_hit(lens: impl Into<String>, age: Duration) -> Self {
+        Self::CacheHit {
+            lens: lens.into(),
+            age,
             timestamp: SystemTime::now(),
         }
     }
@@ -194,6 +298,8 @@ impl LensEvent {
             Self::Data { lens, .. } => lens,
             Self::Completed { lens, .. } => lens,
             Self::Failed { lens, .. } => lens,
+            Self::Retrying { lens, .. } => lens,
+            Self::CacheHit { lens, .. } => lens,
             Self::Checkpoint { lens, .. } => lens,
         }
     }
@@ -206,10 +312,86 @@ impl LensEvent {
             Self::Data { timestamp, .. } => *timestamp,
             Self::Completed { timestamp, .. } => *timestamp,
             Self::Failed { timestamp, .. } => *timestamp,
+            Self::Retrying { timestamp, .. } => *timestamp,
+            Self::CacheHit { timestamp, .. } => *timestamp,
             Self::Checkpoint { timestamp, .. } => *timestamp,
         }
     }
 
+    /// Get the run initiator recorded on a Started event
+    pub fn initiator(&self) -> Option<&Initiator> {
+        match self {
+            Self::Started { initiator, .. } => initiator.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Oldest schema version that has this event type
+    pub fn schema_version(&self) -> u32 {
+        match self {
+            Self::Retrying { .. } | Self::CacheHit { .. } => 2,
+            _ => 1,
+        }
+    }
+
+    /// Rewrite this event for a host that speaks schema `version`.
+    ///
+    /// Version 1 hosts get `Retrying` and `CacheHit` as `Progress` events,
+    /// and `Started` and `Failed` without the `initiator` and `code` fields.
+    pub fn for_schema_version(self, version: u32) -> Self {
+        if version >= 2 {
+            return self;
+        }
+        match self {
+            Self::Started {
+                lens,
+                task,
+                timestamp,
+                ..
+            } => Self::Started {
+                lens,
+                task,
+                initiator: None,
+                timestamp,
+            },
+            Self::Failed {
+                lens,
+                error,
+                recoverable,
+                timestamp,
+                ..
+            } => Self::Failed {
+                lens,
+                error,
+                recoverable,
+                code: None,
+                timestamp,
+            },
+            Self::Retrying {
+                lens,
+                attempt,
+                max_attempts,
+                error,
+                timestamp,
+                ..
+            } => Self::Progress {
+                lens,
+                message: format!("Retrying (attempt {attempt} of {max_attempts}): {error}"),
+                percent: None,
+                timestamp,
+            },
+            Self::CacheHit {
+                lens, timestamp, ..
+            } => Self::Progress {
+                lens,
+                message: "Answered from a cached result".to_string(),
+                percent: None,
+                timestamp,
+            },
+            event => event,
+        }
+    }
+
     /// Get the event type as a string (for testing assertions)
     pub fn event_type(&self) -> &'static str {
         match self {
@@ -218,6 +400,8 @@ impl LensEvent {
             Self::Data { .. } => "Data",
             Self::Completed { .. } => "Completed",
             Self::Failed { .. } => "Failed",
+            Self::Retrying { .. } => "Retrying",
+            Self::CacheHit { .. } => "CacheHit",
             Self::Checkpoint { .. } => "Checkpoint",
         }
     }
@@ -454,6 +638,30 @@ mod tests {
         assert!(serialized.contains("\"task\":\"decompose\""));
     }
 
+    #[test]
+    fn test_started_by_records_initiator() {
+        let initiator = Initiator::agent_tool_call(Some("claude".to_string()), "decompose");
+        let event = LensEvent::started_by("figma", "decompose", initiator.clone());
+
+        assert_eq!(event.initiator(), Some(&initiator));
+
+        let serialized = serde_json::to_value(&event).unwrap();
+        assert_eq!(serialized["initiator"]["kind"], "agent");
+        assert_eq!(serialized["initiator"]["tool"], "decompose");
+
+        let roundtrip: LensEvent = serde_json::from_value(serialized).unwrap();
+        assert_eq!(roundtrip.initiator(), Some(&initiator));
+    }
+
+    #[test]
+    fn test_started_without_initiator_omits_field() {
+        let event = LensEvent::started("figma", "decompose");
+        let serialized = serde_json::to_string(&event).unwrap();
+
+        assert!(!serialized.contains("initiator"));
+        assert!(event.initiator().is_none());
+    }
+
     #[test]
     fn test_progress_event_serialization() {
         let event = LensEvent::progress_with_percent("test", "Working", 50.0);
@@ -489,6 +697,49 @@ mod tests {
 
         assert!(serialized.contains("\"type\":\"failed\""));
         assert!(serialized.contains("\"recoverable\":true"));
+        assert!(!serialized.contains("\"code\""));
+    }
+
+    #[test]
+    fn test_failed_with_error_carries_code() {
+        let error = crate::LensError::ResourceLimitExceeded("ran for 30s".to_string());
+        let event = LensEvent::failed_with_error("figma", &error);
+        let serialized = serde_json::to_string(&event).unwrap();
+        assert!(serialized.contains("\"code\":\"ResourceLimitExceeded\""));
+
+        match serde_json::from_str(&serialized).unwrap() {
+            LensEvent::Failed {
+                error,
+                code,
+                recoverable,
+                ..
+            } => {
+                assert_eq!(error, "Resource limit exceeded: ran for 30s");
+                assert_eq!(code.as_deref(), Some("ResourceLimitExceeded"));
+                assert!(!recoverable);
+            }
+            _ => panic!("Expected Failed event"),
+        }
+    }
+
+    #[test]
+    fn test_retrying_event_serialization() {
+        let event = LensEvent::retrying("figma", 2, 3, Duration::from_millis(250), "timed out");
+        assert_eq!(event.event_type(), "Retrying");
+        let serialized = serde_json::to_string(&event).unwrap();
+        assert!(serialized.contains("\"type\":\"retrying\""));
+        assert!(serialized.contains("\"delay\":250"));
+
+        let parsed: LensEvent = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(parsed.lens(), "figma");
+        assert!(matches!(
+            parsed,
+            LensEvent::Retrying {
+                attempt: 2,
+                max_attempts: 3,
+                ..
+            }
+        ));
     }
 
     #[test]
@@ -551,6 +802,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_events_rewritten_for_schema_version_1() {
+        let started = LensEvent::started_by("figma", "decompose", Initiator::User);
+        assert!(started.clone().for_schema_version(2).initiator().is_some());
+        let value = serde_json::to_value(started.for_schema_version(1)).unwrap();
+        assert!(value.get("initiator").is_none());
+
+        let error = crate::LensError::ResourceLimitExceeded("timeout".into());
+        let failed = LensEvent::failed_with_error("figma", &error).for_schema_version(1);
+        assert!(matches!(failed, LensEvent::Failed { code: None, .. }));
+
+        let retrying = LensEvent::retrying("figma", 2, 3, Duration::from_secs(1), "timeout");
+        assert_eq!(retrying.schema_version(), 2);
+        match retrying.for_schema_version(1) {
+            LensEvent::Progress { message, .. } => {
+                assert_eq!(message, "Retrying (attempt 2 of 3): timeout");
+            }
+            other => panic!("Expected Progress event, got {:?}", other),
+        }
+        let hit = LensEvent::cache_hit("figma", Duration::from_secs(5));
+        assert_eq!(hit.for_schema_version(1).event_type(), "Progress");
+
+        let data = LensEvent::data("figma", "frame", json!({}));
+        assert_eq!(data.schema_version(), 1);
+        assert_eq!(data.for_schema_version(1).event_type(), "Data");
+    }
+
     #[test]
     fn test_event_clone() {
         let event = LensEvent::progress("test", "message");
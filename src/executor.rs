@@ -0,0 +1,1145 @@
+//! # Execution Engine
+//!
+//! [`LensExecutor`] runs lenses on behalf of a host with bounded
+//! concurrency: at most `max_concurrent` runs overall and, optionally, a
+//! smaller number per lens. Submissions beyond either limit wait in FIFO
+//! order instead of piling up as unbounded tasks.
+//!
+//! Each submission returns an [`ExecutionHandle`], a future resolving to
+//! the run's result that also carries the run's event stream:
+//!
+//! ```rust,ignore
+//! let executor = LensExecutor::new(8).with_per_lens_limit(2);
+//!
+//! let mut handle = executor.submit(registry.get("figma")?, ctx);
+//! let mut events = handle.take_events().unwrap();
+//! tokio::spawn(async move {
+//!     while let Some(event) = events.next().await {
+//!         ui.render(event);
+//!     }
+//! });
+//! let result = handle.await?;
+//! ```
+//!
+//! Plain lenses get `Started` and `Completed`/`Failed` events from the
+//! executor; a lens submitted with
+//! [`submit_streaming`](LensExecutor::submit_streaming) streams its own.
+//! Either way a run that has to wait for a slot first reports a `Progress`
+//! event saying so. Events are rewritten for the newest schema version
+//! `ctx.host` understands (see [`HostInfo::adapt_event`]).
+//!
+//! With [`with_retry_policy`](LensExecutor::with_retry_policy), runs failing
+//! with an error the [`RetryPolicy`] retries, or streaming a `Failed` event
+//! marked `recoverable`, are re-run after a `Retrying` event.
+//!
+//! Lenses declaring `[cache]` in their manifest (see
+//! [`with_manifest`](LensExecutor::with_manifest)) are run once per distinct
+//! input, config, and run scope within the TTL; repeated runs answer from
+//! the cached result with a single `CacheHit` event. The host names the
+//! scope with [`LensContext::with_run_scope`], covering the user, project,
+//! and accounts a run acts for; runs without a scope are never cached.
+//!
+//! With [`with_coalescing`](LensExecutor::with_coalescing), identical
+//! submissions (same lens, version, input, config, and run scope) made
+//! while a run is in flight join that run rather than starting another:
+//! every caller gets the same result and the same events, including those
+//! emitted before it joined. The shared run is cancelled only once every
+//! caller has cancelled its `ctx.cancellation`.
+//!
+//! [`execute_all`](LensExecutor::execute_all) runs a batch of independent
+//! lenses side by side, for example every lens @mentioned in one message,
+//! and merges their events into one stream tagged by run id:
+//!
+//! ```rust,ignore
+//! let (results, mut events) = executor.execute_all([
+//!     RunRequest::new("figma", registry.get("figma")?, ctx.clone()),
+//!     RunRequest::new("notes", registry.get("notes")?, ctx),
+//! ]);
+//! tokio::spawn(async move {
+//!     while let Some(RunEvent { run_id, event }) = events.next().await {
+//!         ui.render(&run_id, event);
+//!     }
+//! });
+//! for outcome in results.await {
+//!     // a failed run leaves the others' results intact
+//! }
+//! ```
+//!
+//! [`shutdown`](LensExecutor::shutdown) stops the executor when the host
+//! exits: it refuses new work, cancels each running lens's
+//! `ctx.cancellation`, waits out a grace period, then drops whatever is
+//! still running. Every unfinished run ends with a `Failed` event, so no
+//! caller is left waiting.
+
+mod cache;
+mod coalesce;
+mod fan_out;
+mod shutdown;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use tokio::sync::{mpsc, oneshot, Notify, OwnedSemaphorePermit, Semaphore};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::StreamExt;
+
+use self::cache::ResultCache;
+use self::coalesce::{join_cancellation, SharedRun};
+use self::shutdown::{shut_down, ActiveGuard, ActiveRun, CountGuard};
+use crate::cancel::CancellationToken;
+use crate::context::RetryPolicy;
+use crate::error::{LensError, Result};
+use crate::lens::execute_catching_panics;
+use crate::manifest::{CacheConfig, LensManifest};
+use crate::streaming::{LensEventStream, StreamingLens};
+use crate::{HostInfo, Lens, LensContext, LensEvent, LensResult};
+
+pub use self::fan_out::{RunEvent, RunEventStream, RunOutcome, RunRequest};
+
+/// Runs lenses under global and per-lens concurrency limits.
+///
+/// Cheap to clone; clones share the same queue, slots, and cache.
+/// Configuring a clone with a `with_*` builder changes that clone's
+/// settings only. Submitting spawns onto the current Tokio runtime.
+#[derive(Debug, Clone)]
+pub struct LensExecutor {
+    inner: Arc<Inner>,
+    /// Copied on write by the builders
+    config: Arc<Config>,
+}
+
+/// Settings the `with_*` builders change
+#[derive(Debug, Clone)]
+struct Config {
+    /// Limit for lenses without an entry in `lens_limits`
+    per_lens_limit: Option<usize>,
+    lens_limits: HashMap<String, usize>,
+    retry: RetryPolicy,
+    /// Result TTLs of the lenses that opted into caching
+    cache_ttls: HashMap<String, Duration>,
+    /// Whether identical submissions share a run
+    coalesce: bool,
+}
+
+#[derive(Debug)]
+struct Inner {
+    global: Arc<Semaphore>,
+    max_concurrent: usize,
+    /// Per-lens slots by lens and limit, created on first submission
+    per_lens: Mutex<HashMap<(String, usize), Arc<Semaphore>>>,
+    cache: ResultCache,
+    /// Runs in flight by [`run_key`], for coalescing
+    in_flight: Mutex<HashMap<String, Arc<Mutex<SharedRun>>>>,
+    running: AtomicUsize,
+    queued: AtomicUsize,
+    /// Cancelled by [`LensExecutor::shutdown`]
+    closed: CancellationToken,
+    /// Spawned runs that have not finished, by a per-executor id
+    active: Mutex<HashMap<u64, ActiveRun>>,
+    next_run: AtomicU64,
+    /// Notified when the last active run ends
+    idle: Notify,
+}
+
+/// Outcome of one attempt at a run
+struct Attempt {
+    outcome: Result<LensResult>,
+    /// Whether the failure is worth another attempt
+    retryable: bool,
+}
+
+impl LensExecutor {
+    /// Executor running at most `max_concurrent` lenses at once
+    pub fn new(max_concurrent: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        Self {
+            inner: Arc::new(Inner {
+                global: Arc::new(Semaphore::new(max_concurrent)),
+                max_concurrent,
+                per_lens: Mutex::new(HashMap::new()),
+                cache: ResultCache::default(),
+                in_flight: Mutex::new(HashMap::new()),
+                running: AtomicUsize::new(0),
+                queued: AtomicUsize::new(0),
+                closed: CancellationToken::new(),
+                active: Mutex::new(HashMap::new()),
+                next_run: AtomicU64::new(0),
+                idle: Notify::new(),
+            }),
+            config: Arc::new(Config {
+                per_lens_limit: None,
+                lens_limits: HashMap::new(),
+                retry: RetryPolicy::new().with_max_attempts(1),
+                cache_ttls: HashMap::new(),
+                coalesce: false,
+            }),
+        }
+    }
+
+    /// Run at most `limit` instances of any one lens at once (builder pattern)
+    pub fn with_per_lens_limit(mut self, limit: usize) -> Self {
+        self.config_mut().per_lens_limit = Some(limit.max(1));
+        self
+    }
+
+    /// Run at most `limit` instances of lens `lens_id` at once, overriding
+    /// the per-lens limit (builder pattern)
+    pub fn with_lens_limit(mut self, lens_id: impl Into<String>, limit: usize) -> Self {
+        self.config_mut()
+            .lens_limits
+            .insert(lens_id.into(), limit.max(1));
+        self
+    }
+
+    /// Re-run lenses that fail with a retryable error (builder pattern).
+    ///
+    /// By default each run gets a single attempt. A run keeps its slot
+    /// while backing off between attempts.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.config_mut().retry = policy;
+        self
+    }
+
+    /// Share one run between identical submissions (same lens, version,
+    /// input, config, and run scope) made while it is in flight; off by
+    /// default (builder pattern)
+    pub fn with_coalescing(mut self, coalesce: bool) -> Self {
+        self.config_mut().coalesce = coalesce;
+        self
+    }
+
+    /// Cache successful results of lens `lens_id` as `cache` declares
+    /// (builder pattern)
+    pub fn with_cache(mut self, lens_id: impl Into<String>, cache: &CacheConfig) -> Self {
+        self.config_mut()
+            .cache_ttls
+            .insert(lens_id.into(), cache.ttl());
+        self
+    }
+
+    /// Apply the `[limits] max_concurrent_runs` and `[cache]` sections
+    /// `manifest` declares to its lens (builder pattern)
+    pub fn with_manifest(mut self, manifest: &LensManifest) -> Self {
+        let id = &manifest.lens.id;
+        if let Some(limit) = manifest.resource_limits().max_concurrent_runs {
+            self = self.with_lens_limit(id, limit as usize);
+        }
+        if let Some(cache) = &manifest.cache {
+            self = self.with_cache(id, cache);
+        }
+        self
+    }
+
+    /// Drop cached results of lens `lens_id`, or of every lens when `None`
+    pub fn clear_cache(&self, lens_id: Option<&str>) {
+        self.inner.cache.clear(lens_id);
+    }
+
+    /// Maximum number of runs in flight across all lenses
+    pub fn max_concurrent(&self) -> usize {
+        self.inner.max_concurrent
+    }
+
+    /// Number of runs currently executing
+    pub fn running(&self) -> usize {
+        self.inner.running.load(Ordering::SeqCst)
+    }
+
+    /// Number of runs waiting for a slot
+    pub fn queued(&self) -> usize {
+        self.inner.queued.load(Ordering::SeqCst)
+    }
+
+    /// Queue `lens` to run with `ctx`
+    pub fn submit(&self, lens: Arc<dyn Lens>, ctx: LensContext) -> ExecutionHandle {
+        let (id, version) = (lens.id().to_string(), lens.version().to_string());
+        let policy = self.config.retry.clone();
+        self.spawn(&id, &version, ctx, move |ctx, events| {
+            let lens = Arc::clone(&lens);
+            let policy = policy.clone();
+            async move {
+                let started = Instant::now();
+                let _ = events.send(LensEvent::started_by(
+                    lens.id(),
+                    lens.name(),
+                    ctx.initiator.clone(),
+                ));
+                let outcome = execute_catching_panics(lens.as_ref(), ctx).await;
+                let retryable = outcome
+                    .as_ref()
+                    .err()
+                    .is_some_and(|error| policy.should_retry(error));
+                let terminal = match &outcome {
+                    Ok(result) if result.success => {
+                        LensEvent::completed(lens.id(), started.elapsed())
+                    }
+                    Ok(result) => LensEvent::failed(
+                        lens.id(),
+                        result.message.as_deref().unwrap_or("Lens reported failure"),
+                        false,
+                    ),
+                    Err(error) => {
+                        let mut event = LensEvent::failed_with_error(lens.id(), error);
+                        if let LensEvent::Failed { recoverable, .. } = &mut event {
+                            *recoverable = retryable;
+                        }
+                        event
+                    }
+                };
+                let _ = events.send(terminal);
+                Attempt { outcome, retryable }
+            }
+        })
+    }
+
+    /// Queue a streaming `lens` to run with `ctx`, forwarding its events.
+    ///
+    /// The run keeps its slot until the lens's event stream ends. A
+    /// `Failed` event marked `recoverable` makes the run retryable.
+    pub fn submit_streaming(
+        &self,
+        lens: Arc<dyn StreamingLens>,
+        ctx: LensContext,
+    ) -> ExecutionHandle {
+        let (id, version) = (lens.id().to_string(), lens.version().to_string());
+        let policy = self.config.retry.clone();
+        self.spawn(&id, &version, ctx, move |ctx, events| {
+            let lens = Arc::clone(&lens);
+            let policy = policy.clone();
+            async move {
+                match lens.execute_streaming(ctx).await {
+                    Ok((result, mut stream)) => {
+                        let mut retryable = false;
+                        while let Some(event) = stream.next().await {
+                            retryable |= matches!(
+                                event,
+                                LensEvent::Failed {
+                                    recoverable: true,
+                                    ..
+                                }
+                            );
+                            let _ = events.send(event);
+                        }
+                        Attempt {
+                            outcome: Ok(result),
+                            retryable,
+                        }
+                    }
+                    Err(error) => {
+                        let _ = events.send(LensEvent::failed_with_error(lens.id(), &error));
+                        Attempt {
+                            retryable: policy.should_retry(&error),
+                            outcome: Err(error),
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn spawn<F, Fut>(
+        &self,
+        lens_id: &str,
+        version: &str,
+        ctx: LensContext,
+        mut attempt: F,
+    ) -> ExecutionHandle
+    where
+        F: FnMut(LensContext, mpsc::UnboundedSender<LensEvent>) -> Fut + Send + 'static,
+        Fut: Future<Output = Attempt> + Send,
+    {
+        let host = ctx.host.clone();
+        if self.is_shut_down() {
+            let reason = "was rejected: the executor is shutting down";
+            let mut run = SharedRun::default();
+            let (events, result_rx) = run.subscribe();
+            run.publish(LensEvent::failed(lens_id, reason, false));
+            run.finish(Err(shut_down(lens_id, reason)));
+            return ExecutionHandle::new(lens_id, events, result_rx, false, host);
+        }
+
+        let key = run_key(lens_id, version, &ctx);
+        let cache_ttl = key
+            .as_ref()
+            .and(self.config.cache_ttls.get(lens_id).copied());
+        if let (Some(key), Some(_)) = (&key, cache_ttl) {
+            if let Some((result, age)) = self.inner.cache.get(key) {
+                let mut run = SharedRun::default();
+                let (events, result_rx) = run.subscribe();
+                run.publish(LensEvent::cache_hit(lens_id, age));
+                run.finish(Ok(result));
+                return ExecutionHandle::new(lens_id, events, result_rx, false, host);
+            }
+        }
+
+        let run = Arc::new(Mutex::new(SharedRun::default()));
+        // Key identical submissions join this run under, if any
+        let shared_key = key.clone().filter(|_| self.config.coalesce);
+        if let Some(shared_key) = &shared_key {
+            let mut in_flight = self.inner.in_flight.lock().unwrap();
+            if let Some(existing) = in_flight.get(shared_key) {
+                let (events, result_rx) = existing.lock().unwrap().subscribe();
+                join_cancellation(existing, ctx.cancellation);
+                return ExecutionHandle::new(lens_id, events, result_rx, true, host);
+            }
+            in_flight.insert(shared_key.clone(), Arc::clone(&run));
+        }
+        let mut ctx = ctx;
+        if shared_key.is_some() {
+            // The lens sees the run's token, not the first caller's
+            ctx.cancellation = join_cancellation(&run, ctx.cancellation.clone());
+        }
+        let (events, result_rx) = run.lock().unwrap().subscribe();
+
+        let (events_tx, mut run_events) = mpsc::unbounded_channel();
+        let forwarded = Arc::clone(&run);
+        tokio::spawn(async move {
+            while let Some(event) = run_events.recv().await {
+                forwarded.lock().unwrap().publish(event);
+            }
+        });
+
+        let inner = Arc::clone(&self.inner);
+        let retry = self.config.retry.clone();
+        let lens_slots = self.lens_slots(lens_id);
+        let lens_id = lens_id.to_string();
+        let handle_id = lens_id.clone();
+        let run_id = inner.next_run.fetch_add(1, Ordering::SeqCst);
+        inner.active.lock().unwrap().insert(
+            run_id,
+            ActiveRun {
+                lens_id: lens_id.clone(),
+                key: shared_key.clone(),
+                cancel: ctx.cancellation.clone(),
+                run: Arc::clone(&run),
+                abort: None,
+            },
+        );
+        let task = tokio::spawn(async move {
+            let _active = ActiveGuard {
+                inner: Arc::clone(&inner),
+                run_id,
+            };
+            let acquired = tokio::select! {
+                permits = inner.acquire(&lens_id, lens_slots, &events_tx) => Some(permits),
+                _ = inner.closed.cancelled() => None,
+            };
+            let Some(_permits) = acquired else {
+                let reason = "was cancelled before it started: the executor is shutting down";
+                let _ = events_tx.send(LensEvent::failed(&lens_id, reason, false));
+                if let Some(shared_key) = &shared_key {
+                    inner.in_flight.lock().unwrap().remove(shared_key);
+                }
+                drop(attempt);
+                run.lock().unwrap().finish(Err(shut_down(&lens_id, reason)));
+                return;
+            };
+            let running = CountGuard::new(&inner.running);
+            let max_attempts = retry.max_attempts.max(1);
+            let mut number = 1;
+            let outcome = loop {
+                let Attempt { outcome, retryable } = attempt(ctx.clone(), events_tx.clone()).await;
+                if !retryable || number >= max_attempts || inner.closed.is_cancelled() {
+                    break outcome;
+                }
+                let delay = retry.backoff(number);
+                number += 1;
+                let error = match &outcome {
+                    Ok(result) => result
+                        .message
+                        .clone()
+                        .unwrap_or_else(|| "Lens reported a recoverable failure".to_string()),
+                    Err(error) => error.to_string(),
+                };
+                let _ = events_tx.send(LensEvent::retrying(
+                    &lens_id,
+                    number,
+                    max_attempts,
+                    delay,
+                    error,
+                ));
+                tokio::time::sleep(delay).await;
+            };
+            drop(running);
+            if let (Some(ttl), Some(key), Ok(result)) = (cache_ttl, key, &outcome) {
+                if result.success {
+                    inner.cache.insert(key, result.clone(), ttl);
+                }
+            }
+            // Callers arriving from here on start a new run (or hit the cache)
+            if let Some(shared_key) = &shared_key {
+                inner.in_flight.lock().unwrap().remove(shared_key);
+            }
+            // Release the lens before reporting, so callers see it unused
+            drop(attempt);
+            run.lock().unwrap().finish(outcome);
+        });
+        if let Some(active) = self.inner.active.lock().unwrap().get_mut(&run_id) {
+            active.abort = Some(task.abort_handle());
+        }
+
+        ExecutionHandle::new(&handle_id, events, result_rx, false, host)
+    }
+
+    fn lens_slots(&self, lens_id: &str) -> Option<Arc<Semaphore>> {
+        let limit = self
+            .config
+            .lens_limits
+            .get(lens_id)
+            .copied()
+            .or(self.config.per_lens_limit)?;
+        let mut per_lens = self.inner.per_lens.lock().unwrap();
+        Some(Arc::clone(
+            per_lens
+                .entry((lens_id.to_string(), limit))
+                .or_insert_with(|| Arc::new(Semaphore::new(limit))),
+        ))
+    }
+
+    fn config_mut(&mut self) -> &mut Config {
+        Arc::make_mut(&mut self.config)
+    }
+}
+
+/// Identity of a run: the lens, its version, the host's
+/// [`run_scope`](LensContext::run_scope), the config, and the input.
+///
+/// `None` for runs without a scope, which are never cached or coalesced:
+/// the executor cannot tell whose they are, so a result computed for one
+/// user or project is never handed to another. serde_json keeps object
+/// keys sorted, so inputs differing only in key order share a key.
+fn run_key(lens_id: &str, version: &str, ctx: &LensContext) -> Option<String> {
+    let scope = ctx.run_scope.as_deref()?;
+    Some(format!(
+        "{}@{}\n{:?}\n{}\n{}",
+        lens_id,
+        version,
+        scope,
+        ctx.config.as_ref().unwrap_or(&serde_json::Value::Null),
+        ctx.input
+    ))
+}
+
+impl Inner {
+    /// Wait for a per-lens slot, then a global one, so a lens at its own
+    /// limit does not hold global slots other lenses could use
+    async fn acquire(
+        &self,
+        lens_id: &str,
+        lens_slots: Option<Arc<Semaphore>>,
+        events: &mpsc::UnboundedSender<LensEvent>,
+    ) -> (Option<OwnedSemaphorePermit>, OwnedSemaphorePermit) {
+        let saturated = self.global.available_permits() == 0
+            || lens_slots
+                .as_ref()
+                .is_some_and(|slots| slots.available_permits() == 0);
+        if saturated {
+            let _ = events.send(LensEvent::progress(
+                lens_id,
+                "Waiting for a free execution slot",
+            ));
+        }
+
+        let _queued = CountGuard::new(&self.queued);
+        let lens_permit = match lens_slots {
+            Some(slots) => Some(slots.acquire_owned().await.expect("semaphore never closed")),
+            None => None,
+        };
+        let global_permit = Arc::clone(&self.global)
+            .acquire_owned()
+            .await
+            .expect("semaphore never closed");
+        (lens_permit, global_permit)
+    }
+}
+
+/// A submitted run: awaits to the run's result and carries its events
+pub struct ExecutionHandle {
+    lens_id: String,
+    result: oneshot::Receiver<Result<LensResult>>,
+    events: Option<LensEventStream>,
+    coalesced: bool,
+}
+
+impl std::fmt::Debug for ExecutionHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExecutionHandle")
+            .field("lens_id", &self.lens_id)
+            .field("coalesced", &self.coalesced)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ExecutionHandle {
+    fn new(
+        lens_id: &str,
+        events: mpsc::UnboundedReceiver<LensEvent>,
+        result: oneshot::Receiver<Result<LensResult>>,
+        coalesced: bool,
+        host: HostInfo,
+    ) -> Self {
+        let events = UnboundedReceiverStream::new(events).map(move |event| host.adapt_event(event));
+        Self {
+            lens_id: lens_id.to_string(),
+            result,
+            events: Some(Box::pin(events)),
+            coalesced,
+        }
+    }
+
+    /// Id of the lens being run
+    pub fn lens_id(&self) -> &str {
+        &self.lens_id
+    }
+
+    /// The run's event stream; `None` once taken.
+    ///
+    /// Events are buffered until the stream is read, so taking it late
+    /// loses nothing.
+    pub fn take_events(&mut self) -> Option<LensEventStream> {
+        self.events.take()
+    }
+
+    /// Whether this submission joined an identical run already in flight
+    /// instead of starting its own
+    pub fn is_coalesced(&self) -> bool {
+        self.coalesced
+    }
+}
+
+impl Future for ExecutionHandle {
+    type Output = Result<LensResult>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let lens_id = self.lens_id.clone();
+        Pin::new(&mut self.result).poll(cx).map(|outcome| {
+            outcome.unwrap_or_else(|_| {
+                Err(LensError::ExecutionFailed(format!(
+                    "Execution of lens '{}' ended without a result",
+                    lens_id
+                )))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    /// Sleeps for `input.sleep_ms` (cut short by cancellation unless
+    /// `input.stubborn`) and records its runs and the peak number of
+    /// concurrent runs
+    struct SlowLens {
+        id: &'static str,
+        runs: AtomicUsize,
+        active: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    impl SlowLens {
+        fn new(id: &'static str) -> Self {
+            Self {
+                id,
+                runs: AtomicUsize::new(0),
+                active: Arc::default(),
+                peak: Arc::default(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Lens for SlowLens {
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        fn name(&self) -> &str {
+            "Slow"
+        }
+
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+
+        async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(active, Ordering::SeqCst);
+            let ms = ctx.input["sleep_ms"].as_u64().unwrap_or(0);
+            let sleep = tokio::time::sleep(Duration::from_millis(ms));
+            let cancelled = async {
+                if ctx.input["stubborn"] == true {
+                    std::future::pending().await
+                }
+                ctx.cancellation.cancelled().await
+            };
+            let finished = tokio::select! {
+                _ = sleep => true,
+                _ = cancelled => false,
+            };
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            if !finished {
+                return Err(LensError::ExecutionFailed("cancelled".to_string()));
+            }
+            if ctx.input["fail"] == true {
+                return Err(LensError::ExecutionFailed("asked to fail".to_string()));
+            }
+            Ok(LensResult::success(ctx.input))
+        }
+    }
+
+    fn ctx(sleep_ms: u64) -> LensContext {
+        LensContext::new(PathBuf::from("/tmp"), json!({ "sleep_ms": sleep_ms }))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_executor_enforces_limits() {
+        let executor = LensExecutor::new(3).with_per_lens_limit(2);
+        let figma = Arc::new(SlowLens::new("figma"));
+        let notes = Arc::new(SlowLens::new("notes"));
+
+        let mut handles = Vec::new();
+        for _ in 0..5 {
+            handles.push(executor.submit(figma.clone(), ctx(100)));
+        }
+        for _ in 0..3 {
+            handles.push(executor.submit(notes.clone(), ctx(100)));
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(executor.running(), 3);
+        assert_eq!(executor.queued(), 5);
+
+        for handle in handles {
+            assert!(handle.await.unwrap().success);
+        }
+        assert_eq!(figma.peak.load(Ordering::SeqCst), 2);
+        assert!(notes.peak.load(Ordering::SeqCst) <= 2);
+        assert_eq!(executor.running(), 0);
+        assert_eq!(executor.queued(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_executor_lens_limit_overrides_default() {
+        let executor = LensExecutor::new(8)
+            .with_per_lens_limit(4)
+            .with_lens_limit("figma", 1);
+        let figma = Arc::new(SlowLens::new("figma"));
+
+        let handles: Vec<_> = (0..3)
+            .map(|_| executor.submit(figma.clone(), ctx(50)))
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(figma.peak.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_executor_clone_configured_separately() {
+        let executor = LensExecutor::new(2);
+        let limited = executor.clone().with_lens_limit("figma", 1);
+        let figma = Arc::new(SlowLens::new("figma"));
+
+        // Both share the global slots; only the clone limits figma
+        let handles: Vec<_> = (0..2)
+            .map(|_| limited.submit(figma.clone(), ctx(50)))
+            .collect();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(executor.running(), 1);
+        assert_eq!(executor.queued(), 1);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let figma = Arc::new(SlowLens::new("figma"));
+        let handles: Vec<_> = (0..2)
+            .map(|_| executor.submit(figma.clone(), ctx(50)))
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(figma.peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_execution_handle_streams_lifecycle_events() {
+        let executor = LensExecutor::new(1);
+        let lens = Arc::new(SlowLens::new("figma"));
+
+        let first = executor.submit(lens.clone(), ctx(50));
+        let mut second = executor.submit(lens.clone(), ctx(50));
+        let mut failing = executor.submit(
+            lens.clone(),
+            LensContext::new(PathBuf::from("/tmp"), json!({ "fail": true })),
+        );
+
+        let events: Vec<_> = second.take_events().unwrap().collect().await;
+        let types: Vec<_> = events.iter().map(|event| event.event_type()).collect();
+        assert_eq!(types, ["Progress", "Started", "Completed"]);
+        assert!(second.take_events().is_none());
+
+        first.await.unwrap();
+        assert_eq!(second.await.unwrap().output["sleep_ms"], 50);
+
+        let err = (&mut failing).await.unwrap_err();
+        assert!(matches!(err, LensError::ExecutionFailed(_)));
+        let events: Vec<_> = failing.take_events().unwrap().collect().await;
+        match events.last() {
+            Some(LensEvent::Failed { code, .. }) => {
+                assert_eq!(code.as_deref(), Some("ExecutionFailed"))
+            }
+            other => panic!("Expected Failed event, got {:?}", other),
+        }
+    }
+
+    /// Fails with the queued errors, then succeeds
+    struct FlakyLens {
+        failures: Mutex<Vec<LensError>>,
+        attempts: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Lens for FlakyLens {
+        fn id(&self) -> &str {
+            "flaky"
+        }
+
+        fn name(&self) -> &str {
+            "Flaky"
+        }
+
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+
+        async fn execute(&self, _ctx: LensContext) -> Result<LensResult> {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            match self.failures.lock().unwrap().pop() {
+                Some(error) => Err(error),
+                None => Ok(LensResult::success(json!({ "ok": true }))),
+            }
+        }
+    }
+
+    fn flaky(failures: Vec<LensError>) -> Arc<FlakyLens> {
+        Arc::new(FlakyLens {
+            failures: Mutex::new(failures),
+            attempts: AtomicUsize::new(0),
+        })
+    }
+
+    fn closed() -> LensError {
+        LensError::IoError(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "closed",
+        ))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_executor_retries_recoverable_failures() {
+        let executor = LensExecutor::new(1).with_retry_policy(
+            RetryPolicy::new()
+                .with_max_attempts(3)
+                .with_retry_on(["ResourceLimitExceeded"]),
+        );
+
+        let lens = flaky(vec![
+            closed(),
+            LensError::ResourceLimitExceeded("too slow".to_string()),
+        ]);
+        let mut handle = executor.submit(lens.clone(), ctx(0));
+        assert!(handle.take_events().is_some());
+        assert!(handle.await.unwrap().success);
+        assert_eq!(lens.attempts.load(Ordering::SeqCst), 3);
+
+        let lens = flaky(vec![closed(), closed(), closed()]);
+        let mut handle = executor.submit(lens.clone(), ctx(0));
+        let events: Vec<_> = handle.take_events().unwrap().collect().await;
+        assert!(matches!(handle.await, Err(LensError::IoError(_))));
+        assert_eq!(lens.attempts.load(Ordering::SeqCst), 3);
+        let retries: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                LensEvent::Retrying {
+                    attempt,
+                    max_attempts,
+                    ..
+                } => Some((*attempt, *max_attempts)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(retries, [(2, 3), (3, 3)]);
+        assert!(matches!(
+            events.last(),
+            Some(LensEvent::Failed {
+                recoverable: true,
+                ..
+            })
+        ));
+
+        let lens = flaky(vec![LensError::InvalidInput("bad".to_string())]);
+        assert!(executor.submit(lens.clone(), ctx(0)).await.is_err());
+        assert_eq!(lens.attempts.load(Ordering::SeqCst), 1);
+
+        // Hosts speaking schema version 1 see the retry as progress
+        let host = HostInfo::default().with_event_versions(vec![1]);
+        let mut handle = executor.submit(flaky(vec![closed()]), ctx(0).with_host(host));
+        let events: Vec<_> = handle.take_events().unwrap().collect().await;
+        let types: Vec<_> = events.iter().map(|event| event.event_type()).collect();
+        assert_eq!(
+            types,
+            ["Started", "Failed", "Progress", "Started", "Completed"]
+        );
+        assert!(events[0].initiator().is_none());
+        assert!(matches!(events[1], LensEvent::Failed { code: None, .. }));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_executor_caches_declared_lenses() {
+        let manifest = LensManifest::from_toml(
+            "[lens]\nid = \"flaky\"\nname = \"Flaky\"\nversion = \"1.0.0\"\n\n\
+             [cache]\nttl_secs = 60\n",
+        )
+        .unwrap();
+        let executor = LensExecutor::new(2).with_manifest(&manifest);
+        let lens = flaky(Vec::new());
+        let input = |json: &str| {
+            let mut ctx = ctx(0).with_run_scope("alice/figma-tokens");
+            ctx.input = serde_json::from_str(json).unwrap();
+            ctx
+        };
+
+        executor
+            .submit(lens.clone(), input(r#"{"a": 1, "b": 2}"#))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        let mut hit = executor.submit(lens.clone(), input(r#"{"b": 2, "a": 1}"#));
+        let events: Vec<_> = hit.take_events().unwrap().collect().await;
+        assert!(hit.await.unwrap().success);
+        assert_eq!(lens.attempts.load(Ordering::SeqCst), 1);
+        match events.as_slice() {
+            [LensEvent::CacheHit { age, .. }] => assert_eq!(*age, Duration::from_secs(5)),
+            other => panic!("Expected a single CacheHit event, got {:?}", other),
+        }
+
+        executor
+            .submit(lens.clone(), input(r#"{"a": 2, "b": 1}"#))
+            .await
+            .unwrap();
+        assert_eq!(lens.attempts.load(Ordering::SeqCst), 2);
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        executor
+            .submit(lens.clone(), input(r#"{"a": 1, "b": 2}"#))
+            .await
+            .unwrap();
+        assert_eq!(lens.attempts.load(Ordering::SeqCst), 3);
+
+        // The same input in another scope, or in none, is not a hit
+        let elsewhere = input(r#"{"a": 1, "b": 2}"#).with_run_scope("bob/figma-tokens");
+        executor.submit(lens.clone(), elsewhere).await.unwrap();
+        let mut unscoped = input(r#"{"a": 1, "b": 2}"#);
+        unscoped.run_scope = None;
+        executor
+            .submit(lens.clone(), unscoped.clone())
+            .await
+            .unwrap();
+        executor.submit(lens.clone(), unscoped).await.unwrap();
+        assert_eq!(lens.attempts.load(Ordering::SeqCst), 6);
+
+        executor.clear_cache(Some("flaky"));
+        executor
+            .submit(lens.clone(), input(r#"{"a": 1, "b": 2}"#))
+            .await
+            .unwrap();
+        assert_eq!(lens.attempts.load(Ordering::SeqCst), 7);
+
+        let uncached = LensExecutor::new(2);
+        uncached
+            .submit(lens.clone(), input(r#"{"a": 1, "b": 2}"#))
+            .await
+            .unwrap();
+        uncached
+            .submit(lens.clone(), input(r#"{"a": 1, "b": 2}"#))
+            .await
+            .unwrap();
+        assert_eq!(lens.attempts.load(Ordering::SeqCst), 9);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_executor_coalesces_identical_runs() {
+        let executor = LensExecutor::new(4).with_coalescing(true);
+        let lens = Arc::new(SlowLens::new("figma"));
+        let ctx = |sleep_ms| ctx(sleep_ms).with_run_scope("alice");
+
+        let mut first = executor.submit(lens.clone(), ctx(100));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let mut second = executor.submit(lens.clone(), ctx(100));
+        let other = executor.submit(lens.clone(), ctx(50));
+        assert!(!first.is_coalesced());
+        assert!(second.is_coalesced());
+        assert!(!other.is_coalesced());
+
+        let first_events: Vec<_> = first.take_events().unwrap().collect().await;
+        let second_events: Vec<_> = second.take_events().unwrap().collect().await;
+        let types = |events: &[LensEvent]| {
+            events
+                .iter()
+                .map(|event| event.event_type())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(types(&first_events), ["Started", "Completed"]);
+        assert_eq!(types(&second_events), types(&first_events));
+
+        assert_eq!(first.await.unwrap().output, second.await.unwrap().output);
+        other.await.unwrap();
+        assert_eq!(lens.runs.load(Ordering::SeqCst), 2);
+
+        // Finished runs are not joined
+        executor.submit(lens.clone(), ctx(100)).await.unwrap();
+        assert_eq!(lens.runs.load(Ordering::SeqCst), 3);
+
+        // Nor are runs in another scope, or without one
+        let first = executor.submit(lens.clone(), ctx(100));
+        let elsewhere = executor.submit(lens.clone(), ctx(100).with_run_scope("bob"));
+        assert!(!elsewhere.is_coalesced());
+        let mut unscoped = ctx(100);
+        unscoped.run_scope = None;
+        executor.submit(lens.clone(), unscoped.clone());
+        let unscoped = executor.submit(lens.clone(), unscoped);
+        assert!(!unscoped.is_coalesced());
+        first.await.unwrap();
+        elsewhere.await.unwrap();
+        unscoped.await.unwrap();
+        assert_eq!(lens.runs.load(Ordering::SeqCst), 7);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_coalesced_run_cancelled_by_all_callers() {
+        let executor = LensExecutor::new(4).with_coalescing(true);
+        let lens = Arc::new(SlowLens::new("figma"));
+        let ctx = |sleep_ms| ctx(sleep_ms).with_run_scope("alice");
+        let (one, two) = (CancellationToken::new(), CancellationToken::new());
+
+        let first = executor.submit(lens.clone(), ctx(1_000).with_cancellation(one.clone()));
+        let second = executor.submit(lens.clone(), ctx(1_000).with_cancellation(two.clone()));
+        assert!(second.is_coalesced());
+
+        // One caller giving up leaves the run going for the other
+        one.cancel();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(executor.running(), 1);
+
+        two.cancel();
+        assert!(first.await.is_err());
+        assert!(second.await.is_err());
+        assert_eq!(lens.runs.load(Ordering::SeqCst), 1);
+
+        // A lone caller still cancels its own run
+        let three = CancellationToken::new();
+        let run = executor.submit(lens.clone(), ctx(1_000).with_cancellation(three.clone()));
+        three.cancel();
+        assert!(run.await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_execute_all_runs_side_by_side() {
+        let executor = LensExecutor::new(4);
+        let figma = Arc::new(SlowLens::new("figma"));
+        let notes = Arc::new(SlowLens::new("notes"));
+        let failing = LensContext::new(
+            PathBuf::from("/tmp"),
+            json!({ "sleep_ms": 50, "fail": true }),
+        );
+
+        let started = tokio::time::Instant::now();
+        let (results, events) = executor.execute_all([
+            RunRequest::new("a", figma.clone(), ctx(100)),
+            RunRequest::new("b", notes.clone(), failing),
+            RunRequest::new("c", figma.clone(), ctx(80)),
+        ]);
+        let events: Vec<RunEvent> = events.collect().await;
+        let outcomes = results.await;
+        assert_eq!(started.elapsed(), Duration::from_millis(100));
+
+        let ids: Vec<_> = outcomes.iter().map(|o| o.run_id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+        let successes: Vec<_> = outcomes.iter().map(RunOutcome::is_success).collect();
+        assert_eq!(successes, [true, false, true]);
+        assert_eq!(outcomes[1].lens_id, "notes");
+        assert!(matches!(
+            outcomes[1].result,
+            Err(LensError::ExecutionFailed(_))
+        ));
+
+        // Terminal events arrive in finishing order, each tagged with its run
+        let terminal: Vec<_> = events
+            .iter()
+            .filter(|tagged| {
+                matches!(
+                    tagged.event,
+                    LensEvent::Completed { .. } | LensEvent::Failed { .. }
+                )
+            })
+            .map(|tagged| tagged.run_id.as_str())
+            .collect();
+        assert_eq!(terminal, ["b", "c", "a"]);
+        assert_eq!(events.len(), 6);
+
+        let value = serde_json::to_value(&events[0]).unwrap();
+        assert_eq!(value["type"], "started");
+        assert!(value["run_id"].is_string());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_cancels_and_drops_runs() {
+        let executor = LensExecutor::new(2);
+        let lens = Arc::new(SlowLens::new("figma"));
+        let stubborn = LensContext::new(
+            PathBuf::from("/tmp"),
+            json!({ "sleep_ms": 10_000, "stubborn": true }),
+        );
+
+        let mut polite = executor.submit(lens.clone(), ctx(10_000));
+        let mut forced = executor.submit(lens.clone(), stubborn);
+        let mut queued = executor.submit(lens.clone(), ctx(20_000));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!((executor.running(), executor.queued()), (2, 1));
+
+        let started = tokio::time::Instant::now();
+        assert_eq!(executor.shutdown(Duration::from_millis(500)).await, 1);
+        assert_eq!(started.elapsed(), Duration::from_millis(500));
+        assert!(executor.is_shut_down());
+
+        // Each run ends with a Failed event and an error
+        for handle in [&mut polite, &mut forced, &mut queued] {
+            let mut events = handle.take_events().unwrap();
+            assert!(handle.await.is_err());
+            let mut last = None;
+            while let Ok(Some(event)) =
+                tokio::time::timeout(Duration::from_millis(1), events.next()).await
+            {
+                last = Some(event);
+            }
+            assert_eq!(last.unwrap().event_type(), "Failed");
+        }
+        assert_eq!(lens.runs.load(Ordering::SeqCst), 2);
+        assert_eq!((executor.running(), executor.queued()), (0, 0));
+
+        // Later submissions are turned away
+        let err = executor.submit(lens.clone(), ctx(0)).await.unwrap_err();
+        assert!(err.to_string().contains("shutting down"));
+        assert_eq!(executor.shutdown(Duration::from_secs(1)).await, 0);
+    }
+}
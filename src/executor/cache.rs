@@ -0,0 +1,66 @@
+//! Result cache for lenses declaring `[cache]`
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::LensResult;
+
+/// Successful results by lens, version, and input
+#[derive(Debug, Default)]
+pub(super) struct ResultCache {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    result: LensResult,
+    stored: Instant,
+    expires: Instant,
+}
+
+impl ResultCache {
+    /// The result stored under `key` and its age, unless it expired
+    pub(super) fn get(&self, key: &str) -> Option<(LensResult, Duration)> {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        match entries.get(key) {
+            Some(entry) if entry.expires > now => Some((entry.result.clone(), now - entry.stored)),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Store `result` under `key` for `ttl`, dropping expired entries
+    pub(super) fn insert(&self, key: String, result: LensResult, ttl: Duration) {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        entries.retain(|_, entry| entry.expires > now);
+        entries.insert(
+            key,
+            Entry {
+                result,
+                stored: now,
+                expires: now + ttl,
+            },
+        );
+    }
+
+    /// Drop the results of `lens_id`, or of every lens
+    pub(super) fn clear(&self, lens_id: Option<&str>) {
+        let mut entries = self.entries.lock().unwrap();
+        match lens_id {
+            Some(id) => {
+                // Keys start with the lens id, see `run_key`
+                let prefix = format!("{}@", id);
+                entries.retain(|key, _| !key.starts_with(&prefix));
+            }
+            None => entries.clear(),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Sharing one run between every caller that submitted it
+
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{mpsc, oneshot};
+
+use crate::cancel::CancellationToken;
+use crate::error::{LensError, Result};
+use crate::{LensEvent, LensResult};
+
+/// Events and result of one run, fanned out to its callers
+#[derive(Debug, Default)]
+pub(super) struct SharedRun {
+    /// Every event so far, replayed to callers joining late
+    events: Vec<LensEvent>,
+    subscribers: Vec<mpsc::UnboundedSender<LensEvent>>,
+    waiters: Vec<oneshot::Sender<Result<LensResult>>>,
+    finished: bool,
+    /// Token the lens sees, cancelled once every caller has cancelled
+    cancel: CancellationToken,
+    /// Each caller's own `ctx.cancellation`
+    callers: Vec<CancellationToken>,
+    /// Cancelled when the run finishes, ending the caller watchers
+    done: CancellationToken,
+}
+
+impl SharedRun {
+    /// Add a caller, returning its event and result receivers
+    pub(super) fn subscribe(
+        &mut self,
+    ) -> (
+        mpsc::UnboundedReceiver<LensEvent>,
+        oneshot::Receiver<Result<LensResult>>,
+    ) {
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        for event in &self.events {
+            let _ = events_tx.send(event.clone());
+        }
+        self.subscribers.push(events_tx);
+        let (result_tx, result_rx) = oneshot::channel();
+        self.waiters.push(result_tx);
+        (events_rx, result_rx)
+    }
+
+    /// Send `event` to every caller
+    pub(super) fn publish(&mut self, event: LensEvent) {
+        self.subscribers
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        self.events.push(event);
+    }
+
+    /// Whether the result has been handed out
+    pub(super) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Hand `outcome` to every caller waiting for the result
+    pub(super) fn finish(&mut self, outcome: Result<LensResult>) {
+        self.finished = true;
+        self.done.cancel();
+        let mut waiters = std::mem::take(&mut self.waiters);
+        let Some(last) = waiters.pop() else {
+            return;
+        };
+        for waiter in waiters {
+            let shared = match &outcome {
+                Ok(result) => Ok(result.clone()),
+                Err(error) => Err(share_error(error)),
+            };
+            let _ = waiter.send(shared);
+        }
+        let _ = last.send(outcome);
+    }
+}
+
+/// Count `caller` among the run's callers, returning the token the lens
+/// should see.
+///
+/// The run is cancelled once every caller that joined it has cancelled, so
+/// one caller giving up does not fail the others.
+pub(super) fn join_cancellation(
+    run: &Arc<Mutex<SharedRun>>,
+    caller: CancellationToken,
+) -> CancellationToken {
+    let (cancel, done) = {
+        let mut shared = run.lock().unwrap();
+        shared.callers.push(caller.clone());
+        (shared.cancel.clone(), shared.done.clone())
+    };
+    let run = Arc::clone(run);
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = caller.cancelled() => {
+                let shared = run.lock().unwrap();
+                if shared.callers.iter().all(CancellationToken::is_cancelled) {
+                    shared.cancel.cancel();
+                }
+            }
+            _ = done.cancelled() => {}
+        }
+    });
+    cancel
+}
+
+/// Copy of `error` with the same variant and message
+fn share_error(error: &LensError) -> LensError {
+    match error {
+        LensError::ExecutionFailed(message) => LensError::ExecutionFailed(message.clone()),
+        LensError::InvalidContext(message) => LensError::InvalidContext(message.clone()),
+        LensError::InvalidInput(message) => LensError::InvalidInput(message.clone()),
+        LensError::LensNotFound(message) => LensError::LensNotFound(message.clone()),
+        LensError::Initialization(message) => LensError::Initialization(message.clone()),
+        LensError::PermissionDenied(message) => LensError::PermissionDenied(message.clone()),
+        LensError::ResourceLimitExceeded(message) => {
+            LensError::ResourceLimitExceeded(message.clone())
+        }
+        LensError::StreamError(message) => LensError::StreamError(message.clone()),
+        LensError::SerializationError(e) => {
+            LensError::SerializationError(serde::de::Error::custom(e.to_string()))
+        }
+        LensError::IoError(e) => LensError::IoError(std::io::Error::new(e.kind(), e.to_string())),
+        LensError::Other(message) => LensError::Other(message.clone()),
+    }
+}
@@ -0,0 +1,153 @@
+//! Running independent lenses side by side
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio_stream::{Stream, StreamExt, StreamMap};
+
+use super::{ExecutionHandle, LensExecutor};
+use crate::error::Result;
+use crate::streaming::StreamingLens;
+use crate::{Lens, LensContext, LensEvent, LensResult};
+
+/// One run in a [`LensExecutor::execute_all`] batch
+pub struct RunRequest {
+    run_id: String,
+    lens: RequestedLens,
+    ctx: LensContext,
+}
+
+enum RequestedLens {
+    Plain(Arc<dyn Lens>),
+    Streaming(Arc<dyn StreamingLens>),
+}
+
+impl std::fmt::Debug for RunRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lens_id = match &self.lens {
+            RequestedLens::Plain(lens) => lens.id(),
+            RequestedLens::Streaming(lens) => lens.id(),
+        };
+        f.debug_struct("RunRequest")
+            .field("run_id", &self.run_id)
+            .field("lens_id", &lens_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RunRequest {
+    /// Run `lens` with `ctx`, tagging its events with `run_id`
+    pub fn new(run_id: impl Into<String>, lens: Arc<dyn Lens>, ctx: LensContext) -> Self {
+        Self {
+            run_id: run_id.into(),
+            lens: RequestedLens::Plain(lens),
+            ctx,
+        }
+    }
+
+    /// Run a streaming `lens` with `ctx`, tagging its events with `run_id`
+    pub fn streaming(
+        run_id: impl Into<String>,
+        lens: Arc<dyn StreamingLens>,
+        ctx: LensContext,
+    ) -> Self {
+        Self {
+            run_id: run_id.into(),
+            lens: RequestedLens::Streaming(lens),
+            ctx,
+        }
+    }
+
+    /// Id tagging this run's events and outcome
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+}
+
+/// A `LensEvent` tagged with the id of the run that emitted it.
+///
+/// Serializes as the flat event object with an extra `run_id` field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunEvent {
+    pub run_id: String,
+    #[serde(flatten)]
+    pub event: LensEvent,
+}
+
+/// Events of every run in a batch, interleaved as they happen
+pub type RunEventStream = Pin<Box<dyn Stream<Item = RunEvent> + Send>>;
+
+/// Result of one run in a batch
+#[derive(Debug)]
+pub struct RunOutcome {
+    /// Id given in the [`RunRequest`]
+    pub run_id: String,
+    /// Id of the lens that ran
+    pub lens_id: String,
+    /// The run's result; failures do not affect the other runs
+    pub result: Result<LensResult>,
+}
+
+impl RunOutcome {
+    /// Whether the run finished with a successful result
+    pub fn is_success(&self) -> bool {
+        self.result.as_ref().is_ok_and(|result| result.success)
+    }
+}
+
+impl LensExecutor {
+    /// Submit every request at once and merge their events.
+    ///
+    /// Returns a future resolving to one [`RunOutcome`] per request, in
+    /// request order, and a stream interleaving the runs' events tagged
+    /// with their run ids. Runs are independent: a failing run does not
+    /// cancel the others, and the future resolves once all have finished.
+    /// The usual limits still apply, so a large batch queues rather than
+    /// running everything at once. The stream ends after the last run's
+    /// final event.
+    pub fn execute_all(
+        &self,
+        requests: impl IntoIterator<Item = RunRequest>,
+    ) -> (
+        impl Future<Output = Vec<RunOutcome>> + Send + 'static,
+        RunEventStream,
+    ) {
+        let mut streams = StreamMap::new();
+        let mut handles: Vec<(String, ExecutionHandle)> = Vec::new();
+        for (index, request) in requests.into_iter().enumerate() {
+            let mut handle = match request.lens {
+                RequestedLens::Plain(lens) => self.submit(lens, request.ctx),
+                RequestedLens::Streaming(lens) => self.submit_streaming(lens, request.ctx),
+            };
+            if let Some(events) = handle.take_events() {
+                let run_id = request.run_id.clone();
+                // Keyed by position, since run ids are not required to differ
+                streams.insert(
+                    index,
+                    events.map(move |event| RunEvent {
+                        run_id: run_id.clone(),
+                        event,
+                    }),
+                );
+            }
+            handles.push((request.run_id, handle));
+        }
+
+        let results = async move {
+            let mut outcomes = Vec::with_capacity(handles.len());
+            for (run_id, handle) in handles {
+                let lens_id = handle.lens_id().to_string();
+                outcomes.push(RunOutcome {
+                    run_id,
+                    lens_id,
+                    result: handle.await,
+                });
+            }
+            outcomes
+        };
+        let events: RunEventStream = Box::pin(streams.map(|(_, event)| event));
+        (results, events)
+    }
+}
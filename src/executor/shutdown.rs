@@ -0,0 +1,122 @@
+//! Stopping an executor and the runs it owns
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::task::AbortHandle;
+
+use super::coalesce::SharedRun;
+use super::{Inner, LensExecutor};
+use crate::cancel::CancellationToken;
+use crate::error::LensError;
+use crate::LensEvent;
+
+/// A run that has been spawned and not yet finished
+#[derive(Debug)]
+pub(super) struct ActiveRun {
+    pub(super) lens_id: String,
+    /// Key in `in_flight`, for coalesced runs
+    pub(super) key: Option<String>,
+    /// The run's `ctx.cancellation`
+    pub(super) cancel: CancellationToken,
+    pub(super) run: Arc<Mutex<SharedRun>>,
+    pub(super) abort: Option<AbortHandle>,
+}
+
+/// Removes a run from `active` when its task ends or is aborted
+pub(super) struct ActiveGuard {
+    pub(super) inner: Arc<Inner>,
+    pub(super) run_id: u64,
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        let mut active = self.inner.active.lock().unwrap();
+        active.remove(&self.run_id);
+        if active.is_empty() {
+            self.inner.idle.notify_waiters();
+        }
+    }
+}
+
+/// Counts something (a queued or running run) until dropped, so aborted
+/// runs do not stay counted
+pub(super) struct CountGuard<'a>(&'a AtomicUsize);
+
+impl<'a> CountGuard<'a> {
+    pub(super) fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self(counter)
+    }
+}
+
+impl Drop for CountGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Error given to runs stopped by a shutdown
+pub(super) fn shut_down(lens_id: &str, reason: &str) -> LensError {
+    LensError::ExecutionFailed(format!("Run of lens '{}' {}", lens_id, reason))
+}
+
+impl LensExecutor {
+    /// Stop the executor and the runs it owns.
+    ///
+    /// New submissions fail straight away and queued runs fail without
+    /// starting. Running lenses see their `ctx.cancellation` cancelled and
+    /// get `grace` to wind down; runs still going after that are dropped
+    /// mid-flight with a terminal `Failed` event. Every caller's handle
+    /// resolves either way. Returns the number of runs that had to be
+    /// dropped.
+    pub async fn shutdown(&self, grace: Duration) -> usize {
+        let inner = &self.inner;
+        inner.closed.cancel();
+        for run in inner.active.lock().unwrap().values() {
+            run.cancel.cancel();
+        }
+
+        let deadline = tokio::time::Instant::now() + grace;
+        loop {
+            let idle = inner.idle.notified();
+            if inner.active.lock().unwrap().is_empty() {
+                return 0;
+            }
+            if tokio::time::timeout_at(deadline, idle).await.is_err() {
+                break;
+            }
+        }
+
+        let remaining: Vec<ActiveRun> = inner
+            .active
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, run)| run)
+            .collect();
+        let mut forced = 0;
+        for run in &remaining {
+            if let Some(abort) = &run.abort {
+                abort.abort();
+            }
+            if let Some(key) = &run.key {
+                inner.in_flight.lock().unwrap().remove(key);
+            }
+            let reason = "did not stop within the shutdown grace period";
+            let mut shared = run.run.lock().unwrap();
+            if !shared.is_finished() {
+                shared.publish(LensEvent::failed(&run.lens_id, reason, false));
+                shared.finish(Err(shut_down(&run.lens_id, reason)));
+                forced += 1;
+            }
+        }
+        forced
+    }
+
+    /// Whether [`shutdown`](Self::shutdown) has been called
+    pub fn is_shut_down(&self) -> bool {
+        self.inner.closed.is_cancelled()
+    }
+}
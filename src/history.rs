@@ -0,0 +1,173 @@
+//! # Run History
+//!
+//! Per-run event logs on disk, and reports rendered from them.
+//!
+//! Requires the `runtime` feature.
+//!
+//! # Directory Structure
+//!
+//! ```text
+//! <history root>/
+//! └── <run_id>/
+//!     └── events.jsonl     # Append-only log, one LensEvent per line
+//! ```
+//!
+//! ```rust,ignore
+//! let history = RunHistory::new(dir).with_output_spec(spec);
+//! while let Some(event) = events.next().await {
+//!     history.record("run-42", &event)?;
+//! }
+//! std::fs::write("run-42.html", history.render_report("run-42", ReportFormat::Html)?)?;
+//! ```
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::artifacts::validate_segment;
+use crate::error::{LensError, Result};
+use crate::output_spec::LensOutputSpec;
+use crate::report::{ReportFormat, RunReport};
+use crate::LensEvent;
+
+/// Event log filename inside each run directory
+pub const HISTORY_EVENTS_FILENAME: &str = "events.jsonl";
+
+/// Filesystem-backed run history
+#[derive(Debug)]
+pub struct RunHistory {
+    root: PathBuf,
+    output_specs: HashMap<String, LensOutputSpec>,
+    lock: Mutex<()>,
+}
+
+impl RunHistory {
+    /// Create a history rooted at the given directory
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            output_specs: HashMap::new(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Use `spec` for reports on runs of `spec.lens_id` (builder pattern)
+    pub fn with_output_spec(mut self, spec: LensOutputSpec) -> Self {
+        self.output_specs.insert(spec.lens_id.clone(), spec);
+        self
+    }
+
+    /// Directory holding a run's event log
+    pub fn run_dir(&self, run_id: &str) -> PathBuf {
+        self.root.join(run_id)
+    }
+
+    /// Append an event to a run's log
+    pub fn record(&self, run_id: &str, event: &LensEvent) -> Result<()> {
+        validate_segment("run id", run_id)?;
+        let run_dir = self.run_dir(run_id);
+        std::fs::create_dir_all(&run_dir)?;
+
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+
+        let _guard = self
+            .lock
+            .lock()
+            .map_err(|_| LensError::Other("Run history lock poisoned".to_string()))?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(run_dir.join(HISTORY_EVENTS_FILENAME))?
+            .write_all(&line)?;
+        Ok(())
+    }
+
+    /// All events recorded for a run, in order
+    pub fn events(&self, run_id: &str) -> Result<Vec<LensEvent>> {
+        validate_segment("run id", run_id)?;
+        let log_path = self.run_dir(run_id).join(HISTORY_EVENTS_FILENAME);
+        if !log_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        std::fs::read_to_string(&log_path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(LensError::from))
+            .collect()
+    }
+
+    /// Render a recorded run as a static document.
+    ///
+    /// The report uses the output spec registered for the run's lens, when
+    /// there is one.
+    pub fn render_report(&self, run_id: &str, format: ReportFormat) -> Result<String> {
+        let events = self.events(run_id)?;
+        let lens_id = events.first().map(LensEvent::lens).ok_or_else(|| {
+            LensError::InvalidInput(format!("No recorded events for run '{}'", run_id))
+        })?;
+        let mut report = RunReport::new(lens_id, run_id, &events);
+        if let Some(spec) = self.output_specs.get(lens_id) {
+            report = report.with_output_spec(spec);
+        }
+        Ok(report.render(format))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_record_and_render_report() {
+        let temp_dir = tempdir().unwrap();
+        let spec = LensOutputSpec::from_yaml(
+            r#"
+lens_id: audit
+outputs:
+  - key: findings
+    title: Findings
+    render_blocks:
+      - type: table
+        source: rows
+    examples:
+      - rows: []
+"#,
+        )
+        .unwrap();
+        let history = RunHistory::new(temp_dir.path()).with_output_spec(spec);
+
+        for event in [
+            LensEvent::started("audit", "scan"),
+            LensEvent::data("audit", "findings", json!({"rows": [{"file": "a.rs"}]})),
+            LensEvent::completed("audit", Duration::from_millis(40)),
+        ] {
+            history.record("run-1", &event).unwrap();
+        }
+        assert_eq!(history.events("run-1").unwrap().len(), 3);
+
+        let markdown = history
+            .render_report("run-1", ReportFormat::Markdown)
+            .unwrap();
+        assert!(markdown.starts_with("# audit — run run-1"));
+        assert!(markdown.contains("### Findings\n\n| file |\n| --- |\n| a.rs |"));
+        assert!(markdown.contains("- **Duration:** 40 ms"));
+
+        let html = history.render_report("run-1", ReportFormat::Html).unwrap();
+        assert!(html.contains("<td>a.rs</td>"));
+
+        let err = history
+            .render_report("run-2", ReportFormat::Html)
+            .unwrap_err();
+        assert_eq!(err.code(), "InvalidInput");
+        assert!(history
+            .record("../escape", &LensEvent::started("audit", "x"))
+            .is_err());
+    }
+}
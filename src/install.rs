@@ -0,0 +1,497 @@
+//! # Lens Installation
+//!
+//! A `.lens` package is a tar archive, optionally gzip-compressed, holding
+//! one lens directory: `lens.toml`, `lens.output.yaml`, the compiled
+//! library, and any `components/`. Files may sit at the archive root or
+//! inside a single top-level directory.
+//!
+//! Requires the `runtime` feature.
+//!
+//! ```rust,ignore
+//! let installer = LensInstaller::new(LensDiscovery::default_directory()?);
+//! let lens = installer.install_from_archive("figma-1.2.0.lens")?;
+//! println!("installed {} {}", lens.id(), lens.version());
+//! ```
+//!
+//! Lenses can also be installed straight from a git repository with
+//! [`LensInstaller::install_from_git`], or downloaded as a `.lens` package
+//! with `LensInstaller::install_from_url` (`remote-install` feature).
+//!
+//! Every source is unpacked into a hidden staging directory inside the
+//! lenses directory, validated there, and renamed into place, so a failed
+//! install never leaves a half-written lens behind. Pass a channel to
+//! [`LensInstaller::with_progress`] to follow an install as it runs.
+//!
+//! [`LensInstaller::uninstall`] reverses an install, including the lens's
+//! state directory, lockfile entry, and stored OAuth tokens.
+
+mod git;
+mod uninstall;
+#[cfg(feature = "remote-install")]
+mod url;
+
+pub use uninstall::UninstallReport;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::read::GzDecoder;
+use tar::{Archive, EntryType};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::discovery::{DiscoveredLens, LensDiscovery, DISABLED_MARKER, MANIFEST_FILENAME};
+use crate::error::{LensError, Result};
+use crate::lockfile::LOCKFILE_FILENAME;
+use crate::oauth::FileOAuthBroker;
+#[cfg(feature = "signing")]
+use crate::security::KeyPins;
+
+/// File extension of packaged lenses
+pub const LENS_ARCHIVE_EXTENSION: &str = "lens";
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Progress of an install, sent to the channel given to
+/// [`LensInstaller::with_progress`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallEvent {
+    /// Started installing from `source` (archive path, git URL, or URL)
+    Started { source: String },
+    /// Downloaded `received` bytes of `total`, when the server reports it
+    Downloading { received: u64, total: Option<u64> },
+    /// Checking the manifest, library hash, and signature of lens `id`
+    Verifying { id: String },
+    /// Lens installed at `path`
+    Installed {
+        id: String,
+        version: String,
+        path: PathBuf,
+    },
+}
+
+/// Installs packaged lenses into a lenses directory
+#[derive(Debug, Clone)]
+pub struct LensInstaller {
+    discovery: LensDiscovery,
+    replace: bool,
+    progress: Option<UnboundedSender<InstallEvent>>,
+    state_dir: Option<PathBuf>,
+    lockfile: Option<PathBuf>,
+    oauth: Option<Arc<FileOAuthBroker>>,
+    #[cfg(feature = "signing")]
+    trusted_keys: Option<KeyPins>,
+}
+
+impl LensInstaller {
+    /// Install into the lenses directory of `discovery`
+    pub fn new(discovery: LensDiscovery) -> Self {
+        Self {
+            discovery,
+            replace: false,
+            progress: None,
+            state_dir: None,
+            lockfile: None,
+            oauth: None,
+            #[cfg(feature = "signing")]
+            trusted_keys: None,
+        }
+    }
+
+    /// Replace an installed lens with the same id instead of failing
+    pub fn with_replace(mut self, replace: bool) -> Self {
+        self.replace = replace;
+        self
+    }
+
+    /// Report [`InstallEvent`]s to `progress`
+    pub fn with_progress(mut self, progress: UnboundedSender<InstallEvent>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Directory holding per-lens state in `<state_dir>/<lens id>`, removed
+    /// on uninstall
+    pub fn with_state_dir<P: AsRef<Path>>(mut self, state_dir: P) -> Self {
+        self.state_dir = Some(state_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Lockfile to keep in step on uninstall, instead of the `lenses.lock`
+    /// next to the lenses directory
+    pub fn with_lockfile<P: AsRef<Path>>(mut self, lockfile: P) -> Self {
+        self.lockfile = Some(lockfile.as_ref().to_path_buf());
+        self
+    }
+
+    /// Token store to clear on uninstall for providers no other lens uses
+    pub fn with_oauth_store(mut self, oauth: Arc<FileOAuthBroker>) -> Self {
+        self.oauth = Some(oauth);
+        self
+    }
+
+    /// Only install packages signed by one of `trusted_keys`; an empty list
+    /// refuses every package.
+    ///
+    /// See [`LensManifest::verify_signature`](crate::manifest::LensManifest::verify_signature)
+    /// for how the keys are applied.
+    #[cfg(feature = "signing")]
+    pub fn with_trusted_keys(self, trusted_keys: Vec<String>) -> Self {
+        self.with_key_pins(trusted_keys.into())
+    }
+
+    /// Only install packages signed by a key `pins` trusts for the lens id,
+    /// e.g. first-party lenses only from the first-party key.
+    ///
+    /// See [`KeyPins::keys_for`] for how pins are applied.
+    #[cfg(feature = "signing")]
+    pub fn with_key_pins(mut self, pins: KeyPins) -> Self {
+        self.trusted_keys = Some(pins);
+        self
+    }
+
+    /// Discovery for the target lenses directory
+    pub fn discovery(&self) -> &LensDiscovery {
+        &self.discovery
+    }
+
+    /// Lockfile updated by this installer
+    pub fn lockfile_path(&self) -> PathBuf {
+        self.lockfile.clone().unwrap_or_else(|| {
+            let lenses_dir = self.discovery.plugins_dir();
+            lenses_dir
+                .parent()
+                .unwrap_or(lenses_dir)
+                .join(LOCKFILE_FILENAME)
+        })
+    }
+
+    /// Validate and install the `.lens` package at `archive`.
+    ///
+    /// Rejects packages with links, paths outside the package, a missing or
+    /// invalid manifest, a library that does not match
+    /// `[security].library_hash`, or (with trusted keys) a bad signature.
+    pub fn install_from_archive<P: AsRef<Path>>(&self, archive: P) -> Result<DiscoveredLens> {
+        let archive = archive.as_ref();
+        let source = archive.display().to_string();
+        self.emit(InstallEvent::Started {
+            source: source.clone(),
+        });
+
+        let staging = self.staging_dir()?;
+        unpack(archive, staging.path())?;
+        self.install_staged(&staging, &source)
+    }
+
+    fn emit(&self, event: InstallEvent) {
+        if let Some(progress) = &self.progress {
+            let _ = progress.send(event);
+        }
+    }
+
+    fn staging_dir(&self) -> Result<StagingDir> {
+        self.discovery.ensure_exists()?;
+        StagingDir::create(self.discovery.plugins_dir(), "install")
+    }
+
+    /// Validate the lens unpacked into `staging` and move it into place
+    fn install_staged(&self, staging: &StagingDir, source: &str) -> Result<DiscoveredLens> {
+        let root = package_root(staging.path()).ok_or_else(|| {
+            LensError::InvalidInput(format!(
+                "{} does not contain a {}",
+                source, MANIFEST_FILENAME
+            ))
+        })?;
+
+        let lens = self.discovery.load_lens(&root)?;
+        self.emit(InstallEvent::Verifying {
+            id: lens.id().to_string(),
+        });
+        self.verify(&lens)?;
+        let dest = self.install_dir(lens.id())?;
+        self.move_into_place(&root, &dest)?;
+
+        let lens = self.discovery.load_lens(&dest)?;
+        self.emit(InstallEvent::Installed {
+            id: lens.id().to_string(),
+            version: lens.version().to_string(),
+            path: dest,
+        });
+        Ok(lens)
+    }
+
+    fn verify(&self, lens: &DiscoveredLens) -> Result<()> {
+        let declared = lens
+            .manifest
+            .security
+            .as_ref()
+            .and_then(|security| security.library_hash.as_deref());
+        if let Some(declared) = declared {
+            let actual = lens.compute_library_hash()?;
+            if actual.as_deref() != Some(declared) {
+                return Err(LensError::InvalidInput(format!(
+                    "Library for lens '{}' does not match [security].library_hash (expected {}, got {})",
+                    lens.id(),
+                    declared,
+                    actual.as_deref().unwrap_or("no library")
+                )));
+            }
+        }
+
+        #[cfg(feature = "signing")]
+        if let Some(pins) = &self.trusted_keys {
+            lens.verify_signature(&pins.keys_for(lens.id())?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Directory the lens `id` installs into
+    fn install_dir(&self, id: &str) -> Result<PathBuf> {
+        let safe = !id.is_empty() && !id.starts_with('.') && !id.contains(['/', '\\', ':']);
+        if !safe {
+            return Err(LensError::InvalidInput(format!(
+                "Lens id '{}' cannot be used as a directory name",
+                id
+            )));
+        }
+        Ok(self.discovery.plugins_dir().join(id))
+    }
+
+    fn move_into_place(&self, staged: &Path, dest: &Path) -> Result<()> {
+        if !dest.exists() {
+            std::fs::rename(staged, dest)?;
+            return Ok(());
+        }
+        if !self.replace {
+            return Err(LensError::InvalidInput(format!(
+                "A lens is already installed at {:?}",
+                dest
+            )));
+        }
+
+        // Keep the old lens until the new one is in place
+        let backup = StagingDir::path_for(self.discovery.plugins_dir(), "replaced");
+        std::fs::rename(dest, &backup)?;
+        if let Err(e) = std::fs::rename(staged, dest) {
+            std::fs::rename(&backup, dest)?;
+            return Err(e.into());
+        }
+        // A lens the user turned off stays off across upgrades
+        if backup.join(DISABLED_MARKER).exists() {
+            std::fs::write(dest.join(DISABLED_MARKER), "")?;
+        }
+        if let Err(e) = std::fs::remove_dir_all(&backup) {
+            eprintln!(
+                "Warning: Failed to remove replaced lens at {:?}: {}",
+                backup, e
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Hidden directory inside the lenses directory, removed on drop
+struct StagingDir(PathBuf);
+
+impl StagingDir {
+    fn path_for(parent: &Path, purpose: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        parent.join(format!(".{}-{}-{}", purpose, std::process::id(), nanos))
+    }
+
+    fn create(parent: &Path, purpose: &str) -> Result<Self> {
+        let path = Self::path_for(parent, purpose);
+        std::fs::create_dir(&path)?;
+        Ok(Self(path))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Unpack `archive` into `dest`, gunzipping if needed
+fn unpack(archive: &Path, dest: &Path) -> Result<()> {
+    let file = File::open(archive).map_err(|e| {
+        LensError::InvalidInput(format!("Failed to open package {:?}: {}", archive, e))
+    })?;
+    let mut reader = BufReader::new(file);
+    let gzipped = reader.fill_buf()?.starts_with(&GZIP_MAGIC);
+    let reader: Box<dyn Read> = if gzipped {
+        Box::new(GzDecoder::new(reader))
+    } else {
+        Box::new(reader)
+    };
+
+    let invalid = |e: std::io::Error| {
+        LensError::InvalidInput(format!("Invalid package {:?}: {}", archive, e))
+    };
+    let mut tar = Archive::new(reader);
+    for entry in tar.entries().map_err(invalid)? {
+        let mut entry = entry.map_err(invalid)?;
+        let path = entry.path().map_err(invalid)?.into_owned();
+        match entry.header().entry_type() {
+            EntryType::Regular | EntryType::Directory => {}
+            EntryType::XGlobalHeader => continue,
+            other => {
+                return Err(LensError::InvalidInput(format!(
+                    "Package {:?} contains unsupported entry {:?} ({:?}); only files and directories are allowed",
+                    archive, path, other
+                )))
+            }
+        }
+        if !entry.unpack_in(dest).map_err(invalid)? {
+            return Err(LensError::InvalidInput(format!(
+                "Package {:?} contains path {:?} outside the package",
+                archive, path
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Directory holding the manifest: `dir` itself or its only subdirectory
+fn package_root(dir: &Path) -> Option<PathBuf> {
+    if dir.join(MANIFEST_FILENAME).is_file() {
+        return Some(dir.to_path_buf());
+    }
+    let mut entries = std::fs::read_dir(dir).ok()?.flatten();
+    let only = entries.next()?.path();
+    if entries.next().is_some() || !only.join(MANIFEST_FILENAME).is_file() {
+        return None;
+    }
+    Some(only)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn manifest(version: &str, library_hash: Option<&str>) -> String {
+        let mut toml = format!(
+            "[lens]\nid = \"figma\"\nname = \"Figma\"\nversion = \"{}\"\n",
+            version
+        );
+        if let Some(hash) = library_hash {
+            toml.push_str(&format!("\n[security]\nlibrary_hash = \"{}\"\n", hash));
+        }
+        toml
+    }
+
+    /// Build a gzipped package from (path, contents) pairs
+    fn package(dir: &Path, files: &[(&str, &str)]) -> PathBuf {
+        let path = dir.join("figma.lens");
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            File::create(&path).unwrap(),
+            Compression::default(),
+        ));
+        for (name, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, contents.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+        path
+    }
+
+    fn installer(dir: &Path) -> LensInstaller {
+        LensInstaller::new(LensDiscovery::new(dir.join("lenses")))
+    }
+
+    #[test]
+    fn test_install_from_archive() {
+        let temp_dir = tempdir().unwrap();
+        let archive = package(
+            temp_dir.path(),
+            &[
+                ("figma/lens.toml", &manifest("1.0.0", None)),
+                ("figma/components/Frame.tsx", "export {}"),
+            ],
+        );
+        let installer = installer(temp_dir.path());
+
+        let lens = installer.install_from_archive(&archive).unwrap();
+        assert_eq!(lens.id(), "figma");
+        assert_eq!(lens.path, installer.discovery().plugins_dir().join("figma"));
+        assert!(lens.path.join("components/Frame.tsx").is_file());
+
+        // Installing again needs replace, and leaves no staging directories
+        assert!(installer.install_from_archive(&archive).is_err());
+        let archive = package(temp_dir.path(), &[("lens.toml", &manifest("1.1.0", None))]);
+        let lens = installer
+            .with_replace(true)
+            .install_from_archive(&archive)
+            .unwrap();
+        assert_eq!(lens.version(), "1.1.0");
+        let entries: Vec<_> = fs::read_dir(temp_dir.path().join("lenses"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, vec!["figma"]);
+    }
+
+    #[test]
+    fn test_install_rejects_hash_mismatch() {
+        let temp_dir = tempdir().unwrap();
+        let library = format!("libfigma.{}", std::env::consts::DLL_EXTENSION);
+        let archive = package(
+            temp_dir.path(),
+            &[
+                ("lens.toml", &manifest("1.0.0", Some("sha256:0000"))),
+                (&library, "not the signed library"),
+            ],
+        );
+
+        let err = installer(temp_dir.path())
+            .install_from_archive(&archive)
+            .unwrap_err();
+        assert!(err.to_string().contains("does not match"));
+        assert!(!temp_dir.path().join("lenses/figma").exists());
+    }
+
+    #[test]
+    fn test_install_rejects_links() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("evil.lens");
+        let mut builder = tar::Builder::new(File::create(&path).unwrap());
+        let manifest = manifest("1.0.0", None);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "lens.toml", manifest.as_bytes())
+            .unwrap();
+        let mut link = tar::Header::new_gnu();
+        link.set_entry_type(EntryType::Symlink);
+        link.set_size(0);
+        builder
+            .append_link(&mut link, "secrets", "/etc/passwd")
+            .unwrap();
+        builder.finish().unwrap();
+
+        let err = installer(temp_dir.path())
+            .install_from_archive(&path)
+            .unwrap_err();
+        assert!(err.to_string().contains("unsupported entry"));
+    }
+}
@@ -0,0 +1,160 @@
+//! Installing lenses from git repositories, using the `git` executable
+
+use std::path::Path;
+use std::process::Command;
+
+use super::{InstallEvent, LensInstaller};
+use crate::discovery::DiscoveredLens;
+use crate::error::{LensError, Result};
+
+impl LensInstaller {
+    /// Install the lens at the root of the git repository `url`.
+    ///
+    /// `git_ref` may be a branch, tag, or commit; `None` installs the default
+    /// branch. Only that one commit is fetched, and the `.git` directory is
+    /// not kept. Values starting with `-` are refused, since git would read
+    /// them as options.
+    pub fn install_from_git(&self, url: &str, git_ref: Option<&str>) -> Result<DiscoveredLens> {
+        for (what, value) in [("URL", Some(url)), ("ref", git_ref)] {
+            if value.is_some_and(|value| value.starts_with('-')) {
+                return Err(LensError::InvalidInput(format!(
+                    "Git {} must not start with '-': {}",
+                    what,
+                    value.unwrap_or_default()
+                )));
+            }
+        }
+        let source = match git_ref {
+            Some(git_ref) => format!("git+{}#{}", url, git_ref),
+            None => format!("git+{}", url),
+        };
+        self.emit(InstallEvent::Started {
+            source: source.clone(),
+        });
+
+        let staging = self.staging_dir()?;
+        let dir = staging.path();
+        git(dir, &["init", "--quiet"])?;
+        git(
+            dir,
+            &[
+                "fetch",
+                "--quiet",
+                "--depth",
+                "1",
+                "--",
+                url,
+                git_ref.unwrap_or("HEAD"),
+            ],
+        )?;
+        git(dir, &["checkout", "--quiet", "FETCH_HEAD"])?;
+        std::fs::remove_dir_all(dir.join(".git"))?;
+
+        self.install_staged(&staging, &source)
+    }
+}
+
+/// Run `git` in `dir`, never prompting for credentials
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|e| LensError::Initialization(format!("Failed to run git: {}", e)))?;
+    if !output.status.success() {
+        return Err(LensError::ExecutionFailed(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::LensDiscovery;
+    use std::fs;
+    use tempfile::tempdir;
+    use tokio::sync::mpsc;
+
+    fn commit_manifest(repo: &Path, version: &str) {
+        fs::write(
+            repo.join("lens.toml"),
+            format!(
+                "[lens]\nid = \"gitlens\"\nname = \"Git Lens\"\nversion = \"{}\"\n",
+                version
+            ),
+        )
+        .unwrap();
+        let run = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(repo)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        run(&["add", "."]);
+        run(&[
+            "-c",
+            "user.name=Test",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "--quiet",
+            "-m",
+            version,
+        ]);
+        run(&["tag", &format!("v{}", version)]);
+    }
+
+    #[test]
+    fn test_install_from_git() {
+        let temp_dir = tempdir().unwrap();
+        let repo = temp_dir.path().join("repo");
+        fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "--quiet"]).unwrap();
+        commit_manifest(&repo, "1.0.0");
+        commit_manifest(&repo, "2.0.0");
+        let url = format!("file://{}", repo.display());
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let installer = LensInstaller::new(LensDiscovery::new(temp_dir.path().join("lenses")))
+            .with_progress(tx);
+        let lens = installer.install_from_git(&url, Some("v1.0.0")).unwrap();
+        assert_eq!(lens.version(), "1.0.0");
+        assert!(!lens.path.join(".git").exists());
+
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            InstallEvent::Started {
+                source: format!("git+{}#v1.0.0", url)
+            }
+        );
+        assert!(matches!(rx.try_recv(), Ok(InstallEvent::Verifying { .. })));
+        assert!(matches!(rx.try_recv(), Ok(InstallEvent::Installed { .. })));
+
+        let lens = installer
+            .clone()
+            .with_replace(true)
+            .install_from_git(&url, None)
+            .unwrap();
+        assert_eq!(lens.version(), "2.0.0");
+        assert!(installer
+            .install_from_git(&url, Some("no-such-ref"))
+            .is_err());
+
+        // Option-like values never reach git
+        let marker = temp_dir.path().join("pwned");
+        let upload_pack = format!("--upload-pack=touch {}", marker.display());
+        for (url, git_ref) in [(upload_pack.as_str(), None), (url.as_str(), Some("-q"))] {
+            let err = installer.install_from_git(url, git_ref).unwrap_err();
+            assert!(matches!(err, LensError::InvalidInput(_)));
+        }
+        assert!(!marker.exists());
+    }
+}
@@ -0,0 +1,213 @@
+//! Removing installed lenses and everything kept on their behalf
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use super::LensInstaller;
+use crate::error::{LensError, Result};
+use crate::lockfile::Lockfile;
+
+/// What [`LensInstaller::uninstall`] removed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UninstallReport {
+    pub id: String,
+    /// The deleted lens directory
+    pub lens_dir: PathBuf,
+    /// The deleted state directory, if the lens had one
+    pub state_dir: Option<PathBuf>,
+    /// OAuth providers whose stored tokens were deleted
+    pub disconnected_providers: Vec<String>,
+    /// Lockfile the lens entry was removed from
+    pub lockfile: Option<PathBuf>,
+}
+
+impl LensInstaller {
+    /// Remove the lens `lens_id` from the lenses directory, along with its
+    /// state directory, its lockfile entry, and stored tokens for OAuth
+    /// providers that no other installed lens declares.
+    ///
+    /// Project-local lenses are never removed. Fails with
+    /// `LensError::LensNotFound` when the lens is not installed. The lockfile
+    /// and tokens are dealt with before any directory is deleted, so a
+    /// failure there leaves the lens installed.
+    pub fn uninstall(&self, lens_id: &str) -> Result<UninstallReport> {
+        // Also rejects ids that would escape the lenses or state directory
+        let install_dir = self.install_dir(lens_id)?;
+        let lenses = self.discovery.scan()?;
+        let (ours, others): (Vec<_>, Vec<_>) = lenses.iter().partition(|lens| {
+            lens.id() == lens_id && lens.path.starts_with(self.discovery.plugins_dir())
+        });
+
+        // A lens whose manifest no longer loads is still removable by directory
+        let lens_dir = match ours.first() {
+            Some(lens) => lens.path.clone(),
+            None => install_dir,
+        };
+        if !lens_dir.is_dir() {
+            return Err(LensError::LensNotFound(lens_id.to_string()));
+        }
+
+        let lockfile_path = self.lockfile_path();
+        let mut lockfile = None;
+        if lockfile_path.is_file() {
+            let mut locked = Lockfile::load(&lockfile_path)?;
+            if locked.remove(lens_id).is_some() {
+                locked.save(&lockfile_path)?;
+                lockfile = Some(lockfile_path);
+            }
+        }
+
+        let mut disconnected_providers = Vec::new();
+        if let (Some(oauth), Some(lens)) = (&self.oauth, ours.first()) {
+            let still_used: BTreeSet<&str> = others
+                .iter()
+                .flat_map(|other| &other.manifest.oauth_providers)
+                .map(|requirement| requirement.provider.as_str())
+                .collect();
+            for requirement in &lens.manifest.oauth_providers {
+                let provider = requirement.provider.as_str();
+                if still_used.contains(provider)
+                    || disconnected_providers.iter().any(|p| p == provider)
+                {
+                    continue;
+                }
+                oauth
+                    .disconnect(provider)
+                    .map_err(|e| LensError::Other(e.to_string()))?;
+                disconnected_providers.push(provider.to_string());
+            }
+        }
+
+        std::fs::remove_dir_all(&lens_dir)?;
+        let state_dir = match &self.state_dir {
+            Some(root) => {
+                let dir = root.join(lens_id);
+                if dir.is_dir() {
+                    std::fs::remove_dir_all(&dir)?;
+                    Some(dir)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+
+        Ok(UninstallReport {
+            id: lens_id.to_string(),
+            lens_dir,
+            state_dir,
+            disconnected_providers,
+            lockfile,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::LensDiscovery;
+    use crate::lockfile::LockedLens;
+    use crate::oauth::{FileOAuthBroker, OAuthBroker, OAuthToken};
+    use std::fs;
+    use std::path::Path;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    fn write_lens(lenses_dir: &Path, id: &str, providers: &[&str]) {
+        let dir = lenses_dir.join(id);
+        fs::create_dir_all(&dir).unwrap();
+        let mut toml = format!(
+            "[lens]\nid = \"{}\"\nname = \"{}\"\nversion = \"1.0.0\"\n",
+            id, id
+        );
+        for provider in providers {
+            toml.push_str(&format!(
+                "\n[[oauth_providers]]\nprovider = \"{}\"\n",
+                provider
+            ));
+        }
+        fs::write(dir.join("lens.toml"), toml).unwrap();
+    }
+
+    fn token() -> OAuthToken {
+        OAuthToken {
+            access_token: "token".to_string(),
+            refresh_token: None,
+            expires_at: None,
+            scope: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_uninstall_cleans_up() {
+        let temp_dir = tempdir().unwrap();
+        let lenses_dir = temp_dir.path().join("lenses");
+        write_lens(&lenses_dir, "figma", &["figma", "google"]);
+        write_lens(&lenses_dir, "drive", &["google"]);
+
+        let state_root = temp_dir.path().join("state");
+        fs::create_dir_all(state_root.join("figma")).unwrap();
+        let oauth = Arc::new(FileOAuthBroker::with_key(
+            temp_dir.path().join("oauth"),
+            [7; 32],
+        ));
+        oauth.store_token("figma", &token()).unwrap();
+        oauth.store_token("google", &token()).unwrap();
+
+        let installer = LensInstaller::new(LensDiscovery::new(&lenses_dir))
+            .with_state_dir(&state_root)
+            .with_oauth_store(oauth.clone());
+        let mut lockfile = Lockfile::default();
+        for id in ["drive", "figma"] {
+            lockfile.upsert(LockedLens {
+                id: id.to_string(),
+                version: "1.0.0".to_string(),
+                library_hash: None,
+                source: None,
+            });
+        }
+        lockfile.save(installer.lockfile_path()).unwrap();
+
+        let report = installer.uninstall("figma").unwrap();
+        assert_eq!(report.lens_dir, lenses_dir.join("figma"));
+        assert_eq!(report.state_dir, Some(state_root.join("figma")));
+        assert_eq!(report.disconnected_providers, vec!["figma"]);
+        assert_eq!(report.lockfile, Some(installer.lockfile_path()));
+
+        assert!(!lenses_dir.join("figma").exists());
+        assert!(!oauth.is_connected("figma").await);
+        // Still needed by the drive lens
+        assert!(oauth.is_connected("google").await);
+        let lockfile = Lockfile::load(installer.lockfile_path()).unwrap();
+        assert!(lockfile.get("figma").is_none() && lockfile.get("drive").is_some());
+
+        assert!(matches!(
+            installer.uninstall("figma"),
+            Err(LensError::LensNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_uninstall_failures_keep_the_lens() {
+        let temp_dir = tempdir().unwrap();
+        let lenses_dir = temp_dir.path().join("lenses");
+        write_lens(&lenses_dir, "figma", &[]);
+        let state_root = temp_dir.path().join("state");
+        fs::create_dir_all(temp_dir.path().join("outside")).unwrap();
+        let installer =
+            LensInstaller::new(LensDiscovery::new(&lenses_dir)).with_state_dir(&state_root);
+
+        for id in ["../outside", "..", ""] {
+            assert!(
+                matches!(installer.uninstall(id), Err(LensError::InvalidInput(_))),
+                "{}",
+                id
+            );
+        }
+        assert!(temp_dir.path().join("outside").is_dir());
+
+        fs::write(installer.lockfile_path(), "not a lockfile").unwrap();
+        assert!(installer.uninstall("figma").is_err());
+        assert!(lenses_dir.join("figma/lens.toml").is_file());
+    }
+}
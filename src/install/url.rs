@@ -0,0 +1,171 @@
+//! Downloading `.lens` packages over HTTP(S)
+
+use std::fs::File;
+use std::io::{Read, Write};
+
+use sha2::{Digest, Sha256};
+
+use super::{unpack, InstallEvent, LensInstaller, StagingDir};
+use crate::discovery::DiscoveredLens;
+use crate::error::{LensError, Result};
+
+/// Emit a `Downloading` event at most once per this many bytes
+const PROGRESS_INTERVAL: u64 = 64 * 1024;
+
+impl LensInstaller {
+    /// Download and install the `.lens` package at `url`.
+    ///
+    /// A `#sha256=<hex>` fragment pins the package checksum; the download is
+    /// rejected when it does not match.
+    pub fn install_from_url(&self, url: &str) -> Result<DiscoveredLens> {
+        let (location, checksum) = split_checksum(url)?;
+        self.emit(InstallEvent::Started {
+            source: url.to_string(),
+        });
+
+        self.discovery.ensure_exists()?;
+        let download = StagingDir::create(self.discovery.plugins_dir(), "download")?;
+        let archive = download.path().join("package.lens");
+        let actual = self.download(location, &mut File::create(&archive)?)?;
+        if let Some(expected) = checksum {
+            if !actual.eq_ignore_ascii_case(&expected) {
+                return Err(LensError::InvalidInput(format!(
+                    "Checksum mismatch for {}: expected sha256:{}, got sha256:{}",
+                    location, expected, actual
+                )));
+            }
+        }
+
+        let staging = self.staging_dir()?;
+        unpack(&archive, staging.path())?;
+        self.install_staged(&staging, url)
+    }
+
+    /// Stream `url` into `out`, returning the hex SHA-256 of the body
+    fn download(&self, url: &str, out: &mut File) -> Result<String> {
+        let response = ureq::get(url).call().map_err(|e| {
+            LensError::ExecutionFailed(format!("Failed to download {}: {}", url, e))
+        })?;
+        let total = response
+            .header("Content-Length")
+            .and_then(|len| len.parse().ok());
+
+        let mut reader = response.into_reader();
+        let mut hasher = Sha256::new();
+        let mut buf = [0u8; 16 * 1024];
+        let mut received = 0u64;
+        let mut reported = 0u64;
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            out.write_all(&buf[..n])?;
+            received += n as u64;
+            if received - reported >= PROGRESS_INTERVAL {
+                reported = received;
+                self.emit(InstallEvent::Downloading { received, total });
+            }
+        }
+        self.emit(InstallEvent::Downloading { received, total });
+
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+}
+
+/// Split a `#sha256=<hex>` fragment off `url`
+fn split_checksum(url: &str) -> Result<(&str, Option<String>)> {
+    let Some((location, fragment)) = url.split_once('#') else {
+        return Ok((url, None));
+    };
+    match fragment.strip_prefix("sha256=") {
+        Some(hex) if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Ok((location, Some(hex.to_string())))
+        }
+        _ => Err(LensError::InvalidInput(format!(
+            "Unsupported URL fragment '#{}'; expected #sha256=<hex>",
+            fragment
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::LensDiscovery;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use tempfile::tempdir;
+    use tokio::sync::mpsc;
+
+    /// Serve `body` to every request on a local port
+    fn serve(body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+        format!("http://{}/figma.lens", addr)
+    }
+
+    fn package() -> Vec<u8> {
+        let manifest = "[lens]\nid = \"figma\"\nname = \"Figma\"\nversion = \"1.0.0\"\n";
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "lens.toml", manifest.as_bytes())
+            .unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_install_from_url_verifies_checksum() {
+        let temp_dir = tempdir().unwrap();
+        let body = package();
+        let checksum = format!("{:x}", Sha256::digest(&body));
+        let url = serve(body.clone());
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let installer = LensInstaller::new(LensDiscovery::new(temp_dir.path().join("lenses")))
+            .with_progress(tx);
+
+        let wrong = format!("{}#sha256={}", url, "0".repeat(64));
+        let err = installer.install_from_url(&wrong).unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"));
+
+        let lens = installer
+            .install_from_url(&format!("{}#sha256={}", url, checksum))
+            .unwrap();
+        assert_eq!(lens.id(), "figma");
+
+        let events: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+        assert!(events.contains(&InstallEvent::Downloading {
+            received: body.len() as u64,
+            total: Some(body.len() as u64),
+        }));
+        assert!(matches!(
+            events.last(),
+            Some(InstallEvent::Installed { .. })
+        ));
+
+        assert!(split_checksum("https://x/a.lens#md5=abc").is_err());
+    }
+}
@@ -1,6 +1,12 @@
+use std::any::Any;
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
 use async_trait::async_trait;
 
-use crate::{LensContext, LensResult, Result};
+use crate::{LensContext, LensError, LensResult, Result};
 
 /// Core lens trait that all lenses must implement.
 ///
@@ -65,6 +71,50 @@ pub trait Lens: Send + Sync {
     }
 }
 
+/// Run `lens.execute(ctx)`, turning a panic inside the lens into
+/// `LensError::ExecutionFailed` carrying the panic message instead of
+/// unwinding into the host.
+pub async fn execute_catching_panics<L: Lens + ?Sized>(
+    lens: &L,
+    ctx: LensContext,
+) -> Result<LensResult> {
+    match CatchUnwind(lens.execute(ctx)).await {
+        Ok(result) => result,
+        Err(payload) => Err(LensError::ExecutionFailed(format!(
+            "Lens '{}' panicked: {}",
+            lens.id(),
+            panic_message(payload.as_ref())
+        ))),
+    }
+}
+
+/// Text of a panic payload, when it carries one
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
+/// Future adapter that catches panics raised while polling the inner future
+struct CatchUnwind<F>(F);
+
+impl<F: Future + Unpin> Future for CatchUnwind<F> {
+    type Output = std::thread::Result<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = &mut self.0;
+        match catch_unwind(AssertUnwindSafe(|| Pin::new(inner).poll(cx))) {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,6 +191,46 @@ mod tests {
         assert_eq!(result.output["doubled"], 0);
     }
 
+    struct PanickingLens;
+
+    #[async_trait]
+    impl Lens for PanickingLens {
+        fn id(&self) -> &str {
+            "panicky"
+        }
+
+        fn name(&self) -> &str {
+            "Panicky"
+        }
+
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+
+        async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+            tokio::task::yield_now().await;
+            panic!("bad input {}", ctx.input);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_catching_panics() {
+        let ctx = LensContext::new(PathBuf::from("/tmp"), json!(7));
+        let err = execute_catching_panics(&PanickingLens, ctx)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LensError::ExecutionFailed(_)));
+        assert_eq!(
+            err.to_string(),
+            "Lens execution failed: Lens 'panicky' panicked: bad input 7"
+        );
+
+        let ctx = LensContext::new(PathBuf::from("/tmp"), json!({"value": 2}));
+        let lens: &dyn Lens = &TestLens::new();
+        let result = execute_catching_panics(lens, ctx).await.unwrap();
+        assert_eq!(result.output["doubled"], 4);
+    }
+
     #[test]
     fn test_lens_is_send_sync() {
         fn assert_send_sync<T: Send + Sync>() {}
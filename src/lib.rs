@@ -5,7 +5,8 @@
 //! This crate defines the **Lens** interface — what a Lens is, how it
 //! communicates, and how it streams events.
 //!
-//! Enable the `runtime` feature for discovery and dynamic loading.
+//! Enable the `runtime` feature for discovery, dynamic loading, and running
+//! lenses (executor, scheduler, pipelines, and execution profiles).
 //!
 //! # Quick Start
 //!
@@ -27,44 +28,138 @@
 //! }
 //! ```
 
+pub mod abi;
+pub mod cancel;
 pub mod context;
+pub mod credentials;
+pub mod cron;
 pub mod error;
 pub mod events;
 pub mod lens;
+pub mod limits;
 pub mod manifest;
 pub mod mcp_server;
 pub mod oauth;
 pub mod output_spec;
+pub mod report;
+pub mod sandbox;
+pub mod schema;
+pub mod security;
 pub mod streaming;
+pub mod testing;
 
+#[cfg(feature = "runtime")]
+pub mod artifacts;
 #[cfg(feature = "runtime")]
 pub mod discovery;
 #[cfg(feature = "runtime")]
+pub mod executor;
+#[cfg(feature = "runtime")]
+pub mod history;
+#[cfg(feature = "runtime")]
+pub mod install;
+#[cfg(feature = "runtime")]
 pub mod loader;
+#[cfg(feature = "runtime")]
+pub mod lockfile;
+#[cfg(feature = "runtime")]
+pub mod mcp_client;
+#[cfg(feature = "runtime")]
+pub mod package;
+#[cfg(feature = "runtime")]
+pub mod pipeline;
+#[cfg(feature = "runtime")]
+pub mod profile;
+#[cfg(feature = "runtime")]
+pub mod registry;
+#[cfg(feature = "runtime")]
+pub mod scheduler;
+#[cfg(feature = "runtime")]
+pub mod subprocess;
 
-pub use context::{LensContext, LensResult, ToolCaller};
+pub use abi::{BuildInfo, LENS_ENTRY_POINT};
+pub use cancel::CancellationToken;
+pub use context::{
+    HostInfo, Initiator, LensContext, LensResult, RetryPolicy, RetryingToolCaller,
+    SandboxedToolCaller, ScopedToolCaller, ToolCaller, ToolResultChunk, ToolResultStream,
+};
+pub use credentials::{Credential, CredentialError, CredentialsBroker};
+pub use cron::CronSchedule;
 pub use error::{LensError, Result};
-pub use events::LensEvent;
-pub use lens::Lens;
+pub use events::{LensEvent, EVENT_SCHEMA_VERSION};
+pub use lens::{execute_catching_panics, Lens};
+pub use limits::LimitedLens;
 pub use manifest::{
-    LensDependency, LensManifest, LensMetadata, LensSurface, MessageType, Permission, SandboxLevel,
-    SecurityConfig,
+    current_platform, Branding, CacheConfig, EnvRequirements, EnvVar, HookEvent, LensDependency,
+    LensEntry, LensEntryType, LensExample, LensHook, LensManifest, LensMetadata, LensSurface,
+    LensTrigger, LocalizedStrings, ManifestSignature, MessageType, MissedRunPolicy,
+    OAuthProviderRequirement, OverlapPolicy, Permission, ResourceLimits, SandboxLevel,
+    SecurityConfig, ToolAccess, TriggerType, FRAMEWORK_VERSION,
 };
+#[cfg(feature = "macros")]
+pub use mcp_server::{mcp_tool, mcp_tools};
 pub use mcp_server::{
-    McpContent, McpPropertySchema, McpServerLens, McpTool, McpToolBuilder, McpToolResponse,
-    McpToolSchema,
+    McpAggregator, McpContent, McpPropertySchema, McpServerLens, McpTool, McpToolAnnotations,
+    McpToolBuilder, McpToolResponse, McpToolSchema, SandboxAuthorizer, ToolAuthorizer,
 };
-pub use oauth::{OAuthBroker, OAuthError, OAuthToken};
+pub use oauth::{CachingOAuthBroker, OAuthAccount, OAuthBroker, OAuthError, OAuthToken};
 pub use output_spec::{
-    InteractivityMode, LensOutputSpec, OutputDefinition, OutputErrorMode, RenderBlock,
-    RenderBlockType, OUTPUT_SPEC_FILENAME,
+    BlockOptions, Condition, FormField, FormFieldType, FormSpec, FormSubmission, InteractivityMode,
+    LensOutputSpec, OutputDefinition, OutputErrorMode, RenderBlock, RenderBlockType,
+    OUTPUT_SPEC_FILENAME,
+};
+pub use report::{render_preview, ReportFormat, RunMetrics, RunReport};
+pub use sandbox::{
+    AuditEvent, FsAccess, FsGuard, NetworkGuard, PermissionDecision, PermissionPrompter,
+    PermissionStore,
 };
-pub use streaming::{LensEventStream, StreamingLens};
+pub use security::{KeyPins, PinnedKey, TRUSTED_KEYS_FILENAME};
+pub use streaming::{EventEmitter, LensEventStream, StreamingLens};
 
+#[cfg(feature = "runtime")]
+pub use artifacts::{ArtifactRecord, ArtifactStore, ARTIFACT_INDEX_FILENAME};
 #[cfg(feature = "runtime")]
 pub use discovery::{
-    load_manifest, load_output_spec, parse_lens_uri, DiscoveredLens, LensDiscovery, LENS_DIR,
-    LENS_URI_PREFIX, MANIFEST_FILENAME,
+    check_auth_requirements, find_project_lenses_dir, load_manifest, load_output_spec,
+    parse_lens_uri, resolve_load_order, ConsistencyIssue, DependencyError, DependencyIssue,
+    DiscoveredLens, DiscoveryEvent, LensDiscovery, LensWatchStream, MissingConnection,
+    DISABLED_MARKER, LENS_DIR, LENS_URI_PREFIX, MANIFEST_FILENAME,
 };
 #[cfg(feature = "runtime")]
-pub use loader::{LensLoader, LoadedLens, LENS_ENTRY_POINT};
+pub use executor::{
+    ExecutionHandle, LensExecutor, RunEvent, RunEventStream, RunOutcome, RunRequest,
+};
+#[cfg(feature = "runtime")]
+pub use history::{RunHistory, HISTORY_EVENTS_FILENAME};
+#[cfg(feature = "runtime")]
+pub use install::{InstallEvent, LensInstaller, UninstallReport, LENS_ARCHIVE_EXTENSION};
+#[cfg(feature = "legacy-abi")]
+pub use loader::LEGACY_ENTRY_POINT;
+#[cfg(feature = "runtime")]
+pub use loader::{
+    LazyLoadedLens, LensCapabilities, LensLoader, LoadedLens, SecurityPolicy, DEFAULT_LOAD_TIMEOUT,
+};
+#[cfg(feature = "runtime")]
+pub use lockfile::{LensFetcher, LockMismatch, LockedLens, Lockfile, LOCKFILE_FILENAME};
+#[cfg(feature = "runtime")]
+pub use mcp_client::StdioToolCaller;
+#[cfg(feature = "runtime")]
+pub use oauth::FileOAuthBroker;
+#[cfg(feature = "runtime")]
+pub use package::{LensPackager, PackageMetadata};
+#[cfg(feature = "runtime")]
+pub use pipeline::{LensPipeline, PipelineHandle, PipelineResult, StepCheckpoint};
+#[cfg(feature = "runtime")]
+pub use profile::{CheckpointPolicy, ExecutionProfile, TaggedEvent};
+#[cfg(feature = "runtime")]
+pub use registry::{LensRegistry, RegistryEvent};
+#[cfg(feature = "runtime")]
+pub use scheduler::{LensScheduler, ScheduledRun};
+#[cfg(feature = "runtime")]
+pub use subprocess::{SubprocessLens, SUBPROCESS_PROTOCOL_VERSION};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use async_trait;
+    pub use serde_json;
+}
@@ -0,0 +1,265 @@
+//! # Resource Limits
+//!
+//! [`LimitedLens`] enforces a manifest's `[limits]` around any lens. A run
+//! that outlives `max_execution_secs` has its `ctx.cancellation` cancelled,
+//! so work it spawned can stop too, and fails with
+//! [`LensError::ResourceLimitExceeded`]; a streaming run ends with a
+//! `Failed` event carrying the `ResourceLimitExceeded` code.
+//!
+//! `max_memory_mb` can only be enforced on a lens running in its own
+//! process, so `SubprocessLens` applies it to its child (`runtime`
+//! feature). An in-process lens shares the host's allocator.
+//!
+//! ```rust,ignore
+//! let limits = discovered.manifest.resource_limits();
+//! let lens = LimitedLens::new(Arc::new(loaded), limits);
+//! let result = lens.execute(ctx).await;
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::StreamExt;
+
+use crate::error::{LensError, Result};
+use crate::manifest::ResourceLimits;
+use crate::streaming::{LensEventStream, StreamingLens};
+use crate::{Lens, LensContext, LensEvent, LensResult};
+
+/// A lens run within its declared [`ResourceLimits`]
+#[derive(Debug)]
+pub struct LimitedLens<L: ?Sized> {
+    inner: Arc<L>,
+    limits: ResourceLimits,
+}
+
+impl<L: ?Sized> Clone for LimitedLens<L> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            limits: self.limits.clone(),
+        }
+    }
+}
+
+impl<L: Lens + ?Sized> LimitedLens<L> {
+    /// Enforce `limits` on every run of `inner`
+    pub fn new(inner: Arc<L>, limits: ResourceLimits) -> Self {
+        Self { inner, limits }
+    }
+
+    /// The wrapped lens
+    pub fn inner(&self) -> &Arc<L> {
+        &self.inner
+    }
+
+    /// Limits applied to each run
+    pub fn limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
+
+    fn timed_out(&self, budget: Duration) -> LensError {
+        LensError::ResourceLimitExceeded(format!(
+            "Lens '{}' exceeded its execution time limit of {:?}",
+            self.inner.id(),
+            budget
+        ))
+    }
+}
+
+#[async_trait]
+impl<L: Lens + ?Sized> Lens for LimitedLens<L> {
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn version(&self) -> &str {
+        self.inner.version()
+    }
+
+    fn supports_mcp(&self) -> bool {
+        self.inner.supports_mcp()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+        let Some(budget) = self.limits.max_execution_time() else {
+            return self.inner.execute(ctx).await;
+        };
+        let cancel = ctx.cancellation.clone();
+        tokio::time::timeout(budget, self.inner.execute(ctx))
+            .await
+            .map_err(|_| {
+                cancel.cancel();
+                self.timed_out(budget)
+            })?
+    }
+}
+
+#[async_trait]
+impl<L: StreamingLens + ?Sized> StreamingLens for LimitedLens<L> {
+    /// The budget covers both producing the result and draining the event
+    /// stream; events still pending at the deadline are replaced by a
+    /// `Failed` event.
+    async fn execute_streaming(&self, ctx: LensContext) -> Result<(LensResult, LensEventStream)> {
+        let Some(budget) = self.limits.max_execution_time() else {
+            return self.inner.execute_streaming(ctx).await;
+        };
+        let deadline = Instant::now() + budget;
+        let lens_id = self.id().to_string();
+        let error = self.timed_out(budget);
+        let cancel = ctx.cancellation.clone();
+
+        let (result, mut stream) =
+            match tokio::time::timeout_at(deadline, self.inner.execute_streaming(ctx)).await {
+                Ok(outcome) => outcome?,
+                Err(_) => {
+                    cancel.cancel();
+                    let failed = LensEvent::failed_with_error(&lens_id, &error);
+                    return Ok((
+                        LensResult::failure(error.to_string()),
+                        Box::pin(tokio_stream::iter([failed])),
+                    ));
+                }
+            };
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                match tokio::time::timeout_at(deadline, stream.next()).await {
+                    Ok(Some(event)) => {
+                        if tx.send(event).is_err() {
+                            return;
+                        }
+                    }
+                    Ok(None) => return,
+                    Err(_) => {
+                        cancel.cancel();
+                        let _ = tx.send(LensEvent::failed_with_error(&lens_id, &error));
+                        return;
+                    }
+                }
+            }
+        });
+        Ok((result, Box::pin(UnboundedReceiverStream::new(rx))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancel::CancellationToken;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    struct SleepyLens;
+
+    #[async_trait]
+    impl Lens for SleepyLens {
+        fn id(&self) -> &str {
+            "sleepy"
+        }
+
+        fn name(&self) -> &str {
+            "Sleepy"
+        }
+
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+
+        async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+            let secs = ctx.input["sleep_secs"].as_u64().unwrap_or(0);
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+            Ok(LensResult::success(json!({ "slept": secs })))
+        }
+    }
+
+    #[async_trait]
+    impl StreamingLens for SleepyLens {
+        async fn execute_streaming(
+            &self,
+            ctx: LensContext,
+        ) -> Result<(LensResult, LensEventStream)> {
+            let secs = ctx.input["sleep_secs"].as_u64().unwrap_or(0);
+            let (tx, rx) = mpsc::unbounded_channel();
+            tokio::spawn(async move {
+                let _ = tx.send(LensEvent::started("sleepy", "sleeping"));
+                tokio::time::sleep(Duration::from_secs(secs)).await;
+                let _ = tx.send(LensEvent::completed("sleepy", Duration::from_secs(secs)));
+            });
+            Ok((
+                LensResult::success(json!({})),
+                Box::pin(UnboundedReceiverStream::new(rx)),
+            ))
+        }
+    }
+
+    fn ctx(sleep_secs: u64) -> LensContext {
+        LensContext::new(PathBuf::from("/tmp"), json!({ "sleep_secs": sleep_secs }))
+    }
+
+    fn limited(max_execution_secs: Option<u64>) -> LimitedLens<SleepyLens> {
+        LimitedLens::new(
+            Arc::new(SleepyLens),
+            ResourceLimits {
+                max_execution_secs,
+                ..ResourceLimits::default()
+            },
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_execution_time_limit() {
+        let result = limited(Some(5)).execute(ctx(1)).await.unwrap();
+        assert_eq!(result.output["slept"], 1);
+        limited(None).execute(ctx(60)).await.unwrap();
+
+        let token = CancellationToken::new();
+        let err = limited(Some(5))
+            .execute(ctx(60).with_cancellation(token.clone()))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, LensError::ResourceLimitExceeded(_)),
+            "{:?}",
+            err
+        );
+        assert!(err.to_string().contains("'sleepy'"));
+        assert!(token.is_cancelled());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_streaming_time_limit_ends_with_failed_event() {
+        let (_, stream) = limited(Some(5)).execute_streaming(ctx(1)).await.unwrap();
+        let events: Vec<_> = stream.collect().await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].event_type(), "Completed");
+
+        let token = CancellationToken::new();
+        let (_, stream) = limited(Some(5))
+            .execute_streaming(ctx(60).with_cancellation(token.clone()))
+            .await
+            .unwrap();
+        let events: Vec<_> = stream.collect().await;
+        assert!(token.is_cancelled());
+        assert_eq!(events.len(), 2);
+        match &events[1] {
+            LensEvent::Failed { code, .. } => {
+                assert_eq!(code.as_deref(), Some("ResourceLimitExceeded"))
+            }
+            other => panic!("Expected Failed event, got {:?}", other),
+        }
+    }
+}
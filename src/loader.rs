@@ -4,7 +4,9 @@
 //!
 //! Requires the `runtime` feature.
 //!
-//! Lenses expose a C ABI entry point that returns a boxed Lens trait object:
+//! Lenses export a `#[repr(C)]` vtable through [`export_lens!`](crate::export_lens)
+//! (see [`crate::abi`]), so they load regardless of the rustc version
+//! either side was built with:
 //!
 //! ```rust,ignore
 //! use lens::export_lens;
@@ -14,37 +16,83 @@
 //!
 //! export_lens!(MyLens::new());
 //! ```
+//!
+//! Lenses built against the old `create_lens` entry point, which returns a
+//! `*mut dyn Lens` and only works when lens and host share a compiler,
+//! still load with the `legacy-abi` feature.
 
 use std::ffi::OsStr;
-use std::path::Path;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use libloading::{Library, Symbol};
+use libloading::Library;
 
+use crate::abi::{
+    BuildInfo, BuildInfoFn, LensEntryFn, McpEntryFn, StreamingEntryFn, BUILD_INFO_SYMBOL,
+    LENS_ABI_VERSION, MCP_ENTRY_POINT, STREAMING_ENTRY_POINT,
+};
+use crate::discovery::DiscoveredLens;
 use crate::error::{LensError, Result};
-use crate::lens::Lens;
+use crate::lens::{execute_catching_panics, Lens};
+use crate::manifest::{LensEntryType, LensManifest};
+use crate::mcp_server::McpServerLens;
+use crate::streaming::StreamingLens;
+use crate::{LensContext, LensResult};
+
+pub use crate::abi::LENS_ENTRY_POINT;
+pub use lazy::LazyLoadedLens;
+pub use policy::SecurityPolicy;
+pub use probe::LensCapabilities;
+
+use abi_lens::AbiLens;
 
-/// Function signature for lens entry point
+mod abi_lens;
+mod lazy;
+mod policy;
+mod probe;
+
+/// Function signature for the legacy lens entry point
+#[cfg(feature = "legacy-abi")]
 #[allow(improper_ctypes_definitions)]
 type CreateLensFn = unsafe extern "C" fn() -> *mut dyn Lens;
 
-/// Entry point function name that lenses must export
-pub const LENS_ENTRY_POINT: &[u8] = b"create_lens";
+/// Entry point name of lenses built with [`export_lens_legacy!`](crate::export_lens_legacy)
+#[cfg(feature = "legacy-abi")]
+pub const LEGACY_ENTRY_POINT: &[u8] = b"create_lens";
+
+/// The lens inside a [`LoadedLens`]
+enum LensHandle {
+    /// Loaded through the stable ABI, possibly with capability entry points
+    Abi(AbiLens),
+    /// Loaded through the legacy trait-object entry point
+    #[cfg_attr(not(feature = "legacy-abi"), allow(dead_code))]
+    Boxed(Box<dyn Lens>),
+}
 
 /// A loaded lens with its library handle
 pub struct LoadedLens {
     /// The lens instance
-    lens: Box<dyn Lens>,
+    lens: LensHandle,
+    /// How the library was built, when it says
+    build_info: Option<BuildInfo>,
     /// Library handle (must be kept alive while lens is in use)
     _library: Arc<Library>,
+    /// Counted by [`LensLoader::handle_count`]; the library itself has
+    /// other owners, such as the lens's own vtable
+    _handle: Arc<()>,
 }
 
 impl std::fmt::Debug for LoadedLens {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("LoadedLens")
-            .field("id", &self.lens.id())
-            .field("name", &self.lens.name())
-            .field("version", &self.lens.version())
+            .field("id", &self.id())
+            .field("name", &self.name())
+            .field("version", &self.version())
+            .field("streaming", &self.as_streaming().is_some())
+            .field("mcp", &self.as_mcp().is_some())
+            .field("build_info", &self.build_info)
             .finish()
     }
 }
@@ -52,35 +100,85 @@ impl std::fmt::Debug for LoadedLens {
 impl LoadedLens {
     /// Get reference to the lens
     pub fn plugin(&self) -> &dyn Lens {
-        self.lens.as_ref()
+        match &self.lens {
+            LensHandle::Abi(lens) => lens,
+            LensHandle::Boxed(lens) => lens.as_ref(),
+        }
     }
 
     /// Get mutable reference to the lens
     pub fn plugin_mut(&mut self) -> &mut dyn Lens {
-        self.lens.as_mut()
+        match &mut self.lens {
+            LensHandle::Abi(lens) => lens,
+            LensHandle::Boxed(lens) => lens.as_mut(),
+        }
+    }
+
+    /// The lens as a [`StreamingLens`], if it was exported with
+    /// `export_lens!(..., streaming)`
+    pub fn as_streaming(&self) -> Option<&dyn StreamingLens> {
+        match &self.lens {
+            LensHandle::Abi(lens) if lens.is_streaming() => Some(lens),
+            _ => None,
+        }
+    }
+
+    /// The lens as an [`McpServerLens`], if it was exported with
+    /// `export_lens!(..., mcp)`
+    pub fn as_mcp(&self) -> Option<&dyn McpServerLens> {
+        match &self.lens {
+            LensHandle::Abi(lens) if lens.is_mcp() => Some(lens),
+            _ => None,
+        }
     }
 
     /// Get the lens ID
     pub fn id(&self) -> &str {
-        self.lens.id()
+        self.plugin().id()
     }
 
     /// Get the lens name
     pub fn name(&self) -> &str {
-        self.lens.name()
+        self.plugin().name()
     }
 
     /// Get the lens version
     pub fn version(&self) -> &str {
-        self.lens.version()
+        self.plugin().version()
+    }
+
+    /// How the library was built, if it exports `lens_build_info`
+    /// (libraries from before build info was added do not)
+    pub fn build_info(&self) -> Option<&BuildInfo> {
+        self.build_info.as_ref()
+    }
+
+    /// Execute the lens, reporting a panic inside it as
+    /// `LensError::ExecutionFailed` instead of unwinding into the host
+    pub async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+        execute_catching_panics(self.plugin(), ctx).await
     }
 }
 
+/// Default bound on opening a library and constructing its lens
+pub const DEFAULT_LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What a load was doing, for timeout errors
+type LoadStage = Arc<Mutex<&'static str>>;
+
 /// Dynamic lens loader
-#[derive(Default)]
 pub struct LensLoader {
-    /// Loaded libraries (kept alive to prevent unloading)
-    libraries: Vec<Arc<Library>>,
+    /// Loaded libraries by lens id (kept alive until `unload`), each with
+    /// the token cloned into its [`LoadedLens`] handles
+    libraries: Vec<(String, Arc<Library>, Arc<()>)>,
+    load_timeout: Option<Duration>,
+    security_policy: SecurityPolicy,
+}
+
+impl Default for LensLoader {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LensLoader {
@@ -88,22 +186,62 @@ impl LensLoader {
     pub fn new() -> Self {
         Self {
             libraries: Vec::new(),
+            load_timeout: Some(DEFAULT_LOAD_TIMEOUT),
+            security_policy: SecurityPolicy::default(),
         }
     }
 
+    /// Verify every library against `policy` right before opening it
+    /// (builder pattern)
+    pub fn with_security_policy(mut self, policy: SecurityPolicy) -> Self {
+        self.security_policy = policy;
+        self
+    }
+
+    /// Bound opening a library, its entry point, and the first probe call
+    /// into the lens by `timeout` (builder pattern). Defaults to
+    /// [`DEFAULT_LOAD_TIMEOUT`].
+    ///
+    /// A load that times out fails with `LensError::Initialization`; the
+    /// stuck library call cannot be interrupted and is left running on its
+    /// own thread.
+    pub fn with_load_timeout(mut self, timeout: Duration) -> Self {
+        self.load_timeout = Some(timeout);
+        self
+    }
+
+    /// Wait for loads however long they take (builder pattern)
+    pub fn without_load_timeout(mut self) -> Self {
+        self.load_timeout = None;
+        self
+    }
+
     /// Load a lens from a shared library path
     ///
     /// # Safety
     ///
     /// This function is unsafe because:
     /// - It loads arbitrary code from the filesystem
-    /// - The lens must be compiled with a compatible Rust version
-    /// - The lens must properly implement the create_lens function
+    /// - The lens must export a valid `lens_abi_v1` entry point (or, with
+    ///   the `legacy-abi` feature, a `create_lens` built by the same rustc)
     ///
     /// Only load lenses from trusted sources.
+    ///
+    /// Without a manifest there is no declared hash to check, so a
+    /// [`SecurityPolicy`] requiring one rejects the library; use
+    /// [`load_discovered`](Self::load_discovered) instead.
     pub unsafe fn load<P: AsRef<OsStr>>(&mut self, library_path: P) -> Result<LoadedLens> {
-        let path = library_path.as_ref();
-        let path_buf = Path::new(path).to_path_buf();
+        self.load_verified(library_path.as_ref(), None)
+    }
+
+    /// Load a library, checking it against `manifest` and the security
+    /// policy immediately before opening it
+    unsafe fn load_verified(
+        &mut self,
+        library_path: &OsStr,
+        manifest: Option<&LensManifest>,
+    ) -> Result<LoadedLens> {
+        let path_buf = Path::new(library_path).to_path_buf();
 
         if !path_buf.exists() {
             return Err(LensError::LensNotFound(format!(
@@ -112,34 +250,207 @@ impl LensLoader {
             )));
         }
 
-        let library = Library::new(path).map_err(|e| {
-            LensError::Initialization(format!("Failed to load library {:?}: {}", path_buf, e))
+        let path = path_buf.clone();
+        let manifest = manifest.cloned();
+        let policy = self.security_policy.clone();
+        let (library, lens, build_info) =
+            with_load_timeout(self.load_timeout, &path_buf, move |stage| {
+                *stage.lock().unwrap() = "verifying the library";
+                let checked = policy::verify_library(&path, manifest.as_ref(), &policy)?;
+                // SAFETY: upheld by the caller of `load`
+                unsafe { Self::open(&path, &checked.path(), stage) }
+            })?;
+        let loaded = LoadedLens {
+            lens,
+            build_info,
+            _library: library,
+            _handle: Arc::new(()),
+        };
+        self.libraries.push((
+            loaded.id().to_string(),
+            Arc::clone(&loaded._library),
+            Arc::clone(&loaded._handle),
+        ));
+        Ok(loaded)
+    }
+
+    /// Open the library from `load_path` (see [`policy::CheckedLibrary`]),
+    /// construct its lens, and probe it once
+    unsafe fn open(path: &Path, load_path: &Path, stage: &LoadStage) -> Result<Opened> {
+        *stage.lock().unwrap() = "opening the library";
+        let library = Library::new(load_path).map_err(|e| {
+            LensError::Initialization(format!("Failed to load library {:?}: {}", path, e))
         })?;
 
         let library = Arc::new(library);
+        let build_info = read_build_info(&library);
+        let lens = Self::construct(&library, path, build_info.as_ref(), stage)
+            .map_err(|e| describe_build(e, build_info.as_ref()))?;
+        Ok((library, lens, build_info))
+    }
 
-        let create_lens: Symbol<CreateLensFn> = library.get(LENS_ENTRY_POINT).map_err(|e| {
-            LensError::Initialization(format!(
-                "Lens {:?} missing 'create_lens' entry point: {}",
-                path_buf, e
-            ))
-        })?;
+    unsafe fn construct(
+        library: &Arc<Library>,
+        path: &Path,
+        build_info: Option<&BuildInfo>,
+        stage: &LoadStage,
+    ) -> Result<LensHandle> {
+        // The stable entry point also probes the lens's metadata getters
+        *stage.lock().unwrap() = "in its entry point";
+        let lens = match library.get::<LensEntryFn>(LENS_ENTRY_POINT) {
+            Ok(entry) => {
+                let mut lens = AbiLens::new(entry(), Some(Arc::clone(library)))?;
+                if let Ok(streaming) = library.get::<StreamingEntryFn>(STREAMING_ENTRY_POINT) {
+                    lens = lens.with_streaming(streaming())?;
+                }
+                if let Ok(mcp) = library.get::<McpEntryFn>(MCP_ENTRY_POINT) {
+                    lens = lens.with_mcp(mcp())?;
+                }
+                LensHandle::Abi(lens)
+            }
+            #[cfg(feature = "legacy-abi")]
+            Err(_) => LensHandle::Boxed(Self::load_legacy(library, path, build_info)?),
+            #[cfg(not(feature = "legacy-abi"))]
+            Err(e) => {
+                let _ = build_info;
+                return Err(LensError::Initialization(format!(
+                    "Lens {:?} missing 'lens_abi_v1' entry point ({}); lenses built \
+                     with the old 'create_lens' entry point need the legacy-abi feature",
+                    path, e
+                )));
+            }
+        };
+
+        *stage.lock().unwrap() = "in its first call (id)";
+        if let LensHandle::Boxed(lens) = &lens {
+            let _ = lens.id();
+        }
+        Ok(lens)
+    }
+
+    /// Load a lens through the legacy `create_lens` trait-object entry point
+    #[cfg(feature = "legacy-abi")]
+    unsafe fn load_legacy(
+        library: &Library,
+        path: &Path,
+        build_info: Option<&BuildInfo>,
+    ) -> Result<Box<dyn Lens>> {
+        // Trait objects only line up when both sides share a compiler
+        let host = BuildInfo::current();
+        if let Some(info) = build_info.filter(|info| info.rustc_version != host.rustc_version) {
+            eprintln!(
+                "Warning: lens {:?} uses the legacy ABI and was built with {}, but the host was built with {}",
+                path, info.rustc_version, host.rustc_version
+            );
+        }
+
+        let create_lens: libloading::Symbol<CreateLensFn> =
+            library.get(LEGACY_ENTRY_POINT).map_err(|e| {
+                LensError::Initialization(format!(
+                    "Lens {:?} exports neither 'lens_abi_v1' nor 'create_lens': {}",
+                    path, e
+                ))
+            })?;
 
         let lens_ptr = create_lens();
 
         if lens_ptr.is_null() {
             return Err(LensError::Initialization(format!(
                 "Lens {:?} returned null from create_lens",
-                path_buf
+                path
             )));
         }
 
-        let lens = Box::from_raw(lens_ptr);
-        self.libraries.push(Arc::clone(&library));
+        Ok(Box::from_raw(lens_ptr))
+    }
 
-        Ok(LoadedLens {
-            lens,
-            _library: library,
+    /// Load a discovered lens from its declared (or conventional) library path
+    ///
+    /// # Safety
+    ///
+    /// Same safety requirements as `load`.
+    pub unsafe fn load_discovered(&mut self, lens: &DiscoveredLens) -> Result<LoadedLens> {
+        let library_path = discovered_library(lens)?;
+        self.load_verified(library_path.as_os_str(), Some(&lens.manifest))
+    }
+
+    /// Defer loading a discovered lens until it is first executed.
+    ///
+    /// The entry point and library path are checked now; the library is
+    /// opened later, under this loader's security policy and load timeout.
+    /// Lazily loaded libraries are not tracked by this loader.
+    ///
+    /// # Safety
+    ///
+    /// Same safety requirements as `load`, for whenever the lens is first used.
+    pub unsafe fn load_lazy(&self, lens: &DiscoveredLens) -> Result<LazyLoadedLens> {
+        discovered_library(lens)?;
+        Ok(LazyLoadedLens::new(
+            lens.clone(),
+            LensLoader {
+                libraries: Vec::new(),
+                load_timeout: self.load_timeout,
+                security_policy: self.security_policy.clone(),
+            },
+        ))
+    }
+
+    /// Report which entry points the library at `library_path` exports,
+    /// without constructing its lens.
+    ///
+    /// The library is verified against the security policy, opened, and
+    /// closed again; the load timeout applies.
+    ///
+    /// # Safety
+    ///
+    /// Opening a library runs its initializers; same requirements as `load`.
+    pub unsafe fn probe<P: AsRef<OsStr>>(&self, library_path: P) -> Result<LensCapabilities> {
+        self.probe_verified(library_path.as_ref(), None)
+    }
+
+    /// Probe a discovered lens's library and record the result in
+    /// [`DiscoveredLens::capabilities`]
+    ///
+    /// # Safety
+    ///
+    /// Same safety requirements as `probe`.
+    pub unsafe fn probe_discovered(&self, lens: &mut DiscoveredLens) -> Result<LensCapabilities> {
+        let library_path = lens.library_path.as_ref().ok_or_else(|| {
+            LensError::LensNotFound(format!(
+                "No compiled library found for lens '{}'",
+                lens.id()
+            ))
+        })?;
+        let capabilities = self.probe_verified(library_path.as_os_str(), Some(&lens.manifest))?;
+        lens.capabilities = Some(capabilities.clone());
+        Ok(capabilities)
+    }
+
+    unsafe fn probe_verified(
+        &self,
+        library_path: &OsStr,
+        manifest: Option<&LensManifest>,
+    ) -> Result<LensCapabilities> {
+        let path = Path::new(library_path).to_path_buf();
+        if !path.exists() {
+            return Err(LensError::LensNotFound(format!(
+                "Library not found: {:?}",
+                path
+            )));
+        }
+
+        let manifest = manifest.cloned();
+        let policy = self.security_policy.clone();
+        with_load_timeout(self.load_timeout, &path.clone(), move |stage| {
+            *stage.lock().unwrap() = "verifying the library";
+            let checked = policy::verify_library(&path, manifest.as_ref(), &policy)?;
+            *stage.lock().unwrap() = "opening the library";
+            // SAFETY: upheld by the caller of `probe`
+            let library = unsafe { Library::new(checked.path()) }.map_err(|e| {
+                LensError::Initialization(format!("Failed to load library {:?}: {}", path, e))
+            })?;
+            *stage.lock().unwrap() = "reporting its capabilities";
+            Ok(unsafe { probe::probe_library(&library) })
         })
     }
 
@@ -159,34 +470,164 @@ impl LensLoader {
     pub fn loaded_count(&self) -> usize {
         self.libraries.len()
     }
+
+    /// Whether a library for `lens_id` is loaded
+    pub fn is_loaded(&self, lens_id: &str) -> bool {
+        self.libraries.iter().any(|(id, _, _)| id == lens_id)
+    }
+
+    /// Number of [`LoadedLens`] handles still alive for `lens_id`
+    pub fn handle_count(&self, lens_id: &str) -> usize {
+        self.libraries
+            .iter()
+            .filter(|(id, _, _)| id == lens_id)
+            .map(|(_, _, handles)| Arc::strong_count(handles) - 1)
+            .sum()
+    }
+
+    /// Release the libraries loaded for `lens_id`.
+    ///
+    /// Refuses while any [`LoadedLens`] for it is alive (including clones
+    /// of a [`load_shared`](Self::load_shared) handle held by running
+    /// executions), since its code would be unmapped under them. Drop
+    /// those handles first.
+    pub fn unload(&mut self, lens_id: &str) -> Result<()> {
+        if !self.is_loaded(lens_id) {
+            return Err(LensError::LensNotFound(format!(
+                "Lens '{}' is not loaded",
+                lens_id
+            )));
+        }
+        let handles = self.handle_count(lens_id);
+        if handles > 0 {
+            return Err(LensError::Other(format!(
+                "Lens '{}' is still in use ({} live handle{})",
+                lens_id,
+                handles,
+                if handles == 1 { "" } else { "s" }
+            )));
+        }
+        // Dropping the last reference closes the library
+        self.libraries.retain(|(id, _, _)| id != lens_id);
+        Ok(())
+    }
 }
 
-/// Macro to generate the lens entry point
-///
-/// # Example
-///
-/// ```rust,ignore
-/// use lens::export_lens;
-///
-/// struct MyLens { /* ... */ }
-/// impl Lens for MyLens { /* ... */ }
+/// Library handle, its lens, and its build info
+type Opened = (Arc<Library>, LensHandle, Option<BuildInfo>);
+
+/// The library of a discovered dylib lens, refusing other entry types and
+/// libraries already known not to match their declared hash
+fn discovered_library(lens: &DiscoveredLens) -> Result<&PathBuf> {
+    if lens.entry_type() != LensEntryType::Dylib {
+        return Err(LensError::Initialization(format!(
+            "Lens '{}' declares a {:?} entry point; the dynamic library loader only loads dylib entries",
+            lens.id(),
+            lens.entry_type()
+        )));
+    }
+
+    let library_path = lens.library_path.as_ref().ok_or_else(|| {
+        LensError::LensNotFound(format!(
+            "No compiled library found for lens '{}'",
+            lens.id()
+        ))
+    })?;
+    if lens.hash_verified() == Some(false) {
+        return Err(LensError::PermissionDenied(format!(
+            "Library for lens '{}' does not match its declared library_hash",
+            lens.id()
+        )));
+    }
+
+    Ok(library_path)
+}
+
+/// Build info exported by `library`, if it has any in a known layout
+unsafe fn read_build_info(library: &Library) -> Option<BuildInfo> {
+    let build_info = library.get::<BuildInfoFn>(BUILD_INFO_SYMBOL).ok()?;
+    let build_info = build_info();
+    (build_info.abi_version == LENS_ABI_VERSION).then(|| build_info.to_owned())
+}
+
+/// Add how the library and host were built to an initialization error
+fn describe_build(err: LensError, build_info: Option<&BuildInfo>) -> LensError {
+    let host = BuildInfo::current();
+    match err {
+        LensError::Initialization(message) => LensError::Initialization(match build_info {
+            Some(info) => format!(
+                "{} (library: {}; host: {} for {})",
+                message, info, host.rustc_version, host.target
+            ),
+            None => format!(
+                "{} (library exports no build info; host: {} for {})",
+                message, host.rustc_version, host.target
+            ),
+        }),
+        other => other,
+    }
+}
+
+/// Run `load` on its own thread, giving up after `timeout`
+fn with_load_timeout<T, F>(timeout: Option<Duration>, path: &Path, load: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&LoadStage) -> Result<T> + Send + 'static,
+{
+    let Some(timeout) = timeout else {
+        return load(&LoadStage::default());
+    };
+
+    let stage = LoadStage::default();
+    let (tx, rx) = std::sync::mpsc::channel();
+    let thread_stage = Arc::clone(&stage);
+    std::thread::Builder::new()
+        .name("lens-load".to_string())
+        .spawn(move || {
+            let _ = tx.send(load(&thread_stage));
+        })?;
+
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(LensError::Initialization(format!(
+            "Lens {:?} timed out after {:?} {}",
+            path,
+            timeout,
+            stage.lock().unwrap()
+        ))),
+        Err(RecvTimeoutError::Disconnected) => Err(LensError::Initialization(format!(
+            "Lens {:?} panicked while loading",
+            path
+        ))),
+    }
+}
+
+/// Macro to generate the legacy `create_lens` entry point, which returns a
+/// Rust trait object and only loads into hosts built with the same rustc.
 ///
-/// export_lens!(MyLens::new());
-/// ```
+/// Prefer [`export_lens!`](crate::export_lens).
+#[cfg(feature = "legacy-abi")]
 #[macro_export]
-macro_rules! export_lens {
+macro_rules! export_lens_legacy {
     ($constructor:expr) => {
         #[no_mangle]
         pub extern "C" fn create_lens() -> *mut dyn $crate::Lens {
             let lens: Box<dyn $crate::Lens> = Box::new($constructor);
             Box::into_raw(lens)
         }
+
+        #[no_mangle]
+        pub extern "C" fn lens_build_info() -> $crate::abi::LensBuildInfo {
+            $crate::abi::LensBuildInfo::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+        }
     };
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::abi::{LensVTable, LENS_ABI_VERSION};
+    use async_trait::async_trait;
 
     #[test]
     fn test_loader_new() {
@@ -194,6 +635,339 @@ mod tests {
         assert_eq!(loader.loaded_count(), 0);
     }
 
+    #[test]
+    fn test_load_discovered_rejects_non_dylib_entries() {
+        let manifest = crate::manifest::LensManifest::from_toml(
+            r#"
+[lens]
+id = "script"
+name = "Script"
+version = "1.0.0"
+entry = { type = "wasm", path = "lens.wasm" }
+"#,
+        )
+        .unwrap();
+        let mut lens = DiscoveredLens::embedded(&EchoLens);
+        lens.manifest = manifest;
+        lens.path = "/tmp/script".into();
+        lens.manifest_path = "/tmp/script/lens.toml".into();
+        lens.entry_path = Some("/tmp/script/lens.wasm".into());
+
+        let mut loader = LensLoader::new();
+        let err = unsafe { loader.load_discovered(&lens) }.unwrap_err();
+        assert!(err.to_string().contains("Wasm entry point"));
+        let err = unsafe { loader.load_lazy(&lens) }.unwrap_err();
+        assert!(err.to_string().contains("Wasm entry point"));
+    }
+
+    #[tokio::test]
+    async fn test_lazy_load_defers_opening() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let library_path = temp_dir.path().join("liblazy.so");
+        let mut lens = DiscoveredLens::embedded(&EchoLens);
+        lens.manifest = crate::manifest::LensManifest::from_toml(
+            "[lens]\nid = \"lazy\"\nname = \"Lazy\"\nversion = \"0.1.0\"\ndescription = \"Opens late\"\n",
+        )
+        .unwrap();
+        lens.path = temp_dir.path().to_path_buf();
+        lens.manifest_path = temp_dir.path().join("lens.toml");
+        lens.library_path = Some(library_path.clone());
+
+        // Nothing is read until the first execution
+        let lazy = unsafe { LensLoader::new().load_lazy(&lens) }.unwrap();
+        assert_eq!(
+            (lazy.id(), lazy.name(), lazy.version(), lazy.description()),
+            ("lazy", "Lazy", "0.1.0", "Opens late")
+        );
+        assert!(!lazy.is_loaded());
+
+        let ctx = || LensContext::new(temp_dir.path().to_path_buf(), serde_json::json!({}));
+        let err = lazy.execute(ctx()).await.unwrap_err();
+        assert!(matches!(err, LensError::LensNotFound(_)));
+
+        // A failed load is retried
+        std::fs::write(&library_path, "not a library").unwrap();
+        let err = lazy.execute(ctx()).await.unwrap_err();
+        assert!(err.to_string().contains("Failed to load library"));
+        assert!(!lazy.is_loaded());
+    }
+
+    struct EchoLens;
+
+    #[async_trait]
+    impl Lens for EchoLens {
+        fn id(&self) -> &str {
+            "echo"
+        }
+
+        fn name(&self) -> &str {
+            "Echo"
+        }
+
+        fn version(&self) -> &str {
+            "1.2.3"
+        }
+
+        async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+            if let Some(message) = ctx.input.get("panic").and_then(|v| v.as_str()) {
+                panic!("{}", message);
+            }
+            match ctx.input.get("fail") {
+                Some(_) => Err(LensError::InvalidInput("asked to fail".to_string())),
+                None => Ok(LensResult::success(ctx.input)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_abi_lens_round_trip() {
+        let lens = unsafe { AbiLens::new(LensVTable::new(EchoLens), None) }.unwrap();
+        assert_eq!(
+            (lens.id(), lens.name(), lens.version()),
+            ("echo", "Echo", "1.2.3")
+        );
+        assert!(!lens.supports_mcp());
+
+        let input = serde_json::json!({"text": "hi"});
+        let result = lens
+            .execute(LensContext::new("/tmp".into(), input.clone()))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, input);
+
+        let err = lens
+            .execute(LensContext::new(
+                "/tmp".into(),
+                serde_json::json!({"fail": true}),
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid lens input: asked to fail");
+
+        let err = lens
+            .execute(LensContext::new(
+                "/tmp".into(),
+                serde_json::json!({"panic": "at the disco"}),
+            ))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LensError::ExecutionFailed(_)));
+        assert!(err
+            .to_string()
+            .ends_with("Lens 'echo' panicked: at the disco"));
+
+        let vtable = LensVTable::catching(|| -> EchoLens { panic!("no config") });
+        assert!(vtable.instance.is_null());
+        let err = unsafe { AbiLens::new(vtable, None) }.err().unwrap();
+        assert!(err.to_string().contains("panicked while being constructed"));
+
+        let mut vtable = LensVTable::new(EchoLens);
+        vtable.abi_version = LENS_ABI_VERSION + 1;
+        assert!(unsafe { AbiLens::new(vtable, None) }.is_err());
+    }
+
+    #[async_trait]
+    impl StreamingLens for EchoLens {
+        async fn execute_streaming(
+            &self,
+            ctx: LensContext,
+        ) -> Result<(LensResult, crate::streaming::LensEventStream)> {
+            let events = vec![
+                crate::LensEvent::started("echo", "echoing"),
+                crate::LensEvent::progress("echo", "echoed"),
+            ];
+            let result = self.execute(ctx).await?;
+            Ok((result, Box::pin(tokio_stream::iter(events))))
+        }
+    }
+
+    #[async_trait]
+    impl McpServerLens for EchoLens {
+        fn mcp_tools(&self) -> Vec<crate::mcp_server::McpTool> {
+            vec![crate::mcp_server::McpTool::builder("shout").build()]
+        }
+
+        async fn call_tool(
+            &self,
+            name: &str,
+            params: serde_json::Value,
+        ) -> Result<crate::mcp_server::McpToolResponse> {
+            match name {
+                "shout" => Ok(crate::mcp_server::McpToolResponse::text(
+                    params["text"].as_str().unwrap_or_default().to_uppercase(),
+                )),
+                _ => Err(LensError::InvalidInput(format!("unknown tool {}", name))),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_abi_lens_capabilities() {
+        use crate::abi::{McpVTable, StreamingVTable};
+        use tokio_stream::StreamExt;
+
+        let lens = unsafe { AbiLens::new(LensVTable::new(EchoLens), None) }.unwrap();
+        assert!(!lens.is_streaming() && !lens.is_mcp());
+        let input = serde_json::json!({"text": "hi"});
+
+        // Without the streaming table, streaming falls back to execute
+        let (result, events) = lens
+            .execute_streaming(LensContext::new("/tmp".into(), input.clone()))
+            .await
+            .unwrap();
+        assert_eq!(result.output, input);
+        assert_eq!(events.collect::<Vec<_>>().await.len(), 0);
+        assert!(lens.call_tool("shout", input.clone()).await.is_err());
+
+        let lens = unsafe {
+            lens.with_streaming(StreamingVTable::new(|| EchoLens))
+                .unwrap()
+                .with_mcp(McpVTable::new(|| EchoLens))
+                .unwrap()
+        };
+        assert!(lens.is_streaming() && lens.supports_mcp());
+
+        let (result, events) = lens
+            .execute_streaming(LensContext::new("/tmp".into(), input.clone()))
+            .await
+            .unwrap();
+        assert_eq!(result.output, input);
+        let events: Vec<_> = events.map(|event| event.event_type()).collect().await;
+        assert_eq!(events, ["Started", "Progress"]);
+
+        let tools = lens.mcp_tools();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "shout");
+        assert_eq!(lens.mcp_server_name(), "graphyn-echo");
+        let response = lens.call_tool("shout", input).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::to_value(crate::mcp_server::McpToolResponse::text("HI")).unwrap()
+        );
+        let err = lens
+            .call_tool("whisper", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown tool whisper"));
+
+        let mut table = StreamingVTable::new(|| EchoLens);
+        table.abi_version = LENS_ABI_VERSION + 1;
+        let lens = unsafe { AbiLens::new(LensVTable::new(EchoLens), None) }.unwrap();
+        assert!(unsafe { lens.with_streaming(table) }.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unload_refuses_while_in_use() {
+        let library = Arc::new(Library::from(libloading::os::unix::Library::this()));
+        let mut loader = LensLoader::new();
+        let handles = Arc::new(());
+        loader.libraries.push((
+            "echo".to_string(),
+            Arc::clone(&library),
+            Arc::clone(&handles),
+        ));
+        // ABI lenses hold the library too, which must not count as a handle
+        let lens = unsafe { AbiLens::new(LensVTable::new(EchoLens), Some(Arc::clone(&library))) };
+        let handle = Arc::new(LoadedLens {
+            lens: LensHandle::Abi(lens.unwrap()),
+            build_info: None,
+            _library: library,
+            _handle: handles,
+        });
+        let execution = Arc::clone(&handle);
+
+        assert!(loader.is_loaded("echo"));
+        assert_eq!(loader.handle_count("echo"), 1);
+        let err = loader.unload("echo").unwrap_err();
+        assert!(err.to_string().contains("still in use (1 live handle)"));
+
+        drop(handle);
+        assert!(loader.unload("echo").is_err());
+        drop(execution);
+        loader.unload("echo").unwrap();
+        assert_eq!(loader.loaded_count(), 0);
+        assert!(matches!(
+            loader.unload("echo"),
+            Err(LensError::LensNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_timeout_reports_stage() {
+        let path = Path::new("/tmp/libstuck.so");
+        let err = with_load_timeout(Some(Duration::from_millis(50)), path, |stage| {
+            *stage.lock().unwrap() = "in its entry point";
+            std::thread::sleep(Duration::from_secs(2));
+            Ok(())
+        })
+        .unwrap_err();
+        assert!(matches!(err, LensError::Initialization(_)));
+        assert!(err
+            .to_string()
+            .contains("timed out after 50ms in its entry point"));
+
+        let value = with_load_timeout(Some(Duration::from_secs(5)), path, |_| Ok(7)).unwrap();
+        assert_eq!(value, 7);
+        assert_eq!(with_load_timeout(None, path, |_| Ok(8)).unwrap(), 8);
+    }
+
+    #[test]
+    fn test_policy_checked_before_open() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("libunpinned.so");
+        std::fs::write(&path, "not a library").unwrap();
+
+        let mut loader = LensLoader::new().with_security_policy(SecurityPolicy {
+            require_hash: true,
+            ..SecurityPolicy::default()
+        });
+        let err = unsafe { loader.load(&path) }.unwrap_err();
+        assert!(matches!(err, LensError::PermissionDenied(_)), "{:?}", err);
+
+        // Without the policy the file reaches dlopen, which rejects it
+        let err = unsafe { LensLoader::new().load(&path) }.unwrap_err();
+        assert!(matches!(err, LensError::Initialization(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn test_build_info_in_errors() {
+        let info = unsafe { crate::abi::LensBuildInfo::new("figma-lens", "0.3.0").to_owned() };
+        let host = BuildInfo::current();
+        assert_eq!(
+            (info.crate_name.as_str(), info.crate_version.as_str()),
+            ("figma-lens", "0.3.0")
+        );
+        assert_eq!(info.framework_version, crate::FRAMEWORK_VERSION);
+        assert_eq!(info.rustc_version, host.rustc_version);
+        assert!(info.rustc_version.starts_with("rustc "));
+        assert_eq!(info.target, host.target);
+
+        let err = describe_build(
+            LensError::Initialization("Lens panicked while being constructed".to_string()),
+            Some(&info),
+        );
+        assert!(err
+            .to_string()
+            .contains("(library: figma-lens 0.3.0 built with rustc"));
+        let err = describe_build(LensError::Other("busy".to_string()), Some(&info));
+        assert_eq!(err.to_string(), "busy");
+    }
+
+    #[test]
+    fn test_probe_errors() {
+        let loader = LensLoader::new();
+        let err = unsafe { loader.probe("/nonexistent/path/liblens.so") }.unwrap_err();
+        assert!(matches!(err, LensError::LensNotFound(_)));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("libfake.so");
+        std::fs::write(&path, "not a library").unwrap();
+        let err = unsafe { loader.probe(&path) }.unwrap_err();
+        assert!(err.to_string().contains("Failed to load library"));
+    }
+
     #[test]
     fn test_load_nonexistent_library() {
         let mut loader = LensLoader::new();
@@ -0,0 +1,280 @@
+//! Host-side adapter for lenses loaded through the stable ABI
+
+use std::ffi::c_void;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use libloading::Library;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use crate::abi::{
+    AbiResponse, EventSink, FfiBuffer, FfiStr, LensVTable, McpVTable, StreamingVTable,
+    LENS_ABI_VERSION,
+};
+use crate::error::{LensError, Result};
+use crate::lens::Lens;
+use crate::mcp_server::{McpServerLens, McpTool, McpToolResponse};
+use crate::streaming::{LensEventStream, StreamingLens};
+use crate::{LensContext, LensEvent, LensResult};
+
+/// A lens behind a [`LensVTable`], plus its optional capability tables
+pub(super) struct AbiLens {
+    instance: Arc<AbiInstance>,
+    id: String,
+    name: String,
+    version: String,
+    description: String,
+    supports_mcp: bool,
+    streaming: Option<StreamingVTable>,
+    mcp: Option<(McpVTable, String, String)>,
+}
+
+/// Owns the lens instance behind a vtable and drops it through the library
+struct AbiInstance {
+    vtable: LensVTable,
+    /// Dropped after the instance, keeping its code mapped until then
+    _library: Option<Arc<Library>>,
+}
+
+// SAFETY: `LensVTable::new` only accepts `Lens` implementations, which are
+// `Send + Sync`.
+unsafe impl Send for AbiInstance {}
+unsafe impl Sync for AbiInstance {}
+
+impl Drop for AbiInstance {
+    fn drop(&mut self) {
+        unsafe { (self.vtable.drop)(self.vtable.instance) }
+    }
+}
+
+impl AbiInstance {
+    unsafe fn string(&self, f: unsafe extern "C" fn(*const c_void) -> FfiStr) -> String {
+        String::from_utf8_lossy(f(self.vtable.instance).as_bytes()).into_owned()
+    }
+
+    /// Decode and free a response buffer returned by the library
+    unsafe fn response<T: DeserializeOwned>(&self, buffer: FfiBuffer) -> Result<AbiResponse<T>> {
+        let response = serde_json::from_slice(std::slice::from_raw_parts(buffer.ptr, buffer.len));
+        (self.vtable.free_buffer)(buffer);
+        Ok(response?)
+    }
+}
+
+/// Send each JSON event from the library into the channel behind `context`
+unsafe extern "C" fn forward_event(context: *mut c_void, event: FfiStr) {
+    let events = &*(context as *const mpsc::UnboundedSender<LensEvent>);
+    if let Ok(event) = serde_json::from_slice(event.as_bytes()) {
+        let _ = events.send(event);
+    }
+}
+
+impl AbiLens {
+    /// # Safety
+    ///
+    /// `vtable` must come from a lens entry point, and `library` (when given)
+    /// must be the library that produced it.
+    pub(super) unsafe fn new(vtable: LensVTable, library: Option<Arc<Library>>) -> Result<Self> {
+        // The rest of an unknown vtable cannot be trusted, so a mismatched
+        // instance is leaked rather than dropped
+        if vtable.abi_version != LENS_ABI_VERSION {
+            return Err(LensError::Initialization(format!(
+                "Lens uses ABI version {}; this host supports version {}",
+                vtable.abi_version, LENS_ABI_VERSION
+            )));
+        }
+        if vtable.instance.is_null() {
+            return Err(LensError::Initialization(
+                "Lens panicked while being constructed".to_string(),
+            ));
+        }
+        let instance = AbiInstance {
+            vtable,
+            _library: library,
+        };
+        Ok(Self {
+            id: instance.string(instance.vtable.id),
+            name: instance.string(instance.vtable.name),
+            version: instance.string(instance.vtable.version),
+            description: instance.string(instance.vtable.description),
+            supports_mcp: (instance.vtable.supports_mcp)(instance.vtable.instance),
+            streaming: None,
+            mcp: None,
+            instance: Arc::new(instance),
+        })
+    }
+
+    /// Attach the streaming entry points
+    ///
+    /// # Safety
+    ///
+    /// `table` must come from the same library and lens type as the vtable.
+    pub(super) unsafe fn with_streaming(mut self, table: StreamingVTable) -> Result<Self> {
+        self.check_version("streaming", table.abi_version)?;
+        self.streaming = Some(table);
+        Ok(self)
+    }
+
+    /// Attach the MCP entry points
+    ///
+    /// # Safety
+    ///
+    /// `table` must come from the same library and lens type as the vtable.
+    pub(super) unsafe fn with_mcp(mut self, table: McpVTable) -> Result<Self> {
+        self.check_version("MCP", table.abi_version)?;
+        let buffer = (table.server_info)(self.instance.vtable.instance);
+        let (name, version) = self.unwrap_response(self.instance.response(buffer)?)?;
+        self.mcp = Some((table, name, version));
+        self.supports_mcp = true;
+        Ok(self)
+    }
+
+    pub(super) fn is_streaming(&self) -> bool {
+        self.streaming.is_some()
+    }
+
+    pub(super) fn is_mcp(&self) -> bool {
+        self.mcp.is_some()
+    }
+
+    fn check_version(&self, table: &str, abi_version: u32) -> Result<()> {
+        if abi_version != LENS_ABI_VERSION {
+            return Err(LensError::Initialization(format!(
+                "Lens '{}' exports {} entry points for ABI version {}; this host supports version {}",
+                self.id, table, abi_version, LENS_ABI_VERSION
+            )));
+        }
+        Ok(())
+    }
+
+    fn unwrap_response<T>(&self, response: AbiResponse<T>) -> Result<T> {
+        match response {
+            AbiResponse::Ok(value) => Ok(value),
+            AbiResponse::Error(message) => Err(LensError::Other(message)),
+            AbiResponse::Panicked(message) => Err(LensError::ExecutionFailed(format!(
+                "Lens '{}' panicked: {}",
+                self.id, message
+            ))),
+        }
+    }
+
+    /// Make a blocking call into the library off the async runtime
+    async fn call<T, F>(&self, call: F) -> Result<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+        F: FnOnce(*const c_void) -> FfiBuffer + Send + 'static,
+    {
+        let instance = Arc::clone(&self.instance);
+        let response = tokio::task::spawn_blocking(move || unsafe {
+            let buffer = call(instance.vtable.instance);
+            instance.response(buffer)
+        })
+        .await
+        .map_err(|e| LensError::ExecutionFailed(e.to_string()))??;
+        self.unwrap_response(response)
+    }
+
+    fn mcp_table(&self) -> Result<&(McpVTable, String, String)> {
+        self.mcp.as_ref().ok_or_else(|| {
+            LensError::Other(format!(
+                "Lens '{}' does not export MCP entry points",
+                self.id
+            ))
+        })
+    }
+}
+
+#[async_trait]
+impl Lens for AbiLens {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn supports_mcp(&self) -> bool {
+        self.supports_mcp
+    }
+
+    async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+        let request = serde_json::to_vec(&ctx)?;
+        let execute = self.instance.vtable.execute;
+        self.call(move |instance| unsafe { execute(instance, FfiStr::new(&request)) })
+            .await
+    }
+}
+
+#[async_trait]
+impl StreamingLens for AbiLens {
+    async fn execute_streaming(&self, ctx: LensContext) -> Result<(LensResult, LensEventStream)> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let result = match self.streaming {
+            Some(table) => {
+                let request = serde_json::to_vec(&ctx)?;
+                self.call(move |instance| unsafe {
+                    let sink = EventSink {
+                        context: &tx as *const _ as *mut c_void,
+                        emit: forward_event,
+                    };
+                    (table.execute_streaming)(instance, FfiStr::new(&request), sink)
+                })
+                .await?
+            }
+            None => self.execute(ctx).await?,
+        };
+        Ok((result, Box::pin(UnboundedReceiverStream::new(rx))))
+    }
+}
+
+#[async_trait]
+impl McpServerLens for AbiLens {
+    fn mcp_tools(&self) -> Vec<McpTool> {
+        let tools = self.mcp_table().and_then(|(table, _, _)| unsafe {
+            let buffer = (table.tools)(self.instance.vtable.instance);
+            self.unwrap_response(self.instance.response(buffer)?)
+        });
+        tools.unwrap_or_else(|e| {
+            eprintln!(
+                "Warning: failed to list MCP tools of lens '{}': {}",
+                self.id, e
+            );
+            Vec::new()
+        })
+    }
+
+    async fn call_tool(&self, name: &str, params: Value) -> Result<McpToolResponse> {
+        let table = self.mcp_table()?.0;
+        let name = name.to_string();
+        let params = serde_json::to_vec(&params)?;
+        self.call(move |instance| unsafe {
+            (table.call_tool)(instance, FfiStr::new(name.as_bytes()), FfiStr::new(&params))
+        })
+        .await
+    }
+
+    fn mcp_server_name(&self) -> String {
+        match &self.mcp {
+            Some((_, name, _)) => name.clone(),
+            None => format!("graphyn-{}", self.id),
+        }
+    }
+
+    fn mcp_server_version(&self) -> String {
+        match &self.mcp {
+            Some((_, _, version)) => version.clone(),
+            None => self.version.clone(),
+        }
+    }
+}
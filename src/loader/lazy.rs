@@ -0,0 +1,105 @@
+//! Lenses whose library is opened on first use
+
+use std::sync::{Mutex, OnceLock};
+
+use async_trait::async_trait;
+
+use super::{LensLoader, LoadedLens};
+use crate::discovery::DiscoveredLens;
+use crate::error::Result;
+use crate::lens::Lens;
+use crate::{LensContext, LensResult};
+
+/// A discovered lens that opens its library the first time it is executed,
+/// from [`LensLoader::load_lazy`].
+///
+/// Metadata comes from the manifest, so listing lazily loaded lenses never
+/// touches their libraries. Initialization happens once even when several
+/// threads execute concurrently; a failed load is retried on the next call.
+/// The first call blocks while the library opens.
+pub struct LazyLoadedLens {
+    lens: DiscoveredLens,
+    /// Also serializes initialization
+    loader: Mutex<LensLoader>,
+    loaded: OnceLock<LoadedLens>,
+}
+
+impl std::fmt::Debug for LazyLoadedLens {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyLoadedLens")
+            .field("id", &self.lens.id())
+            .field("library_path", &self.lens.library_path)
+            .field("loaded", &self.loaded.get())
+            .finish()
+    }
+}
+
+impl LazyLoadedLens {
+    pub(super) fn new(lens: DiscoveredLens, loader: LensLoader) -> Self {
+        Self {
+            lens,
+            loader: Mutex::new(loader),
+            loaded: OnceLock::new(),
+        }
+    }
+
+    /// The discovered lens this handle loads
+    pub fn discovered(&self) -> &DiscoveredLens {
+        &self.lens
+    }
+
+    /// Whether the library has been opened yet
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.get().is_some()
+    }
+
+    /// The loaded lens, opening its library if this is the first use
+    pub fn get(&self) -> Result<&LoadedLens> {
+        if let Some(loaded) = self.loaded.get() {
+            return Ok(loaded);
+        }
+        let mut loader = self.loader.lock().unwrap();
+        if let Some(loaded) = self.loaded.get() {
+            return Ok(loaded);
+        }
+        // SAFETY: accepted by the caller of `LensLoader::load_lazy`
+        let loaded = unsafe { loader.load_discovered(&self.lens) }?;
+        Ok(self.loaded.get_or_init(|| loaded))
+    }
+}
+
+#[async_trait]
+impl Lens for LazyLoadedLens {
+    fn id(&self) -> &str {
+        self.lens.id()
+    }
+
+    fn name(&self) -> &str {
+        self.lens.name()
+    }
+
+    fn version(&self) -> &str {
+        self.lens.version()
+    }
+
+    fn description(&self) -> &str {
+        &self.lens.manifest.lens.description
+    }
+
+    /// Known once loaded, or from a prior
+    /// [`probe_discovered`](LensLoader::probe_discovered)
+    fn supports_mcp(&self) -> bool {
+        match self.loaded.get() {
+            Some(loaded) => loaded.plugin().supports_mcp(),
+            None => self
+                .lens
+                .capabilities
+                .as_ref()
+                .is_some_and(|capabilities| capabilities.mcp),
+        }
+    }
+
+    async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+        self.get()?.execute(ctx).await
+    }
+}
@@ -0,0 +1,263 @@
+//! Checks applied to a library immediately before it is opened
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{LensError, Result};
+use crate::manifest::LensManifest;
+use crate::security::KeyPins;
+
+/// What [`LensLoader`](super::LensLoader) requires of a library before
+/// opening it.
+///
+/// A declared `[security].library_hash` is always checked against the file
+/// on disk; the policy decides what must be declared. Checks run right
+/// before `dlopen`, so a library swapped after install is still caught, and
+/// the checked file itself is what gets opened, so it cannot be swapped in
+/// between.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SecurityPolicy {
+    /// Refuse libraries whose manifest does not pin a `library_hash`
+    pub require_hash: bool,
+    /// Refuse libraries whose manifest is not validly signed (requires the
+    /// `signing` feature). Implies `require_hash`.
+    pub require_signature: bool,
+    /// Keys a signature must come from, optionally pinned to lens ids; a
+    /// lens no key is trusted for is refused (see [`KeyPins::keys_for`])
+    pub trusted_keys: KeyPins,
+}
+
+impl SecurityPolicy {
+    /// Require a pinned hash and a signature from one of `trusted_keys`
+    pub fn signed_by(trusted_keys: impl Into<KeyPins>) -> Self {
+        Self {
+            require_hash: true,
+            require_signature: true,
+            trusted_keys: trusted_keys.into(),
+        }
+    }
+}
+
+/// A library that passed [`verify_library`], to be opened through
+/// [`path`](Self::path)
+#[derive(Debug)]
+pub(super) enum CheckedLibrary {
+    /// Nothing was checked, so the path is opened as is
+    Unchecked(PathBuf),
+    /// The checked file, kept open so `/proc/self/fd/N` names it
+    #[cfg(target_os = "linux")]
+    Pinned(File),
+    /// A private copy of the checked bytes, removed once dropped
+    #[cfg(not(target_os = "linux"))]
+    Copied(PathBuf),
+}
+
+impl CheckedLibrary {
+    /// Read the library once, so the bytes checked are the bytes opened
+    fn read(path: &Path) -> Result<(Self, Vec<u8>)> {
+        let mut file = File::open(path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        #[cfg(target_os = "linux")]
+        let checked = Self::Pinned(file);
+        #[cfg(not(target_os = "linux"))]
+        let checked = Self::Copied(private_copy(path, &bytes)?);
+        Ok((checked, bytes))
+    }
+
+    /// Path to hand to `dlopen`; only valid while `self` is alive
+    pub(super) fn path(&self) -> PathBuf {
+        match self {
+            Self::Unchecked(path) => path.clone(),
+            #[cfg(target_os = "linux")]
+            Self::Pinned(file) => {
+                use std::os::fd::AsRawFd;
+                PathBuf::from(format!("/proc/self/fd/{}", file.as_raw_fd()))
+            }
+            #[cfg(not(target_os = "linux"))]
+            Self::Copied(path) => path.clone(),
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Drop for CheckedLibrary {
+    fn drop(&mut self) {
+        if let Self::Copied(path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Copy `bytes` to a new file only this process writes to
+#[cfg(not(target_os = "linux"))]
+fn private_copy(path: &Path, bytes: &[u8]) -> Result<PathBuf> {
+    use std::io::Write;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let copy = std::env::temp_dir().join(format!(
+        "lens-{}-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed),
+        name
+    ));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o700);
+    }
+    options.open(&copy)?.write_all(bytes)?;
+    Ok(copy)
+}
+
+/// Check the library at `path` against `manifest` under `policy`
+pub(super) fn verify_library(
+    path: &Path,
+    manifest: Option<&LensManifest>,
+    policy: &SecurityPolicy,
+) -> Result<CheckedLibrary> {
+    if policy.require_signature {
+        let Some(manifest) = manifest else {
+            return Err(LensError::PermissionDenied(format!(
+                "Library {:?} requires a signed manifest; load it with load_discovered",
+                path
+            )));
+        };
+        let trusted_keys = policy.trusted_keys.keys_for(&manifest.lens.id)?;
+        let (checked, bytes) = CheckedLibrary::read(path)?;
+        verify_signature(manifest, &bytes, &trusted_keys)?;
+        return Ok(checked);
+    }
+
+    let declared = manifest
+        .and_then(|manifest| manifest.security.as_ref())
+        .and_then(|security| security.library_hash.as_deref());
+    match declared {
+        Some(declared) => {
+            let (checked, bytes) = CheckedLibrary::read(path)?;
+            let actual = format!("sha256:{:x}", Sha256::digest(&bytes));
+            if actual != declared {
+                return Err(LensError::PermissionDenied(format!(
+                    "Library {:?} does not match its declared library_hash (expected {}, got {})",
+                    path, declared, actual
+                )));
+            }
+            Ok(checked)
+        }
+        None if policy.require_hash => Err(LensError::PermissionDenied(format!(
+            "Library {:?} has no declared [security].library_hash",
+            path
+        ))),
+        None => Ok(CheckedLibrary::Unchecked(path.to_path_buf())),
+    }
+}
+
+#[cfg(feature = "signing")]
+fn verify_signature(
+    manifest: &LensManifest,
+    library_bytes: &[u8],
+    trusted_keys: &[String],
+) -> Result<()> {
+    crate::security::verify_signature(manifest, library_bytes, trusted_keys)
+        .map_err(|e| LensError::PermissionDenied(e.to_string()))
+}
+
+#[cfg(not(feature = "signing"))]
+fn verify_signature(
+    manifest: &LensManifest,
+    _library_bytes: &[u8],
+    _trusted_keys: &[String],
+) -> Result<()> {
+    Err(LensError::PermissionDenied(format!(
+        "Cannot verify the signature of lens '{}': built without the signing feature",
+        manifest.lens.id
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn manifest(library_hash: Option<&str>) -> LensManifest {
+        let mut toml = "[lens]\nid = \"pinned\"\nname = \"Pinned\"\nversion = \"1.0.0\"\nmanifest_version = 2\n".to_string();
+        if let Some(hash) = library_hash {
+            toml.push_str(&format!("\n[security]\nlibrary_hash = \"{}\"\n", hash));
+        }
+        LensManifest::from_toml(&toml).unwrap()
+    }
+
+    #[test]
+    fn test_verify_library() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("libpinned.so");
+        std::fs::write(&path, "compiled").unwrap();
+        let hash = format!("sha256:{:x}", Sha256::digest(b"compiled"));
+        let open = SecurityPolicy::default();
+        let strict = SecurityPolicy {
+            require_hash: true,
+            ..SecurityPolicy::default()
+        };
+
+        verify_library(&path, None, &open).unwrap();
+        verify_library(&path, Some(&manifest(None)), &open).unwrap();
+        let checked = verify_library(&path, Some(&manifest(Some(&hash))), &strict).unwrap();
+
+        // Replacing the file after the check does not change what is opened
+        let replacement = temp_dir.path().join("replacement");
+        std::fs::write(&replacement, "swapped").unwrap();
+        std::fs::rename(&replacement, &path).unwrap();
+        assert_eq!(std::fs::read(checked.path()).unwrap(), b"compiled");
+        drop(checked);
+        std::fs::write(&path, "compiled").unwrap();
+
+        let denied = |manifest: Option<LensManifest>, policy: &SecurityPolicy| {
+            let result = verify_library(&path, manifest.as_ref(), policy);
+            match result {
+                Err(LensError::PermissionDenied(message)) => message,
+                other => panic!("expected PermissionDenied, got {:?}", other),
+            }
+        };
+        assert!(denied(Some(manifest(Some("sha256:00"))), &open).contains("does not match"));
+        assert!(denied(Some(manifest(None)), &strict).contains("no declared"));
+        assert!(denied(None, &strict).contains("no declared"));
+
+        let signed = SecurityPolicy::signed_by(Vec::new());
+        assert!(denied(None, &signed).contains("signed manifest"));
+        denied(Some(manifest(Some(&hash))), &signed);
+
+        #[cfg(feature = "signing")]
+        {
+            let key = ed25519_dalek::SigningKey::from_bytes(&[5; 32]);
+            let public_key: String = key
+                .verifying_key()
+                .as_bytes()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            let mut manifest = manifest(Some(&hash));
+            manifest.sign(&key).unwrap();
+
+            // Without trusted keys, a self-signed manifest proves nothing
+            assert!(denied(Some(manifest.clone()), &signed).contains("No trusted key"));
+
+            let signed = SecurityPolicy::signed_by(vec![public_key]);
+            verify_library(&path, Some(&manifest), &signed).unwrap();
+
+            let pinned_elsewhere = SecurityPolicy::signed_by(
+                KeyPins::default().pin("ff".repeat(32), vec!["pinned".to_string()]),
+            );
+            assert!(denied(Some(manifest.clone()), &pinned_elsewhere).contains("untrusted key"));
+
+            std::fs::write(&path, "swapped").unwrap();
+            assert!(denied(Some(manifest), &signed).contains("does not match"));
+        }
+    }
+}
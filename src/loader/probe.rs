@@ -0,0 +1,112 @@
+//! Inspecting a library's entry points without constructing its lens
+
+use libloading::Library;
+use serde::{Deserialize, Serialize};
+
+use crate::abi::{
+    BuildInfo, BuildInfoFn, McpEntryFn, StreamingEntryFn, BUILD_INFO_SYMBOL, LENS_ABI_VERSION,
+    LENS_ENTRY_POINT, MCP_ENTRY_POINT, STREAMING_ENTRY_POINT,
+};
+
+/// Which entry points a lens library exports, from
+/// [`LensLoader::probe`](super::LensLoader::probe)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LensCapabilities {
+    /// Exports the stable `lens_abi_v1` entry point
+    pub core: bool,
+    /// Exports the legacy `create_lens` entry point
+    pub legacy: bool,
+    /// Exports `lens_streaming_v1`
+    pub streaming: bool,
+    /// Exports `lens_mcp_v1`
+    pub mcp: bool,
+    /// ABI version the library reports through its build info or capability
+    /// tables; `None` when it reports none
+    pub abi_version: Option<u32>,
+    /// How the library was built, when it says and its ABI version matches
+    pub build_info: Option<BuildInfo>,
+}
+
+impl LensCapabilities {
+    /// Whether this host can load the library
+    pub fn is_loadable(&self) -> bool {
+        let abi_matches = self.abi_version.is_none_or(|v| v == LENS_ABI_VERSION);
+        (self.core && abi_matches) || (self.legacy && cfg!(feature = "legacy-abi"))
+    }
+}
+
+/// Read the capabilities of an opened library.
+///
+/// Only the build info and capability table functions are called; they
+/// return static data, so the lens itself is never constructed.
+pub(super) unsafe fn probe_library(library: &Library) -> LensCapabilities {
+    let mut capabilities = LensCapabilities {
+        core: library
+            .get::<unsafe extern "C" fn()>(LENS_ENTRY_POINT)
+            .is_ok(),
+        legacy: library
+            .get::<unsafe extern "C" fn()>(b"create_lens")
+            .is_ok(),
+        ..LensCapabilities::default()
+    };
+
+    if let Ok(build_info) = library.get::<BuildInfoFn>(BUILD_INFO_SYMBOL) {
+        let build_info = build_info();
+        capabilities.abi_version = Some(build_info.abi_version);
+        if build_info.abi_version == LENS_ABI_VERSION {
+            capabilities.build_info = Some(build_info.to_owned());
+        }
+    }
+    if let Ok(streaming) = library.get::<StreamingEntryFn>(STREAMING_ENTRY_POINT) {
+        capabilities.streaming = true;
+        capabilities
+            .abi_version
+            .get_or_insert_with(|| streaming().abi_version);
+    }
+    if let Ok(mcp) = library.get::<McpEntryFn>(MCP_ENTRY_POINT) {
+        capabilities.mcp = true;
+        capabilities
+            .abi_version
+            .get_or_insert_with(|| mcp().abi_version);
+    }
+    capabilities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_loadable() {
+        let core = LensCapabilities {
+            core: true,
+            ..LensCapabilities::default()
+        };
+        assert!(core.is_loadable());
+        assert!(LensCapabilities {
+            abi_version: Some(LENS_ABI_VERSION),
+            ..core.clone()
+        }
+        .is_loadable());
+        assert!(!LensCapabilities {
+            abi_version: Some(LENS_ABI_VERSION + 1),
+            ..core
+        }
+        .is_loadable());
+        assert!(!LensCapabilities::default().is_loadable());
+
+        let legacy = LensCapabilities {
+            legacy: true,
+            ..LensCapabilities::default()
+        };
+        assert_eq!(legacy.is_loadable(), cfg!(feature = "legacy-abi"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_probe_library_without_entry_points() {
+        let library = Library::from(libloading::os::unix::Library::this());
+        let capabilities = unsafe { probe_library(&library) };
+        assert_eq!(capabilities, LensCapabilities::default());
+    }
+}
@@ -0,0 +1,422 @@
+//! # Lens Lockfile
+//!
+//! `lenses.lock` pins the exact set of installed lenses (id, version, binary
+//! hash, and where each came from) so a team can reproduce it on another
+//! machine.
+//!
+//! Requires the `runtime` feature.
+//!
+//! ```toml
+//! version = 1
+//!
+//! [[lens]]
+//! id = "figma"
+//! version = "1.2.0"
+//! library_hash = "sha256:9f86d0..."
+//! source = "git+https://github.com/acme/figma-lens#v1.2.0"
+//! ```
+//!
+//! [`Lockfile::generate`] records the current lenses, [`Lockfile::verify`]
+//! lists how a directory differs, and [`Lockfile::sync`] makes the directory
+//! match using a [`LensFetcher`] to install missing or mismatched lenses.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::discovery::{DiscoveredLens, LensDiscovery};
+use crate::error::{LensError, Result};
+
+/// Lockfile filename, stored next to the lenses directory
+pub const LOCKFILE_FILENAME: &str = "lenses.lock";
+
+/// Current lockfile format version
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// One pinned lens
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LockedLens {
+    pub id: String,
+    pub version: String,
+    /// Binary hash as "sha256:<hex>"; absent for lenses without a binary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library_hash: Option<String>,
+    /// Where the lens was installed from (archive path, URL, or git ref)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl LockedLens {
+    /// Pin `lens` as currently installed
+    pub fn from_discovered(lens: &DiscoveredLens) -> Result<Self> {
+        Ok(Self {
+            id: lens.id().to_string(),
+            version: lens.version().to_string(),
+            library_hash: lens.compute_library_hash()?,
+            source: None,
+        })
+    }
+}
+
+/// A difference between a lockfile and the installed lenses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockMismatch {
+    /// Locked but not installed
+    Missing { id: String },
+    /// Installed at a different version
+    VersionMismatch {
+        id: String,
+        locked: String,
+        installed: String,
+    },
+    /// Installed binary differs from the locked hash
+    HashMismatch {
+        id: String,
+        locked: Option<String>,
+        installed: Option<String>,
+    },
+    /// Installed but not locked
+    Unlocked { id: String },
+}
+
+impl LockMismatch {
+    /// Lens the mismatch is about
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Missing { id }
+            | Self::VersionMismatch { id, .. }
+            | Self::HashMismatch { id, .. }
+            | Self::Unlocked { id } => id,
+        }
+    }
+}
+
+impl std::fmt::Display for LockMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hash = |h: &Option<String>| h.clone().unwrap_or_else(|| "no binary".to_string());
+        match self {
+            Self::Missing { id } => write!(f, "lens '{}' is locked but not installed", id),
+            Self::VersionMismatch {
+                id,
+                locked,
+                installed,
+            } => write!(
+                f,
+                "lens '{}' is locked at {} but {} is installed",
+                id, locked, installed
+            ),
+            Self::HashMismatch {
+                id,
+                locked,
+                installed,
+            } => write!(
+                f,
+                "lens '{}' binary is {} but the lockfile expects {}",
+                id,
+                hash(installed),
+                hash(locked)
+            ),
+            Self::Unlocked { id } => write!(f, "lens '{}' is installed but not locked", id),
+        }
+    }
+}
+
+/// Installs a locked lens from its source, used by [`Lockfile::sync`]
+pub trait LensFetcher {
+    /// Install `lens` into the directory `dest`, which does not exist yet
+    fn fetch(&self, lens: &LockedLens, dest: &Path) -> Result<()>;
+}
+
+/// Parsed `lenses.lock`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Lockfile {
+    pub version: u32,
+    #[serde(default, rename = "lens")]
+    pub lenses: Vec<LockedLens>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: LOCKFILE_VERSION,
+            lenses: Vec::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    /// Pin every lens `discovery` finds, sorted by id
+    pub fn generate(discovery: &LensDiscovery) -> Result<Self> {
+        let lenses = discovery
+            .scan()?
+            .iter()
+            .map(LockedLens::from_discovered)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            version: LOCKFILE_VERSION,
+            lenses,
+        })
+    }
+
+    /// Parse a lockfile from TOML
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let lockfile: Self = toml::from_str(content)
+            .map_err(|e| LensError::InvalidInput(format!("Failed to parse lockfile: {}", e)))?;
+        if lockfile.version > LOCKFILE_VERSION {
+            return Err(LensError::InvalidInput(format!(
+                "Lockfile version {} is newer than supported version {}",
+                lockfile.version, LOCKFILE_VERSION
+            )));
+        }
+        Ok(lockfile)
+    }
+
+    /// Serialize to TOML
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string_pretty(self)
+            .map_err(|e| LensError::Other(format!("Failed to serialize lockfile: {}", e)))
+    }
+
+    /// Read a lockfile from disk
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            LensError::InvalidInput(format!("Failed to read lockfile {:?}: {}", path, e))
+        })?;
+        Self::from_toml(&content)
+    }
+
+    /// Write the lockfile to disk
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_toml()?)?;
+        Ok(())
+    }
+
+    /// Locked entry for `id`
+    pub fn get(&self, id: &str) -> Option<&LockedLens> {
+        self.lenses.iter().find(|lens| lens.id == id)
+    }
+
+    /// Add or replace the entry for `lens.id`, keeping entries sorted by id
+    pub fn upsert(&mut self, lens: LockedLens) {
+        self.lenses.retain(|existing| existing.id != lens.id);
+        self.lenses.push(lens);
+        self.lenses.sort_by(|a, b| a.id.cmp(&b.id));
+    }
+
+    /// Drop the entry for `id`, returning it
+    pub fn remove(&mut self, id: &str) -> Option<LockedLens> {
+        let index = self.lenses.iter().position(|lens| lens.id == id)?;
+        Some(self.lenses.remove(index))
+    }
+
+    /// How the lenses `discovery` finds differ from this lockfile
+    pub fn verify(&self, discovery: &LensDiscovery) -> Result<Vec<LockMismatch>> {
+        let installed = discovery.scan()?;
+        let mut mismatches = Vec::new();
+
+        for locked in &self.lenses {
+            let Some(lens) = installed.iter().find(|lens| lens.id() == locked.id) else {
+                mismatches.push(LockMismatch::Missing {
+                    id: locked.id.clone(),
+                });
+                continue;
+            };
+            if lens.version() != locked.version {
+                mismatches.push(LockMismatch::VersionMismatch {
+                    id: locked.id.clone(),
+                    locked: locked.version.clone(),
+                    installed: lens.version().to_string(),
+                });
+                continue;
+            }
+            let hash = lens.compute_library_hash()?;
+            if hash != locked.library_hash {
+                mismatches.push(LockMismatch::HashMismatch {
+                    id: locked.id.clone(),
+                    locked: locked.library_hash.clone(),
+                    installed: hash,
+                });
+            }
+        }
+
+        for lens in &installed {
+            if self.get(lens.id()).is_none() {
+                mismatches.push(LockMismatch::Unlocked {
+                    id: lens.id().to_string(),
+                });
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    /// Make the lenses directory match this lockfile.
+    ///
+    /// Missing and mismatched lenses are reinstalled through `fetcher` and
+    /// checked against the lockfile again. With `prune`, unlocked lenses in
+    /// the lenses directory are deleted; project-local lenses are never
+    /// touched. Returns the mismatches that were fixed.
+    pub fn sync(
+        &self,
+        discovery: &LensDiscovery,
+        fetcher: &dyn LensFetcher,
+        prune: bool,
+    ) -> Result<Vec<LockMismatch>> {
+        discovery.ensure_exists()?;
+        let installed = discovery.scan()?;
+        let mut fixed = Vec::new();
+
+        for mismatch in self.verify(discovery)? {
+            let existing = installed
+                .iter()
+                .find(|lens| lens.id() == mismatch.id())
+                .filter(|lens| lens.path.starts_with(discovery.plugins_dir()));
+
+            match &mismatch {
+                LockMismatch::Unlocked { .. } => {
+                    let Some(lens) = existing.filter(|_| prune) else {
+                        continue;
+                    };
+                    std::fs::remove_dir_all(&lens.path)?;
+                }
+                _ => {
+                    let locked = self.get(mismatch.id()).expect("mismatch of a locked lens");
+                    let dest = match existing {
+                        Some(lens) => {
+                            std::fs::remove_dir_all(&lens.path)?;
+                            lens.path.clone()
+                        }
+                        None => discovery.plugins_dir().join(&locked.id),
+                    };
+                    fetcher.fetch(locked, &dest)?;
+                    self.check_installed(discovery, locked, &dest)?;
+                }
+            }
+            fixed.push(mismatch);
+        }
+
+        Ok(fixed)
+    }
+
+    /// Confirm a freshly fetched lens matches its lock entry
+    fn check_installed(
+        &self,
+        discovery: &LensDiscovery,
+        locked: &LockedLens,
+        dest: &Path,
+    ) -> Result<()> {
+        let lens = discovery.load_lens(dest)?;
+        let actual = LockedLens {
+            source: locked.source.clone(),
+            ..LockedLens::from_discovered(&lens)?
+        };
+        if &actual != locked {
+            return Err(LensError::InvalidInput(format!(
+                "Fetched lens '{}' does not match the lockfile (got {} {:?}, expected {} {:?})",
+                locked.id, actual.version, actual.library_hash, locked.version, locked.library_hash
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn write_lens(dir: &Path, id: &str, version: &str, library: &[u8]) {
+        let lens_dir = dir.join(id);
+        fs::create_dir_all(&lens_dir).unwrap();
+        fs::write(
+            lens_dir.join("lens.toml"),
+            format!(
+                "[lens]\nid = \"{}\"\nname = \"{}\"\nversion = \"{}\"\n",
+                id, id, version
+            ),
+        )
+        .unwrap();
+        fs::write(lens_dir.join(library_name(id)), library).unwrap();
+    }
+
+    fn library_name(id: &str) -> String {
+        let ext = if cfg!(target_os = "windows") {
+            "dll"
+        } else if cfg!(target_os = "macos") {
+            "dylib"
+        } else {
+            "so"
+        };
+        format!("lib{}.{}", id.replace('-', "_"), ext)
+    }
+
+    /// Installs lenses by copying them out of a "registry" directory
+    struct CopyFetcher(PathBuf);
+
+    impl LensFetcher for CopyFetcher {
+        fn fetch(&self, lens: &LockedLens, dest: &Path) -> Result<()> {
+            fs::create_dir_all(dest)?;
+            for entry in fs::read_dir(self.0.join(&lens.id))? {
+                let entry = entry?;
+                fs::copy(entry.path(), dest.join(entry.file_name()))?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_lockfile_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let lenses_dir = temp_dir.path().join("lenses");
+        write_lens(&lenses_dir, "figma", "1.2.0", b"figma-v1");
+        let discovery = LensDiscovery::new(&lenses_dir);
+
+        let mut lockfile = Lockfile::generate(&discovery).unwrap();
+        let mut figma = lockfile.get("figma").unwrap().clone();
+        assert!(figma
+            .library_hash
+            .as_deref()
+            .unwrap()
+            .starts_with("sha256:"));
+        figma.source = Some("git+https://example.com/figma-lens#v1.2.0".to_string());
+        lockfile.upsert(figma);
+
+        let path = temp_dir.path().join(LOCKFILE_FILENAME);
+        lockfile.save(&path).unwrap();
+        assert_eq!(Lockfile::load(&path).unwrap(), lockfile);
+        assert!(lockfile.verify(&discovery).unwrap().is_empty());
+        assert!(Lockfile::from_toml("version = 99").is_err());
+    }
+
+    #[test]
+    fn test_lockfile_verify_and_sync() {
+        let temp_dir = tempdir().unwrap();
+        let registry = temp_dir.path().join("registry");
+        write_lens(&registry, "figma", "1.2.0", b"figma-v1");
+        write_lens(&registry, "vibe", "0.3.0", b"vibe");
+        let lockfile = Lockfile::generate(&LensDiscovery::new(&registry)).unwrap();
+
+        let lenses_dir = temp_dir.path().join("lenses");
+        write_lens(&lenses_dir, "figma", "1.2.0", b"figma-tampered");
+        write_lens(&lenses_dir, "stray", "1.0.0", b"stray");
+        let discovery = LensDiscovery::new(&lenses_dir);
+
+        let ids = |mismatches: Vec<LockMismatch>| -> Vec<String> {
+            mismatches.iter().map(ToString::to_string).collect()
+        };
+        let before = lockfile.verify(&discovery).unwrap();
+        assert!(matches!(&before[0], LockMismatch::HashMismatch { id, .. } if id == "figma"));
+        assert_eq!(before[1], LockMismatch::Missing { id: "vibe".into() });
+        assert_eq!(before[2], LockMismatch::Unlocked { id: "stray".into() });
+
+        let fixed = lockfile
+            .sync(&discovery, &CopyFetcher(registry.clone()), true)
+            .unwrap();
+        assert_eq!(ids(fixed), ids(before));
+        assert!(lockfile.verify(&discovery).unwrap().is_empty());
+        assert!(!lenses_dir.join("stray").exists());
+    }
+}
@@ -23,8 +23,20 @@
 //! description = "Renders a document with syntax highlighting"
 //! ```
 
+pub mod capability;
+pub mod migrate;
+mod signature;
+
+pub use capability::{capability_satisfies, normalize_capability, WELL_KNOWN_CAPABILITIES};
+pub use signature::{ManifestSignature, SIGNATURE_ALGORITHM};
+
+use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
 
+use crate::cron::CronSchedule;
+use crate::error::LensError;
+use crate::schema;
+
 /// Lens manifest parsed from lens.toml
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LensManifest {
@@ -40,7 +52,7 @@ pub struct LensManifest {
     #[serde(default)]
     pub message_types: Vec<MessageType>,
 
-    /// Lens capabilities (optional)
+    /// What the lens can do, see [`capability`] for the well-known ids
     #[serde(default)]
     pub capabilities: Vec<String>,
 
@@ -84,6 +96,46 @@ pub struct LensManifest {
     /// Runtime availability gate for store/install surfaces.
     #[serde(default)]
     pub availability: Option<LensAvailability>,
+
+    /// OAuth providers this lens needs a connected account for
+    #[serde(default)]
+    pub oauth_providers: Vec<OAuthProviderRequirement>,
+
+    /// Declared resource budgets the runtime enforces
+    #[serde(default)]
+    pub limits: Option<ResourceLimits>,
+
+    /// Opt-in result caching for lenses whose output depends only on input
+    #[serde(default)]
+    pub cache: Option<CacheConfig>,
+
+    /// External MCP tools the lens may call through `ctx.tool_caller`
+    #[serde(default)]
+    pub tool_access: Option<ToolAccess>,
+
+    /// Icon and color branding shown by host UIs
+    #[serde(default)]
+    pub branding: Option<Branding>,
+
+    /// Example invocations for "try it" suggestions and smoke tests
+    #[serde(default)]
+    pub examples: Vec<LensExample>,
+
+    /// Scheduled triggers a host scheduler can run this lens on
+    #[serde(default)]
+    pub triggers: Vec<LensTrigger>,
+
+    /// Environment variables the lens reads
+    #[serde(default)]
+    pub env: Option<EnvRequirements>,
+
+    /// Publisher signature over this manifest and its library hash
+    #[serde(default)]
+    pub signature: Option<ManifestSignature>,
+
+    /// lens.toml text this manifest was parsed from; signatures cover it
+    #[serde(skip)]
+    source: Option<String>,
 }
 
 impl LensManifest {
@@ -114,6 +166,377 @@ pub struct LensAvailability {
     pub reason: Option<String>,
 }
 
+/// OAuth provider requirement declared by a lens
+///
+/// Example in lens.toml:
+/// ```toml
+/// [[oauth_providers]]
+/// provider = "figma"
+/// scopes = ["file_read"]
+/// optional = false
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OAuthProviderRequirement {
+    /// Provider identifier passed to `OAuthBroker::get_token` (e.g. "figma")
+    pub provider: String,
+
+    /// Scopes the lens requires from the provider
+    #[serde(default)]
+    pub scopes: Vec<String>,
+
+    /// Whether the lens can run (with reduced functionality) without this provider
+    #[serde(default)]
+    pub optional: bool,
+
+    /// Human-readable reason shown when prompting the user to connect
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Resource budgets declared by a lens
+///
+/// Example in lens.toml:
+/// ```toml
+/// [limits]
+/// max_memory_mb = 512
+/// max_execution_secs = 300
+/// max_concurrent_runs = 2
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Memory ceiling in megabytes
+    #[serde(default)]
+    pub max_memory_mb: Option<u64>,
+
+    /// Wall-clock budget for a single execution in seconds
+    #[serde(default)]
+    pub max_execution_secs: Option<u64>,
+
+    /// Maximum number of simultaneous executions of this lens
+    #[serde(default)]
+    pub max_concurrent_runs: Option<u32>,
+}
+
+impl ResourceLimits {
+    /// Memory ceiling in bytes
+    pub fn max_memory_bytes(&self) -> Option<u64> {
+        self.max_memory_mb.map(|mb| mb.saturating_mul(1024 * 1024))
+    }
+
+    /// Wall-clock budget for a single execution
+    pub fn max_execution_time(&self) -> Option<std::time::Duration> {
+        self.max_execution_secs.map(std::time::Duration::from_secs)
+    }
+
+    /// Reject zero budgets, which would make the lens unrunnable
+    pub fn validate(&self) -> crate::Result<()> {
+        let zero = [
+            ("max_memory_mb", self.max_memory_mb == Some(0)),
+            ("max_execution_secs", self.max_execution_secs == Some(0)),
+            ("max_concurrent_runs", self.max_concurrent_runs == Some(0)),
+        ];
+        if let Some((field, _)) = zero.iter().find(|(_, is_zero)| *is_zero) {
+            return Err(LensError::InvalidInput(format!(
+                "[limits] {} must be greater than zero",
+                field
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Result caching declared by a lens whose output depends only on its input
+///
+/// Example in lens.toml:
+/// ```toml
+/// [cache]
+/// ttl_secs = 600
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// How long a successful result is reused for the same input
+    pub ttl_secs: u64,
+}
+
+impl CacheConfig {
+    /// How long a successful result is reused
+    pub fn ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.ttl_secs)
+    }
+
+    /// Reject a zero TTL, which would cache nothing
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.ttl_secs == 0 {
+            return Err(LensError::InvalidInput(
+                "[cache] ttl_secs must be greater than zero".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// External MCP tools a lens may call
+///
+/// Hosts enforce this with `ScopedToolCaller`; a lens without a
+/// `[tool_access]` section may not call any tools.
+///
+/// Example in lens.toml:
+/// ```toml
+/// [tool_access]
+/// allow = ["mcp__graphyn-base__", "mcp__figma__get_"]
+/// deny = ["mcp__graphyn-base__delete_collection"]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ToolAccess {
+    /// Tool name prefixes the lens may call
+    #[serde(default)]
+    pub allow: Vec<String>,
+
+    /// Exact tool names the lens may not call, even when a prefix allows them
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl ToolAccess {
+    /// Whether a call to `tool` is permitted
+    pub fn permits(&self, tool: &str) -> bool {
+        !self.deny.iter().any(|denied| denied == tool)
+            && self
+                .allow
+                .iter()
+                .any(|prefix| tool.starts_with(prefix.as_str()))
+    }
+}
+
+/// Branding assets declared by a lens
+///
+/// Example in lens.toml:
+/// ```toml
+/// [branding]
+/// icon = "assets/icon.png"
+/// icon_dark = "assets/icon-dark.png"
+/// icon_light = "assets/icon-light.png"
+/// accent_color = "#FF5A1F"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct Branding {
+    /// Default icon path relative to the lens root
+    #[serde(default)]
+    pub icon: Option<String>,
+
+    /// Icon variant for dark themes
+    #[serde(default)]
+    pub icon_dark: Option<String>,
+
+    /// Icon variant for light themes
+    #[serde(default)]
+    pub icon_light: Option<String>,
+
+    /// Accent color as `#RGB` or `#RRGGBB`
+    #[serde(default)]
+    pub accent_color: Option<String>,
+}
+
+impl Branding {
+    /// Icon path for a theme, falling back to the default icon
+    pub fn icon_for_theme(&self, dark: bool) -> Option<&str> {
+        let variant = if dark {
+            &self.icon_dark
+        } else {
+            &self.icon_light
+        };
+        variant.as_deref().or(self.icon.as_deref())
+    }
+
+    /// Validate the accent color and that every referenced icon exists under `lens_dir`
+    pub fn validate(&self, lens_dir: &std::path::Path) -> crate::Result<()> {
+        if let Some(color) = &self.accent_color {
+            let hex = color.strip_prefix('#').unwrap_or("");
+            if !matches!(hex.len(), 3 | 6) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(LensError::InvalidInput(format!(
+                    "[branding] accent_color '{}' must be #RGB or #RRGGBB",
+                    color
+                )));
+            }
+        }
+
+        for (field, path) in [
+            ("icon", &self.icon),
+            ("icon_dark", &self.icon_dark),
+            ("icon_light", &self.icon_light),
+        ] {
+            if let Some(path) = path {
+                if !lens_dir.join(path).is_file() {
+                    return Err(LensError::InvalidInput(format!(
+                        "[branding] {} '{}' does not exist in {:?}",
+                        field, path, lens_dir
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Example invocation of a lens
+///
+/// ```toml
+/// [[examples]]
+/// title = "Search for onboarding docs"
+/// description = "Finds documents mentioning onboarding"
+/// input = { query = "onboarding", limit = 5 }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LensExample {
+    /// Short label shown in suggestion lists
+    pub title: String,
+
+    /// Longer explanation of what the example does
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Value passed as `LensContext::input`
+    #[serde(default)]
+    pub input: serde_json::Value,
+}
+
+/// Kind of scheduled trigger
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerType {
+    /// Fires on a 5-field cron schedule
+    Cron,
+}
+
+/// What a scheduler does about fire times that passed while it was not
+/// running (host asleep or closed)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    /// Wait for the next fire time
+    #[default]
+    Skip,
+    /// Run once to catch up, however many fire times were missed
+    RunOnce,
+}
+
+/// What a scheduler does when a trigger fires while its previous run is
+/// still going
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// Skip this fire time
+    #[default]
+    Skip,
+    /// Start another run alongside (or queued behind) the previous one
+    Allow,
+}
+
+/// Scheduled trigger declaration
+///
+/// Cron expressions are validated when the manifest is parsed.
+/// ```toml
+/// [[triggers]]
+/// type = "cron"
+/// schedule = "0 9 * * *"
+/// input = { digest = "daily" }
+/// missed = "run_once"
+/// overlap = "skip"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LensTrigger {
+    /// Trigger kind
+    #[serde(rename = "type")]
+    pub trigger_type: TriggerType,
+
+    /// When the trigger fires
+    pub schedule: CronSchedule,
+
+    /// Optional label shown in host scheduling UIs
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Value passed as `LensContext::input` when the trigger fires
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub input: serde_json::Value,
+
+    /// Handling of fire times missed while the host was not running
+    #[serde(default)]
+    pub missed: MissedRunPolicy,
+
+    /// Handling of fire times reached while the previous run is going
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+}
+
+/// Environment variables a lens expects
+///
+/// Entries are either a bare name or a table with a description:
+/// ```toml
+/// [env]
+/// required = ["FIGMA_TOKEN"]
+/// optional = [{ name = "FIGMA_TEAM_ID", description = "Limits search to one team" }]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct EnvRequirements {
+    /// Variables that must be set for the lens to run
+    #[serde(default)]
+    pub required: Vec<EnvVar>,
+
+    /// Variables that enable extra behavior when set
+    #[serde(default)]
+    pub optional: Vec<EnvVar>,
+}
+
+/// A declared environment variable
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(from = "EnvVarDecl")]
+pub struct EnvVar {
+    /// Variable name
+    pub name: String,
+
+    /// What the variable is used for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EnvVarDecl {
+    Name(String),
+    Detailed {
+        name: String,
+        #[serde(default)]
+        description: Option<String>,
+    },
+}
+
+impl From<EnvVarDecl> for EnvVar {
+    fn from(decl: EnvVarDecl) -> Self {
+        match decl {
+            EnvVarDecl::Name(name) => Self {
+                name,
+                description: None,
+            },
+            EnvVarDecl::Detailed { name, description } => Self { name, description },
+        }
+    }
+}
+
+impl EnvRequirements {
+    /// Required variables that `lookup` reports as unset or empty
+    pub fn missing<F>(&self, lookup: F) -> Vec<&EnvVar>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        self.required
+            .iter()
+            .filter(|var| lookup(&var.name).is_none_or(|value| value.is_empty()))
+            .collect()
+    }
+}
+
 /// Security configuration for lens installation
 ///
 /// Example in lens.toml:
@@ -211,6 +634,38 @@ impl Permission {
             _ => None,
         }
     }
+
+    /// Whether this granted permission covers `required`.
+    ///
+    /// Types must match. A grant without an action covers every action; a
+    /// scope of `*` covers everything, and a trailing `*` covers any scope
+    /// with that prefix (e.g. `fs:read:~/Documents/*`).
+    pub fn covers(&self, required: &Permission) -> bool {
+        if self.permission_type != required.permission_type {
+            return false;
+        }
+        if self.action.is_some() && self.action != required.action {
+            return false;
+        }
+        match self.scope.strip_suffix('*') {
+            Some(prefix) => required.scope.starts_with(prefix),
+            None => self.scope == required.scope,
+        }
+    }
+}
+
+impl SandboxLevel {
+    /// Whether this level permits a permission of `permission_type`.
+    ///
+    /// Filesystem access needs `full`, network access needs `network` or
+    /// `full`; other types (e.g. `secrets`) are not gated by the sandbox.
+    pub fn permits(&self, permission_type: &str) -> bool {
+        match permission_type {
+            "fs" => *self == SandboxLevel::Full,
+            "network" => *self != SandboxLevel::Restricted,
+            _ => true,
+        }
+    }
 }
 
 impl SecurityConfig {
@@ -222,6 +677,16 @@ impl SecurityConfig {
             .collect()
     }
 
+    /// Check whether this config grants `required`, both by an explicit
+    /// permission entry and by sandbox level
+    pub fn grants(&self, required: &Permission) -> bool {
+        self.sandbox.permits(&required.permission_type)
+            && self
+                .parsed_permissions()
+                .iter()
+                .any(|granted| granted.covers(required))
+    }
+
     /// Check if this lens requires full access
     pub fn requires_full_access(&self) -> bool {
         self.sandbox == SandboxLevel::Full
@@ -286,12 +751,149 @@ pub struct LensMetadata {
     /// If empty, the lens supports only its primary `surface` type.
     #[serde(default)]
     pub surfaces: Vec<LensSurface>,
+
+    /// Categories for browsing (e.g. "design", "productivity")
+    #[serde(default)]
+    pub categories: Vec<String>,
+
+    /// Short labels for filtering
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Extra search terms that don't belong in the name or description
+    #[serde(default)]
+    pub keywords: Vec<String>,
+
+    /// Declared entry point (replaces library filename guessing when present)
+    #[serde(default)]
+    pub entry: Option<LensEntry>,
+
+    /// Platforms this lens is built for, as `<os>-<arch>` (e.g. "macos-aarch64")
+    /// or a bare `<os>` matching any architecture. Empty means all platforms.
+    #[serde(default)]
+    pub platforms: Vec<String>,
+
+    /// JSON Schema for the `LensContext::input` this lens accepts.
+    /// Hosts use it to generate @mention input forms and to validate input before `execute()`.
+    #[serde(default)]
+    pub input_schema: Option<serde_json::Value>,
+
+    /// Per-locale overrides for `name` and `description`, keyed by locale (e.g. "fr", "pt-BR")
+    #[serde(default)]
+    pub i18n: std::collections::HashMap<String, LocalizedStrings>,
+}
+
+/// Localized overrides for user-facing manifest strings
+///
+/// Example in lens.toml:
+/// ```toml
+/// [lens.i18n.fr]
+/// name = "Base de connaissances"
+/// description = "Recherche sémantique locale"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct LocalizedStrings {
+    /// Localized display name
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Localized description
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Find the overrides for `locale`, falling back from "pt-BR" to "pt"
+pub(crate) fn lookup_locale<'a, T>(
+    i18n: &'a std::collections::HashMap<String, T>,
+    locale: &str,
+) -> Option<&'a T> {
+    i18n.get(locale).or_else(|| {
+        let language = locale.split(['-', '_']).next()?;
+        i18n.get(language)
+    })
 }
 
 fn default_manifest_version() -> u32 {
     1
 }
 
+/// Version of this framework crate, compared against `min/max_framework_version`
+pub const FRAMEWORK_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Platform identifier of the running host, e.g. "macos-aarch64" or "linux-x86_64"
+pub fn current_platform() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// How a lens is executed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LensEntryType {
+    /// Shared library loaded in-process (.dylib, .so, .dll)
+    Dylib,
+    /// WebAssembly module
+    Wasm,
+    /// Standalone executable speaking the subprocess protocol over stdio
+    Subprocess,
+    /// Compiled into the host and registered in-process; has no file
+    Embedded,
+}
+
+/// Declared lens entry point
+///
+/// Example in lens.toml:
+/// ```toml
+/// [lens]
+/// id = "figma"
+/// entry = { type = "dylib", path = "target/release/libfigma" }
+/// ```
+///
+/// For `dylib` entries the path may omit the extension; the platform's
+/// shared library extension is appended when resolving.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LensEntry {
+    /// Entry point kind
+    #[serde(rename = "type")]
+    pub entry_type: LensEntryType,
+
+    /// Path relative to the lens directory
+    pub path: String,
+}
+
+impl LensEntry {
+    /// Resolve the entry point file inside a lens directory, if it exists.
+    ///
+    /// Absolute paths and `..` segments are rejected.
+    pub fn resolve(&self, lens_dir: &std::path::Path) -> crate::Result<Option<std::path::PathBuf>> {
+        if !is_inside_lens(&self.path) {
+            return Err(LensError::InvalidInput(format!(
+                "Entry path '{}' must be a relative path inside the lens",
+                self.path
+            )));
+        }
+        let path = lens_dir.join(&self.path);
+        if path.is_file() {
+            return Ok(Some(path));
+        }
+        if self.entry_type == LensEntryType::Dylib && path.extension().is_none() {
+            let with_ext = path.with_extension(std::env::consts::DLL_EXTENSION);
+            if with_ext.is_file() {
+                return Ok(Some(with_ext));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Whether `path` stays inside the directory it is joined to
+fn is_inside_lens(path: &str) -> bool {
+    use std::path::Component;
+
+    std::path::Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
 /// Structured author information (v2)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Author {
@@ -386,6 +988,105 @@ pub struct LifecycleHooks {
     pub post_uninstall: Option<String>,
 }
 
+/// Point in the lens lifecycle a hook runs at
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    PreInstall,
+    PostInstall,
+    PreEnable,
+    PostEnable,
+    PreDisable,
+    PostDisable,
+    PreUninstall,
+    PostUninstall,
+}
+
+impl HookEvent {
+    /// All events in lifecycle order
+    pub const ALL: [HookEvent; 8] = [
+        HookEvent::PreInstall,
+        HookEvent::PostInstall,
+        HookEvent::PreEnable,
+        HookEvent::PostEnable,
+        HookEvent::PreDisable,
+        HookEvent::PostDisable,
+        HookEvent::PreUninstall,
+        HookEvent::PostUninstall,
+    ];
+
+    /// Key used in the `[hooks]` table
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::PreInstall => "pre_install",
+            HookEvent::PostInstall => "post_install",
+            HookEvent::PreEnable => "pre_enable",
+            HookEvent::PostEnable => "post_enable",
+            HookEvent::PreDisable => "pre_disable",
+            HookEvent::PostDisable => "post_disable",
+            HookEvent::PreUninstall => "pre_uninstall",
+            HookEvent::PostUninstall => "post_uninstall",
+        }
+    }
+}
+
+/// A declared lifecycle hook script
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LensHook {
+    /// When the hook runs
+    pub event: HookEvent,
+
+    /// Script path relative to the lens root
+    pub script: String,
+}
+
+impl LensHook {
+    /// Resolve the script under `lens_dir`, rejecting absolute paths and `..` segments
+    pub fn resolve(&self, lens_dir: &std::path::Path) -> crate::Result<std::path::PathBuf> {
+        if !is_inside_lens(&self.script) {
+            return Err(LensError::InvalidInput(format!(
+                "Hook {} script '{}' must be a relative path inside the lens",
+                self.event.as_str(),
+                self.script
+            )));
+        }
+        Ok(lens_dir.join(&self.script))
+    }
+
+    /// Hooks run arbitrary code, so only lenses granted full sandbox access may run them
+    pub fn is_allowed(&self, sandbox: &SandboxLevel) -> bool {
+        *sandbox == SandboxLevel::Full
+    }
+}
+
+impl LifecycleHooks {
+    /// Script declared for `event`
+    pub fn get(&self, event: HookEvent) -> Option<LensHook> {
+        let script = match event {
+            HookEvent::PreInstall => &self.pre_install,
+            HookEvent::PostInstall => &self.post_install,
+            HookEvent::PreEnable => &self.pre_enable,
+            HookEvent::PostEnable => &self.post_enable,
+            HookEvent::PreDisable => &self.pre_disable,
+            HookEvent::PostDisable => &self.post_disable,
+            HookEvent::PreUninstall => &self.pre_uninstall,
+            HookEvent::PostUninstall => &self.post_uninstall,
+        };
+        script.as_ref().map(|script| LensHook {
+            event,
+            script: script.clone(),
+        })
+    }
+
+    /// All declared hooks in lifecycle order
+    pub fn all(&self) -> Vec<LensHook> {
+        HookEvent::ALL
+            .iter()
+            .filter_map(|event| self.get(*event))
+            .collect()
+    }
+}
+
 /// Dependency with version constraint (v2 enhanced)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LensDependencyV2 {
@@ -425,6 +1126,34 @@ pub struct MessageType {
     /// JSON schema for the message payload (optional, for validation)
     #[serde(default)]
     pub schema: Option<serde_json::Value>,
+
+    /// Whether this key is deprecated and renderers should migrate away from it
+    #[serde(default)]
+    pub deprecated: bool,
+
+    /// Key that supersedes this one, if any
+    #[serde(default)]
+    pub replaced_by: Option<String>,
+
+    /// Per-locale description overrides (`[message_types.i18n.<locale>]`)
+    #[serde(default)]
+    pub i18n: std::collections::HashMap<String, LocalizedStrings>,
+}
+
+impl MessageType {
+    /// Human-readable deprecation notice, or `None` if the key is not deprecated
+    pub fn deprecation_notice(&self) -> Option<String> {
+        if !self.deprecated {
+            return None;
+        }
+        Some(match &self.replaced_by {
+            Some(replacement) => format!(
+                "message type '{}' is deprecated, use '{}' instead",
+                self.key, replacement
+            ),
+            None => format!("message type '{}' is deprecated", self.key),
+        })
+    }
 }
 
 /// Lens shortcut definition.
@@ -455,15 +1184,143 @@ pub struct LensDependency {
     pub version: Option<String>,
 }
 
+impl LensDependency {
+    /// Parsed version requirement (`None` means any version)
+    pub fn version_req(&self) -> Result<Option<VersionReq>, semver::Error> {
+        self.version.as_deref().map(VersionReq::parse).transpose()
+    }
+
+    /// Check whether a concrete version satisfies this dependency
+    pub fn matches(&self, version: &str) -> bool {
+        match (self.version_req(), Version::parse(version)) {
+            (Ok(None), _) => true,
+            (Ok(Some(req)), Ok(version)) => req.matches(&version),
+            _ => false,
+        }
+    }
+}
+
 impl LensManifest {
     /// Parse manifest from TOML string
+    ///
+    /// Version fields are validated as semver, and `[limits]` budgets and the
+    /// `[cache]` TTL must be non-zero; invalid values are rejected.
     pub fn from_toml(toml_str: &str) -> Result<Self, toml::de::Error> {
-        toml::from_str(toml_str)
+        let mut manifest: Self = toml::from_str(toml_str)?;
+        manifest
+            .validate_parsed()
+            .map_err(|e| <toml::de::Error as serde::de::Error>::custom(e.to_string()))?;
+        manifest.source = Some(toml_str.to_string());
+        Ok(manifest)
+    }
+
+    /// Parse manifest from TOML string, upgrading older layouts first.
+    ///
+    /// Returns the notes describing each change applied by [`migrate::migrate`].
+    pub fn from_toml_migrated(
+        toml_str: &str,
+    ) -> Result<(Self, Vec<migrate::MigrationNote>), toml::de::Error> {
+        let document: toml::Value = toml::from_str(toml_str)?;
+        let (document, notes) = migrate::migrate(document)
+            .map_err(|e| <toml::de::Error as serde::de::Error>::custom(e.to_string()))?;
+        let mut manifest: Self = document.try_into()?;
+        manifest
+            .validate_parsed()
+            .map_err(|e| <toml::de::Error as serde::de::Error>::custom(e.to_string()))?;
+        manifest.source = Some(toml_str.to_string());
+        Ok((manifest, notes))
+    }
+
+    /// Checks every parsed manifest must pass
+    fn validate_parsed(&self) -> crate::Result<()> {
+        self.validate_versions()?;
+        if let Some(limits) = &self.limits {
+            limits.validate()?;
+        }
+        if let Some(cache) = &self.cache {
+            cache.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Validate `lens.version`, framework bounds, and dependency ranges as semver
+    pub fn validate_versions(&self) -> crate::Result<()> {
+        let invalid = |field: &str, value: &str, err: semver::Error| {
+            LensError::InvalidInput(format!(
+                "Lens '{}': invalid {} '{}': {}",
+                self.lens.id, field, value, err
+            ))
+        };
+
+        Version::parse(&self.lens.version)
+            .map_err(|e| invalid("version", &self.lens.version, e))?;
+
+        for (field, value) in [
+            ("min_framework_version", &self.lens.min_framework_version),
+            ("max_framework_version", &self.lens.max_framework_version),
+        ] {
+            if let Some(value) = value {
+                Version::parse(value).map_err(|e| invalid(field, value, e))?;
+            }
+        }
+
+        let ranges = self
+            .dependencies
+            .iter()
+            .map(|d| (&d.id, &d.version))
+            .chain(self.dependencies_v2.iter().map(|d| (&d.id, &d.version)));
+        for (id, version) in ranges {
+            if let Some(version) = version {
+                VersionReq::parse(version).map_err(|e| {
+                    invalid(
+                        &format!("version range for dependency '{}'", id),
+                        version,
+                        e,
+                    )
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check whether this lens supports the given framework version
+    pub fn is_compatible_with(&self, framework_version: &str) -> bool {
+        let Ok(framework) = Version::parse(framework_version) else {
+            return false;
+        };
+
+        let parse = |v: &Option<String>| v.as_deref().map(Version::parse).transpose();
+        match (
+            parse(&self.lens.min_framework_version),
+            parse(&self.lens.max_framework_version),
+        ) {
+            (Ok(min), Ok(max)) => {
+                min.is_none_or(|min| framework >= min) && max.is_none_or(|max| framework <= max)
+            }
+            _ => false,
+        }
+    }
+
+    /// Check whether this lens supports the running framework crate version
+    pub fn is_compatible_with_framework(&self) -> bool {
+        self.is_compatible_with(FRAMEWORK_VERSION)
     }
 
-    /// Serialize manifest to TOML string
+    /// Serialize manifest to TOML string.
+    ///
+    /// Signed manifests serialize as the exact text that was signed, since
+    /// re-serializing would invalidate the signature.
     pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
-        toml::to_string_pretty(self)
+        match (&self.signature, &self.source) {
+            (Some(_), Some(source)) => Ok(source.clone()),
+            _ => toml::to_string_pretty(self),
+        }
+    }
+
+    /// The lens.toml text this manifest was parsed from (or signed as)
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
     }
 
     /// Get message type by key
@@ -471,6 +1328,58 @@ impl LensManifest {
         self.message_types.iter().find(|mt| mt.key == key)
     }
 
+    /// Copy of this manifest with `name` and descriptions replaced by `locale` overrides.
+    ///
+    /// Locales fall back to their language ("pt-BR" → "pt"); strings without an
+    /// override keep the manifest's default text.
+    pub fn localized(&self, locale: &str) -> LensManifest {
+        let mut manifest = self.clone();
+
+        if let Some(strings) = lookup_locale(&self.lens.i18n, locale) {
+            if let Some(name) = &strings.name {
+                manifest.lens.name = name.clone();
+            }
+            if let Some(description) = &strings.description {
+                manifest.lens.description = description.clone();
+            }
+        }
+
+        for message_type in &mut manifest.message_types {
+            if let Some(description) = lookup_locale(&message_type.i18n, locale)
+                .and_then(|strings| strings.description.clone())
+            {
+                message_type.description = description;
+            }
+        }
+
+        manifest
+    }
+
+    /// Deprecation notices for declared message types, including dangling `replaced_by` keys
+    pub fn deprecation_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for message_type in &self.message_types {
+            if let Some(notice) = message_type.deprecation_notice() {
+                warnings.push(notice);
+            }
+            if let Some(replacement) = &message_type.replaced_by {
+                if self.get_message_type(replacement).is_none() {
+                    warnings.push(format!(
+                        "message type '{}' is replaced by undeclared key '{}'",
+                        message_type.key, replacement
+                    ));
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Deprecation notice for an emitted `LensEvent::Data` key, if it is deprecated
+    pub fn check_emitted_key(&self, key: &str) -> Option<String> {
+        self.get_message_type(key)
+            .and_then(MessageType::deprecation_notice)
+    }
+
     /// Check if a message type is interactive (requires user input)
     pub fn is_interactive(&self, key: &str) -> bool {
         self.get_message_type(key)
@@ -494,6 +1403,13 @@ impl Default for LensMetadata {
             manifest_version: 1,
             surface: LensSurface::Pane,
             surfaces: Vec::new(),
+            categories: Vec::new(),
+            tags: Vec::new(),
+            keywords: Vec::new(),
+            entry: None,
+            platforms: Vec::new(),
+            input_schema: None,
+            i18n: std::collections::HashMap::new(),
         }
     }
 }
@@ -509,6 +1425,64 @@ impl LensMetadata {
         }
     }
 
+    /// Score how well this lens matches a search query (0 = no match).
+    ///
+    /// Every whitespace-separated term must match the id, name, categories, tags,
+    /// keywords, or description (case-insensitive). Identity matches rank highest,
+    /// then structured metadata, then the description.
+    pub fn search_score(&self, query: &str) -> u32 {
+        let terms: Vec<String> = query.split_whitespace().map(|t| t.to_lowercase()).collect();
+        if terms.is_empty() {
+            return 0;
+        }
+
+        let id = self.id.to_lowercase();
+        let name = self.name.to_lowercase();
+        let description = self.description.to_lowercase();
+        let labels: Vec<String> = self
+            .categories
+            .iter()
+            .chain(&self.tags)
+            .chain(&self.keywords)
+            .map(|l| l.to_lowercase())
+            .collect();
+
+        let mut score = 0;
+        for term in &terms {
+            let term_score = if id == *term || name == *term {
+                10
+            } else if id.contains(term.as_str()) || name.contains(term.as_str()) {
+                6
+            } else if labels.iter().any(|l| l == term) {
+                5
+            } else if labels.iter().any(|l| l.contains(term.as_str())) {
+                3
+            } else if description.contains(term.as_str()) {
+                1
+            } else {
+                return 0;
+            };
+            score += term_score;
+        }
+        score
+    }
+
+    /// Check if this lens declares support for a platform (`<os>-<arch>`)
+    pub fn supports_platform(&self, platform: &str) -> bool {
+        if self.platforms.is_empty() {
+            return true;
+        }
+        let os = platform.split('-').next().unwrap_or(platform);
+        self.platforms
+            .iter()
+            .any(|declared| declared == platform || declared == os)
+    }
+
+    /// Check if this lens can run on the current host platform
+    pub fn supports_current_platform(&self) -> bool {
+        self.supports_platform(&current_platform())
+    }
+
     /// Get all supported surfaces. Falls back to primary surface if none declared.
     pub fn all_surfaces(&self) -> Vec<&LensSurface> {
         if self.surfaces.is_empty() {
@@ -536,22 +1510,75 @@ impl LensManifest {
         }
     }
 
-    /// Get license SPDX identifier
-    pub fn license_spdx(&self) -> Option<&str> {
-        self.license_info
-            .as_ref()
-            .map(|l| l.spdx.as_str())
-            .or(self.lens.license.as_deref())
+    /// Get license SPDX identifier
+    pub fn license_spdx(&self) -> Option<&str> {
+        self.license_info
+            .as_ref()
+            .map(|l| l.spdx.as_str())
+            .or(self.lens.license.as_deref())
+    }
+
+    /// Get MCP tool by name
+    pub fn get_mcp_tool(&self, name: &str) -> Option<&McpTool> {
+        self.mcp_tools.iter().find(|t| t.name == name)
+    }
+
+    /// Get entry point for mode
+    pub fn get_entry_point(&self, mode: &str) -> Option<&EntryPoint> {
+        self.entry_points.iter().find(|e| e.mode == mode)
+    }
+
+    /// Validate execution input against `[lens].input_schema`.
+    ///
+    /// Lenses without an input schema accept any input.
+    pub fn validate_input(&self, input: &serde_json::Value) -> crate::Result<()> {
+        let Some(input_schema) = &self.lens.input_schema else {
+            return Ok(());
+        };
+
+        let violations = schema::validate(input_schema, input);
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        Err(LensError::InvalidInput(format!(
+            "Input for lens '{}' does not match input_schema: {}",
+            self.lens.id,
+            schema::describe(&violations)
+        )))
+    }
+
+    /// Validate every `[[examples]]` input against `input_schema`
+    pub fn validate_examples(&self) -> crate::Result<()> {
+        for example in &self.examples {
+            self.validate_input(&example.input).map_err(|e| {
+                LensError::InvalidInput(format!("Example '{}': {}", example.title, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Declared tool access (no tools when no `[tool_access]` section is present)
+    pub fn tool_access(&self) -> ToolAccess {
+        self.tool_access.clone().unwrap_or_default()
     }
 
-    /// Get MCP tool by name
-    pub fn get_mcp_tool(&self, name: &str) -> Option<&McpTool> {
-        self.mcp_tools.iter().find(|t| t.name == name)
+    /// Declared resource limits (unlimited when no `[limits]` section is present)
+    pub fn resource_limits(&self) -> ResourceLimits {
+        self.limits.clone().unwrap_or_default()
     }
 
-    /// Get entry point for mode
-    pub fn get_entry_point(&self, mode: &str) -> Option<&EntryPoint> {
-        self.entry_points.iter().find(|e| e.mode == mode)
+    /// Get OAuth provider requirement by provider id
+    pub fn get_oauth_provider(&self, provider: &str) -> Option<&OAuthProviderRequirement> {
+        self.oauth_providers.iter().find(|p| p.provider == provider)
+    }
+
+    /// OAuth providers that must be connected before the lens can run
+    pub fn required_oauth_providers(&self) -> Vec<&OAuthProviderRequirement> {
+        self.oauth_providers
+            .iter()
+            .filter(|p| !p.optional)
+            .collect()
     }
 }
 
@@ -695,6 +1722,32 @@ sandbox = "network"
         assert!(no_hash.verify_hash("anything"));
     }
 
+    #[test]
+    fn test_security_grants() {
+        let security = SecurityConfig {
+            library_hash: None,
+            permissions: vec![
+                "network:api.example.com".to_string(),
+                "fs:read:~/Documents/*".to_string(),
+            ],
+            sandbox: SandboxLevel::Network,
+        };
+        let perm = |s: &str| Permission::parse(s).unwrap();
+
+        assert!(security.grants(&perm("network:api.example.com")));
+        assert!(!security.grants(&perm("network:evil.example.com")));
+        // Declared, but the network sandbox forbids filesystem access
+        assert!(!security.grants(&perm("fs:read:~/Documents/notes.md")));
+
+        let full = SecurityConfig {
+            sandbox: SandboxLevel::Full,
+            ..security
+        };
+        assert!(full.grants(&perm("fs:read:~/Documents/notes.md")));
+        assert!(!full.grants(&perm("fs:write:~/Documents/notes.md")));
+        assert!(!full.grants(&perm("fs:read:~/.ssh/id_rsa")));
+    }
+
     // === v2 Manifest Tests ===
 
     #[test]
@@ -814,6 +1867,39 @@ pre_uninstall = "scripts/cleanup.sh"
         let hooks = manifest.hooks.unwrap();
         assert_eq!(hooks.post_install, Some("scripts/setup.sh".to_string()));
         assert_eq!(hooks.pre_uninstall, Some("scripts/cleanup.sh".to_string()));
+
+        let declared = hooks.all();
+        assert_eq!(declared.len(), 2);
+        assert_eq!(declared[0].event, HookEvent::PostInstall);
+        assert_eq!(
+            hooks.get(HookEvent::PreUninstall).unwrap().script,
+            "scripts/cleanup.sh"
+        );
+        assert!(hooks.get(HookEvent::PreEnable).is_none());
+    }
+
+    #[test]
+    fn test_lens_hook_resolve_and_gating() {
+        let lens_dir = std::path::Path::new("/lenses/setup");
+        let hook = LensHook {
+            event: HookEvent::PostInstall,
+            script: "scripts/setup.sh".to_string(),
+        };
+        assert_eq!(
+            hook.resolve(lens_dir).unwrap(),
+            lens_dir.join("scripts/setup.sh")
+        );
+        assert!(hook.is_allowed(&SandboxLevel::Full));
+        assert!(!hook.is_allowed(&SandboxLevel::Network));
+        assert!(!hook.is_allowed(&SandboxLevel::Restricted));
+
+        for script in ["../escape.sh", "/bin/sh"] {
+            let hook = LensHook {
+                event: HookEvent::PostInstall,
+                script: script.to_string(),
+            };
+            assert!(hook.resolve(lens_dir).is_err());
+        }
     }
 
     #[test]
@@ -935,6 +2021,77 @@ surfaces = ["tray"]
         assert!(!manifest.lens.supports_surface(&LensSurface::DesktopApp));
     }
 
+    #[test]
+    fn test_platform_compatibility() {
+        let toml = r#"
+[lens]
+id = "native"
+name = "Native"
+version = "0.1.0"
+platforms = ["macos-aarch64", "linux"]
+"#;
+        let manifest = LensManifest::from_toml(toml).unwrap();
+
+        assert!(manifest.lens.supports_platform("macos-aarch64"));
+        assert!(!manifest.lens.supports_platform("macos-x86_64"));
+        assert!(manifest.lens.supports_platform("linux-x86_64"));
+        assert!(manifest.lens.supports_platform("linux-aarch64"));
+        assert!(!manifest.lens.supports_platform("windows-x86_64"));
+
+        // No declaration = every platform
+        assert!(LensMetadata::default().supports_platform("windows-x86_64"));
+        assert!(LensMetadata::default().supports_current_platform());
+    }
+
+    #[test]
+    fn test_entry_declaration() {
+        let toml = r#"
+[lens]
+id = "runner"
+name = "Runner"
+version = "0.1.0"
+entry = { type = "subprocess", path = "bin/runner" }
+"#;
+        let manifest = LensManifest::from_toml(toml).unwrap();
+        let entry = manifest.lens.entry.unwrap();
+
+        assert_eq!(entry.entry_type, LensEntryType::Subprocess);
+        assert_eq!(entry.path, "bin/runner");
+
+        let lens_dir = std::path::Path::new("/lenses/runner");
+        assert_eq!(entry.resolve(lens_dir).unwrap(), None);
+        for path in ["../../bin/sh", "/bin/sh"] {
+            let entry = LensEntry {
+                path: path.to_string(),
+                ..entry.clone()
+            };
+            assert!(entry.resolve(lens_dir).is_err(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_search_metadata() {
+        let toml = r#"
+[lens]
+id = "figma"
+name = "Figma Decomposer"
+version = "0.1.0"
+description = "Extract components from design files"
+categories = ["design"]
+tags = ["ui", "tokens"]
+keywords = ["sketch alternative"]
+"#;
+        let lens = LensManifest::from_toml(toml).unwrap().lens;
+
+        assert_eq!(lens.categories, vec!["design".to_string()]);
+        assert!(lens.search_score("figma") > lens.search_score("tokens"));
+        assert!(lens.search_score("tokens") > lens.search_score("components"));
+        assert!(lens.search_score("Design UI") > 0);
+        assert!(lens.search_score("sketch") > 0);
+        assert_eq!(lens.search_score("design spotify"), 0);
+        assert_eq!(lens.search_score("   "), 0);
+    }
+
     #[test]
     fn test_shortcuts_manifest() {
         let toml = r#"
@@ -961,6 +2118,500 @@ description = "Open lens launcher"
         assert_eq!(shortcut.description.as_deref(), Some("Open lens launcher"));
     }
 
+    #[test]
+    fn test_oauth_providers_manifest() {
+        let toml = r#"
+[lens]
+id = "figma"
+name = "Figma"
+version = "0.1.0"
+
+[[oauth_providers]]
+provider = "figma"
+scopes = ["file_read"]
+description = "Read design files"
+
+[[oauth_providers]]
+provider = "github"
+scopes = ["repo"]
+optional = true
+"#;
+
+        let manifest = LensManifest::from_toml(toml).unwrap();
+        assert_eq!(manifest.oauth_providers.len(), 2);
+
+        let figma = manifest.get_oauth_provider("figma").unwrap();
+        assert_eq!(figma.scopes, vec!["file_read".to_string()]);
+        assert!(!figma.optional);
+        assert!(manifest.get_oauth_provider("github").unwrap().optional);
+
+        let required = manifest.required_oauth_providers();
+        assert_eq!(required.len(), 1);
+        assert_eq!(required[0].provider, "figma");
+    }
+
+    #[test]
+    fn test_validate_input_against_schema() {
+        let toml = r#"
+[lens]
+id = "search"
+name = "Search"
+version = "0.1.0"
+
+[lens.input_schema]
+type = "object"
+required = ["query"]
+
+[lens.input_schema.properties.query]
+type = "string"
+
+[lens.input_schema.properties.limit]
+type = "integer"
+"#;
+
+        let manifest = LensManifest::from_toml(toml).unwrap();
+        assert!(manifest.lens.input_schema.is_some());
+
+        manifest
+            .validate_input(&serde_json::json!({"query": "button", "limit": 5}))
+            .unwrap();
+
+        let err = manifest
+            .validate_input(&serde_json::json!({"limit": "five"}))
+            .unwrap_err();
+        assert!(matches!(err, LensError::InvalidInput(_)));
+        // The json-schema backend words its messages differently
+        if cfg!(feature = "json-schema") {
+            assert!(err.to_string().contains("query"));
+            assert!(err.to_string().contains("/limit: "));
+        } else {
+            assert!(err.to_string().contains("missing required field 'query'"));
+            assert!(err.to_string().contains("/limit: expected integer"));
+        }
+    }
+
+    #[test]
+    fn test_validate_input_without_schema_accepts_anything() {
+        let manifest = LensManifest::from_toml(
+            r#"
+[lens]
+id = "free"
+name = "Free"
+version = "0.1.0"
+"#,
+        )
+        .unwrap();
+
+        manifest
+            .validate_input(&serde_json::json!("anything"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_resource_limits_manifest() {
+        let toml = r#"
+[lens]
+id = "heavy"
+name = "Heavy"
+version = "0.1.0"
+
+[limits]
+max_memory_mb = 512
+max_execution_secs = 300
+max_concurrent_runs = 2
+"#;
+        let manifest = LensManifest::from_toml(toml).unwrap();
+        let limits = manifest.resource_limits();
+
+        assert_eq!(limits.max_memory_bytes(), Some(512 * 1024 * 1024));
+        assert_eq!(
+            limits.max_execution_time(),
+            Some(std::time::Duration::from_secs(300))
+        );
+        assert_eq!(limits.max_concurrent_runs, Some(2));
+        limits.validate().unwrap();
+
+        let zero = ResourceLimits {
+            max_concurrent_runs: Some(0),
+            ..ResourceLimits::default()
+        };
+        assert!(zero
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("max_concurrent_runs"));
+
+        // A zero budget is refused when parsing, not when the lens first runs
+        let err = LensManifest::from_toml(&toml.replace("= 2", "= 0")).unwrap_err();
+        assert!(err.to_string().contains("max_concurrent_runs"), "{}", err);
+    }
+
+    #[test]
+    fn test_cache_manifest() {
+        let toml = r#"
+[lens]
+id = "figma-tokens"
+name = "Figma Tokens"
+version = "0.1.0"
+
+[cache]
+ttl_secs = 600
+"#;
+        let manifest = LensManifest::from_toml(toml).unwrap();
+        let cache = manifest.cache.unwrap();
+        assert_eq!(cache.ttl(), std::time::Duration::from_secs(600));
+        cache.validate().unwrap();
+
+        assert!(CacheConfig { ttl_secs: 0 }.validate().is_err());
+        let err = LensManifest::from_toml(&toml.replace("600", "0")).unwrap_err();
+        assert!(err.to_string().contains("ttl_secs"), "{}", err);
+    }
+
+    #[test]
+    fn test_resource_limits_default_unlimited() {
+        let manifest = LensManifest::from_toml(
+            r#"
+[lens]
+id = "light"
+name = "Light"
+version = "0.1.0"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.resource_limits(), ResourceLimits::default());
+        assert!(manifest.resource_limits().max_execution_time().is_none());
+    }
+
+    #[test]
+    fn test_tool_access_manifest() {
+        let manifest = LensManifest::from_toml(
+            r#"
+[lens]
+id = "researcher"
+name = "Researcher"
+version = "0.1.0"
+
+[tool_access]
+allow = ["mcp__graphyn-base__"]
+deny = ["mcp__graphyn-base__delete_collection"]
+"#,
+        )
+        .unwrap();
+        let access = manifest.tool_access();
+
+        assert!(access.permits("mcp__graphyn-base__search"));
+        assert!(!access.permits("mcp__graphyn-base__delete_collection"));
+        assert!(!access.permits("mcp__figma__get_file"));
+
+        let undeclared = LensManifest::from_toml(
+            "[lens]\nid = \"quiet\"\nname = \"Quiet\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        assert!(!undeclared
+            .tool_access()
+            .permits("mcp__graphyn-base__search"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_semver() {
+        let bad_version = r#"
+[lens]
+id = "bad"
+name = "Bad"
+version = "latest"
+"#;
+        let err = LensManifest::from_toml(bad_version).unwrap_err();
+        assert!(err.to_string().contains("invalid version 'latest'"));
+
+        let bad_range = r#"
+[lens]
+id = "bad"
+name = "Bad"
+version = "1.0.0"
+
+[[dependencies]]
+id = "base"
+version = "whatever"
+"#;
+        let err = LensManifest::from_toml(bad_range).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("version range for dependency 'base'"));
+    }
+
+    #[test]
+    fn test_framework_compatibility() {
+        let toml = r#"
+[lens]
+id = "bounded"
+name = "Bounded"
+version = "1.0.0"
+min_framework_version = "0.5.0"
+max_framework_version = "0.9.0"
+"#;
+        let manifest = LensManifest::from_toml(toml).unwrap();
+
+        assert!(manifest.is_compatible_with("0.5.0"));
+        assert!(manifest.is_compatible_with("0.7.3"));
+        assert!(!manifest.is_compatible_with("0.4.9"));
+        assert!(!manifest.is_compatible_with("1.0.0"));
+        assert!(!manifest.is_compatible_with("not-a-version"));
+
+        let unbounded = LensManifest::from_toml(
+            r#"
+[lens]
+id = "any"
+name = "Any"
+version = "1.0.0"
+"#,
+        )
+        .unwrap();
+        assert!(unbounded.is_compatible_with_framework());
+    }
+
+    #[test]
+    fn test_dependency_version_matching() {
+        let dep = LensDependency {
+            id: "base".to_string(),
+            version: Some(">=1.0.0, <2.0.0".to_string()),
+        };
+        assert!(dep.matches("1.4.0"));
+        assert!(!dep.matches("2.0.0"));
+
+        let any = LensDependency {
+            id: "base".to_string(),
+            version: None,
+        };
+        assert!(any.matches("0.0.1"));
+    }
+
+    #[test]
+    fn test_message_type_deprecation() {
+        let toml = r#"
+[lens]
+id = "media"
+name = "Media"
+version = "0.1.0"
+
+[[message_types]]
+key = "player"
+component = "components/Player.tsx"
+deprecated = true
+replaced_by = "media_player"
+
+[[message_types]]
+key = "media_player"
+component = "components/MediaPlayer.tsx"
+
+[[message_types]]
+key = "legacy_list"
+component = "components/List.tsx"
+deprecated = true
+replaced_by = "list"
+"#;
+        let manifest = LensManifest::from_toml(toml).unwrap();
+
+        assert_eq!(
+            manifest.check_emitted_key("player").as_deref(),
+            Some("message type 'player' is deprecated, use 'media_player' instead")
+        );
+        assert!(manifest.check_emitted_key("media_player").is_none());
+        assert!(manifest.check_emitted_key("unknown").is_none());
+
+        let warnings = manifest.deprecation_warnings();
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[2].contains("undeclared key 'list'"));
+    }
+
+    #[test]
+    fn test_localized_manifest() {
+        let toml = r#"
+[lens]
+id = "kb"
+name = "Knowledge Base"
+version = "0.1.0"
+description = "Local knowledge base"
+
+[lens.i18n.fr]
+name = "Base de connaissances"
+description = "Base de connaissances locale"
+
+[lens.i18n.de]
+name = "Wissensdatenbank"
+
+[[message_types]]
+key = "search_results"
+component = "components/SearchResults.tsx"
+description = "Search results"
+
+[message_types.i18n.fr]
+description = "Résultats de recherche"
+"#;
+        let manifest = LensManifest::from_toml(toml).unwrap();
+
+        let fr = manifest.localized("fr-CA");
+        assert_eq!(fr.lens.name, "Base de connaissances");
+        assert_eq!(fr.lens.description, "Base de connaissances locale");
+        assert_eq!(fr.message_types[0].description, "Résultats de recherche");
+
+        let de = manifest.localized("de");
+        assert_eq!(de.lens.name, "Wissensdatenbank");
+        assert_eq!(de.lens.description, "Local knowledge base");
+        assert_eq!(de.message_types[0].description, "Search results");
+
+        let ja = manifest.localized("ja");
+        assert_eq!(ja.lens.name, "Knowledge Base");
+    }
+
+    #[test]
+    fn test_triggers() {
+        let toml = r#"
+[lens]
+id = "digest"
+name = "Digest"
+version = "0.1.0"
+
+[[triggers]]
+type = "cron"
+schedule = "0 9 * * MON-FRI"
+name = "Weekday digest"
+input = { period = "daily" }
+missed = "run_once"
+
+[[triggers]]
+type = "cron"
+schedule = "*/5 * * * *"
+overlap = "allow"
+"#;
+        let manifest = LensManifest::from_toml(toml).unwrap();
+        let trigger = &manifest.triggers[0];
+        assert_eq!(trigger.trigger_type, TriggerType::Cron);
+        assert_eq!(trigger.schedule.expression(), "0 9 * * MON-FRI");
+        assert_eq!(trigger.input["period"], "daily");
+        assert!(trigger.schedule.matches(0, 9, 6, 10, 2));
+        assert_eq!(trigger.missed, MissedRunPolicy::RunOnce);
+        assert_eq!(trigger.overlap, OverlapPolicy::Skip);
+        assert_eq!(manifest.triggers[1].missed, MissedRunPolicy::Skip);
+        assert_eq!(manifest.triggers[1].overlap, OverlapPolicy::Allow);
+
+        let reparsed = LensManifest::from_toml(&manifest.to_toml().unwrap()).unwrap();
+        assert_eq!(reparsed.triggers, manifest.triggers);
+
+        let invalid = r#"
+[lens]
+id = "digest"
+name = "Digest"
+version = "0.1.0"
+
+[[triggers]]
+type = "cron"
+schedule = "0 25 * * *"
+"#;
+        let err = LensManifest::from_toml(invalid).unwrap_err();
+        assert!(err.to_string().contains("Invalid cron expression"));
+    }
+
+    #[test]
+    fn test_examples() {
+        let toml = r#"
+[lens]
+id = "search"
+name = "Search"
+version = "0.1.0"
+input_schema = { type = "object", required = ["query"], properties = { query = { type = "string" } } }
+
+[[examples]]
+title = "Onboarding docs"
+description = "Finds onboarding material"
+input = { query = "onboarding" }
+
+[[examples]]
+title = "Broken"
+input = { limit = 5 }
+"#;
+        let manifest = LensManifest::from_toml(toml).unwrap();
+        assert_eq!(manifest.examples.len(), 2);
+        assert_eq!(manifest.examples[0].input["query"], "onboarding");
+        assert_eq!(
+            manifest.examples[0].description.as_deref(),
+            Some("Finds onboarding material")
+        );
+
+        let err = manifest.validate_examples().unwrap_err();
+        assert!(err.to_string().contains("Example 'Broken'"));
+    }
+
+    #[test]
+    fn test_env_requirements() {
+        let toml = r#"
+[lens]
+id = "figma"
+name = "Figma"
+version = "0.1.0"
+
+[env]
+required = ["FIGMA_TOKEN", { name = "FIGMA_ORG", description = "Organization slug" }]
+optional = [{ name = "FIGMA_TEAM_ID", description = "Limits search to one team" }]
+"#;
+        let manifest = LensManifest::from_toml(toml).unwrap();
+        let env = manifest.env.as_ref().unwrap();
+
+        assert_eq!(env.required[0].name, "FIGMA_TOKEN");
+        assert_eq!(env.required[0].description, None);
+        assert_eq!(
+            env.required[1].description.as_deref(),
+            Some("Organization slug")
+        );
+        assert_eq!(env.optional[0].name, "FIGMA_TEAM_ID");
+
+        let missing = env.missing(|name| match name {
+            "FIGMA_TOKEN" => Some("secret".to_string()),
+            "FIGMA_ORG" => Some(String::new()),
+            _ => None,
+        });
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].name, "FIGMA_ORG");
+
+        // Round trip keeps descriptions
+        let reparsed = LensManifest::from_toml(&manifest.to_toml().unwrap()).unwrap();
+        assert_eq!(reparsed.env, manifest.env);
+    }
+
+    #[test]
+    fn test_branding_manifest() {
+        let toml = r##"
+[lens]
+id = "branded"
+name = "Branded"
+version = "0.1.0"
+
+[branding]
+icon = "icon.png"
+icon_dark = "icon-dark.png"
+accent_color = "#FF5A1F"
+"##;
+        let manifest = LensManifest::from_toml(toml).unwrap();
+        let branding = manifest.branding.unwrap();
+
+        assert_eq!(branding.icon_for_theme(true), Some("icon-dark.png"));
+        assert_eq!(branding.icon_for_theme(false), Some("icon.png"));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let err = branding.validate(temp_dir.path()).unwrap_err();
+        assert!(err.to_string().contains("icon 'icon.png' does not exist"));
+
+        std::fs::write(temp_dir.path().join("icon.png"), b"").unwrap();
+        std::fs::write(temp_dir.path().join("icon-dark.png"), b"").unwrap();
+        branding.validate(temp_dir.path()).unwrap();
+
+        let bad_color = Branding {
+            accent_color: Some("orange".to_string()),
+            ..Branding::default()
+        };
+        assert!(bad_color.validate(temp_dir.path()).is_err());
+    }
+
     #[test]
     fn test_availability_blocks_install() {
         let toml = r#"
@@ -0,0 +1,97 @@
+//! # Capability Taxonomy
+//!
+//! Lenses list what they can do in the top-level `capabilities` array of
+//! `lens.toml`, so hosts and other lenses can ask for "any lens that can do
+//! X" instead of hard-coding lens ids.
+//!
+//! Capability ids are compared case-insensitively, with `-` and `_`
+//! treated alike (`semantic_search` matches `semantic-search`). Lenses may
+//! declare any id, but should prefer the well-known ones below. A specific
+//! capability implies its broader parent, so a query for `search` also
+//! finds lenses that declare `semantic-search`.
+//!
+//! | Capability | Implies | Meaning |
+//! |------------|---------|---------|
+//! | `read` | | Reads user files or data sources |
+//! | `write` | | Creates or modifies user files or data |
+//! | `execute` | | Runs commands or scripts |
+//! | `search` | | Finds items matching a query |
+//! | `semantic-search` | `search` | Search by meaning (embeddings) |
+//! | `full-text-search` | `search` | Keyword search over text |
+//! | `indexing` | | Builds a searchable index of content |
+//! | `summarization` | | Condenses documents or conversations |
+//! | `translation` | | Translates text between languages |
+//! | `code-analysis` | | Inspects source code |
+//! | `code-generation` | | Writes source code |
+//! | `design-decomposition` | | Breaks designs into components and tokens |
+//! | `image-generation` | | Produces images |
+
+/// Well-known capability ids with the broader capability each implies
+pub const WELL_KNOWN_CAPABILITIES: &[(&str, Option<&str>)] = &[
+    ("read", None),
+    ("write", None),
+    ("execute", None),
+    ("search", None),
+    ("semantic-search", Some("search")),
+    ("full-text-search", Some("search")),
+    ("indexing", None),
+    ("summarization", None),
+    ("translation", None),
+    ("code-analysis", None),
+    ("code-generation", None),
+    ("design-decomposition", None),
+    ("image-generation", None),
+];
+
+/// Canonical form of a capability id: lowercase, `_` replaced by `-`
+pub fn normalize_capability(capability: &str) -> String {
+    capability.trim().to_ascii_lowercase().replace('_', "-")
+}
+
+/// Whether declaring `declared` satisfies a query for `wanted`, either
+/// directly or through the capability it implies
+pub fn capability_satisfies(declared: &str, wanted: &str) -> bool {
+    let wanted = normalize_capability(wanted);
+    let mut current = Some(normalize_capability(declared));
+    while let Some(capability) = current {
+        if capability == wanted {
+            return true;
+        }
+        current = WELL_KNOWN_CAPABILITIES
+            .iter()
+            .find(|(id, _)| *id == capability)
+            .and_then(|(_, parent)| parent.map(str::to_string));
+    }
+    false
+}
+
+impl super::LensManifest {
+    /// Whether the lens declares `capability`, directly or through a more
+    /// specific capability that implies it
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.capabilities
+            .iter()
+            .any(|declared| capability_satisfies(declared, capability))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capability_matching() {
+        assert!(capability_satisfies("semantic_search", "semantic-search"));
+        assert!(capability_satisfies("Semantic-Search", "search"));
+        assert!(!capability_satisfies("search", "semantic-search"));
+        assert!(capability_satisfies("custom-thing", "custom_thing"));
+        assert!(!capability_satisfies("read", "write"));
+
+        for (id, parent) in WELL_KNOWN_CAPABILITIES {
+            assert_eq!(normalize_capability(id), *id);
+            if let Some(parent) = parent {
+                assert!(WELL_KNOWN_CAPABILITIES.iter().any(|(p, _)| p == parent));
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! # Manifest Migration
+//!
+//! Upgrades older lens.toml layouts to the current [`LensManifest`](super::LensManifest)
+//! shape before deserialization, so a format change doesn't break installed lenses.
+//!
+//! Migrations operate on the raw TOML table and record a [`MigrationNote`] for
+//! every field they move or rewrite. Hosts can surface the notes to lens authors.
+//! A manifest without legacy fields is left alone, so a valid v1 manifest
+//! stays v1 and produces no notes.
+//!
+//! | From | To | Changes |
+//! |------|----|---------|
+//! | legacy | 1 | `[plugin]` table renamed to `[lens]` |
+//! | 1 | 2 | `library_hash`/`permissions`/`sandbox` in `[lens]` move to `[security]` |
+
+use serde::Serialize;
+use toml::value::Table;
+use toml::Value;
+
+use crate::error::LensError;
+
+/// Manifest layout version produced by [`migrate`]
+pub const CURRENT_MANIFEST_VERSION: u32 = 2;
+
+/// A single change applied while migrating a manifest
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct MigrationNote {
+    /// Layout version the change migrates from
+    pub from_version: u32,
+    /// Layout version the change migrates to
+    pub to_version: u32,
+    /// Dotted path of the affected field (e.g. "lens.authors")
+    pub field: String,
+    /// What was changed
+    pub message: String,
+}
+
+impl std::fmt::Display for MigrationNote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "v{} -> v{}: {}: {}",
+            self.from_version, self.to_version, self.field, self.message
+        )
+    }
+}
+
+/// Upgrade a parsed lens.toml document to [`CURRENT_MANIFEST_VERSION`].
+///
+/// Manifests already at the current version are returned unchanged with no notes.
+/// Manifests declaring a newer version than this framework understands are rejected.
+pub fn migrate(mut document: Value) -> crate::Result<(Value, Vec<MigrationNote>)> {
+    let root = document
+        .as_table_mut()
+        .ok_or_else(|| LensError::InvalidInput("Manifest must be a TOML table".to_string()))?;
+    let mut notes = Vec::new();
+
+    // Legacy: the metadata table used to be called [plugin]
+    if !root.contains_key("lens") {
+        if let Some(plugin) = root.remove("plugin") {
+            root.insert("lens".to_string(), plugin);
+            notes.push(note(1, 1, "plugin", "renamed [plugin] table to [lens]"));
+        }
+    }
+
+    let version = root
+        .get("lens")
+        .and_then(|lens| lens.get("manifest_version"))
+        .and_then(Value::as_integer)
+        .unwrap_or(1);
+    let version = u32::try_from(version)
+        .map_err(|_| LensError::InvalidInput(format!("Invalid manifest_version {}", version)))?;
+
+    if version > CURRENT_MANIFEST_VERSION {
+        return Err(LensError::InvalidInput(format!(
+            "Manifest version {} is newer than supported version {}",
+            version, CURRENT_MANIFEST_VERSION
+        )));
+    }
+
+    if version < 2 {
+        migrate_v1_to_v2(root, &mut notes);
+    }
+
+    Ok((document, notes))
+}
+
+/// Move verification fields out of `[lens]`, bumping the version only when
+/// there were any to move
+fn migrate_v1_to_v2(root: &mut Table, notes: &mut Vec<MigrationNote>) {
+    let Some(lens) = root.get_mut("lens").and_then(Value::as_table_mut) else {
+        return;
+    };
+
+    // Pre-security layout kept verification fields alongside the metadata
+    let mut security = Table::new();
+    for key in ["library_hash", "permissions", "sandbox"] {
+        if let Some(value) = lens.remove(key) {
+            security.insert(key.to_string(), value);
+            notes.push(note(
+                1,
+                2,
+                &format!("lens.{}", key),
+                &format!("moved to [security].{}", key),
+            ));
+        }
+    }
+    if security.is_empty() {
+        return;
+    }
+    lens.insert("manifest_version".to_string(), Value::Integer(2));
+
+    let existing = root
+        .entry("security")
+        .or_insert_with(|| Value::Table(Table::new()));
+    if let Some(existing) = existing.as_table_mut() {
+        for (key, value) in security {
+            existing.entry(key).or_insert(value);
+        }
+    }
+}
+
+fn note(from_version: u32, to_version: u32, field: &str, message: &str) -> MigrationNote {
+    MigrationNote {
+        from_version,
+        to_version,
+        field: field.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::LensManifest;
+
+    #[test]
+    fn test_migrate_v1_manifest() {
+        let toml = r#"
+[plugin]
+id = "legacy"
+name = "Legacy"
+version = "0.3.0"
+authors = ["Ada Lovelace <ada@example.com>", "Charles"]
+license = "MIT"
+library_hash = "sha256:abc"
+permissions = ["network:api.example.com"]
+"#;
+        let (manifest, notes) = LensManifest::from_toml_migrated(toml).unwrap();
+
+        assert!(manifest.is_v2());
+        assert_eq!(
+            manifest.all_authors(),
+            vec!["Ada Lovelace <ada@example.com>", "Charles"]
+        );
+        assert_eq!(manifest.license_spdx(), Some("MIT"));
+
+        let security = manifest.security.unwrap();
+        assert_eq!(security.library_hash.as_deref(), Some("sha256:abc"));
+        assert_eq!(security.permissions, vec!["network:api.example.com"]);
+
+        let fields: Vec<&str> = notes.iter().map(|n| n.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["plugin", "lens.library_hash", "lens.permissions"]
+        );
+    }
+
+    #[test]
+    fn test_valid_v1_manifest_unchanged() {
+        let toml = r#"
+[lens]
+id = "classic"
+name = "Classic"
+version = "0.3.0"
+authors = ["Ada Lovelace <ada@example.com>"]
+license = "MIT"
+"#;
+        let (manifest, notes) = LensManifest::from_toml_migrated(toml).unwrap();
+        assert!(notes.is_empty(), "{:?}", notes);
+        assert!(!manifest.is_v2());
+        assert_eq!(manifest.license_spdx(), Some("MIT"));
+    }
+
+    #[test]
+    fn test_current_manifest_unchanged() {
+        let toml = r#"
+[lens]
+id = "modern"
+name = "Modern"
+version = "1.0.0"
+manifest_version = 2
+"#;
+        let (_, notes) = LensManifest::from_toml_migrated(toml).unwrap();
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_newer_manifest_version() {
+        let toml = r#"
+[lens]
+id = "future"
+name = "Future"
+version = "1.0.0"
+manifest_version = 99
+"#;
+        let err = migrate(toml::from_str(toml).unwrap()).unwrap_err();
+        assert!(matches!(err, LensError::InvalidInput(_)));
+        assert!(err.to_string().contains("newer than supported"));
+    }
+}
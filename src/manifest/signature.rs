@@ -0,0 +1,309 @@
+//! # Manifest Signatures
+//!
+//! A `[signature]` block binds a lens manifest, including its declared
+//! `[security].library_hash`, to a publisher's ed25519 key:
+//!
+//! ```toml
+//! [signature]
+//! algorithm = "ed25519"
+//! public_key = "<64 hex chars>"
+//! signature = "<128 hex chars>"
+//! ```
+//!
+//! The signature covers the lens.toml text itself, minus the `[signature]`
+//! table (see [`LensManifest::signing_payload`]), so editing any line,
+//! including keys this crate does not know, or swapping the library (which
+//! changes its hash) invalidates it. Verification requires the `signing`
+//! feature.
+
+use serde::{Deserialize, Serialize};
+
+use super::LensManifest;
+#[cfg(feature = "signing")]
+use crate::error::LensError;
+
+/// Only supported signature algorithm
+pub const SIGNATURE_ALGORITHM: &str = "ed25519";
+
+const PAYLOAD_PREFIX: &str = "lens-manifest-signature-v2";
+
+/// Publisher signature over the manifest and library hash
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ManifestSignature {
+    /// Signature algorithm (only "ed25519" is supported)
+    #[serde(default = "default_algorithm")]
+    pub algorithm: String,
+
+    /// Hex-encoded 32-byte public key of the publisher
+    pub public_key: String,
+
+    /// Hex-encoded 64-byte signature over the signing payload
+    pub signature: String,
+}
+
+fn default_algorithm() -> String {
+    SIGNATURE_ALGORITHM.to_string()
+}
+
+impl LensManifest {
+    /// Bytes covered by the `[signature]` block.
+    ///
+    /// The lens.toml text the manifest was parsed from, with the
+    /// `[signature]` table cut out and trailing whitespace trimmed. Fails
+    /// for manifests that were not parsed from text.
+    pub fn signing_payload(&self) -> crate::Result<Vec<u8>> {
+        let source = self.source().ok_or_else(|| {
+            crate::error::LensError::InvalidInput(format!(
+                "Manifest for lens '{}' has no lens.toml text to sign",
+                self.lens.id
+            ))
+        })?;
+        Ok(payload(source))
+    }
+
+    /// Whether the manifest carries a `[signature]` block
+    pub fn is_signed(&self) -> bool {
+        self.signature.is_some()
+    }
+
+    /// Sign the manifest as serialized by [`to_toml`](Self::to_toml),
+    /// replacing any existing `[signature]` block.
+    ///
+    /// [`to_toml`](Self::to_toml) then returns the signed text. To sign a
+    /// hand-written lens.toml byte for byte, use [`sign_toml`](Self::sign_toml).
+    /// Only current-layout manifests can be signed.
+    #[cfg(feature = "signing")]
+    pub fn sign(&mut self, key: &ed25519_dalek::SigningKey) -> crate::Result<()> {
+        if self.lens.manifest_version < super::migrate::CURRENT_MANIFEST_VERSION {
+            return Err(LensError::InvalidInput(format!(
+                "Manifest for lens '{}' must be migrated to version {} before signing",
+                self.lens.id,
+                super::migrate::CURRENT_MANIFEST_VERSION
+            )));
+        }
+
+        let mut unsigned = self.clone();
+        unsigned.signature = None;
+        unsigned.source = None;
+        let text = unsigned
+            .to_toml()
+            .map_err(|e| LensError::Other(format!("Failed to serialize manifest: {}", e)))?;
+        *self = Self::from_toml(&Self::sign_toml(&text, key)?)
+            .map_err(|e| LensError::InvalidInput(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Sign lens.toml `text`, returning it with a fresh `[signature]` table
+    /// appended in place of any existing one
+    #[cfg(feature = "signing")]
+    pub fn sign_toml(text: &str, key: &ed25519_dalek::SigningKey) -> crate::Result<String> {
+        use ed25519_dalek::Signer;
+
+        let unsigned = without_signature_table(text);
+        let signature = key.sign(&payload(&unsigned));
+        Ok(format!(
+            "{}\n\n[signature]\nalgorithm = \"{}\"\npublic_key = \"{}\"\nsignature = \"{}\"\n",
+            unsigned.trim_end(),
+            SIGNATURE_ALGORITHM,
+            encode_hex(key.verifying_key().as_bytes()),
+            encode_hex(&signature.to_bytes())
+        ))
+    }
+
+    /// Verify the `[signature]` block against the manifest contents.
+    ///
+    /// The signing key must be one of `trusted_keys` (hex-encoded,
+    /// case-insensitive). The public key travels inside `[signature]`, so an
+    /// empty `trusted_keys` rejects every signature rather than trusting
+    /// whoever signed.
+    #[cfg(feature = "signing")]
+    pub fn verify_signature(&self, trusted_keys: &[String]) -> crate::Result<()> {
+        use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+        let id = &self.lens.id;
+        let invalid = |reason: &str| {
+            LensError::InvalidInput(format!("Signature for lens '{}' {}", id, reason))
+        };
+
+        let signed = self
+            .signature
+            .as_ref()
+            .ok_or_else(|| LensError::InvalidInput(format!("Lens '{}' is not signed", id)))?;
+
+        if signed.algorithm != SIGNATURE_ALGORITHM {
+            return Err(invalid(&format!(
+                "uses unsupported algorithm '{}'",
+                signed.algorithm
+            )));
+        }
+
+        if trusted_keys.is_empty() {
+            return Err(invalid("cannot be checked: no trusted keys"));
+        }
+        if !trusted_keys
+            .iter()
+            .any(|key| key.eq_ignore_ascii_case(&signed.public_key))
+        {
+            return Err(invalid("was made by an untrusted key"));
+        }
+
+        let public_key: [u8; 32] = decode_hex(&signed.public_key)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid("has a malformed public key"))?;
+        let signature: [u8; 64] = decode_hex(&signed.signature)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid("is malformed"))?;
+
+        let verifying_key =
+            VerifyingKey::from_bytes(&public_key).map_err(|_| invalid("has an invalid key"))?;
+        verifying_key
+            .verify(&self.signing_payload()?, &Signature::from_bytes(&signature))
+            .map_err(|_| invalid("does not match the manifest"))
+    }
+}
+
+fn payload(text: &str) -> Vec<u8> {
+    let unsigned = without_signature_table(text);
+    format!("{}\n{}", PAYLOAD_PREFIX, unsigned.trim_end()).into_bytes()
+}
+
+/// `text` without its `[signature]` table
+fn without_signature_table(text: &str) -> String {
+    let mut kept = String::with_capacity(text.len());
+    let mut in_signature = false;
+    for line in text.split_inclusive('\n') {
+        if let Some(header) = line.trim_start().strip_prefix('[') {
+            in_signature = !header.starts_with('[')
+                && header
+                    .split(']')
+                    .next()
+                    .is_some_and(|name| name.trim() == "signature");
+        }
+        if !in_signature {
+            kept.push_str(line);
+        }
+    }
+    kept
+}
+
+#[cfg(feature = "signing")]
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(feature = "signing")]
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(all(test, feature = "signing"))]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+
+    fn manifest() -> LensManifest {
+        LensManifest::from_toml(
+            r#"
+[lens]
+id = "signed"
+name = "Signed"
+version = "1.0.0"
+manifest_version = 2
+
+[security]
+library_hash = "sha256:abc123"
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let trusted = [encode_hex(key.verifying_key().as_bytes())];
+        let mut manifest = manifest();
+        assert!(manifest.verify_signature(&trusted).is_err());
+
+        manifest.sign(&key).unwrap();
+        manifest.verify_signature(&trusted).unwrap();
+
+        // Signature survives a TOML round trip
+        let reparsed = LensManifest::from_toml(&manifest.to_toml().unwrap()).unwrap();
+        assert!(reparsed.is_signed());
+        reparsed.verify_signature(&trusted).unwrap();
+
+        manifest
+            .verify_signature(&[trusted[0].to_uppercase()])
+            .unwrap();
+        // The embedded key alone vouches for nothing
+        let err = manifest.verify_signature(&[]).unwrap_err();
+        assert!(err.to_string().contains("no trusted keys"));
+        let err = manifest
+            .verify_signature(&[encode_hex(&[1u8; 32])])
+            .unwrap_err();
+        assert!(err.to_string().contains("untrusted key"));
+    }
+
+    #[test]
+    fn test_sign_requires_current_layout() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let mut manifest = manifest();
+        manifest.lens.manifest_version = 1;
+        let err = manifest.sign(&key).unwrap_err();
+        assert!(err.to_string().contains("must be migrated"));
+    }
+
+    #[test]
+    fn test_tampering_invalidates_signature() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let trusted = [encode_hex(key.verifying_key().as_bytes())];
+        let mut manifest = manifest();
+        manifest.sign(&key).unwrap();
+        let signed = manifest.to_toml().unwrap();
+        let tampered = |from: &str, to: &str| {
+            assert!(signed.contains(from));
+            LensManifest::from_toml(&signed.replace(from, to)).unwrap()
+        };
+
+        let err = tampered("sha256:abc123", "sha256:evil")
+            .verify_signature(&trusted)
+            .unwrap_err();
+        assert!(err.to_string().contains("does not match"));
+        assert!(tampered("Signed\"", "Impostor\"")
+            .verify_signature(&trusted)
+            .is_err());
+    }
+
+    #[test]
+    fn test_signature_covers_raw_text() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let trusted = [encode_hex(key.verifying_key().as_bytes())];
+        let text = "# Hand-written\n[lens]\nid = \"raw\"\nname = \"Raw\"\nversion = \"1.0.0\"\n\n[x-publisher]\nchannel = \"stable\"\n";
+
+        // Signed byte for byte: comments and unknown tables are kept
+        let signed = LensManifest::sign_toml(text, &key).unwrap();
+        assert!(signed.starts_with(text.trim_end()));
+        let manifest = LensManifest::from_toml(&signed).unwrap();
+        manifest.verify_signature(&trusted).unwrap();
+        assert_eq!(manifest.to_toml().unwrap(), signed);
+        assert_eq!(
+            manifest.signing_payload().unwrap(),
+            format!("{}\n{}", PAYLOAD_PREFIX, text.trim_end()).into_bytes()
+        );
+
+        // Keys this crate ignores are still covered
+        let edited = LensManifest::from_toml(&signed.replace("stable", "beta")).unwrap();
+        assert!(edited.verify_signature(&trusted).is_err());
+
+        // Re-signing replaces the old table instead of signing it
+        let resigned = LensManifest::sign_toml(&signed, &key).unwrap();
+        assert_eq!(resigned.matches("[signature]").count(), 1);
+        assert_eq!(resigned, signed);
+    }
+}
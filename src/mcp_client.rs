@@ -0,0 +1,507 @@
+//! # MCP stdio Client
+//!
+//! [`StdioToolCaller`] implements [`ToolCaller`] against an MCP server that
+//! speaks newline-delimited JSON-RPC, either a child process it spawns or any
+//! reader/writer pair it attaches to. It performs the `initialize` handshake,
+//! matches responses to requests by id, and bounds every call with a timeout.
+//! Transport failures (a closed connection, a timeout) are `LensError::IoError`;
+//! JSON-RPC errors and failed tools are `ExecutionFailed`.
+//!
+//! ```rust,ignore
+//! let mut command = tokio::process::Command::new("npx");
+//! command.args(["-y", "@modelcontextprotocol/server-filesystem", "/tmp"]);
+//! let caller = StdioToolCaller::spawn(command).await?;
+//!
+//! let ctx = LensContext::new(cwd, input).with_tool_caller(Arc::new(caller));
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use crate::context::{ToolCaller, ToolResultChunk, ToolResultStream};
+use crate::error::{LensError, Result};
+use crate::mcp_server::protocol::MCP_PROTOCOL_VERSION;
+use crate::mcp_server::McpTool;
+
+/// Timeout for the handshake and for calls, unless changed with `with_timeout`
+pub const DEFAULT_TOOL_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+type Writer = Box<dyn AsyncWrite + Send + Unpin>;
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>;
+type ChunkSender = mpsc::UnboundedSender<Result<ToolResultChunk>>;
+type ProgressSinks = Arc<Mutex<HashMap<String, ChunkSender>>>;
+
+/// [`ToolCaller`] over an MCP stdio connection
+pub struct StdioToolCaller {
+    connection: Arc<Connection>,
+    timeout: Duration,
+    server_info: Value,
+    // Held so the server process lives (and is killed) with the caller
+    _child: Option<Child>,
+}
+
+/// Shared request/response plumbing, cloned into streaming calls
+struct Connection {
+    writer: tokio::sync::Mutex<Writer>,
+    pending: Pending,
+    progress: ProgressSinks,
+    next_id: AtomicU64,
+}
+
+impl StdioToolCaller {
+    /// Spawn `command` as an MCP server and complete the handshake.
+    ///
+    /// Stdin and stdout are piped; stderr is inherited so server logs stay
+    /// visible. The process is killed when the caller is dropped.
+    pub async fn spawn(mut command: Command) -> Result<Self> {
+        command
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true);
+        let mut child = command
+            .spawn()
+            .map_err(|e| LensError::Initialization(format!("Failed to spawn MCP server: {}", e)))?;
+
+        let stdin = child.stdin.take().ok_or_else(|| {
+            LensError::Initialization("MCP server stdin is not piped".to_string())
+        })?;
+        let stdout = child.stdout.take().ok_or_else(|| {
+            LensError::Initialization("MCP server stdout is not piped".to_string())
+        })?;
+
+        let mut caller = Self::attach(stdout, stdin).await?;
+        caller._child = Some(child);
+        Ok(caller)
+    }
+
+    /// Attach to an MCP server over an existing reader/writer pair and
+    /// complete the handshake.
+    pub async fn attach<R, W>(reader: R, writer: W) -> Result<Self>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let connection = Arc::new(Connection {
+            writer: tokio::sync::Mutex::new(Box::new(writer)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            progress: Arc::new(Mutex::new(HashMap::new())),
+            next_id: AtomicU64::new(1),
+        });
+        tokio::spawn(read_messages(
+            reader,
+            connection.pending.clone(),
+            connection.progress.clone(),
+        ));
+
+        let init = connection
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "lens",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }),
+                DEFAULT_TOOL_CALL_TIMEOUT,
+            )
+            .await
+            .map_err(|e| LensError::Initialization(format!("MCP handshake failed: {}", e)))?;
+        connection
+            .send(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .await?;
+
+        Ok(Self {
+            connection,
+            timeout: DEFAULT_TOOL_CALL_TIMEOUT,
+            server_info: init.get("serverInfo").cloned().unwrap_or(Value::Null),
+            _child: None,
+        })
+    }
+
+    /// Bound each call by `timeout` instead of [`DEFAULT_TOOL_CALL_TIMEOUT`]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// `serverInfo` reported by the server during the handshake
+    pub fn server_info(&self) -> &Value {
+        &self.server_info
+    }
+
+    /// List every tool the server offers, following pagination cursors
+    pub async fn list_tools(&self) -> Result<Vec<McpTool>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let mut page = self
+                .connection
+                .request("tools/list", params, self.timeout)
+                .await?;
+            let items: Vec<McpTool> = serde_json::from_value(page["tools"].take())?;
+            tools.extend(items);
+            match page.get("nextCursor").and_then(Value::as_str) {
+                Some(next) => cursor = Some(next.to_string()),
+                None => return Ok(tools),
+            }
+        }
+    }
+}
+
+impl Connection {
+    /// Send a request and wait for its result, or fail on error or timeout
+    async fn request(&self, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.request_with_id(id, method, params, timeout).await
+    }
+
+    async fn request_with_id(
+        &self,
+        id: u64,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value> {
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+
+        let sent = self
+            .send(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))
+            .await;
+        if let Err(e) = sent {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(e);
+        }
+
+        let response = match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(_)) => {
+                return Err(LensError::IoError(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "MCP server closed the connection",
+                )))
+            }
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                // Best effort: let the server stop working on it
+                let _ = self
+                    .send(json!({
+                        "jsonrpc": "2.0",
+                        "method": "notifications/cancelled",
+                        "params": { "requestId": id, "reason": "timeout" },
+                    }))
+                    .await;
+                return Err(LensError::IoError(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "MCP request '{}' timed out after {}ms",
+                        method,
+                        timeout.as_millis()
+                    ),
+                )));
+            }
+        };
+
+        if let Some(error) = response.get("error") {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error");
+            return Err(LensError::ExecutionFailed(format!(
+                "MCP error {}: {}",
+                error.get("code").unwrap_or(&Value::Null),
+                message
+            )));
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    async fn send(&self, message: Value) -> Result<()> {
+        let mut line = serde_json::to_vec(&message)?;
+        line.push(b'\n');
+        let mut writer = self.writer.lock().await;
+        writer.write_all(&line).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+}
+
+/// Route responses to waiting requests and progress notifications to
+/// streaming calls until the server closes its output
+async fn read_messages<R: AsyncRead + Unpin>(reader: R, pending: Pending, progress: ProgressSinks) {
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            eprintln!("Warning: ignoring non-JSON line from MCP server");
+            continue;
+        };
+
+        match message.get("method").and_then(Value::as_str) {
+            Some("notifications/progress") => {
+                let params = message.get("params").cloned().unwrap_or(Value::Null);
+                let token = params.get("progressToken").map(Value::to_string);
+                if let Some(sink) = token.and_then(|t| progress.lock().unwrap().get(&t).cloned()) {
+                    let _ = sink.send(Ok(ToolResultChunk::Partial(params)));
+                }
+            }
+            // Other server-initiated requests and notifications are not supported
+            Some(_) => {}
+            None => {
+                let Some(id) = message.get("id").and_then(Value::as_u64) else {
+                    continue;
+                };
+                if let Some(tx) = pending.lock().unwrap().remove(&id) {
+                    let _ = tx.send(message);
+                }
+            }
+        }
+    }
+    // Dropping the senders fails every outstanding request and ends every stream
+    pending.lock().unwrap().clear();
+    progress.lock().unwrap().clear();
+}
+
+/// Unwrap a `tools/call` result, turning `isError` results into errors
+fn tool_result(name: &str, result: Value) -> Result<Value> {
+    if result.get("isError").and_then(Value::as_bool) != Some(true) {
+        return Ok(result);
+    }
+    let message = result["content"]
+        .as_array()
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter_map(|block| block.get("text").and_then(Value::as_str))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default();
+    Err(LensError::ExecutionFailed(format!(
+        "Tool '{}' failed: {}",
+        name, message
+    )))
+}
+
+#[async_trait]
+impl ToolCaller for StdioToolCaller {
+    /// Call `name` and return the MCP result object (`content`, and
+    /// `structuredContent` when the tool provides it).
+    ///
+    /// A result with `isError: true` is returned as `ExecutionFailed` carrying
+    /// the tool's text content.
+    async fn call_tool(&self, name: &str, params: Value) -> Result<Value> {
+        let result = self
+            .connection
+            .request(
+                "tools/call",
+                json!({ "name": name, "arguments": params }),
+                self.timeout,
+            )
+            .await?;
+        tool_result(name, result)
+    }
+
+    /// Call `name` with a progress token; each `notifications/progress` the
+    /// server sends becomes a `Partial` chunk holding the notification params.
+    async fn call_tool_streaming(&self, name: &str, params: Value) -> Result<ToolResultStream> {
+        let connection = self.connection.clone();
+        let id = connection.next_id.fetch_add(1, Ordering::SeqCst);
+        let token = json!(format!("lens-{}", id));
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        connection
+            .progress
+            .lock()
+            .unwrap()
+            .insert(token.to_string(), tx.clone());
+
+        let name = name.to_string();
+        let timeout = self.timeout;
+        tokio::spawn(async move {
+            let params = json!({
+                "name": name,
+                "arguments": params,
+                "_meta": { "progressToken": token },
+            });
+            let result = connection
+                .request_with_id(id, "tools/call", params, timeout)
+                .await
+                .and_then(|result| tool_result(&name, result));
+            connection
+                .progress
+                .lock()
+                .unwrap()
+                .remove(&token.to_string());
+            // The reader queues progress in arrival order, so this lands last
+            let _ = tx.send(result.map(ToolResultChunk::Complete));
+        });
+
+        Ok(Box::pin(UnboundedReceiverStream::new(rx)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_server::{serve, McpServerLens, McpToolResponse};
+    use crate::{Lens, LensContext, LensResult};
+
+    struct MathLens;
+
+    #[async_trait]
+    impl Lens for MathLens {
+        fn id(&self) -> &str {
+            "math"
+        }
+        fn name(&self) -> &str {
+            "Math"
+        }
+        fn version(&self) -> &str {
+            "0.3.0"
+        }
+        async fn execute(&self, _ctx: LensContext) -> Result<LensResult> {
+            Ok(LensResult::success(json!({})))
+        }
+    }
+
+    #[async_trait]
+    impl McpServerLens for MathLens {
+        fn mcp_tools(&self) -> Vec<McpTool> {
+            vec![
+                McpTool::builder("double")
+                    .number_param("n", "Number to double")
+                    .required("n")
+                    .build(),
+                McpTool::builder("divide_by_zero").build(),
+                McpTool::builder("slow").build(),
+            ]
+        }
+
+        async fn call_tool(&self, name: &str, params: Value) -> Result<McpToolResponse> {
+            match name {
+                "double" => Ok(McpToolResponse::text("doubled").with_structured_content(
+                    json!({ "result": params["n"].as_f64().unwrap() * 2.0 }),
+                )),
+                "slow" => {
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    Ok(McpToolResponse::text("done"))
+                }
+                _ => Err(LensError::ExecutionFailed("division by zero".to_string())),
+            }
+        }
+    }
+
+    async fn connect() -> StdioToolCaller {
+        let (client, server) = tokio::io::duplex(4096);
+        let (server_read, server_write) = tokio::io::split(server);
+        tokio::spawn(serve(Arc::new(MathLens), server_read, server_write));
+
+        let (client_read, client_write) = tokio::io::split(client);
+        StdioToolCaller::attach(client_read, client_write)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stdio_tool_caller_round_trip() {
+        let caller = connect().await;
+        assert_eq!(caller.server_info()["name"], "graphyn-math");
+
+        let tools = caller.list_tools().await.unwrap();
+        assert_eq!(tools.len(), 3);
+
+        let result = caller.call_tool("double", json!({"n": 21})).await.unwrap();
+        assert_eq!(result["structuredContent"]["result"], 42.0);
+
+        let err = caller
+            .call_tool("divide_by_zero", json!({}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("division by zero"), "{}", err);
+
+        // Protocol errors (here: schema violation) surface as errors too
+        assert!(caller.call_tool("double", json!({})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stdio_tool_caller_timeout() {
+        let caller = connect().await.with_timeout(Duration::from_millis(50));
+        let err = caller.call_tool("slow", json!({})).await.unwrap_err();
+        assert!(
+            matches!(&err, LensError::IoError(e) if e.kind() == io::ErrorKind::TimedOut),
+            "{}",
+            err
+        );
+
+        // The connection is still usable afterwards
+        let caller = caller.with_timeout(DEFAULT_TOOL_CALL_TIMEOUT);
+        assert!(caller.call_tool("double", json!({"n": 1})).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stdio_tool_caller_streams_progress() {
+        use tokio_stream::StreamExt;
+
+        // Minimal server that reports two progress steps before answering
+        let (client, server) = tokio::io::duplex(4096);
+        let (server_read, mut server_write) = tokio::io::split(server);
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(server_read).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let request: Value = serde_json::from_str(&line).unwrap();
+                let Some(id) = request.get("id").cloned() else {
+                    continue;
+                };
+                let mut out = Vec::new();
+                if request["method"] == "tools/call" {
+                    let token = request["params"]["_meta"]["progressToken"].clone();
+                    for step in 1..=2 {
+                        out.push(json!({
+                            "jsonrpc": "2.0",
+                            "method": "notifications/progress",
+                            "params": {"progressToken": token, "progress": step, "total": 2},
+                        }));
+                    }
+                }
+                out.push(json!({"jsonrpc": "2.0", "id": id, "result": {"content": []}}));
+                for message in out {
+                    let line = format!("{}\n", message);
+                    server_write.write_all(line.as_bytes()).await.unwrap();
+                }
+            }
+        });
+
+        let (client_read, client_write) = tokio::io::split(client);
+        let caller = StdioToolCaller::attach(client_read, client_write)
+            .await
+            .unwrap();
+        let chunks: Vec<ToolResultChunk> = caller
+            .call_tool_streaming("tail", json!({}))
+            .await
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(chunks.len(), 3);
+        assert!(matches!(&chunks[0], ToolResultChunk::Partial(p) if p["progress"] == 1));
+        assert!(matches!(&chunks[1], ToolResultChunk::Partial(p) if p["progress"] == 2));
+        assert!(matches!(&chunks[2], ToolResultChunk::Complete(r) if r["content"] == json!([])));
+    }
+}
@@ -82,11 +82,35 @@
 //! ```
 
 use async_trait::async_trait;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::error::Result;
+use crate::cancel::CancellationToken;
+use crate::error::{LensError, Result};
 use crate::lens::Lens;
+use crate::manifest::Permission;
+use crate::schema::{self, SchemaViolation};
+
+pub mod aggregator;
+pub mod authorize;
+#[cfg(feature = "mcp-http")]
+pub mod http;
+pub mod page;
+pub mod protocol;
+pub mod router;
+mod stdio;
+
+pub use aggregator::McpAggregator;
+pub use authorize::{AllowAllTools, SandboxAuthorizer, ToolAuthorizer};
+#[cfg(feature = "mcp-http")]
+pub use http::{serve_http, BearerAuth, McpHttpServer, StaticBearerTokens};
+#[cfg(feature = "macros")]
+pub use lens_macros::{mcp_tool, mcp_tools};
+pub use page::{paginate, McpPage, DEFAULT_MCP_PAGE_SIZE};
+pub use protocol::McpDispatcher;
+pub use router::{McpRouter, Params};
+pub use stdio::{serve, serve_dispatcher, serve_stdio};
 
 /// MCP tool definition that agents can call
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,6 +124,49 @@ pub struct McpTool {
     /// JSON Schema for tool input parameters
     #[serde(rename = "inputSchema")]
     pub input_schema: McpToolSchema,
+
+    /// Behavior hints for agent hosts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<McpToolAnnotations>,
+
+    /// Permissions the tool needs, in manifest format (e.g. "network:api.com")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<String>,
+}
+
+/// MCP tool annotations
+///
+/// Hints only: hosts use them to decide when to ask the user for confirmation,
+/// but must not rely on them for security.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolAnnotations {
+    /// Human-readable display title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// The tool does not modify its environment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_only_hint: Option<bool>,
+
+    /// The tool may perform destructive updates (meaningful when not read-only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destructive_hint: Option<bool>,
+
+    /// Repeating a call with the same arguments has no additional effect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotent_hint: Option<bool>,
+
+    /// The tool interacts with entities outside the host (e.g. the web)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_world_hint: Option<bool>,
+}
+
+impl McpToolAnnotations {
+    /// Whether no annotation is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 /// JSON Schema for MCP tool inputs
@@ -140,6 +207,271 @@ pub struct McpPropertySchema {
     /// Items schema (for arrays)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub items: Option<Box<McpPropertySchema>>,
+
+    /// Nested property definitions (for objects)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub properties: Option<std::collections::HashMap<String, McpPropertySchema>>,
+
+    /// Required nested property names (for objects)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required: Vec<String>,
+
+    /// Inclusive lower bound (for numbers)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+
+    /// Inclusive upper bound (for numbers)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+
+    /// Minimum length (for strings)
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "minLength")]
+    pub min_length: Option<u64>,
+
+    /// Maximum length (for strings)
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "maxLength")]
+    pub max_length: Option<u64>,
+
+    /// Minimum item count (for arrays)
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "minItems")]
+    pub min_items: Option<u64>,
+
+    /// Maximum item count (for arrays)
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "maxItems")]
+    pub max_items: Option<u64>,
+
+    /// String format hint (e.g. "uri", "date-time", "email")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+impl McpToolSchema {
+    /// Convert a JSON Schema object into a tool input schema.
+    ///
+    /// Keeps what MCP tool schemas use: top-level `properties` and `required`.
+    pub fn from_json_schema(schema: &Value) -> Self {
+        let properties = schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|properties| {
+                properties
+                    .iter()
+                    .map(|(name, prop)| (name.clone(), McpPropertySchema::from_json_schema(prop)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let required = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| {
+                required
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            schema_type: "object".to_string(),
+            properties,
+            required,
+        }
+    }
+
+    /// Derive the input schema from the params type a tool deserializes into.
+    ///
+    /// ```rust,ignore
+    /// #[derive(Deserialize, JsonSchema)]
+    /// struct SearchParams {
+    ///     /// The search query
+    ///     query: String,
+    ///     limit: Option<u32>,
+    /// }
+    ///
+    /// let schema = McpToolSchema::from_type::<SearchParams>();
+    /// ```
+    #[cfg(feature = "tool-schema")]
+    pub fn from_type<T: schemars::JsonSchema>() -> Self {
+        let schema = schemars::generate::SchemaSettings::draft07()
+            .with(|settings| settings.inline_subschemas = true)
+            .into_generator()
+            .into_root_schema_for::<T>();
+        Self::from_json_schema(schema.as_value())
+    }
+}
+
+impl McpPropertySchema {
+    /// A property of JSON type `prop_type`
+    pub fn new(prop_type: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            prop_type: prop_type.into(),
+            description: description.into(),
+            default: None,
+            enum_values: None,
+            items: None,
+            properties: None,
+            required: Vec::new(),
+            minimum: None,
+            maximum: None,
+            min_length: None,
+            max_length: None,
+            min_items: None,
+            max_items: None,
+            format: None,
+        }
+    }
+
+    /// An array property whose elements match `items`
+    pub fn array(description: impl Into<String>, items: McpPropertySchema) -> Self {
+        Self::new("array", description).with_items(items)
+    }
+
+    /// An object property with no declared fields yet
+    pub fn object(description: impl Into<String>) -> Self {
+        Self {
+            properties: Some(std::collections::HashMap::new()),
+            ..Self::new("object", description)
+        }
+    }
+
+    /// Set the schema of array items
+    pub fn with_items(mut self, items: McpPropertySchema) -> Self {
+        self.items = Some(Box::new(items));
+        self
+    }
+
+    /// Declare a nested object field
+    pub fn with_property(mut self, name: impl Into<String>, schema: McpPropertySchema) -> Self {
+        self.properties
+            .get_or_insert_with(Default::default)
+            .insert(name.into(), schema);
+        self
+    }
+
+    /// Mark a nested object field as required
+    pub fn with_required(mut self, name: impl Into<String>) -> Self {
+        self.required.push(name.into());
+        self
+    }
+
+    /// Set the inclusive numeric range; either bound may be open
+    pub fn with_range(mut self, minimum: Option<f64>, maximum: Option<f64>) -> Self {
+        self.minimum = minimum;
+        self.maximum = maximum;
+        self
+    }
+
+    /// Set the allowed string length; either bound may be open
+    pub fn with_length(mut self, min: Option<u64>, max: Option<u64>) -> Self {
+        self.min_length = min;
+        self.max_length = max;
+        self
+    }
+
+    /// Set the allowed array item count; either bound may be open
+    pub fn with_item_count(mut self, min: Option<u64>, max: Option<u64>) -> Self {
+        self.min_items = min;
+        self.max_items = max;
+        self
+    }
+
+    /// Set the string format hint
+    pub fn with_format(mut self, format: impl Into<String>) -> Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    /// Set the default value
+    pub fn with_default(mut self, default: Value) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Convert a JSON Schema property, unwrapping nullable types to the non-null type.
+    pub fn from_json_schema(schema: &Value) -> Self {
+        // Option<T> comes through as `anyOf: [T, {type: null}]` or `type: [T, "null"]`
+        let non_null = ["anyOf", "oneOf"].iter().find_map(|key| {
+            schema
+                .get(*key)?
+                .as_array()?
+                .iter()
+                .find(|branch| branch.get("type").and_then(Value::as_str) != Some("null"))
+        });
+        if let Some(branch) = non_null {
+            let mut property = Self::from_json_schema(branch);
+            if let Some(description) = schema.get("description").and_then(Value::as_str) {
+                property.description = description.to_string();
+            }
+            return property;
+        }
+
+        let prop_type = match schema.get("type") {
+            Some(Value::String(t)) => t.clone(),
+            Some(Value::Array(types)) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|t| *t != "null")
+                .unwrap_or("string")
+                .to_string(),
+            _ if schema.get("properties").is_some() => "object".to_string(),
+            _ => "string".to_string(),
+        };
+
+        let enum_values = schema.get("enum").and_then(Value::as_array).map(|values| {
+            values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        });
+
+        Self {
+            prop_type,
+            description: schema
+                .get("description")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            default: schema.get("default").cloned(),
+            enum_values,
+            items: schema
+                .get("items")
+                .filter(|items| items.is_object())
+                .map(|items| Box::new(Self::from_json_schema(items))),
+            properties: schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .map(|properties| {
+                    properties
+                        .iter()
+                        .map(|(name, prop)| (name.clone(), Self::from_json_schema(prop)))
+                        .collect()
+                }),
+            required: schema
+                .get("required")
+                .and_then(Value::as_array)
+                .map(|names| {
+                    names
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            minimum: schema.get("minimum").and_then(Value::as_f64),
+            maximum: schema.get("maximum").and_then(Value::as_f64),
+            min_length: schema.get("minLength").and_then(Value::as_u64),
+            max_length: schema.get("maxLength").and_then(Value::as_u64),
+            min_items: schema.get("minItems").and_then(Value::as_u64),
+            max_items: schema.get("maxItems").and_then(Value::as_u64),
+            format: schema
+                .get("format")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        }
+    }
 }
 
 /// Builder for creating MCP tools with fluent API
@@ -148,6 +480,8 @@ pub struct McpToolBuilder {
     description: String,
     properties: std::collections::HashMap<String, McpPropertySchema>,
     required: Vec<String>,
+    annotations: McpToolAnnotations,
+    permissions: Vec<String>,
 }
 
 impl McpToolBuilder {
@@ -158,6 +492,8 @@ impl McpToolBuilder {
             description: String::new(),
             properties: std::collections::HashMap::new(),
             required: Vec::new(),
+            annotations: McpToolAnnotations::default(),
+            permissions: Vec::new(),
         }
     }
 
@@ -170,16 +506,8 @@ impl McpToolBuilder {
     /// Add a string parameter
     pub fn string_param(mut self, name: impl Into<String>, desc: impl Into<String>) -> Self {
         let name = name.into();
-        self.properties.insert(
-            name.clone(),
-            McpPropertySchema {
-                prop_type: "string".to_string(),
-                description: desc.into(),
-                default: None,
-                enum_values: None,
-                items: None,
-            },
-        );
+        self.properties
+            .insert(name.clone(), McpPropertySchema::new("string", desc));
         self
     }
 
@@ -190,16 +518,8 @@ impl McpToolBuilder {
         desc: impl Into<String>,
     ) -> Self {
         let name = name.into();
-        self.properties.insert(
-            name.clone(),
-            McpPropertySchema {
-                prop_type: "string".to_string(),
-                description: desc.into(),
-                default: None,
-                enum_values: None,
-                items: None,
-            },
-        );
+        self.properties
+            .insert(name.clone(), McpPropertySchema::new("string", desc));
         self.required.push(name);
         self
     }
@@ -207,48 +527,58 @@ impl McpToolBuilder {
     /// Add a number parameter
     pub fn number_param(mut self, name: impl Into<String>, desc: impl Into<String>) -> Self {
         let name = name.into();
-        self.properties.insert(
-            name.clone(),
-            McpPropertySchema {
-                prop_type: "number".to_string(),
-                description: desc.into(),
-                default: None,
-                enum_values: None,
-                items: None,
-            },
-        );
+        self.properties
+            .insert(name.clone(), McpPropertySchema::new("number", desc));
         self
     }
 
     /// Add a boolean parameter
     pub fn bool_param(mut self, name: impl Into<String>, desc: impl Into<String>) -> Self {
         let name = name.into();
-        self.properties.insert(
-            name.clone(),
-            McpPropertySchema {
-                prop_type: "boolean".to_string(),
-                description: desc.into(),
-                default: None,
-                enum_values: None,
-                items: None,
-            },
-        );
+        self.properties
+            .insert(name.clone(), McpPropertySchema::new("boolean", desc));
         self
     }
 
     /// Add an object parameter
     pub fn object_param(mut self, name: impl Into<String>, desc: impl Into<String>) -> Self {
         let name = name.into();
-        self.properties.insert(
-            name.clone(),
-            McpPropertySchema {
-                prop_type: "object".to_string(),
-                description: desc.into(),
-                default: None,
-                enum_values: None,
-                items: None,
-            },
-        );
+        self.properties
+            .insert(name.clone(), McpPropertySchema::new("object", desc));
+        self
+    }
+
+    /// Add an array parameter whose elements match `items`
+    pub fn array_param(
+        mut self,
+        name: impl Into<String>,
+        desc: impl Into<String>,
+        items: McpPropertySchema,
+    ) -> Self {
+        self.properties
+            .insert(name.into(), McpPropertySchema::array(desc, items));
+        self
+    }
+
+    /// Add an object parameter, declaring its fields with `fields`
+    ///
+    /// ```rust,ignore
+    /// McpTool::builder("geocode").object_param_with("point", "Location", |point| {
+    ///     point
+    ///         .with_property("lat", McpPropertySchema::new("number", "").with_range(Some(-90.0), Some(90.0)))
+    ///         .with_property("lon", McpPropertySchema::new("number", "").with_range(Some(-180.0), Some(180.0)))
+    ///         .with_required("lat")
+    ///         .with_required("lon")
+    /// })
+    /// ```
+    pub fn object_param_with(
+        mut self,
+        name: impl Into<String>,
+        desc: impl Into<String>,
+        fields: impl FnOnce(McpPropertySchema) -> McpPropertySchema,
+    ) -> Self {
+        self.properties
+            .insert(name.into(), fields(McpPropertySchema::object(desc)));
         self
     }
 
@@ -263,22 +593,80 @@ impl McpToolBuilder {
         self.properties.insert(
             name.clone(),
             McpPropertySchema {
-                prop_type: "string".to_string(),
-                description: desc.into(),
-                default: None,
                 enum_values: Some(values),
-                items: None,
+                ..McpPropertySchema::new("string", desc)
             },
         );
         self
     }
 
+    /// Add a parameter with an explicit schema
+    pub fn property(mut self, name: impl Into<String>, schema: McpPropertySchema) -> Self {
+        self.properties.insert(name.into(), schema);
+        self
+    }
+
     /// Mark a parameter as required
     pub fn required(mut self, name: impl Into<String>) -> Self {
         self.required.push(name.into());
         self
     }
 
+    /// Set the display title annotation
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.annotations.title = Some(title.into());
+        self
+    }
+
+    /// Hint that the tool does not modify its environment
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.annotations.read_only_hint = Some(read_only);
+        self
+    }
+
+    /// Hint that the tool may perform destructive updates
+    pub fn destructive(mut self, destructive: bool) -> Self {
+        self.annotations.destructive_hint = Some(destructive);
+        self
+    }
+
+    /// Hint that repeated calls with the same arguments have no additional effect
+    pub fn idempotent(mut self, idempotent: bool) -> Self {
+        self.annotations.idempotent_hint = Some(idempotent);
+        self
+    }
+
+    /// Hint that the tool reaches entities outside the host
+    pub fn open_world(mut self, open_world: bool) -> Self {
+        self.annotations.open_world_hint = Some(open_world);
+        self
+    }
+
+    /// Require a permission (manifest format, e.g. "fs:read:~/Documents")
+    pub fn permission(mut self, permission: impl Into<String>) -> Self {
+        self.permissions.push(permission.into());
+        self
+    }
+
+    /// Replace all annotations
+    pub fn annotations(mut self, annotations: McpToolAnnotations) -> Self {
+        self.annotations = annotations;
+        self
+    }
+
+    /// Add every field of the params type `T` as a parameter
+    #[cfg(feature = "tool-schema")]
+    pub fn params_from<T: schemars::JsonSchema>(mut self) -> Self {
+        let schema = McpToolSchema::from_type::<T>();
+        self.properties.extend(schema.properties);
+        for name in schema.required {
+            if !self.required.contains(&name) {
+                self.required.push(name);
+            }
+        }
+        self
+    }
+
     /// Build the MCP tool
     pub fn build(self) -> McpTool {
         McpTool {
@@ -289,6 +677,8 @@ impl McpToolBuilder {
                 properties: self.properties,
                 required: self.required,
             },
+            annotations: (!self.annotations.is_empty()).then_some(self.annotations),
+            permissions: self.permissions,
         }
     }
 }
@@ -298,6 +688,44 @@ impl McpTool {
     pub fn builder(name: impl Into<String>) -> McpToolBuilder {
         McpToolBuilder::new(name)
     }
+
+    /// Schema violations of call `params` against this tool's input schema.
+    ///
+    /// `null` params are checked as an empty object, matching `tools/call`
+    /// requests that omit `arguments`.
+    pub fn param_violations(&self, params: &Value) -> Vec<SchemaViolation> {
+        let schema = match serde_json::to_value(&self.input_schema) {
+            Ok(schema) => schema,
+            Err(_) => return Vec::new(),
+        };
+        if params.is_null() {
+            schema::validate(&schema, &Value::Object(Default::default()))
+        } else {
+            schema::validate(&schema, params)
+        }
+    }
+
+    /// Check call `params` against this tool's input schema
+    pub fn validate_params(&self, params: &Value) -> Result<()> {
+        let violations = self.param_violations(params);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(LensError::InvalidInput(format!(
+                "Invalid params for tool '{}': {}",
+                self.name,
+                schema::describe(&violations)
+            )))
+        }
+    }
+
+    /// Parse the required permission strings, skipping malformed entries
+    pub fn required_permissions(&self) -> Vec<Permission> {
+        self.permissions
+            .iter()
+            .filter_map(|s| Permission::parse(s))
+            .collect()
+    }
 }
 
 /// MCP tool call response
@@ -307,8 +735,21 @@ pub struct McpToolResponse {
     pub content: Vec<McpContent>,
 
     /// Whether this is an error response
-    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[serde(
+        default,
+        rename = "isError",
+        alias = "is_error",
+        skip_serializing_if = "std::ops::Not::not"
+    )]
     pub is_error: bool,
+
+    /// Machine-readable result alongside the content blocks
+    #[serde(
+        default,
+        rename = "structuredContent",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub structured_content: Option<Value>,
 }
 
 /// MCP content block
@@ -321,7 +762,11 @@ pub enum McpContent {
 
     /// Image content (base64)
     #[serde(rename = "image")]
-    Image { data: String, mime_type: String },
+    Image {
+        data: String,
+        #[serde(rename = "mimeType", alias = "mime_type")]
+        mime_type: String,
+    },
 
     /// Resource reference
     #[serde(rename = "resource")]
@@ -336,6 +781,7 @@ impl McpToolResponse {
                 text: content.into(),
             }],
             is_error: false,
+            structured_content: None,
         }
     }
 
@@ -353,8 +799,153 @@ impl McpToolResponse {
                 text: message.into(),
             }],
             is_error: true,
+            structured_content: None,
         }
     }
+
+    /// Attach machine-readable content
+    pub fn with_structured_content(mut self, content: Value) -> Self {
+        self.structured_content = Some(content);
+        self
+    }
+}
+
+/// Conversion from a tool method's return value into an MCP response.
+///
+/// Used by `#[mcp_tools]`; `Result`s map their error to `Err` so the dispatcher
+/// reports it as an `isError` response.
+pub trait IntoToolResponse {
+    /// Convert into a tool response
+    fn into_tool_response(self) -> Result<McpToolResponse>;
+}
+
+/// Wrapper that serializes any value as a JSON text response
+#[derive(Debug, Clone)]
+pub struct Json<T>(pub T);
+
+impl IntoToolResponse for McpToolResponse {
+    fn into_tool_response(self) -> Result<McpToolResponse> {
+        Ok(self)
+    }
+}
+
+impl IntoToolResponse for String {
+    fn into_tool_response(self) -> Result<McpToolResponse> {
+        Ok(McpToolResponse::text(self))
+    }
+}
+
+impl IntoToolResponse for &str {
+    fn into_tool_response(self) -> Result<McpToolResponse> {
+        Ok(McpToolResponse::text(self))
+    }
+}
+
+impl IntoToolResponse for Value {
+    fn into_tool_response(self) -> Result<McpToolResponse> {
+        McpToolResponse::json(&self)
+    }
+}
+
+impl<T: Serialize> IntoToolResponse for Json<T> {
+    fn into_tool_response(self) -> Result<McpToolResponse> {
+        McpToolResponse::json(&self.0)
+    }
+}
+
+impl<T: IntoToolResponse> IntoToolResponse for Result<T> {
+    fn into_tool_response(self) -> Result<McpToolResponse> {
+        self?.into_tool_response()
+    }
+}
+
+/// Deserialize the tool parameter `name` from call `params`.
+///
+/// A missing parameter deserializes from `null`, so `Option<T>` parameters are
+/// optional and anything else reports it as missing.
+pub fn tool_param<T: DeserializeOwned>(params: &Value, name: &str) -> Result<T> {
+    match params.get(name) {
+        Some(value) => serde_json::from_value(value.clone())
+            .map_err(|e| LensError::InvalidInput(format!("Invalid parameter '{}': {}", name, e))),
+        None => serde_json::from_value(Value::Null)
+            .map_err(|_| LensError::InvalidInput(format!("Missing required parameter '{}'", name))),
+    }
+}
+
+/// Call a tool on `lens` after validating `params` against its input schema.
+///
+/// Unknown tools and invalid params are rejected without reaching
+/// `call_tool`, so lenses can rely on required fields being present.
+pub async fn dispatch_tool(
+    lens: &dyn McpServerLens,
+    name: &str,
+    params: Value,
+) -> Result<McpToolResponse> {
+    let tool = lens
+        .mcp_tools()
+        .into_iter()
+        .find(|tool| tool.name == name)
+        .ok_or_else(|| LensError::InvalidInput(format!("Unknown tool: {}", name)))?;
+    tool.validate_params(&params)?;
+    let params = if params.is_null() {
+        Value::Object(Default::default())
+    } else {
+        params
+    };
+    lens.call_tool(name, params).await
+}
+
+/// Call a tool on `lens`, giving up after `timeout` or when `cancel` fires.
+///
+/// A timed-out or cancelled call returns an `isError` response whose
+/// `structuredContent` is `{"error": "timeout", "timeoutMs": ...}` or
+/// `{"error": "cancelled"}`; the tool's future is dropped. Errors from the
+/// tool itself are passed through unchanged.
+pub async fn call_tool_with_timeout(
+    lens: &dyn McpServerLens,
+    name: &str,
+    params: Value,
+    timeout: std::time::Duration,
+    cancel: &CancellationToken,
+) -> Result<McpToolResponse> {
+    run_tool(lens, name, params, Some(timeout), cancel).await
+}
+
+/// Run a tool call, racing it against an optional timeout and `cancel`
+pub(crate) async fn run_tool(
+    lens: &dyn McpServerLens,
+    name: &str,
+    params: Value,
+    timeout: Option<std::time::Duration>,
+    cancel: &CancellationToken,
+) -> Result<McpToolResponse> {
+    let call = lens.call_tool(name, params);
+    let deadline = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        result = call => result,
+        _ = deadline => {
+            let timeout_ms = timeout.unwrap_or_default().as_millis() as u64;
+            Ok(McpToolResponse::error(format!(
+                "Tool '{}' timed out after {}ms",
+                name, timeout_ms
+            ))
+            .with_structured_content(serde_json::json!({
+                "error": "timeout",
+                "timeoutMs": timeout_ms,
+            })))
+        }
+        _ = cancel.cancelled() => Ok(McpToolResponse::error(format!(
+            "Tool '{}' was cancelled",
+            name
+        ))
+        .with_structured_content(serde_json::json!({ "error": "cancelled" }))),
+    }
 }
 
 /// Trait for lenses that expose MCP tools for agent consumption
@@ -410,6 +1001,15 @@ pub trait McpServerLens: Lens {
     fn mcp_server_version(&self) -> String {
         self.version().to_string()
     }
+
+    /// One page of tools for `tools/list`
+    ///
+    /// Defaults to paging through `mcp_tools()` in chunks of
+    /// [`DEFAULT_MCP_PAGE_SIZE`]. Override to issue custom cursors when tools
+    /// are generated from a large backing store.
+    async fn list_tools(&self, cursor: Option<&str>) -> Result<McpPage<McpTool>> {
+        paginate(self.mcp_tools(), cursor, DEFAULT_MCP_PAGE_SIZE)
+    }
 }
 
 #[cfg(test)]
@@ -431,6 +1031,102 @@ mod tests {
         assert!(tool.input_schema.required.contains(&"query".to_string()));
     }
 
+    #[test]
+    fn test_tool_builder_array_and_nested_object() {
+        let tool = McpTool::builder("geocode")
+            .array_param(
+                "tags",
+                "Filter tags",
+                McpPropertySchema::new("string", "Tag").with_length(Some(1), Some(32)),
+            )
+            .object_param_with("point", "Location", |point| {
+                point
+                    .with_property(
+                        "lat",
+                        McpPropertySchema::new("number", "Latitude")
+                            .with_range(Some(-90.0), Some(90.0)),
+                    )
+                    .with_property(
+                        "source",
+                        McpPropertySchema::new("string", "Source URL").with_format("uri"),
+                    )
+                    .with_required("lat")
+            })
+            .required("point")
+            .build();
+
+        let json = serde_json::to_value(&tool.input_schema).unwrap();
+        assert_eq!(json["properties"]["tags"]["type"], "array");
+        assert_eq!(json["properties"]["tags"]["items"]["maxLength"], 32);
+        let point = &json["properties"]["point"];
+        assert_eq!(point["type"], "object");
+        assert_eq!(point["required"], serde_json::json!(["lat"]));
+        assert_eq!(point["properties"]["lat"]["minimum"], -90.0);
+        assert_eq!(point["properties"]["source"]["format"], "uri");
+
+        // Round-trips through the JSON Schema conversion
+        let parsed = McpToolSchema::from_json_schema(&json);
+        let lat = &parsed.properties["point"].properties.as_ref().unwrap()["lat"];
+        assert_eq!(lat.maximum, Some(90.0));
+
+        // Constraints are enforced by the schema validator
+        let valid = serde_json::json!({"point": {"lat": 10.0}, "tags": ["a"]});
+        let invalid = serde_json::json!({"point": {"lat": 120.0}, "tags": [""]});
+        assert!(crate::schema::validate(&json, &valid).is_empty());
+        assert_eq!(crate::schema::validate(&json, &invalid).len(), 2);
+    }
+
+    #[test]
+    fn test_validate_params() {
+        let tool = McpTool::builder("search")
+            .string_param_required("query", "Search query")
+            .number_param("limit", "Maximum results")
+            .build();
+
+        assert!(tool
+            .validate_params(&serde_json::json!({"query": "x"}))
+            .is_ok());
+
+        let violations = tool.param_violations(&serde_json::json!({"limit": "ten"}));
+        assert_eq!(violations.len(), 2);
+        assert!(violations.iter().any(|v| v.pointer == "/limit"));
+
+        let err = tool.validate_params(&Value::Null).unwrap_err().to_string();
+        assert!(err.contains("Invalid params for tool 'search'"), "{}", err);
+    }
+
+    #[test]
+    fn test_tool_annotations() {
+        let plain = McpTool::builder("search").build();
+        assert!(plain.annotations.is_none());
+        assert!(serde_json::to_value(&plain)
+            .unwrap()
+            .get("annotations")
+            .is_none());
+
+        let tool = McpTool::builder("delete_file")
+            .title("Delete file")
+            .read_only(false)
+            .destructive(true)
+            .idempotent(true)
+            .build();
+        let json = serde_json::to_value(&tool).unwrap();
+        assert_eq!(
+            json["annotations"],
+            serde_json::json!({
+                "title": "Delete file",
+                "readOnlyHint": false,
+                "destructiveHint": true,
+                "idempotentHint": true,
+            })
+        );
+
+        let parsed: McpTool = serde_json::from_value(json).unwrap();
+        let annotations = parsed.annotations.unwrap();
+        assert_eq!(annotations.destructive_hint, Some(true));
+        assert_eq!(annotations.open_world_hint, None);
+    }
+
     #[test]
     fn test_tool_builder_with_enum() {
         let tool = McpTool::builder("format")
@@ -484,6 +1180,95 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_schema_from_json_schema() {
+        let schema = McpToolSchema::from_json_schema(&serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": {"type": "string", "description": "Search query"},
+                "limit": {"type": ["integer", "null"], "default": 10},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "mode": {
+                    "description": "Match mode",
+                    "anyOf": [{"type": "string", "enum": ["exact", "fuzzy"]}, {"type": "null"}]
+                }
+            },
+            "required": ["query"]
+        }));
+
+        assert_eq!(schema.required, vec!["query"]);
+        assert_eq!(schema.properties["query"].description, "Search query");
+        assert_eq!(schema.properties["limit"].prop_type, "integer");
+        assert_eq!(
+            schema.properties["limit"].default,
+            Some(serde_json::json!(10))
+        );
+        assert_eq!(
+            schema.properties["tags"].items.as_ref().unwrap().prop_type,
+            "string"
+        );
+        let mode = &schema.properties["mode"];
+        assert_eq!(mode.prop_type, "string");
+        assert_eq!(mode.description, "Match mode");
+        assert_eq!(mode.enum_values.as_ref().unwrap(), &["exact", "fuzzy"]);
+    }
+
+    #[cfg(feature = "tool-schema")]
+    #[test]
+    fn test_params_from_type() {
+        #[derive(serde::Deserialize, schemars::JsonSchema)]
+        #[allow(dead_code)]
+        enum Sort {
+            Relevance,
+            Date,
+        }
+
+        #[derive(serde::Deserialize, schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct SearchParams {
+            /// The search query
+            query: String,
+            /// Maximum results
+            limit: Option<u32>,
+            sort: Option<Sort>,
+            collections: Vec<String>,
+        }
+
+        let tool = McpTool::builder("search")
+            .description("Search")
+            .params_from::<SearchParams>()
+            .bool_param("verbose", "Include snippets")
+            .build();
+
+        let schema = &tool.input_schema;
+        assert_eq!(schema.properties.len(), 5);
+        assert!(schema.required.contains(&"query".to_string()));
+        assert!(schema.required.contains(&"collections".to_string()));
+        assert!(!schema.required.contains(&"limit".to_string()));
+        assert_eq!(schema.properties["query"].description, "The search query");
+        assert_eq!(schema.properties["limit"].prop_type, "integer");
+        assert_eq!(
+            schema.properties["sort"].enum_values.as_ref().unwrap(),
+            &["Relevance", "Date"]
+        );
+        assert_eq!(schema.properties["collections"].prop_type, "array");
+    }
+
+    #[test]
+    fn test_tool_param() {
+        let params = serde_json::json!({"query": "rust", "limit": "ten"});
+        assert_eq!(tool_param::<String>(&params, "query").unwrap(), "rust");
+        assert_eq!(tool_param::<Option<u32>>(&params, "page").unwrap(), None);
+        assert!(tool_param::<u32>(&params, "limit")
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid parameter 'limit'"));
+        assert!(tool_param::<u32>(&params, "page")
+            .unwrap_err()
+            .to_string()
+            .contains("Missing required parameter 'page'"));
+    }
+
     #[test]
     fn test_tool_serialization() {
         let tool = McpTool::builder("test")
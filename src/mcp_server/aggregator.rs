@@ -0,0 +1,382 @@
+//! # MCP Aggregator
+//!
+//! [`McpAggregator`] mounts several [`McpServerLens`] instances behind one MCP
+//! server. Each lens's tools are listed as `<lens_id>__<tool>`, and calls are
+//! routed back to the owning lens with the prefix stripped, so a host can
+//! expose every installed lens on a single stdio or HTTP endpoint:
+//!
+//! ```rust,ignore
+//! let mut aggregator = McpAggregator::new("graphyn-desktop", "1.0.0");
+//! aggregator.mount(Arc::new(BaseLens::new()))?;
+//! aggregator.mount(Arc::new(FigmaLens::new()))?;
+//!
+//! lens::mcp_server::serve_stdio(Arc::new(aggregator)).await?;
+//! ```
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::{McpServerLens, McpTool, McpToolResponse, ToolAuthorizer};
+use crate::context::LensContext;
+use crate::error::{LensError, Result};
+use crate::{Lens, LensResult};
+
+/// Separator between the lens ID and the tool name in namespaced tool names
+pub const TOOL_NAMESPACE_SEPARATOR: &str = "__";
+
+/// Single MCP server exposing the tools of several lenses
+pub struct McpAggregator {
+    name: String,
+    version: String,
+    mounts: Vec<(String, Arc<dyn McpServerLens>)>,
+    authorizer: Option<Arc<dyn ToolAuthorizer>>,
+}
+
+impl McpAggregator {
+    /// Create an empty aggregator reporting `name` and `version` to MCP clients
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            mounts: Vec::new(),
+            authorizer: None,
+        }
+    }
+
+    /// Consult `authorizer` before every call, with the mounted lens's ID
+    pub fn with_authorizer(mut self, authorizer: Arc<dyn ToolAuthorizer>) -> Self {
+        self.authorizer = Some(authorizer);
+        self
+    }
+
+    /// Mount `lens` under its own ID
+    pub fn mount(&mut self, lens: Arc<dyn McpServerLens>) -> Result<()> {
+        let id = lens.id().to_string();
+        self.mount_as(id, lens)
+    }
+
+    /// Mount `lens` under `namespace` instead of its ID
+    ///
+    /// Fails if the namespace is empty, contains the separator, or is taken.
+    pub fn mount_as(
+        &mut self,
+        namespace: impl Into<String>,
+        lens: Arc<dyn McpServerLens>,
+    ) -> Result<()> {
+        let namespace = namespace.into();
+        if namespace.is_empty() || namespace.contains(TOOL_NAMESPACE_SEPARATOR) {
+            return Err(LensError::InvalidInput(format!(
+                "Invalid MCP namespace '{}': must be non-empty and not contain '{}'",
+                namespace, TOOL_NAMESPACE_SEPARATOR
+            )));
+        }
+        if self.mounts.iter().any(|(mounted, _)| *mounted == namespace) {
+            return Err(LensError::InvalidInput(format!(
+                "MCP namespace '{}' is already mounted",
+                namespace
+            )));
+        }
+        self.mounts.push((namespace, lens));
+        Ok(())
+    }
+
+    /// Namespaces in mount order
+    pub fn namespaces(&self) -> Vec<&str> {
+        self.mounts.iter().map(|(ns, _)| ns.as_str()).collect()
+    }
+
+    /// Split a namespaced tool name into its lens and the lens-local tool name
+    fn resolve<'a>(&self, name: &'a str) -> Option<(&Arc<dyn McpServerLens>, &'a str)> {
+        let (namespace, tool) = name.split_once(TOOL_NAMESPACE_SEPARATOR)?;
+        self.mounts
+            .iter()
+            .find(|(mounted, _)| mounted == namespace)
+            .map(|(_, lens)| (lens, tool))
+    }
+}
+
+/// Namespaced tool name for `tool` mounted under `namespace`
+pub fn namespaced_tool_name(namespace: &str, tool: &str) -> String {
+    format!("{}{}{}", namespace, TOOL_NAMESPACE_SEPARATOR, tool)
+}
+
+#[async_trait]
+impl Lens for McpAggregator {
+    fn id(&self) -> &str {
+        &self.name
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    async fn execute(&self, _ctx: LensContext) -> Result<LensResult> {
+        Err(LensError::ExecutionFailed(format!(
+            "{} only serves MCP tools; execute a mounted lens directly",
+            self.name
+        )))
+    }
+
+    fn supports_mcp(&self) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl McpServerLens for McpAggregator {
+    fn mcp_tools(&self) -> Vec<McpTool> {
+        self.mounts
+            .iter()
+            .flat_map(|(namespace, lens)| {
+                lens.mcp_tools().into_iter().map(move |mut tool| {
+                    tool.name = namespaced_tool_name(namespace, &tool.name);
+                    tool
+                })
+            })
+            .collect()
+    }
+
+    async fn call_tool(&self, name: &str, params: Value) -> Result<McpToolResponse> {
+        let (lens, tool) = self
+            .resolve(name)
+            .ok_or_else(|| LensError::InvalidInput(format!("Unknown tool: {}", name)))?;
+        if let Some(authorizer) = &self.authorizer {
+            let definition = lens
+                .mcp_tools()
+                .into_iter()
+                .find(|t| t.name == tool)
+                .ok_or_else(|| LensError::InvalidInput(format!("Unknown tool: {}", name)))?;
+            authorizer.authorize(lens.id(), &definition).await?;
+        }
+        lens.call_tool(tool, params).await
+    }
+
+    fn mcp_server_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn mcp_server_version(&self) -> String {
+        self.version.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_server::protocol::McpDispatcher;
+    use crate::mcp_server::McpContent;
+    use serde_json::json;
+
+    struct PrefixLens {
+        id: &'static str,
+    }
+
+    #[async_trait]
+    impl Lens for PrefixLens {
+        fn id(&self) -> &str {
+            self.id
+        }
+        fn name(&self) -> &str {
+            self.id
+        }
+        fn version(&self) -> &str {
+            "0.1.0"
+        }
+        async fn execute(&self, _ctx: LensContext) -> Result<LensResult> {
+            Ok(LensResult::success(json!({})))
+        }
+    }
+
+    #[async_trait]
+    impl McpServerLens for PrefixLens {
+        fn mcp_tools(&self) -> Vec<McpTool> {
+            vec![
+                McpTool::builder("search")
+                    .string_param_required("query", "Query")
+                    .build(),
+                McpTool::builder("whoami").build(),
+                McpTool::builder("fetch")
+                    .permission("network:api.example.com")
+                    .build(),
+            ]
+        }
+
+        async fn call_tool(&self, name: &str, params: Value) -> Result<McpToolResponse> {
+            match name {
+                "search" => Ok(McpToolResponse::text(format!(
+                    "{}:{}",
+                    self.id, params["query"]
+                ))),
+                "whoami" | "fetch" => Ok(McpToolResponse::text(self.id)),
+                _ => Err(LensError::InvalidInput(format!("Unknown tool: {}", name))),
+            }
+        }
+    }
+
+    fn aggregator() -> McpAggregator {
+        let mut aggregator = McpAggregator::new("desktop", "2.0.0");
+        aggregator
+            .mount(Arc::new(PrefixLens { id: "base" }))
+            .unwrap();
+        aggregator
+            .mount(Arc::new(PrefixLens { id: "figma" }))
+            .unwrap();
+        aggregator
+    }
+
+    fn text(response: &McpToolResponse) -> &str {
+        match &response.content[0] {
+            McpContent::Text { text } => text,
+            other => panic!("unexpected content {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_aggregator_lists_and_routes() {
+        let aggregator = aggregator();
+
+        let names: Vec<String> = aggregator.mcp_tools().into_iter().map(|t| t.name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "base__search",
+                "base__whoami",
+                "base__fetch",
+                "figma__search",
+                "figma__whoami",
+                "figma__fetch"
+            ]
+        );
+
+        let response = aggregator
+            .call_tool("figma__search", json!({"query": "frames"}))
+            .await
+            .unwrap();
+        assert_eq!(text(&response), "figma:\"frames\"");
+
+        let response = aggregator
+            .call_tool("base__whoami", json!({}))
+            .await
+            .unwrap();
+        assert_eq!(text(&response), "base");
+
+        assert!(aggregator.call_tool("search", json!({})).await.is_err());
+        assert!(aggregator
+            .call_tool("slack__search", json!({}))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_tool_validates_through_aggregator() {
+        use crate::mcp_server::dispatch_tool;
+
+        let aggregator = aggregator();
+        let response = dispatch_tool(&aggregator, "base__search", json!({"query": "q"}))
+            .await
+            .unwrap();
+        assert_eq!(text(&response), "base:\"q\"");
+
+        let err = dispatch_tool(&aggregator, "base__search", json!({"query": 3}))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LensError::InvalidInput(_)));
+        assert!(dispatch_tool(&aggregator, "base__nope", json!({}))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_aggregator_rejects_bad_namespaces() {
+        let mut aggregator = aggregator();
+        assert!(aggregator
+            .mount(Arc::new(PrefixLens { id: "base" }))
+            .is_err());
+        assert!(aggregator
+            .mount_as("my__lens", Arc::new(PrefixLens { id: "x" }))
+            .is_err());
+        assert!(aggregator
+            .mount_as("", Arc::new(PrefixLens { id: "x" }))
+            .is_err());
+        aggregator
+            .mount_as("base2", Arc::new(PrefixLens { id: "base" }))
+            .unwrap();
+        assert_eq!(aggregator.namespaces(), vec!["base", "figma", "base2"]);
+    }
+
+    #[tokio::test]
+    async fn test_aggregator_authorizes_with_mounted_lens_id() {
+        use crate::manifest::{SandboxLevel, SecurityConfig};
+        use crate::mcp_server::SandboxAuthorizer;
+
+        let authorizer = SandboxAuthorizer::new().with_lens(
+            "figma",
+            SecurityConfig {
+                library_hash: None,
+                permissions: vec!["network:api.example.com".to_string()],
+                sandbox: SandboxLevel::Network,
+            },
+        );
+        let aggregator = aggregator().with_authorizer(Arc::new(authorizer));
+
+        assert!(aggregator
+            .call_tool("figma__fetch", json!({}))
+            .await
+            .is_ok());
+        assert!(matches!(
+            aggregator.call_tool("base__fetch", json!({})).await,
+            Err(LensError::PermissionDenied(_))
+        ));
+        assert!(aggregator
+            .call_tool("base__whoami", json!({}))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_aggregator_serves_through_dispatcher() {
+        let dispatcher = McpDispatcher::new(Arc::new(aggregator()));
+
+        let init = dispatcher
+            .handle(json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}))
+            .await
+            .unwrap();
+        assert_eq!(init["result"]["serverInfo"]["name"], "desktop");
+        assert_eq!(init["result"]["serverInfo"]["version"], "2.0.0");
+
+        let call = dispatcher
+            .handle(json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": {"name": "base__whoami"},
+            }))
+            .await
+            .unwrap();
+        assert_eq!(call["result"]["content"][0]["text"], "base");
+
+        // A dispatcher-level authorizer sees the aggregator as the lens
+        let dispatcher = McpDispatcher::new(Arc::new(aggregator()))
+            .with_authorizer(Arc::new(crate::mcp_server::SandboxAuthorizer::new()));
+        let denied = dispatcher
+            .handle(json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "tools/call",
+                "params": {"name": "base__fetch"},
+            }))
+            .await
+            .unwrap();
+        assert_eq!(denied["result"]["isError"], true);
+        assert!(denied["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("Permission denied"));
+    }
+}
@@ -0,0 +1,137 @@
+//! # Tool Authorization
+//!
+//! Tools declare the permissions they need with [`McpToolBuilder::permission`];
+//! a host-supplied [`ToolAuthorizer`] decides at call time whether a lens may
+//! run them. [`McpDispatcher`] and [`McpAggregator`] consult the authorizer
+//! before dispatching, so a denied call never reaches `call_tool`.
+//!
+//! [`SandboxAuthorizer`] enforces the manifest's `[security]` section: every
+//! required permission must be listed in `permissions` and allowed by `sandbox`.
+//!
+//! [`McpToolBuilder::permission`]: super::McpToolBuilder::permission
+//! [`McpDispatcher`]: super::McpDispatcher
+//! [`McpAggregator`]: super::McpAggregator
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use super::McpTool;
+use crate::error::{LensError, Result};
+use crate::manifest::{Permission, SecurityConfig};
+
+/// Host policy deciding whether a lens may run a tool
+#[async_trait]
+pub trait ToolAuthorizer: Send + Sync {
+    /// Return `Ok(())` to allow the call, or `LensError::PermissionDenied`
+    async fn authorize(&self, lens_id: &str, tool: &McpTool) -> Result<()>;
+}
+
+/// Authorizer that allows every call
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllowAllTools;
+
+#[async_trait]
+impl ToolAuthorizer for AllowAllTools {
+    async fn authorize(&self, _lens_id: &str, _tool: &McpTool) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Authorizer enforcing each lens's manifest security config
+///
+/// Tools without required permissions are always allowed. A tool that needs
+/// permissions is denied unless its lens is registered and grants all of them.
+#[derive(Debug, Clone, Default)]
+pub struct SandboxAuthorizer {
+    lenses: HashMap<String, SecurityConfig>,
+}
+
+impl SandboxAuthorizer {
+    /// Create an authorizer with no registered lenses
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the security config for `lens_id`
+    pub fn with_lens(mut self, lens_id: impl Into<String>, security: SecurityConfig) -> Self {
+        self.lenses.insert(lens_id.into(), security);
+        self
+    }
+
+    /// Check `tool` against the config registered for `lens_id`
+    pub fn check(&self, lens_id: &str, tool: &McpTool) -> Result<()> {
+        if tool.permissions.is_empty() {
+            return Ok(());
+        }
+        let security = self.lenses.get(lens_id);
+        for (raw, required) in tool
+            .permissions
+            .iter()
+            .map(|raw| (raw, Permission::parse(raw)))
+        {
+            let granted = match (&required, security) {
+                (Some(required), Some(security)) => security.grants(required),
+                _ => false,
+            };
+            if !granted {
+                return Err(LensError::PermissionDenied(format!(
+                    "tool '{}' of lens '{}' requires '{}'",
+                    tool.name, lens_id, raw
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolAuthorizer for SandboxAuthorizer {
+    async fn authorize(&self, lens_id: &str, tool: &McpTool) -> Result<()> {
+        self.check(lens_id, tool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::SandboxLevel;
+
+    fn security(sandbox: SandboxLevel, permissions: &[&str]) -> SecurityConfig {
+        SecurityConfig {
+            library_hash: None,
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            sandbox,
+        }
+    }
+
+    #[test]
+    fn test_sandbox_authorizer() {
+        let authorizer = SandboxAuthorizer::new()
+            .with_lens(
+                "weather",
+                security(SandboxLevel::Network, &["network:api.weather.com"]),
+            )
+            .with_lens("notes", security(SandboxLevel::Restricted, &[]));
+
+        let forecast = McpTool::builder("forecast")
+            .permission("network:api.weather.com")
+            .build();
+        let upload = McpTool::builder("upload")
+            .permission("network:uploads.example.com")
+            .build();
+        let list = McpTool::builder("list").build();
+
+        assert!(authorizer.check("weather", &forecast).is_ok());
+        assert!(authorizer.check("notes", &list).is_ok());
+        assert!(matches!(
+            authorizer.check("weather", &upload),
+            Err(LensError::PermissionDenied(_))
+        ));
+        // Same permission, but the restricted sandbox forbids network access
+        assert!(authorizer.check("notes", &forecast).is_err());
+        // Unregistered lenses only get permission-free tools
+        assert!(authorizer.check("unknown", &forecast).is_err());
+        assert!(authorizer.check("unknown", &list).is_ok());
+    }
+}
@@ -0,0 +1,525 @@
+//! # MCP HTTP Transport
+//!
+//! Serves a lens over MCP "streamable HTTP": clients POST JSON-RPC messages to a
+//! single endpoint (default `/mcp`) and receive either a JSON body or, when they
+//! only accept `text/event-stream`, the same response as a one-event SSE stream.
+//!
+//! Requires the `mcp-http` feature.
+//!
+//! Sessions are tracked with the `Mcp-Session-Id` header issued on `initialize`
+//! and ended with `DELETE` or after [`DEFAULT_SESSION_TTL`] without requests.
+//! Session ids are 128 random bits from the OS. Requests can be gated on
+//! bearer tokens through [`BearerAuth`]:
+//!
+//! ```rust,ignore
+//! McpHttpServer::new(Arc::new(BaseLens::new()))
+//!     .with_auth(StaticBearerTokens::new(["secret-token"]))
+//!     .serve("127.0.0.1:8931".parse()?)
+//!     .await?;
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Router;
+use serde_json::Value;
+use tokio::time::Instant;
+
+use super::protocol::McpDispatcher;
+use super::{McpServerLens, ToolAuthorizer};
+use crate::cancel::CancellationToken;
+use crate::error::Result;
+
+/// Header carrying the MCP session id
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Default endpoint path
+pub const DEFAULT_MCP_PATH: &str = "/mcp";
+
+/// Default time a session may go without requests before it expires
+pub const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// Hook for validating `Authorization: Bearer` tokens
+#[async_trait]
+pub trait BearerAuth: Send + Sync {
+    /// Whether `token` may use the server. `None` means no bearer token was sent.
+    async fn authorize(&self, token: Option<&str>) -> bool;
+}
+
+/// [`BearerAuth`] accepting a fixed set of tokens
+#[derive(Debug, Clone, Default)]
+pub struct StaticBearerTokens {
+    tokens: HashSet<String>,
+}
+
+impl StaticBearerTokens {
+    /// Accept exactly these tokens
+    pub fn new<I, S>(tokens: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            tokens: tokens.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl BearerAuth for StaticBearerTokens {
+    async fn authorize(&self, token: Option<&str>) -> bool {
+        token.is_some_and(|token| self.tokens.contains(token))
+    }
+}
+
+/// Builder for an MCP HTTP server
+pub struct McpHttpServer {
+    lens: Arc<dyn McpServerLens>,
+    path: String,
+    auth: Option<Arc<dyn BearerAuth>>,
+    authorizer: Option<Arc<dyn ToolAuthorizer>>,
+    tool_timeout: Option<Duration>,
+    session_ttl: Duration,
+}
+
+struct ServerState {
+    dispatcher: McpDispatcher,
+    auth: Option<Arc<dyn BearerAuth>>,
+    /// Live sessions and when each last saw a request
+    sessions: Mutex<HashMap<String, Instant>>,
+    session_ttl: Duration,
+}
+
+impl McpHttpServer {
+    /// Serve `lens` at [`DEFAULT_MCP_PATH`] without authentication
+    pub fn new(lens: Arc<dyn McpServerLens>) -> Self {
+        Self {
+            lens,
+            path: DEFAULT_MCP_PATH.to_string(),
+            auth: None,
+            authorizer: None,
+            tool_timeout: None,
+            session_ttl: DEFAULT_SESSION_TTL,
+        }
+    }
+
+    /// Serve at a different endpoint path
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Require requests to pass `auth`
+    pub fn with_auth(mut self, auth: impl BearerAuth + 'static) -> Self {
+        self.auth = Some(Arc::new(auth));
+        self
+    }
+
+    /// Consult `authorizer` before every tool call
+    pub fn with_authorizer(mut self, authorizer: Arc<dyn ToolAuthorizer>) -> Self {
+        self.authorizer = Some(authorizer);
+        self
+    }
+
+    /// End tool calls that run longer than `timeout`
+    pub fn with_tool_timeout(mut self, timeout: Duration) -> Self {
+        self.tool_timeout = Some(timeout);
+        self
+    }
+
+    /// Expire sessions after `ttl` without requests
+    pub fn with_session_ttl(mut self, ttl: Duration) -> Self {
+        self.session_ttl = ttl;
+        self
+    }
+
+    /// Build the axum router, e.g. to nest it into an existing app
+    pub fn router(self) -> Router {
+        let mut dispatcher = McpDispatcher::new(self.lens);
+        if let Some(authorizer) = self.authorizer {
+            dispatcher = dispatcher.with_authorizer(authorizer);
+        }
+        if let Some(timeout) = self.tool_timeout {
+            dispatcher = dispatcher.with_tool_timeout(timeout);
+        }
+        let state = Arc::new(ServerState {
+            dispatcher,
+            auth: self.auth,
+            sessions: Mutex::new(HashMap::new()),
+            session_ttl: self.session_ttl,
+        });
+
+        Router::new()
+            .route(
+                &self.path,
+                post(handle_post).get(handle_get).delete(handle_delete),
+            )
+            .with_state(state)
+    }
+
+    /// Bind `addr` and serve until the task is dropped
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        axum::serve(listener, self.router()).await?;
+        Ok(())
+    }
+}
+
+/// Serve `lens` over streamable HTTP at `addr`, without authentication.
+pub async fn serve_http(lens: Arc<dyn McpServerLens>, addr: SocketAddr) -> Result<()> {
+    McpHttpServer::new(lens).serve(addr).await
+}
+
+impl ServerState {
+    async fn check_auth(&self, headers: &HeaderMap) -> std::result::Result<(), Response> {
+        let Some(auth) = &self.auth else {
+            return Ok(());
+        };
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if auth.authorize(token).await {
+            Ok(())
+        } else {
+            Err((
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+            )
+                .into_response())
+        }
+    }
+
+    fn session<'a>(&self, headers: &'a HeaderMap) -> Option<&'a str> {
+        headers
+            .get(SESSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+    }
+
+    /// Start a session, or `None` if the OS has no randomness to offer
+    fn new_session(&self) -> Option<String> {
+        let mut bytes = [0u8; 16];
+        getrandom::getrandom(&mut bytes).ok()?;
+        let id: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, last_seen| now.duration_since(*last_seen) < self.session_ttl);
+        sessions.insert(id.clone(), now);
+        Some(id)
+    }
+
+    /// Whether `id` is a live session, marking it as just used
+    fn touch_session(&self, id: &str) -> bool {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.get_mut(id) {
+            Some(last_seen) if now.duration_since(*last_seen) < self.session_ttl => {
+                *last_seen = now;
+                true
+            }
+            Some(_) => {
+                sessions.remove(id);
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+async fn handle_post(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    if let Err(response) = state.check_auth(&headers).await {
+        return response;
+    }
+
+    let message: Value = match serde_json::from_str(&body) {
+        Ok(message) => message,
+        Err(_) => {
+            let response = state
+                .dispatcher
+                .handle_line(&body)
+                .await
+                .unwrap_or_default();
+            return json_response(StatusCode::BAD_REQUEST, response);
+        }
+    };
+
+    let is_initialize = message.get("method").and_then(Value::as_str) == Some("initialize");
+    if !is_initialize {
+        match state.session(&headers) {
+            Some(id) if state.touch_session(id) => {}
+            Some(_) => return StatusCode::NOT_FOUND.into_response(),
+            None => {
+                return (StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header").into_response()
+            }
+        }
+    }
+
+    let session = state.session(&headers).unwrap_or_default();
+    let Some(response) = state
+        .dispatcher
+        .handle_in_session(session, message, CancellationToken::new())
+        .await
+    else {
+        // Notifications and client responses
+        return StatusCode::ACCEPTED.into_response();
+    };
+    let initialized = is_initialize && response.get("result").is_some();
+    let body = response.to_string();
+
+    let mut response = if wants_sse_only(&headers) {
+        (
+            [(header::CONTENT_TYPE, "text/event-stream")],
+            format!("event: message\ndata: {}\n\n", body),
+        )
+            .into_response()
+    } else {
+        json_response(StatusCode::OK, body)
+    };
+
+    if initialized {
+        let Some(session) = state.new_session() else {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Cannot create a session").into_response();
+        };
+        if let Ok(value) = HeaderValue::from_str(&session) {
+            response.headers_mut().insert(SESSION_HEADER, value);
+        }
+    }
+    response
+}
+
+async fn handle_get(State(state): State<Arc<ServerState>>, headers: HeaderMap) -> Response {
+    if let Err(response) = state.check_auth(&headers).await {
+        return response;
+    }
+    // No server-initiated messages are sent, so there is no standalone SSE stream
+    StatusCode::METHOD_NOT_ALLOWED.into_response()
+}
+
+async fn handle_delete(State(state): State<Arc<ServerState>>, headers: HeaderMap) -> Response {
+    if let Err(response) = state.check_auth(&headers).await {
+        return response;
+    }
+    match state.session(&headers) {
+        Some(id) if state.sessions.lock().unwrap().remove(id).is_some() => {
+            StatusCode::OK.into_response()
+        }
+        Some(_) => StatusCode::NOT_FOUND.into_response(),
+        None => StatusCode::BAD_REQUEST.into_response(),
+    }
+}
+
+fn wants_sse_only(headers: &HeaderMap) -> bool {
+    let accept = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    accept.contains("text/event-stream") && !accept.contains("application/json")
+}
+
+fn json_response(status: StatusCode, body: String) -> Response {
+    (status, [(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_server::{McpTool, McpToolResponse};
+    use crate::{Lens, LensContext, LensResult};
+    use axum::body::Body;
+    use axum::http::Request;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    struct PingLens;
+
+    #[async_trait]
+    impl Lens for PingLens {
+        fn id(&self) -> &str {
+            "ping"
+        }
+        fn name(&self) -> &str {
+            "Ping"
+        }
+        fn version(&self) -> &str {
+            "0.1.0"
+        }
+        async fn execute(&self, _ctx: LensContext) -> Result<LensResult> {
+            Ok(LensResult::success(json!({})))
+        }
+    }
+
+    #[async_trait]
+    impl McpServerLens for PingLens {
+        fn mcp_tools(&self) -> Vec<McpTool> {
+            vec![McpTool::builder("pong").description("Reply").build()]
+        }
+
+        async fn call_tool(&self, _name: &str, _params: Value) -> Result<McpToolResponse> {
+            Ok(McpToolResponse::text("pong"))
+        }
+    }
+
+    fn post(body: Value, session: Option<&str>, accept: &str) -> Request<Body> {
+        let mut request = Request::post("/mcp")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::ACCEPT, accept)
+            .header(header::AUTHORIZATION, "Bearer secret");
+        if let Some(session) = session {
+            request = request.header(SESSION_HEADER, session);
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    }
+
+    async fn body_text(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_http_session_flow() {
+        let router = McpHttpServer::new(Arc::new(PingLens))
+            .with_auth(StaticBearerTokens::new(["secret"]))
+            .router();
+        let both = "application/json, text/event-stream";
+
+        let init = json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}});
+        let response = router
+            .clone()
+            .oneshot(post(init, None, both))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let session = response.headers()[SESSION_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let list = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"});
+        let response = router
+            .clone()
+            .oneshot(post(list.clone(), None, both))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = router
+            .clone()
+            .oneshot(post(list, Some(&session), both))
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(body["result"]["tools"][0]["name"], "pong");
+
+        let call =
+            json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {"name": "pong"}});
+        let response = router
+            .clone()
+            .oneshot(post(call, Some(&session), "text/event-stream"))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        let events = body_text(response).await;
+        assert!(events.starts_with("event: message\ndata: {"));
+        assert!(events.contains("\"pong\""));
+
+        let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        let response = router
+            .clone()
+            .oneshot(post(notification, Some(&session), both))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        let delete = Request::delete("/mcp")
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .header(SESSION_HEADER, &session)
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            router.clone().oneshot(delete).await.unwrap().status(),
+            StatusCode::OK
+        );
+
+        let ping = json!({"jsonrpc": "2.0", "id": 4, "method": "ping"});
+        let response = router
+            .oneshot(post(ping, Some(&session), both))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_http_sessions_are_random_and_expire() {
+        let router = McpHttpServer::new(Arc::new(PingLens))
+            .with_session_ttl(Duration::from_secs(60))
+            .router();
+        let init = json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"});
+        let mut sessions = Vec::new();
+        for _ in 0..2 {
+            let response = router
+                .clone()
+                .oneshot(post(init.clone(), None, "application/json"))
+                .await
+                .unwrap();
+            sessions.push(
+                response.headers()[SESSION_HEADER]
+                    .to_str()
+                    .unwrap()
+                    .to_string(),
+            );
+        }
+        assert_eq!(sessions[0].len(), 32);
+        assert_ne!(sessions[0], sessions[1]);
+
+        let ping = json!({"jsonrpc": "2.0", "id": 2, "method": "ping"});
+        let status = |session: String| {
+            let router = router.clone();
+            let ping = ping.clone();
+            async move {
+                router
+                    .oneshot(post(ping, Some(&session), "application/json"))
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        // Requests keep a session alive; idle ones expire
+        tokio::time::advance(Duration::from_secs(45)).await;
+        assert_eq!(status(sessions[0].clone()).await, StatusCode::OK);
+        tokio::time::advance(Duration::from_secs(45)).await;
+        assert_eq!(status(sessions[0].clone()).await, StatusCode::OK);
+        assert_eq!(status(sessions[1].clone()).await, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_http_bearer_auth() {
+        let router = McpHttpServer::new(Arc::new(PingLens))
+            .with_auth(StaticBearerTokens::new(["other"]))
+            .router();
+        let init = json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"});
+        let response = router
+            .oneshot(post(init, None, "application/json"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Bearer");
+    }
+}
@@ -0,0 +1,149 @@
+//! # Cursor Pagination
+//!
+//! MCP list methods (`tools/list`, and future `resources/list`) take an
+//! optional opaque `cursor` and return a `nextCursor` while more items remain.
+//! [`paginate`] implements offset cursors over an in-memory list, which is
+//! what [`McpServerLens::list_tools`](super::McpServerLens::list_tools) uses
+//! by default; lenses that generate tools lazily can issue their own cursors.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{LensError, Result};
+
+/// Items per page when a lens doesn't choose its own page size
+pub const DEFAULT_MCP_PAGE_SIZE: usize = 100;
+
+/// One page of a paginated MCP listing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct McpPage<T> {
+    /// Items on this page
+    pub items: Vec<T>,
+
+    /// Cursor for the next page; `None` on the last page
+    #[serde(
+        default,
+        rename = "nextCursor",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> McpPage<T> {
+    /// A page holding every item, with no further pages
+    pub fn complete(items: Vec<T>) -> Self {
+        Self {
+            items,
+            next_cursor: None,
+        }
+    }
+}
+
+/// Slice `items` into the page starting at `cursor`.
+///
+/// Cursors are opaque to clients; a cursor that this function didn't issue is
+/// rejected with `InvalidInput`.
+pub fn paginate<T>(items: Vec<T>, cursor: Option<&str>, page_size: usize) -> Result<McpPage<T>> {
+    let offset = match cursor {
+        None => 0,
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .ok()
+            .filter(|offset| *offset <= items.len())
+            .ok_or_else(|| LensError::InvalidInput(format!("Invalid cursor: {}", cursor)))?,
+    };
+    let page_size = page_size.max(1);
+    let end = offset.saturating_add(page_size).min(items.len());
+    let next_cursor = (end < items.len()).then(|| end.to_string());
+
+    Ok(McpPage {
+        items: items.into_iter().skip(offset).take(end - offset).collect(),
+        next_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate_walks_all_pages() {
+        let items: Vec<u32> = (0..5).collect();
+
+        let first = paginate(items.clone(), None, 2).unwrap();
+        assert_eq!(first.items, vec![0, 1]);
+        let second = paginate(items.clone(), first.next_cursor.as_deref(), 2).unwrap();
+        assert_eq!(second.items, vec![2, 3]);
+        let last = paginate(items.clone(), second.next_cursor.as_deref(), 2).unwrap();
+        assert_eq!(last.items, vec![4]);
+        assert_eq!(last.next_cursor, None);
+
+        assert!(paginate(items.clone(), Some("bogus"), 2).is_err());
+        assert!(paginate(items, Some("9"), 2).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tools_list_pagination() {
+        use crate::mcp_server::{McpDispatcher, McpServerLens, McpTool, McpToolResponse};
+        use crate::{Lens, LensContext, LensResult};
+        use async_trait::async_trait;
+        use serde_json::{json, Value};
+        use std::sync::Arc;
+
+        struct CollectionsLens;
+
+        #[async_trait]
+        impl Lens for CollectionsLens {
+            fn id(&self) -> &str {
+                "collections"
+            }
+            fn name(&self) -> &str {
+                "Collections"
+            }
+            fn version(&self) -> &str {
+                "1.0.0"
+            }
+            async fn execute(&self, _ctx: LensContext) -> Result<LensResult> {
+                Ok(LensResult::success(json!({})))
+            }
+        }
+
+        #[async_trait]
+        impl McpServerLens for CollectionsLens {
+            fn mcp_tools(&self) -> Vec<McpTool> {
+                (0..3)
+                    .map(|i| McpTool::builder(format!("search_{}", i)).build())
+                    .collect()
+            }
+            async fn call_tool(&self, _name: &str, _params: Value) -> Result<McpToolResponse> {
+                Ok(McpToolResponse::text("ok"))
+            }
+            async fn list_tools(&self, cursor: Option<&str>) -> Result<McpPage<McpTool>> {
+                paginate(self.mcp_tools(), cursor, 2)
+            }
+        }
+
+        let dispatcher = McpDispatcher::new(Arc::new(CollectionsLens));
+        let list = |params: Value| json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list", "params": params});
+
+        let first = dispatcher.handle(list(json!({}))).await.unwrap();
+        assert_eq!(first["result"]["tools"].as_array().unwrap().len(), 2);
+        let cursor = first["result"]["nextCursor"].clone();
+        assert!(cursor.is_string());
+
+        let second = dispatcher
+            .handle(list(json!({ "cursor": cursor })))
+            .await
+            .unwrap();
+        assert_eq!(second["result"]["tools"][0]["name"], "search_2");
+        assert!(second["result"].get("nextCursor").is_none());
+
+        let invalid = dispatcher
+            .handle(list(json!({"cursor": "not-a-cursor"})))
+            .await
+            .unwrap();
+        assert_eq!(
+            invalid["error"]["code"],
+            crate::mcp_server::protocol::INVALID_PARAMS
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! # MCP JSON-RPC Protocol
+//!
+//! Transport-agnostic handling of MCP messages for a single [`McpServerLens`].
+//! Transports (stdio, HTTP) feed decoded JSON-RPC messages to [`McpDispatcher`]
+//! and write back whatever it returns.
+//!
+//! Supported methods: `initialize`, `notifications/initialized`, `ping`,
+//! `tools/list` (paginated via `cursor`/`nextCursor`), and `tools/call`. Tool
+//! failures are reported in-band as `isError` results, as the MCP spec
+//! requires; only malformed requests,
+//! unknown methods, unknown tools, and arguments that fail the tool's input
+//! schema produce JSON-RPC errors. Schema violations are listed in the error's
+//! `data.violations`.
+//!
+//! Tool calls can be bounded with [`McpDispatcher::with_tool_timeout`] and are
+//! cancelled by the client's `notifications/cancelled`, or by the token passed
+//! to [`McpDispatcher::handle_cancellable`]. Either way the call ends with an
+//! `isError` result instead of holding the connection. Transports sharing one
+//! dispatcher between sessions use [`McpDispatcher::handle_in_session`], so a
+//! cancellation only reaches calls from the session that sent it.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use super::{run_tool, McpServerLens, McpToolResponse, ToolAuthorizer};
+use crate::cancel::CancellationToken;
+use crate::error::LensError;
+use crate::schema;
+
+/// Newest MCP protocol revision this server speaks
+pub const MCP_PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Protocol revisions accepted during `initialize`, newest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// JSON-RPC: invalid JSON
+pub const PARSE_ERROR: i64 = -32700;
+/// JSON-RPC: not a valid request object
+pub const INVALID_REQUEST: i64 = -32600;
+/// JSON-RPC: unknown method
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC: invalid method parameters
+pub const INVALID_PARAMS: i64 = -32602;
+/// JSON-RPC: internal server error
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// Routes MCP JSON-RPC messages to a lens
+pub struct McpDispatcher {
+    lens: Arc<dyn McpServerLens>,
+    authorizer: Option<Arc<dyn ToolAuthorizer>>,
+    tool_timeout: Option<Duration>,
+    /// Cancellation tokens of in-flight tool calls, keyed by session id and
+    /// serialized request id
+    in_flight: Mutex<HashMap<(String, String), CancellationToken>>,
+}
+
+impl McpDispatcher {
+    /// Create a dispatcher for `lens`
+    pub fn new(lens: Arc<dyn McpServerLens>) -> Self {
+        Self {
+            lens,
+            authorizer: None,
+            tool_timeout: None,
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// End tool calls that run longer than `timeout`
+    pub fn with_tool_timeout(mut self, timeout: Duration) -> Self {
+        self.tool_timeout = Some(timeout);
+        self
+    }
+
+    /// Consult `authorizer` before every tool call
+    pub fn with_authorizer(mut self, authorizer: Arc<dyn ToolAuthorizer>) -> Self {
+        self.authorizer = Some(authorizer);
+        self
+    }
+
+    /// Handle one line of newline-delimited JSON.
+    ///
+    /// Returns the serialized response, or `None` for notifications and blank lines.
+    pub async fn handle_line(&self, line: &str) -> Option<String> {
+        if line.trim().is_empty() {
+            return None;
+        }
+        let response = match serde_json::from_str::<Value>(line) {
+            Ok(message) => self.handle(message).await?,
+            Err(e) => error_response(Value::Null, PARSE_ERROR, &format!("Parse error: {}", e)),
+        };
+        Some(response.to_string())
+    }
+
+    /// Handle one decoded JSON-RPC message.
+    ///
+    /// Returns `None` for notifications (messages without an `id`).
+    pub async fn handle(&self, message: Value) -> Option<Value> {
+        self.handle_cancellable(message, CancellationToken::new())
+            .await
+    }
+
+    /// Handle one decoded JSON-RPC message, abandoning a tool call when
+    /// `cancel` fires (e.g. because the transport connection closed).
+    pub async fn handle_cancellable(
+        &self,
+        message: Value,
+        cancel: CancellationToken,
+    ) -> Option<Value> {
+        self.handle_in_session("", message, cancel).await
+    }
+
+    /// Handle one decoded JSON-RPC message sent in `session`.
+    ///
+    /// Request ids are only unique within a session, so
+    /// `notifications/cancelled` only cancels calls made in the same session.
+    pub async fn handle_in_session(
+        &self,
+        session: &str,
+        message: Value,
+        cancel: CancellationToken,
+    ) -> Option<Value> {
+        let Some(request) = message.as_object() else {
+            return Some(error_response(
+                Value::Null,
+                INVALID_REQUEST,
+                "Invalid request: expected a JSON-RPC object",
+            ));
+        };
+
+        let id = request.get("id").cloned();
+        let method = request.get("method").and_then(Value::as_str);
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        let Some(method) = method else {
+            // Responses to server-initiated requests are not expected; ignore them
+            if request.contains_key("result") || request.contains_key("error") {
+                return None;
+            }
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "Invalid request: missing method",
+            ));
+        };
+
+        if method == "notifications/cancelled" {
+            if let Some(request_id) = params.get("requestId") {
+                self.cancel_request(session, request_id);
+            }
+            return None;
+        }
+
+        // Notifications never get a response, even when they fail
+        let id = id?;
+
+        let outcome = match method {
+            "initialize" => Ok(self.initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => self.list_tools(&params).await,
+            "tools/call" => self.call_tool(session, &id, params, cancel).await,
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Method not found: {}", method),
+            )),
+        };
+
+        Some(match outcome {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => error.into_response(id),
+        })
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let requested = params.get("protocolVersion").and_then(Value::as_str);
+        let version = requested
+            .filter(|v| SUPPORTED_PROTOCOL_VERSIONS.contains(v))
+            .unwrap_or(MCP_PROTOCOL_VERSION);
+
+        json!({
+            "protocolVersion": version,
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": {
+                "name": self.lens.mcp_server_name(),
+                "version": self.lens.mcp_server_version(),
+            },
+        })
+    }
+
+    async fn list_tools(&self, params: &Value) -> Result<Value, RpcError> {
+        let cursor = params.get("cursor").and_then(Value::as_str);
+        let page = self.lens.list_tools(cursor).await.map_err(|e| match e {
+            LensError::InvalidInput(message) => RpcError::new(INVALID_PARAMS, message),
+            other => RpcError::new(INTERNAL_ERROR, other.to_string()),
+        })?;
+
+        let mut result = json!({ "tools": page.items });
+        if let Some(next_cursor) = page.next_cursor {
+            result["nextCursor"] = Value::String(next_cursor);
+        }
+        Ok(result)
+    }
+
+    fn cancel_request(&self, session: &str, request_id: &Value) {
+        let key = (session.to_string(), request_id.to_string());
+        if let Some(token) = self.in_flight.lock().unwrap().get(&key) {
+            token.cancel();
+        }
+    }
+
+    async fn call_tool(
+        &self,
+        session: &str,
+        id: &Value,
+        params: Value,
+        cancel: CancellationToken,
+    ) -> Result<Value, RpcError> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "tools/call requires a tool name"))?;
+
+        let tools = self.lens.mcp_tools();
+        let Some(tool) = tools.iter().find(|tool| tool.name == name) else {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                format!("Unknown tool: {}", name),
+            ));
+        };
+
+        let arguments = match params.get("arguments") {
+            None | Some(Value::Null) => json!({}),
+            Some(arguments) => arguments.clone(),
+        };
+
+        let violations = tool.param_violations(&arguments);
+        if !violations.is_empty() {
+            return Err(RpcError {
+                code: INVALID_PARAMS,
+                message: format!(
+                    "Invalid params for tool '{}': {}",
+                    name,
+                    schema::describe(&violations)
+                ),
+                data: Some(json!({ "violations": violations })),
+            });
+        }
+
+        let authorized = match &self.authorizer {
+            Some(authorizer) => authorizer.authorize(self.lens.id(), tool).await,
+            None => Ok(()),
+        };
+        let outcome = match authorized {
+            Ok(()) => {
+                let key = (session.to_string(), id.to_string());
+                self.in_flight
+                    .lock()
+                    .unwrap()
+                    .insert(key.clone(), cancel.clone());
+                let outcome =
+                    run_tool(&*self.lens, name, arguments, self.tool_timeout, &cancel).await;
+                self.in_flight.lock().unwrap().remove(&key);
+                outcome
+            }
+            Err(e) => Err(e),
+        };
+        let response = match outcome {
+            Ok(response) => response,
+            Err(e) => McpToolResponse::error(e.to_string()),
+        };
+        serde_json::to_value(response).map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()))
+    }
+}
+
+/// JSON-RPC error produced while handling a request
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn into_response(self, id: Value) -> Value {
+        let mut response = error_response(id, self.code, &self.message);
+        if let Some(data) = self.data {
+            response["error"]["data"] = data;
+        }
+        response
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
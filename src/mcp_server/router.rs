@@ -0,0 +1,212 @@
+//! # Typed Tool Router
+//!
+//! [`McpRouter`] maps tool names to typed async handlers. Before a handler runs,
+//! call params are checked against the tool's input schema and deserialized into
+//! the handler's `Params<T>`; afterwards the return value is converted with
+//! [`IntoToolResponse`]. A `call_tool` implementation becomes a one-line delegation:
+//!
+//! ```rust,ignore
+//! #[derive(Deserialize)]
+//! struct SearchParams { query: String, limit: Option<u32> }
+//!
+//! let kb = self.kb.clone();
+//! let router = McpRouter::new().route(
+//!     McpTool::builder("search").string_param_required("query", "Search query").build(),
+//!     move |Params(p): Params<SearchParams>| {
+//!         let kb = kb.clone();
+//!         async move { Ok(Json(kb.search(&p.query, p.limit).await)) }
+//!     },
+//! );
+//!
+//! // in McpServerLens
+//! fn mcp_tools(&self) -> Vec<McpTool> { self.router.tools() }
+//! async fn call_tool(&self, name: &str, params: Value) -> Result<McpToolResponse> {
+//!     self.router.call(name, params).await
+//! }
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::{IntoToolResponse, McpTool, McpToolResponse};
+use crate::error::{LensError, Result};
+
+/// Deserialized tool call parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct Params<T>(pub T);
+
+type HandlerFuture = Pin<Box<dyn Future<Output = Result<McpToolResponse>> + Send>>;
+type Handler = Box<dyn Fn(Value) -> HandlerFuture + Send + Sync>;
+
+struct Route {
+    tool: McpTool,
+    handler: Handler,
+}
+
+/// Name-based router from MCP tool calls to typed handlers
+#[derive(Default)]
+pub struct McpRouter {
+    routes: Vec<Route>,
+}
+
+impl McpRouter {
+    /// Create an empty router
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `handler` for `tool`, replacing any route with the same name
+    pub fn route<T, R, F, Fut>(mut self, tool: McpTool, handler: F) -> Self
+    where
+        T: DeserializeOwned + Send + 'static,
+        R: IntoToolResponse + 'static,
+        F: Fn(Params<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R>> + Send + 'static,
+    {
+        let name = tool.name.clone();
+        let handler: Handler = Box::new(move |params: Value| {
+            let parsed = serde_json::from_value::<T>(params).map_err(|e| {
+                LensError::InvalidInput(format!("Invalid params for tool '{}': {}", name, e))
+            });
+            match parsed {
+                Ok(params) => {
+                    let future = handler(Params(params));
+                    Box::pin(async move { future.await.into_tool_response() })
+                }
+                Err(e) => Box::pin(async move { Err(e) }),
+            }
+        });
+
+        self.routes.retain(|route| route.tool.name != tool.name);
+        self.routes.push(Route { tool, handler });
+        self
+    }
+
+    /// Tool definitions in registration order
+    pub fn tools(&self) -> Vec<McpTool> {
+        self.routes.iter().map(|route| route.tool.clone()).collect()
+    }
+
+    /// Whether a tool named `name` is registered
+    pub fn has_tool(&self, name: &str) -> bool {
+        self.routes.iter().any(|route| route.tool.name == name)
+    }
+
+    /// Validate, deserialize, and dispatch a tool call
+    pub async fn call(&self, name: &str, params: Value) -> Result<McpToolResponse> {
+        let route = self
+            .routes
+            .iter()
+            .find(|route| route.tool.name == name)
+            .ok_or_else(|| LensError::InvalidInput(format!("Unknown tool: {}", name)))?;
+
+        route.tool.validate_params(&params)?;
+        let params = if params.is_null() {
+            Value::Object(Default::default())
+        } else {
+            params
+        };
+
+        (route.handler)(params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_server::{Json, McpContent};
+    use serde::Deserialize;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Deserialize)]
+    struct AddParams {
+        a: i64,
+        b: i64,
+    }
+
+    fn router(calls: Arc<AtomicUsize>) -> McpRouter {
+        McpRouter::new()
+            .route(
+                McpTool::builder("add")
+                    .number_param("a", "Left")
+                    .number_param("b", "Right")
+                    .required("a")
+                    .required("b")
+                    .build(),
+                move |Params(p): Params<AddParams>| {
+                    let calls = calls.clone();
+                    async move {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        Ok(Json(serde_json::json!({ "sum": p.a + p.b })))
+                    }
+                },
+            )
+            .route(
+                McpTool::builder("version").build(),
+                |_: Params<Value>| async { Ok("1.0.0") },
+            )
+    }
+
+    fn text(response: &McpToolResponse) -> &str {
+        match &response.content[0] {
+            McpContent::Text { text } => text,
+            other => panic!("unexpected content {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_dispatch() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = router(calls.clone());
+
+        let names: Vec<String> = router.tools().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, vec!["add", "version"]);
+
+        let response = router
+            .call("add", serde_json::json!({"a": 2, "b": 3}))
+            .await
+            .unwrap();
+        assert!(text(&response).contains("\"sum\": 5"));
+
+        let response = router.call("version", Value::Null).await.unwrap();
+        assert_eq!(text(&response), "1.0.0");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_router_rejects_bad_params() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = router(calls.clone());
+
+        let missing = router
+            .call("add", serde_json::json!({"a": 2}))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            missing.contains("Invalid params for tool 'add'"),
+            "{}",
+            missing
+        );
+        assert!(missing.contains('b'), "{}", missing);
+
+        // Schema says number; the handler wants an integer
+        let fractional = router
+            .call("add", serde_json::json!({"a": 2.5, "b": 1}))
+            .await
+            .unwrap_err();
+        assert!(fractional.to_string().contains("Invalid params"));
+
+        assert!(router
+            .call("subtract", Value::Null)
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown tool: subtract"));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,349 @@
+//! # MCP stdio Transport
+//!
+//! Serves a lens over newline-delimited JSON-RPC on stdin/stdout, the transport
+//! MCP clients use when they spawn a server process. Anything a lens logs must
+//! go to stderr; stdout carries protocol messages only.
+
+use std::sync::Arc;
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+use super::protocol::McpDispatcher;
+use super::McpServerLens;
+use crate::error::Result;
+
+/// Serve `lens` as an MCP server on the process's stdin and stdout.
+///
+/// Returns when stdin is closed.
+///
+/// ```rust,ignore
+/// #[tokio::main]
+/// async fn main() -> lens::Result<()> {
+///     lens::mcp_server::serve_stdio(Arc::new(BaseLens::new())).await
+/// }
+/// ```
+pub async fn serve_stdio(lens: Arc<dyn McpServerLens>) -> Result<()> {
+    serve(lens, tokio::io::stdin(), tokio::io::stdout()).await
+}
+
+/// Serve `lens` over any newline-delimited reader/writer pair.
+pub async fn serve<R, W>(lens: Arc<dyn McpServerLens>, reader: R, writer: W) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    serve_dispatcher(McpDispatcher::new(lens), reader, writer).await
+}
+
+/// Serve a configured dispatcher (e.g. with a tool timeout or authorizer).
+///
+/// Messages are handled concurrently, so a slow tool call doesn't block
+/// `ping` or the `notifications/cancelled` that aborts it; responses are
+/// written in completion order. After EOF, in-flight calls run to completion.
+pub async fn serve_dispatcher<R, W>(
+    dispatcher: McpDispatcher,
+    reader: R,
+    mut writer: W,
+) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let dispatcher = Arc::new(dispatcher);
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    let mut lines = BufReader::new(reader).lines();
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else { break };
+                let dispatcher = dispatcher.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    if let Some(response) = dispatcher.handle_line(&line).await {
+                        let _ = tx.send(response);
+                    }
+                });
+            }
+            Some(response) = rx.recv() => write_line(&mut writer, &response).await?,
+        }
+    }
+
+    // Every handler holds a sender, so this drains until the last one finishes
+    drop(tx);
+    while let Some(response) = rx.recv().await {
+        write_line(&mut writer, &response).await?;
+    }
+
+    Ok(())
+}
+
+async fn write_line<W: AsyncWrite + Unpin>(writer: &mut W, line: &str) -> Result<()> {
+    writer.write_all(line.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LensError;
+    use crate::mcp_server::protocol::{
+        INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR,
+    };
+    use crate::mcp_server::{McpTool, McpToolResponse};
+    use crate::CancellationToken;
+    use crate::{Lens, LensContext, LensResult};
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use std::time::Duration;
+
+    struct EchoLens;
+
+    #[async_trait]
+    impl Lens for EchoLens {
+        fn id(&self) -> &str {
+            "echo"
+        }
+        fn name(&self) -> &str {
+            "Echo"
+        }
+        fn version(&self) -> &str {
+            "1.2.0"
+        }
+        async fn execute(&self, _ctx: LensContext) -> Result<LensResult> {
+            Ok(LensResult::success(json!({})))
+        }
+    }
+
+    #[async_trait]
+    impl McpServerLens for EchoLens {
+        fn mcp_tools(&self) -> Vec<McpTool> {
+            vec![
+                McpTool::builder("echo")
+                    .description("Echo the input")
+                    .string_param_required("text", "Text to echo")
+                    .build(),
+                McpTool::builder("fail").description("Always fails").build(),
+                McpTool::builder("hang")
+                    .description("Never returns")
+                    .build(),
+            ]
+        }
+
+        async fn call_tool(&self, name: &str, params: Value) -> Result<McpToolResponse> {
+            match name {
+                "echo" => Ok(McpToolResponse::text(
+                    params["text"].as_str().unwrap_or_default(),
+                )),
+                "hang" => std::future::pending().await,
+                _ => Err(LensError::ExecutionFailed("boom".to_string())),
+            }
+        }
+    }
+
+    async fn run(input: &[Value]) -> Vec<Value> {
+        let mut stdin = String::new();
+        for message in input {
+            stdin.push_str(&message.to_string());
+            stdin.push('\n');
+        }
+        stdin.push_str("{not json\n");
+
+        let mut stdout = Vec::new();
+        serve(Arc::new(EchoLens), stdin.as_bytes(), &mut stdout)
+            .await
+            .unwrap();
+
+        String::from_utf8(stdout)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    /// Responses arrive in completion order; look them up by id
+    fn by_id(responses: &[Value], id: Value) -> &Value {
+        responses
+            .iter()
+            .find(|response| response["id"] == id)
+            .unwrap_or_else(|| panic!("no response with id {}", id))
+    }
+
+    fn anonymous_error_codes(responses: &[Value]) -> Vec<i64> {
+        let mut codes: Vec<i64> = responses
+            .iter()
+            .filter(|response| response["id"].is_null())
+            .filter_map(|response| response["error"]["code"].as_i64())
+            .collect();
+        codes.sort();
+        codes
+    }
+
+    #[tokio::test]
+    async fn test_stdio_handshake_and_tools() {
+        let responses = run(&[
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "0"}
+            }}),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {
+                "name": "echo", "arguments": {"text": "hi"}
+            }}),
+            json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call", "params": {"name": "fail"}}),
+        ])
+        .await;
+
+        // The initialized notification gets no response; the bad line gets a parse error
+        assert_eq!(responses.len(), 5);
+
+        let init = &by_id(&responses, json!(1))["result"];
+        assert_eq!(init["protocolVersion"], "2024-11-05");
+        assert_eq!(init["serverInfo"]["name"], "graphyn-echo");
+        assert_eq!(init["serverInfo"]["version"], "1.2.0");
+        assert!(init["capabilities"]["tools"].is_object());
+
+        let tools = by_id(&responses, json!(2))["result"]["tools"]
+            .as_array()
+            .unwrap();
+        assert_eq!(tools.len(), 3);
+        assert_eq!(tools[0]["inputSchema"]["required"], json!(["text"]));
+
+        assert_eq!(
+            by_id(&responses, json!(3))["result"]["content"][0]["text"],
+            "hi"
+        );
+
+        let failed = &by_id(&responses, json!(4))["result"];
+        assert_eq!(failed["isError"], true);
+        assert!(failed["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("boom"));
+
+        assert_eq!(anonymous_error_codes(&responses), vec![PARSE_ERROR]);
+    }
+
+    #[tokio::test]
+    async fn test_stdio_protocol_errors() {
+        let responses = run(&[
+            json!({"jsonrpc": "2.0", "id": "a", "method": "resources/list"}),
+            json!({"jsonrpc": "2.0", "id": "b", "method": "tools/call", "params": {"name": "nope"}}),
+            json!({"jsonrpc": "2.0", "id": "c", "method": "ping"}),
+            json!([1, 2]),
+            json!({"jsonrpc": "2.0", "id": "d", "method": "tools/call", "params": {
+                "name": "echo", "arguments": {"text": 42}
+            }}),
+        ])
+        .await;
+
+        assert_eq!(
+            by_id(&responses, json!("a"))["error"]["code"],
+            METHOD_NOT_FOUND
+        );
+        let unknown = &by_id(&responses, json!("b"))["error"];
+        assert_eq!(unknown["code"], INVALID_PARAMS);
+        assert!(unknown["message"]
+            .as_str()
+            .unwrap()
+            .contains("Unknown tool: nope"));
+        assert_eq!(by_id(&responses, json!("c"))["result"], json!({}));
+        assert_eq!(
+            anonymous_error_codes(&responses),
+            vec![PARSE_ERROR, INVALID_REQUEST]
+        );
+
+        // Schema violations are rejected before the lens runs
+        let invalid = &by_id(&responses, json!("d"))["error"];
+        assert_eq!(invalid["code"], INVALID_PARAMS);
+        assert_eq!(invalid["data"]["violations"][0]["pointer"], "/text");
+    }
+
+    #[tokio::test]
+    async fn test_tool_timeout_and_cancellation() {
+        let dispatcher = Arc::new(
+            McpDispatcher::new(Arc::new(EchoLens)).with_tool_timeout(Duration::from_millis(50)),
+        );
+        let call = |id: i64| json!({"jsonrpc": "2.0", "id": id, "method": "tools/call", "params": {"name": "hang"}});
+
+        let timed_out = dispatcher.handle(call(1)).await.unwrap();
+        assert_eq!(timed_out["result"]["isError"], true);
+        assert_eq!(timed_out["result"]["structuredContent"]["error"], "timeout");
+        assert_eq!(timed_out["result"]["structuredContent"]["timeoutMs"], 50);
+
+        // A client cancellation ends the call before the timeout
+        let dispatcher = Arc::new(McpDispatcher::new(Arc::new(EchoLens)));
+        let pending = tokio::spawn({
+            let dispatcher = dispatcher.clone();
+            async move { dispatcher.handle(call(2)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let ack = dispatcher
+            .handle(json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/cancelled",
+                "params": {"requestId": 2, "reason": "user abort"},
+            }))
+            .await;
+        assert!(ack.is_none());
+
+        let cancelled = tokio::time::timeout(Duration::from_secs(5), pending)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(cancelled["id"], 2);
+        assert_eq!(
+            cancelled["result"]["structuredContent"]["error"],
+            "cancelled"
+        );
+
+        // So does the caller's own token
+        let token = CancellationToken::new();
+        token.cancel();
+        let response = dispatcher.handle_cancellable(call(3), token).await.unwrap();
+        assert_eq!(response["result"]["isError"], true);
+
+        // Another session reusing the request id cannot cancel the call
+        let cancel = |session: &'static str| {
+            let dispatcher = dispatcher.clone();
+            async move {
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "notifications/cancelled",
+                    "params": {"requestId": 4},
+                });
+                dispatcher
+                    .handle_in_session(session, notification, CancellationToken::new())
+                    .await
+            }
+        };
+        let pending = tokio::spawn({
+            let dispatcher = dispatcher.clone();
+            async move {
+                dispatcher
+                    .handle_in_session("a", call(4), CancellationToken::new())
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        cancel("b").await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!pending.is_finished());
+        cancel("a").await;
+        let cancelled = tokio::time::timeout(Duration::from_secs(5), pending)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            cancelled["result"]["structuredContent"]["error"],
+            "cancelled"
+        );
+    }
+}
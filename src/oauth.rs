@@ -1,15 +1,127 @@
 use async_trait::async_trait;
-use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
 use thiserror::Error;
 
+mod cache;
+pub mod device_flow;
+#[cfg(feature = "runtime")]
+mod file;
+
+pub use cache::{CachingOAuthBroker, DEFAULT_EXPIRY_SKEW};
+#[cfg(feature = "runtime")]
+pub use file::{FileOAuthBroker, OAUTH_KEY_FILENAME};
+
 /// Broker interface for fetching OAuth tokens on behalf of lenses.
 #[async_trait]
 pub trait OAuthBroker: Send + Sync {
     /// Fetch a token for the given provider (e.g. "figma").
+    ///
+    /// Brokers should return a token that is valid right now, refreshing an
+    /// expired one first when they can. `OAuthError::Expired` means the broker
+    /// could not; `OAuthError::RefreshFailed` that it tried and failed.
     async fn get_token(&self, provider: &str) -> Result<OAuthToken, OAuthError>;
 
+    /// Fetch a token that carries at least `scopes`.
+    ///
+    /// Fails with `OAuthError::InsufficientScope` listing the missing scopes,
+    /// which hosts can use to prompt for incremental authorization. The default
+    /// checks the token from `get_token`; brokers that can request additional
+    /// scopes should override it.
+    async fn get_token_with_scopes(
+        &self,
+        provider: &str,
+        scopes: &[&str],
+    ) -> Result<OAuthToken, OAuthError> {
+        let token = self.get_token(provider).await?;
+        let missing = token.missing_scopes(scopes);
+        if missing.is_empty() {
+            Ok(token)
+        } else {
+            Err(OAuthError::InsufficientScope { missing })
+        }
+    }
+
     /// Check whether the user has connected the provider.
     async fn is_connected(&self, provider: &str) -> bool;
+
+    /// Accounts the user has connected for the provider (e.g. two Figma
+    /// workspaces). Brokers that keep a single account per provider return an
+    /// empty list, the default.
+    async fn list_accounts(&self, _provider: &str) -> Result<Vec<OAuthAccount>, OAuthError> {
+        Ok(Vec::new())
+    }
+
+    /// Fetch a token for a specific account from `list_accounts`.
+    async fn get_token_for_account(
+        &self,
+        provider: &str,
+        account_id: &str,
+    ) -> Result<OAuthToken, OAuthError> {
+        Err(OAuthError::AccountNotFound {
+            provider: provider.to_string(),
+            account: account_id.to_string(),
+        })
+    }
+
+    /// Force a refresh of the provider's token and return the new one.
+    ///
+    /// Lenses in long pipelines call this when an API rejects a token that
+    /// `get_token` returned earlier. Brokers that cannot refresh keep the
+    /// default, which fails with `RefreshFailed`.
+    async fn refresh_token(&self, provider: &str) -> Result<OAuthToken, OAuthError> {
+        Err(OAuthError::RefreshFailed(format!(
+            "broker cannot refresh tokens for {}",
+            provider
+        )))
+    }
+}
+
+/// Lets decorators such as [`CachingOAuthBroker`] wrap a shared
+/// `Arc<dyn OAuthBroker>`
+#[async_trait]
+impl<T: OAuthBroker + ?Sized> OAuthBroker for std::sync::Arc<T> {
+    async fn get_token(&self, provider: &str) -> Result<OAuthToken, OAuthError> {
+        (**self).get_token(provider).await
+    }
+
+    async fn get_token_with_scopes(
+        &self,
+        provider: &str,
+        scopes: &[&str],
+    ) -> Result<OAuthToken, OAuthError> {
+        (**self).get_token_with_scopes(provider, scopes).await
+    }
+
+    async fn is_connected(&self, provider: &str) -> bool {
+        (**self).is_connected(provider).await
+    }
+
+    async fn list_accounts(&self, provider: &str) -> Result<Vec<OAuthAccount>, OAuthError> {
+        (**self).list_accounts(provider).await
+    }
+
+    async fn get_token_for_account(
+        &self,
+        provider: &str,
+        account_id: &str,
+    ) -> Result<OAuthToken, OAuthError> {
+        (**self).get_token_for_account(provider, account_id).await
+    }
+
+    async fn refresh_token(&self, provider: &str) -> Result<OAuthToken, OAuthError> {
+        (**self).refresh_token(provider).await
+    }
+}
+
+/// A connected account for an OAuth provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OAuthAccount {
+    /// Broker-assigned identifier, passed to `get_token_for_account`
+    pub id: String,
+    /// Display name for account pickers (e.g. workspace name or email)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
 /// OAuth token payload returned by the broker.
@@ -21,6 +133,37 @@ pub struct OAuthToken {
     pub scope: Option<String>,
 }
 
+impl OAuthToken {
+    /// Granted scopes, split from the space-separated `scope` field
+    pub fn scopes(&self) -> Vec<&str> {
+        self.scope
+            .as_deref()
+            .map(|scope| scope.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+
+    /// The entries of `required` this token was not granted
+    pub fn missing_scopes(&self, required: &[&str]) -> Vec<String> {
+        let granted = self.scopes();
+        required
+            .iter()
+            .filter(|scope| !granted.contains(scope))
+            .map(|scope| scope.to_string())
+            .collect()
+    }
+
+    /// Whether the token has expired. Tokens without `expires_at` never do.
+    pub fn is_expired(&self) -> bool {
+        self.expires_within(Duration::ZERO)
+    }
+
+    /// Whether the token expires within `window` from now
+    pub fn expires_within(&self, window: Duration) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= SystemTime::now() + window)
+    }
+}
+
 /// OAuth broker errors.
 #[derive(Error, Debug)]
 pub enum OAuthError {
@@ -30,6 +173,99 @@ pub enum OAuthError {
     #[error("OAuth token expired")]
     Expired,
 
+    #[error("OAuth token refresh failed: {0}")]
+    RefreshFailed(String),
+
+    #[error("OAuth token is missing scopes: {}", missing.join(", "))]
+    InsufficientScope { missing: Vec<String> },
+
+    #[error("OAuth account '{account}' not found for provider {provider}")]
+    AccountNotFound { provider: String, account: String },
+
+    #[error("OAuth token storage error: {0}")]
+    Storage(String),
+
     #[error("OAuth token fetch failed: {0}")]
     NetworkError(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(expires_at: Option<SystemTime>) -> OAuthToken {
+        OAuthToken {
+            access_token: "abc".to_string(),
+            refresh_token: None,
+            expires_at,
+            scope: Some("file_read file_comments:write".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_token_expiry() {
+        let hour = Duration::from_secs(3600);
+        assert!(!token(None).is_expired());
+        assert!(token(Some(SystemTime::now() - hour)).is_expired());
+
+        let soon = token(Some(SystemTime::now() + Duration::from_secs(30)));
+        assert!(!soon.is_expired());
+        assert!(soon.expires_within(Duration::from_secs(60)));
+        assert!(!soon.expires_within(Duration::from_secs(10)));
+    }
+
+    struct StaticBroker;
+
+    #[async_trait]
+    impl OAuthBroker for StaticBroker {
+        async fn get_token(&self, _provider: &str) -> Result<OAuthToken, OAuthError> {
+            Ok(token(None))
+        }
+
+        async fn is_connected(&self, _provider: &str) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_refresh_fails() {
+        assert!(matches!(
+            StaticBroker.refresh_token("figma").await,
+            Err(OAuthError::RefreshFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_default_accounts() {
+        assert!(StaticBroker
+            .list_accounts("figma")
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            StaticBroker.get_token_for_account("figma", "acme").await,
+            Err(OAuthError::AccountNotFound { account, .. }) if account == "acme"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_token_with_scopes() {
+        assert!(StaticBroker
+            .get_token_with_scopes("figma", &["file_read"])
+            .await
+            .is_ok());
+
+        let err = StaticBroker
+            .get_token_with_scopes("figma", &["file_read", "webhooks:write"])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            OAuthError::InsufficientScope { missing } if missing == &["webhooks:write"]
+        ));
+        assert_eq!(
+            err.to_string(),
+            "OAuth token is missing scopes: webhooks:write"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+//! # Caching OAuth Broker
+//!
+//! [`CachingOAuthBroker`] wraps another broker, keeps the tokens it returns,
+//! and refreshes them shortly before they expire, so lenses can call
+//! `get_token` before every request without re-fetching or doing their own
+//! expiry math.
+//!
+//! ```rust,ignore
+//! let broker = CachingOAuthBroker::new(FileOAuthBroker::new()?)
+//!     .with_skew(Duration::from_secs(120));
+//! let ctx = LensContext::new(cwd, input).with_oauth_broker(Arc::new(broker));
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::{OAuthAccount, OAuthBroker, OAuthError, OAuthToken};
+
+/// Default margin before `expires_at` at which cached tokens are refreshed
+pub const DEFAULT_EXPIRY_SKEW: Duration = Duration::from_secs(60);
+
+/// Cache key: provider, the account (`None` for the provider's default), and
+/// the sorted scopes requested (empty for a plain `get_token`)
+type CacheKey = (String, Option<String>, Vec<String>);
+
+/// [`OAuthBroker`] decorator caching tokens until shortly before they expire
+pub struct CachingOAuthBroker<B> {
+    inner: B,
+    skew: Duration,
+    tokens: Mutex<HashMap<CacheKey, OAuthToken>>,
+}
+
+impl<B: OAuthBroker> CachingOAuthBroker<B> {
+    /// Cache tokens from `inner`, refreshing [`DEFAULT_EXPIRY_SKEW`] early
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            skew: DEFAULT_EXPIRY_SKEW,
+            tokens: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Refresh tokens once they are within `skew` of expiring
+    pub fn with_skew(mut self, skew: Duration) -> Self {
+        self.skew = skew;
+        self
+    }
+
+    /// The wrapped broker
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Drop every cached token for `provider`
+    pub fn invalidate(&self, provider: &str) {
+        self.tokens
+            .lock()
+            .unwrap()
+            .retain(|(cached, _, _), _| cached != provider);
+    }
+
+    fn cached(&self, key: &CacheKey) -> Option<OAuthToken> {
+        self.tokens.lock().unwrap().get(key).cloned()
+    }
+
+    fn forget(&self, key: &CacheKey) {
+        self.tokens.lock().unwrap().remove(key);
+    }
+
+    fn store(&self, key: CacheKey, token: &OAuthToken) {
+        self.tokens.lock().unwrap().insert(key, token.clone());
+    }
+
+    fn is_fresh(&self, token: &OAuthToken) -> bool {
+        !token.expires_within(self.skew)
+    }
+}
+
+#[async_trait]
+impl<B: OAuthBroker> OAuthBroker for CachingOAuthBroker<B> {
+    /// Return the cached token while it is fresh. Near expiry, ask the inner
+    /// broker to refresh it, falling back to a plain `get_token` when the
+    /// inner broker cannot refresh.
+    async fn get_token(&self, provider: &str) -> Result<OAuthToken, OAuthError> {
+        let key = (provider.to_string(), None, Vec::new());
+        let token = match self.cached(&key) {
+            Some(token) if self.is_fresh(&token) => return Ok(token),
+            Some(_) => match self.inner.refresh_token(provider).await {
+                Ok(token) => token,
+                Err(_) => self.inner.get_token(provider).await?,
+            },
+            None => self.inner.get_token(provider).await?,
+        };
+        if token.is_expired() {
+            self.invalidate(provider);
+            return Err(OAuthError::Expired);
+        }
+        self.store(key, &token);
+        Ok(token)
+    }
+
+    /// Cached separately per set of scopes, so a token granted for fewer
+    /// scopes is never handed out for more
+    async fn get_token_with_scopes(
+        &self,
+        provider: &str,
+        scopes: &[&str],
+    ) -> Result<OAuthToken, OAuthError> {
+        let mut requested: Vec<String> = scopes.iter().map(|s| s.to_string()).collect();
+        requested.sort();
+        requested.dedup();
+        let key = (provider.to_string(), None, requested);
+        if let Some(token) = self.cached(&key).filter(|t| self.is_fresh(t)) {
+            return Ok(token);
+        }
+        let token = self.inner.get_token_with_scopes(provider, scopes).await?;
+        if token.is_expired() {
+            self.forget(&key);
+            return Err(OAuthError::Expired);
+        }
+        self.store(key, &token);
+        Ok(token)
+    }
+
+    async fn is_connected(&self, provider: &str) -> bool {
+        self.inner.is_connected(provider).await
+    }
+
+    async fn refresh_token(&self, provider: &str) -> Result<OAuthToken, OAuthError> {
+        let token = self.inner.refresh_token(provider).await?;
+        self.store((provider.to_string(), None, Vec::new()), &token);
+        Ok(token)
+    }
+
+    async fn list_accounts(&self, provider: &str) -> Result<Vec<OAuthAccount>, OAuthError> {
+        self.inner.list_accounts(provider).await
+    }
+
+    async fn get_token_for_account(
+        &self,
+        provider: &str,
+        account_id: &str,
+    ) -> Result<OAuthToken, OAuthError> {
+        let key = (
+            provider.to_string(),
+            Some(account_id.to_string()),
+            Vec::new(),
+        );
+        if let Some(token) = self.cached(&key).filter(|t| self.is_fresh(t)) {
+            return Ok(token);
+        }
+        let token = self
+            .inner
+            .get_token_for_account(provider, account_id)
+            .await?;
+        if token.is_expired() {
+            self.forget(&key);
+            return Err(OAuthError::Expired);
+        }
+        self.store(key, &token);
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::SystemTime;
+
+    /// Hands out tokens expiring after `lifetime`, counting fetches and refreshes
+    struct CountingBroker {
+        lifetime: Duration,
+        fetches: AtomicUsize,
+        refreshes: AtomicUsize,
+    }
+
+    impl CountingBroker {
+        fn new(lifetime: Duration) -> Self {
+            Self {
+                lifetime,
+                fetches: AtomicUsize::new(0),
+                refreshes: AtomicUsize::new(0),
+            }
+        }
+
+        fn token(&self, access_token: String) -> OAuthToken {
+            OAuthToken {
+                access_token,
+                refresh_token: None,
+                expires_at: Some(SystemTime::now() + self.lifetime),
+                scope: None,
+            }
+        }
+
+        fn fetch(&self) -> OAuthToken {
+            let n = self.fetches.fetch_add(1, Ordering::SeqCst) + 1;
+            self.token(format!("fetched-{}", n))
+        }
+    }
+
+    #[async_trait]
+    impl OAuthBroker for CountingBroker {
+        async fn get_token(&self, _provider: &str) -> Result<OAuthToken, OAuthError> {
+            Ok(self.fetch())
+        }
+
+        async fn get_token_with_scopes(
+            &self,
+            _provider: &str,
+            scopes: &[&str],
+        ) -> Result<OAuthToken, OAuthError> {
+            Ok(OAuthToken {
+                scope: Some(scopes.join(" ")),
+                ..self.fetch()
+            })
+        }
+
+        async fn get_token_for_account(
+            &self,
+            _provider: &str,
+            _account_id: &str,
+        ) -> Result<OAuthToken, OAuthError> {
+            Ok(self.fetch())
+        }
+
+        async fn is_connected(&self, _provider: &str) -> bool {
+            true
+        }
+
+        async fn refresh_token(&self, _provider: &str) -> Result<OAuthToken, OAuthError> {
+            let n = self.refreshes.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(self.token(format!("refreshed-{}", n)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_caches_fresh_tokens() {
+        let broker = CachingOAuthBroker::new(CountingBroker::new(Duration::from_secs(3600)));
+
+        for _ in 0..3 {
+            let token = broker.get_token("figma").await.unwrap();
+            assert_eq!(token.access_token, "fetched-1");
+        }
+        assert_eq!(broker.inner().fetches.load(Ordering::SeqCst), 1);
+
+        broker.invalidate("figma");
+        assert_eq!(
+            broker.get_token("figma").await.unwrap().access_token,
+            "fetched-2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refreshes_tokens_within_skew() {
+        // Tokens live 30s, inside the 60s default skew, so every call after
+        // the first refreshes
+        let broker = CachingOAuthBroker::new(CountingBroker::new(Duration::from_secs(30)));
+
+        assert_eq!(
+            broker.get_token("figma").await.unwrap().access_token,
+            "fetched-1"
+        );
+        assert_eq!(
+            broker.get_token("figma").await.unwrap().access_token,
+            "refreshed-1"
+        );
+
+        // A smaller skew keeps them cached
+        let broker = broker.with_skew(Duration::from_secs(10));
+        for _ in 0..2 {
+            assert_eq!(
+                broker.get_token("figma").await.unwrap().access_token,
+                "refreshed-1"
+            );
+        }
+        assert_eq!(broker.inner().refreshes.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_caches_scoped_tokens_per_scope_set() {
+        let broker = CachingOAuthBroker::new(CountingBroker::new(Duration::from_secs(3600)));
+
+        let read = broker
+            .get_token_with_scopes("figma", &["read"])
+            .await
+            .unwrap();
+        assert_eq!(read.scope.as_deref(), Some("read"));
+        let both = broker
+            .get_token_with_scopes("figma", &["read", "write"])
+            .await
+            .unwrap();
+        assert_eq!(both.scope.as_deref(), Some("read write"));
+        let again = broker
+            .get_token_with_scopes("figma", &["write", "read"])
+            .await
+            .unwrap();
+        assert_eq!(again.access_token, both.access_token);
+        assert_eq!(broker.inner().fetches.load(Ordering::SeqCst), 2);
+
+        // The plain token is cached apart from the scoped ones
+        broker.get_token("figma").await.unwrap();
+        assert_eq!(broker.inner().fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_expired_account_tokens_are_not_cached() {
+        let broker = CachingOAuthBroker::new(CountingBroker::new(Duration::ZERO));
+
+        for _ in 0..2 {
+            assert!(matches!(
+                broker.get_token_for_account("figma", "work").await,
+                Err(OAuthError::Expired)
+            ));
+        }
+        assert_eq!(broker.inner().fetches.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,311 @@
+//! # Device Authorization Grant
+//!
+//! Helpers for the OAuth device flow (RFC 8628), for hosts without a browser
+//! redirect such as CLIs. The host shows the user a code and URL, then polls
+//! the token endpoint until the user approves, denies, or the code expires.
+//!
+//! This module owns the protocol logic (polling interval, `slow_down`
+//! backoff, error mapping); the HTTP requests are left to a
+//! [`DeviceFlowClient`] supplied by the broker:
+//!
+//! ```rust,ignore
+//! let auth = client.request_device_code(&["file_read"]).await?;
+//! println!("Open {} and enter {}", auth.verification_uri, auth.user_code);
+//! let token = poll_for_token(&client, &auth).await?;
+//! ```
+
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use super::{OAuthError, OAuthToken};
+
+/// Polling interval when the server doesn't specify one (RFC 8628 §3.2)
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Added to the polling interval on every `slow_down` response (RFC 8628 §3.5)
+pub const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+
+/// Device authorization response: what to show the user and how to poll
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeviceAuthorization {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Verification URL with the user code embedded, if the server offers one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification_uri_complete: Option<String>,
+    /// Seconds until `device_code` expires
+    pub expires_in: u64,
+    /// Minimum seconds between token requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+}
+
+impl DeviceAuthorization {
+    /// Polling interval requested by the server, or the RFC default
+    pub fn poll_interval(&self) -> Duration {
+        self.interval
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_POLL_INTERVAL)
+    }
+}
+
+/// HTTP side of the device flow, implemented per provider
+#[async_trait]
+pub trait DeviceFlowClient: Send + Sync {
+    /// POST to the device authorization endpoint
+    async fn request_device_code(
+        &self,
+        scopes: &[&str],
+    ) -> Result<DeviceAuthorization, DeviceFlowError>;
+
+    /// POST `device_code` to the token endpoint and return the JSON body,
+    /// whether it is a token or an error response
+    async fn request_token(&self, device_code: &str) -> Result<Value, DeviceFlowError>;
+}
+
+/// Device flow errors
+#[derive(Error, Debug)]
+pub enum DeviceFlowError {
+    #[error("User denied the authorization request")]
+    AccessDenied,
+
+    #[error("Device code expired before the user approved it")]
+    ExpiredToken,
+
+    #[error("Token endpoint returned error '{error}'{}", detail(description))]
+    Provider {
+        error: String,
+        description: Option<String>,
+    },
+
+    #[error("Malformed token response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Device flow request failed: {0}")]
+    Transport(String),
+}
+
+fn detail(description: &Option<String>) -> String {
+    description
+        .as_deref()
+        .map(|d| format!(": {}", d))
+        .unwrap_or_default()
+}
+
+impl From<DeviceFlowError> for OAuthError {
+    fn from(error: DeviceFlowError) -> Self {
+        match error {
+            DeviceFlowError::ExpiredToken => OAuthError::Expired,
+            other => OAuthError::NetworkError(other.to_string()),
+        }
+    }
+}
+
+/// One parsed token endpoint response
+#[derive(Debug, Clone)]
+pub enum PollOutcome {
+    /// The user hasn't approved yet (`authorization_pending`)
+    Pending,
+    /// Poll less often (`slow_down`)
+    SlowDown,
+    /// The user approved
+    Token(OAuthToken),
+}
+
+/// Interpret a token endpoint response body
+pub fn parse_token_response(body: &Value) -> Result<PollOutcome, DeviceFlowError> {
+    if let Some(error) = body.get("error").and_then(Value::as_str) {
+        return match error {
+            "authorization_pending" => Ok(PollOutcome::Pending),
+            "slow_down" => Ok(PollOutcome::SlowDown),
+            "access_denied" => Err(DeviceFlowError::AccessDenied),
+            "expired_token" => Err(DeviceFlowError::ExpiredToken),
+            other => Err(DeviceFlowError::Provider {
+                error: other.to_string(),
+                description: body
+                    .get("error_description")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            }),
+        };
+    }
+
+    let access_token = body
+        .get("access_token")
+        .and_then(Value::as_str)
+        .ok_or_else(|| DeviceFlowError::InvalidResponse("missing access_token".to_string()))?;
+    let string = |key: &str| body.get(key).and_then(Value::as_str).map(str::to_string);
+    Ok(PollOutcome::Token(OAuthToken {
+        access_token: access_token.to_string(),
+        refresh_token: string("refresh_token"),
+        expires_at: body
+            .get("expires_in")
+            .and_then(Value::as_u64)
+            .map(|secs| SystemTime::now() + Duration::from_secs(secs)),
+        scope: string("scope"),
+    }))
+}
+
+/// Poll the token endpoint until the user approves or the flow fails.
+///
+/// Waits `auth.interval` between requests, adding [`SLOW_DOWN_INCREMENT`] on
+/// every `slow_down`, and gives up with `ExpiredToken` once `expires_in` has
+/// passed.
+pub async fn poll_for_token(
+    client: &dyn DeviceFlowClient,
+    auth: &DeviceAuthorization,
+) -> Result<OAuthToken, DeviceFlowError> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(auth.expires_in);
+    let mut interval = auth.poll_interval();
+    loop {
+        if tokio::time::Instant::now() + interval > deadline {
+            return Err(DeviceFlowError::ExpiredToken);
+        }
+        tokio::time::sleep(interval).await;
+
+        let body = client.request_token(&auth.device_code).await?;
+        match parse_token_response(&body)? {
+            PollOutcome::Token(token) => return Ok(token),
+            PollOutcome::Pending => {}
+            PollOutcome::SlowDown => interval += SLOW_DOWN_INCREMENT,
+        }
+    }
+}
+
+/// Run the whole flow: request a device code, hand it to `prompt` to show the
+/// user, then poll for the token.
+pub async fn authorize(
+    client: &dyn DeviceFlowClient,
+    scopes: &[&str],
+    prompt: impl FnOnce(&DeviceAuthorization),
+) -> Result<OAuthToken, DeviceFlowError> {
+    let auth = client.request_device_code(scopes).await?;
+    prompt(&auth);
+    poll_for_token(client, &auth).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// Replays token responses and records when each poll happened
+    struct ScriptedClient {
+        responses: Mutex<Vec<Value>>,
+        polls: Mutex<Vec<tokio::time::Instant>>,
+    }
+
+    impl ScriptedClient {
+        fn new(mut responses: Vec<Value>) -> Self {
+            responses.reverse();
+            Self {
+                responses: Mutex::new(responses),
+                polls: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl DeviceFlowClient for ScriptedClient {
+        async fn request_device_code(
+            &self,
+            _scopes: &[&str],
+        ) -> Result<DeviceAuthorization, DeviceFlowError> {
+            Ok(DeviceAuthorization {
+                device_code: "dev-123".to_string(),
+                user_code: "WDJB-MJHT".to_string(),
+                verification_uri: "https://example.com/device".to_string(),
+                verification_uri_complete: None,
+                expires_in: 60,
+                interval: Some(2),
+            })
+        }
+
+        async fn request_token(&self, device_code: &str) -> Result<Value, DeviceFlowError> {
+            assert_eq!(device_code, "dev-123");
+            self.polls.lock().unwrap().push(tokio::time::Instant::now());
+            Ok(self
+                .responses
+                .lock()
+                .unwrap()
+                .pop()
+                .unwrap_or(json!({ "error": "authorization_pending" })))
+        }
+    }
+
+    #[test]
+    fn test_parse_token_response() {
+        let token = parse_token_response(&json!({
+            "access_token": "abc",
+            "token_type": "bearer",
+            "expires_in": 3600,
+            "scope": "file_read",
+        }))
+        .unwrap();
+        let PollOutcome::Token(token) = token else {
+            panic!("expected a token");
+        };
+        assert_eq!(token.access_token, "abc");
+        assert!(!token.is_expired());
+        assert_eq!(token.scopes(), vec!["file_read"]);
+
+        assert!(matches!(
+            parse_token_response(&json!({ "error": "access_denied" })),
+            Err(DeviceFlowError::AccessDenied)
+        ));
+        assert!(matches!(
+            parse_token_response(&json!({ "error": "invalid_client", "error_description": "bad id" })),
+            Err(DeviceFlowError::Provider { error, .. }) if error == "invalid_client"
+        ));
+        assert!(matches!(
+            parse_token_response(&json!({ "token_type": "bearer" })),
+            Err(DeviceFlowError::InvalidResponse(_))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poll_backs_off_on_slow_down() {
+        let client = ScriptedClient::new(vec![
+            json!({ "error": "authorization_pending" }),
+            json!({ "error": "slow_down" }),
+            json!({ "access_token": "abc" }),
+        ]);
+        let start = tokio::time::Instant::now();
+        let mut shown = None;
+
+        let token = authorize(&client, &["file_read"], |auth| {
+            shown = Some(auth.user_code.clone())
+        })
+        .await
+        .unwrap();
+        assert_eq!(token.access_token, "abc");
+        assert_eq!(shown.as_deref(), Some("WDJB-MJHT"));
+
+        let offsets: Vec<u64> = client
+            .polls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|at| (*at - start).as_secs())
+            .collect();
+        // 2s interval, then 7s after slow_down
+        assert_eq!(offsets, vec![2, 4, 11]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poll_expires() {
+        let client = ScriptedClient::new(Vec::new());
+        let auth = client.request_device_code(&[]).await.unwrap();
+
+        let err = poll_for_token(&client, &auth).await.unwrap_err();
+        assert!(matches!(err, DeviceFlowError::ExpiredToken));
+        assert!(matches!(OAuthError::from(err), OAuthError::Expired));
+        assert_eq!(client.polls.lock().unwrap().len(), 30);
+    }
+}
@@ -0,0 +1,463 @@
+//! # File OAuth Broker
+//!
+//! [`FileOAuthBroker`] keeps tokens on disk so standalone lens runners and
+//! tests have a working [`OAuthBroker`] without the desktop app.
+//!
+//! Requires the `runtime` feature.
+//!
+//! # Directory Structure
+//!
+//! ```text
+//! ~/.graphyn/oauth/
+//! ├── .key             # 32-byte encryption key, mode 0600
+//! └── figma.enc        # nonce + ChaCha20-Poly1305 sealed JSON, one per provider
+//! ```
+//!
+//! Each file is sealed with its provider id as associated data, so a file
+//! copied over another provider's fails to decrypt. Files are written to a
+//! temporary file and renamed into place, so a crash never leaves one torn.
+//!
+//! Hosts that keep the key in the OS keychain pass it with
+//! [`FileOAuthBroker::with_key`] instead, and no key file is written.
+//!
+//! The broker cannot refresh tokens itself (it knows no provider endpoints):
+//! an expired token yields `OAuthError::Expired` until the host stores a new
+//! one.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+
+use super::{OAuthAccount, OAuthBroker, OAuthError, OAuthToken};
+
+/// Name of the key file inside the broker directory
+pub const OAUTH_KEY_FILENAME: &str = ".key";
+
+const NONCE_LEN: usize = 12;
+
+/// Token as persisted on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredToken {
+    access_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+    /// Seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+}
+
+impl From<&OAuthToken> for StoredToken {
+    fn from(token: &OAuthToken) -> Self {
+        Self {
+            access_token: token.access_token.clone(),
+            refresh_token: token.refresh_token.clone(),
+            expires_at: token.expires_at.map(|at| {
+                at.duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0)
+            }),
+            scope: token.scope.clone(),
+        }
+    }
+}
+
+impl From<StoredToken> for OAuthToken {
+    fn from(token: StoredToken) -> Self {
+        Self {
+            access_token: token.access_token,
+            refresh_token: token.refresh_token,
+            expires_at: token
+                .expires_at
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+            scope: token.scope,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredAccount {
+    #[serde(flatten)]
+    account: OAuthAccount,
+    token: StoredToken,
+}
+
+/// Everything stored for one provider; the first account is the default
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProviderFile {
+    accounts: Vec<StoredAccount>,
+}
+
+/// [`OAuthBroker`] backed by encrypted files
+pub struct FileOAuthBroker {
+    dir: PathBuf,
+    cipher: ChaCha20Poly1305,
+    lock: Mutex<()>,
+}
+
+impl std::fmt::Debug for FileOAuthBroker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileOAuthBroker")
+            .field("dir", &self.dir)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FileOAuthBroker {
+    /// Open the default ~/.graphyn/oauth directory
+    pub fn new() -> Result<Self, OAuthError> {
+        let home = dirs::home_dir()
+            .ok_or_else(|| OAuthError::Storage("could not determine home directory".into()))?;
+        Self::open(home.join(".graphyn").join("oauth"))
+    }
+
+    /// Open `dir`, creating it and its key file on first use
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, OAuthError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(storage)?;
+        let key_path = dir.join(OAUTH_KEY_FILENAME);
+
+        let key = match fs::read(&key_path) {
+            Ok(bytes) => <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_| {
+                OAuthError::Storage(format!("{} is not a 32-byte key", key_path.display()))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key: [u8; 32] = ChaCha20Poly1305::generate_key(&mut OsRng).into();
+                write_private(&key_path, &key)?;
+                key
+            }
+            Err(e) => return Err(storage(e)),
+        };
+        Ok(Self::with_key(dir, key))
+    }
+
+    /// Use `dir` with a key kept elsewhere (e.g. the OS keychain)
+    pub fn with_key<P: AsRef<Path>>(dir: P, key: [u8; 32]) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Directory holding the token files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Store the provider's default token, replacing any stored accounts
+    pub fn store_token(&self, provider: &str, token: &OAuthToken) -> Result<(), OAuthError> {
+        let _guard = self.lock.lock().unwrap();
+        let file = ProviderFile {
+            accounts: vec![StoredAccount {
+                account: OAuthAccount {
+                    id: "default".to_string(),
+                    label: None,
+                },
+                token: token.into(),
+            }],
+        };
+        self.write(provider, &file)
+    }
+
+    /// Store or replace the token for one account of the provider
+    pub fn store_account_token(
+        &self,
+        provider: &str,
+        account: OAuthAccount,
+        token: &OAuthToken,
+    ) -> Result<(), OAuthError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut file = self.read(provider)?.unwrap_or_default();
+        let stored = StoredAccount {
+            account,
+            token: token.into(),
+        };
+        match file
+            .accounts
+            .iter_mut()
+            .find(|a| a.account.id == stored.account.id)
+        {
+            Some(existing) => *existing = stored,
+            None => file.accounts.push(stored),
+        }
+        self.write(provider, &file)
+    }
+
+    /// Forget every token stored for the provider
+    pub fn disconnect(&self, provider: &str) -> Result<(), OAuthError> {
+        let _guard = self.lock.lock().unwrap();
+        match fs::remove_file(self.path(provider)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(storage(e)),
+            _ => Ok(()),
+        }
+    }
+
+    fn path(&self, provider: &str) -> Result<PathBuf, OAuthError> {
+        let valid = !provider.is_empty()
+            && provider
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(OAuthError::Storage(format!(
+                "invalid provider id: {:?}",
+                provider
+            )));
+        }
+        Ok(self.dir.join(format!("{}.enc", provider)))
+    }
+
+    fn read(&self, provider: &str) -> Result<Option<ProviderFile>, OAuthError> {
+        let sealed = match fs::read(self.path(provider)?) {
+            Ok(sealed) => sealed,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(storage(e)),
+        };
+        if sealed.len() < NONCE_LEN {
+            return Err(OAuthError::Storage(format!(
+                "token file for {} is truncated",
+                provider
+            )));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: provider.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                OAuthError::Storage(format!(
+                    "token file for {} could not be decrypted",
+                    provider
+                ))
+            })?;
+        serde_json::from_slice(&plaintext)
+            .map(Some)
+            .map_err(|e| OAuthError::Storage(e.to_string()))
+    }
+
+    fn write(&self, provider: &str, file: &ProviderFile) -> Result<(), OAuthError> {
+        let path = self.path(provider)?;
+        let plaintext = serde_json::to_vec(file).map_err(|e| OAuthError::Storage(e.to_string()))?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: provider.as_bytes(),
+                },
+            )
+            .map_err(|_| OAuthError::Storage("token encryption failed".into()))?;
+
+        fs::create_dir_all(&self.dir).map_err(storage)?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        write_private(&path, &sealed)
+    }
+
+    fn account_token(
+        &self,
+        provider: &str,
+        account_id: Option<&str>,
+    ) -> Result<OAuthToken, OAuthError> {
+        let _guard = self.lock.lock().unwrap();
+        let file = self
+            .read(provider)?
+            .ok_or_else(|| OAuthError::NotConnected(provider.to_string()))?;
+        let stored = match account_id {
+            Some(id) => file.accounts.into_iter().find(|a| a.account.id == id),
+            None => file.accounts.into_iter().next(),
+        };
+        let Some(stored) = stored else {
+            return Err(match account_id {
+                Some(id) => OAuthError::AccountNotFound {
+                    provider: provider.to_string(),
+                    account: id.to_string(),
+                },
+                None => OAuthError::NotConnected(provider.to_string()),
+            });
+        };
+
+        let token = OAuthToken::from(stored.token);
+        if token.is_expired() {
+            return Err(OAuthError::Expired);
+        }
+        Ok(token)
+    }
+}
+
+#[async_trait]
+impl OAuthBroker for FileOAuthBroker {
+    async fn get_token(&self, provider: &str) -> Result<OAuthToken, OAuthError> {
+        self.account_token(provider, None)
+    }
+
+    async fn is_connected(&self, provider: &str) -> bool {
+        let _guard = self.lock.lock().unwrap();
+        matches!(self.read(provider), Ok(Some(file)) if !file.accounts.is_empty())
+    }
+
+    async fn list_accounts(&self, provider: &str) -> Result<Vec<OAuthAccount>, OAuthError> {
+        let _guard = self.lock.lock().unwrap();
+        Ok(self
+            .read(provider)?
+            .map(|file| file.accounts.into_iter().map(|a| a.account).collect())
+            .unwrap_or_default())
+    }
+
+    async fn get_token_for_account(
+        &self,
+        provider: &str,
+        account_id: &str,
+    ) -> Result<OAuthToken, OAuthError> {
+        self.account_token(provider, Some(account_id))
+    }
+}
+
+fn storage(error: std::io::Error) -> OAuthError {
+    OAuthError::Storage(error.to_string())
+}
+
+/// Replace `path` with `bytes`, readable only by the current user
+fn write_private(path: &Path, bytes: &[u8]) -> Result<(), OAuthError> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options.open(&temp).and_then(|mut file| {
+        std::io::Write::write_all(&mut file, bytes)?;
+        file.sync_all()
+    });
+    match written.and_then(|()| fs::rename(&temp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(storage(e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+    use tempfile::tempdir;
+
+    fn token(access_token: &str, expires_in: Option<i64>) -> OAuthToken {
+        OAuthToken {
+            access_token: access_token.to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_at: expires_in.map(|secs| {
+                if secs >= 0 {
+                    SystemTime::now() + Duration::from_secs(secs as u64)
+                } else {
+                    SystemTime::now() - Duration::from_secs(secs.unsigned_abs())
+                }
+            }),
+            scope: Some("file_read".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_broker_round_trip() {
+        let dir = tempdir().unwrap();
+        let broker = FileOAuthBroker::open(dir.path()).unwrap();
+        assert!(!broker.is_connected("figma").await);
+
+        broker
+            .store_token("figma", &token("secret-token", Some(3600)))
+            .unwrap();
+        assert!(broker.is_connected("figma").await);
+
+        // Tokens survive reopening and are not stored in plaintext
+        let reopened = FileOAuthBroker::open(dir.path()).unwrap();
+        let stored = reopened.get_token("figma").await.unwrap();
+        assert_eq!(stored.access_token, "secret-token");
+        assert_eq!(stored.scopes(), vec!["file_read"]);
+        let raw = fs::read(dir.path().join("figma.enc")).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("secret-token"));
+
+        // A different key cannot read them
+        let wrong_key = FileOAuthBroker::with_key(dir.path(), [7; 32]);
+        assert!(matches!(
+            wrong_key.get_token("figma").await,
+            Err(OAuthError::Storage(_))
+        ));
+
+        // Nor can another provider's file, and no temporary files are left
+        fs::copy(dir.path().join("figma.enc"), dir.path().join("google.enc")).unwrap();
+        assert!(matches!(
+            broker.get_token("google").await,
+            Err(OAuthError::Storage(_))
+        ));
+        let mut names: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, [".key", "figma.enc", "google.enc"]);
+
+        broker.disconnect("figma").unwrap();
+        assert!(matches!(
+            broker.get_token("figma").await,
+            Err(OAuthError::NotConnected(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_file_broker_accounts_and_expiry() {
+        let dir = tempdir().unwrap();
+        let broker = FileOAuthBroker::open(dir.path()).unwrap();
+        let account = |id: &str| OAuthAccount {
+            id: id.to_string(),
+            label: Some(format!("{} workspace", id)),
+        };
+
+        broker
+            .store_account_token("figma", account("acme"), &token("acme-token", None))
+            .unwrap();
+        broker
+            .store_account_token("figma", account("side"), &token("old", Some(-60)))
+            .unwrap();
+
+        let ids: Vec<String> = broker
+            .list_accounts("figma")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|a| a.id)
+            .collect();
+        assert_eq!(ids, vec!["acme", "side"]);
+        assert_eq!(
+            broker.get_token("figma").await.unwrap().access_token,
+            "acme-token"
+        );
+        assert!(matches!(
+            broker.get_token_for_account("figma", "side").await,
+            Err(OAuthError::Expired)
+        ));
+        assert!(matches!(
+            broker.get_token_for_account("figma", "other").await,
+            Err(OAuthError::AccountNotFound { .. })
+        ));
+        assert!(broker.store_token("../escape", &token("x", None)).is_err());
+    }
+}
@@ -3,13 +3,40 @@
 //! Declarative output contracts loaded from `lens.output.yaml`.
 //! This file defines the payload shape and framework-owned render hints for each
 //! lens output key.
+//!
+//! Large specs can be split across files with `extends:` (a single base spec)
+//! and `include:` (fragments, e.g. one per pipeline phase). Paths are relative
+//! to the including file and resolved by [`LensOutputSpec::from_file`]:
+//!
+//! ```yaml
+//! lens_id: figma
+//! extends: ../shared/common.output.yaml
+//! include:
+//!   - outputs/phase_0.yaml
+//!   - outputs/phase_1.yaml
+//! outputs: []
+//! ```
+//!
+//! Inherited and included outputs are merged in order; outputs declared in the
+//! including file replace inherited or included outputs with the same key.
 
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
-use std::collections::HashSet;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use crate::error::{LensError, Result};
+use crate::manifest::lookup_locale;
+use crate::schema::{self, SchemaViolation};
+
+pub mod codegen;
+pub mod condition;
+pub mod form;
+pub mod options;
+
+pub use condition::Condition;
+pub use form::{FormField, FormFieldType, FormSpec, FormSubmission};
+pub use options::BlockOptions;
 
 /// Standard output spec file name expected in each lens directory.
 pub const OUTPUT_SPEC_FILENAME: &str = "lens.output.yaml";
@@ -18,8 +45,18 @@ pub const OUTPUT_SPEC_FILENAME: &str = "lens.output.yaml";
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LensOutputSpec {
     /// Lens identifier. Must match lens.toml `[lens].id`.
+    /// May be omitted in fragments, which inherit it from the including file.
+    #[serde(default)]
     pub lens_id: String,
 
+    /// Base spec whose outputs this file inherits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+
+    /// Fragment files whose outputs are merged into this spec.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
     /// Declarative output definitions for Data/Checkpoint rendering.
     #[serde(default)]
     pub outputs: Vec<OutputDefinition>,
@@ -38,6 +75,14 @@ pub struct OutputDefinition {
     #[serde(default)]
     pub description: String,
 
+    /// Per-locale title overrides, keyed by locale ("de", "pt-BR").
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub title_i18n: HashMap<String, String>,
+
+    /// Per-locale description overrides.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub description_i18n: HashMap<String, String>,
+
     /// JSON-schema-like payload descriptor.
     #[serde(default = "default_payload_schema")]
     pub payload_schema: Value,
@@ -50,6 +95,10 @@ pub struct OutputDefinition {
     #[serde(default)]
     pub interactivity: InteractivityMode,
 
+    /// Form fields, required when `interactivity: form`.
+    #[serde(default)]
+    pub form: Option<FormSpec>,
+
     /// Fields that must exist in the runtime payload object.
     #[serde(default)]
     pub required_fields: Vec<String>,
@@ -74,6 +123,18 @@ pub struct RenderBlock {
     #[serde(default)]
     pub title: Option<String>,
 
+    /// Optional block description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Per-locale title overrides.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub title_i18n: HashMap<String, String>,
+
+    /// Per-locale description overrides.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub description_i18n: HashMap<String, String>,
+
     /// Optional payload field path this block binds to.
     #[serde(default)]
     pub source: Option<String>,
@@ -81,6 +142,33 @@ pub struct RenderBlock {
     /// Optional renderer-specific options.
     #[serde(default)]
     pub options: Value,
+
+    /// Optional condition on the payload; the block is hidden when it is false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible_if: Option<Condition>,
+}
+
+impl RenderBlock {
+    /// `source` as a JSON pointer (e.g. `items.0.name` becomes `/items/0/name`).
+    ///
+    /// `source` may be written as a dotted path or as a JSON pointer; both resolve
+    /// the same way, so hosts can look the value up with [`Value::pointer`].
+    pub fn source_pointer(&self) -> Option<String> {
+        let segments = source_segments(self.source.as_deref()?).ok()?;
+        Some(
+            segments
+                .iter()
+                .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+                .collect(),
+        )
+    }
+
+    /// Whether the block should render for `payload`.
+    pub fn is_visible(&self, payload: &Value) -> bool {
+        self.visible_if
+            .as_ref()
+            .is_none_or(|condition| condition.evaluate(payload))
+    }
 }
 
 /// Framework-owned block catalog.
@@ -97,6 +185,12 @@ pub enum RenderBlockType {
     CheckpointGate,
     Notice,
     Actions,
+    LineChart,
+    BarChart,
+    PieChart,
+    Markdown,
+    Image,
+    Media,
 }
 
 /// Interaction mode for an output.
@@ -125,21 +219,89 @@ fn default_payload_schema() -> Value {
 
 impl LensOutputSpec {
     /// Parse output spec from YAML string.
+    ///
+    /// `extends`/`include` need a base directory; use [`Self::from_file`] for them.
     pub fn from_yaml(yaml: &str) -> Result<Self> {
-        let spec: Self = serde_yaml::from_str(yaml).map_err(|e| {
-            LensError::InvalidInput(format!("Failed to parse lens output spec YAML: {}", e))
-        })?;
+        let spec = Self::parse_unvalidated(yaml)?;
+        if spec.extends.is_some() || !spec.include.is_empty() {
+            return Err(LensError::InvalidInput(format!(
+                "lens.output.yaml for '{}': extends/include are only supported when loading from a file",
+                spec.lens_id
+            )));
+        }
         spec.validate()?;
         Ok(spec)
     }
 
-    /// Parse output spec from file path.
+    /// Parse output spec from file path, resolving `extends` and `include`.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let path = path.as_ref();
-        let content = std::fs::read_to_string(path).map_err(|e| {
+        let spec = Self::load_merged(path.as_ref(), &mut Vec::new())?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    fn parse_unvalidated(yaml: &str) -> Result<Self> {
+        serde_yaml::from_str(yaml).map_err(|e| {
+            LensError::InvalidInput(format!("Failed to parse lens output spec YAML: {}", e))
+        })
+    }
+
+    /// Load a file and everything it extends/includes, without validating the result.
+    fn load_merged(path: &Path, stack: &mut Vec<PathBuf>) -> Result<Self> {
+        let canonical = path.canonicalize().map_err(|e| {
             LensError::InvalidInput(format!("Failed to read output spec {:?}: {}", path, e))
         })?;
-        Self::from_yaml(&content)
+        if stack.contains(&canonical) {
+            return Err(LensError::InvalidInput(format!(
+                "Output spec {:?} is included recursively",
+                path
+            )));
+        }
+
+        let content = std::fs::read_to_string(&canonical).map_err(|e| {
+            LensError::InvalidInput(format!("Failed to read output spec {:?}: {}", path, e))
+        })?;
+        let mut spec = Self::parse_unvalidated(&content)?;
+        let dir = canonical.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+        stack.push(canonical);
+        let mut outputs = Vec::new();
+        let mut lens_id = spec.lens_id.clone();
+        let mut parents = Vec::new();
+        if let Some(base) = spec.extends.take() {
+            parents.push(base);
+        }
+        parents.append(&mut spec.include);
+
+        for parent in parents {
+            let parent_spec = Self::load_merged(&dir.join(&parent), stack)?;
+            if !parent_spec.lens_id.is_empty() {
+                if lens_id.is_empty() {
+                    lens_id = parent_spec.lens_id.clone();
+                } else if parent_spec.lens_id != lens_id {
+                    return Err(LensError::InvalidInput(format!(
+                        "Output spec {:?} declares lens_id '{}' but '{}' is for '{}'",
+                        path, lens_id, parent, parent_spec.lens_id
+                    )));
+                }
+            }
+            outputs.extend(parent_spec.outputs);
+        }
+        stack.pop();
+
+        for output in std::mem::take(&mut spec.outputs) {
+            match outputs
+                .iter_mut()
+                .find(|existing| existing.key == output.key)
+            {
+                Some(existing) => *existing = output,
+                None => outputs.push(output),
+            }
+        }
+
+        spec.lens_id = lens_id;
+        spec.outputs = outputs;
+        Ok(spec)
     }
 
     /// Serialize output spec to YAML.
@@ -149,6 +311,34 @@ impl LensOutputSpec {
         })
     }
 
+    /// Copy of this spec with titles and descriptions replaced by `locale` overrides.
+    ///
+    /// Falls back from a regional locale ("pt-BR") to its language ("pt"), and to
+    /// the untranslated text when neither is declared.
+    pub fn localized(&self, locale: &str) -> LensOutputSpec {
+        let mut spec = self.clone();
+
+        for output in &mut spec.outputs {
+            if let Some(title) = lookup_locale(&output.title_i18n, locale) {
+                output.title = title.clone();
+            }
+            if let Some(description) = lookup_locale(&output.description_i18n, locale) {
+                output.description = description.clone();
+            }
+
+            for block in &mut output.render_blocks {
+                if let Some(title) = lookup_locale(&block.title_i18n, locale) {
+                    block.title = Some(title.clone());
+                }
+                if let Some(description) = lookup_locale(&block.description_i18n, locale) {
+                    block.description = Some(description.clone());
+                }
+            }
+        }
+
+        spec
+    }
+
     /// Return an output definition by key.
     pub fn get_output(&self, key: &str) -> Option<&OutputDefinition> {
         self.outputs.iter().find(|o| o.key == key)
@@ -190,7 +380,48 @@ impl LensOutputSpec {
     }
 
     /// Validate a runtime payload for a specific output key.
+    ///
+    /// The error lists every violation of `required_fields` and `payload_schema`.
     pub fn validate_payload(&self, key: &str, payload: &Value) -> Result<()> {
+        let violations = self.payload_violations(key, payload)?;
+        if violations.is_empty() {
+            return Ok(());
+        }
+        Err(LensError::InvalidInput(format!(
+            "Output '{}' payload is invalid: {}",
+            key,
+            schema::describe(&violations)
+        )))
+    }
+
+    /// Validate a form submission against the form declared for its output key.
+    pub fn validate_submission(&self, submission: &FormSubmission) -> Result<()> {
+        let key = &submission.output_key;
+        let form = self
+            .get_output(key)
+            .and_then(|output| output.form.as_ref())
+            .ok_or_else(|| {
+                LensError::InvalidInput(format!(
+                    "Output '{}' in lens.output.yaml for '{}' does not declare a form",
+                    key, self.lens_id
+                ))
+            })?;
+
+        let violations = form.validate_values(&submission.values);
+        if violations.is_empty() {
+            return Ok(());
+        }
+        Err(LensError::InvalidInput(format!(
+            "Form submission for '{}' is invalid: {}",
+            key,
+            schema::describe(&violations)
+        )))
+    }
+
+    /// Collect all `required_fields` and `payload_schema` violations for a payload.
+    ///
+    /// Fails only when `key` is not declared in the spec.
+    pub fn payload_violations(&self, key: &str, payload: &Value) -> Result<Vec<SchemaViolation>> {
         let output = self.get_output(key).ok_or_else(|| {
             LensError::InvalidInput(format!(
                 "Output key '{}' is not declared in lens.output.yaml for '{}'",
@@ -198,31 +429,105 @@ impl LensOutputSpec {
             ))
         })?;
 
-        if output.required_fields.is_empty() {
-            return Ok(());
+        Ok(output.payload_violations(payload))
+    }
+}
+
+impl OutputDefinition {
+    /// Collect all `required_fields` and `payload_schema` violations for a payload.
+    pub fn payload_violations(&self, payload: &Value) -> Vec<SchemaViolation> {
+        let mut violations = Vec::new();
+        if !self.required_fields.is_empty() {
+            match payload.as_object() {
+                Some(obj) => {
+                    for required in &self.required_fields {
+                        if !obj.contains_key(required) {
+                            violations.push(SchemaViolation {
+                                pointer: String::new(),
+                                message: format!("missing required field '{}'", required),
+                            });
+                        }
+                    }
+                }
+                None => violations.push(SchemaViolation {
+                    pointer: String::new(),
+                    message: "expected an object payload to check required_fields".to_string(),
+                }),
+            }
         }
 
-        let obj = payload.as_object().ok_or_else(|| {
-            LensError::InvalidInput(format!(
-                "Output '{}' expects an object payload to validate required_fields",
-                key
-            ))
-        })?;
+        for violation in schema::validate(&self.payload_schema, payload) {
+            if !violations.contains(&violation) {
+                violations.push(violation);
+            }
+        }
 
-        for required in &output.required_fields {
-            if !obj.contains_key(required) {
-                return Err(LensError::InvalidInput(format!(
-                    "Output '{}' missing required field '{}'",
-                    key, required
-                )));
+        violations
+    }
+
+    /// Schema of the payload field at `path` (dotted or JSON pointer).
+    ///
+    /// Returns `None` when the path is broken or passes through a part of the
+    /// schema that declares no structure.
+    pub fn property_schema(&self, path: &str) -> Option<&Value> {
+        self.resolve_source(path).ok().flatten()
+    }
+
+    /// Resolve a block `source` against `payload_schema`.
+    ///
+    /// Object segments follow `properties` (then `additionalProperties`), numeric
+    /// segments index into array `items`. `Ok(None)` means the path reached a
+    /// schema without declared structure, so it cannot be checked further.
+    pub fn resolve_source(&self, source: &str) -> std::result::Result<Option<&Value>, String> {
+        resolve_schema_path(&self.payload_schema, source)
+    }
+
+    /// Check table columns and sort field against the schema of the rows.
+    fn check_table(
+        &self,
+        block: &RenderBlock,
+        table: &options::TableOptions,
+    ) -> std::result::Result<(), String> {
+        let rows = match block.source.as_deref() {
+            Some(source) => self.resolve_source(source)?,
+            None => Some(&self.payload_schema),
+        };
+        let Some(rows) = rows else {
+            return Ok(());
+        };
+        match rows.get("type").and_then(Value::as_str) {
+            Some("array") => {}
+            Some(other) => return Err(format!("rows must be an array, found {}", other)),
+            None => return Ok(()),
+        }
+        let Some(row) = rows.get("items") else {
+            return Ok(());
+        };
+
+        for column in &table.columns {
+            let field = resolve_schema_path(row, &column.field)
+                .map_err(|e| format!("column '{}' does not resolve: {}", column.field, e))?;
+            let field_type = field.and_then(|f| f.get("type")).and_then(Value::as_str);
+            if column.format.is_numeric()
+                && field_type.is_some_and(|t| t != "number" && t != "integer")
+            {
+                return Err(format!(
+                    "column '{}' uses {:?} format on a field of type {}",
+                    column.field,
+                    column.format,
+                    field_type.unwrap_or_default()
+                ));
             }
         }
 
+        if let Some(sort) = &table.default_sort {
+            resolve_schema_path(row, &sort.field)
+                .map_err(|e| format!("default_sort '{}' does not resolve: {}", sort.field, e))?;
+        }
+
         Ok(())
     }
-}
 
-impl OutputDefinition {
     fn validate(&self, lens_id: &str) -> Result<()> {
         if self.title.trim().is_empty() {
             return Err(LensError::InvalidInput(format!(
@@ -245,6 +550,108 @@ impl OutputDefinition {
             )));
         }
 
+        if let Err(e) = schema::check_schema(&self.payload_schema) {
+            return Err(LensError::InvalidInput(format!(
+                "lens.output.yaml for '{}': output '{}' has an invalid payload_schema: {}",
+                lens_id, self.key, e
+            )));
+        }
+
+        match (&self.form, self.interactivity) {
+            (None, InteractivityMode::Form) => {
+                return Err(LensError::InvalidInput(format!(
+                    "lens.output.yaml for '{}': output '{}' uses interactivity: form but declares no form",
+                    lens_id, self.key
+                )));
+            }
+            (Some(_), mode) if mode != InteractivityMode::Form => {
+                return Err(LensError::InvalidInput(format!(
+                    "lens.output.yaml for '{}': output '{}' declares a form but interactivity is {:?}",
+                    lens_id, self.key, mode
+                )));
+            }
+            (Some(form), _) => {
+                form.validate().map_err(|e| {
+                    LensError::InvalidInput(format!(
+                        "lens.output.yaml for '{}': output '{}' has an invalid form: {}",
+                        lens_id, self.key, e
+                    ))
+                })?;
+            }
+            (None, _) => {}
+        }
+
+        for (index, block) in self.render_blocks.iter().enumerate() {
+            if let Err(e) = block.parsed_options() {
+                return Err(LensError::InvalidInput(format!(
+                    "lens.output.yaml for '{}': output '{}' render block {} has invalid options: {}",
+                    lens_id, self.key, index, e
+                )));
+            }
+        }
+
+        for (index, block) in self.render_blocks.iter().enumerate() {
+            let Some(source) = block.source.as_deref() else {
+                continue;
+            };
+            if let Err(e) = self.resolve_source(source) {
+                return Err(LensError::InvalidInput(format!(
+                    "lens.output.yaml for '{}': output '{}' render block {} source '{}' does not resolve: {}",
+                    lens_id, self.key, index, source, e
+                )));
+            }
+        }
+
+        for (index, block) in self.render_blocks.iter().enumerate() {
+            if let Ok(BlockOptions::Table(table)) = block.parsed_options() {
+                self.check_table(block, &table).map_err(|e| {
+                    LensError::InvalidInput(format!(
+                        "lens.output.yaml for '{}': output '{}' render block {} has an invalid table: {}",
+                        lens_id, self.key, index, e
+                    ))
+                })?;
+            }
+        }
+
+        for (index, block) in self.render_blocks.iter().enumerate() {
+            if !matches!(
+                block.block_type,
+                RenderBlockType::Markdown | RenderBlockType::Image | RenderBlockType::Media
+            ) {
+                continue;
+            }
+            let declared_type = block
+                .source
+                .as_deref()
+                .and_then(|source| self.property_schema(source))
+                .and_then(|schema| schema.get("type"));
+            if let Some(declared) = declared_type {
+                if declared != "string" {
+                    return Err(LensError::InvalidInput(format!(
+                        "lens.output.yaml for '{}': output '{}' render block {} binds {:?} to non-string field '{}'",
+                        lens_id,
+                        self.key,
+                        index,
+                        block.block_type,
+                        block.source.as_deref().unwrap_or_default()
+                    )));
+                }
+            }
+        }
+
+        for (index, example) in self.examples.iter().enumerate() {
+            let violations = self.payload_violations(example);
+            if !violations.is_empty() {
+                return Err(LensError::InvalidInput(format!(
+                    "lens.output.yaml for '{}': output '{}' example {} does not match payload_schema: {}",
+                    lens_id,
+                    self.key,
+                    index,
+                    schema::describe(&violations)
+                )));
+            }
+        }
+
         for field in &self.required_fields {
             if field.trim().is_empty() {
                 return Err(LensError::InvalidInput(format!(
@@ -283,6 +690,81 @@ impl OutputDefinition {
     }
 }
 
+/// Resolve a dotted or pointer `path` against `schema`; see [`OutputDefinition::resolve_source`].
+fn resolve_schema_path<'a>(
+    schema: &'a Value,
+    path: &str,
+) -> std::result::Result<Option<&'a Value>, String> {
+    let mut current = schema;
+    let mut walked = String::new();
+
+    for segment in source_segments(path)? {
+        let properties = current.get("properties").and_then(Value::as_object);
+        let additional = current.get("additionalProperties");
+        let items = current.get("items");
+
+        if let Some(next) = properties.and_then(|p| p.get(&segment)) {
+            current = next;
+        } else if let Some(next) = items.filter(|_| segment.parse::<usize>().is_ok()) {
+            current = next;
+        } else if let Some(next) = additional.filter(|a| a.is_object()) {
+            current = next;
+        } else if properties.is_some() || items.is_some() || additional == Some(&Value::Bool(false))
+        {
+            return Err(if walked.is_empty() {
+                format!("'{}' is not declared in payload_schema", segment)
+            } else {
+                format!("'{}' is not declared under '{}'", segment, walked)
+            });
+        } else if let Some(scalar) = current
+            .get("type")
+            .and_then(Value::as_str)
+            .filter(|t| *t != "object" && *t != "array")
+        {
+            return Err(format!(
+                "'{}' is a {}, not an object or array",
+                if walked.is_empty() {
+                    "payload"
+                } else {
+                    &walked
+                },
+                scalar
+            ));
+        } else {
+            return Ok(None);
+        }
+
+        if !walked.is_empty() {
+            walked.push('.');
+        }
+        walked.push_str(&segment);
+    }
+
+    Ok(Some(current))
+}
+
+/// Split a block `source` into path segments.
+///
+/// Accepts dotted paths (`items.0.name`, with an optional `$.` prefix) and
+/// JSON pointers (`/items/0/name`, with `~0`/`~1` escapes).
+fn source_segments(source: &str) -> std::result::Result<Vec<String>, String> {
+    let source = source.trim();
+    let segments: Vec<String> = if let Some(pointer) = source.strip_prefix('/') {
+        pointer
+            .split('/')
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+            .collect()
+    } else {
+        let dotted = source.strip_prefix("$.").unwrap_or(source);
+        dotted.split('.').map(str::to_string).collect()
+    };
+
+    if source.is_empty() || segments.iter().any(String::is_empty) {
+        return Err(format!("'{}' is not a valid path", source));
+    }
+    Ok(segments)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -377,6 +859,557 @@ outputs:
         assert!(err.to_string().contains("missing required field"));
     }
 
+    #[test]
+    fn test_validate_payload_reports_all_schema_violations() {
+        let yaml = r#"
+lens_id: test
+outputs:
+  - key: issues
+    title: Issues
+    payload_schema:
+      type: object
+      properties:
+        total:
+          type: integer
+        items:
+          type: array
+          items:
+            type: object
+            required: [id]
+            properties:
+              state:
+                enum: [open, closed]
+    render_blocks:
+      - type: table
+        source: items
+    required_fields: [total]
+    examples:
+      - total: 1
+        items: [{ id: 1, state: open }]
+"#;
+        let spec = LensOutputSpec::from_yaml(yaml).unwrap();
+        spec.validate_payload("issues", &json!({"total": 1, "items": [{"id": 1}]}))
+            .unwrap();
+
+        let violations = spec
+            .payload_violations(
+                "issues",
+                &json!({"total": "one", "items": [{"state": "lost"}]}),
+            )
+            .unwrap();
+        let pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+        assert_eq!(violations.len(), 3);
+        assert!(pointers.contains(&"/total"));
+        assert!(pointers.contains(&"/items/0"));
+        assert!(pointers.contains(&"/items/0/state"));
+
+        let err = spec.validate_payload("issues", &json!({})).unwrap_err();
+        assert!(err.to_string().contains("missing required field 'total'"));
+        assert!(spec.payload_violations("unknown", &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_reject_example_not_matching_schema() {
+        let yaml = r#"
+lens_id: test
+outputs:
+  - key: result
+    title: Result
+    payload_schema:
+      type: object
+      properties:
+        status:
+          type: string
+    render_blocks:
+      - type: json_view
+    required_fields: [status]
+    examples:
+      - status: ok
+      - status: 200
+"#;
+        let err = LensOutputSpec::from_yaml(yaml).unwrap_err();
+        assert!(err.to_string().contains("output 'result' example 1"));
+        assert!(err.to_string().contains("/status"));
+
+        let missing_required = yaml.replace("status: 200", "other: true");
+        let err = LensOutputSpec::from_yaml(&missing_required).unwrap_err();
+        assert!(err.to_string().contains("example 1"));
+        assert!(err.to_string().contains("missing required field 'status'"));
+    }
+
+    #[test]
+    fn test_reject_invalid_block_options() {
+        let yaml = r#"
+lens_id: test
+outputs:
+  - key: rows
+    title: Rows
+    render_blocks:
+      - type: header
+      - type: table
+        options:
+          columns: [name]
+          sortble: true
+    examples:
+      - name: a
+"#;
+        let err = LensOutputSpec::from_yaml(yaml).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("output 'rows' render block 1 has invalid options"));
+    }
+
+    #[test]
+    fn test_chart_blocks() {
+        let yaml = r#"
+lens_id: analytics
+outputs:
+  - key: traffic
+    title: Traffic
+    payload_schema:
+      type: object
+      properties:
+        daily:
+          type: array
+    render_blocks:
+      - type: line_chart
+        title: Visits per day
+        options:
+          x_label: Day
+          y_label: Visits
+          series:
+            - source: daily
+              x: date
+              y: visits
+              label: Visits
+      - type: pie_chart
+        options:
+          series:
+            - source: daily
+              x: date
+              y: visits
+    examples:
+      - daily: [{ date: "2026-01-01", visits: 10 }]
+"#;
+        let spec = LensOutputSpec::from_yaml(yaml).unwrap();
+        let blocks = &spec.outputs[0].render_blocks;
+        assert_eq!(blocks[0].block_type, RenderBlockType::LineChart);
+        assert_eq!(blocks[1].block_type, RenderBlockType::PieChart);
+
+        let no_series = yaml.replace(
+            "      - type: pie_chart\n        options:\n          series:",
+            "      - type: bar_chart\n        options:\n          unused_series:",
+        );
+        assert!(LensOutputSpec::from_yaml(&no_series).is_err());
+    }
+
+    #[test]
+    fn test_markdown_block() {
+        let yaml = r#"
+lens_id: summarizer
+outputs:
+  - key: summary
+    title: Summary
+    payload_schema:
+      type: object
+      properties:
+        body:
+          type: string
+        stats:
+          type: object
+          properties:
+            words:
+              type: integer
+    render_blocks:
+      - type: markdown
+        source: body
+        options:
+          sanitize: basic
+          max_length: 4000
+    examples:
+      - body: "**Summary** of the run"
+"#;
+        let spec = LensOutputSpec::from_yaml(yaml).unwrap();
+        assert_eq!(
+            spec.outputs[0].property_schema("stats.words").unwrap()["type"],
+            "integer"
+        );
+
+        let non_string = yaml.replace("source: body", "source: stats.words");
+        let err = LensOutputSpec::from_yaml(&non_string).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Markdown to non-string field 'stats.words'"));
+    }
+
+    #[test]
+    fn test_render_block_sources_resolve() {
+        let yaml = r#"
+lens_id: figma
+outputs:
+  - key: tokens
+    title: Tokens
+    payload_schema:
+      type: object
+      properties:
+        summary:
+          type: string
+        colors:
+          type: array
+          items:
+            type: object
+            properties:
+              hex:
+                type: string
+        extra: {}
+    render_blocks:
+      - type: table
+        source: colors
+    examples:
+      - colors: []
+"#;
+        let spec = LensOutputSpec::from_yaml(yaml).unwrap();
+        let output = &spec.outputs[0];
+        assert_eq!(
+            output.property_schema("colors.0.hex").unwrap()["type"],
+            "string"
+        );
+        assert_eq!(
+            output.property_schema("/colors/0/hex"),
+            output.property_schema("$.colors.0.hex")
+        );
+        assert_eq!(output.resolve_source("extra.anything"), Ok(None));
+        assert_eq!(
+            output.render_blocks[0].source_pointer().as_deref(),
+            Some("/colors")
+        );
+
+        for (source, reason) in [
+            ("colour", "'colour' is not declared in payload_schema"),
+            ("/colors/0/rgb", "'rgb' is not declared under 'colors.0'"),
+            ("summary.length", "'summary' is a string"),
+            ("colors..hex", "is not a valid path"),
+        ] {
+            let broken = yaml.replace("source: colors", &format!("source: \"{}\"", source));
+            let err = LensOutputSpec::from_yaml(&broken).unwrap_err().to_string();
+            assert!(err.contains("does not resolve"), "{}", err);
+            assert!(err.contains(reason), "{}", err);
+        }
+
+        // Without declared structure there is nothing to check against
+        let untyped = r#"
+lens_id: figma
+outputs:
+  - key: raw
+    title: Raw
+    render_blocks:
+      - type: json_view
+        source: anything.at.all
+    examples:
+      - {}
+"#;
+        assert!(LensOutputSpec::from_yaml(untyped).is_ok());
+    }
+
+    #[test]
+    fn test_table_columns_match_row_schema() {
+        let yaml = r#"
+lens_id: billing
+outputs:
+  - key: invoices
+    title: Invoices
+    payload_schema:
+      type: object
+      properties:
+        rows:
+          type: array
+          items:
+            type: object
+            properties:
+              customer:
+                type: object
+                properties:
+                  name: { type: string }
+              amount: { type: number }
+              note: { type: string }
+    render_blocks:
+      - type: table
+        source: rows
+        options:
+          columns:
+            - customer.name
+            - { field: amount, format: currency }
+            - { field: note, header: Note }
+          default_sort: { field: amount, direction: desc }
+    examples:
+      - rows: []
+"#;
+        LensOutputSpec::from_yaml(yaml).unwrap();
+
+        for (from, to, reason) in [
+            (
+                "- customer.name",
+                "- customer.email",
+                "column 'customer.email' does not resolve",
+            ),
+            (
+                "header: Note",
+                "format: percent",
+                "Percent format on a field of type string",
+            ),
+            (
+                "source: rows",
+                "source: rows.0",
+                "rows must be an array, found object",
+            ),
+        ] {
+            let err = LensOutputSpec::from_yaml(&yaml.replace(from, to))
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("invalid table"), "{}", err);
+            assert!(err.contains(reason), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_localized_spec() {
+        let yaml = r#"
+lens_id: figma
+outputs:
+  - key: summary
+    title: Summary
+    description: Run summary
+    title_i18n:
+      de: Zusammenfassung
+      pt: Resumo
+    description_i18n:
+      de: Zusammenfassung des Laufs
+    render_blocks:
+      - type: header
+        title: Overview
+        title_i18n:
+          pt-BR: Visão geral
+        description_i18n:
+          pt-BR: Totais da execução
+    examples:
+      - {}
+"#;
+        let spec = LensOutputSpec::from_yaml(yaml).unwrap();
+
+        let german = spec.localized("de-AT");
+        assert_eq!(german.outputs[0].title, "Zusammenfassung");
+        assert_eq!(german.outputs[0].description, "Zusammenfassung des Laufs");
+        assert_eq!(
+            german.outputs[0].render_blocks[0].title.as_deref(),
+            Some("Overview")
+        );
+
+        let brazilian = spec.localized("pt-BR");
+        assert_eq!(brazilian.outputs[0].title, "Resumo");
+        assert_eq!(brazilian.outputs[0].description, "Run summary");
+        let block = &brazilian.outputs[0].render_blocks[0];
+        assert_eq!(block.title.as_deref(), Some("Visão geral"));
+        assert_eq!(block.description.as_deref(), Some("Totais da execução"));
+
+        let portuguese = spec.localized("pt");
+        assert_eq!(
+            portuguese.outputs[0].render_blocks[0].title.as_deref(),
+            Some("Overview")
+        );
+    }
+
+    #[test]
+    fn test_image_block() {
+        let yaml = r#"
+lens_id: figma
+outputs:
+  - key: component_preview
+    title: Component Preview
+    payload_schema:
+      type: object
+      properties:
+        thumbnail:
+          type: string
+        name:
+          type: string
+    render_blocks:
+      - type: image
+        source: thumbnail
+        options:
+          source_kind: base64
+          mime_types: [image/png, image/webp]
+          alt_field: name
+    examples:
+      - thumbnail: iVBORw0KGgo=
+        name: Button
+"#;
+        let spec = LensOutputSpec::from_yaml(yaml).unwrap();
+        match spec.outputs[0].render_blocks[0].parsed_options().unwrap() {
+            BlockOptions::Image(options) => assert!(options.accepts("image/png")),
+            other => panic!("unexpected options {:?}", other),
+        }
+
+        let wrong_mime = yaml.replace("image/webp", "video/mp4");
+        let err = LensOutputSpec::from_yaml(&wrong_mime).unwrap_err();
+        assert!(err.to_string().contains("video/mp4"));
+    }
+
+    #[test]
+    fn test_form_output() {
+        let yaml = r#"
+lens_id: tracker
+outputs:
+  - key: new_issue
+    title: New Issue
+    render_blocks:
+      - type: notice
+    interactivity: form
+    form:
+      submit_label: Create
+      fields:
+        - name: title
+          type: text
+          label: Title
+          required: true
+        - name: priority
+          type: select
+          label: Priority
+          options: [low, high]
+          default: low
+    examples:
+      - {}
+"#;
+        let spec = LensOutputSpec::from_yaml(yaml).unwrap();
+        let form = spec.outputs[0].form.as_ref().unwrap();
+        assert_eq!(form.fields.len(), 2);
+        assert_eq!(form.fields[1].field_type, FormFieldType::Select);
+
+        spec.validate_submission(
+            &FormSubmission::new("new_issue").with_value("title", json!("Broken login")),
+        )
+        .unwrap();
+        let err = spec
+            .validate_submission(&FormSubmission::new("new_issue"))
+            .unwrap_err();
+        assert!(err.to_string().contains("title"));
+
+        let missing_form = yaml.replace("    form:\n      submit_label: Create\n", "    unused:\n");
+        let err = LensOutputSpec::from_yaml(&missing_form).unwrap_err();
+        assert!(err.to_string().contains("declares no form"));
+
+        let wrong_mode = yaml.replace("interactivity: form", "interactivity: confirm");
+        let err = LensOutputSpec::from_yaml(&wrong_mode).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("declares a form but interactivity is Confirm"));
+    }
+
+    #[test]
+    fn test_visible_if_blocks() {
+        let yaml = r#"
+lens_id: test
+outputs:
+  - key: status
+    title: Status
+    render_blocks:
+      - type: header
+      - type: notice
+        visible_if: "warnings && level != 'ok'"
+    examples:
+      - level: ok
+"#;
+        let spec = LensOutputSpec::from_yaml(yaml).unwrap();
+        let blocks = &spec.outputs[0].render_blocks;
+        let payload = json!({"warnings": ["disk"], "level": "warn"});
+        assert!(blocks[0].is_visible(&payload));
+        assert!(blocks[1].is_visible(&payload));
+        assert!(!blocks[1].is_visible(&json!({"level": "warn"})));
+
+        let round_trip = LensOutputSpec::from_yaml(&spec.to_yaml().unwrap()).unwrap();
+        assert_eq!(
+            round_trip.outputs[0].render_blocks[1]
+                .visible_if
+                .as_ref()
+                .unwrap()
+                .as_str(),
+            "warnings && level != 'ok'"
+        );
+
+        let invalid = yaml.replace("level != 'ok'", "level = 'ok'");
+        let err = LensOutputSpec::from_yaml(&invalid).unwrap_err();
+        assert!(err.to_string().contains("Invalid visible_if"));
+    }
+
+    #[test]
+    fn test_from_file_merges_extends_and_include() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("outputs")).unwrap();
+
+        let output = |key: &str, title: &str| {
+            format!(
+                "  - key: {}\n    title: {}\n    render_blocks:\n      - type: notice\n    examples:\n      - {{}}\n",
+                key, title
+            )
+        };
+
+        std::fs::write(
+            root.join("base.yaml"),
+            format!(
+                "outputs:\n{}{}",
+                output("status", "Base Status"),
+                output("log", "Log")
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("outputs/phase_0.yaml"),
+            format!("lens_id: figma\noutputs:\n{}", output("phase_0", "Phase 0")),
+        )
+        .unwrap();
+        std::fs::write(
+            root.join(OUTPUT_SPEC_FILENAME),
+            format!(
+                "lens_id: figma\nextends: base.yaml\ninclude:\n  - outputs/phase_0.yaml\noutputs:\n{}",
+                output("status", "Status")
+            ),
+        )
+        .unwrap();
+
+        let spec = LensOutputSpec::from_file(root.join(OUTPUT_SPEC_FILENAME)).unwrap();
+        let keys: Vec<&str> = spec.outputs.iter().map(|o| o.key.as_str()).collect();
+        assert_eq!(keys, vec!["status", "log", "phase_0"]);
+        assert_eq!(spec.get_output("status").unwrap().title, "Status");
+        assert!(spec.include.is_empty() && spec.extends.is_none());
+
+        // Included fragments may not clash with each other
+        std::fs::write(
+            root.join("outputs/phase_1.yaml"),
+            format!("outputs:\n{}", output("phase_0", "Dup")),
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("dup.yaml"),
+            "lens_id: figma\ninclude: [outputs/phase_0.yaml, outputs/phase_1.yaml]\n",
+        )
+        .unwrap();
+        let err = LensOutputSpec::from_file(root.join("dup.yaml")).unwrap_err();
+        assert!(err.to_string().contains("duplicate output key 'phase_0'"));
+
+        std::fs::write(
+            root.join("cycle.yaml"),
+            "lens_id: figma\nextends: cycle.yaml\n",
+        )
+        .unwrap();
+        let err = LensOutputSpec::from_file(root.join("cycle.yaml")).unwrap_err();
+        assert!(err.to_string().contains("included recursively"));
+
+        let err = LensOutputSpec::from_yaml("lens_id: figma\ninclude: [a.yaml]\n").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("only supported when loading from a file"));
+    }
+
     #[test]
     fn test_reject_missing_examples() {
         let yaml = r#"
@@ -0,0 +1,356 @@
+//! # Payload Code Generation
+//!
+//! Turns `lens.output.yaml` into Rust payload structs plus `emit_<key>` helpers,
+//! so payloads that drift from the spec fail to compile instead of failing to render.
+//!
+//! Typical `build.rs`:
+//!
+//! ```rust,ignore
+//! fn main() {
+//!     let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("lens_outputs.rs");
+//!     lens::output_spec::codegen::generate_to_file("lens.output.yaml", &out).unwrap();
+//!     println!("cargo:rerun-if-changed=lens.output.yaml");
+//! }
+//! ```
+//!
+//! and in the lens crate (which must also depend on `serde`):
+//!
+//! ```rust,ignore
+//! mod outputs {
+//!     include!(concat!(env!("OUT_DIR"), "/lens_outputs.rs"));
+//! }
+//!
+//! outputs::emit_phase_0_tokens(&emitter, &outputs::Phase0TokensPayload { colors: vec![] }).await?;
+//! ```
+//!
+//! Schema mapping: `string` → `String`, `integer` → `i64`, `number` → `f64`,
+//! `boolean` → `bool`, `array` → `Vec<T>`, `object` with `properties` → a nested
+//! struct, anything else → `serde_json::Value`. Fields that are neither listed in
+//! `required` nor `required_fields`, or whose type allows `null`, become `Option<T>`.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::{LensOutputSpec, OutputDefinition};
+use crate::error::Result;
+
+const RESERVED: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof", "unsized",
+    "virtual", "yield", "try", "gen",
+];
+
+/// Generate Rust source for every output in `spec`.
+pub fn generate(spec: &LensOutputSpec) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "// @generated from lens.output.yaml for '{}'. Do not edit.\n",
+        spec.lens_id
+    );
+    for output in &spec.outputs {
+        generate_output(output, &mut out);
+    }
+    out
+}
+
+/// Load `spec_path` (resolving `extends`/`include`) and write the generated source to `out_path`.
+pub fn generate_to_file(spec_path: impl AsRef<Path>, out_path: impl AsRef<Path>) -> Result<()> {
+    let spec = LensOutputSpec::from_file(spec_path)?;
+    std::fs::write(out_path, generate(&spec))?;
+    Ok(())
+}
+
+fn generate_output(output: &OutputDefinition, out: &mut String) {
+    let type_name = format!("{}Payload", pascal_case(&output.key));
+    let const_name = format!("{}_KEY", snake_case(&output.key).to_uppercase());
+    let fn_name = format!("emit_{}", snake_case(&output.key));
+
+    let _ = writeln!(out, "/// Output key for `{}`.", output.title);
+    let _ = writeln!(
+        out,
+        "pub const {}: &str = {:?};\n",
+        const_name.trim_start_matches('_'),
+        output.key
+    );
+
+    let mut structs = Vec::new();
+    let root = &output.payload_schema;
+    if root.get("properties").and_then(Value::as_object).is_some() {
+        generate_struct(
+            &type_name,
+            Some(&output.title),
+            root,
+            &output.required_fields,
+            &mut structs,
+        );
+    } else {
+        let rust_type = rust_type(root, &type_name, &mut structs);
+        structs.push(format!(
+            "/// Payload for `{}`.\npub type {} = {};\n",
+            output.title, type_name, rust_type
+        ));
+    }
+    for definition in structs {
+        out.push_str(&definition);
+        out.push('\n');
+    }
+
+    let _ = writeln!(out, "/// Emit a `{}` payload.", output.key);
+    let _ = writeln!(
+        out,
+        "pub async fn {}(emitter: &::lens::EventEmitter, payload: &{}) -> ::lens::Result<()> {{\n    emitter.data({}, payload).await\n}}\n",
+        fn_name,
+        type_name,
+        const_name.trim_start_matches('_')
+    );
+}
+
+fn generate_struct(
+    name: &str,
+    doc: Option<&str>,
+    schema: &Value,
+    extra_required: &[String],
+    structs: &mut Vec<String>,
+) {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let mut body = String::new();
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        for (field, field_schema) in properties {
+            let ident = field_ident(field);
+            let nested_name = format!("{}{}", name, pascal_case(field));
+            let mut rust_type = rust_type(field_schema, &nested_name, structs);
+            let is_required =
+                required.contains(&field.as_str()) || extra_required.iter().any(|r| r == field);
+
+            if let Some(description) = field_schema.get("description").and_then(Value::as_str) {
+                let _ = writeln!(body, "    /// {}", description);
+            }
+            if ident.trim_start_matches("r#") != field {
+                let _ = writeln!(body, "    #[serde(rename = {:?})]", field);
+            }
+            if !is_required || allows_null(field_schema) {
+                if !rust_type.starts_with("Option<") {
+                    rust_type = format!("Option<{}>", rust_type);
+                }
+                let _ = writeln!(
+                    body,
+                    "    #[serde(default, skip_serializing_if = \"Option::is_none\")]"
+                );
+            }
+            let _ = writeln!(body, "    pub {}: {},", ident, rust_type);
+        }
+    }
+
+    let mut definition = String::new();
+    if let Some(doc) = doc {
+        let _ = writeln!(definition, "/// Payload for `{}`.", doc);
+    }
+    let _ = writeln!(
+        definition,
+        "#[derive(Debug, Clone, PartialEq, ::serde::Serialize, ::serde::Deserialize)]\npub struct {} {{\n{}}}",
+        name, body
+    );
+    structs.insert(0, definition);
+}
+
+fn rust_type(schema: &Value, name: &str, structs: &mut Vec<String>) -> String {
+    let base = match primary_type(schema) {
+        Some("string") => "String".to_string(),
+        Some("integer") => "i64".to_string(),
+        Some("number") => "f64".to_string(),
+        Some("boolean") => "bool".to_string(),
+        Some("array") => {
+            let item = schema
+                .get("items")
+                .map(|items| rust_type(items, &format!("{}Item", name), structs))
+                .unwrap_or_else(|| "::serde_json::Value".to_string());
+            format!("Vec<{}>", item)
+        }
+        Some("object") | None
+            if schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .is_some() =>
+        {
+            generate_struct(name, None, schema, &[], structs);
+            name.to_string()
+        }
+        _ => "::serde_json::Value".to_string(),
+    };
+    if allows_null(schema) && base != "::serde_json::Value" {
+        format!("Option<{}>", base)
+    } else {
+        base
+    }
+}
+
+fn primary_type(schema: &Value) -> Option<&str> {
+    match schema.get("type")? {
+        Value::String(t) => Some(t),
+        Value::Array(types) => {
+            let non_null: Vec<&str> = types
+                .iter()
+                .filter_map(Value::as_str)
+                .filter(|t| *t != "null")
+                .collect();
+            match non_null.as_slice() {
+                [single] => Some(single),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn allows_null(schema: &Value) -> bool {
+    schema
+        .get("type")
+        .and_then(Value::as_array)
+        .is_some_and(|types| types.iter().any(|t| t == "null"))
+}
+
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_ascii_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        current.push(c.to_ascii_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn pascal_case(name: &str) -> String {
+    let pascal: String = words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    if pascal.is_empty() || pascal.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("Output{}", pascal)
+    } else {
+        pascal
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let snake = words(name).join("_");
+    if snake.is_empty() || snake.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", snake)
+    } else {
+        snake
+    }
+}
+
+fn field_ident(name: &str) -> String {
+    let ident = snake_case(name);
+    match ident.as_str() {
+        "self" | "super" | "crate" | "extern" => format!("{}_", ident),
+        _ if RESERVED.contains(&ident.as_str()) => format!("r#{}", ident),
+        _ => ident,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_payload_structs() {
+        let spec = LensOutputSpec::from_yaml(
+            r#"
+lens_id: figma
+outputs:
+  - key: phase_0_tokens
+    title: Design Tokens
+    payload_schema:
+      type: object
+      required: [colors]
+      properties:
+        colors:
+          type: array
+          items:
+            type: object
+            required: [name]
+            properties:
+              name: { type: string }
+              hexValue: { type: string }
+        type: { type: [string, "null"] }
+        total: { type: integer, description: Number of tokens }
+    render_blocks:
+      - type: table
+        source: colors
+    required_fields: [total]
+    examples:
+      - colors: []
+        total: 0
+  - key: raw-dump
+    title: Raw
+    render_blocks:
+      - type: json_view
+    examples:
+      - {}
+"#,
+        )
+        .unwrap();
+
+        let code = generate(&spec);
+
+        assert!(code.contains("pub const PHASE_0_TOKENS_KEY: &str = \"phase_0_tokens\";"));
+        assert!(code.contains("pub struct Phase0TokensPayload {"));
+        assert!(code.contains("    pub colors: Vec<Phase0TokensPayloadColorsItem>,"));
+        assert!(code.contains("    /// Number of tokens\n    pub total: i64,"));
+        assert!(code.contains(
+            "    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub r#type: Option<String>,"
+        ));
+        assert!(code.contains("pub struct Phase0TokensPayloadColorsItem {"));
+        assert!(code.contains("    pub name: String,"));
+        assert!(code.contains("    #[serde(rename = \"hexValue\")]"));
+        assert!(code.contains("    pub hex_value: Option<String>,"));
+        assert!(code.contains(
+            "pub async fn emit_phase_0_tokens(emitter: &::lens::EventEmitter, payload: &Phase0TokensPayload)"
+        ));
+
+        assert!(code.contains("pub type RawDumpPayload = ::serde_json::Value;"));
+        assert!(code.contains("pub async fn emit_raw_dump("));
+    }
+
+    #[test]
+    fn test_identifier_helpers() {
+        assert_eq!(pascal_case("phase_0_tokens"), "Phase0Tokens");
+        assert_eq!(pascal_case("componentPreview"), "ComponentPreview");
+        assert_eq!(pascal_case("3d-view"), "Output3dView");
+        assert_eq!(snake_case("componentPreview"), "component_preview");
+        assert_eq!(field_ident("match"), "r#match");
+        assert_eq!(field_ident("self"), "self_");
+        assert_eq!(field_ident("2fa"), "_2fa");
+    }
+}
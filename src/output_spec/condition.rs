@@ -0,0 +1,441 @@
+//! # Block Visibility Conditions
+//!
+//! Grammar for `RenderBlock.visible_if`, parsed when the spec is loaded:
+//!
+//! ```text
+//! expr       := and ("||" and)*
+//! and        := unary ("&&" unary)*
+//! unary      := "!" unary | "(" expr ")" | comparison
+//! comparison := path (op literal)?
+//! path       := ["$."] name ("." name | "[" index "]")*
+//! op         := "==" | "!=" | ">" | ">=" | "<" | "<="
+//! literal    := number | 'string' | "string" | true | false | null
+//! ```
+//!
+//! A bare path is true when the field exists and is truthy (not `null`,
+//! `false`, `0`, `""`, `[]`, or `{}`). Comparisons against a missing field are
+//! false, except `!=`.
+//!
+//! ```yaml
+//! render_blocks:
+//!   - type: notice
+//!     visible_if: "warnings && summary.status != 'ok'"
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::LensError;
+
+/// A parsed `visible_if` expression.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Or(Vec<Expr>),
+    And(Vec<Expr>),
+    Not(Box<Expr>),
+    Truthy(Vec<Segment>),
+    Compare(Vec<Segment>, Op, Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Dot,
+    Index(usize),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+    Literal(Value),
+}
+
+impl Condition {
+    /// Parse an expression.
+    pub fn parse(source: &str) -> crate::Result<Self> {
+        let invalid = |reason: String| {
+            LensError::InvalidInput(format!("Invalid visible_if '{}': {}", source, reason))
+        };
+
+        let tokens = tokenize(source).map_err(invalid)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.expr().map_err(invalid)?;
+        if parser.pos != parser.tokens.len() {
+            return Err(invalid(format!(
+                "unexpected token {:?}",
+                parser.tokens[parser.pos]
+            )));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    /// The expression as written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluate against a payload.
+    pub fn evaluate(&self, payload: &Value) -> bool {
+        eval(&self.expr, payload)
+    }
+}
+
+impl TryFrom<String> for Condition {
+    type Error = LensError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<Condition> for String {
+    fn from(condition: Condition) -> Self {
+        condition.source
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn eval(expr: &Expr, payload: &Value) -> bool {
+    match expr {
+        Expr::Or(exprs) => exprs.iter().any(|e| eval(e, payload)),
+        Expr::And(exprs) => exprs.iter().all(|e| eval(e, payload)),
+        Expr::Not(inner) => !eval(inner, payload),
+        Expr::Truthy(path) => resolve(path, payload).is_some_and(is_truthy),
+        Expr::Compare(path, op, literal) => match resolve(path, payload) {
+            Some(value) => compare(value, *op, literal),
+            None => *op == Op::Ne,
+        },
+    }
+}
+
+fn resolve<'a>(path: &[Segment], payload: &'a Value) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(payload, |value, segment| match segment {
+            Segment::Key(key) => value.get(key),
+            Segment::Index(index) => value.get(index),
+        })
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+fn compare(value: &Value, op: Op, literal: &Value) -> bool {
+    if let (Some(a), Some(b)) = (value.as_f64(), literal.as_f64()) {
+        return match op {
+            Op::Eq => a == b,
+            Op::Ne => a != b,
+            Op::Gt => a > b,
+            Op::Ge => a >= b,
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+        };
+    }
+    if let (Some(a), Some(b)) = (value.as_str(), literal.as_str()) {
+        return match op {
+            Op::Eq => a == b,
+            Op::Ne => a != b,
+            Op::Gt => a > b,
+            Op::Ge => a >= b,
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+        };
+    }
+    match op {
+        Op::Eq => value == literal,
+        Op::Ne => value != literal,
+        _ => false,
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '$' if next == Some('.') && tokens.is_empty() => i += 2,
+            '.' => {
+                tokens.push(Token::Dot);
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .ok_or("unclosed '['")?;
+                let index: String = chars[i + 1..i + end].iter().collect();
+                let index = index
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid index '{}'", index))?;
+                tokens.push(Token::Index(index));
+                i += end + 1;
+            }
+            '&' | '|' => {
+                if next != Some(c) {
+                    return Err(format!("expected '{}{}'", c, c));
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+                i += 2;
+            }
+            '=' | '!' | '<' | '>' => {
+                let (token, len) = match (c, next) {
+                    ('=', Some('=')) => (Token::Op(Op::Eq), 2),
+                    ('!', Some('=')) => (Token::Op(Op::Ne), 2),
+                    ('<', Some('=')) => (Token::Op(Op::Le), 2),
+                    ('>', Some('=')) => (Token::Op(Op::Ge), 2),
+                    ('<', _) => (Token::Op(Op::Lt), 1),
+                    ('>', _) => (Token::Op(Op::Gt), 1),
+                    ('!', _) => (Token::Not, 1),
+                    _ => return Err("use '==' for equality".to_string()),
+                };
+                tokens.push(token);
+                i += len;
+            }
+            '\'' | '"' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|ch| *ch == c)
+                    .ok_or("unterminated string")?;
+                let text: String = chars[i + 1..i + 1 + end].iter().collect();
+                tokens.push(Token::Literal(Value::String(text)));
+                i += end + 2;
+            }
+            c if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number: f64 = text
+                    .parse()
+                    .map_err(|_| format!("invalid number '{}'", text))?;
+                let value = serde_json::Number::from_f64(number)
+                    .map(Value::Number)
+                    .ok_or_else(|| format!("invalid number '{}'", text))?;
+                tokens.push(Token::Literal(value));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '-'))
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                tokens.push(match word.as_str() {
+                    "true" => Token::Literal(Value::Bool(true)),
+                    "false" => Token::Literal(Value::Bool(false)),
+                    "null" => Token::Literal(Value::Null),
+                    _ => Token::Ident(word),
+                });
+            }
+            other => return Err(format!("unexpected character '{}'", other)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut terms = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expr::Or(terms)
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut terms = vec![self.unary()?];
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            terms.push(self.unary()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expr::And(terms)
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let inner = self.expr()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err("expected ')'".to_string()),
+                }
+            }
+            _ => self.comparison(),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let path = self.path()?;
+        let Some(Token::Op(op)) = self.peek().cloned() else {
+            return Ok(Expr::Truthy(path));
+        };
+        self.pos += 1;
+        match self.next() {
+            Some(Token::Literal(value)) => Ok(Expr::Compare(path, op, value)),
+            _ => Err("expected a literal after comparison operator".to_string()),
+        }
+    }
+
+    fn path(&mut self) -> Result<Vec<Segment>, String> {
+        let mut path = match self.next() {
+            Some(Token::Ident(name)) => vec![Segment::Key(name)],
+            other => return Err(format!("expected a field path, found {:?}", other)),
+        };
+        loop {
+            match self.peek() {
+                Some(Token::Dot) => {
+                    self.pos += 1;
+                    match self.next() {
+                        Some(Token::Ident(name)) => path.push(Segment::Key(name)),
+                        _ => return Err("expected a field name after '.'".to_string()),
+                    }
+                }
+                Some(Token::Index(index)) => {
+                    path.push(Segment::Index(*index));
+                    self.pos += 1;
+                }
+                _ => return Ok(path),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_truthiness_and_comparisons() {
+        let payload = json!({
+            "warnings": ["slow"],
+            "errors": [],
+            "summary": {"status": "degraded", "score": 72},
+            "rows": [{"id": 1}],
+            "draft": false
+        });
+
+        let visible = |expr: &str| Condition::parse(expr).unwrap().evaluate(&payload);
+
+        assert!(visible("warnings"));
+        assert!(!visible("errors"));
+        assert!(!visible("missing"));
+        assert!(!visible("draft"));
+        assert!(visible("!draft"));
+        assert!(visible("summary.status == 'degraded'"));
+        assert!(visible("$.summary.score >= 70"));
+        assert!(!visible("summary.score < 50"));
+        assert!(visible("rows[0].id == 1"));
+        assert!(visible("missing != 'x'"));
+        assert!(!visible("missing == null"));
+        assert!(visible(
+            "warnings && (summary.score > 90 || summary.status != \"ok\")"
+        ));
+        assert!(!visible("errors || draft == true"));
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for expr in [
+            "",
+            "status =",
+            "status = 'ok'",
+            "a & b",
+            "(a",
+            "'ok' == status",
+            "rows[x]",
+            "a == 'open",
+            "a b",
+        ] {
+            let err = Condition::parse(expr).unwrap_err();
+            assert!(err.to_string().contains("Invalid visible_if"), "{}", expr);
+        }
+    }
+}
@@ -0,0 +1,356 @@
+//! # Output Forms
+//!
+//! Field definitions for outputs declared with `interactivity: form`, and the
+//! [`FormSubmission`] shape hosts send back when the user submits.
+//!
+//! ```yaml
+//! interactivity: form
+//! form:
+//!   submit_label: Create issue
+//!   fields:
+//!     - name: title
+//!       type: text
+//!       label: Title
+//!       required: true
+//!       validation: { max_length: 120 }
+//!     - name: priority
+//!       type: select
+//!       label: Priority
+//!       options: [low, medium, high]
+//!       default: medium
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::schema::{self, SchemaViolation};
+
+/// Form definition attached to an interactive output.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FormSpec {
+    /// Fields in display order.
+    #[serde(default)]
+    pub fields: Vec<FormField>,
+
+    /// Label of the submit button.
+    #[serde(default)]
+    pub submit_label: Option<String>,
+}
+
+/// Input widget kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FormFieldType {
+    Text,
+    Textarea,
+    Number,
+    Integer,
+    Boolean,
+    Select,
+    Multiselect,
+    Date,
+}
+
+/// One form field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormField {
+    /// Key in `FormSubmission::values`.
+    pub name: String,
+
+    /// Widget kind.
+    #[serde(rename = "type")]
+    pub field_type: FormFieldType,
+
+    /// Human-readable label.
+    pub label: String,
+
+    /// Whether a value must be submitted.
+    #[serde(default)]
+    pub required: bool,
+
+    /// Initial value.
+    #[serde(default)]
+    pub default: Option<Value>,
+
+    /// Choices for `select` and `multiselect` fields.
+    #[serde(default)]
+    pub options: Vec<String>,
+
+    /// Extra constraints on the submitted value.
+    #[serde(default)]
+    pub validation: Option<FieldValidation>,
+}
+
+/// Constraints on a submitted field value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldValidation {
+    /// Minimum for number/integer fields.
+    #[serde(default)]
+    pub min: Option<f64>,
+
+    /// Maximum for number/integer fields.
+    #[serde(default)]
+    pub max: Option<f64>,
+
+    /// Minimum length for text fields.
+    #[serde(default)]
+    pub min_length: Option<u64>,
+
+    /// Maximum length for text fields.
+    #[serde(default)]
+    pub max_length: Option<u64>,
+
+    /// Regular expression for text fields (enforced with the `json-schema` feature).
+    #[serde(default)]
+    pub pattern: Option<String>,
+}
+
+/// Values a host sends back when the user submits a form.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FormSubmission {
+    /// Output key the form belongs to.
+    pub output_key: String,
+
+    /// Submitted values keyed by field name.
+    #[serde(default)]
+    pub values: Map<String, Value>,
+}
+
+impl FormSubmission {
+    /// Create an empty submission for an output.
+    pub fn new(output_key: impl Into<String>) -> Self {
+        Self {
+            output_key: output_key.into(),
+            values: Map::new(),
+        }
+    }
+
+    /// Set a field value.
+    pub fn with_value(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.values.insert(name.into(), value);
+        self
+    }
+}
+
+impl FormSpec {
+    /// Get a field by name.
+    pub fn get_field(&self, name: &str) -> Option<&FormField> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// JSON Schema describing valid `FormSubmission::values`.
+    pub fn to_json_schema(&self) -> Value {
+        let properties: Map<String, Value> = self
+            .fields
+            .iter()
+            .map(|field| (field.name.clone(), field.value_schema()))
+            .collect();
+        let required: Vec<&str> = self
+            .fields
+            .iter()
+            .filter(|f| f.required)
+            .map(|f| f.name.as_str())
+            .collect();
+
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        })
+    }
+
+    /// Validate submitted values, returning every violation.
+    pub fn validate_values(&self, values: &Map<String, Value>) -> Vec<SchemaViolation> {
+        schema::validate(&self.to_json_schema(), &Value::Object(values.clone()))
+    }
+
+    /// Check the form definition itself.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.fields.is_empty() {
+            return Err("form must declare at least one field".to_string());
+        }
+
+        let mut names = std::collections::HashSet::new();
+        for field in &self.fields {
+            if field.name.trim().is_empty() {
+                return Err("form field name cannot be empty".to_string());
+            }
+            if !names.insert(field.name.as_str()) {
+                return Err(format!("duplicate form field '{}'", field.name));
+            }
+            field.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl FormField {
+    fn is_choice(&self) -> bool {
+        matches!(
+            self.field_type,
+            FormFieldType::Select | FormFieldType::Multiselect
+        )
+    }
+
+    /// JSON Schema for this field's value.
+    pub fn value_schema(&self) -> Value {
+        let mut schema = match self.field_type {
+            FormFieldType::Text | FormFieldType::Textarea => json!({"type": "string"}),
+            FormFieldType::Date => json!({"type": "string", "format": "date"}),
+            FormFieldType::Number => json!({"type": "number"}),
+            FormFieldType::Integer => json!({"type": "integer"}),
+            FormFieldType::Boolean => json!({"type": "boolean"}),
+            FormFieldType::Select => json!({"type": "string", "enum": self.options}),
+            FormFieldType::Multiselect => json!({
+                "type": "array",
+                "items": {"type": "string", "enum": self.options},
+            }),
+        };
+
+        if let (Some(validation), Some(schema)) = (&self.validation, schema.as_object_mut()) {
+            let keywords = [
+                ("minimum", validation.min.map(Value::from)),
+                ("maximum", validation.max.map(Value::from)),
+                ("minLength", validation.min_length.map(Value::from)),
+                ("maxLength", validation.max_length.map(Value::from)),
+                ("pattern", validation.pattern.clone().map(Value::from)),
+            ];
+            for (keyword, value) in keywords {
+                if let Some(value) = value {
+                    schema.insert(keyword.to_string(), value);
+                }
+            }
+        }
+
+        schema
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.label.trim().is_empty() {
+            return Err(format!("form field '{}' must have a label", self.name));
+        }
+
+        if self.is_choice() && self.options.is_empty() {
+            return Err(format!(
+                "form field '{}' of type {:?} requires options",
+                self.name, self.field_type
+            ));
+        }
+        if !self.is_choice() && !self.options.is_empty() {
+            return Err(format!(
+                "form field '{}' of type {:?} does not take options",
+                self.name, self.field_type
+            ));
+        }
+
+        if let Some(validation) = &self.validation {
+            if let (Some(min), Some(max)) = (validation.min, validation.max) {
+                if min > max {
+                    return Err(format!("form field '{}' has min > max", self.name));
+                }
+            }
+            if let (Some(min), Some(max)) = (validation.min_length, validation.max_length) {
+                if min > max {
+                    return Err(format!(
+                        "form field '{}' has min_length > max_length",
+                        self.name
+                    ));
+                }
+            }
+        }
+
+        if let Some(default) = &self.default {
+            let violations = schema::validate(&self.value_schema(), default);
+            if !violations.is_empty() {
+                return Err(format!(
+                    "form field '{}' default is invalid: {}",
+                    self.name,
+                    schema::describe(&violations)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form() -> FormSpec {
+        serde_json::from_value(json!({
+            "fields": [
+                {"name": "title", "type": "text", "label": "Title", "required": true,
+                 "validation": {"max_length": 10}},
+                {"name": "priority", "type": "select", "label": "Priority",
+                 "options": ["low", "high"], "default": "low"},
+                {"name": "estimate", "type": "integer", "label": "Estimate",
+                 "validation": {"min": 1, "max": 8}}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_submission_values() {
+        let form = form();
+        form.validate().unwrap();
+
+        let ok = FormSubmission::new("issue")
+            .with_value("title", json!("Fix login"))
+            .with_value("priority", json!("high"));
+        assert!(form.validate_values(&ok.values).is_empty());
+
+        let bad = FormSubmission::new("issue")
+            .with_value("priority", json!("urgent"))
+            .with_value("estimate", json!(20))
+            .with_value("assignee", json!("sam"));
+        let violations = form.validate_values(&bad.values);
+        let pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+        // missing title, bad priority, out-of-range estimate, unknown assignee
+        assert_eq!(violations.len(), 4);
+        assert!(pointers.contains(&"/priority"));
+        assert!(pointers.contains(&"/estimate"));
+        let message = schema::describe(&violations);
+        assert!(message.contains("title"));
+        assert!(message.contains("assignee"));
+    }
+
+    #[test]
+    fn test_reject_invalid_form_definitions() {
+        let mut select_without_options = form();
+        select_without_options.fields[1].options.clear();
+        select_without_options.fields[1].default = None;
+        assert!(select_without_options
+            .validate()
+            .unwrap_err()
+            .contains("requires options"));
+
+        let mut bad_default = form();
+        bad_default.fields[1].default = Some(json!("urgent"));
+        assert!(bad_default.validate().unwrap_err().contains("default"));
+
+        let mut duplicate = form();
+        duplicate.fields[2].name = "title".to_string();
+        assert!(duplicate.validate().unwrap_err().contains("duplicate"));
+
+        assert!(FormSpec::default().validate().is_err());
+    }
+
+    #[test]
+    fn test_submission_round_trip() {
+        let submission = FormSubmission::new("issue").with_value("title", json!("x"));
+        let json = serde_json::to_value(&submission).unwrap();
+        assert_eq!(
+            json,
+            json!({"output_key": "issue", "values": {"title": "x"}})
+        );
+        assert_eq!(
+            serde_json::from_value::<FormSubmission>(json).unwrap(),
+            submission
+        );
+    }
+}
@@ -0,0 +1,790 @@
+//! # Render Block Options
+//!
+//! Typed `options` for each [`RenderBlockType`]. Options are stored on
+//! [`RenderBlock`] as free-form JSON so specs stay forward compatible with
+//! renderers, but they are parsed into these structs during
+//! `LensOutputSpec::validate()` so unknown keys and wrong types fail at load time.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{RenderBlock, RenderBlockType};
+
+/// Options for `header` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HeaderOptions {
+    /// Secondary line shown under the title.
+    #[serde(default)]
+    pub subtitle: Option<String>,
+}
+
+/// Number formatting for KPI values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KpiFormat {
+    #[default]
+    Number,
+    Percent,
+    Currency,
+    Duration,
+}
+
+/// Options for `kpi_row` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KpiRowOptions {
+    /// How values are formatted.
+    #[serde(default)]
+    pub format: KpiFormat,
+
+    /// ISO 4217 code used with `format: currency`.
+    #[serde(default)]
+    pub currency: Option<String>,
+}
+
+/// Rows per page when a table does not set `page_size`.
+pub const DEFAULT_TABLE_PAGE_SIZE: u32 = 50;
+
+/// How a table cell value is displayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnFormat {
+    #[default]
+    Text,
+    Number,
+    Percent,
+    Currency,
+    Date,
+    Datetime,
+    Duration,
+    Link,
+    Code,
+}
+
+impl ColumnFormat {
+    /// Whether the format only makes sense for numeric fields.
+    pub fn is_numeric(self) -> bool {
+        matches!(self, Self::Number | Self::Percent | Self::Currency)
+    }
+}
+
+/// A declared table column.
+///
+/// Written either as a bare field path (`- name`) or as a table:
+///
+/// ```yaml
+/// columns:
+///   - field: owner.email
+///     header: Owner
+///     width: 240
+///   - { field: cost, format: currency }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "TableColumnDecl")]
+pub struct TableColumn {
+    /// Row field path (dotted or JSON pointer, relative to a row).
+    pub field: String,
+
+    /// Header text. Defaults to the field path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+
+    /// Cell formatting.
+    #[serde(default)]
+    pub format: ColumnFormat,
+
+    /// Column width in pixels. Unset lets the renderer size it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+}
+
+impl TableColumn {
+    /// Header text shown for this column.
+    pub fn header(&self) -> &str {
+        self.header.as_deref().unwrap_or(&self.field)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TableColumnDecl {
+    Field(String),
+    Detailed(TableColumnFields),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TableColumnFields {
+    field: String,
+    #[serde(default)]
+    header: Option<String>,
+    #[serde(default)]
+    format: ColumnFormat,
+    #[serde(default)]
+    width: Option<u32>,
+}
+
+impl From<TableColumnDecl> for TableColumn {
+    fn from(decl: TableColumnDecl) -> Self {
+        match decl {
+            TableColumnDecl::Field(field) => Self {
+                field,
+                header: None,
+                format: ColumnFormat::Text,
+                width: None,
+            },
+            TableColumnDecl::Detailed(column) => Self {
+                field: column.field,
+                header: column.header,
+                format: column.format,
+                width: column.width,
+            },
+        }
+    }
+}
+
+/// Sort direction for a table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Initial sort order for a table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TableSort {
+    /// Row field path to sort by.
+    pub field: String,
+
+    /// Ascending or descending.
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+/// Options for `table` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TableOptions {
+    /// Columns to show, in order. Empty shows every top-level row field.
+    #[serde(default)]
+    pub columns: Vec<TableColumn>,
+
+    /// Whether users can sort by clicking column headers.
+    #[serde(default)]
+    pub sortable: bool,
+
+    /// Sort applied before the user picks one.
+    #[serde(default)]
+    pub default_sort: Option<TableSort>,
+
+    /// Rows per page. Defaults to [`DEFAULT_TABLE_PAGE_SIZE`].
+    #[serde(default)]
+    pub page_size: Option<u32>,
+}
+
+impl TableOptions {
+    /// Rows per page the renderer should use.
+    pub fn effective_page_size(&self) -> u32 {
+        self.page_size.unwrap_or(DEFAULT_TABLE_PAGE_SIZE)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.page_size == Some(0) {
+            return Err("page_size must be greater than zero".to_string());
+        }
+
+        let mut fields = std::collections::HashSet::new();
+        for column in &self.columns {
+            if column.field.trim().is_empty() {
+                return Err("table columns require a non-empty field".to_string());
+            }
+            if !fields.insert(column.field.as_str()) {
+                return Err(format!("duplicate table column '{}'", column.field));
+            }
+            if column.width == Some(0) {
+                return Err(format!(
+                    "column '{}' width must be greater than zero",
+                    column.field
+                ));
+            }
+        }
+
+        if let Some(sort) = &self.default_sort {
+            if !self.columns.is_empty() && !fields.contains(sort.field.as_str()) {
+                return Err(format!(
+                    "default_sort field '{}' is not a declared column",
+                    sort.field
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Options for `card_list` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CardListOptions {
+    /// Item field used as the card title.
+    #[serde(default)]
+    pub title_field: Option<String>,
+
+    /// Item field used as the card subtitle.
+    #[serde(default)]
+    pub subtitle_field: Option<String>,
+
+    /// Item field holding a link opened on click.
+    #[serde(default)]
+    pub link_field: Option<String>,
+}
+
+/// Options for `timeline` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimelineOptions {
+    /// Item field holding the timestamp.
+    #[serde(default)]
+    pub time_field: Option<String>,
+
+    /// Item field holding the entry label.
+    #[serde(default)]
+    pub label_field: Option<String>,
+}
+
+/// Diff presentation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffMode {
+    #[default]
+    Unified,
+    Split,
+}
+
+/// Options for `diff` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiffOptions {
+    /// Syntax highlighting language.
+    #[serde(default)]
+    pub language: Option<String>,
+
+    /// Unified or side-by-side.
+    #[serde(default)]
+    pub mode: DiffMode,
+}
+
+/// Options for `json_view` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JsonViewOptions {
+    /// Nesting depth expanded initially. Unset expands everything.
+    #[serde(default)]
+    pub expand_depth: Option<u32>,
+}
+
+/// Options for `checkpoint_gate` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CheckpointGateOptions {
+    /// Label of the approve button.
+    #[serde(default)]
+    pub approve_label: Option<String>,
+
+    /// Label of the reject button.
+    #[serde(default)]
+    pub reject_label: Option<String>,
+}
+
+/// Severity of a notice.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoticeLevel {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// Options for `notice` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoticeOptions {
+    /// Visual severity.
+    #[serde(default)]
+    pub level: NoticeLevel,
+}
+
+/// Options for `actions` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ActionsOptions {
+    /// Action id highlighted as the primary button.
+    #[serde(default)]
+    pub primary: Option<String>,
+}
+
+/// One data series bound to the payload.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChartSeries {
+    /// Payload path of the array holding the data points.
+    pub source: String,
+
+    /// Item field for the x value (line/bar) or slice label (pie).
+    #[serde(default)]
+    pub x: Option<String>,
+
+    /// Item field holding the numeric value.
+    pub y: String,
+
+    /// Legend label. Defaults to `y`.
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Options for `line_chart`, `bar_chart`, and `pie_chart` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChartOptions {
+    /// Plotted series. Pie charts take exactly one.
+    #[serde(default)]
+    pub series: Vec<ChartSeries>,
+
+    /// X axis title (line/bar only).
+    #[serde(default)]
+    pub x_label: Option<String>,
+
+    /// Y axis title (line/bar only).
+    #[serde(default)]
+    pub y_label: Option<String>,
+
+    /// Stack series instead of overlaying them (line/bar only).
+    #[serde(default)]
+    pub stacked: bool,
+}
+
+impl ChartOptions {
+    fn check(&self, pie: bool) -> Result<(), String> {
+        if self.series.is_empty() {
+            return Err("charts require at least one series".to_string());
+        }
+        for series in &self.series {
+            if series.source.trim().is_empty() || series.y.trim().is_empty() {
+                return Err("chart series require non-empty source and y".to_string());
+            }
+        }
+        if pie {
+            if self.series.len() != 1 {
+                return Err("pie charts take exactly one series".to_string());
+            }
+            if self.x_label.is_some() || self.y_label.is_some() || self.stacked {
+                return Err("pie charts have no axes; remove x_label/y_label/stacked".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How much markup survives sanitization.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SanitizeLevel {
+    /// Formatting only: no raw HTML, images, or links.
+    #[default]
+    Strict,
+    /// Formatting, links, and images; raw HTML stripped.
+    Basic,
+    /// Render as-is. Only for trusted content.
+    None,
+}
+
+/// Options for `markdown` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarkdownOptions {
+    /// Sanitization applied before rendering.
+    #[serde(default)]
+    pub sanitize: SanitizeLevel,
+
+    /// Characters rendered before truncating. Unset renders everything.
+    #[serde(default)]
+    pub max_length: Option<u32>,
+}
+
+/// Where the bytes behind a media `source` field come from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaSourceKind {
+    /// The field holds an http(s) or data URL.
+    #[default]
+    Url,
+    /// The field holds base64-encoded bytes.
+    Base64,
+    /// The field holds the name of an artifact stored for the run.
+    Artifact,
+}
+
+/// Options for `image` and `media` blocks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MediaOptions {
+    /// How to interpret the `source` field.
+    #[serde(default)]
+    pub source_kind: MediaSourceKind,
+
+    /// Accepted mime types, e.g. "image/png" or "video/*". Empty accepts any
+    /// type valid for the block.
+    #[serde(default)]
+    pub mime_types: Vec<String>,
+
+    /// Payload field holding alt text or a caption.
+    #[serde(default)]
+    pub alt_field: Option<String>,
+}
+
+impl MediaOptions {
+    /// Whether a concrete mime type satisfies `mime_types`.
+    pub fn accepts(&self, mime: &str) -> bool {
+        self.mime_types.is_empty()
+            || self
+                .mime_types
+                .iter()
+                .any(|pattern| mime_matches(pattern, mime))
+    }
+
+    fn check(&self, allowed_top_levels: &[&str]) -> Result<(), String> {
+        for pattern in &self.mime_types {
+            let Some((top, sub)) = pattern.split_once('/') else {
+                return Err(format!("invalid mime type '{}'", pattern));
+            };
+            if sub.is_empty() || !allowed_top_levels.contains(&top) {
+                return Err(format!(
+                    "mime type '{}' is not allowed here (expected {})",
+                    pattern,
+                    allowed_top_levels
+                        .iter()
+                        .map(|top| format!("{}/*", top))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn mime_matches(pattern: &str, mime: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(top) => mime
+            .split_once('/')
+            .is_some_and(|(mime_top, _)| mime_top.eq_ignore_ascii_case(top)),
+        None => pattern.eq_ignore_ascii_case(mime),
+    }
+}
+
+/// Parsed options for a render block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockOptions {
+    Header(HeaderOptions),
+    KpiRow(KpiRowOptions),
+    Table(TableOptions),
+    CardList(CardListOptions),
+    Timeline(TimelineOptions),
+    Diff(DiffOptions),
+    JsonView(JsonViewOptions),
+    CheckpointGate(CheckpointGateOptions),
+    Notice(NoticeOptions),
+    Actions(ActionsOptions),
+    LineChart(ChartOptions),
+    BarChart(ChartOptions),
+    PieChart(ChartOptions),
+    Markdown(MarkdownOptions),
+    Image(MediaOptions),
+    Media(MediaOptions),
+}
+
+impl RenderBlock {
+    /// Parse `options` into the typed struct for this block's type.
+    ///
+    /// Missing (`null`) options yield the defaults.
+    pub fn parsed_options(&self) -> Result<BlockOptions, String> {
+        let options = &self.options;
+        Ok(match self.block_type {
+            RenderBlockType::Header => BlockOptions::Header(parse(options)?),
+            RenderBlockType::KpiRow => BlockOptions::KpiRow(parse(options)?),
+            RenderBlockType::Table => {
+                let table: TableOptions = parse(options)?;
+                table.check()?;
+                BlockOptions::Table(table)
+            }
+            RenderBlockType::CardList => BlockOptions::CardList(parse(options)?),
+            RenderBlockType::Timeline => BlockOptions::Timeline(parse(options)?),
+            RenderBlockType::Diff => BlockOptions::Diff(parse(options)?),
+            RenderBlockType::JsonView => BlockOptions::JsonView(parse(options)?),
+            RenderBlockType::CheckpointGate => BlockOptions::CheckpointGate(parse(options)?),
+            RenderBlockType::Notice => BlockOptions::Notice(parse(options)?),
+            RenderBlockType::Actions => BlockOptions::Actions(parse(options)?),
+            RenderBlockType::LineChart => BlockOptions::LineChart(chart(options, false)?),
+            RenderBlockType::BarChart => BlockOptions::BarChart(chart(options, false)?),
+            RenderBlockType::PieChart => BlockOptions::PieChart(chart(options, true)?),
+            RenderBlockType::Image => BlockOptions::Image(self.media(&["image"])?),
+            RenderBlockType::Media => BlockOptions::Media(self.media(&["audio", "video"])?),
+            RenderBlockType::Markdown => {
+                self.require_source()?;
+                let markdown: MarkdownOptions = parse(options)?;
+                if markdown.max_length == Some(0) {
+                    return Err("max_length must be greater than zero".to_string());
+                }
+                BlockOptions::Markdown(markdown)
+            }
+        })
+    }
+}
+
+impl RenderBlock {
+    fn require_source(&self) -> Result<(), String> {
+        if self.source.as_deref().is_none_or(|s| s.trim().is_empty()) {
+            return Err(format!(
+                "{:?} blocks must bind a payload field via source",
+                self.block_type
+            ));
+        }
+        Ok(())
+    }
+
+    fn media(&self, allowed_top_levels: &[&str]) -> Result<MediaOptions, String> {
+        self.require_source()?;
+        let media: MediaOptions = parse(&self.options)?;
+        media.check(allowed_top_levels)?;
+        Ok(media)
+    }
+}
+
+fn chart(options: &Value, pie: bool) -> Result<ChartOptions, String> {
+    let chart: ChartOptions = parse(options)?;
+    chart.check(pie)?;
+    Ok(chart)
+}
+
+fn parse<T: DeserializeOwned + Default>(options: &Value) -> Result<T, String> {
+    if options.is_null() {
+        return Ok(T::default());
+    }
+    serde_json::from_value(options.clone()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn block(block_type: RenderBlockType, options: Value) -> RenderBlock {
+        RenderBlock {
+            block_type,
+            title: None,
+            description: None,
+            title_i18n: Default::default(),
+            description_i18n: Default::default(),
+            source: None,
+            options,
+            visible_if: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_typed_options() {
+        let table = block(
+            RenderBlockType::Table,
+            json!({"columns": ["name", "status"], "sortable": true}),
+        );
+        match table.parsed_options().unwrap() {
+            BlockOptions::Table(options) => {
+                let fields: Vec<&str> = options.columns.iter().map(|c| c.field.as_str()).collect();
+                assert_eq!(fields, vec!["name", "status"]);
+                assert_eq!(options.columns[0].header(), "name");
+                assert!(options.sortable);
+                assert_eq!(options.page_size, None);
+                assert_eq!(options.effective_page_size(), DEFAULT_TABLE_PAGE_SIZE);
+            }
+            other => panic!("unexpected options {:?}", other),
+        }
+
+        let kpi = block(RenderBlockType::KpiRow, json!({"format": "percent"}));
+        assert_eq!(
+            kpi.parsed_options().unwrap(),
+            BlockOptions::KpiRow(KpiRowOptions {
+                format: KpiFormat::Percent,
+                currency: None,
+            })
+        );
+
+        let notice = block(RenderBlockType::Notice, Value::Null);
+        assert_eq!(
+            notice.parsed_options().unwrap(),
+            BlockOptions::Notice(NoticeOptions::default())
+        );
+    }
+
+    #[test]
+    fn test_chart_options() {
+        let series = json!({"source": "daily", "x": "date", "y": "visits"});
+
+        let line = block(
+            RenderBlockType::LineChart,
+            json!({"series": [series, {"source": "daily", "y": "signups"}], "y_label": "Count"}),
+        );
+        match line.parsed_options().unwrap() {
+            BlockOptions::LineChart(options) => {
+                assert_eq!(options.series.len(), 2);
+                assert_eq!(options.series[1].x, None);
+                assert_eq!(options.y_label.as_deref(), Some("Count"));
+            }
+            other => panic!("unexpected options {:?}", other),
+        }
+
+        let empty = block(RenderBlockType::BarChart, Value::Null);
+        assert!(empty
+            .parsed_options()
+            .unwrap_err()
+            .contains("at least one series"));
+
+        let two_pies = block(
+            RenderBlockType::PieChart,
+            json!({"series": [series, series]}),
+        );
+        assert!(two_pies
+            .parsed_options()
+            .unwrap_err()
+            .contains("exactly one"));
+
+        let pie_axes = block(
+            RenderBlockType::PieChart,
+            json!({"series": [series], "x_label": "Day"}),
+        );
+        assert!(pie_axes.parsed_options().unwrap_err().contains("no axes"));
+    }
+
+    #[test]
+    fn test_markdown_options() {
+        let mut markdown = block(RenderBlockType::Markdown, json!({"max_length": 200}));
+        assert!(markdown.parsed_options().unwrap_err().contains("source"));
+
+        markdown.source = Some("summary".to_string());
+        assert_eq!(
+            markdown.parsed_options().unwrap(),
+            BlockOptions::Markdown(MarkdownOptions {
+                sanitize: SanitizeLevel::Strict,
+                max_length: Some(200),
+            })
+        );
+
+        markdown.options = json!({"sanitize": "off"});
+        assert!(markdown.parsed_options().is_err());
+    }
+
+    #[test]
+    fn test_media_options() {
+        let mut image = block(RenderBlockType::Image, json!({"mime_types": ["image/*"]}));
+        assert!(image.parsed_options().unwrap_err().contains("source"));
+
+        image.source = Some("preview".to_string());
+        match image.parsed_options().unwrap() {
+            BlockOptions::Image(options) => {
+                assert_eq!(options.source_kind, MediaSourceKind::Url);
+                assert!(options.accepts("image/jpeg"));
+                assert!(!options.accepts("video/mp4"));
+            }
+            other => panic!("unexpected options {:?}", other),
+        }
+
+        let mut media = block(
+            RenderBlockType::Media,
+            json!({"source_kind": "artifact", "mime_types": ["audio/mpeg", "video/*"]}),
+        );
+        media.source = Some("recording".to_string());
+        assert!(media.parsed_options().is_ok());
+
+        media.options = json!({"mime_types": ["image/png"]});
+        assert!(media.parsed_options().unwrap_err().contains("not allowed"));
+
+        media.options = json!({"mime_types": ["png"]});
+        assert!(media
+            .parsed_options()
+            .unwrap_err()
+            .contains("invalid mime type"));
+    }
+
+    #[test]
+    fn test_reject_invalid_options() {
+        let typo = block(RenderBlockType::Table, json!({"sortabel": true}));
+        assert!(typo.parsed_options().unwrap_err().contains("sortabel"));
+
+        let bad_enum = block(RenderBlockType::KpiRow, json!({"format": "percentage"}));
+        assert!(bad_enum.parsed_options().is_err());
+
+        let zero_page = block(RenderBlockType::Table, json!({"page_size": 0}));
+        assert!(zero_page.parsed_options().is_err());
+    }
+
+    #[test]
+    fn test_table_columns() {
+        let table = block(
+            RenderBlockType::Table,
+            json!({
+                "columns": [
+                    "name",
+                    {"field": "cost", "header": "Cost", "format": "currency", "width": 120}
+                ],
+                "default_sort": {"field": "cost", "direction": "desc"},
+                "page_size": 25
+            }),
+        );
+        let BlockOptions::Table(options) = table.parsed_options().unwrap() else {
+            panic!("expected table options");
+        };
+        assert_eq!(options.columns[1].header(), "Cost");
+        assert_eq!(options.columns[1].format, ColumnFormat::Currency);
+        assert_eq!(options.columns[1].width, Some(120));
+        assert_eq!(
+            options.default_sort,
+            Some(TableSort {
+                field: "cost".to_string(),
+                direction: SortDirection::Desc,
+            })
+        );
+        assert_eq!(options.effective_page_size(), 25);
+
+        for (options, reason) in [
+            (
+                json!({"columns": ["name", "name"]}),
+                "duplicate table column",
+            ),
+            (
+                json!({"columns": [{"field": "name", "width": 0}]}),
+                "width must be greater than zero",
+            ),
+            (
+                json!({"columns": ["name"], "default_sort": {"field": "cost"}}),
+                "not a declared column",
+            ),
+        ] {
+            let err = block(RenderBlockType::Table, options)
+                .parsed_options()
+                .unwrap_err();
+            assert!(err.contains(reason), "{}", err);
+        }
+
+        let typo = block(
+            RenderBlockType::Table,
+            json!({"columns": [{"field": "name", "heading": "Name"}]}),
+        );
+        assert!(typo.parsed_options().is_err());
+    }
+}
@@ -0,0 +1,454 @@
+//! # Lens Packaging
+//!
+//! [`LensPackager`] turns a lens source directory into a `.lens` package
+//! ready for [`LensInstaller::install_from_archive`](crate::install::LensInstaller::install_from_archive)
+//! or registry upload:
+//!
+//! 1. builds the library with `cargo build --release` when the lens has a
+//!    `Cargo.toml` (unless disabled),
+//! 2. loads and validates `lens.toml` and `lens.output.yaml`, treating
+//!    consistency issues as errors,
+//! 3. writes the library's hash into `[security].library_hash` and, with a
+//!    signing key, signs the manifest (`signing` feature),
+//! 4. writes `<id>-<version>.lens` and a `<id>-<version>.json`
+//!    [`PackageMetadata`] file into the output directory.
+//!
+//! Requires the `runtime` feature.
+//!
+//! ```rust,ignore
+//! let archive = LensPackager::new().package("lenses/figma")?;
+//! let metadata = PackageMetadata::load(archive.with_extension("json"))?;
+//! ```
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::discovery::{DiscoveredLens, LensDiscovery, MANIFEST_FILENAME};
+use crate::error::{LensError, Result};
+use crate::install::LENS_ARCHIVE_EXTENSION;
+#[cfg(feature = "signing")]
+use crate::manifest::migrate::CURRENT_MANIFEST_VERSION;
+use crate::manifest::{SandboxLevel, SecurityConfig};
+
+/// Default output directory, relative to the lens directory
+pub const DEFAULT_PACKAGE_DIR: &str = "dist";
+
+/// Registry metadata written next to each package
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PackageMetadata {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Package file name
+    pub archive: String,
+    /// SHA-256 of the package as "sha256:<hex>"
+    pub archive_hash: String,
+    pub archive_size: u64,
+    /// Hash written into `[security].library_hash`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library_hash: Option<String>,
+    /// Publisher key, when the manifest is signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+}
+
+impl PackageMetadata {
+    /// Read metadata written by [`LensPackager::package`]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Builds `.lens` packages from lens source directories
+#[derive(Clone)]
+pub struct LensPackager {
+    out_dir: Option<PathBuf>,
+    build: bool,
+    require_output_spec: bool,
+    #[cfg(feature = "signing")]
+    signing_key: Option<ed25519_dalek::SigningKey>,
+}
+
+impl Default for LensPackager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LensPackager {
+    /// Packager writing to `<lens dir>/dist`, building with cargo when needed
+    pub fn new() -> Self {
+        Self {
+            out_dir: None,
+            build: true,
+            require_output_spec: true,
+            #[cfg(feature = "signing")]
+            signing_key: None,
+        }
+    }
+
+    /// Write packages to `out_dir` instead of `<lens dir>/dist`
+    pub fn with_out_dir<P: AsRef<Path>>(mut self, out_dir: P) -> Self {
+        self.out_dir = Some(out_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Run `cargo build --release` for lenses with a `Cargo.toml` (default: true)
+    pub fn with_build(mut self, build: bool) -> Self {
+        self.build = build;
+        self
+    }
+
+    /// Fail when the lens has no `lens.output.yaml` (default: true)
+    pub fn with_require_output_spec(mut self, require: bool) -> Self {
+        self.require_output_spec = require;
+        self
+    }
+
+    /// Sign the packaged manifest with `key`
+    #[cfg(feature = "signing")]
+    pub fn with_signing_key(mut self, key: ed25519_dalek::SigningKey) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    /// Package the lens in `lens_dir`, returning the path of the `.lens` file.
+    ///
+    /// The metadata file is written next to it with a `.json` extension.
+    pub fn package<P: AsRef<Path>>(&self, lens_dir: P) -> Result<PathBuf> {
+        let lens_dir = lens_dir.as_ref();
+        let cargo = lens_dir.join("Cargo.toml").is_file();
+        if self.build && cargo {
+            cargo_build(lens_dir)?;
+        }
+
+        let lens = LensDiscovery::new(lens_dir.parent().unwrap_or(lens_dir)).load_lens(lens_dir)?;
+        self.validate(&lens)?;
+        let binary = lens.library_path.clone().or(lens.entry_path.clone());
+        let library_hash = lens.compute_library_hash()?;
+
+        let mut manifest = lens.manifest.clone();
+        let declared = manifest
+            .security
+            .as_ref()
+            .and_then(|security| security.library_hash.clone());
+        if declared != library_hash {
+            // A signature over another library no longer holds
+            manifest.signature = None;
+            manifest
+                .security
+                .get_or_insert_with(|| SecurityConfig {
+                    library_hash: None,
+                    permissions: Vec::new(),
+                    sandbox: SandboxLevel::default(),
+                })
+                .library_hash = library_hash.clone();
+        }
+        #[cfg(feature = "signing")]
+        if let Some(key) = &self.signing_key {
+            // Migration leaves valid v1 manifests alone, and they already fit
+            // the current layout that signing requires
+            manifest.lens.manifest_version = CURRENT_MANIFEST_VERSION;
+            manifest.sign(key)?;
+        }
+        let manifest_toml = manifest
+            .to_toml()
+            .map_err(|e| LensError::Other(format!("Failed to serialize manifest: {}", e)))?;
+
+        let out_dir = self
+            .out_dir
+            .clone()
+            .unwrap_or_else(|| lens_dir.join(DEFAULT_PACKAGE_DIR));
+        std::fs::create_dir_all(&out_dir)?;
+        let stem = format!("{}-{}", lens.id(), lens.version());
+        let archive = out_dir.join(format!("{}.{}", stem, LENS_ARCHIVE_EXTENSION));
+
+        let mut files = Vec::new();
+        collect_files(lens_dir, lens_dir, &out_dir, cargo, &mut files)?;
+        files.retain(|(name, _)| name != Path::new(MANIFEST_FILENAME));
+        // Libraries built into target/ ship at the package root
+        if let Some(binary) = binary.filter(|b| b.starts_with(lens_dir.join("target"))) {
+            if let Some(file_name) = binary.file_name() {
+                files.retain(|(name, _)| name != Path::new(file_name));
+                files.push((PathBuf::from(file_name), binary));
+            }
+        }
+        files.sort();
+        write_archive(&archive, &files, &manifest_toml)?;
+
+        let (archive_hash, archive_size) = file_hash(&archive)?;
+        let metadata = PackageMetadata {
+            id: lens.id().to_string(),
+            name: lens.name().to_string(),
+            version: lens.version().to_string(),
+            description: Some(manifest.lens.description.clone()).filter(|d| !d.is_empty()),
+            archive: archive
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            archive_hash,
+            archive_size,
+            library_hash,
+            public_key: manifest.signature.as_ref().map(|s| s.public_key.clone()),
+        };
+        std::fs::write(
+            archive.with_extension("json"),
+            serde_json::to_string_pretty(&metadata)?,
+        )?;
+
+        Ok(archive)
+    }
+
+    fn validate(&self, lens: &DiscoveredLens) -> Result<()> {
+        if self.require_output_spec && lens.output_spec.is_none() {
+            return Err(LensError::InvalidInput(format!(
+                "Lens '{}' has no lens.output.yaml",
+                lens.id()
+            )));
+        }
+        if let Some(issue) = lens.validate_consistency().first() {
+            return Err(LensError::InvalidInput(format!(
+                "Lens '{}': {}",
+                lens.id(),
+                issue
+            )));
+        }
+        if let Some(limits) = &lens.manifest.limits {
+            limits.validate()?;
+        }
+        if let Some(cache) = &lens.manifest.cache {
+            cache.validate()?;
+        }
+        lens.manifest.validate_examples()
+    }
+}
+
+fn cargo_build(lens_dir: &Path) -> Result<()> {
+    let output = Command::new("cargo")
+        .args(["build", "--release"])
+        .current_dir(lens_dir)
+        .output()
+        .map_err(|e| LensError::Initialization(format!("Failed to run cargo: {}", e)))?;
+    if !output.status.success() {
+        return Err(LensError::ExecutionFailed(format!(
+            "cargo build --release failed in {:?}: {}",
+            lens_dir,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Package path and source of each file under `dir`. Skips hidden entries,
+/// `target/`, the output directory, and cargo sources for cargo lenses.
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    out_dir: &Path,
+    cargo: bool,
+    files: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+        let name = relative.to_string_lossy();
+        let skipped = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+            || name == "target"
+            || path == out_dir
+            || (cargo && ["src", "Cargo.toml", "Cargo.lock", "build.rs"].contains(&name.as_ref()));
+        if skipped {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(root, &path, out_dir, cargo, files)?;
+        } else if path.is_file() {
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+/// Write a deterministic gzipped tar of `files` plus `manifest` as the manifest
+fn write_archive(archive: &Path, files: &[(PathBuf, PathBuf)], manifest: &str) -> Result<()> {
+    let mut builder = tar::Builder::new(GzEncoder::new(
+        File::create(archive)?,
+        Compression::default(),
+    ));
+    builder.mode(tar::HeaderMode::Deterministic);
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST_FILENAME, manifest.as_bytes())?;
+
+    for (name, source) in files {
+        builder.append_path_with_name(source, name)?;
+    }
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn file_hash(path: &Path) -> Result<(String, u64)> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 16 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok((format!("sha256:{:x}", hasher.finalize()), size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::install::LensInstaller;
+    use std::fs;
+    use tempfile::tempdir;
+
+    const OUTPUT_SPEC: &str = r#"
+lens_id: figma
+outputs:
+  - key: frame
+    title: Frame
+    render_blocks:
+      - type: json_view
+    examples:
+      - {}
+"#;
+
+    fn write_lens(dir: &Path) {
+        fs::create_dir_all(dir.join("components")).unwrap();
+        fs::write(
+            dir.join("lens.toml"),
+            "[lens]\nid = \"figma\"\nname = \"Figma\"\nversion = \"1.2.0\"\ndescription = \"Figma frames\"\n\n[[message_types]]\nkey = \"frame\"\ncomponent = \"components/Frame.tsx\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("lens.output.yaml"), OUTPUT_SPEC).unwrap();
+        fs::write(dir.join("components/Frame.tsx"), "export {}").unwrap();
+        fs::write(dir.join(".disabled"), "").unwrap();
+        let release = dir.join("target/release");
+        fs::create_dir_all(&release).unwrap();
+        fs::write(
+            release.join(format!("libfigma.{}", std::env::consts::DLL_EXTENSION)),
+            "compiled",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_package_round_trips_through_installer() {
+        let temp_dir = tempdir().unwrap();
+        let lens_dir = temp_dir.path().join("figma");
+        write_lens(&lens_dir);
+
+        let archive = LensPackager::new()
+            .with_build(false)
+            .package(&lens_dir)
+            .unwrap();
+        assert_eq!(archive, lens_dir.join("dist/figma-1.2.0.lens"));
+        let metadata = PackageMetadata::load(archive.with_extension("json")).unwrap();
+        assert_eq!(metadata.archive, "figma-1.2.0.lens");
+        assert_eq!(metadata.description.as_deref(), Some("Figma frames"));
+        assert_eq!(metadata.archive_hash, file_hash(&archive).unwrap().0);
+        let library_hash = metadata.library_hash.clone().unwrap();
+
+        // Packaging is deterministic
+        LensPackager::new()
+            .with_build(false)
+            .package(&lens_dir)
+            .unwrap();
+        assert_eq!(
+            PackageMetadata::load(archive.with_extension("json")).unwrap(),
+            metadata
+        );
+
+        let installer = LensInstaller::new(LensDiscovery::new(temp_dir.path().join("lenses")));
+        let lens = installer.install_from_archive(&archive).unwrap();
+        let security = lens.manifest.security.as_ref().unwrap();
+        assert_eq!(
+            security.library_hash.as_deref(),
+            Some(library_hash.as_str())
+        );
+        assert_eq!(lens.compute_library_hash().unwrap(), Some(library_hash));
+        assert!(lens.path.join("components/Frame.tsx").is_file());
+        assert!(lens.output_spec.is_some());
+        assert!(lens.is_enabled());
+        assert!(!lens.path.join("target").exists());
+    }
+
+    #[test]
+    fn test_package_rejects_inconsistent_lens() {
+        let temp_dir = tempdir().unwrap();
+        let lens_dir = temp_dir.path().join("figma");
+        write_lens(&lens_dir);
+        fs::write(
+            lens_dir.join("lens.output.yaml"),
+            OUTPUT_SPEC.replace("frame", "other"),
+        )
+        .unwrap();
+
+        let err = LensPackager::new()
+            .with_build(false)
+            .package(&lens_dir)
+            .unwrap_err();
+        assert!(err.to_string().contains("figma"));
+        assert!(!lens_dir.join("dist").exists());
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn test_package_signs_manifest() {
+        let temp_dir = tempdir().unwrap();
+        let lens_dir = temp_dir.path().join("figma");
+        write_lens(&lens_dir);
+        let key = ed25519_dalek::SigningKey::from_bytes(&[3; 32]);
+
+        let archive = LensPackager::new()
+            .with_build(false)
+            .with_signing_key(key)
+            .package(&lens_dir)
+            .unwrap();
+        let metadata = PackageMetadata::load(archive.with_extension("json")).unwrap();
+        let public_key = metadata.public_key.unwrap();
+
+        let pins = crate::security::KeyPins::from(vec![public_key.clone()])
+            .pin("ff".repeat(32), vec!["figma".to_string()]);
+        let err = LensInstaller::new(LensDiscovery::new(temp_dir.path().join("lenses")))
+            .with_key_pins(pins)
+            .install_from_archive(&archive)
+            .unwrap_err();
+        assert!(err.to_string().contains("untrusted key"));
+
+        // An empty key list trusts nobody, not whoever signed the package
+        let err = LensInstaller::new(LensDiscovery::new(temp_dir.path().join("lenses")))
+            .with_trusted_keys(Vec::new())
+            .install_from_archive(&archive)
+            .unwrap_err();
+        assert!(err.to_string().contains("No trusted key"));
+
+        let lens = LensInstaller::new(LensDiscovery::new(temp_dir.path().join("lenses")))
+            .with_trusted_keys(vec![public_key])
+            .install_from_archive(&archive)
+            .unwrap();
+        assert!(lens.manifest.is_signed());
+    }
+}
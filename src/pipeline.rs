@@ -0,0 +1,603 @@
+//! # Lens Pipelines
+//!
+//! [`LensPipeline`] chains lenses so that each step's output feeds the next
+//! step's input. Steps run one after another on a [`LensExecutor`], so the
+//! executor's limits, retries, and cache apply to every step:
+//!
+//! ```rust,ignore
+//! let pipeline = LensPipeline::new("figma-to-pr")
+//!     .step("extract", registry.get("figma")?)
+//!     .step("codegen", registry.get("codegen")?)
+//!     .map("$.components", "$.components")
+//!     .map("$input.framework", "$.framework")
+//!     .step("open-pr", registry.get("github")?)
+//!     .map("$.files", "$.files")
+//!     .map("$steps.extract.title", "$.title");
+//!
+//! let mut handle = pipeline.run(&executor, ctx);
+//! let mut events = handle.take_events().unwrap();
+//! let result = handle.await?;
+//! ```
+//!
+//! A step without mappings receives the previous step's output as is (the
+//! first step receives the pipeline's input). A step with mappings receives
+//! an object built from them: each mapping copies the value at its source
+//! path into the step's input at its target path. Source paths read from
+//! the previous step's output (`$.a.b[0]`), the pipeline's input
+//! (`$input.a`), or an earlier step's output (`$steps.<name>.a`); target
+//! paths always start at `$`.
+//!
+//! The handle's event stream merges every step's events. After each step
+//! completes, the pipeline emits a `Checkpoint` event whose phase is the
+//! step's name and whose data is the step's output. The same outputs are
+//! returned as [`StepCheckpoint`]s in the [`PipelineResult`], whether or not
+//! the run got to the end, and can be passed to
+//! [`resume`](LensPipeline::resume) to re-run a failed pipeline without
+//! repeating the steps that completed.
+
+mod path;
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::StreamExt;
+
+use self::path::{JsonPath, Root};
+use crate::error::{LensError, Result};
+use crate::executor::LensExecutor;
+use crate::streaming::LensEventStream;
+use crate::{Lens, LensContext, LensEvent, LensResult};
+
+/// A sequence of lenses, each fed from the outputs before it
+#[derive(Clone)]
+pub struct LensPipeline {
+    id: String,
+    steps: Vec<Step>,
+    /// Mappings declared before any step, reported by `validate`
+    orphan_mappings: usize,
+}
+
+#[derive(Clone)]
+struct Step {
+    name: String,
+    lens: Arc<dyn Lens>,
+    mappings: Vec<Mapping>,
+}
+
+#[derive(Debug, Clone)]
+struct Mapping {
+    from: String,
+    to: String,
+}
+
+impl std::fmt::Debug for LensPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LensPipeline")
+            .field("id", &self.id)
+            .field("steps", &self.step_names())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Output of a completed step, as recorded by a pipeline run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepCheckpoint {
+    /// Name of the step
+    pub step: String,
+    /// The step's `LensResult.output`
+    pub output: Value,
+}
+
+/// Outcome of a pipeline run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineResult {
+    /// Result of the last step run: the final step on success, the failing
+    /// step otherwise
+    pub result: LensResult,
+    /// Outputs of the steps that completed, in order
+    pub checkpoints: Vec<StepCheckpoint>,
+    /// Name of the step that failed, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_step: Option<String>,
+}
+
+impl PipelineResult {
+    /// Whether every step completed successfully
+    pub fn is_success(&self) -> bool {
+        self.failed_step.is_none() && self.result.success
+    }
+}
+
+impl LensPipeline {
+    /// Create an empty pipeline; `id` tags the pipeline's own events
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            steps: Vec::new(),
+            orphan_mappings: 0,
+        }
+    }
+
+    /// Append a step running `lens` (builder pattern)
+    pub fn step(mut self, name: impl Into<String>, lens: Arc<dyn Lens>) -> Self {
+        self.steps.push(Step {
+            name: name.into(),
+            lens,
+            mappings: Vec::new(),
+        });
+        self
+    }
+
+    /// Copy the value at `from` into the last step's input at `to`
+    /// (builder pattern)
+    pub fn map(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        match self.steps.last_mut() {
+            Some(step) => step.mappings.push(Mapping {
+                from: from.into(),
+                to: to.into(),
+            }),
+            None => self.orphan_mappings += 1,
+        }
+        self
+    }
+
+    /// Id of the pipeline
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Names of the steps, in order
+    pub fn step_names(&self) -> Vec<&str> {
+        self.steps.iter().map(|step| step.name.as_str()).collect()
+    }
+
+    /// Check step names and mapping paths.
+    ///
+    /// Steps need unique names, and mappings need valid paths whose
+    /// `$steps.<name>` sources name an earlier step. Runs validate first.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| {
+            Err(LensError::InvalidInput(format!(
+                "Pipeline '{}': {}",
+                self.id, message
+            )))
+        };
+        if self.steps.is_empty() {
+            return invalid("has no steps".to_string());
+        }
+        if self.orphan_mappings > 0 {
+            return invalid("mappings must follow the step they feed".to_string());
+        }
+        let mut earlier = HashSet::new();
+        for step in &self.steps {
+            for mapping in &step.mappings {
+                let from = JsonPath::parse(&mapping.from)?;
+                if let Root::Step(name) = &from.root {
+                    if !earlier.contains(name.as_str()) {
+                        return invalid(format!(
+                            "step '{}' maps from '{}', which is not an earlier step",
+                            step.name, name
+                        ));
+                    }
+                }
+                if JsonPath::parse(&mapping.to)?.root != Root::Current {
+                    return invalid(format!(
+                        "step '{}' maps to '{}'; targets must start at '$'",
+                        step.name, mapping.to
+                    ));
+                }
+            }
+            if !earlier.insert(step.name.as_str()) {
+                return invalid(format!("duplicate step name '{}'", step.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run every step on `executor`, starting from `ctx.input`
+    pub fn run(&self, executor: &LensExecutor, ctx: LensContext) -> PipelineHandle {
+        self.resume(executor, ctx, Vec::new())
+    }
+
+    /// Run the steps after those recorded in `checkpoints`.
+    ///
+    /// `checkpoints` come from an earlier run's [`PipelineResult`] and must
+    /// match the first steps in order; their outputs stand in for running
+    /// those steps again.
+    pub fn resume(
+        &self,
+        executor: &LensExecutor,
+        ctx: LensContext,
+        checkpoints: Vec<StepCheckpoint>,
+    ) -> PipelineHandle {
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        let (result_tx, result_rx) = oneshot::channel();
+        let handle = PipelineHandle::new(&self.id, events_rx, result_rx);
+
+        if let Err(e) = self
+            .validate()
+            .and_then(|()| self.check_resume(&checkpoints))
+        {
+            let _ = result_tx.send(Err(e));
+            return handle;
+        }
+        let pipeline = self.clone();
+        let executor = executor.clone();
+        tokio::spawn(async move {
+            let result = pipeline
+                .drive(&executor, ctx, checkpoints, &events_tx)
+                .await;
+            let _ = result_tx.send(Ok(result));
+        });
+        handle
+    }
+
+    fn check_resume(&self, checkpoints: &[StepCheckpoint]) -> Result<()> {
+        if checkpoints.len() > self.steps.len() {
+            return Err(LensError::InvalidInput(format!(
+                "Pipeline '{}' has {} steps but {} checkpoints",
+                self.id,
+                self.steps.len(),
+                checkpoints.len()
+            )));
+        }
+        for (step, checkpoint) in self.steps.iter().zip(checkpoints) {
+            if step.name != checkpoint.step {
+                return Err(LensError::InvalidInput(format!(
+                    "Pipeline '{}' cannot resume: expected a checkpoint for step '{}', got '{}'",
+                    self.id, step.name, checkpoint.step
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    async fn drive(
+        &self,
+        executor: &LensExecutor,
+        ctx: LensContext,
+        mut checkpoints: Vec<StepCheckpoint>,
+        events: &mpsc::UnboundedSender<LensEvent>,
+    ) -> PipelineResult {
+        let mut result = LensResult::success(
+            checkpoints
+                .last()
+                .map_or_else(|| ctx.input.clone(), |last| last.output.clone()),
+        );
+        for step in &self.steps[checkpoints.len()..] {
+            let fail = |result: LensResult, checkpoints: Vec<StepCheckpoint>| PipelineResult {
+                result,
+                checkpoints,
+                failed_step: Some(step.name.clone()),
+            };
+
+            let input = match self.step_input(step, &ctx.input, &checkpoints) {
+                Ok(input) => input,
+                Err(e) => {
+                    let message = format!("Step '{}': {}", step.name, e);
+                    let _ = events.send(LensEvent::failed(&self.id, &message, false));
+                    return fail(LensResult::failure(message), checkpoints);
+                }
+            };
+            let mut step_ctx = ctx.clone();
+            step_ctx.input = input;
+
+            let mut handle = executor.submit(Arc::clone(&step.lens), step_ctx);
+            if let Some(mut stream) = handle.take_events() {
+                while let Some(event) = stream.next().await {
+                    let _ = events.send(event);
+                }
+            }
+            result = match handle.await {
+                Ok(result) if result.success => result,
+                Ok(result) => return fail(result, checkpoints),
+                Err(e) => return fail(LensResult::failure(e.to_string()), checkpoints),
+            };
+
+            let _ = events.send(LensEvent::checkpoint(
+                &self.id,
+                &step.name,
+                result.output.clone(),
+                format!("Step '{}' completed", step.name),
+            ));
+            checkpoints.push(StepCheckpoint {
+                step: step.name.clone(),
+                output: result.output.clone(),
+            });
+        }
+        PipelineResult {
+            result,
+            checkpoints,
+            failed_step: None,
+        }
+    }
+
+    /// Input for `step`, given the pipeline input and the outputs so far
+    fn step_input(
+        &self,
+        step: &Step,
+        input: &Value,
+        checkpoints: &[StepCheckpoint],
+    ) -> Result<Value> {
+        let previous = checkpoints.last().map_or(input, |last| &last.output);
+        if step.mappings.is_empty() {
+            return Ok(previous.clone());
+        }
+        let mut mapped = Value::Object(Default::default());
+        for mapping in &step.mappings {
+            let from = JsonPath::parse(&mapping.from)?;
+            let source = match &from.root {
+                Root::Current => previous,
+                Root::Input => input,
+                Root::Step(name) => checkpoints
+                    .iter()
+                    .find(|checkpoint| &checkpoint.step == name)
+                    .map(|checkpoint| &checkpoint.output)
+                    .ok_or_else(|| {
+                        LensError::InvalidInput(format!("no output from step '{}'", name))
+                    })?,
+            };
+            let value = from.get(source).ok_or_else(|| {
+                LensError::InvalidInput(format!("'{}' matched nothing", mapping.from))
+            })?;
+            JsonPath::parse(&mapping.to)?.set(&mut mapped, value.clone())?;
+        }
+        Ok(mapped)
+    }
+}
+
+/// A pipeline run: awaits to the run's result and carries the merged
+/// events of its steps
+pub struct PipelineHandle {
+    pipeline_id: String,
+    result: oneshot::Receiver<Result<PipelineResult>>,
+    events: Option<LensEventStream>,
+}
+
+impl std::fmt::Debug for PipelineHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PipelineHandle")
+            .field("pipeline_id", &self.pipeline_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PipelineHandle {
+    fn new(
+        pipeline_id: &str,
+        events: mpsc::UnboundedReceiver<LensEvent>,
+        result: oneshot::Receiver<Result<PipelineResult>>,
+    ) -> Self {
+        Self {
+            pipeline_id: pipeline_id.to_string(),
+            result,
+            events: Some(Box::pin(UnboundedReceiverStream::new(events))),
+        }
+    }
+
+    /// Id of the pipeline being run
+    pub fn pipeline_id(&self) -> &str {
+        &self.pipeline_id
+    }
+
+    /// Events of every step plus the pipeline's checkpoints; `None` once
+    /// taken
+    pub fn take_events(&mut self) -> Option<LensEventStream> {
+        self.events.take()
+    }
+}
+
+impl Future for PipelineHandle {
+    type Output = Result<PipelineResult>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let pipeline_id = self.pipeline_id.clone();
+        Pin::new(&mut self.result).poll(cx).map(|outcome| {
+            outcome.unwrap_or_else(|_| {
+                Err(LensError::ExecutionFailed(format!(
+                    "Pipeline '{}' ended without a result",
+                    pipeline_id
+                )))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Returns `{ "from": id, "input": input }`, or fails when the input
+    /// has `fail: true`
+    struct EchoLens {
+        id: &'static str,
+        runs: AtomicUsize,
+    }
+
+    impl EchoLens {
+        fn new(id: &'static str) -> Arc<Self> {
+            Arc::new(Self {
+                id,
+                runs: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Lens for EchoLens {
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        fn name(&self) -> &str {
+            "Echo"
+        }
+
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+
+        async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            if ctx.input["fail"] == true {
+                return Err(LensError::ExecutionFailed("asked to fail".to_string()));
+            }
+            Ok(LensResult::success(
+                json!({ "from": self.id, "input": ctx.input }),
+            ))
+        }
+    }
+
+    fn ctx(input: Value) -> LensContext {
+        LensContext::new(PathBuf::from("/tmp"), input)
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_maps_outputs_into_inputs() {
+        let pipeline = LensPipeline::new("chain")
+            .step("first", EchoLens::new("first"))
+            .step("second", EchoLens::new("second"))
+            .map("$.input.name", "$.title")
+            .map("$input.tags[1]", "$.meta.tag")
+            .step("third", EchoLens::new("third"))
+            .map("$steps.first.from", "$.origin")
+            .map("$.input", "$.previous");
+
+        let mut handle = pipeline.run(
+            &LensExecutor::new(2),
+            ctx(json!({ "name": "Button", "tags": ["ui", "core"] })),
+        );
+        let events: Vec<_> = handle.take_events().unwrap().collect().await;
+        let result = handle.await.unwrap();
+
+        assert!(result.is_success());
+        assert_eq!(
+            result.checkpoints[1].output["input"],
+            json!({ "title": "Button", "meta": { "tag": "core" } })
+        );
+        assert_eq!(
+            result.result.output["input"],
+            json!({
+                "origin": "first",
+                "previous": { "title": "Button", "meta": { "tag": "core" } }
+            })
+        );
+
+        let checkpoints: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                LensEvent::Checkpoint { lens, phase, .. } => {
+                    assert_eq!(lens, "chain");
+                    Some(phase.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(checkpoints, ["first", "second", "third"]);
+        let started: Vec<_> = events
+            .iter()
+            .filter(|event| event.event_type() == "Started")
+            .map(|event| event.lens())
+            .collect();
+        assert_eq!(started, ["first", "second", "third"]);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_stops_at_failed_step_and_resumes() {
+        let first = EchoLens::new("first");
+        let second = EchoLens::new("second");
+        let pipeline = LensPipeline::new("chain")
+            .step("first", first.clone())
+            .step("second", second.clone())
+            .map("$.input.flag", "$.fail");
+        let executor = LensExecutor::new(2);
+
+        let result = pipeline
+            .run(&executor, ctx(json!({ "flag": true })))
+            .await
+            .unwrap();
+        assert!(!result.is_success());
+        assert_eq!(result.failed_step.as_deref(), Some("second"));
+        assert_eq!(result.checkpoints.len(), 1);
+
+        // Resume with the first step's output patched to stop the failure
+        let mut checkpoints = result.checkpoints;
+        checkpoints[0].output["input"]["flag"] = json!(false);
+        let result = pipeline
+            .resume(&executor, ctx(json!({ "flag": true })), checkpoints)
+            .await
+            .unwrap();
+        assert!(result.is_success());
+        assert_eq!(result.checkpoints.len(), 2);
+        assert_eq!(first.runs.load(Ordering::SeqCst), 1);
+        assert_eq!(second.runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_reports_missing_mapping_source() {
+        let pipeline = LensPipeline::new("chain")
+            .step("first", EchoLens::new("first"))
+            .step("second", EchoLens::new("second"))
+            .map("$.nothing", "$.value");
+
+        let mut handle = pipeline.run(&LensExecutor::new(1), ctx(json!({})));
+        let events: Vec<_> = handle.take_events().unwrap().collect().await;
+        let result = handle.await.unwrap();
+        assert_eq!(result.failed_step.as_deref(), Some("second"));
+        assert!(result.result.message.unwrap().contains("'$.nothing'"));
+        assert!(matches!(
+            events.last(),
+            Some(LensEvent::Failed { lens, .. }) if lens == "chain"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_validation() {
+        let lens = EchoLens::new("echo");
+        let invalid = [
+            LensPipeline::new("empty"),
+            LensPipeline::new("orphan")
+                .map("$.a", "$.b")
+                .step("a", lens.clone()),
+            LensPipeline::new("dupe")
+                .step("a", lens.clone())
+                .step("a", lens.clone()),
+            LensPipeline::new("later")
+                .step("a", lens.clone())
+                .map("$steps.b.x", "$.x")
+                .step("b", lens.clone()),
+            LensPipeline::new("target")
+                .step("a", lens.clone())
+                .map("$.x", "$input.x"),
+            LensPipeline::new("path")
+                .step("a", lens.clone())
+                .map("x", "$.x"),
+        ];
+        for pipeline in &invalid {
+            assert!(pipeline.validate().is_err(), "{}", pipeline.id());
+        }
+
+        let pipeline = LensPipeline::new("ok").step("a", lens.clone());
+        assert!(pipeline.validate().is_ok());
+        let wrong = vec![StepCheckpoint {
+            step: "b".to_string(),
+            output: json!({}),
+        }];
+        let err = pipeline
+            .resume(&LensExecutor::new(1), ctx(json!({})), wrong)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LensError::InvalidInput(_)));
+    }
+}
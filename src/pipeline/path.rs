@@ -0,0 +1,183 @@
+//! JSON paths used by pipeline mappings
+
+use serde_json::{Map, Value};
+
+use crate::error::{LensError, Result};
+
+/// Where a path starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Root {
+    /// `$`: the previous step's output (or the input of the mapped step)
+    Current,
+    /// `$input`: the pipeline's input
+    Input,
+    /// `$steps.<name>`: the output of an earlier step
+    Step(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// A parsed path such as `$.files[0].name` or `$steps.extract.tokens`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct JsonPath {
+    pub(super) root: Root,
+    pub(super) segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    pub(super) fn parse(path: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            LensError::InvalidInput(format!("Invalid JSON path '{}': {}", path, reason))
+        };
+        let rest = path
+            .strip_prefix('$')
+            .ok_or_else(|| invalid("must start with '$'"))?;
+        let (root, rest) = if let Some(rest) = rest.strip_prefix("input") {
+            (Root::Input, rest)
+        } else if let Some(rest) = rest.strip_prefix("steps.") {
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(invalid("missing step name after '$steps.'"));
+            }
+            (Root::Step(rest[..end].to_string()), &rest[end..])
+        } else {
+            (Root::Current, rest)
+        };
+
+        let mut segments = Vec::new();
+        let mut rest = rest;
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end == 0 {
+                    return Err(invalid("empty key"));
+                }
+                segments.push(Segment::Key(after[..end].to_string()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let (index, after) = after
+                    .split_once(']')
+                    .ok_or_else(|| invalid("unclosed '['"))?;
+                let index = index
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid("array index must be a number"))?;
+                segments.push(Segment::Index(index));
+                rest = after;
+            } else {
+                return Err(invalid("expected '.' or '['"));
+            }
+        }
+        Ok(Self { root, segments })
+    }
+
+    /// The value at this path below `value`, ignoring the root
+    pub(super) fn get<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |current, segment| match segment {
+                Segment::Key(key) => current.get(key),
+                Segment::Index(index) => current.get(index),
+            })
+    }
+
+    /// Put `new` at this path below `target`, creating objects on the way.
+    ///
+    /// Arrays can be indexed within their length or appended to.
+    pub(super) fn set(&self, target: &mut Value, new: Value) -> Result<()> {
+        let mut current = target;
+        for segment in &self.segments {
+            current = match segment {
+                Segment::Key(key) => {
+                    if !current.is_object() {
+                        *current = Value::Object(Map::new());
+                    }
+                    current
+                        .as_object_mut()
+                        .expect("just made an object")
+                        .entry(key.clone())
+                        .or_insert(Value::Null)
+                }
+                Segment::Index(index) => {
+                    if current.is_null() {
+                        *current = Value::Array(Vec::new());
+                    }
+                    let array = current.as_array_mut().ok_or_else(|| {
+                        LensError::InvalidInput(format!(
+                            "Cannot index a non-array with [{}]",
+                            index
+                        ))
+                    })?;
+                    let len = array.len();
+                    if *index == len {
+                        array.push(Value::Null);
+                    }
+                    array.get_mut(*index).ok_or_else(|| {
+                        LensError::InvalidInput(format!(
+                            "Index [{}] is past the end of an array of {}",
+                            index, len
+                        ))
+                    })?
+                }
+            };
+        }
+        *current = new;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_paths() {
+        let path = JsonPath::parse("$.files[0].name").unwrap();
+        assert_eq!(path.root, Root::Current);
+        assert_eq!(
+            path.segments,
+            [
+                Segment::Key("files".into()),
+                Segment::Index(0),
+                Segment::Key("name".into())
+            ]
+        );
+        assert_eq!(JsonPath::parse("$input").unwrap().root, Root::Input);
+        assert_eq!(
+            JsonPath::parse("$steps.extract.tokens").unwrap().root,
+            Root::Step("extract".into())
+        );
+        for invalid in ["files", "$.", "$[x]", "$[0", "$steps.", "$foo"] {
+            assert!(JsonPath::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_get_and_set() {
+        let value = json!({ "files": [{ "name": "a.rs" }] });
+        let name = JsonPath::parse("$.files[0].name").unwrap();
+        assert_eq!(name.get(&value), Some(&json!("a.rs")));
+        assert_eq!(JsonPath::parse("$.missing").unwrap().get(&value), None);
+        assert_eq!(JsonPath::parse("$").unwrap().get(&value), Some(&value));
+
+        let mut target = Value::Null;
+        name.set(&mut target, json!("b.rs")).unwrap();
+        JsonPath::parse("$.title")
+            .unwrap()
+            .set(&mut target, json!("PR"))
+            .unwrap();
+        assert_eq!(
+            target,
+            json!({ "files": [{ "name": "b.rs" }], "title": "PR" })
+        );
+        assert!(JsonPath::parse("$.files[5]")
+            .unwrap()
+            .set(&mut target, json!(1))
+            .is_err());
+    }
+}
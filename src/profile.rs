@@ -0,0 +1,382 @@
+//! # Execution Profiles
+//!
+//! Describes how a host runs a lens: who initiated the run, whether a user is
+//! present to answer interactive outputs, how checkpoints are resolved, and how
+//! long the run may take.
+//!
+//! Agent-initiated runs (e.g. an MCP tool call) use [`ExecutionProfile::agent`],
+//! which disables interactive outputs so the run never blocks waiting for UI.
+//! [`ExecutionProfile::execute_streaming`] resolves the run's checkpoints by
+//! the profile's [`CheckpointPolicy`], and a run outliving the profile's
+//! timeout has its `ctx.cancellation` cancelled.
+//!
+//! ```rust,ignore
+//! let profile = ExecutionProfile::agent(Some("claude".to_string()));
+//! let result = profile.execute(&lens, ctx).await?;
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::cancel::CancellationToken;
+pub use crate::context::Initiator;
+use crate::error::{LensError, Result};
+use crate::streaming::StreamingLens;
+use crate::{Lens, LensContext, LensEvent, LensEventStream, LensResult};
+
+/// Default wall-clock budget for agent-initiated runs.
+pub const AGENT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How a host resolves `Checkpoint` events during a run.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckpointPolicy {
+    /// Pause and wait for the user to review (default for user runs)
+    #[default]
+    WaitForUser,
+    /// Continue immediately as if the user approved
+    AutoApprove,
+    /// Stop the run as if the user rejected
+    AutoReject,
+}
+
+impl CheckpointPolicy {
+    /// Whether checkpoints under this policy block on user input
+    pub fn requires_user(&self) -> bool {
+        matches!(self, Self::WaitForUser)
+    }
+}
+
+/// Execution profile applied by the host before running a lens.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExecutionProfile {
+    /// Who started the run
+    #[serde(default)]
+    pub initiator: Initiator,
+
+    /// Whether interactive outputs (checkpoints, forms) may be shown
+    #[serde(default = "default_true")]
+    pub interactive: bool,
+
+    /// How checkpoints are resolved
+    #[serde(default)]
+    pub checkpoint_policy: CheckpointPolicy,
+
+    /// Whether the lens may raise user notifications
+    #[serde(default = "default_true")]
+    pub notifications: bool,
+
+    /// Wall-clock budget for the run (None = unlimited)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Duration>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ExecutionProfile {
+    fn default() -> Self {
+        Self::user()
+    }
+}
+
+impl ExecutionProfile {
+    /// Profile for user-initiated runs: interactive, no timeout
+    pub fn user() -> Self {
+        Self {
+            initiator: Initiator::User,
+            interactive: true,
+            checkpoint_policy: CheckpointPolicy::WaitForUser,
+            notifications: true,
+            timeout: None,
+        }
+    }
+
+    /// Headless profile for agent-initiated runs.
+    ///
+    /// Interactive outputs are disabled, checkpoints auto-approve, notifications
+    /// are suppressed, and the run is bounded by [`AGENT_TIMEOUT`].
+    pub fn agent(agent_id: Option<String>) -> Self {
+        Self {
+            initiator: Initiator::Agent {
+                agent_id,
+                tool: None,
+            },
+            interactive: false,
+            checkpoint_policy: CheckpointPolicy::AutoApprove,
+            notifications: false,
+            timeout: Some(AGENT_TIMEOUT),
+        }
+    }
+
+    /// Override the initiator (builder pattern)
+    pub fn with_initiator(mut self, initiator: Initiator) -> Self {
+        self.initiator = initiator;
+        self
+    }
+
+    /// Override the checkpoint policy (builder pattern)
+    pub fn with_checkpoint_policy(mut self, policy: CheckpointPolicy) -> Self {
+        self.checkpoint_policy = policy;
+        self
+    }
+
+    /// Override the timeout (builder pattern)
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Apply this profile to a context so the lens sees the effective host capabilities
+    pub fn apply(&self, mut ctx: LensContext) -> LensContext {
+        ctx.host.interactive = ctx.host.interactive && self.interactive;
+        ctx.host.notifications = ctx.host.notifications && self.notifications;
+        ctx.initiator = self.initiator.clone();
+        ctx
+    }
+
+    /// Execute a lens under this profile, enforcing the timeout.
+    ///
+    /// A run that times out has its `ctx.cancellation` cancelled, so work
+    /// it spawned can stop too.
+    pub async fn execute(&self, lens: &dyn Lens, ctx: LensContext) -> Result<LensResult> {
+        let ctx = self.apply(ctx);
+        let cancel = ctx.cancellation.clone();
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, lens.execute(ctx))
+                .await
+                .map_err(|_| {
+                    cancel.cancel();
+                    LensError::ExecutionFailed(format!(
+                        "Lens '{}' timed out after {:?}",
+                        lens.id(),
+                        timeout
+                    ))
+                })?,
+            None => lens.execute(ctx).await,
+        }
+    }
+
+    /// Execute a streaming lens under this profile, resolving its
+    /// checkpoints by the checkpoint policy (see
+    /// [`resolve_checkpoints`](Self::resolve_checkpoints))
+    pub async fn execute_streaming(
+        &self,
+        lens: &dyn StreamingLens,
+        ctx: LensContext,
+    ) -> Result<(LensResult, LensEventStream)> {
+        let ctx = self.apply(ctx);
+        let cancel = ctx.cancellation.clone();
+        let (result, stream) = lens.execute_streaming(ctx).await?;
+        Ok((result, self.resolve_checkpoints(stream, cancel)))
+    }
+
+    /// Resolve the `Checkpoint` events of a run by the checkpoint policy.
+    ///
+    /// - `WaitForUser` passes them through for the host to present.
+    /// - `AutoApprove` turns each into a `Progress` event, so no host waits
+    ///   on it.
+    /// - `AutoReject` cancels the run through `cancel` and ends the stream
+    ///   with a `Failed` event at the first checkpoint.
+    pub fn resolve_checkpoints(
+        &self,
+        stream: LensEventStream,
+        cancel: CancellationToken,
+    ) -> LensEventStream {
+        let policy = self.checkpoint_policy;
+        if policy.requires_user() {
+            return stream;
+        }
+        let mut rejected = false;
+        Box::pin(stream.map_while(move |event| {
+            if rejected {
+                return None;
+            }
+            let LensEvent::Checkpoint {
+                lens,
+                phase,
+                timestamp,
+                ..
+            } = event
+            else {
+                return Some(event);
+            };
+            if policy == CheckpointPolicy::AutoApprove {
+                return Some(LensEvent::Progress {
+                    lens,
+                    message: format!("Checkpoint '{}' approved automatically", phase),
+                    percent: None,
+                    timestamp,
+                });
+            }
+            rejected = true;
+            cancel.cancel();
+            let message = format!("Checkpoint '{}' rejected automatically", phase);
+            Some(LensEvent::failed(lens, message, false))
+        }))
+    }
+
+    /// Tag every event in a stream with this profile's initiator
+    pub fn tag_stream(&self, stream: LensEventStream) -> impl Stream<Item = TaggedEvent> + Send {
+        let initiator = self.initiator.clone();
+        stream.map(move |event| TaggedEvent {
+            initiator: initiator.clone(),
+            event,
+        })
+    }
+}
+
+/// A `LensEvent` tagged with the initiator of its run.
+///
+/// Serializes as the flat event object with an extra `initiator` field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaggedEvent {
+    pub initiator: Initiator,
+    #[serde(flatten)]
+    pub event: LensEvent,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    struct SlowLens;
+
+    #[async_trait]
+    impl Lens for SlowLens {
+        fn id(&self) -> &str {
+            "slow"
+        }
+        fn name(&self) -> &str {
+            "Slow Lens"
+        }
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+
+        async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(LensResult::success(json!({
+                "interactive": ctx.is_interactive()
+            })))
+        }
+    }
+
+    #[test]
+    fn test_agent_profile_disables_interactivity() {
+        let profile = ExecutionProfile::agent(Some("claude".to_string()));
+        let ctx = profile.apply(LensContext::new(PathBuf::from("/tmp"), json!({})));
+
+        assert!(!ctx.is_interactive());
+        assert!(!ctx.host.notifications);
+        assert!(!profile.checkpoint_policy.requires_user());
+        assert_eq!(
+            ctx.initiator,
+            Initiator::Agent {
+                agent_id: Some("claude".to_string()),
+                tool: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_user_profile_keeps_host_capabilities() {
+        let ctx =
+            ExecutionProfile::user().apply(LensContext::new(PathBuf::from("/tmp"), json!({})));
+
+        assert!(ctx.is_interactive());
+        assert_eq!(ctx.initiator, Initiator::User);
+    }
+
+    #[async_trait]
+    impl StreamingLens for SlowLens {
+        async fn execute_streaming(
+            &self,
+            ctx: LensContext,
+        ) -> Result<(LensResult, LensEventStream)> {
+            let events = [
+                LensEvent::started("slow", "review"),
+                LensEvent::checkpoint("slow", "draft", json!({}), "Draft ready"),
+                LensEvent::completed("slow", Duration::from_secs(1)),
+            ];
+            let result = LensResult::success(json!({ "interactive": ctx.is_interactive() }));
+            Ok((result, Box::pin(tokio_stream::iter(events))))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_agent_profile_times_out() {
+        let profile = ExecutionProfile::agent(None).with_timeout(Some(Duration::from_millis(50)));
+        let cancel = CancellationToken::new();
+        let ctx =
+            LensContext::new(PathBuf::from("/tmp"), json!({})).with_cancellation(cancel.clone());
+
+        let err = profile.execute(&SlowLens, ctx).await.unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+        assert!(cancel.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_policy_resolves_checkpoints() {
+        let run = |policy| async move {
+            let cancel = CancellationToken::new();
+            let ctx = LensContext::new(PathBuf::from("/tmp"), json!({}))
+                .with_cancellation(cancel.clone());
+            let profile = ExecutionProfile::agent(None).with_checkpoint_policy(policy);
+            let (_, stream) = profile.execute_streaming(&SlowLens, ctx).await.unwrap();
+            let events: Vec<_> = stream.collect().await;
+            let types: Vec<_> = events.iter().map(LensEvent::event_type).collect();
+            (types, cancel.is_cancelled())
+        };
+
+        let (types, cancelled) = run(CheckpointPolicy::WaitForUser).await;
+        assert_eq!(types, ["Started", "Checkpoint", "Completed"]);
+        assert!(!cancelled);
+
+        let (types, cancelled) = run(CheckpointPolicy::AutoApprove).await;
+        assert_eq!(types, ["Started", "Progress", "Completed"]);
+        assert!(!cancelled);
+
+        let (types, cancelled) = run(CheckpointPolicy::AutoReject).await;
+        assert_eq!(types, ["Started", "Failed"]);
+        assert!(cancelled);
+    }
+
+    #[test]
+    fn test_initiator_display() {
+        assert_eq!(Initiator::User.to_string(), "user");
+        assert_eq!(
+            Initiator::agent_tool_call(Some("claude".to_string()), "decompose").to_string(),
+            "agent:claude via decompose"
+        );
+        assert_eq!(
+            Initiator::Schedule {
+                schedule: "daily-digest".to_string()
+            }
+            .to_string(),
+            "schedule:daily-digest"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tag_stream_adds_initiator() {
+        let profile = ExecutionProfile::agent(Some("claude".to_string()));
+        let stream: LensEventStream = Box::pin(tokio_stream::iter(vec![
+            LensEvent::started("test", "task"),
+            LensEvent::progress("test", "working"),
+        ]));
+
+        let tagged: Vec<TaggedEvent> = profile.tag_stream(stream).collect().await;
+        assert_eq!(tagged.len(), 2);
+
+        let value = serde_json::to_value(&tagged[0]).unwrap();
+        assert_eq!(value["type"], "started");
+        assert_eq!(value["initiator"]["kind"], "agent");
+        assert_eq!(value["initiator"]["agent_id"], "claude");
+    }
+}
@@ -0,0 +1,471 @@
+//! # Lens Registry
+//!
+//! One place for a host to find, instantiate, and release its lenses,
+//! whether they were compiled into the host or installed on disk.
+//!
+//! Requires the `runtime` feature.
+//!
+//! First-party lenses can be registered in-process with
+//! [`LensRegistry::register`], skipping the dylib path entirely. They are
+//! listed by [`LensRegistry::scan`] next to the lenses found by the
+//! registry's [`LensDiscovery`]:
+//!
+//! ```rust,ignore
+//! let registry = LensRegistry::new().with_discovery(LensDiscovery::default_directory()?);
+//! registry.register(Arc::new(FigmaLens::new()));
+//!
+//! for lens in registry.scan()? {
+//!     println!("{} ({:?})", lens.id(), lens.entry_type());
+//! }
+//! let figma = registry.get("figma")?;
+//! ```
+//!
+//! Installed lenses are instantiated on their first [`get`](LensRegistry::get)
+//! and shared afterwards: subprocess lenses directly, dylib lenses through
+//! [`LensLoader::load_lazy`] when the registry was given a loader, so their
+//! library only opens when they first execute.
+//! [`reload`](LensRegistry::reload) and [`unload`](LensRegistry::unload)
+//! replace or release an instance, and [`RegistryEvent`]s report every
+//! change to the lenses on offer.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::discovery::{DiscoveredLens, LensDiscovery};
+use crate::error::{LensError, Result};
+use crate::lens::Lens;
+use crate::loader::LensLoader;
+use crate::manifest::LensEntryType;
+use crate::subprocess::SubprocessLens;
+
+/// Change to the lenses a [`LensRegistry`] offers, sent to the channel
+/// given to [`LensRegistry::with_events`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryEvent {
+    /// An embedded lens was registered
+    Registered { id: String, version: String },
+    /// An embedded lens was removed
+    Unregistered { id: String },
+    /// An installed lens was instantiated for its first use
+    Loaded { id: String, version: String },
+    /// An installed lens was re-read from disk and replaced
+    Reloaded { id: String, version: String },
+    /// An installed lens's instance was released
+    Unloaded { id: String },
+}
+
+/// Lenses registered in-process, plus optional discovery of installed ones
+#[derive(Default)]
+pub struct LensRegistry {
+    discovery: Option<LensDiscovery>,
+    loader: Option<LensLoader>,
+    events: Option<UnboundedSender<RegistryEvent>>,
+    embedded: Mutex<BTreeMap<String, Arc<dyn Lens>>>,
+    /// Installed lenses instantiated so far
+    instances: Mutex<BTreeMap<String, Arc<dyn Lens>>>,
+}
+
+impl std::fmt::Debug for LensRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LensRegistry")
+            .field("discovery", &self.discovery)
+            .field("embedded", &self.embedded_ids())
+            .field("loaded", &self.loaded_ids())
+            .finish()
+    }
+}
+
+impl LensRegistry {
+    /// Create an empty registry without discovery
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// List installed lenses found by `discovery` too (builder pattern)
+    pub fn with_discovery(mut self, discovery: LensDiscovery) -> Self {
+        self.discovery = Some(discovery);
+        self
+    }
+
+    /// Instantiate installed dylib lenses with `loader` (builder pattern).
+    /// Without a loader only subprocess lenses are instantiated from disk.
+    ///
+    /// # Safety
+    ///
+    /// Same safety requirements as [`LensLoader::load`], for every dylib
+    /// lens the registry's discovery finds.
+    pub unsafe fn with_loader(mut self, loader: LensLoader) -> Self {
+        self.loader = Some(loader);
+        self
+    }
+
+    /// Report [`RegistryEvent`]s to `events` (builder pattern)
+    pub fn with_events(mut self, events: UnboundedSender<RegistryEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Register a lens compiled into the host, returning the lens it
+    /// replaces with the same id
+    pub fn register(&self, lens: Arc<dyn Lens>) -> Option<Arc<dyn Lens>> {
+        self.emit(RegistryEvent::Registered {
+            id: lens.id().to_string(),
+            version: lens.version().to_string(),
+        });
+        self.embedded
+            .lock()
+            .unwrap()
+            .insert(lens.id().to_string(), lens)
+    }
+
+    /// Remove an embedded lens
+    pub fn unregister(&self, id: &str) -> Option<Arc<dyn Lens>> {
+        let removed = self.embedded.lock().unwrap().remove(id);
+        if removed.is_some() {
+            self.emit(RegistryEvent::Unregistered { id: id.to_string() });
+        }
+        removed
+    }
+
+    /// The lens with `id`: the embedded lens registered under it, or the
+    /// installed lens, instantiated on first use and shared afterwards
+    pub fn get(&self, id: &str) -> Result<Arc<dyn Lens>> {
+        if let Some(lens) = self.embedded.lock().unwrap().get(id) {
+            return Ok(Arc::clone(lens));
+        }
+        // Held while instantiating so concurrent callers share one instance
+        let mut instances = self.instances.lock().unwrap();
+        if let Some(lens) = instances.get(id) {
+            return Ok(Arc::clone(lens));
+        }
+        let lens = self.instantiate(id)?;
+        instances.insert(id.to_string(), Arc::clone(&lens));
+        self.emit(RegistryEvent::Loaded {
+            id: id.to_string(),
+            version: lens.version().to_string(),
+        });
+        Ok(lens)
+    }
+
+    /// Re-read the installed lens `id` from disk and replace its instance.
+    ///
+    /// Handles to the old instance keep working until they are dropped.
+    pub fn reload(&self, id: &str) -> Result<Arc<dyn Lens>> {
+        if self.embedded.lock().unwrap().contains_key(id) {
+            return Err(LensError::InvalidInput(format!(
+                "Lens '{}' is embedded in the host and cannot be reloaded",
+                id
+            )));
+        }
+        let lens = self.instantiate(id)?;
+        self.instances
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), Arc::clone(&lens));
+        self.emit(RegistryEvent::Reloaded {
+            id: id.to_string(),
+            version: lens.version().to_string(),
+        });
+        Ok(lens)
+    }
+
+    /// Release the instance of the installed lens `id`; the next
+    /// [`get`](Self::get) instantiates it again.
+    ///
+    /// Refuses while handles returned by `get` are still alive, since a
+    /// dylib lens's library closes with its last handle.
+    pub fn unload(&self, id: &str) -> Result<()> {
+        let mut instances = self.instances.lock().unwrap();
+        let Some(lens) = instances.get(id) else {
+            return Err(LensError::LensNotFound(format!(
+                "Lens '{}' is not loaded",
+                id
+            )));
+        };
+        let handles = Arc::strong_count(lens) - 1;
+        if handles > 0 {
+            return Err(LensError::Other(format!(
+                "Lens '{}' is still in use ({} live handle{})",
+                id,
+                handles,
+                if handles == 1 { "" } else { "s" }
+            )));
+        }
+        instances.remove(id);
+        self.emit(RegistryEvent::Unloaded { id: id.to_string() });
+        Ok(())
+    }
+
+    /// Number of handles from [`get`](Self::get) still alive for the
+    /// installed lens `id`
+    pub fn handle_count(&self, id: &str) -> usize {
+        self.instances
+            .lock()
+            .unwrap()
+            .get(id)
+            .map_or(0, |lens| Arc::strong_count(lens) - 1)
+    }
+
+    /// Ids of the embedded lenses, sorted
+    pub fn embedded_ids(&self) -> Vec<String> {
+        self.embedded.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Ids of the installed lenses instantiated so far, sorted
+    pub fn loaded_ids(&self) -> Vec<String> {
+        self.instances.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Installed and embedded lenses, sorted by id.
+    ///
+    /// Embedded lenses take precedence over installed lenses with the same
+    /// id, matching what [`get`](Self::get) returns.
+    pub fn scan(&self) -> Result<Vec<DiscoveredLens>> {
+        let mut lenses = match &self.discovery {
+            Some(discovery) => discovery.scan()?,
+            None => Vec::new(),
+        };
+
+        let embedded = self.embedded.lock().unwrap();
+        lenses.retain(|lens| {
+            let shadowed = embedded.contains_key(lens.id());
+            if shadowed {
+                eprintln!(
+                    "Warning: installed lens '{}' at {:?} is shadowed by an embedded lens",
+                    lens.id(),
+                    lens.path
+                );
+            }
+            !shadowed
+        });
+        lenses.extend(
+            embedded
+                .values()
+                .map(|lens| DiscoveredLens::embedded(lens.as_ref())),
+        );
+        lenses.sort_by(|a, b| a.id().cmp(b.id()));
+        Ok(lenses)
+    }
+
+    fn instantiate(&self, id: &str) -> Result<Arc<dyn Lens>> {
+        let discovered = match &self.discovery {
+            Some(discovery) => discovery.get_lens(id)?,
+            None => None,
+        }
+        .ok_or_else(|| LensError::LensNotFound(id.to_string()))?;
+        if !discovered.is_enabled() {
+            return Err(LensError::PermissionDenied(format!(
+                "Lens '{}' is disabled",
+                id
+            )));
+        }
+
+        match discovered.entry_type() {
+            LensEntryType::Subprocess => {
+                Ok(Arc::new(SubprocessLens::from_discovered(&discovered)?))
+            }
+            LensEntryType::Dylib => {
+                let loader = self.loader.as_ref().ok_or_else(|| {
+                    LensError::Initialization(format!(
+                        "Lens '{}' is a dylib lens and the registry has no loader",
+                        id
+                    ))
+                })?;
+                // SAFETY: accepted by the caller of `with_loader`
+                Ok(Arc::new(unsafe { loader.load_lazy(&discovered) }?))
+            }
+            other => Err(LensError::Initialization(format!(
+                "Lens '{}' declares a {:?} entry point, which the registry cannot instantiate",
+                id, other
+            ))),
+        }
+    }
+
+    fn emit(&self, event: RegistryEvent) {
+        if let Some(events) = &self.events {
+            let _ = events.send(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LensContext, LensResult};
+    use async_trait::async_trait;
+    use std::fs;
+    use tempfile::tempdir;
+
+    struct Builtin(&'static str);
+
+    #[async_trait]
+    impl Lens for Builtin {
+        fn id(&self) -> &str {
+            self.0
+        }
+
+        fn name(&self) -> &str {
+            "Builtin"
+        }
+
+        fn version(&self) -> &str {
+            "2.0.0"
+        }
+
+        fn description(&self) -> &str {
+            "Ships with the host"
+        }
+
+        async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+            Ok(LensResult::success(ctx.input))
+        }
+    }
+
+    #[test]
+    fn test_register_and_get() {
+        let registry = LensRegistry::new();
+        assert!(matches!(
+            registry.get("notes"),
+            Err(LensError::LensNotFound(_))
+        ));
+
+        assert!(registry.register(Arc::new(Builtin("notes"))).is_none());
+        assert!(registry.register(Arc::new(Builtin("notes"))).is_some());
+        assert_eq!(registry.get("notes").unwrap().version(), "2.0.0");
+        assert_eq!(registry.embedded_ids(), ["notes"]);
+
+        assert!(registry.unregister("notes").is_some());
+        assert!(registry.get("notes").is_err());
+    }
+
+    #[test]
+    fn test_scan_lists_embedded_with_installed() {
+        let temp_dir = tempdir().unwrap();
+        for id in ["figma", "notes"] {
+            let dir = temp_dir.path().join(id);
+            fs::create_dir(&dir).unwrap();
+            fs::write(
+                dir.join("lens.toml"),
+                format!("[lens]\nid = \"{id}\"\nname = \"{id}\"\nversion = \"1.0.0\"\n"),
+            )
+            .unwrap();
+        }
+
+        let registry = LensRegistry::new().with_discovery(LensDiscovery::new(temp_dir.path()));
+        registry.register(Arc::new(Builtin("notes")));
+        registry.register(Arc::new(Builtin("calendar")));
+
+        let lenses = registry.scan().unwrap();
+        let listed: Vec<_> = lenses
+            .iter()
+            .map(|lens| (lens.id(), lens.entry_type()))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("calendar", LensEntryType::Embedded),
+                ("figma", LensEntryType::Dylib),
+                ("notes", LensEntryType::Embedded),
+            ]
+        );
+        assert_eq!(lenses[2].version(), "2.0.0");
+        assert_eq!(lenses[2].manifest.lens.description, "Ships with the host");
+        assert!(lenses[2].library_path.is_none());
+    }
+
+    fn write_subprocess_lens(root: &std::path::Path, id: &str, version: &str) {
+        let dir = root.join(id);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("lens.toml"),
+            format!(
+                "[lens]\nid = \"{id}\"\nname = \"{id}\"\nversion = \"{version}\"\n\n\
+                 [lens.entry]\ntype = \"subprocess\"\npath = \"run.sh\"\n"
+            ),
+        )
+        .unwrap();
+        fs::write(dir.join("run.sh"), "#!/bin/sh\n").unwrap();
+    }
+
+    #[test]
+    fn test_get_instantiates_installed_lens_once() {
+        let temp_dir = tempdir().unwrap();
+        write_subprocess_lens(temp_dir.path(), "figma", "1.0.0");
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let registry = LensRegistry::new()
+            .with_discovery(LensDiscovery::new(temp_dir.path()))
+            .with_events(tx);
+
+        let figma = registry.get("figma").unwrap();
+        assert_eq!(figma.version(), "1.0.0");
+        let again = registry.get("figma").unwrap();
+        assert!(Arc::ptr_eq(&figma, &again));
+        assert_eq!(registry.handle_count("figma"), 2);
+        assert_eq!(registry.loaded_ids(), ["figma"]);
+        assert!(matches!(
+            registry.get("missing"),
+            Err(LensError::LensNotFound(_))
+        ));
+
+        let err = registry.unload("figma").unwrap_err();
+        assert!(err.to_string().contains("2 live handles"), "{}", err);
+
+        write_subprocess_lens(temp_dir.path(), "figma", "1.1.0");
+        let reloaded = registry.reload("figma").unwrap();
+        assert_eq!(reloaded.version(), "1.1.0");
+        assert_eq!(figma.version(), "1.0.0");
+        assert!(Arc::ptr_eq(&registry.get("figma").unwrap(), &reloaded));
+
+        drop((figma, again, reloaded));
+        registry.unload("figma").unwrap();
+        assert!(registry.loaded_ids().is_empty());
+        assert!(registry.unload("figma").is_err());
+
+        registry.register(Arc::new(Builtin("notes")));
+        assert!(registry.reload("notes").is_err());
+        registry.unregister("notes");
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        assert_eq!(
+            events,
+            [
+                RegistryEvent::Loaded {
+                    id: "figma".into(),
+                    version: "1.0.0".into()
+                },
+                RegistryEvent::Reloaded {
+                    id: "figma".into(),
+                    version: "1.1.0".into()
+                },
+                RegistryEvent::Unloaded { id: "figma".into() },
+                RegistryEvent::Registered {
+                    id: "notes".into(),
+                    version: "2.0.0".into()
+                },
+                RegistryEvent::Unregistered { id: "notes".into() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dylib_lens_needs_loader() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().join("figma");
+        fs::create_dir(&dir).unwrap();
+        fs::write(
+            dir.join("lens.toml"),
+            "[lens]\nid = \"figma\"\nname = \"figma\"\nversion = \"1.0.0\"\n",
+        )
+        .unwrap();
+
+        let registry = LensRegistry::new().with_discovery(LensDiscovery::new(temp_dir.path()));
+        let err = registry.get("figma").err().unwrap();
+        assert!(matches!(err, LensError::Initialization(_)), "{:?}", err);
+        assert!(registry.loaded_ids().is_empty());
+    }
+}
@@ -0,0 +1,338 @@
+//! # Run Reports
+//!
+//! Render a finished lens run as a single static Markdown or HTML document that
+//! users can attach to tickets or share with teammates who don't run the app.
+//!
+//! The report combines the lens output spec (titles and descriptions for each
+//! output key), the emitted `Data`/`Checkpoint` payloads rendered through
+//! their render blocks by [`render_preview`], the event timeline, and basic
+//! metrics. Recorded runs are rendered with `RunHistory::render_report`.
+//!
+//! ```rust,ignore
+//! let report = RunReport::new("figma", "run-42", &events).with_output_spec(&spec);
+//! std::fs::write("run-42.md", report.render(ReportFormat::Markdown))?;
+//! ```
+
+mod preview;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::output_spec::LensOutputSpec;
+use crate::LensEvent;
+
+pub use preview::render_preview;
+
+/// Output format for a rendered report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+/// A captured lens run ready to be rendered
+#[derive(Debug, Clone)]
+pub struct RunReport<'a> {
+    pub lens_id: &'a str,
+    pub run_id: &'a str,
+    pub events: &'a [LensEvent],
+    pub output_spec: Option<&'a LensOutputSpec>,
+}
+
+/// Summary metrics derived from a run's events
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunMetrics {
+    pub event_count: usize,
+    pub data_count: usize,
+    pub checkpoint_count: usize,
+    pub duration: Option<Duration>,
+    pub succeeded: Option<bool>,
+}
+
+impl<'a> RunReport<'a> {
+    /// Create a report for a run's captured events
+    pub fn new(lens_id: &'a str, run_id: &'a str, events: &'a [LensEvent]) -> Self {
+        Self {
+            lens_id,
+            run_id,
+            events,
+            output_spec: None,
+        }
+    }
+
+    /// Attach the lens output spec for titles and descriptions (builder pattern)
+    pub fn with_output_spec(mut self, spec: &'a LensOutputSpec) -> Self {
+        self.output_spec = Some(spec);
+        self
+    }
+
+    /// Compute summary metrics
+    pub fn metrics(&self) -> RunMetrics {
+        let mut metrics = RunMetrics {
+            event_count: self.events.len(),
+            ..RunMetrics::default()
+        };
+        for event in self.events {
+            match event {
+                LensEvent::Data { .. } => metrics.data_count += 1,
+                LensEvent::Checkpoint { .. } => metrics.checkpoint_count += 1,
+                LensEvent::Completed { duration, .. } => {
+                    metrics.duration = Some(*duration);
+                    metrics.succeeded = Some(true);
+                }
+                LensEvent::Failed { .. } => metrics.succeeded = Some(false),
+                LensEvent::CacheHit { .. } => metrics.succeeded = Some(true),
+                _ => {}
+            }
+        }
+        metrics
+    }
+
+    /// Render the report in the requested format
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => self.render_markdown(),
+            ReportFormat::Html => self.render_html(),
+        }
+    }
+
+    fn sections(&self, format: ReportFormat) -> Vec<Section> {
+        self.events
+            .iter()
+            .filter_map(|event| match event {
+                LensEvent::Data { key, value, .. } => Some((key, value, None)),
+                LensEvent::Checkpoint {
+                    phase,
+                    data,
+                    message,
+                    ..
+                } => Some((phase, data, Some(message.as_str()))),
+                _ => None,
+            })
+            .map(|(key, payload, message)| {
+                let output = self.output_spec.and_then(|spec| spec.get_output(key));
+                Section {
+                    title: output
+                        .map(|o| o.title.clone())
+                        .unwrap_or_else(|| key.clone()),
+                    description: output
+                        .map(|o| o.description.clone())
+                        .filter(|d| !d.is_empty())
+                        .or_else(|| message.map(str::to_string)),
+                    body: render_preview(
+                        output
+                            .map(|o| o.render_blocks.as_slice())
+                            .unwrap_or_default(),
+                        payload,
+                        format,
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    fn render_markdown(&self) -> String {
+        let metrics = self.metrics();
+        let mut out = format!("# {} — run {}\n\n", self.lens_id, self.run_id);
+
+        out.push_str("## Summary\n\n");
+        for (label, value) in metric_rows(&metrics) {
+            out.push_str(&format!("- **{}:** {}\n", label, value));
+        }
+
+        out.push_str("\n## Outputs\n");
+        for section in self.sections(ReportFormat::Markdown) {
+            out.push_str(&format!("\n### {}\n\n", section.title));
+            if let Some(description) = section.description {
+                out.push_str(&format!("{}\n\n", description));
+            }
+            out.push_str(&section.body);
+        }
+
+        out.push_str("\n## Timeline\n\n| Time | Event | Detail |\n|------|-------|--------|\n");
+        for event in self.events {
+            out.push_str(&format!(
+                "| {} | {} | {} |\n",
+                format_timestamp(event.timestamp()),
+                event.event_type(),
+                event_detail(event).replace('|', "\\|")
+            ));
+        }
+        out
+    }
+
+    fn render_html(&self) -> String {
+        let metrics = self.metrics();
+        let title = format!("{} — run {}", self.lens_id, self.run_id);
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body>\n<h1>{}</h1>\n",
+            escape_html(&title),
+            escape_html(&title)
+        );
+
+        out.push_str("<h2>Summary</h2>\n<ul>\n");
+        for (label, value) in metric_rows(&metrics) {
+            out.push_str(&format!(
+                "<li><strong>{}:</strong> {}</li>\n",
+                label,
+                escape_html(&value)
+            ));
+        }
+        out.push_str("</ul>\n<h2>Outputs</h2>\n");
+        for section in self.sections(ReportFormat::Html) {
+            out.push_str(&format!("<h3>{}</h3>\n", escape_html(&section.title)));
+            if let Some(description) = section.description {
+                out.push_str(&format!("<p>{}</p>\n", escape_html(&description)));
+            }
+            out.push_str(&section.body);
+        }
+
+        out.push_str(
+            "<h2>Timeline</h2>\n<table>\n<tr><th>Time</th><th>Event</th><th>Detail</th></tr>\n",
+        );
+        for event in self.events {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                format_timestamp(event.timestamp()),
+                event.event_type(),
+                escape_html(&event_detail(event))
+            ));
+        }
+        out.push_str("</table>\n</body>\n</html>\n");
+        out
+    }
+}
+
+struct Section {
+    title: String,
+    description: Option<String>,
+    /// Payload preview, already in the report's format
+    body: String,
+}
+
+fn metric_rows(metrics: &RunMetrics) -> Vec<(&'static str, String)> {
+    vec![
+        (
+            "Status",
+            match metrics.succeeded {
+                Some(true) => "completed".to_string(),
+                Some(false) => "failed".to_string(),
+                None => "incomplete".to_string(),
+            },
+        ),
+        (
+            "Duration",
+            metrics
+                .duration
+                .map(|d| format!("{} ms", d.as_millis()))
+                .unwrap_or_else(|| "n/a".to_string()),
+        ),
+        ("Events", metrics.event_count.to_string()),
+        ("Data payloads", metrics.data_count.to_string()),
+        ("Checkpoints", metrics.checkpoint_count.to_string()),
+    ]
+}
+
+fn event_detail(event: &LensEvent) -> String {
+    match event {
+        LensEvent::Started { task, .. } => task.clone(),
+        LensEvent::Progress {
+            message, percent, ..
+        } => match percent {
+            Some(p) => format!("{} ({:.0}%)", message, p),
+            None => message.clone(),
+        },
+        LensEvent::Data { key, .. } => key.clone(),
+        LensEvent::Completed { duration, .. } => format!("{} ms", duration.as_millis()),
+        LensEvent::Failed { error, .. } => error.clone(),
+        LensEvent::Retrying {
+            attempt,
+            max_attempts,
+            error,
+            ..
+        } => format!("attempt {}/{}: {}", attempt, max_attempts, error),
+        LensEvent::CacheHit { age, .. } => format!("cached {} s ago", age.as_secs()),
+        LensEvent::Checkpoint { phase, message, .. } => format!("{}: {}", phase, message),
+    }
+}
+
+fn format_timestamp(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs().to_string())
+        .unwrap_or_default()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_events() -> Vec<LensEvent> {
+        vec![
+            LensEvent::started("figma", "decompose"),
+            LensEvent::progress_with_percent("figma", "Extracting", 50.0),
+            LensEvent::data("figma", "phase_0_tokens", json!({"colors": ["#fff"]})),
+            LensEvent::completed("figma", Duration::from_millis(1200)),
+        ]
+    }
+
+    #[test]
+    fn test_metrics() {
+        let events = sample_events();
+        let metrics = RunReport::new("figma", "run-1", &events).metrics();
+
+        assert_eq!(metrics.event_count, 4);
+        assert_eq!(metrics.data_count, 1);
+        assert_eq!(metrics.duration, Some(Duration::from_millis(1200)));
+        assert_eq!(metrics.succeeded, Some(true));
+    }
+
+    #[test]
+    fn test_markdown_uses_output_spec_titles() {
+        let spec = LensOutputSpec::from_yaml(
+            r#"
+lens_id: figma
+outputs:
+  - key: phase_0_tokens
+    title: Design Tokens
+    description: Extracted tokens
+    render_blocks:
+      - type: json_view
+    examples:
+      - colors: []
+"#,
+        )
+        .unwrap();
+        let events = sample_events();
+        let markdown = RunReport::new("figma", "run-1", &events)
+            .with_output_spec(&spec)
+            .render(ReportFormat::Markdown);
+
+        assert!(markdown.starts_with("# figma — run run-1"));
+        assert!(markdown.contains("### Design Tokens"));
+        assert!(markdown.contains("Extracted tokens"));
+        assert!(markdown.contains("\"#fff\""));
+        assert!(markdown.contains("| Progress | Extracting (50%) |"));
+        assert!(markdown.contains("- **Status:** completed"));
+    }
+
+    #[test]
+    fn test_html_escapes_payloads() {
+        let events = vec![
+            LensEvent::data("x", "html", json!({"snippet": "<script>"})),
+            LensEvent::failed("x", "boom & bust", false),
+        ];
+        let html = RunReport::new("x", "run-2", &events).render(ReportFormat::Html);
+
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("boom &amp; bust"));
+        assert!(html.contains("<strong>Status:</strong> failed"));
+    }
+}
@@ -0,0 +1,213 @@
+//! Static previews of `Data` payloads
+//!
+//! Renders a payload through the render blocks of its output definition, as
+//! far as a static document allows: text, tables, KPIs, and images render
+//! natively, and every other block (charts, diffs, actions, ...) falls back to
+//! its JSON. Payloads without render blocks render as JSON.
+
+use serde_json::Value;
+
+use super::{escape_html, ReportFormat};
+use crate::output_spec::{RenderBlock, RenderBlockType};
+
+/// Render `payload` through `blocks` in `format`
+pub fn render_preview(blocks: &[RenderBlock], payload: &Value, format: ReportFormat) -> String {
+    let rendered: Vec<String> = blocks
+        .iter()
+        .filter(|block| block.is_visible(payload))
+        .filter_map(|block| {
+            let value = match block.source_pointer() {
+                Some(pointer) => payload.pointer(&pointer)?,
+                None => payload,
+            };
+            Some(render_block(block, value, format))
+        })
+        .collect();
+    if rendered.is_empty() {
+        return json_block(payload, format);
+    }
+    rendered.concat()
+}
+
+fn render_block(block: &RenderBlock, value: &Value, format: ReportFormat) -> String {
+    let mut out = match (&block.title, format) {
+        (Some(title), ReportFormat::Markdown) => format!("**{}**\n\n", title),
+        (Some(title), ReportFormat::Html) => format!("<h4>{}</h4>\n", escape_html(title)),
+        (None, _) => String::new(),
+    };
+    let body = match (block.block_type, value) {
+        (
+            RenderBlockType::Header | RenderBlockType::Notice | RenderBlockType::Markdown,
+            Value::String(text),
+        ) => match format {
+            ReportFormat::Markdown => format!("{}\n\n", text),
+            ReportFormat::Html => format!("<p>{}</p>\n", escape_html(text)),
+        },
+        (RenderBlockType::Image, Value::String(url)) => match format {
+            ReportFormat::Markdown => {
+                format!("![{}]({})\n\n", block.title.as_deref().unwrap_or(""), url)
+            }
+            ReportFormat::Html => format!("<img src=\"{}\">\n", escape_html(url)),
+        },
+        (RenderBlockType::KpiRow, Value::Object(fields)) => {
+            let rows = fields
+                .iter()
+                .map(|(label, value)| (label.as_str(), cell(value)));
+            match format {
+                ReportFormat::Markdown => rows
+                    .map(|(label, value)| format!("- **{}:** {}\n", label, value))
+                    .chain(Some("\n".to_string()))
+                    .collect(),
+                ReportFormat::Html => format!(
+                    "<ul>\n{}</ul>\n",
+                    rows.map(|(label, value)| format!(
+                        "<li><strong>{}:</strong> {}</li>\n",
+                        escape_html(label),
+                        escape_html(&value)
+                    ))
+                    .collect::<String>()
+                ),
+            }
+        }
+        (RenderBlockType::Table, Value::Array(rows)) => match table_columns(rows) {
+            Some(columns) => render_table(&columns, rows, format),
+            None => json_block(value, format),
+        },
+        _ => json_block(value, format),
+    };
+    out.push_str(&body);
+    out
+}
+
+/// Columns of an array of objects, in first-seen order
+fn table_columns(rows: &[Value]) -> Option<Vec<&str>> {
+    let mut columns = Vec::new();
+    for row in rows {
+        for key in row.as_object()?.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key.as_str());
+            }
+        }
+    }
+    Some(columns).filter(|columns| !columns.is_empty())
+}
+
+fn render_table(columns: &[&str], rows: &[Value], format: ReportFormat) -> String {
+    let cells = |row: &Value| -> Vec<String> {
+        columns
+            .iter()
+            .map(|column| row.get(column).map(cell).unwrap_or_default())
+            .collect()
+    };
+    match format {
+        ReportFormat::Markdown => {
+            let line = |cells: Vec<String>| {
+                format!(
+                    "| {} |\n",
+                    cells
+                        .iter()
+                        .map(|cell| cell.replace('|', "\\|"))
+                        .collect::<Vec<_>>()
+                        .join(" | ")
+                )
+            };
+            let mut out = line(columns.iter().map(|c| c.to_string()).collect());
+            out.push_str(&line(columns.iter().map(|_| "---".to_string()).collect()));
+            for row in rows {
+                out.push_str(&line(cells(row)));
+            }
+            out.push('\n');
+            out
+        }
+        ReportFormat::Html => {
+            let line = |tag: &str, cells: Vec<String>| {
+                format!(
+                    "<tr>{}</tr>\n",
+                    cells
+                        .iter()
+                        .map(|cell| format!("<{tag}>{}</{tag}>", escape_html(cell)))
+                        .collect::<String>()
+                )
+            };
+            let mut out = String::from("<table>\n");
+            out.push_str(&line("th", columns.iter().map(|c| c.to_string()).collect()));
+            for row in rows {
+                out.push_str(&line("td", cells(row)));
+            }
+            out.push_str("</table>\n");
+            out
+        }
+    }
+}
+
+/// A scalar as plain text, anything else as compact JSON
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn json_block(value: &Value, format: ReportFormat) -> String {
+    let json = serde_json::to_string_pretty(value).unwrap_or_default();
+    match format {
+        ReportFormat::Markdown => format!("```json\n{}\n```\n", json),
+        ReportFormat::Html => format!("<pre><code>{}</code></pre>\n", escape_html(&json)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn blocks(yaml: &str) -> Vec<RenderBlock> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_render_preview_blocks() {
+        let blocks = blocks(
+            r#"
+- type: header
+  source: title
+- type: kpi_row
+  title: Totals
+  source: totals
+- type: table
+  source: rows
+- type: bar_chart
+  source: rows
+  visible_if: show_chart
+"#,
+        );
+        let payload = json!({
+            "title": "Audit",
+            "totals": {"errors": 2},
+            "rows": [{"file": "a.rs", "line": 3}, {"file": "b|c.rs"}],
+            "show_chart": false
+        });
+
+        let markdown = render_preview(&blocks, &payload, ReportFormat::Markdown);
+        assert!(markdown.starts_with("Audit\n\n**Totals**\n\n- **errors:** 2\n"));
+        assert!(
+            markdown.contains("| file | line |\n| --- | --- |\n| a.rs | 3 |\n| b\\|c.rs |  |\n")
+        );
+        assert!(!markdown.contains("```json"));
+
+        let html = render_preview(&blocks, &payload, ReportFormat::Html);
+        assert!(html.contains("<tr><th>file</th><th>line</th></tr>"));
+        assert!(html.contains("<li><strong>errors:</strong> 2</li>"));
+    }
+
+    #[test]
+    fn test_render_preview_falls_back_to_json() {
+        let payload = json!({"snippet": "<b>"});
+        let markdown = render_preview(&[], &payload, ReportFormat::Markdown);
+        assert!(markdown.starts_with("```json\n"));
+
+        let html = render_preview(&blocks("- type: line_chart"), &payload, ReportFormat::Html);
+        assert!(html.contains("&lt;b&gt;"));
+    }
+}
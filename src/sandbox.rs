@@ -0,0 +1,74 @@
+//! # Sandbox
+//!
+//! Runtime enforcement of the permissions a lens declares in
+//! `[security]`.
+//!
+//! [`FsGuard`] turns `fs:read:<path>` / `fs:write:<path>` permissions into
+//! checks on the paths a lens touches through [`LensContext`]'s filesystem
+//! helpers:
+//!
+//! ```rust,ignore
+//! let guard = FsGuard::from_security(lens.id(), &security, &lens_dir);
+//! let ctx = LensContext::new(cwd, input).with_fs_guard(Arc::new(guard));
+//!
+//! // Inside the lens
+//! let notes = ctx.read_to_string("~/Documents/notes.md")?;
+//! ```
+//!
+//! [`NetworkGuard`] does the same for `network:<domain>` permissions; HTTP
+//! clients used by lenses consult it through
+//! [`LensContext::check_network`](crate::LensContext::check_network).
+//! Guards report denials as [`AuditEvent`]s.
+//!
+//! Hosts can plug in a [`PermissionPrompter`] to let the user allow access
+//! a lens did not declare — once, always (kept in a [`PermissionStore`]),
+//! or not at all.
+//!
+//! In-process checks only cover access that goes through these APIs; a
+//! native lens calling `std::fs` directly is not stopped by them.
+//!
+//! [`LensContext`]: crate::LensContext
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+mod fs;
+mod network;
+mod prompt;
+
+pub use fs::{FsAccess, FsGuard};
+pub use network::NetworkGuard;
+pub use prompt::{
+    PermissionDecision, PermissionPrompter, PermissionRequest, PermissionStore, PromptReason,
+    FULL_ACCESS_PERMISSION,
+};
+
+/// Sandbox decisions reported to the channel given to a guard's
+/// `with_audit`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// `lens` was refused `permission` (e.g. `network:evil.com`)
+    Denied { lens: String, permission: String },
+}
+
+/// Expand a leading `~` to the user's home directory
+pub(crate) fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(feature = "runtime")]
+fn home_dir() -> Option<PathBuf> {
+    dirs::home_dir()
+}
+
+#[cfg(not(feature = "runtime"))]
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
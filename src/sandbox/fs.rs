@@ -0,0 +1,293 @@
+//! Filesystem permission checks
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use crate::error::{LensError, Result};
+use crate::manifest::SecurityConfig;
+
+use super::expand_home;
+use super::prompt::{Approval, PermissionPrompter, PermissionStore};
+
+/// Kind of filesystem access being checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsAccess {
+    Read,
+    Write,
+}
+
+impl FsAccess {
+    /// The permission action, `read` or `write`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+        }
+    }
+}
+
+/// One granted path scope
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathGrant {
+    /// `*`: any path
+    Any,
+    /// A single file or directory
+    Exact(PathBuf),
+    /// `<dir>/*`: everything under a directory
+    Under(PathBuf),
+}
+
+/// Enforces a lens's `fs:` permissions.
+///
+/// A path is allowed when a grant for the access covers it after `~`
+/// expansion and canonicalization, so `..` segments and symlinks cannot
+/// lead outside a granted directory. Grants without an action (`fs:<path>`)
+/// cover both reads and writes. A guard grants nothing by default.
+///
+/// With [`with_prompter`](Self::with_prompter), access outside the grants
+/// is put to the user instead of refused, as is the first access of a lens
+/// declaring the `full` sandbox.
+#[derive(Debug, Clone, Default)]
+pub struct FsGuard {
+    read: Vec<PathGrant>,
+    write: Vec<PathGrant>,
+    /// Relative paths in grants resolve against this directory
+    base_dir: PathBuf,
+    lens: String,
+    full_access: bool,
+    approval: Option<Arc<Approval>>,
+}
+
+impl FsGuard {
+    /// Allow every path; for hosts that do not sandbox lenses
+    pub fn unrestricted() -> Self {
+        Self {
+            read: vec![PathGrant::Any],
+            write: vec![PathGrant::Any],
+            ..Self::default()
+        }
+    }
+
+    /// Grants from `security`'s `fs:` permissions for lens `lens`, or none
+    /// when its sandbox level does not permit filesystem access.
+    ///
+    /// Relative scopes resolve against `base_dir`, usually the lens directory.
+    pub fn from_security(
+        lens: impl Into<String>,
+        security: &SecurityConfig,
+        base_dir: &Path,
+    ) -> Self {
+        let mut guard = Self {
+            base_dir: base_dir.to_path_buf(),
+            lens: lens.into(),
+            full_access: security.requires_full_access(),
+            ..Self::default()
+        };
+        if !security.sandbox.permits("fs") {
+            return guard;
+        }
+        for permission in security.parsed_permissions() {
+            if permission.permission_type != "fs" {
+                continue;
+            }
+            let grant = match permission.scope.strip_suffix('*') {
+                Some("") => PathGrant::Any,
+                Some(dir) => PathGrant::Under(PathBuf::from(dir)),
+                None => PathGrant::Exact(PathBuf::from(&permission.scope)),
+            };
+            match permission.action.as_deref() {
+                Some("read") => guard.read.push(grant),
+                Some("write") => guard.write.push(grant),
+                None => {
+                    guard.read.push(grant.clone());
+                    guard.write.push(grant);
+                }
+                Some(_) => {}
+            }
+        }
+        guard
+    }
+
+    /// Ask `prompter` about access outside the grants, remembering "always"
+    /// answers in `store` (builder pattern)
+    pub fn with_prompter(
+        mut self,
+        prompter: Arc<dyn PermissionPrompter>,
+        store: Arc<PermissionStore>,
+    ) -> Self {
+        self.approval = Some(Arc::new(Approval::new(self.lens.clone(), prompter, store)));
+        self
+    }
+
+    /// Check `access` to `path`, resolving a relative `path` against `cwd`.
+    ///
+    /// Returns the canonical path to use for the access, so the checked
+    /// path is the one that gets opened.
+    pub fn check(&self, access: FsAccess, path: &Path, cwd: &Path) -> Result<PathBuf> {
+        let resolved = resolve(path, cwd)?;
+        let grants = match access {
+            FsAccess::Read => &self.read,
+            FsAccess::Write => &self.write,
+        };
+        let allowed = grants.iter().any(|grant| match grant {
+            PathGrant::Any => true,
+            PathGrant::Exact(scope) => {
+                resolve(scope, &self.base_dir).is_ok_and(|scope| resolved == scope)
+            }
+            PathGrant::Under(scope) => {
+                resolve(scope, &self.base_dir).is_ok_and(|scope| resolved.starts_with(scope))
+            }
+        });
+        let approved = match &self.approval {
+            Some(approval) => approval.approves(
+                allowed,
+                self.full_access,
+                &format!("fs:{}:{}", access.as_str(), resolved.display()),
+            ),
+            None => allowed,
+        };
+        if !approved {
+            return Err(LensError::PermissionDenied(format!(
+                "No fs:{} permission covers {:?}",
+                access.as_str(),
+                resolved
+            )));
+        }
+        Ok(resolved)
+    }
+}
+
+/// Absolute, canonical form of `path`.
+///
+/// The longest existing ancestor is canonicalized and the missing
+/// components are appended, so paths that are about to be created resolve
+/// too; `..` is only allowed in the existing part.
+fn resolve(path: &Path, cwd: &Path) -> Result<PathBuf> {
+    let path = expand_home(path);
+    let path = if path.is_absolute() {
+        path
+    } else {
+        cwd.join(path)
+    };
+
+    let mut existing = path.as_path();
+    let mut missing = Vec::new();
+    let canonical = loop {
+        match existing.canonicalize() {
+            Ok(canonical) => break canonical,
+            Err(_) => {
+                match existing.components().next_back() {
+                    Some(Component::Normal(name)) => missing.push(name),
+                    Some(Component::CurDir) => {}
+                    _ => {
+                        return Err(LensError::PermissionDenied(format!(
+                            "Cannot resolve {:?} inside existing directories",
+                            path
+                        )))
+                    }
+                }
+                existing = existing.parent().unwrap_or(Path::new(""));
+            }
+        }
+    };
+    Ok(missing
+        .into_iter()
+        .rev()
+        .fold(canonical, |resolved, name| resolved.join(name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::SandboxLevel;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn security(sandbox: SandboxLevel, permissions: &[&str]) -> SecurityConfig {
+        SecurityConfig {
+            library_hash: None,
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            sandbox,
+        }
+    }
+
+    #[test]
+    fn test_fs_guard_scopes() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("docs/sub")).unwrap();
+        fs::create_dir_all(root.join("secrets")).unwrap();
+        fs::write(root.join("docs/sub/a.md"), "a").unwrap();
+        fs::write(root.join("secrets/key"), "k").unwrap();
+        fs::write(root.join("notes.md"), "n").unwrap();
+
+        let guard = FsGuard::from_security(
+            "test",
+            &security(
+                SandboxLevel::Full,
+                &["fs:read:docs/*", "fs:write:out/*", "fs:notes.md"],
+            ),
+            root,
+        );
+        let check = |access, path: &str| guard.check(access, Path::new(path), root);
+
+        assert!(check(FsAccess::Read, "docs/sub/a.md").is_ok());
+        assert!(check(FsAccess::Read, "notes.md").is_ok());
+        assert!(check(FsAccess::Write, "notes.md").is_ok());
+        assert!(check(FsAccess::Write, "docs/sub/a.md").is_err());
+        assert!(check(FsAccess::Read, "secrets/key").is_err());
+        assert!(check(FsAccess::Read, "docs/../secrets/key").is_err());
+
+        // Writes may target files and directories that do not exist yet
+        let resolved = check(FsAccess::Write, "out/new/report.md").unwrap();
+        assert_eq!(
+            resolved,
+            root.canonicalize().unwrap().join("out/new/report.md")
+        );
+        assert!(check(FsAccess::Write, "out/new/../../secrets/key").is_err());
+
+        let err = check(FsAccess::Read, "secrets/key").unwrap_err();
+        assert!(matches!(err, LensError::PermissionDenied(_)));
+        assert!(err.to_string().contains("fs:read"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fs_guard_follows_symlinks() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("secret"), "k").unwrap();
+        std::os::unix::fs::symlink(root.join("secret"), root.join("docs/link")).unwrap();
+
+        let guard = FsGuard::from_security(
+            "test",
+            &security(SandboxLevel::Full, &["fs:read:docs/*"]),
+            root,
+        );
+        assert!(guard
+            .check(FsAccess::Read, Path::new("docs/link"), root)
+            .is_err());
+    }
+
+    #[test]
+    fn test_fs_guard_respects_sandbox_level() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let path = Path::new("file.txt");
+
+        for sandbox in [SandboxLevel::Restricted, SandboxLevel::Network] {
+            let guard = FsGuard::from_security("test", &security(sandbox, &["fs:*"]), root);
+            assert!(guard.check(FsAccess::Read, path, root).is_err());
+        }
+        let guard = FsGuard::from_security("test", &security(SandboxLevel::Full, &["fs:*"]), root);
+        assert!(guard.check(FsAccess::Write, path, root).is_ok());
+
+        assert!(FsGuard::default()
+            .check(FsAccess::Read, path, root)
+            .is_err());
+        assert!(FsGuard::unrestricted()
+            .check(FsAccess::Write, path, root)
+            .is_ok());
+    }
+}
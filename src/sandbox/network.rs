@@ -0,0 +1,258 @@
+//! Network allowlist checks
+
+use std::sync::Arc;
+
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::error::{LensError, Result};
+use crate::manifest::SecurityConfig;
+
+use super::prompt::{Approval, PermissionPrompter, PermissionStore};
+use super::AuditEvent;
+
+/// One granted host pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HostGrant {
+    /// `*`: any host
+    Any,
+    /// `api.figma.com`
+    Exact(String),
+    /// `*.figma.com`: any subdomain, stored as `.figma.com`
+    Subdomains(String),
+}
+
+impl HostGrant {
+    fn parse(scope: &str) -> Self {
+        let scope = normalize_host(scope);
+        match scope.strip_prefix('*') {
+            Some("") => Self::Any,
+            Some(suffix) if suffix.starts_with('.') => Self::Subdomains(suffix.to_string()),
+            _ => Self::Exact(scope),
+        }
+    }
+
+    fn covers(&self, host: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Exact(granted) => granted == host,
+            Self::Subdomains(suffix) => {
+                host.len() > suffix.len() && host.ends_with(suffix.as_str())
+            }
+        }
+    }
+}
+
+/// Enforces a lens's `network:<domain>` permissions.
+///
+/// A host is allowed when it equals a granted domain or, for grants like
+/// `*.figma.com`, is a subdomain of it (`figma.com` itself needs its own
+/// grant). Matching ignores case, ports, and a trailing dot. A guard grants
+/// nothing by default.
+///
+/// With [`with_prompter`](Self::with_prompter), hosts outside the grants
+/// are put to the user instead of refused, as is the first request of a
+/// lens declaring the `full` sandbox.
+#[derive(Debug, Clone, Default)]
+pub struct NetworkGuard {
+    lens: String,
+    grants: Vec<HostGrant>,
+    full_access: bool,
+    audit: Option<UnboundedSender<AuditEvent>>,
+    approval: Option<Arc<Approval>>,
+}
+
+impl NetworkGuard {
+    /// Allow every host; for hosts that do not sandbox lenses
+    pub fn unrestricted() -> Self {
+        Self {
+            grants: vec![HostGrant::Any],
+            ..Self::default()
+        }
+    }
+
+    /// Grants from `security`'s `network:` permissions for lens `lens`, or
+    /// none when its sandbox level does not permit network access
+    pub fn from_security(lens: impl Into<String>, security: &SecurityConfig) -> Self {
+        let grants = if security.sandbox.permits("network") {
+            security
+                .parsed_permissions()
+                .iter()
+                .filter(|permission| permission.permission_type == "network")
+                .map(|permission| HostGrant::parse(&permission.scope))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Self {
+            lens: lens.into(),
+            grants,
+            full_access: security.requires_full_access(),
+            ..Self::default()
+        }
+    }
+
+    /// Ask `prompter` about hosts outside the grants, remembering "always"
+    /// answers in `store` (builder pattern)
+    pub fn with_prompter(
+        mut self,
+        prompter: Arc<dyn PermissionPrompter>,
+        store: Arc<PermissionStore>,
+    ) -> Self {
+        self.approval = Some(Arc::new(Approval::new(self.lens.clone(), prompter, store)));
+        self
+    }
+
+    /// Report denied requests to `audit` (builder pattern)
+    pub fn with_audit(mut self, audit: UnboundedSender<AuditEvent>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Check a request to `host`
+    pub fn check_host(&self, host: &str) -> Result<()> {
+        let host = normalize_host(host);
+        let allowed = self.grants.iter().any(|grant| grant.covers(&host));
+        let approved = match &self.approval {
+            Some(approval) => {
+                approval.approves(allowed, self.full_access, &format!("network:{}", host))
+            }
+            None => allowed,
+        };
+        if approved {
+            return Ok(());
+        }
+        if let Some(audit) = &self.audit {
+            let _ = audit.send(AuditEvent::Denied {
+                lens: self.lens.clone(),
+                permission: format!("network:{}", host),
+            });
+        }
+        Err(LensError::PermissionDenied(format!(
+            "No network permission covers {}",
+            host
+        )))
+    }
+
+    /// Check a request to the host of `url`.
+    ///
+    /// URLs with credentials or backslashes are refused, since clients
+    /// disagree on where their host is.
+    pub fn check_url(&self, url: &str) -> Result<()> {
+        let host = url_host(url)?;
+        self.check_host(&host)
+    }
+}
+
+/// Lowercase `host` without its port or trailing dot
+fn normalize_host(host: &str) -> String {
+    let host = match host.strip_prefix('[') {
+        // IPv6 literal, possibly followed by a port
+        Some(rest) => rest.split(']').next().unwrap_or(rest),
+        None => host.split(':').next().unwrap_or(host),
+    };
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Host part of `url`
+fn url_host(url: &str) -> Result<String> {
+    let invalid =
+        |reason: &str| LensError::InvalidInput(format!("Cannot check URL {:?}: {}", url, reason));
+    if url.contains('\\') {
+        return Err(invalid("backslashes are not allowed"));
+    }
+    let parsed = url::Url::parse(url).map_err(|e| invalid(&e.to_string()))?;
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        return Err(invalid("credentials in the URL are not allowed"));
+    }
+    match parsed.host_str() {
+        Some(host) if !host.is_empty() => Ok(host.to_string()),
+        _ => Err(invalid("no host")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::SandboxLevel;
+    use tokio::sync::mpsc;
+
+    fn guard(sandbox: SandboxLevel, permissions: &[&str]) -> NetworkGuard {
+        let security = SecurityConfig {
+            library_hash: None,
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            sandbox,
+        };
+        NetworkGuard::from_security("figma", &security)
+    }
+
+    #[test]
+    fn test_network_guard_patterns() {
+        let guard = guard(
+            SandboxLevel::Network,
+            &[
+                "network:api.example.com",
+                "network:*.figma.com",
+                "fs:read:~/x",
+            ],
+        );
+
+        assert!(guard.check_host("api.example.com").is_ok());
+        assert!(guard.check_host("API.Example.com.").is_ok());
+        assert!(guard.check_host("api.example.com:8443").is_ok());
+        assert!(guard.check_host("evil.example.com").is_err());
+        assert!(guard.check_host("www.figma.com").is_ok());
+        assert!(guard.check_host("a.b.figma.com").is_ok());
+        assert!(guard.check_host("figma.com").is_err());
+        assert!(guard.check_host("notfigma.com").is_err());
+
+        assert!(guard.check_url("https://api.example.com/v1?q=1").is_ok());
+        assert!(guard.check_url("https://www.figma.com:443/file").is_ok());
+        assert!(guard.check_url("https://api.example.com.evil.io/").is_err());
+        assert!(guard.check_url("https://evil.io/?api.example.com").is_err());
+        for url in [
+            "not a url",
+            "https://user:pw@www.figma.com/file",
+            "https://api.example.com@evil.io/",
+            "https://evil.io\\@api.example.com/",
+            "https://api.example.com\\.evil.io/",
+        ] {
+            assert!(
+                matches!(guard.check_url(url), Err(LensError::InvalidInput(_))),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn test_network_guard_respects_sandbox_level() {
+        assert!(guard(SandboxLevel::Restricted, &["network:*"])
+            .check_host("example.com")
+            .is_err());
+        assert!(guard(SandboxLevel::Full, &["network:*"])
+            .check_host("example.com")
+            .is_ok());
+        assert!(NetworkGuard::default().check_host("example.com").is_err());
+        assert!(NetworkGuard::unrestricted()
+            .check_host("example.com")
+            .is_ok());
+    }
+
+    #[test]
+    fn test_network_guard_audits_denials() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let guard = guard(SandboxLevel::Network, &["network:api.figma.com"]).with_audit(tx);
+
+        guard.check_host("api.figma.com").unwrap();
+        guard.check_url("http://[::1]:8080/").unwrap_err();
+
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            AuditEvent::Denied {
+                lens: "figma".to_string(),
+                permission: "network:::1".to_string(),
+            }
+        );
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,333 @@
+//! Asking the user about access outside a lens's declared permissions
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::manifest::Permission;
+
+/// Permission string asked for before a `full` sandbox lens first touches
+/// the filesystem or network
+pub const FULL_ACCESS_PERMISSION: &str = "sandbox:full";
+
+/// Why a lens needs the user's approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptReason {
+    /// The access is not covered by the lens's declared permissions
+    Undeclared,
+    /// The lens declares the `full` sandbox, which needs explicit approval
+    FullAccess,
+}
+
+/// One question for the user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionRequest {
+    /// Lens asking for access
+    pub lens: String,
+    /// Permission in manifest syntax, e.g. `fs:read:/Users/me/notes.md`
+    pub permission: String,
+    pub reason: PromptReason,
+}
+
+/// The user's answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionDecision {
+    /// Allow for the rest of this run
+    AllowOnce,
+    /// Allow now and in later runs
+    AllowAlways,
+    Deny,
+}
+
+/// Host UI for approving access a lens did not declare.
+///
+/// Called synchronously from the guard check, on the thread running the
+/// lens; implementations may block until the user answers.
+pub trait PermissionPrompter: Send + Sync {
+    fn prompt(&self, request: &PermissionRequest) -> PermissionDecision;
+}
+
+/// Permissions users allowed "always", per lens, optionally persisted as
+/// JSON
+#[derive(Debug, Default)]
+pub struct PermissionStore {
+    path: Option<PathBuf>,
+    grants: Mutex<BTreeMap<String, BTreeSet<String>>>,
+}
+
+impl PermissionStore {
+    /// A store that forgets its grants when dropped
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Load grants from `path` (if it exists) and save changes back to it
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let grants = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            path: Some(path),
+            grants: Mutex::new(grants),
+        })
+    }
+
+    /// Permissions granted to `lens`, sorted
+    pub fn grants(&self, lens: &str) -> Vec<String> {
+        self.grants
+            .lock()
+            .unwrap()
+            .get(lens)
+            .map(|grants| grants.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether a stored grant for `lens` covers `permission`
+    pub fn is_granted(&self, lens: &str, permission: &str) -> bool {
+        let Some(required) = Permission::parse(permission) else {
+            return false;
+        };
+        self.grants(lens)
+            .iter()
+            .filter_map(|granted| Permission::parse(granted))
+            .any(|granted| granted.covers(&required))
+    }
+
+    /// Remember that `lens` may use `permission`
+    pub fn grant(&self, lens: &str, permission: &str) -> Result<()> {
+        let mut grants = self.grants.lock().unwrap();
+        grants
+            .entry(lens.to_string())
+            .or_default()
+            .insert(permission.to_string());
+        self.save(&grants)
+    }
+
+    /// Forget `permission` for `lens`, returning whether it was granted
+    pub fn revoke(&self, lens: &str, permission: &str) -> Result<bool> {
+        let mut grants = self.grants.lock().unwrap();
+        let removed = grants
+            .get_mut(lens)
+            .is_some_and(|granted| granted.remove(permission));
+        grants.retain(|_, granted| !granted.is_empty());
+        self.save(&grants)?;
+        Ok(removed)
+    }
+
+    fn save(&self, grants: &BTreeMap<String, BTreeSet<String>>) -> Result<()> {
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, serde_json::to_vec_pretty(grants)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// A guard's link to the prompter, with the answers given during this run
+pub(super) struct Approval {
+    lens: String,
+    prompter: Arc<dyn PermissionPrompter>,
+    store: Arc<PermissionStore>,
+    /// Permissions allowed once (for this guard's lifetime) or denied
+    answered: Mutex<BTreeMap<String, bool>>,
+}
+
+impl std::fmt::Debug for Approval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Approval")
+            .field("lens", &self.lens)
+            .field("store", &self.store)
+            .finish()
+    }
+}
+
+impl Approval {
+    pub(super) fn new(
+        lens: String,
+        prompter: Arc<dyn PermissionPrompter>,
+        store: Arc<PermissionStore>,
+    ) -> Self {
+        Self {
+            lens,
+            prompter,
+            store,
+            answered: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Whether access needing `permission` may go ahead, given whether the
+    /// lens declared it and whether the lens runs in the `full` sandbox
+    pub(super) fn approves(&self, declared: bool, full_access: bool, permission: &str) -> bool {
+        if !declared {
+            return self.allows(permission, PromptReason::Undeclared);
+        }
+        !full_access || self.allows(FULL_ACCESS_PERMISSION, PromptReason::FullAccess)
+    }
+
+    /// Whether the user allows `permission`, asking at most once per run
+    fn allows(&self, permission: &str, reason: PromptReason) -> bool {
+        if self.store.is_granted(&self.lens, permission) {
+            return true;
+        }
+        let mut answered = self.answered.lock().unwrap();
+        if let Some(allowed) = answered.get(permission) {
+            return *allowed;
+        }
+
+        let request = PermissionRequest {
+            lens: self.lens.clone(),
+            permission: permission.to_string(),
+            reason,
+        };
+        let allowed = match self.prompter.prompt(&request) {
+            PermissionDecision::AllowOnce => true,
+            PermissionDecision::AllowAlways => {
+                if let Err(e) = self.store.grant(&self.lens, permission) {
+                    eprintln!(
+                        "Warning: failed to save permission {} for lens '{}': {}",
+                        permission, self.lens, e
+                    );
+                }
+                true
+            }
+            PermissionDecision::Deny => false,
+        };
+        answered.insert(permission.to_string(), allowed);
+        allowed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{SandboxLevel, SecurityConfig};
+    use crate::sandbox::NetworkGuard;
+    use tempfile::tempdir;
+
+    /// Answers from a script and records every request
+    #[derive(Default)]
+    struct Scripted {
+        asked: Mutex<Vec<PermissionRequest>>,
+    }
+
+    impl PermissionPrompter for Scripted {
+        fn prompt(&self, request: &PermissionRequest) -> PermissionDecision {
+            self.asked.lock().unwrap().push(request.clone());
+            match request.permission.as_str() {
+                "network:cdn.example.com" | FULL_ACCESS_PERMISSION => PermissionDecision::AllowOnce,
+                "network:api.other.com" => PermissionDecision::AllowAlways,
+                _ => PermissionDecision::Deny,
+            }
+        }
+    }
+
+    fn guard(
+        sandbox: SandboxLevel,
+        prompter: &Arc<Scripted>,
+        store: &Arc<PermissionStore>,
+    ) -> NetworkGuard {
+        let security = SecurityConfig {
+            library_hash: None,
+            permissions: vec!["network:api.figma.com".to_string()],
+            sandbox,
+        };
+        NetworkGuard::from_security("figma", &security)
+            .with_prompter(prompter.clone(), store.clone())
+    }
+
+    #[test]
+    fn test_prompts_for_undeclared_access() {
+        let prompter = Arc::new(Scripted::default());
+        let store = Arc::new(PermissionStore::in_memory());
+        let guard = guard(SandboxLevel::Network, &prompter, &store);
+
+        for _ in 0..2 {
+            guard.check_host("api.figma.com").unwrap();
+            guard.check_host("cdn.example.com").unwrap();
+            guard.check_host("api.other.com").unwrap();
+            assert!(guard.check_host("evil.example.com").is_err());
+        }
+        let asked: Vec<_> = prompter
+            .asked
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| (request.permission.clone(), request.reason))
+            .collect();
+        assert_eq!(
+            asked,
+            [
+                (
+                    "network:cdn.example.com".to_string(),
+                    PromptReason::Undeclared
+                ),
+                (
+                    "network:api.other.com".to_string(),
+                    PromptReason::Undeclared
+                ),
+                (
+                    "network:evil.example.com".to_string(),
+                    PromptReason::Undeclared
+                ),
+            ]
+        );
+
+        // Only "always" outlives the run
+        let prompter = Arc::new(Scripted::default());
+        let guard = self::guard(SandboxLevel::Network, &prompter, &store);
+        guard.check_host("api.other.com").unwrap();
+        guard.check_host("cdn.example.com").unwrap();
+        assert_eq!(prompter.asked.lock().unwrap().len(), 1);
+        assert_eq!(store.grants("figma"), ["network:api.other.com"]);
+    }
+
+    #[test]
+    fn test_prompts_once_for_full_access() {
+        let prompter = Arc::new(Scripted::default());
+        let store = Arc::new(PermissionStore::in_memory());
+        let guard = guard(SandboxLevel::Full, &prompter, &store);
+
+        guard.check_host("api.figma.com").unwrap();
+        guard.check_host("api.figma.com").unwrap();
+        let asked = prompter.asked.lock().unwrap();
+        assert_eq!(asked.len(), 1);
+        assert_eq!(asked[0].permission, FULL_ACCESS_PERMISSION);
+        assert_eq!(asked[0].reason, PromptReason::FullAccess);
+    }
+
+    #[test]
+    fn test_permission_store_persists() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("state/permissions.json");
+
+        let store = PermissionStore::open(&path).unwrap();
+        store.grant("figma", "network:api.figma.com").unwrap();
+        store.grant("figma", "fs:read:/data/*").unwrap();
+        store.grant("figma", FULL_ACCESS_PERMISSION).unwrap();
+
+        let store = PermissionStore::open(&path).unwrap();
+        assert!(store.is_granted("figma", "network:api.figma.com"));
+        assert!(store.is_granted("figma", "fs:read:/data/notes.md"));
+        assert!(store.is_granted("figma", FULL_ACCESS_PERMISSION));
+        assert!(!store.is_granted("figma", "network:evil.com"));
+        assert!(!store.is_granted("notes", FULL_ACCESS_PERMISSION));
+
+        assert!(store.revoke("figma", FULL_ACCESS_PERMISSION).unwrap());
+        assert!(!store.revoke("figma", FULL_ACCESS_PERMISSION).unwrap());
+        let store = PermissionStore::open(&path).unwrap();
+        assert_eq!(
+            store.grants("figma"),
+            ["fs:read:/data/*", "network:api.figma.com"]
+        );
+    }
+}
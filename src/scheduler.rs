@@ -0,0 +1,461 @@
+//! # Lens Scheduler
+//!
+//! [`LensScheduler`] runs lenses on the `[[triggers]]` their manifests
+//! declare, submitting each run to a [`LensExecutor`] with the trigger's
+//! `input` and an [`Initiator::Schedule`] initiator:
+//!
+//! ```rust,ignore
+//! let mut scheduler = LensScheduler::new(executor.clone()).resume_from(last_shutdown);
+//! for lens in discovery.scan()? {
+//!     scheduler.add(registry.get(lens.id())?, &lens.manifest);
+//! }
+//!
+//! let (tx, mut runs) = mpsc::unbounded_channel();
+//! tokio::spawn(scheduler.run(tx));
+//! while let Some(run) = runs.recv().await {
+//!     history.record(run.lens_id, run.handle.await);
+//! }
+//! ```
+//!
+//! Schedules are evaluated on the UTC calendar, shifted by
+//! [`with_utc_offset`](LensScheduler::with_utc_offset) for hosts that want
+//! local times. Each trigger's [`MissedRunPolicy`] decides whether fire
+//! times that passed while the scheduler was not running (see
+//! [`resume_from`](LensScheduler::resume_from)) cause a catch-up run, and
+//! its [`OverlapPolicy`] whether a fire time reached while the previous run
+//! is still going starts another.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::error::Result;
+use crate::executor::{ExecutionHandle, LensExecutor};
+use crate::manifest::{LensManifest, LensTrigger, MissedRunPolicy, OverlapPolicy};
+use crate::profile::Initiator;
+use crate::{Lens, LensContext, LensResult};
+
+/// How late a fire time may be handled and still count as on time
+pub const DEFAULT_GRACE: Duration = Duration::from_secs(60);
+
+/// Submits trigger-declared runs to an executor as their fire times arrive
+#[derive(Debug)]
+pub struct LensScheduler {
+    executor: LensExecutor,
+    entries: Vec<Entry>,
+    cwd: PathBuf,
+    utc_offset: i64,
+    grace: Duration,
+    /// Fire times at or before this were handled before the scheduler started
+    resumed_from: Option<SystemTime>,
+    created: SystemTime,
+}
+
+struct Entry {
+    lens: Arc<dyn Lens>,
+    trigger: LensTrigger,
+    /// Fire times at or before this have been handled
+    checked: Option<SystemTime>,
+    /// Set while a run started by this trigger is going
+    running: Arc<AtomicBool>,
+}
+
+impl std::fmt::Debug for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Entry")
+            .field("lens", &self.lens.id())
+            .field("trigger", &self.trigger)
+            .field("checked", &self.checked)
+            .finish()
+    }
+}
+
+/// A run the scheduler submitted
+#[derive(Debug)]
+pub struct ScheduledRun {
+    /// Lens being run
+    pub lens_id: String,
+    /// Trigger name, or its cron expression when unnamed
+    pub trigger: String,
+    /// Fire time the run is for; earlier than now for catch-up runs
+    pub scheduled_for: SystemTime,
+    /// Whether the run catches up on missed fire times
+    pub catch_up: bool,
+    /// The submitted execution
+    pub handle: ExecutionHandle,
+}
+
+impl LensScheduler {
+    /// Scheduler submitting to `executor`, starting from now
+    pub fn new(executor: LensExecutor) -> Self {
+        Self {
+            executor,
+            entries: Vec::new(),
+            cwd: std::env::temp_dir(),
+            utc_offset: 0,
+            grace: DEFAULT_GRACE,
+            resumed_from: None,
+            created: SystemTime::now(),
+        }
+    }
+
+    /// Working directory of scheduled runs; the system temp directory by
+    /// default (builder pattern)
+    pub fn with_cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.cwd = cwd.into();
+        self
+    }
+
+    /// Evaluate schedules `offset_secs` ahead of UTC, e.g. `3600` for
+    /// UTC+1 (builder pattern)
+    pub fn with_utc_offset(mut self, offset_secs: i32) -> Self {
+        self.utc_offset = i64::from(offset_secs);
+        self
+    }
+
+    /// How late a fire time may be handled and still run as scheduled
+    /// rather than be treated as missed (builder pattern)
+    pub fn with_grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// Treat fire times after `last_active`, usually when the host last
+    /// shut down, as missed (builder pattern).
+    ///
+    /// Without it, the scheduler only considers fire times after it was
+    /// created.
+    pub fn resume_from(mut self, last_active: SystemTime) -> Self {
+        self.resumed_from = Some(last_active);
+        self
+    }
+
+    /// Schedule every trigger `manifest` declares for `lens`, returning how
+    /// many were added
+    pub fn add(&mut self, lens: Arc<dyn Lens>, manifest: &LensManifest) -> usize {
+        for trigger in &manifest.triggers {
+            self.add_trigger(Arc::clone(&lens), trigger.clone());
+        }
+        manifest.triggers.len()
+    }
+
+    /// Schedule a single trigger for `lens`
+    pub fn add_trigger(&mut self, lens: Arc<dyn Lens>, trigger: LensTrigger) {
+        self.entries.push(Entry {
+            lens,
+            trigger,
+            checked: None,
+            running: Arc::new(AtomicBool::new(false)),
+        });
+    }
+
+    /// Remove the triggers of lens `lens_id`
+    pub fn remove(&mut self, lens_id: &str) {
+        self.entries.retain(|entry| entry.lens.id() != lens_id);
+    }
+
+    /// Number of scheduled triggers
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no triggers are scheduled
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Earliest upcoming fire time across all triggers
+    pub fn next_fire(&self) -> Option<SystemTime> {
+        self.entries
+            .iter()
+            .filter_map(|entry| self.next_after(entry, entry.checked.unwrap_or(self.start())))
+            .min()
+    }
+
+    /// Submit the runs due at `now`, applying missed-run and overlap
+    /// policies
+    pub fn tick(&mut self, now: SystemTime) -> Vec<ScheduledRun> {
+        let start = self.start();
+        let mut runs = Vec::new();
+        for index in 0..self.entries.len() {
+            let entry = &self.entries[index];
+            let checked = entry.checked.unwrap_or(start);
+            let Some(due) = self
+                .last_at_or_before(entry, now)
+                .filter(|due| *due > checked)
+            else {
+                continue;
+            };
+            self.entries[index].checked = Some(now);
+
+            let entry = &self.entries[index];
+            let late = now.duration_since(due).unwrap_or_default() > self.grace;
+            if late && entry.trigger.missed == MissedRunPolicy::Skip {
+                continue;
+            }
+            if entry.running.load(Ordering::SeqCst) && entry.trigger.overlap == OverlapPolicy::Skip
+            {
+                eprintln!(
+                    "Warning: skipping scheduled run of '{}' ({}): previous run still going",
+                    entry.lens.id(),
+                    trigger_label(&entry.trigger)
+                );
+                continue;
+            }
+            runs.push(self.submit(entry, due, late));
+        }
+        runs
+    }
+
+    /// Submit runs as their fire times arrive, reporting each to `runs`,
+    /// until `runs` is closed
+    pub async fn run(mut self, runs: UnboundedSender<ScheduledRun>) {
+        loop {
+            for run in self.tick(SystemTime::now()) {
+                if runs.send(run).is_err() {
+                    return;
+                }
+            }
+            // Re-check at least once a minute so clock changes and system
+            // sleep are noticed
+            let wait = match self.next_fire() {
+                Some(next) => next
+                    .duration_since(SystemTime::now())
+                    .unwrap_or_default()
+                    .min(Duration::from_secs(60)),
+                None => Duration::from_secs(60),
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = runs.closed() => return,
+            }
+        }
+    }
+
+    fn submit(&self, entry: &Entry, due: SystemTime, catch_up: bool) -> ScheduledRun {
+        let label = trigger_label(&entry.trigger);
+        let ctx = LensContext::new(self.cwd.clone(), entry.trigger.input.clone()).with_initiator(
+            Initiator::Schedule {
+                schedule: format!("{}:{}", entry.lens.id(), label),
+            },
+        );
+
+        entry.running.store(true, Ordering::SeqCst);
+        let tracked = Arc::new(TrackedLens {
+            inner: Arc::clone(&entry.lens),
+            _running: RunningGuard(Arc::clone(&entry.running)),
+        });
+        ScheduledRun {
+            lens_id: entry.lens.id().to_string(),
+            trigger: label,
+            scheduled_for: due,
+            catch_up,
+            handle: self.executor.submit(tracked, ctx),
+        }
+    }
+
+    fn start(&self) -> SystemTime {
+        self.resumed_from.unwrap_or(self.created)
+    }
+
+    fn next_after(&self, entry: &Entry, time: SystemTime) -> Option<SystemTime> {
+        let local = shift(time, self.utc_offset)?;
+        shift(entry.trigger.schedule.next_after(local)?, -self.utc_offset)
+    }
+
+    fn last_at_or_before(&self, entry: &Entry, time: SystemTime) -> Option<SystemTime> {
+        let local = shift(time, self.utc_offset)?;
+        shift(
+            entry.trigger.schedule.last_at_or_before(local)?,
+            -self.utc_offset,
+        )
+    }
+}
+
+fn trigger_label(trigger: &LensTrigger) -> String {
+    trigger
+        .name
+        .clone()
+        .unwrap_or_else(|| trigger.schedule.expression().to_string())
+}
+
+fn shift(time: SystemTime, secs: i64) -> Option<SystemTime> {
+    let offset = Duration::from_secs(secs.unsigned_abs());
+    if secs >= 0 {
+        time.checked_add(offset)
+    } else {
+        time.checked_sub(offset)
+    }
+}
+
+/// Clears a trigger's running flag when its run ends
+struct RunningGuard(Arc<AtomicBool>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// The scheduled lens, holding its trigger's running flag for as long as
+/// the executor keeps the run alive
+struct TrackedLens {
+    inner: Arc<dyn Lens>,
+    _running: RunningGuard,
+}
+
+#[async_trait]
+impl Lens for TrackedLens {
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn version(&self) -> &str {
+        self.inner.version()
+    }
+
+    fn supports_mcp(&self) -> bool {
+        self.inner.supports_mcp()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+        self.inner.execute(ctx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    struct Digest;
+
+    #[async_trait]
+    impl Lens for Digest {
+        fn id(&self) -> &str {
+            "digest"
+        }
+
+        fn name(&self) -> &str {
+            "Digest"
+        }
+
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+
+        async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+            let secs = ctx.input["sleep_secs"].as_u64().unwrap_or(0);
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+            Ok(LensResult::success(ctx.input))
+        }
+    }
+
+    fn manifest(triggers: &str) -> LensManifest {
+        LensManifest::from_toml(&format!(
+            "[lens]\nid = \"digest\"\nname = \"Digest\"\nversion = \"1.0.0\"\n\n{}",
+            triggers
+        ))
+        .unwrap()
+    }
+
+    /// 2024-03-15 plus `hours` and `minutes`, UTC
+    fn at(hours: u64, minutes: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_710_460_800 + hours * 3600 + minutes * 60)
+    }
+
+    fn scheduler(triggers: &str) -> LensScheduler {
+        let executor = LensExecutor::new(4).with_coalescing(false);
+        let mut scheduler = LensScheduler::new(executor).resume_from(at(8, 0));
+        assert_eq!(scheduler.add(Arc::new(Digest), &manifest(triggers)), 1);
+        scheduler
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_fires_on_time() {
+        let mut scheduler = scheduler(
+            "[[triggers]]\ntype = \"cron\"\nschedule = \"0 9 * * *\"\n\
+             name = \"Morning\"\ninput = { period = \"daily\" }\n",
+        );
+        assert_eq!(scheduler.next_fire(), Some(at(9, 0)));
+        assert!(scheduler.tick(at(8, 59)).is_empty());
+
+        let mut runs = scheduler.tick(at(9, 0));
+        assert_eq!(runs.len(), 1);
+        let run = runs.pop().unwrap();
+        assert_eq!(run.trigger, "Morning");
+        assert_eq!(run.scheduled_for, at(9, 0));
+        assert!(!run.catch_up);
+        let result = run.handle.await.unwrap();
+        assert_eq!(result.output["period"], "daily");
+
+        // Already handled
+        assert!(scheduler.tick(at(9, 0)).is_empty());
+        assert_eq!(scheduler.next_fire(), Some(at(33, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_missed_runs() {
+        let trigger = "[[triggers]]\ntype = \"cron\"\nschedule = \"*/10 * * * *\"\n";
+
+        // Woke up at 10:05, long after fire times from 08:10 on
+        let mut skipping = scheduler(trigger);
+        assert!(skipping.tick(at(10, 5)).is_empty());
+        assert_eq!(skipping.tick(at(10, 10)).len(), 1);
+
+        let mut catching_up = scheduler(&format!("{}missed = \"run_once\"\n", trigger));
+        let runs = catching_up.tick(at(10, 5));
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0].catch_up);
+        assert_eq!(runs[0].scheduled_for, at(10, 0));
+        assert!(catching_up.tick(at(10, 6)).is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_overlap_policy() {
+        let trigger = "[[triggers]]\ntype = \"cron\"\nschedule = \"* * * * *\"\n\
+                       input = { sleep_secs = 90 }\n";
+
+        let mut skipping = scheduler(trigger);
+        let first = skipping.tick(at(9, 0));
+        assert_eq!(first.len(), 1);
+        assert!(skipping.tick(at(9, 1)).is_empty());
+        for run in first {
+            run.handle.await.unwrap();
+        }
+        assert_eq!(skipping.tick(at(9, 2)).len(), 1);
+
+        let mut allowing = scheduler(&format!("{}overlap = \"allow\"\n", trigger));
+        assert_eq!(allowing.tick(at(9, 0)).len(), 1);
+        assert_eq!(allowing.tick(at(9, 1)).len(), 1);
+    }
+
+    #[test]
+    fn test_scheduler_utc_offset() {
+        let executor = LensExecutor::new(1);
+        let mut scheduler = LensScheduler::new(executor)
+            .resume_from(at(0, 0))
+            .with_utc_offset(2 * 3600);
+        scheduler.add(
+            Arc::new(Digest),
+            &manifest("[[triggers]]\ntype = \"cron\"\nschedule = \"0 9 * * *\"\n"),
+        );
+        // 09:00 at UTC+2 is 07:00 UTC
+        assert_eq!(scheduler.next_fire(), Some(at(7, 0)));
+        assert_eq!(scheduler.len(), 1);
+        scheduler.remove("digest");
+        assert!(scheduler.is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+//! # JSON Schema Validation
+//!
+//! Lightweight validator for the JSON Schema subset used by lens manifests,
+//! output specs, and MCP tool inputs: `type`, `properties`, `required`,
+//! `additionalProperties`, `items`, `enum`, `const`, numeric bounds, and
+//! string/array length bounds.
+//!
+//! Violations are reported with JSON pointers into the validated value.
+//!
+//! With the `json-schema` feature, validation is delegated to the `jsonschema`
+//! crate for full draft support (`$ref`, `oneOf`, `pattern`, formats, ...).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A single schema violation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value ("" for the root)
+    pub pointer: String,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(f, "{}: {}", pointer, self.message)
+    }
+}
+
+/// Validate `value` against `schema`, returning every violation found.
+pub fn validate(schema: &Value, value: &Value) -> Vec<SchemaViolation> {
+    #[cfg(feature = "json-schema")]
+    {
+        validate_full(schema, value)
+    }
+    #[cfg(not(feature = "json-schema"))]
+    {
+        validate_builtin(schema, value)
+    }
+}
+
+/// Check that `schema` itself is a well-formed JSON Schema.
+///
+/// Only the `json-schema` feature can detect malformed schemas; the built-in
+/// validator ignores keywords it doesn't understand.
+pub fn check_schema(schema: &Value) -> std::result::Result<(), String> {
+    #[cfg(feature = "json-schema")]
+    {
+        jsonschema::validator_for(schema)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+    #[cfg(not(feature = "json-schema"))]
+    {
+        let _ = schema;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "json-schema")]
+fn validate_full(schema: &Value, value: &Value) -> Vec<SchemaViolation> {
+    match jsonschema::validator_for(schema) {
+        Ok(validator) => validator
+            .iter_errors(value)
+            .map(|error| SchemaViolation {
+                pointer: error.instance_path.to_string(),
+                message: error.to_string(),
+            })
+            .collect(),
+        Err(e) => vec![SchemaViolation {
+            pointer: String::new(),
+            message: format!("invalid schema: {}", e),
+        }],
+    }
+}
+
+#[cfg_attr(feature = "json-schema", allow(dead_code))]
+fn validate_builtin(schema: &Value, value: &Value) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    validate_at(schema, value, "", &mut violations);
+    violations
+}
+
+/// Join violations into a single message suitable for `LensError::InvalidInput`.
+pub fn describe(violations: &[SchemaViolation]) -> String {
+    violations
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Escape a property name for use as a JSON pointer segment.
+pub fn escape_pointer_segment(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+fn validate_at(schema: &Value, value: &Value, pointer: &str, out: &mut Vec<SchemaViolation>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    let mut push = |message: String| {
+        out.push(SchemaViolation {
+            pointer: pointer.to_string(),
+            message,
+        })
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| matches_type(t, value)) {
+            push(format!(
+                "expected {}, got {}",
+                types.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            push(format!(
+                "value {} is not one of {}",
+                value,
+                Value::Array(allowed.clone())
+            ));
+        }
+    }
+
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            push(format!("expected constant {}", expected));
+        }
+    }
+
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(|m| m.as_f64()) {
+            if n < min {
+                push(format!("{} is less than minimum {}", n, min));
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(|m| m.as_f64()) {
+            if n > max {
+                push(format!("{} is greater than maximum {}", n, max));
+            }
+        }
+    }
+
+    if let Some(s) = value.as_str() {
+        let len = s.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(|m| m.as_u64()) {
+            if len < min {
+                push(format!("string shorter than minLength {}", min));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(|m| m.as_u64()) {
+            if len > max {
+                push(format!("string longer than maxLength {}", max));
+            }
+        }
+    }
+
+    if let Some(items) = value.as_array() {
+        let len = items.len() as u64;
+        if let Some(min) = schema.get("minItems").and_then(|m| m.as_u64()) {
+            if len < min {
+                push(format!("array has fewer than minItems {}", min));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(|m| m.as_u64()) {
+            if len > max {
+                push(format!("array has more than maxItems {}", max));
+            }
+        }
+        if let Some(item_schema) = schema.get("items") {
+            for (index, item) in items.iter().enumerate() {
+                validate_at(item_schema, item, &format!("{}/{}", pointer, index), out);
+            }
+        }
+    }
+
+    if let Some(obj) = value.as_object() {
+        if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+            for field in required.iter().filter_map(|f| f.as_str()) {
+                if !obj.contains_key(field) {
+                    out.push(SchemaViolation {
+                        pointer: pointer.to_string(),
+                        message: format!("missing required field '{}'", field),
+                    });
+                }
+            }
+        }
+
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+        for (key, child) in obj {
+            let child_pointer = format!("{}/{}", pointer, escape_pointer_segment(key));
+            match properties.and_then(|p| p.get(key)) {
+                Some(child_schema) => validate_at(child_schema, child, &child_pointer, out),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => out.push(SchemaViolation {
+                        pointer: child_pointer,
+                        message: format!("unexpected property '{}'", key),
+                    }),
+                    Some(extra @ Value::Object(_)) => {
+                        validate_at(extra, child, &child_pointer, out)
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_valid_value_has_no_violations() {
+        let schema = json!({
+            "type": "object",
+            "required": ["query"],
+            "properties": {
+                "query": { "type": "string", "minLength": 1 },
+                "limit": { "type": "integer", "minimum": 1, "maximum": 100 }
+            }
+        });
+
+        assert!(validate_builtin(&schema, &json!({"query": "button", "limit": 10})).is_empty());
+    }
+
+    #[test]
+    fn test_reports_all_violations_with_pointers() {
+        let schema = json!({
+            "type": "object",
+            "required": ["query", "mode"],
+            "additionalProperties": false,
+            "properties": {
+                "query": { "type": "string" },
+                "limit": { "type": "integer", "maximum": 100 },
+                "tags": { "type": "array", "items": { "enum": ["a", "b"] } }
+            }
+        });
+        let value = json!({"query": 5, "limit": 500, "tags": ["a", "c"], "extra": true});
+
+        let violations = validate_builtin(&schema, &value);
+        let pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+
+        assert_eq!(violations.len(), 5);
+        assert!(pointers.contains(&""));
+        assert!(pointers.contains(&"/query"));
+        assert!(pointers.contains(&"/limit"));
+        assert!(pointers.contains(&"/tags/1"));
+        assert!(pointers.contains(&"/extra"));
+    }
+
+    #[test]
+    fn test_type_mismatch_stops_descent() {
+        let schema = json!({"type": "object", "required": ["a"]});
+        let violations = validate_builtin(&schema, &json!("nope"));
+
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].message, "expected object, got string");
+    }
+
+    #[test]
+    fn test_pointer_segments_are_escaped() {
+        let schema = json!({"properties": {"a/b": {"type": "string"}}});
+        let violations = validate_builtin(&schema, &json!({"a/b": 1}));
+
+        assert_eq!(violations[0].pointer, "/a~1b");
+        assert_eq!(describe(&violations), "/a~1b: expected string, got number");
+    }
+
+    #[test]
+    fn test_validate_reports_nested_violations() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "rows": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["id"],
+                        "properties": { "status": { "enum": ["open", "closed"] } }
+                    }
+                }
+            }
+        });
+        let violations = validate(&schema, &json!({"rows": [{"id": 1}, {"status": "lost"}]}));
+        let pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+
+        assert_eq!(violations.len(), 2);
+        assert!(pointers
+            .iter()
+            .all(|p| *p == "/rows/1" || *p == "/rows/1/status"));
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[test]
+    fn test_full_validator_keywords() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "code": { "type": "string", "pattern": "^[A-Z]{3}$" } }
+        });
+        let violations = validate(&schema, &json!({"code": "usd"}));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].pointer, "/code");
+
+        assert!(check_schema(&json!({"type": "not-a-type"})).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+//! # Publisher Verification
+//!
+//! Checks that a lens package comes from a trusted publisher: the
+//! manifest's `[signature]` must be valid, come from a trusted key, and pin
+//! the exact library bytes being installed or loaded.
+//!
+//! Trusted keys can be pinned to lens ids in a `trusted_keys.toml`, so
+//! first-party lenses are only accepted from the first-party key even when
+//! other publishers' keys are trusted:
+//!
+//! ```toml
+//! # Trusted for lenses no pin below matches
+//! [[key]]
+//! name = "community"
+//! public_key = "<64 hex chars>"
+//!
+//! # The only key accepted for these lenses
+//! [[key]]
+//! name = "fuego"
+//! public_key = "<64 hex chars>"
+//! lenses = ["figma", "fuego-*"]
+//! ```
+//!
+//! Verification requires the `signing` feature.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{LensError, Result};
+#[cfg(feature = "signing")]
+use crate::manifest::LensManifest;
+
+/// Conventional name of a key pin file
+pub const TRUSTED_KEYS_FILENAME: &str = "trusted_keys.toml";
+
+/// Trusted publisher keys, optionally pinned to lens ids
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyPins {
+    #[serde(default, rename = "key")]
+    pub keys: Vec<PinnedKey>,
+}
+
+/// One trusted publisher key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinnedKey {
+    /// Label for humans and error messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Hex-encoded ed25519 public key
+    pub public_key: String,
+    /// Lens ids only this key (and other keys pinned to them) may sign; a
+    /// trailing `*` matches a prefix. Empty trusts the key for any lens no
+    /// pin matches.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lenses: Vec<String>,
+}
+
+impl PinnedKey {
+    fn pins(&self, lens_id: &str) -> bool {
+        self.lenses
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => lens_id.starts_with(prefix),
+                None => pattern == lens_id,
+            })
+    }
+}
+
+impl From<Vec<String>> for KeyPins {
+    /// Trust each key for any lens
+    fn from(public_keys: Vec<String>) -> Self {
+        public_keys
+            .into_iter()
+            .fold(Self::default(), |pins, key| pins.trust(key))
+    }
+}
+
+impl KeyPins {
+    /// Parse a `trusted_keys.toml`
+    pub fn from_toml(content: &str) -> Result<Self> {
+        toml::from_str(content)
+            .map_err(|e| LensError::InvalidInput(format!("Invalid trusted keys: {}", e)))
+    }
+
+    /// Read a `trusted_keys.toml`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Trust `public_key` for any lens no pin matches
+    pub fn trust(mut self, public_key: impl Into<String>) -> Self {
+        self.keys.push(PinnedKey {
+            name: None,
+            public_key: public_key.into(),
+            lenses: Vec::new(),
+        });
+        self
+    }
+
+    /// Accept only `public_key` (and other keys pinned to the same ids) for
+    /// the lenses matching `lenses`
+    pub fn pin(mut self, public_key: impl Into<String>, lenses: Vec<String>) -> Self {
+        self.keys.push(PinnedKey {
+            name: None,
+            public_key: public_key.into(),
+            lenses,
+        });
+        self
+    }
+
+    /// Keys trusted to sign lens `lens_id`: those pinned to it if any,
+    /// otherwise the unpinned keys.
+    ///
+    /// Fails with `PermissionDenied` when no key is trusted for the lens,
+    /// so a lens nobody vouches for is never accepted.
+    pub fn keys_for(&self, lens_id: &str) -> Result<Vec<String>> {
+        let pinned: Vec<String> = self
+            .keys
+            .iter()
+            .filter(|key| key.pins(lens_id))
+            .map(|key| key.public_key.clone())
+            .collect();
+        if !pinned.is_empty() {
+            return Ok(pinned);
+        }
+        let unpinned: Vec<String> = self
+            .keys
+            .iter()
+            .filter(|key| key.lenses.is_empty())
+            .map(|key| key.public_key.clone())
+            .collect();
+        if unpinned.is_empty() {
+            return Err(LensError::PermissionDenied(format!(
+                "No trusted key for lens '{}'",
+                lens_id
+            )));
+        }
+        Ok(unpinned)
+    }
+}
+
+/// Verify that `library_bytes` is the library `manifest` was signed for.
+///
+/// The `[signature]` must be valid and made by one of `trusted_keys` (none
+/// is accepted when empty), and the signed `[security].library_hash` must match
+/// the SHA-256 of `library_bytes`.
+#[cfg(feature = "signing")]
+pub fn verify_signature(
+    manifest: &LensManifest,
+    library_bytes: &[u8],
+    trusted_keys: &[String],
+) -> Result<()> {
+    use sha2::{Digest, Sha256};
+
+    manifest.verify_signature(trusted_keys)?;
+
+    let declared = manifest
+        .security
+        .as_ref()
+        .and_then(|security| security.library_hash.as_deref())
+        .ok_or_else(|| {
+            LensError::InvalidInput(format!(
+                "Signed lens '{}' ships a library without [security].library_hash",
+                manifest.lens.id
+            ))
+        })?;
+    let actual = format!("sha256:{:x}", Sha256::digest(library_bytes));
+    if actual != declared {
+        return Err(LensError::InvalidInput(format!(
+            "Library for lens '{}' does not match its signed library_hash",
+            manifest.lens.id
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_pins() {
+        let pins = KeyPins::from_toml(
+            r#"
+[[key]]
+name = "community"
+public_key = "aa"
+
+[[key]]
+name = "fuego"
+public_key = "ff"
+lenses = ["figma", "fuego-*"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(pins.keys[1].name.as_deref(), Some("fuego"));
+
+        assert_eq!(pins.keys_for("figma").unwrap(), ["ff"]);
+        assert_eq!(pins.keys_for("fuego-notes").unwrap(), ["ff"]);
+        assert_eq!(pins.keys_for("figma-clone").unwrap(), ["aa"]);
+        assert_eq!(pins.keys_for("weather").unwrap(), ["aa"]);
+
+        let pins = KeyPins::from(vec!["aa".to_string()]).pin("ff", vec!["figma".to_string()]);
+        assert_eq!(pins.keys_for("figma").unwrap(), ["ff"]);
+        assert_eq!(pins.keys_for("weather").unwrap(), ["aa"]);
+
+        // No key vouches for the lens: rejected rather than open to any signer
+        let err = KeyPins::default().keys_for("weather").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("No trusted key for lens 'weather'"));
+        let pinned_only = KeyPins::default().pin("ff", vec!["figma".to_string()]);
+        assert!(pinned_only.keys_for("weather").is_err());
+
+        assert!(KeyPins::from_toml("[[key]]\nname = \"no key\"\n").is_err());
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn test_verify_signature_checks_library_bytes() {
+        use ed25519_dalek::SigningKey;
+        use sha2::{Digest, Sha256};
+
+        let key = SigningKey::from_bytes(&[3; 32]);
+        let public_key: String = key
+            .verifying_key()
+            .as_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let mut manifest = LensManifest::from_toml(&format!(
+            "[lens]\nid = \"figma\"\nname = \"Figma\"\nversion = \"1.0.0\"\nmanifest_version = 2\n\n[security]\nlibrary_hash = \"sha256:{:x}\"\n",
+            Sha256::digest(b"library")
+        ))
+        .unwrap();
+        manifest.sign(&key).unwrap();
+
+        let trusted = [public_key];
+        verify_signature(&manifest, b"library", &trusted).unwrap();
+        let err = verify_signature(&manifest, b"library", &[]).unwrap_err();
+        assert!(err.to_string().contains("no trusted keys"));
+        let err = verify_signature(&manifest, b"swapped", &trusted).unwrap_err();
+        assert!(err.to_string().contains("does not match"));
+        let err = verify_signature(&manifest, b"library", &["ff".repeat(32)]).unwrap_err();
+        assert!(err.to_string().contains("untrusted key"));
+
+        let mut unpinned = manifest.clone();
+        unpinned.security.as_mut().unwrap().library_hash = None;
+        unpinned.sign(&key).unwrap();
+        let err = verify_signature(&unpinned, b"library", &trusted).unwrap_err();
+        assert!(err.to_string().contains("without [security].library_hash"));
+    }
+}
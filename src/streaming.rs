@@ -1,12 +1,59 @@
 use async_trait::async_trait;
+use serde::Serialize;
 use std::pin::Pin;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::Stream;
 
-use crate::{Lens, LensContext, LensEvent, LensResult, Result};
+use crate::{Lens, LensContext, LensError, LensEvent, LensResult, Result};
 
 /// Event stream type
 pub type LensEventStream = Pin<Box<dyn Stream<Item = LensEvent> + Send>>;
 
+/// Sending half of a lens event stream, bound to a lens id.
+///
+/// Payload helpers generated by `output_spec::codegen` emit through this type.
+#[derive(Debug, Clone)]
+pub struct EventEmitter {
+    lens_id: String,
+    tx: mpsc::Sender<LensEvent>,
+}
+
+impl EventEmitter {
+    /// Wrap an existing channel sender
+    pub fn new(lens_id: impl Into<String>, tx: mpsc::Sender<LensEvent>) -> Self {
+        Self {
+            lens_id: lens_id.into(),
+            tx,
+        }
+    }
+
+    /// Create an emitter and the stream it feeds
+    pub fn channel(lens_id: impl Into<String>, buffer: usize) -> (Self, LensEventStream) {
+        let (tx, rx) = mpsc::channel(buffer);
+        (Self::new(lens_id, tx), Box::pin(ReceiverStream::new(rx)))
+    }
+
+    /// Lens id attached to emitted events
+    pub fn lens_id(&self) -> &str {
+        &self.lens_id
+    }
+
+    /// Send an event
+    pub async fn emit(&self, event: LensEvent) -> Result<()> {
+        self.tx
+            .send(event)
+            .await
+            .map_err(|_| LensError::StreamError("event stream closed".to_string()))
+    }
+
+    /// Serialize `payload` and send it as a `Data` event under `key`
+    pub async fn data<T: Serialize + ?Sized>(&self, key: &str, payload: &T) -> Result<()> {
+        let value = serde_json::to_value(payload)?;
+        self.emit(LensEvent::data(&self.lens_id, key, value)).await
+    }
+}
+
 /// Lens trait with streaming support for observable execution.
 ///
 /// Implement this trait for lenses that emit progress events during execution.
@@ -187,6 +234,25 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_event_emitter_data() {
+        let (emitter, mut stream) = EventEmitter::channel("emitter", 4);
+        emitter.data("result", &json!({"ok": true})).await.unwrap();
+        drop(emitter);
+
+        match stream.next().await.unwrap() {
+            LensEvent::Data {
+                lens, key, value, ..
+            } => {
+                assert_eq!(lens, "emitter");
+                assert_eq!(key, "result");
+                assert_eq!(value, json!({"ok": true}));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(stream.next().await.is_none());
+    }
+
     #[tokio::test]
     async fn test_streaming_task_emits_events() {
         let lens = TestStreamingLens;
@@ -0,0 +1,765 @@
+//! # Subprocess Lenses
+//!
+//! [`SubprocessLens`] runs a lens as a child process speaking the stdio
+//! protocol in `docs/SUBPROCESS_PROTOCOL_V1.md`, so a lens can be any
+//! executable and a crash only takes down its own run.
+//!
+//! Each execution spawns the program, writes one execute request line to
+//! stdin, and reads event lines until a result or error line arrives.
+//! Streaming runs forward each event as soon as it is read, and cancelling
+//! `ctx.cancellation` kills the program.
+//!
+//! ```rust,ignore
+//! let discovered = discovery.get_lens("word-count")?.unwrap();
+//! let lens = SubprocessLens::from_discovered(&discovered)?;
+//! let result = lens.execute(ctx).await?;
+//! ```
+//!
+//! Lenses created with [`SubprocessLens::from_discovered`] run inside an
+//! [`OsSandbox`] built from their `[security]` section.
+
+mod os_sandbox;
+
+pub use os_sandbox::OsSandbox;
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use crate::discovery::DiscoveredLens;
+use crate::error::{LensError, Result};
+use crate::manifest::{LensEntryType, SandboxLevel, SecurityConfig};
+use crate::streaming::{LensEventStream, StreamingLens};
+use crate::{Lens, LensContext, LensEvent, LensResult};
+
+/// Protocol version passed to the lens as `LENS_PROTOCOL_VERSION`
+pub const SUBPROCESS_PROTOCOL_VERSION: u32 = 1;
+
+/// `Data` key of the event carrying a streaming run's final result
+pub const RESULT_EVENT_KEY: &str = "result";
+
+/// How much of the lens's stderr is kept for error messages
+const STDERR_TAIL_BYTES: usize = 4096;
+
+/// How long a lens may take to exit after sending its result
+const EXIT_GRACE: Duration = Duration::from_secs(5);
+
+/// How often a memory-limited lens's peak address space is sampled
+#[cfg(target_os = "linux")]
+const PEAK_SAMPLE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Share of the memory limit a lens must have reached for a signal death
+/// to count as hitting it
+#[cfg(unix)]
+const NEAR_LIMIT_PERCENT: u64 = 90;
+
+/// stderr fragments runtimes print when an allocation fails
+#[cfg(unix)]
+const ALLOCATION_FAILURES: &[&str] = &[
+    "memory allocation of",
+    "out of memory",
+    "cannot allocate memory",
+    "bad_alloc",
+    "memoryerror",
+];
+
+/// One line of the subprocess protocol
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SubprocessMessage {
+    /// Host → lens: run with this context
+    Execute { context: Box<LensContext> },
+    /// Lens → host: an event emitted during the run
+    Event { event: LensEvent },
+    /// Lens → host: the run finished
+    Result { result: LensResult },
+    /// Lens → host: the run failed
+    Error { message: String },
+}
+
+/// A lens backed by an executable speaking the subprocess protocol
+#[derive(Debug, Clone)]
+pub struct SubprocessLens {
+    program: PathBuf,
+    args: Vec<String>,
+    working_dir: Option<PathBuf>,
+    timeout: Option<Duration>,
+    max_memory: Option<u64>,
+    os_sandbox: Option<OsSandbox>,
+    id: String,
+    name: String,
+    version: String,
+    description: String,
+}
+
+impl SubprocessLens {
+    /// Create a lens that runs `program`
+    pub fn new(
+        program: impl Into<PathBuf>,
+        id: impl Into<String>,
+        name: impl Into<String>,
+        version: impl Into<String>,
+    ) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            working_dir: None,
+            timeout: None,
+            max_memory: None,
+            os_sandbox: None,
+            id: id.into(),
+            name: name.into(),
+            version: version.into(),
+            description: String::new(),
+        }
+    }
+
+    /// Create a lens from a discovered `subprocess` entry.
+    ///
+    /// The program runs in the lens directory, bounded by the manifest's
+    /// `limits.max_execution_secs` and `limits.max_memory_mb`, and confined
+    /// by an [`OsSandbox`] derived from its `[security]` section (restricted
+    /// when absent). A declared `[security]` section makes the sandbox
+    /// [required](OsSandbox::required): where the platform cannot enforce
+    /// it, the lens fails to start instead of running unconfined.
+    pub fn from_discovered(lens: &DiscoveredLens) -> Result<Self> {
+        if lens.entry_type() != LensEntryType::Subprocess {
+            return Err(LensError::Initialization(format!(
+                "Lens '{}' declares a {:?} entry point, not a subprocess",
+                lens.id(),
+                lens.entry_type()
+            )));
+        }
+        let program = lens.entry_path.as_ref().ok_or_else(|| {
+            LensError::LensNotFound(format!("No executable found for lens '{}'", lens.id()))
+        })?;
+
+        let mut subprocess = Self::new(program, lens.id(), lens.name(), lens.version())
+            .with_working_dir(&lens.path)
+            .with_description(lens.manifest.lens.description.clone());
+        let limits = lens.manifest.resource_limits();
+        subprocess.timeout = limits.max_execution_time();
+        subprocess.max_memory = limits.max_memory_bytes();
+        let declared = lens.manifest.security.is_some();
+        let security = lens.manifest.security.clone().unwrap_or(SecurityConfig {
+            library_hash: None,
+            permissions: Vec::new(),
+            sandbox: SandboxLevel::Restricted,
+        });
+        if declared && !OsSandbox::is_supported() {
+            eprintln!(
+                "Warning: Lens '{}': OS sandboxing is not supported on this system; \
+                 its [security] restrictions cannot be enforced, so it will not run",
+                lens.id()
+            );
+        }
+        subprocess.os_sandbox =
+            Some(OsSandbox::from_security(&security, &lens.path).required(declared));
+        Ok(subprocess)
+    }
+
+    /// Extra command-line arguments for the program (builder pattern)
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Directory the program runs in (builder pattern)
+    pub fn with_working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// Kill the program if a run takes longer than `timeout` (builder pattern)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Cap the program's address space at `bytes` (builder pattern).
+    ///
+    /// Enforced with `RLIMIT_AS` on Unix; ignored elsewhere.
+    pub fn with_memory_limit(mut self, bytes: u64) -> Self {
+        self.max_memory = Some(bytes);
+        self
+    }
+
+    /// Confine the program with `sandbox` (builder pattern)
+    pub fn with_os_sandbox(mut self, sandbox: OsSandbox) -> Self {
+        self.os_sandbox = Some(sandbox);
+        self
+    }
+
+    /// Description reported by [`Lens::description`] (builder pattern)
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Start the program for one run
+    fn spawn(&self) -> Result<Child> {
+        let mut command = match &self.os_sandbox {
+            Some(sandbox) => sandbox.command(&self.program, &self.args)?,
+            None => {
+                let mut command = Command::new(&self.program);
+                command.args(&self.args);
+                command
+            }
+        };
+        command
+            .env(
+                "LENS_PROTOCOL_VERSION",
+                SUBPROCESS_PROTOCOL_VERSION.to_string(),
+            )
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(dir) = &self.working_dir {
+            command.current_dir(dir);
+        }
+        #[cfg(unix)]
+        if let Some(bytes) = self.max_memory {
+            let limit = libc::rlimit {
+                rlim_cur: bytes as libc::rlim_t,
+                rlim_max: bytes as libc::rlim_t,
+            };
+            // SAFETY: setrlimit is async-signal-safe and touches no memory
+            // shared with the parent
+            unsafe {
+                command.pre_exec(move || {
+                    if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+        }
+        command.spawn().map_err(|e| {
+            LensError::Initialization(format!(
+                "Failed to start lens '{}' ({:?}): {}",
+                self.id, self.program, e
+            ))
+        })
+    }
+
+    /// Hand `ctx` to a started program and read its messages until the
+    /// result, forwarding events to `events` as they arrive.
+    ///
+    /// Cancelling `ctx.cancellation` kills the program.
+    async fn exchange(
+        &self,
+        mut child: Child,
+        ctx: LensContext,
+        events: Option<&mpsc::UnboundedSender<LensEvent>>,
+    ) -> Result<LensResult> {
+        let cancel = ctx.cancellation.clone();
+        let request = serde_json::to_string(&SubprocessMessage::Execute {
+            context: Box::new(ctx),
+        })?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+        let stderr = tokio::spawn(stderr_tail(child.stderr.take().expect("stderr is piped")));
+        let peak = PeakSampler::start(self.max_memory.and(child.id()));
+
+        let exchange = async {
+            // A lens may exit without reading its input; that surfaces below
+            // as a missing result rather than a broken pipe
+            if stdin.write_all(request.as_bytes()).await.is_ok() {
+                let _ = stdin.write_all(b"\n").await;
+            }
+            drop(stdin);
+
+            while let Some(line) = stdout.next_line().await? {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str(&line) {
+                    Ok(SubprocessMessage::Event { event }) => {
+                        if let Some(events) = events {
+                            let _ = events.send(event);
+                        }
+                    }
+                    Ok(SubprocessMessage::Result { result }) => return Ok(Some(Ok(result))),
+                    Ok(SubprocessMessage::Error { message }) => return Ok(Some(Err(message))),
+                    Ok(SubprocessMessage::Execute { .. }) | Err(_) => {
+                        return Err(LensError::ExecutionFailed(format!(
+                            "Lens '{}' wrote an invalid protocol line: {}",
+                            self.id, line
+                        )))
+                    }
+                }
+            }
+            Ok(None)
+        };
+        let bounded = async {
+            match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, exchange).await.map_err(|_| {
+                    LensError::ResourceLimitExceeded(format!(
+                        "Lens '{}' timed out after {:?}",
+                        self.id, timeout
+                    ))
+                })?,
+                None => exchange.await,
+            }
+        };
+        let outcome = tokio::select! {
+            outcome = bounded => outcome?,
+            _ = cancel.cancelled() => {
+                let _ = child.start_kill();
+                return Err(LensError::ExecutionFailed(format!(
+                    "Lens '{}' was cancelled",
+                    self.id
+                )));
+            }
+        };
+
+        match outcome {
+            Some(Ok(result)) => {
+                if tokio::time::timeout(EXIT_GRACE, child.wait())
+                    .await
+                    .is_err()
+                {
+                    eprintln!(
+                        "Warning: lens '{}' did not exit after sending its result; killing it",
+                        self.id
+                    );
+                }
+                Ok(result)
+            }
+            Some(Err(message)) => Err(LensError::ExecutionFailed(message)),
+            None => {
+                let status = child.wait().await?;
+                let stderr = stderr.await.unwrap_or_default();
+                if let Some(error) = self.memory_exceeded(&status, &stderr, peak.stop()) {
+                    return Err(error);
+                }
+                Err(LensError::ExecutionFailed(format!(
+                    "Lens '{}' exited ({}) without a result{}",
+                    self.id,
+                    status,
+                    if stderr.is_empty() {
+                        String::new()
+                    } else {
+                        format!(": {}", stderr.trim())
+                    }
+                )))
+            }
+        }
+    }
+
+    /// Whether a run that ended without a result hit its memory limit.
+    ///
+    /// Allocations past `RLIMIT_AS` fail, so a lens over its limit usually
+    /// exits with `ENOMEM` or dies from a signal after its runtime reports
+    /// the failed allocation. A signal death only counts with evidence: an
+    /// allocation failure on stderr, or a peak address space near the limit.
+    #[cfg(unix)]
+    fn memory_exceeded(
+        &self,
+        status: &std::process::ExitStatus,
+        stderr: &str,
+        peak: Option<u64>,
+    ) -> Option<LensError> {
+        use std::os::unix::process::ExitStatusExt;
+
+        let bytes = self.max_memory?;
+        let limit = bytes / (1024 * 1024);
+        if status.code() == Some(libc::ENOMEM) {
+            return Some(LensError::ResourceLimitExceeded(format!(
+                "Lens '{}' ran out of memory under its {} MiB memory limit",
+                self.id, limit
+            )));
+        }
+        let signal = status.signal()?;
+        let stderr = stderr.to_lowercase();
+        let allocation_failed = ALLOCATION_FAILURES
+            .iter()
+            .any(|failure| stderr.contains(failure));
+        let near_limit = peak.is_some_and(|peak| peak >= bytes / 100 * NEAR_LIMIT_PERCENT);
+        if !allocation_failed && !near_limit {
+            return None;
+        }
+        Some(LensError::ResourceLimitExceeded(format!(
+            "Lens '{}' was killed by signal {} under its {} MiB memory limit",
+            self.id, signal, limit
+        )))
+    }
+
+    #[cfg(not(unix))]
+    fn memory_exceeded(
+        &self,
+        _status: &std::process::ExitStatus,
+        _stderr: &str,
+        _peak: Option<u64>,
+    ) -> Option<LensError> {
+        None
+    }
+}
+
+/// Samples a child's peak address space (`VmPeak`) while it runs
+struct PeakSampler {
+    #[cfg(target_os = "linux")]
+    task: Option<(
+        std::sync::Arc<std::sync::atomic::AtomicU64>,
+        tokio::task::JoinHandle<()>,
+    )>,
+}
+
+impl PeakSampler {
+    /// Sample process `pid`, if given; a no-op off Linux
+    #[cfg(target_os = "linux")]
+    fn start(pid: Option<u32>) -> Self {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::Arc;
+
+        let task = pid.map(|pid| {
+            let peak = Arc::new(AtomicU64::new(0));
+            let sampled = Arc::clone(&peak);
+            let task = tokio::spawn(async move {
+                // The kernel keeps VmPeak as a high-water mark; it is gone
+                // once the process exits
+                while let Some(bytes) = vm_peak(pid) {
+                    sampled.fetch_max(bytes, Ordering::Relaxed);
+                    tokio::time::sleep(PEAK_SAMPLE_INTERVAL).await;
+                }
+            });
+            (peak, task)
+        });
+        Self { task }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn start(_pid: Option<u32>) -> Self {
+        Self {}
+    }
+
+    /// Stop sampling, returning the highest peak seen
+    #[cfg(target_os = "linux")]
+    fn stop(self) -> Option<u64> {
+        let (peak, task) = self.task?;
+        task.abort();
+        Some(peak.load(std::sync::atomic::Ordering::Relaxed)).filter(|bytes| *bytes > 0)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn stop(self) -> Option<u64> {
+        None
+    }
+}
+
+/// `VmPeak` of process `pid` in bytes
+#[cfg(target_os = "linux")]
+fn vm_peak(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmPeak:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Read `stream` to the end, keeping the last [`STDERR_TAIL_BYTES`]
+async fn stderr_tail(mut stream: impl AsyncRead + Unpin) -> String {
+    let mut tail = Vec::new();
+    let mut buf = [0u8; 1024];
+    while let Ok(n) = stream.read(&mut buf).await {
+        if n == 0 {
+            break;
+        }
+        tail.extend_from_slice(&buf[..n]);
+        if tail.len() > STDERR_TAIL_BYTES {
+            tail.drain(..tail.len() - STDERR_TAIL_BYTES);
+        }
+    }
+    String::from_utf8_lossy(&tail).into_owned()
+}
+
+#[async_trait]
+impl Lens for SubprocessLens {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    async fn execute(&self, ctx: LensContext) -> Result<LensResult> {
+        let child = self.spawn()?;
+        self.exchange(child, ctx, None).await
+    }
+}
+
+#[async_trait]
+impl StreamingLens for SubprocessLens {
+    /// Returns once the program has started, with a `{"status": "running"}`
+    /// result. Events are streamed as the program writes them; the run
+    /// ends with a `Data` event under [`RESULT_EVENT_KEY`] carrying the
+    /// final [`LensResult`], or a `Failed` event.
+    async fn execute_streaming(&self, ctx: LensContext) -> Result<(LensResult, LensEventStream)> {
+        let child = self.spawn()?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        let lens = self.clone();
+        tokio::spawn(async move {
+            let last = match lens.exchange(child, ctx, Some(&tx)).await {
+                Ok(result) => LensEvent::data(
+                    &lens.id,
+                    RESULT_EVENT_KEY,
+                    serde_json::to_value(&result).unwrap_or_default(),
+                ),
+                Err(error) => LensEvent::failed_with_error(&lens.id, &error),
+            };
+            let _ = tx.send(last);
+        });
+        let running = LensResult::success(serde_json::json!({ "status": "running" }));
+        Ok((running, Box::pin(UnboundedReceiverStream::new(rx))))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::cancel::CancellationToken;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::tempdir;
+    use tokio_stream::StreamExt;
+
+    /// A lens running `script` through `sh`, so the test never executes a
+    /// file it has just written
+    fn script_lens(dir: &std::path::Path, script: &str) -> SubprocessLens {
+        let path = dir.join("lens.sh");
+        fs::write(&path, script).unwrap();
+        SubprocessLens::new("sh", "script", "Script", "1.0.0")
+            .with_args([path.to_string_lossy()])
+            .with_working_dir(dir)
+    }
+
+    #[tokio::test]
+    async fn test_subprocess_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let lens = script_lens(
+            temp_dir.path(),
+            r#"read -r request
+echo "starting" >&2
+echo '{"type":"event","event":{"type":"progress","lens":"script","message":"half","timestamp":0}}'
+echo
+printf '{"type":"result","result":{"success":true,"output":{"request":%s,"protocol":"%s"}}}\n' "$request" "$LENS_PROTOCOL_VERSION"
+"#,
+        );
+
+        let ctx = || LensContext::new(temp_dir.path().to_path_buf(), json!({"text": "hi"}));
+        let result = lens.execute(ctx()).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.output["request"]["type"], "execute");
+        assert_eq!(result.output["request"]["context"]["input"]["text"], "hi");
+        assert_eq!(result.output["protocol"], "1");
+
+        let (running, events) = lens.execute_streaming(ctx()).await.unwrap();
+        assert_eq!(running.output["status"], "running");
+        let events: Vec<_> = events.collect().await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type(), "Progress");
+        match &events[1] {
+            LensEvent::Data { key, value, .. } => {
+                assert_eq!(key, RESULT_EVENT_KEY);
+                assert_eq!(value["output"]["protocol"], "1");
+            }
+            other => panic!("Expected the result event, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subprocess_streams_live_and_cancels() {
+        let temp_dir = tempdir().unwrap();
+        let lens = script_lens(
+            temp_dir.path(),
+            r#"read -r request
+echo '{"type":"event","event":{"type":"progress","lens":"script","message":"working","timestamp":0}}'
+sleep 30
+"#,
+        );
+        let cancel = CancellationToken::new();
+        let ctx = LensContext::new(temp_dir.path().to_path_buf(), json!({}))
+            .with_cancellation(cancel.clone());
+
+        let (_, mut events) = lens.execute_streaming(ctx).await.unwrap();
+        let first = tokio::time::timeout(Duration::from_secs(5), events.next())
+            .await
+            .expect("events arrive while the lens runs")
+            .unwrap();
+        assert_eq!(first.event_type(), "Progress");
+
+        cancel.cancel();
+        let rest: Vec<_> = tokio::time::timeout(Duration::from_secs(5), events.collect())
+            .await
+            .expect("cancelling ends the run");
+        match rest.as_slice() {
+            [LensEvent::Failed { error, .. }] => assert!(error.contains("cancelled"), "{}", error),
+            other => panic!("Expected a Failed event, got {:?}", other),
+        }
+
+        let cancel = CancellationToken::new();
+        let ctx = LensContext::new(temp_dir.path().to_path_buf(), json!({}))
+            .with_cancellation(cancel.clone());
+        let run = tokio::spawn({
+            let lens = lens.clone();
+            async move { lens.execute(ctx).await }
+        });
+        cancel.cancel();
+        let err = tokio::time::timeout(Duration::from_secs(5), run)
+            .await
+            .expect("cancelling ends the run")
+            .unwrap()
+            .unwrap_err();
+        assert!(err.to_string().contains("cancelled"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_subprocess_failures() {
+        let temp_dir = tempdir().unwrap();
+        let ctx = || LensContext::new(temp_dir.path().to_path_buf(), json!({}));
+
+        let lens = script_lens(
+            temp_dir.path(),
+            r#"echo '{"type":"error","message":"input.text is required"}'"#,
+        );
+        let err = lens.execute(ctx()).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Lens execution failed: input.text is required"
+        );
+
+        let lens = script_lens(temp_dir.path(), "echo 'boom' >&2\nexit 3\n");
+        let err = lens.execute(ctx()).await.unwrap_err().to_string();
+        assert!(
+            err.contains("without a result") && err.contains("boom"),
+            "{}",
+            err
+        );
+
+        let lens = script_lens(temp_dir.path(), "echo 'not json'\n");
+        let err = lens.execute(ctx()).await.unwrap_err().to_string();
+        assert!(err.contains("invalid protocol line"), "{}", err);
+
+        let lens =
+            script_lens(temp_dir.path(), "sleep 5\n").with_timeout(Duration::from_millis(100));
+        let err = lens.execute(ctx()).await.unwrap_err();
+        assert_eq!(err.code(), "ResourceLimitExceeded");
+        assert!(err.to_string().contains("timed out"), "{}", err);
+
+        // A crash under a memory limit is not blamed on it without evidence
+        let lens = script_lens(temp_dir.path(), "kill -ABRT $$\n").with_memory_limit(1 << 30);
+        let err = lens.execute(ctx()).await.unwrap_err();
+        assert_eq!(err.code(), "ExecutionFailed");
+
+        let lens = script_lens(
+            temp_dir.path(),
+            "echo 'memory allocation of 1048576 bytes failed' >&2\nkill -ABRT $$\n",
+        )
+        .with_memory_limit(1 << 30);
+        let err = lens.execute(ctx()).await.unwrap_err();
+        assert_eq!(err.code(), "ResourceLimitExceeded");
+        assert!(err.to_string().contains("1024 MiB"), "{}", err);
+
+        let lens = script_lens(temp_dir.path(), "exit 12\n").with_memory_limit(1 << 30);
+        let err = lens.execute(ctx()).await.unwrap_err();
+        assert_eq!(err.code(), "ResourceLimitExceeded");
+
+        // Without a limit the same exit is an ordinary failure
+        let lens = script_lens(temp_dir.path(), "exit 12\n");
+        let err = lens.execute(ctx()).await.unwrap_err();
+        assert_eq!(err.code(), "ExecutionFailed");
+    }
+
+    #[test]
+    fn test_declared_security_requires_os_sandbox() {
+        let temp_dir = tempdir().unwrap();
+        for (id, security) in [
+            ("plain", ""),
+            ("secured", "[security]\nsandbox = \"network\"\n"),
+        ] {
+            let lens_dir = temp_dir.path().join(id);
+            fs::create_dir_all(&lens_dir).unwrap();
+            fs::write(lens_dir.join("run.sh"), b"").unwrap();
+            fs::write(
+                lens_dir.join("lens.toml"),
+                format!(
+                    "[lens]\nid = \"{}\"\nname = \"{}\"\nversion = \"1.0.0\"\n\
+                     entry = {{ type = \"subprocess\", path = \"run.sh\" }}\n{}",
+                    id, id, security
+                ),
+            )
+            .unwrap();
+        }
+        let discovery = crate::discovery::LensDiscovery::new(temp_dir.path());
+        let required = |id: &str| {
+            let discovered = discovery.get_lens(id).unwrap().unwrap();
+            let lens = SubprocessLens::from_discovered(&discovered).unwrap();
+            lens.os_sandbox.unwrap().is_required()
+        };
+        assert!(!required("plain"));
+        assert!(required("secured"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_os_sandbox_confines_filesystem() {
+        if !OsSandbox::is_supported() {
+            return;
+        }
+        let temp_dir = tempdir().unwrap();
+        let lens_dir = temp_dir.path().join("lens");
+        let secrets = temp_dir.path().join("secrets");
+        fs::create_dir_all(&lens_dir).unwrap();
+        fs::create_dir_all(&secrets).unwrap();
+        fs::write(secrets.join("token"), "hunter2").unwrap();
+        let script = format!(
+            r#"read -r request
+if secret=$(cat {:?}); then
+  printf '{{"type":"result","result":{{"success":true,"output":{{"secret":"%s"}}}}}}\n' "$secret"
+else
+  echo '{{"type":"error","message":"read denied"}}'
+fi
+"#,
+            secrets.join("token")
+        );
+        let security = SecurityConfig {
+            library_hash: None,
+            permissions: Vec::new(),
+            sandbox: SandboxLevel::Restricted,
+        };
+        let ctx = || LensContext::new(lens_dir.clone(), json!({}));
+
+        let confined = script_lens(&lens_dir, &script)
+            .with_os_sandbox(OsSandbox::from_security(&security, &lens_dir).required(true));
+        let err = confined.execute(ctx()).await.unwrap_err();
+        assert!(err.to_string().contains("read denied"), "{}", err);
+
+        let granted = script_lens(&lens_dir, &script).with_os_sandbox(
+            OsSandbox::from_security(&security, &lens_dir)
+                .allow_read(&secrets)
+                .required(true),
+        );
+        let result = granted.execute(ctx()).await.unwrap();
+        assert_eq!(result.output["secret"], "hunter2");
+    }
+}
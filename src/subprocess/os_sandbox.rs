@@ -0,0 +1,392 @@
+//! Operating-system isolation for subprocess lenses
+//!
+//! In-process guards only see access that goes through the lens APIs; a
+//! child process is confined by the kernel instead:
+//!
+//! - **Linux**: Landlock limits the filesystem to system directories, the
+//!   lens directory, and granted `fs:` paths; a seccomp filter refuses every
+//!   socket outside `AF_UNIX` (IP, netlink, packet, ...) unless the sandbox
+//!   level permits network access.
+//! - **macOS**: the program runs under `sandbox-exec` with a Seatbelt
+//!   profile built from the same rules.
+//! - **Windows**: AppContainer isolation is not implemented yet; the lens
+//!   runs unconfined unless the sandbox is [required](OsSandbox::required),
+//!   which it is for lenses declaring a `[security]` section.
+
+use std::path::{Path, PathBuf};
+
+use tokio::process::Command;
+
+use crate::error::{LensError, Result};
+use crate::manifest::{SandboxLevel, SecurityConfig};
+use crate::sandbox::expand_home;
+
+/// Directories every program needs to read to start at all
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+const SYSTEM_READ_DIRS: &[&str] = &[
+    "/bin",
+    "/sbin",
+    "/usr",
+    "/lib",
+    "/lib64",
+    "/etc",
+    "/proc",
+    "/System",
+    "/Library",
+    "/private/etc",
+    "/private/var/db",
+];
+
+/// Paths every program may write, e.g. `/dev/null`
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+const SYSTEM_WRITE_DIRS: &[&str] = &["/dev"];
+
+/// OS-level confinement for a [`SubprocessLens`](super::SubprocessLens),
+/// derived from the manifest's `[security]` section
+#[derive(Debug, Clone, PartialEq)]
+pub struct OsSandbox {
+    level: SandboxLevel,
+    read: Vec<PathBuf>,
+    write: Vec<PathBuf>,
+    required: bool,
+}
+
+impl OsSandbox {
+    /// Confine a lens to `lens_dir` plus what `security` grants.
+    ///
+    /// `fs:` grants only apply under the `full` sandbox level, matching
+    /// [`FsGuard`](crate::FsGuard); a `<dir>/*` grant covers the directory.
+    /// Relative scopes resolve against `lens_dir`.
+    pub fn from_security(security: &SecurityConfig, lens_dir: &Path) -> Self {
+        let mut sandbox = Self {
+            level: security.sandbox.clone(),
+            read: vec![lens_dir.to_path_buf()],
+            write: Vec::new(),
+            required: false,
+        };
+        if !security.sandbox.permits("fs") {
+            return sandbox;
+        }
+        for permission in security.parsed_permissions() {
+            if permission.permission_type != "fs" {
+                continue;
+            }
+            let scope = permission
+                .scope
+                .strip_suffix('*')
+                .unwrap_or(&permission.scope);
+            let path = match expand_home(Path::new(scope)) {
+                path if path.as_os_str().is_empty() => PathBuf::from("/"),
+                path if path.is_relative() => lens_dir.join(path),
+                path => path,
+            };
+            match permission.action.as_deref() {
+                Some("read") => sandbox.read.push(path),
+                Some("write") | None => sandbox.write.push(path),
+                Some(_) => {}
+            }
+        }
+        sandbox
+    }
+
+    /// Also allow reading `path` (builder pattern)
+    pub fn allow_read(mut self, path: impl Into<PathBuf>) -> Self {
+        self.read.push(path.into());
+        self
+    }
+
+    /// Also allow reading and writing `path` (builder pattern)
+    pub fn allow_write(mut self, path: impl Into<PathBuf>) -> Self {
+        self.write.push(path.into());
+        self
+    }
+
+    /// Refuse to start the lens where the platform cannot confine it,
+    /// instead of running it unconfined (builder pattern)
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Whether the lens refuses to start unconfined
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// Whether this platform can confine subprocesses
+    pub fn is_supported() -> bool {
+        platform::is_supported()
+    }
+
+    /// Paths the lens may read (writable paths are readable too)
+    pub fn read_paths(&self) -> &[PathBuf] {
+        &self.read
+    }
+
+    /// Paths the lens may read and write
+    pub fn write_paths(&self) -> &[PathBuf] {
+        &self.write
+    }
+
+    fn network(&self) -> bool {
+        self.level.permits("network")
+    }
+
+    /// Command running `program` with `args` inside the sandbox
+    pub(super) fn command(&self, program: &Path, args: &[String]) -> Result<Command> {
+        if !Self::is_supported() {
+            if self.required {
+                return Err(LensError::PermissionDenied(format!(
+                    "Cannot sandbox {:?}: OS sandboxing is not supported on this system",
+                    program
+                )));
+            }
+            eprintln!(
+                "Warning: OS sandboxing is not supported on this system; running {:?} unconfined",
+                program
+            );
+            let mut command = Command::new(program);
+            command.args(args);
+            return Ok(command);
+        }
+        platform::command(self, program, args)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    use landlock::{
+        path_beneath_rules, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreated,
+        RulesetCreatedAttr, ABI,
+    };
+    use seccompiler::{
+        BpfProgram, SeccompAction, SeccompCmpArgLen, SeccompCmpOp, SeccompCondition, SeccompFilter,
+        SeccompRule, TargetArch,
+    };
+    use tokio::process::Command;
+
+    use super::{OsSandbox, SYSTEM_READ_DIRS, SYSTEM_WRITE_DIRS};
+    use crate::error::{LensError, Result};
+
+    /// Newest Landlock ABI requested; older kernels enforce what they can
+    const LANDLOCK_ABI: ABI = ABI::V5;
+
+    pub(super) fn is_supported() -> bool {
+        // SAFETY: querying the ABI version reads no memory
+        let version = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<libc::c_void>(),
+                0usize,
+                1u32, // LANDLOCK_CREATE_RULESET_VERSION
+            )
+        };
+        version > 0
+    }
+
+    pub(super) fn command(sandbox: &OsSandbox, program: &Path, args: &[String]) -> Result<Command> {
+        // Everything that allocates or opens files happens here, before the
+        // fork; the child only applies what was prepared
+        let mut ruleset = Some(ruleset(sandbox, program)?);
+        let filter = if sandbox.network() {
+            None
+        } else {
+            Some(deny_non_unix_sockets()?)
+        };
+
+        let mut command = Command::new(program);
+        command.args(args);
+        // SAFETY: the closure only issues prctl/landlock/seccomp syscalls on
+        // state prepared before the fork
+        unsafe {
+            command.pre_exec(move || {
+                if let Some(ruleset) = ruleset.take() {
+                    ruleset.restrict_self().map_err(|e| {
+                        std::io::Error::new(std::io::ErrorKind::PermissionDenied, e)
+                    })?;
+                }
+                if let Some(filter) = &filter {
+                    seccompiler::apply_filter(filter).map_err(|e| {
+                        std::io::Error::new(std::io::ErrorKind::PermissionDenied, e)
+                    })?;
+                }
+                Ok(())
+            });
+        }
+        Ok(command)
+    }
+
+    fn ruleset(sandbox: &OsSandbox, program: &Path) -> Result<RulesetCreated> {
+        let read = SYSTEM_READ_DIRS
+            .iter()
+            .map(Path::new)
+            .chain(sandbox.read.iter().map(|path| path.as_path()))
+            .chain(Some(program));
+        let write = SYSTEM_WRITE_DIRS
+            .iter()
+            .map(Path::new)
+            .chain(sandbox.write.iter().map(|path| path.as_path()));
+
+        // Paths that do not exist are skipped by path_beneath_rules
+        Ruleset::default()
+            .handle_access(AccessFs::from_all(LANDLOCK_ABI))
+            .and_then(|ruleset| ruleset.create())
+            .and_then(|ruleset| {
+                ruleset.add_rules(path_beneath_rules(read, AccessFs::from_read(LANDLOCK_ABI)))
+            })
+            .and_then(|ruleset| {
+                ruleset.add_rules(path_beneath_rules(write, AccessFs::from_all(LANDLOCK_ABI)))
+            })
+            .map_err(|e| {
+                LensError::Initialization(format!("Failed to build Landlock rules: {}", e))
+            })
+    }
+
+    /// Filter failing `socket` and `socketpair` with `EACCES` for every
+    /// domain but `AF_UNIX`
+    fn deny_non_unix_sockets() -> Result<BpfProgram> {
+        let failed = |e: seccompiler::BackendError| {
+            LensError::Initialization(format!("Failed to build seccomp filter: {}", e))
+        };
+        let arch = TargetArch::try_from(std::env::consts::ARCH).map_err(failed)?;
+        let not_unix = || {
+            SeccompCondition::new(
+                0,
+                SeccompCmpArgLen::Dword,
+                SeccompCmpOp::Ne,
+                libc::AF_UNIX as u64,
+            )
+            .and_then(|condition| SeccompRule::new(vec![condition]))
+            .map_err(failed)
+        };
+        let rules = BTreeMap::from([
+            (libc::SYS_socket, vec![not_unix()?]),
+            (libc::SYS_socketpair, vec![not_unix()?]),
+        ]);
+        let filter = SeccompFilter::new(
+            rules,
+            SeccompAction::Allow,
+            SeccompAction::Errno(libc::EACCES as u32),
+            arch,
+        )
+        .map_err(failed)?;
+        BpfProgram::try_from(filter).map_err(failed)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::path::Path;
+
+    use tokio::process::Command;
+
+    use super::{OsSandbox, SYSTEM_READ_DIRS, SYSTEM_WRITE_DIRS};
+    use crate::error::Result;
+
+    const SANDBOX_EXEC: &str = "/usr/bin/sandbox-exec";
+
+    pub(super) fn is_supported() -> bool {
+        Path::new(SANDBOX_EXEC).exists()
+    }
+
+    pub(super) fn command(sandbox: &OsSandbox, program: &Path, args: &[String]) -> Result<Command> {
+        let mut command = Command::new(SANDBOX_EXEC);
+        command
+            .arg("-p")
+            .arg(profile(sandbox, program))
+            .arg(program)
+            .args(args);
+        Ok(command)
+    }
+
+    /// Seatbelt profile denying everything the sandbox does not grant
+    fn profile(sandbox: &OsSandbox, program: &Path) -> String {
+        let subpaths = |paths: Vec<&Path>| {
+            paths
+                .into_iter()
+                .map(|path| format!(" (subpath {:?})", path.to_string_lossy()))
+                .collect::<String>()
+        };
+        let read: Vec<&Path> = SYSTEM_READ_DIRS
+            .iter()
+            .map(Path::new)
+            .chain(sandbox.read.iter().map(|path| path.as_path()))
+            .chain(sandbox.write.iter().map(|path| path.as_path()))
+            .chain(Some(program))
+            .collect();
+        let write: Vec<&Path> = SYSTEM_WRITE_DIRS
+            .iter()
+            .map(Path::new)
+            .chain(sandbox.write.iter().map(|path| path.as_path()))
+            .collect();
+
+        let mut profile = String::from(
+            "(version 1)\n(deny default)\n(allow process-exec process-fork signal sysctl-read mach-lookup ipc-posix-shm)\n(allow file-read-metadata)\n",
+        );
+        profile.push_str(&format!("(allow file-read*{})\n", subpaths(read)));
+        profile.push_str(&format!("(allow file-write*{})\n", subpaths(write)));
+        if sandbox.network() {
+            profile.push_str("(allow network*)\n");
+        }
+        profile
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod platform {
+    use std::path::Path;
+
+    use tokio::process::Command;
+
+    use super::OsSandbox;
+    use crate::error::Result;
+
+    pub(super) fn is_supported() -> bool {
+        false
+    }
+
+    pub(super) fn command(
+        _sandbox: &OsSandbox,
+        program: &Path,
+        _args: &[String],
+    ) -> Result<Command> {
+        unreachable!("{:?} is only sandboxed where is_supported()", program)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn security(sandbox: SandboxLevel, permissions: &[&str]) -> SecurityConfig {
+        SecurityConfig {
+            library_hash: None,
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            sandbox,
+        }
+    }
+
+    #[test]
+    fn test_from_security() {
+        let lens_dir = Path::new("/lenses/notes");
+        let permissions = ["fs:read:/data/*", "fs:write:out", "network:api.example.com"];
+
+        let restricted =
+            OsSandbox::from_security(&security(SandboxLevel::Restricted, &permissions), lens_dir);
+        assert_eq!(restricted.read_paths(), [lens_dir]);
+        assert!(restricted.write_paths().is_empty());
+        assert!(!restricted.network());
+
+        let full = OsSandbox::from_security(&security(SandboxLevel::Full, &permissions), lens_dir);
+        assert_eq!(full.read_paths(), [lens_dir, Path::new("/data/")]);
+        assert_eq!(full.write_paths(), [lens_dir.join("out")]);
+        assert!(full.network());
+
+        let everything =
+            OsSandbox::from_security(&security(SandboxLevel::Full, &["fs:*"]), lens_dir);
+        assert_eq!(everything.write_paths(), [Path::new("/")]);
+    }
+}
@@ -0,0 +1,280 @@
+//! # Testing Utilities
+//!
+//! Helpers for unit-testing lenses outside a host.
+//!
+//! [`MockToolCaller`] stands in for the host's [`ToolCaller`]: declare the
+//! calls a lens should make, run it, then [`verify`](MockToolCaller::verify)
+//! that they happened.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use lens::testing::MockToolCaller;
+//! use lens::{LensContext, ToolCaller};
+//! use serde_json::json;
+//!
+//! # tokio_test_block_on(async {
+//! let mut mock = MockToolCaller::new();
+//! mock.expect("search")
+//!     .with_params(json!({ "query": "Button" }))
+//!     .returning(json!({ "hits": 3 }));
+//! let mock = Arc::new(mock);
+//!
+//! let ctx = LensContext::new(".".into(), json!({})).with_tool_caller(mock.clone());
+//! let caller = ctx.tool_caller.as_ref().unwrap();
+//! let result = caller.call_tool("search", json!({ "query": "Button" })).await.unwrap();
+//! assert_eq!(result["hits"], 3);
+//!
+//! mock.verify();
+//! # });
+//! # fn tokio_test_block_on<F: std::future::Future>(f: F) -> F::Output {
+//! #     tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(f)
+//! # }
+//! ```
+
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::context::ToolCaller;
+use crate::error::{LensError, Result};
+
+/// A tool call received by a [`MockToolCaller`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCall {
+    pub name: String,
+    pub params: Value,
+}
+
+/// One expected tool call and its canned response
+#[derive(Debug)]
+pub struct Expectation {
+    name: String,
+    params: Option<Value>,
+    response: std::result::Result<Value, String>,
+    times: Option<usize>,
+    calls: usize,
+}
+
+impl Expectation {
+    fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            params: None,
+            response: Ok(Value::Null),
+            times: None,
+            calls: 0,
+        }
+    }
+
+    /// Only match calls with exactly these params
+    pub fn with_params(&mut self, params: Value) -> &mut Self {
+        self.params = Some(params);
+        self
+    }
+
+    /// Respond with `result`
+    pub fn returning(&mut self, result: Value) -> &mut Self {
+        self.response = Ok(result);
+        self
+    }
+
+    /// Fail with `LensError::ExecutionFailed(message)`
+    pub fn returning_error(&mut self, message: impl Into<String>) -> &mut Self {
+        self.response = Err(message.into());
+        self
+    }
+
+    /// Match exactly `n` calls; further calls fall through to other
+    /// expectations. Without this, any number of calls (at least one) match.
+    pub fn times(&mut self, n: usize) -> &mut Self {
+        self.times = Some(n);
+        self
+    }
+
+    fn matches(&self, name: &str, params: &Value) -> bool {
+        self.name == name
+            && self
+                .params
+                .as_ref()
+                .is_none_or(|expected| expected == params)
+            && self.times.is_none_or(|times| self.calls < times)
+    }
+
+    fn unmet(&self) -> Option<String> {
+        let params = self
+            .params
+            .as_ref()
+            .map(|p| format!(" with params {}", p))
+            .unwrap_or_default();
+        match self.times {
+            Some(times) if self.calls != times => Some(format!(
+                "'{}'{} expected {} call(s), got {}",
+                self.name, params, times, self.calls
+            )),
+            None if self.calls == 0 => Some(format!("'{}'{} was never called", self.name, params)),
+            _ => None,
+        }
+    }
+}
+
+/// [`ToolCaller`] returning canned responses for declared calls.
+///
+/// Calls that match no expectation fail with `ExecutionFailed` and are
+/// reported by [`verify`](Self::verify). Expectations are tried in the order
+/// they were declared.
+#[derive(Debug, Default)]
+pub struct MockToolCaller {
+    expectations: Mutex<Vec<Expectation>>,
+    calls: Mutex<Vec<RecordedCall>>,
+    unexpected: Mutex<Vec<RecordedCall>>,
+}
+
+impl MockToolCaller {
+    /// Create a mock with no expectations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect a call to `tool`
+    pub fn expect(&mut self, tool: impl Into<String>) -> &mut Expectation {
+        let expectations = self.expectations.get_mut().unwrap();
+        expectations.push(Expectation::new(tool));
+        expectations.last_mut().unwrap()
+    }
+
+    /// Every call received so far, in order
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Params of every call to `tool`, in order
+    pub fn calls_to(&self, tool: &str) -> Vec<Value> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|call| call.name == tool)
+            .map(|call| call.params.clone())
+            .collect()
+    }
+
+    /// Panic unless every expectation was met and no unexpected call was made
+    pub fn verify(&self) {
+        let mut problems: Vec<String> = self
+            .unexpected
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|call| {
+                format!(
+                    "unexpected call to '{}' with params {}",
+                    call.name, call.params
+                )
+            })
+            .collect();
+        problems.extend(
+            self.expectations
+                .lock()
+                .unwrap()
+                .iter()
+                .filter_map(Expectation::unmet),
+        );
+        if !problems.is_empty() {
+            panic!(
+                "MockToolCaller verification failed:\n  {}",
+                problems.join("\n  ")
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl ToolCaller for MockToolCaller {
+    async fn call_tool(&self, name: &str, params: Value) -> Result<Value> {
+        let call = RecordedCall {
+            name: name.to_string(),
+            params,
+        };
+        self.calls.lock().unwrap().push(call.clone());
+
+        let mut expectations = self.expectations.lock().unwrap();
+        let Some(expectation) = expectations
+            .iter_mut()
+            .find(|e| e.matches(&call.name, &call.params))
+        else {
+            let message = format!(
+                "MockToolCaller: unexpected call to '{}' with params {}",
+                call.name, call.params
+            );
+            self.unexpected.lock().unwrap().push(call);
+            return Err(LensError::ExecutionFailed(message));
+        };
+
+        expectation.calls += 1;
+        expectation
+            .response
+            .clone()
+            .map_err(LensError::ExecutionFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_mock_tool_caller_matches_expectations() {
+        let mut mock = MockToolCaller::new();
+        mock.expect("search")
+            .with_params(json!({ "query": "Button" }))
+            .returning(json!({ "hits": 3 }));
+        mock.expect("search").returning(json!({ "hits": 0 }));
+        mock.expect("fetch")
+            .times(1)
+            .returning_error("rate limited");
+
+        let hits = mock
+            .call_tool("search", json!({ "query": "Button" }))
+            .await
+            .unwrap();
+        assert_eq!(hits["hits"], 3);
+        let other = mock
+            .call_tool("search", json!({ "query": "Card" }))
+            .await
+            .unwrap();
+        assert_eq!(other["hits"], 0);
+        assert!(matches!(
+            mock.call_tool("fetch", json!({})).await,
+            Err(LensError::ExecutionFailed(message)) if message == "rate limited"
+        ));
+
+        assert_eq!(mock.calls().len(), 3);
+        assert_eq!(
+            mock.calls_to("search"),
+            vec![json!({ "query": "Button" }), json!({ "query": "Card" })]
+        );
+        mock.verify();
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "unexpected call to 'fetch'")]
+    async fn test_mock_tool_caller_rejects_unexpected_calls() {
+        let mut mock = MockToolCaller::new();
+        mock.expect("fetch").times(1).returning(json!({}));
+
+        assert!(mock.call_tool("fetch", json!({})).await.is_ok());
+        // Second call exceeds `times(1)`
+        assert!(mock.call_tool("fetch", json!({})).await.is_err());
+        mock.verify();
+    }
+
+    #[test]
+    #[should_panic(expected = "'search' was never called")]
+    fn test_mock_tool_caller_reports_unmet_expectations() {
+        let mut mock = MockToolCaller::new();
+        mock.expect("search");
+        mock.verify();
+    }
+}
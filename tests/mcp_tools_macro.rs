@@ -0,0 +1,102 @@
+#![cfg(feature = "macros")]
+
+use async_trait::async_trait;
+use lens::{Lens, LensContext, LensResult, McpContent, McpServerLens, Result};
+use serde_json::Value;
+
+#[tokio::test]
+async fn test_mcp_tools_macro() {
+    struct NotesLens;
+
+    #[async_trait]
+    impl Lens for NotesLens {
+        fn id(&self) -> &str {
+            "notes"
+        }
+        fn name(&self) -> &str {
+            "Notes"
+        }
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+        async fn execute(&self, _ctx: LensContext) -> Result<LensResult> {
+            Ok(LensResult::success(serde_json::json!({})))
+        }
+    }
+
+    #[lens::mcp_tools(name = "notes-server")]
+    impl NotesLens {
+        /// Search notes by text
+        #[mcp_tool(params(query = "Text to find", tags = "Only notes with these tags"))]
+        async fn search(
+            &self,
+            query: String,
+            limit: Option<u32>,
+            tags: Vec<String>,
+        ) -> Result<Value> {
+            Ok(serde_json::json!({
+                "query": query,
+                "limit": limit,
+                "tags": tags,
+            }))
+        }
+
+        #[mcp_tool(
+            name = "count",
+            description = "Count notes",
+            read_only,
+            open_world = false
+        )]
+        async fn count_notes(&self) -> String {
+            "3".to_string()
+        }
+
+        #[allow(dead_code)]
+        fn helper(&self) {}
+    }
+
+    let lens = NotesLens;
+    assert_eq!(lens.mcp_server_name(), "notes-server");
+
+    let tools = lens.mcp_tools();
+    assert_eq!(tools.len(), 2);
+    let search = &tools[0].input_schema;
+    assert_eq!(tools[0].description, "Search notes by text");
+    assert_eq!(search.required, vec!["query", "tags"]);
+    assert!(tools[0].annotations.is_none());
+    let hints = tools[1].annotations.as_ref().unwrap();
+    assert_eq!(hints.read_only_hint, Some(true));
+    assert_eq!(hints.open_world_hint, Some(false));
+    assert_eq!(hints.destructive_hint, None);
+    assert_eq!(search.properties["query"].description, "Text to find");
+    assert_eq!(search.properties["limit"].prop_type, "integer");
+    assert_eq!(
+        search.properties["tags"].items.as_ref().unwrap().prop_type,
+        "string"
+    );
+    assert_eq!(tools[1].name, "count");
+
+    let response = lens
+        .call_tool(
+            "search",
+            serde_json::json!({"query": "rust", "tags": ["a"]}),
+        )
+        .await
+        .unwrap();
+    match &response.content[0] {
+        McpContent::Text { text } => assert!(text.contains("\"rust\"")),
+        other => panic!("unexpected content {:?}", other),
+    }
+
+    let err = lens
+        .call_tool("search", serde_json::json!({"tags": []}))
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Missing required parameter 'query'"));
+
+    let count = lens.call_tool("count", Value::Null).await.unwrap();
+    assert!(!count.is_error);
+    assert!(lens.call_tool("nope", Value::Null).await.is_err());
+}